@@ -24,12 +24,91 @@
 //!     async fn inject_pointer_motion(&self, dx: f64, dy: f64) -> zbus::Result<()>;
 //!     async fn inject_pointer_button(&self, button: i32, state: bool) -> zbus::Result<()>;
 //!     async fn inject_pointer_axis(&self, dx: f64, dy: f64) -> zbus::Result<()>;
-//!     async fn start_capture(&self, session: &str) -> zbus::Result<String>;
+//!     async fn list_outputs(&self) -> zbus::Result<Vec<(u32, String, u32, u32)>>;
+//!     async fn start_capture(
+//!         &self,
+//!         session: &str,
+//!         output: u32,
+//!         cursor_mode: u32,
+//!     ) -> zbus::Result<u32>;
+//!     async fn stop_capture(&self, session: &str, output: u32) -> zbus::Result<()>;
+//!     async fn lock_pointer(&self, session: &ObjectPath<'_>) -> zbus::Result<()>;
+//!     async fn unlock_pointer(&self, session: &ObjectPath<'_>) -> zbus::Result<()>;
+//!     async fn set_pointer_transform(
+//!         &self,
+//!         session: &ObjectPath<'_>,
+//!         sensitivity: f64,
+//!         accel_profile: u32,
+//!     ) -> zbus::Result<()>;
+//!
+//!     #[zbus(signal)]
+//!     fn session_revoked(&self, session: &str, reason: &str) -> zbus::Result<()>;
+//!     #[zbus(signal)]
+//!     fn focus_changed(
+//!         &self,
+//!         title: &str,
+//!         app_id: &str,
+//!         x: i32,
+//!         y: i32,
+//!         width: u32,
+//!         height: u32,
+//!     ) -> zbus::Result<()>;
 //! }
 //! ```
+//!
+//! ## Session Revocation
+//!
+//! cosmic-comp (or a settings panel acting through it) may withdraw a
+//! session's access at any time by emitting [`SESSION_REVOKED_SIGNAL`].
+//! Only signals from the current owner of [`COSMIC_COMP_SERVICE`] are
+//! honoured, so no other bus client can close sessions this way.
+//!
+//! ## Focus Tracking
+//!
+//! cosmic-comp emits [`FOCUS_CHANGED_SIGNAL`] when keyboard focus moves to
+//! another toplevel, so the portal can tell a remote operator which
+//! window their input lands in.
+//!
+//! ## Screen Capture
+//!
+//! cosmic-comp captures one output per `StartCapture` call and exports it
+//! as a `PipeWire` node, whose ID it returns. The stream runs until
+//! `StopCapture` is called for the same session and output.
+//!
+//! The cursor mode uses the `ScreenCast` portal's bit values: cosmic-comp
+//! composites the cursor into the buffers for
+//! [`CursorMode::Embedded`], leaves it out for [`CursorMode::Hidden`], and
+//! for [`CursorMode::Metadata`] attaches it to each buffer as `PipeWire`
+//! cursor metadata instead.
+//!
+//! ## Pointer Lock
+//!
+//! `LockPointer` and `UnlockPointer` take the portal session handle (`o`).
+//! While a session holds the lock cosmic-comp keeps the cursor in place
+//! and ignores the session's absolute motion.
+//!
+//! ## Pointer Transform
+//!
+//! `SetPointerTransform` takes the session handle, the sensitivity (`d`)
+//! and the acceleration profile (`u`,
+//! [`AccelProfile`](ion_core::event::AccelProfile) values). cosmic-comp
+//! applies it to the session's relative motion from then on; sensitivity
+//! `1.0` with the flat profile removes it.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
-use tracing::{debug, info, instrument};
-use zbus::Connection;
+use futures::{Stream, StreamExt};
+use ion_core::backend::{
+    ColorSpace, CursorMode, FocusChanged, OutputInfo, OutputTransform, SessionRevocation,
+    SurfaceGeometry,
+};
+use ion_core::event::PointerTransform;
+use ion_core::session::SessionId;
+use tracing::{debug, info, instrument, warn};
+use zbus::proxy::SignalStream;
+use zbus::zvariant::ObjectPath;
+use zbus::{CacheProperties, Connection, ProxyBuilder};
 
 /// D-Bus service name for cosmic-comp `RemoteDesktop` service.
 ///
@@ -38,9 +117,24 @@ use zbus::Connection;
 pub const COSMIC_COMP_SERVICE: &str = "com.system76.cosmic.Comp";
 
 /// D-Bus object path for `RemoteDesktop` interface.
-#[allow(dead_code)] // Will be used when cosmic-comp implements interface
 pub const COSMIC_COMP_PATH: &str = "/com/system76/cosmic/RemoteDesktop";
 
+/// cosmic-comp's `RemoteDesktop` interface name.
+pub const COSMIC_REMOTE_DESKTOP_INTERFACE: &str = "com.system76.cosmic.RemoteDesktop";
+
+/// Signal cosmic-comp emits when it revokes a session.
+///
+/// Arguments are the portal session handle (`s`), as passed to
+/// `StartCapture`, and a human-readable reason (`s`).
+pub const SESSION_REVOKED_SIGNAL: &str = "SessionRevoked";
+
+/// Signal cosmic-comp emits when keyboard focus moves to another toplevel.
+///
+/// Arguments are the toplevel's title (`s`) and app ID (`s`), then its
+/// position (`i`, `i`) and size (`u`, `u`) in global logical
+/// coordinates; a zero size means the geometry is unknown.
+pub const FOCUS_CHANGED_SIGNAL: &str = "FocusChanged";
+
 /// Proxy to cosmic-comp's `RemoteDesktop` D-Bus interface.
 ///
 /// This is a manual proxy implementation until cosmic-comp exposes
@@ -49,21 +143,27 @@ pub const COSMIC_COMP_PATH: &str = "/com/system76/cosmic/RemoteDesktop";
 #[derive(Debug, Clone)]
 pub struct CosmicCompProxy {
     connection: Connection,
-    service_available: bool,
+    service_available: Arc<AtomicBool>,
 }
 
 impl CosmicCompProxy {
     /// Create a new proxy to cosmic-comp.
     ///
-    /// Checks if the cosmic-comp D-Bus service is available.
-    #[instrument(skip(connection))]
-    pub async fn new(connection: &Connection) -> zbus::Result<Self> {
+    /// Checks if the cosmic-comp D-Bus service is available and stores
+    /// the result in `service_available`, which [`Self::watch_service`]
+    /// keeps current afterwards.
+    #[instrument(skip(connection, service_available))]
+    pub async fn new(
+        connection: &Connection,
+        service_available: Arc<AtomicBool>,
+    ) -> zbus::Result<Self> {
         debug!("Creating proxy to cosmic-comp");
 
         // Check if cosmic-comp D-Bus service exists
-        let service_available = Self::check_service_available(connection).await;
+        let available = Self::check_service_available(connection).await;
+        service_available.store(available, Ordering::Release);
 
-        if service_available {
+        if available {
             info!("✓ cosmic-comp D-Bus service available");
         } else {
             debug!("cosmic-comp D-Bus service not yet available (expected - not yet implemented)");
@@ -93,12 +193,215 @@ impl CosmicCompProxy {
 
     /// Check if the cosmic-comp D-Bus service is available.
     pub fn is_available(&self) -> bool {
-        self.service_available
+        self.service_available.load(Ordering::Acquire)
     }
 
-    /// Get the D-Bus connection.
+    /// Track the cosmic-comp service coming and going.
+    ///
+    /// Runs until the connection closes, updating the availability flag on
+    /// every `NameOwnerChanged` for [`COSMIC_COMP_SERVICE`] and calling
+    /// `on_change` with the new state when it differs from the old one.
+    pub async fn watch_service(&self, on_change: impl Fn(bool) + Send) {
+        let changes = match zbus::fdo::DBusProxy::new(&self.connection).await {
+            Ok(proxy) => {
+                proxy
+                    .receive_name_owner_changed_with_args(&[(0, COSMIC_COMP_SERVICE)])
+                    .await
+            },
+            Err(e) => Err(e),
+        };
+        let mut changes = match changes {
+            Ok(changes) => changes,
+            Err(e) => {
+                warn!("Cannot watch cosmic-comp D-Bus service: {e}");
+                return;
+            },
+        };
+
+        while let Some(change) = changes.next().await {
+            let Ok(args) = change.args() else { continue };
+            let available = args.new_owner().is_some();
+            if self.service_available.swap(available, Ordering::AcqRel) != available {
+                info!(available, "cosmic-comp D-Bus service changed");
+                on_change(available);
+            }
+        }
+    }
+
+    /// Subscribe to sessions cosmic-comp revokes.
+    ///
+    /// The stream yields every well-formed [`SESSION_REVOKED_SIGNAL`] sent
+    /// by the owner of [`COSMIC_COMP_SERVICE`] from the moment this
+    /// returns, across cosmic-comp restarts, and ends when the connection
+    /// closes.
+    pub async fn session_revocations(
+        &self,
+    ) -> zbus::Result<impl Stream<Item = SessionRevocation> + Send + 'static> {
+        let signals = self.receive_signal(SESSION_REVOKED_SIGNAL).await?;
+
+        Ok(signals.filter_map(|signal| async move {
+            let (session, reason) = match signal.body().deserialize::<(String, String)>() {
+                Ok(args) => args,
+                Err(e) => {
+                    warn!("Malformed {SESSION_REVOKED_SIGNAL} signal: {e}");
+                    return None;
+                },
+            };
+            let Ok(session_id) = SessionId::new_validated(&session) else {
+                warn!(
+                    session,
+                    "{SESSION_REVOKED_SIGNAL} for an invalid session handle"
+                );
+                return None;
+            };
+            info!(session = %session_id, reason, "cosmic-comp revoked session");
+            Some(SessionRevocation { session_id, reason })
+        }))
+    }
+
+    /// Subscribe to keyboard focus moving between toplevels.
+    ///
+    /// Like [`Self::session_revocations`], the stream yields every
+    /// well-formed [`FOCUS_CHANGED_SIGNAL`] from the owner of
+    /// [`COSMIC_COMP_SERVICE`] and ends when the connection closes.
+    pub async fn focus_changes(
+        &self,
+    ) -> zbus::Result<impl Stream<Item = FocusChanged> + Send + 'static> {
+        let signals = self.receive_signal(FOCUS_CHANGED_SIGNAL).await?;
+
+        Ok(signals.filter_map(|signal| async move {
+            let (surface_title, app_id, x, y, width, height) =
+                match signal
+                    .body()
+                    .deserialize::<(String, String, i32, i32, u32, u32)>()
+                {
+                    Ok(args) => args,
+                    Err(e) => {
+                        warn!("Malformed {FOCUS_CHANGED_SIGNAL} signal: {e}");
+                        return None;
+                    },
+                };
+            let geometry = (width > 0 && height > 0).then_some(SurfaceGeometry {
+                x,
+                y,
+                width,
+                height,
+            });
+            debug!(app_id, "cosmic-comp focus changed");
+            Some(FocusChanged {
+                surface_title,
+                app_id,
+                geometry,
+            })
+        }))
+    }
+
+    /// List cosmic-comp's outputs.
+    ///
+    /// Only the stream ID, name and logical size go over the wire; the
+    /// rest is reported as unknown.
+    pub async fn list_outputs(&self) -> zbus::Result<Vec<OutputInfo>> {
+        let reply = self.call("ListOutputs", &()).await?;
+        let outputs: Vec<(u32, String, u32, u32)> = reply.body().deserialize()?;
+        Ok(outputs
+            .into_iter()
+            .map(|(stream, name, width, height)| OutputInfo {
+                stream,
+                name,
+                width,
+                height,
+                color_space: ColorSpace::default(),
+                hdr: false,
+                transform: OutputTransform::default(),
+            })
+            .collect())
+    }
+
+    /// Start capturing `output` for a session, delivering the cursor as
+    /// `cursor_mode` asks.
     ///
-    /// Will be used when making actual D-Bus calls to cosmic-comp.
+    /// Returns the `PipeWire` node cosmic-comp exports the stream as.
+    pub async fn start_capture(
+        &self,
+        session: &SessionId,
+        output: u32,
+        cursor_mode: CursorMode,
+    ) -> zbus::Result<u32> {
+        let reply = self
+            .call(
+                "StartCapture",
+                &(session.as_str(), output, cursor_mode as u32),
+            )
+            .await?;
+        reply.body().deserialize()
+    }
+
+    /// Stop capturing `output` for a session.
+    pub async fn stop_capture(&self, session: &SessionId, output: u32) -> zbus::Result<()> {
+        self.call("StopCapture", &(session.as_str(), output))
+            .await
+            .map(drop)
+    }
+
+    /// Lock the pointer for a session's relative-only control, or
+    /// release it.
+    pub async fn set_pointer_lock(&self, session: &SessionId, locked: bool) -> zbus::Result<()> {
+        let handle = ObjectPath::try_from(session.as_str())?;
+        let method = if locked {
+            "LockPointer"
+        } else {
+            "UnlockPointer"
+        };
+        self.call(method, &(handle,)).await.map(drop)
+    }
+
+    /// Set how cosmic-comp transforms a session's relative pointer motion.
+    pub async fn set_pointer_transform(
+        &self,
+        session: &SessionId,
+        transform: PointerTransform,
+    ) -> zbus::Result<()> {
+        let handle = ObjectPath::try_from(session.as_str())?;
+        let profile = transform.accel_profile.unwrap_or_default() as u32;
+        self.call(
+            "SetPointerTransform",
+            &(handle, transform.sensitivity, profile),
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Call `method` on cosmic-comp's `RemoteDesktop` interface.
+    async fn call<B>(&self, method: &str, body: &B) -> zbus::Result<zbus::Message>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        self.connection
+            .call_method(
+                Some(COSMIC_COMP_SERVICE),
+                COSMIC_COMP_PATH,
+                Some(COSMIC_REMOTE_DESKTOP_INTERFACE),
+                method,
+                body,
+            )
+            .await
+    }
+
+    /// Signals named `signal` on cosmic-comp's `RemoteDesktop` interface,
+    /// from whichever connection owns [`COSMIC_COMP_SERVICE`].
+    async fn receive_signal(&self, signal: &'static str) -> zbus::Result<SignalStream<'static>> {
+        ProxyBuilder::<zbus::Proxy<'_>>::new(&self.connection)
+            .destination(COSMIC_COMP_SERVICE)?
+            .path(COSMIC_COMP_PATH)?
+            .interface(COSMIC_REMOTE_DESKTOP_INTERFACE)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?
+            .receive_signal(signal)
+            .await
+    }
+
+    /// Get the D-Bus connection.
     #[allow(dead_code)] // Reserved for future use
     pub fn connection(&self) -> &Connection {
         &self.connection
@@ -136,14 +439,42 @@ impl CosmicCompProxy {
 ///   </method>
 ///   
 ///   <!-- Screen Capture Methods -->
+///   <!-- Each output is (stream, name, width, height), sizes logical -->
+///   <method name="ListOutputs">
+///     <arg name="outputs" type="a(usuu)" direction="out"/>
+///   </method>
+///
+///   <!-- Exports the output's frames as a PipeWire node -->
 ///   <method name="StartCapture">
 ///     <arg name="session" type="s" direction="in"/>
-///     <arg name="node" type="s" direction="out"/>
+///     <arg name="output" type="u" direction="in"/>
+///     <arg name="node" type="u" direction="out"/>
 ///   </method>
 ///   
 ///   <method name="StopCapture">
 ///     <arg name="session" type="s" direction="in"/>
+///     <arg name="output" type="u" direction="in"/>
 ///   </method>
+///
+///   <!-- Session Revocation -->
+///   <!-- Emitted when the compositor withdraws a session's access, e.g.
+///        from a settings panel. session is the portal session handle. -->
+///   <signal name="SessionRevoked">
+///     <arg name="session" type="s"/>
+///     <arg name="reason" type="s"/>
+///   </signal>
+///
+///   <!-- Focus Tracking -->
+///   <!-- Emitted when keyboard focus moves to another toplevel. A zero
+///        width or height means the geometry is unknown. -->
+///   <signal name="FocusChanged">
+///     <arg name="title" type="s"/>
+///     <arg name="app_id" type="s"/>
+///     <arg name="x" type="i"/>
+///     <arg name="y" type="i"/>
+///     <arg name="width" type="u"/>
+///     <arg name="height" type="u"/>
+///   </signal>
 /// </interface>
 /// ```
 pub mod interface_spec {
@@ -19,7 +19,8 @@
 use tracing::{debug, instrument};
 
 use ion_core::backend::{BackendError, BackendResult};
-use ion_core::event::{ButtonState, InputEvent, KeyState};
+use ion_core::event::{AxisSource, ButtonState, InputEvent, KeyState, ScrollUnit};
+use ion_core::redact::Sensitive;
 
 use crate::dbus::CosmicCompProxy;
 
@@ -51,7 +52,12 @@ pub async fn inject_event(proxy: &CosmicCompProxy, event: InputEvent) -> Backend
         InputEvent::PointerButton { button, state } => {
             inject_pointer_button(proxy, button, state).await
         },
-        InputEvent::PointerAxis { dx, dy } => inject_pointer_axis(proxy, dx, dy).await,
+        InputEvent::PointerAxis {
+            dx,
+            dy,
+            source,
+            unit,
+        } => inject_pointer_axis(proxy, dx, dy, source, unit).await,
         InputEvent::PointerAxisDiscrete { axis, steps } => {
             inject_pointer_axis_discrete(proxy, axis, steps).await
         },
@@ -93,7 +99,8 @@ async fn inject_keyboard_keycode(
 ) -> BackendResult<()> {
     debug!(
         "Injecting keyboard keycode: {}, state: {:?}",
-        keycode, state
+        Sensitive(keycode),
+        state
     );
 
     // When cosmic-comp D-Bus interface is ready:
@@ -109,7 +116,11 @@ async fn inject_keyboard_keysym(
     keysym: i32,
     state: KeyState,
 ) -> BackendResult<()> {
-    debug!("Injecting keyboard keysym: {}, state: {:?}", keysym, state);
+    debug!(
+        "Injecting keyboard keysym: {}, state: {:?}",
+        Sensitive(keysym),
+        state
+    );
 
     // Convert keysym to keycode and call inject_keyboard_keycode
     // Or if cosmic-comp supports keysym directly, call that
@@ -159,13 +170,22 @@ async fn inject_pointer_button(
     Ok(())
 }
 
-async fn inject_pointer_axis(proxy: &CosmicCompProxy, dx: f64, dy: f64) -> BackendResult<()> {
-    debug!("Injecting pointer axis: dx={}, dy={}", dx, dy);
+async fn inject_pointer_axis(
+    proxy: &CosmicCompProxy,
+    dx: f64,
+    dy: f64,
+    source: AxisSource,
+    unit: ScrollUnit,
+) -> BackendResult<()> {
+    debug!(
+        "Injecting pointer axis: dx={}, dy={}, source={:?}, unit={:?}",
+        dx, dy, source, unit
+    );
 
-    // When cosmic-comp D-Bus interface is ready:
-    // proxy.inject_pointer_axis(dx, dy).await?;
+    // When cosmic-comp D-Bus interface is ready (source/unit go in options):
+    // proxy.inject_pointer_axis(options, dx, dy).await?;
 
-    let _ = (proxy, dx, dy);
+    let _ = (proxy, dx, dy, source, unit);
     Ok(())
 }
 
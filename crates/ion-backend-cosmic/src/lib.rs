@@ -22,8 +22,15 @@
 //! ## Features
 //!
 //! - **Input Injection**: Keyboard and pointer events via D-Bus
-//! - **Screen Capture**: `PipeWire` streams (Phase 2)
+//! - **Screen Capture**: One `PipeWire` stream per output, exported by
+//!   cosmic-comp
 //! - **Session Management**: Tracks active remote desktop sessions
+//! - **Session Revocation**: Follows cosmic-comp's `SessionRevoked` signal
+//!   so sessions the user revokes from the compositor close in the portal
+//! - **Focus Tracking**: Follows cosmic-comp's `FocusChanged` signal so
+//!   clients know which window their input lands in
+//! - **Pointer Lock**: Asks cosmic-comp to hold the pointer in place for
+//!   sessions that want relative-only control
 //!
 //! ## Usage
 //!
@@ -51,17 +58,26 @@ mod input;
 
 pub mod provider;
 
-use std::sync::Arc;
+pub use dbus::{
+    COSMIC_COMP_PATH, COSMIC_COMP_SERVICE, COSMIC_REMOTE_DESKTOP_INTERFACE, FOCUS_CHANGED_SIGNAL,
+    SESSION_REVOKED_SIGNAL,
+};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
 
 use async_trait::async_trait;
-use tokio::sync::RwLock;
-use tracing::{debug, info, instrument};
+use futures::StreamExt;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, instrument, warn};
 
 use ion_core::backend::{
-    BackendCapabilities, BackendError, BackendResult, CaptureStream, CompositorBackend,
-    DisplayServerType,
+    BackendCapabilities, BackendError, BackendResult, CapabilityNotifier, CaptureHandle,
+    CaptureStream, CompositorBackend, DisplayServerType, FocusChanged, OutputCaptureRequest,
+    OutputInfo, SessionRevocation,
 };
-use ion_core::event::InputEvent;
+use ion_core::event::{InputEvent, PointerTransform};
 use ion_core::session::SessionId;
 
 use crate::dbus::CosmicCompProxy;
@@ -70,6 +86,16 @@ use crate::dbus::CosmicCompProxy;
 ///
 /// This backend communicates with cosmic-comp via D-Bus to inject
 /// input events and capture screen content.
+///
+/// Outputs are captured one at a time by cosmic-comp, which exports each
+/// stream as a `PipeWire` node. Frames reach clients through the node, so
+/// the returned streams carry none; stopping a stream's handle tells
+/// cosmic-comp to tear the node down. cosmic-comp honours every cursor
+/// mode, sending the cursor in [`CursorMode::Metadata`] as `PipeWire`
+/// cursor metadata on the node rather than through
+/// [`CaptureStream::next_cursor`].
+///
+/// [`CursorMode::Metadata`]: ion_core::backend::CursorMode::Metadata
 #[derive(Debug)]
 pub struct CosmicBackend {
     /// D-Bus connection to cosmic-comp
@@ -78,6 +104,19 @@ pub struct CosmicBackend {
     proxy: Arc<RwLock<Option<CosmicCompProxy>>>,
     /// Whether the backend is connected
     connected: Arc<RwLock<bool>>,
+    /// Whether cosmic-comp currently owns its D-Bus name
+    service_available: Arc<AtomicBool>,
+    /// Sends capability snapshots when the service comes or goes
+    capability_notifier: CapabilityNotifier,
+    /// Sends sessions cosmic-comp revoked
+    revocations: broadcast::Sender<SessionRevocation>,
+    /// Sends focus changes cosmic-comp reported
+    focus_changes: broadcast::Sender<FocusChanged>,
+    /// Surface focused as of the last focus change
+    focus: Arc<RwLock<Option<FocusChanged>>>,
+    /// Tasks following cosmic-comp's service and signals, aborted on
+    /// disconnect
+    followers: Mutex<Vec<JoinHandle<()>>>,
 }
 
 impl CosmicBackend {
@@ -88,9 +127,114 @@ impl CosmicBackend {
             connection: Arc::new(RwLock::new(None)),
             proxy: Arc::new(RwLock::new(None)),
             connected: Arc::new(RwLock::new(false)),
+            service_available: Arc::new(AtomicBool::new(false)),
+            capability_notifier: CapabilityNotifier::default(),
+            revocations: broadcast::channel(CapabilityNotifier::CAPACITY).0,
+            focus_changes: broadcast::channel(CapabilityNotifier::CAPACITY).0,
+            focus: Arc::new(RwLock::new(None)),
+            followers: Mutex::default(),
         }
     }
 
+    /// Connect over an existing D-Bus connection instead of the session
+    /// bus, e.g. a private test bus.
+    pub async fn connect_with(&mut self, conn: zbus::Connection) -> BackendResult<()> {
+        if *self.connected.read().await {
+            debug!("Already connected to COSMIC compositor");
+            return Ok(());
+        }
+
+        // Create proxy to cosmic-comp
+        let proxy = CosmicCompProxy::new(&conn, Arc::clone(&self.service_available))
+            .await
+            .map_err(|e| BackendError::ConnectionFailed(format!("Failed to create proxy: {e}")))?;
+
+        // Follow cosmic-comp restarts so input capabilities stay accurate
+        let watcher = proxy.clone();
+        let notifier = self.capability_notifier.clone();
+        let mut followers = vec![tokio::spawn(async move {
+            watcher
+                .watch_service(|available| {
+                    notifier.notify(Self::capabilities_with(available));
+                })
+                .await;
+        })];
+        if proxy.is_available() {
+            self.capability_notifier
+                .notify(Self::capabilities_with(true));
+        }
+
+        // Pass on sessions revoked from the compositor side
+        match proxy.session_revocations().await {
+            Ok(revoked) => {
+                let revocations = self.revocations.clone();
+                followers.push(tokio::spawn(revoked.for_each(move |revocation| {
+                    let _ = revocations.send(revocation);
+                    std::future::ready(())
+                })));
+            },
+            Err(e) => warn!("Cannot follow cosmic-comp session revocations: {e}"),
+        }
+
+        // Track where input lands
+        match proxy.focus_changes().await {
+            Ok(changes) => {
+                let (tx, focus) = (self.focus_changes.clone(), Arc::clone(&self.focus));
+                followers.push(tokio::spawn(changes.for_each(move |change| {
+                    let (tx, focus) = (tx.clone(), Arc::clone(&focus));
+                    async move {
+                        *focus.write().await = Some(change.clone());
+                        let _ = tx.send(change);
+                    }
+                })));
+            },
+            Err(e) => warn!("Cannot follow cosmic-comp focus changes: {e}"),
+        }
+
+        // Store connection, proxy and followers
+        *self
+            .followers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = followers;
+        *self.connection.write().await = Some(conn);
+        *self.proxy.write().await = Some(proxy);
+        *self.connected.write().await = true;
+
+        info!("✓ Connected to COSMIC compositor");
+        Ok(())
+    }
+
+    /// Aborts the tasks following cosmic-comp.
+    fn abort_followers(&self) {
+        let followers = std::mem::take(
+            &mut *self
+                .followers
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        followers.iter().for_each(JoinHandle::abort);
+    }
+
+    /// Capabilities with the cosmic-comp D-Bus service present or not.
+    fn capabilities_with(dbus_available: bool) -> BackendCapabilities {
+        BackendCapabilities {
+            can_inject_keyboard: dbus_available,
+            can_inject_pointer: dbus_available,
+            can_capture_screen: dbus_available,
+            display_server_type: DisplayServerType::Wayland,
+            backend_name: "COSMIC (Wayland)".to_string(),
+        }
+    }
+
+    /// The proxy to cosmic-comp, if connected.
+    async fn proxy(&self) -> BackendResult<CosmicCompProxy> {
+        self.proxy
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| BackendError::ConnectionFailed("Not connected to compositor".into()))
+    }
+
     /// Check if we're running in a COSMIC session.
     fn is_cosmic_session() -> bool {
         std::env::var("COSMIC_SESSION").is_ok()
@@ -105,6 +249,12 @@ impl CosmicBackend {
     }
 }
 
+impl Drop for CosmicBackend {
+    fn drop(&mut self) {
+        self.abort_followers();
+    }
+}
+
 impl Default for CosmicBackend {
     fn default() -> Self {
         Self::new()
@@ -142,18 +292,7 @@ impl CompositorBackend for CosmicBackend {
             BackendError::ConnectionFailed(format!("D-Bus connection failed: {e}"))
         })?;
 
-        // Create proxy to cosmic-comp
-        let proxy = CosmicCompProxy::new(&conn).await.map_err(|e| {
-            BackendError::ConnectionFailed(format!("Failed to create proxy: {e}"))
-        })?;
-
-        // Store connection and proxy
-        *self.connection.write().await = Some(conn);
-        *self.proxy.write().await = Some(proxy);
-        *self.connected.write().await = true;
-
-        info!("✓ Connected to COSMIC compositor");
-        Ok(())
+        self.connect_with(conn).await
     }
 
     #[instrument(skip(self, event))]
@@ -188,33 +327,132 @@ impl CompositorBackend for CosmicBackend {
 
         info!("Starting screen capture for session: {}", session);
 
-        // When cosmic-comp implements PipeWire capture:
-        // 1. Request screen capture via D-Bus
-        // 2. Get PipeWire node ID
-        // 3. Set up PipeWire stream
-        // 4. Return CaptureStream with node info
-        //
-        // For now, return error indicating feature not available
+        // cosmic-comp only captures single outputs
         Err(BackendError::CaptureFailed(
-            "Screen capture not yet available in cosmic-comp (PipeWire integration pending)"
-                .to_string(),
+            "cosmic-comp captures per output, use start_capture_output".to_string(),
         ))
     }
 
+    async fn enumerate_outputs(&self) -> BackendResult<Vec<OutputInfo>> {
+        self.proxy()
+            .await?
+            .list_outputs()
+            .await
+            .map_err(|e| BackendError::Other(format!("Failed to list outputs: {e}")))
+    }
+
+    fn supports_output_capture(&self) -> bool {
+        self.service_available.load(Ordering::Acquire)
+    }
+
+    /// Asks cosmic-comp to export the output as a `PipeWire` node, and to
+    /// stop once the stream's handle is stopped.
+    #[instrument(skip(self, session, request), fields(output = request.stream))]
+    async fn start_capture_output(
+        &self,
+        session: &SessionId,
+        request: OutputCaptureRequest,
+    ) -> BackendResult<CaptureStream> {
+        let proxy = self.proxy().await?;
+        let output = self
+            .enumerate_outputs()
+            .await?
+            .into_iter()
+            .find(|o| o.stream == request.stream)
+            .ok_or_else(|| {
+                BackendError::CaptureFailed(format!(
+                    "unknown stream {} for {session}",
+                    request.stream
+                ))
+            })?;
+
+        let node_id = proxy
+            .start_capture(session, output.stream, request.cursor_mode)
+            .await
+            .map_err(|e| BackendError::CaptureFailed(format!("cosmic-comp: {e}")))?;
+        info!(
+            session = %session,
+            node_id,
+            cursor_mode = ?request.cursor_mode,
+            "cosmic-comp exported capture stream"
+        );
+
+        let handle = CaptureHandle::default();
+        let stopped = handle.clone();
+        let (session_id, stream) = (session.clone(), output.stream);
+        tokio::spawn(async move {
+            stopped.stopped().await;
+            if let Err(e) = proxy.stop_capture(&session_id, stream).await {
+                warn!(session = %session_id, stream, "Failed to stop cosmic-comp capture: {e}");
+            }
+        });
+
+        // Frames travel over PipeWire, never through this channel
+        let (_, frames) = mpsc::channel(1);
+        Ok(
+            CaptureStream::for_output(session.clone(), output, &request, frames, handle)
+                .with_node_id(node_id),
+        )
+    }
+
+    /// Asks cosmic-comp to lock the pointer. cosmic-comp injects on its
+    /// own seat, so `seat` is not used.
+    async fn set_pointer_lock(
+        &self,
+        session: &SessionId,
+        _seat: Option<&str>,
+        locked: bool,
+    ) -> BackendResult<()> {
+        self.proxy()
+            .await?
+            .set_pointer_lock(session, locked)
+            .await
+            .map_err(|e| BackendError::InputInjectionFailed(format!("cosmic-comp: {e}")))
+    }
+
+    async fn set_pointer_transform(
+        &self,
+        session: &SessionId,
+        transform: PointerTransform,
+    ) -> BackendResult<()> {
+        self.proxy()
+            .await?
+            .set_pointer_transform(session, transform)
+            .await
+            .map_err(|e| BackendError::InputInjectionFailed(format!("cosmic-comp: {e}")))
+    }
+
     fn capabilities(&self) -> BackendCapabilities {
-        // Get proxy availability status
-        let proxy_guard = self.proxy.blocking_read();
-        let dbus_available = proxy_guard
-            .as_ref()
-            .is_some_and(dbus::CosmicCompProxy::is_available);
+        Self::capabilities_with(self.service_available.load(Ordering::Acquire))
+    }
 
-        BackendCapabilities {
-            can_inject_keyboard: dbus_available,
-            can_inject_pointer: dbus_available,
-            can_capture_screen: false, // Will be true when PipeWire is integrated
-            display_server_type: DisplayServerType::Wayland,
-            backend_name: "COSMIC (Wayland)".to_string(),
-        }
+    fn capability_updates(&self) -> broadcast::Receiver<BackendCapabilities> {
+        self.capability_notifier.subscribe()
+    }
+
+    fn session_revocations(&self) -> broadcast::Receiver<SessionRevocation> {
+        self.revocations.subscribe()
+    }
+
+    fn focus_changes(&self) -> broadcast::Receiver<FocusChanged> {
+        self.focus_changes.subscribe()
+    }
+
+    async fn current_focus(&self) -> Option<FocusChanged> {
+        self.focus.read().await.clone()
+    }
+
+    /// Stops following cosmic-comp and drops the connection to it.
+    ///
+    /// Capture nodes already exported keep running until their handles
+    /// are stopped.
+    async fn disconnect(&self) {
+        self.abort_followers();
+        *self.proxy.write().await = None;
+        *self.connection.write().await = None;
+        *self.connected.write().await = false;
+        self.service_available.store(false, Ordering::Release);
+        info!("Disconnected from COSMIC compositor");
     }
 }
 
@@ -240,7 +478,7 @@ mod tests {
         // In test environment, D-Bus service won't be available
         assert!(!caps.can_inject_keyboard); // False until cosmic-comp implements D-Bus
         assert!(!caps.can_inject_pointer); // False until cosmic-comp implements D-Bus
-        assert!(!caps.can_capture_screen); // False until PipeWire is integrated
+        assert!(!caps.can_capture_screen); // Captured by cosmic-comp, which is absent
     }
 
     #[tokio::test]
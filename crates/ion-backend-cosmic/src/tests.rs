@@ -23,7 +23,7 @@ async fn test_cosmic_backend_capabilities() {
     // In test environment, D-Bus service won't be available
     assert!(!caps.can_inject_keyboard); // False until cosmic-comp implements D-Bus
     assert!(!caps.can_inject_pointer);  // False until cosmic-comp implements D-Bus
-    assert!(!caps.can_capture_screen);  // False until PipeWire is integrated
+    assert!(!caps.can_capture_screen);  // Captured by cosmic-comp, which is absent
 }
 
 #[tokio::test]
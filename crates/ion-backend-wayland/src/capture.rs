@@ -38,7 +38,5 @@ pub async fn start_capture(
     );
 
     // For now, return a placeholder stream
-    Ok(CaptureStream {
-        session_id: session.clone(),
-    })
+    Ok(CaptureStream::new(session.clone()))
 }
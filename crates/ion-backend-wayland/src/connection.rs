@@ -2,20 +2,377 @@
 // Copyright © 2024-2025 DataScienceBioLab
 
 //! Wayland connection management.
+//!
+//! The connection binds the protocol globals we need, batches outgoing
+//! requests so they are flushed once per event-loop turn, and transparently
+//! reconnects (re-binding globals) when the compositor goes away.
+//!
+//! Every `wl_seat` is bound so input can be injected on a named seat;
+//! virtual devices are created per seat on first use.
+//!
+//! Pointer locks are `zwp_locked_pointer_v1` objects on a surface of our
+//! own, which the compositor enforces while that surface has pointer
+//! focus. They are recorded per portal session and taken again after a
+//! reconnect.
+
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use tracing::{debug, info};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+use wayland_client::backend::ObjectId;
+use wayland_client::backend::WaylandError;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{
+    wl_callback, wl_compositor, wl_pointer, wl_registry, wl_seat, wl_surface,
+};
+use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, Proxy, QueueHandle};
+use wayland_protocols::wp::pointer_constraints::zv1::client::zwp_locked_pointer_v1::ZwpLockedPointerV1;
+use wayland_protocols::wp::pointer_constraints::zv1::client::zwp_pointer_constraints_v1::{
+    Lifetime, ZwpPointerConstraintsV1,
+};
+use wayland_protocols_wlr::screencopy::v1::client::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1;
+use wayland_protocols_wlr::virtual_pointer::v1::client::zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1;
+use wayland_protocols_wlr::virtual_pointer::v1::client::zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1;
+
+use ion_core::backend::{BackendError, BackendResult};
+use ion_core::session::SessionId;
+
+use crate::protocols::registry::{Protocol, WaylandProtocols};
+use crate::protocols::virtual_keyboard::{self, ZwpVirtualKeyboardManagerV1, ZwpVirtualKeyboardV1};
+
+/// Lifecycle notifications emitted by a [`WaylandConnection`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// The compositor socket broke and a reconnect is being attempted.
+    Disconnected,
+    /// The connection was re-established and globals were re-bound.
+    Reconnected {
+        /// Number of attempts it took
+        attempts: u32,
+    },
+    /// Reconnecting gave up; the connection stays down.
+    ReconnectFailed {
+        /// Number of attempts made
+        attempts: u32,
+    },
+}
+
+/// Bounds for automatic reconnection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Maximum number of connection attempts after a disconnect
+    pub max_attempts: u32,
+    /// Delay before the first attempt; grows linearly per attempt
+    pub backoff: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Highest `wl_seat` version we bind; version 2 added the seat name.
+const SEAT_VERSION: u32 = 2;
+
+/// Dispatch state for our event queue.
+///
+/// Seat names are the only events we care about.
+#[derive(Debug, Default)]
+struct ProtocolState {
+    seat_names: HashMap<ObjectId, String>,
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for ProtocolState {
+    fn event(
+        _state: &mut Self,
+        _registry: &wl_registry::WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Globals appearing after startup are picked up on reconnect
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for ProtocolState {
+    fn event(
+        state: &mut Self,
+        seat: &wl_seat::WlSeat,
+        event: wl_seat::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wl_seat::Event::Name { name } = event {
+            state.seat_names.insert(seat.id(), name);
+        }
+    }
+}
+
+delegate_noop!(ProtocolState: ignore wl_callback::WlCallback);
+delegate_noop!(ProtocolState: ZwlrVirtualPointerManagerV1);
+delegate_noop!(ProtocolState: ZwlrVirtualPointerV1);
+delegate_noop!(ProtocolState: ZwpVirtualKeyboardManagerV1);
+delegate_noop!(ProtocolState: ZwpVirtualKeyboardV1);
+delegate_noop!(ProtocolState: ZwlrScreencopyManagerV1);
+delegate_noop!(ProtocolState: wl_compositor::WlCompositor);
+delegate_noop!(ProtocolState: ignore wl_surface::WlSurface);
+delegate_noop!(ProtocolState: ignore wl_pointer::WlPointer);
+delegate_noop!(ProtocolState: ZwpPointerConstraintsV1);
+delegate_noop!(ProtocolState: ignore ZwpLockedPointerV1);
+
+/// A live socket with its bound globals.
+#[derive(Debug)]
+struct Session {
+    conn: Connection,
+    queue: EventQueue<ProtocolState>,
+    state: ProtocolState,
+    virtual_pointer: Option<ZwlrVirtualPointerManagerV1>,
+    virtual_keyboard: Option<ZwpVirtualKeyboardManagerV1>,
+    screencopy: Option<ZwlrScreencopyManagerV1>,
+    compositor: Option<wl_compositor::WlCompositor>,
+    pointer_constraints: Option<ZwpPointerConstraintsV1>,
+    /// Every seat, in advertisement order
+    seats: Vec<wl_seat::WlSeat>,
+    /// Virtual pointers created so far, by seat name (`None` = primary)
+    pointers: Mutex<HashMap<Option<String>, ZwlrVirtualPointerV1>>,
+    /// Virtual keyboards created so far, by seat name (`None` = primary)
+    keyboards: Mutex<HashMap<Option<String>, ZwpVirtualKeyboardV1>>,
+    /// Seat pointers locked so far, by seat name (`None` = primary)
+    seat_pointers: Mutex<HashMap<Option<String>, wl_pointer::WlPointer>>,
+    /// Pointer locks by portal session, with the surface each is held on
+    locks: Mutex<HashMap<SessionId, (ZwpLockedPointerV1, wl_surface::WlSurface)>>,
+    /// Known globals at their advertised versions
+    advertised: WaylandProtocols,
+}
+
+impl Session {
+    /// Connect to `socket_path` and bind the globals we use.
+    fn open(socket_path: &Path) -> Result<Self> {
+        let stream = UnixStream::connect(socket_path)
+            .with_context(|| format!("failed to connect to {}", socket_path.display()))?;
+        let conn =
+            Connection::from_socket(stream).context("failed to set up Wayland connection")?;
+        let (globals, mut queue) = registry_queue_init::<ProtocolState>(&conn)
+            .context("failed to read Wayland registry")?;
+        let qh = queue.handle();
+
+        let seats: Vec<wl_seat::WlSeat> = globals.contents().with_list(|list| {
+            list.iter()
+                .filter(|g| g.interface == wl_seat::WlSeat::interface().name)
+                .map(|g| {
+                    globals
+                        .registry()
+                        .bind(g.name, g.version.min(SEAT_VERSION), &qh, ())
+                })
+                .collect()
+        });
+        let mut state = ProtocolState::default();
+        if !seats.is_empty() {
+            // Collect the seat names
+            queue
+                .roundtrip(&mut state)
+                .context("failed to read Wayland seat names")?;
+        }
+
+        let session = Self {
+            virtual_pointer: globals.bind(&qh, 1..=2, ()).ok(),
+            virtual_keyboard: globals.bind(&qh, 1..=1, ()).ok(),
+            screencopy: globals.bind(&qh, 1..=3, ()).ok(),
+            compositor: globals.bind(&qh, 1..=4, ()).ok(),
+            pointer_constraints: globals.bind(&qh, 1..=1, ()).ok(),
+            advertised: globals.contents().with_list(|list| {
+                WaylandProtocols::from_globals(
+                    list.iter().map(|g| (g.interface.as_str(), g.version)),
+                )
+            }),
+            seats,
+            pointers: Mutex::default(),
+            keyboards: Mutex::default(),
+            seat_pointers: Mutex::default(),
+            locks: Mutex::default(),
+            conn,
+            queue,
+            state,
+        };
+        session
+            .conn
+            .flush()
+            .context("failed to bind Wayland globals")?;
+
+        Ok(session)
+    }
+
+    /// Names of the seats that announced one, in advertisement order.
+    fn seat_names(&self) -> Vec<String> {
+        self.seats
+            .iter()
+            .filter_map(|seat| self.state.seat_names.get(&seat.id()).cloned())
+            .collect()
+    }
+
+    /// The seat named `name`.
+    fn seat(&self, name: &str) -> Option<&wl_seat::WlSeat> {
+        self.seats
+            .iter()
+            .find(|seat| self.state.seat_names.get(&seat.id()).map(String::as_str) == Some(name))
+    }
+
+    /// Available protocols, with bound globals at their bound version.
+    fn protocols(&self) -> WaylandProtocols {
+        let mut protocols = self.advertised.clone();
+        protocols.set(
+            Protocol::VirtualPointer,
+            self.virtual_pointer.as_ref().map(Proxy::version),
+        );
+        protocols.set(
+            Protocol::VirtualKeyboard,
+            self.virtual_keyboard.as_ref().map(Proxy::version),
+        );
+        protocols.set(
+            Protocol::Screencopy,
+            self.screencopy.as_ref().map(Proxy::version),
+        );
+        // Locking also needs a surface to lock on
+        protocols.set(
+            Protocol::PointerConstraints,
+            self.pointer_constraints
+                .as_ref()
+                .filter(|_| self.compositor.is_some())
+                .map(Proxy::version),
+        );
+        protocols
+    }
+
+    /// Release the pointer lock `session_id` holds, if any.
+    fn unlock_pointer(&self, session_id: &SessionId) -> bool {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(session_id);
+        let Some((locked, surface)) = lock else {
+            return false;
+        };
+        locked.destroy();
+        surface.destroy();
+        true
+    }
+}
+
+/// Opens a session on the blocking pool, since connecting and the
+/// registry roundtrip block.
+async fn open_session(socket_path: &Path) -> Result<Session> {
+    let socket_path = socket_path.to_path_buf();
+    tokio::task::spawn_blocking(move || Session::open(&socket_path))
+        .await
+        .context("Wayland connect task failed")?
+}
+
+/// Result of [`WaylandConnection::flush_pending`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Flushed {
+    /// This many requests went out
+    Sent(usize),
+    /// The socket is down; requests were discarded and a reconnect is due
+    Disconnected,
+}
+
+/// A session opened by [`Reconnect::open`], ready to be adopted.
+#[derive(Debug)]
+pub(crate) struct Reopened {
+    session: Session,
+    attempts: u32,
+}
+
+/// What a reconnect needs, detached from the connection.
+///
+/// Lets the caller wait out the backoff without holding whatever lock
+/// guards the [`WaylandConnection`], and adopt the result with
+/// [`WaylandConnection::finish_reconnect`].
+#[derive(Debug, Clone)]
+pub(crate) struct Reconnect {
+    socket_path: PathBuf,
+    policy: ReconnectPolicy,
+}
+
+impl Reconnect {
+    /// Try to open a new session, backing off between attempts.
+    pub(crate) async fn open(&self) -> Result<Reopened> {
+        let mut last_error = None;
+        for attempt in 1..=self.policy.max_attempts {
+            tokio::time::sleep(self.policy.backoff * attempt).await;
+
+            match open_session(&self.socket_path).await {
+                Ok(session) => {
+                    return Ok(Reopened {
+                        session,
+                        attempts: attempt,
+                    })
+                },
+                Err(e) => {
+                    debug!("Reconnect attempt {} failed: {:#}", attempt, e);
+                    last_error = Some(e);
+                },
+            }
+        }
+
+        let attempts = self.policy.max_attempts;
+        Err(last_error
+            .unwrap_or_else(|| anyhow::anyhow!("reconnect disabled by policy"))
+            .context(format!("giving up after {attempts} reconnect attempt(s)")))
+    }
+}
+
+/// Whether a Wayland error means the compositor went away.
+fn is_disconnect(err: &WaylandError) -> bool {
+    match err {
+        WaylandError::Io(e) => matches!(
+            e.kind(),
+            ErrorKind::BrokenPipe
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::NotConnected
+                | ErrorKind::UnexpectedEof
+        ),
+        WaylandError::Protocol(_) => false,
+    }
+}
 
 /// Wayland compositor connection.
 ///
 /// Manages the connection to the Wayland compositor and tracks
-/// available protocol extensions.
+/// available protocol extensions. Probe results survive a reconnect:
+/// while disconnected the last known values are reported, and they are
+/// refreshed once globals have been re-bound.
 #[derive(Debug)]
 pub struct WaylandConnection {
+    socket_path: PathBuf,
     compositor_name: String,
-    has_virtual_pointer: bool,
-    has_virtual_keyboard: bool,
-    has_screencopy: bool,
+    session: Option<Session>,
+    protocols: WaylandProtocols,
+    /// Requests queued since the last flush
+    pending: AtomicUsize,
+    policy: ReconnectPolicy,
+    events: broadcast::Sender<ConnectionEvent>,
+    /// Seat names, kept across reconnects like `protocols`
+    seats: Vec<String>,
+    /// Seat each portal session holds the pointer lock of
+    pointer_locks: Mutex<HashMap<SessionId, Option<String>>>,
+    /// Origin of the timestamps sent with input requests
+    epoch: Instant,
 }
 
 impl WaylandConnection {
@@ -31,34 +388,33 @@ impl WaylandConnection {
 
         debug!("Using WAYLAND_DISPLAY: {}", wayland_display);
 
-        // For now, create a basic connection
-        // In a full implementation, we'd use wayland-client to:
-        // 1. Connect to the compositor
-        // 2. Query available globals
-        // 3. Bind to protocol interfaces
-
-        // Probe for capabilities
-        // This would check for:
-        // - zwlr_virtual_pointer_manager_v1
-        // - zwp_virtual_keyboard_manager_v1
-        // - zwlr_screencopy_manager_v1
+        let socket_path = Self::resolve_socket(&wayland_display)?;
+        Self::connect_to(socket_path, ReconnectPolicy::default()).await
+    }
 
+    /// Connect to the compositor listening on `socket_path`.
+    pub async fn connect_to(socket_path: PathBuf, policy: ReconnectPolicy) -> Result<Self> {
+        let session = open_session(&socket_path).await?;
         let compositor_name = Self::detect_compositor_name();
-        let (has_virtual_pointer, has_virtual_keyboard, has_screencopy) =
-            Self::probe_protocols().await;
 
         info!("Connected to Wayland compositor: {}", compositor_name);
-        debug!(
-            "Protocol support: pointer={}, keyboard={}, screencopy={}",
-            has_virtual_pointer, has_virtual_keyboard, has_screencopy
-        );
 
-        Ok(Self {
+        let (events, _) = broadcast::channel(16);
+        let mut conn = Self {
+            socket_path,
             compositor_name,
-            has_virtual_pointer,
-            has_virtual_keyboard,
-            has_screencopy,
-        })
+            session: None,
+            protocols: WaylandProtocols::default(),
+            pending: AtomicUsize::new(0),
+            policy,
+            events,
+            seats: Vec::new(),
+            pointer_locks: Mutex::default(),
+            epoch: Instant::now(),
+        };
+        conn.adopt(session);
+
+        Ok(conn)
     }
 
     /// Get the compositor name.
@@ -66,19 +422,346 @@ impl WaylandConnection {
         &self.compositor_name
     }
 
+    /// Protocols the compositor offers, with their versions.
+    pub fn protocols(&self) -> &WaylandProtocols {
+        &self.protocols
+    }
+
     /// Check if virtual pointer protocol is available.
     pub fn has_virtual_pointer(&self) -> bool {
-        self.has_virtual_pointer
+        self.protocols.can_inject_pointer()
     }
 
     /// Check if virtual keyboard protocol is available.
     pub fn has_virtual_keyboard(&self) -> bool {
-        self.has_virtual_keyboard
+        self.protocols.can_inject_keyboard()
     }
 
     /// Check if screencopy protocol is available.
     pub fn has_screencopy(&self) -> bool {
-        self.has_screencopy
+        self.protocols.has(Protocol::Screencopy)
+    }
+
+    /// Names of the compositor's seats.
+    ///
+    /// Seats too old to announce a name are left out; they can only be
+    /// reached as the primary seat.
+    pub fn seats(&self) -> &[String] {
+        &self.seats
+    }
+
+    /// Fail unless `seat` is `None` (the primary seat) or a known seat.
+    pub fn check_seat(&self, seat: Option<&str>) -> BackendResult<()> {
+        match seat {
+            Some(name) if !self.seats.iter().any(|s| s == name) => {
+                Err(BackendError::SeatNotFound(name.to_string()))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Virtual pointer injecting on `seat`, `None` for the primary seat.
+    ///
+    /// Created on first use and reused until the connection is lost.
+    pub fn virtual_pointer(&self, seat: Option<&str>) -> BackendResult<ZwlrVirtualPointerV1> {
+        self.check_seat(seat)?;
+        let session = self
+            .session
+            .as_ref()
+            .ok_or_else(|| BackendError::ConnectionFailed("Not connected".to_string()))?;
+        let manager = session.virtual_pointer.as_ref().ok_or_else(|| {
+            BackendError::InputInjectionFailed("Virtual pointer protocol not available".to_string())
+        })?;
+
+        let mut pointers = session
+            .pointers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(pointer) = pointers.get(&seat.map(str::to_string)) {
+            return Ok(pointer.clone());
+        }
+        let wl_seat = match seat {
+            Some(name) => Some(
+                session
+                    .seat(name)
+                    .ok_or_else(|| BackendError::SeatNotFound(name.to_string()))?,
+            ),
+            None => None,
+        };
+        let pointer = manager.create_virtual_pointer(wl_seat, &session.queue.handle(), ());
+        debug!(seat = seat.unwrap_or("primary"), "Created virtual pointer");
+        pointers.insert(seat.map(str::to_string), pointer.clone());
+        Ok(pointer)
+    }
+
+    /// Virtual keyboard injecting on `seat`, `None` for the primary seat.
+    ///
+    /// Unlike pointers, keyboards must name a seat, so the primary one is
+    /// the first seat advertised. Created with its keymap on first use and
+    /// reused until the connection is lost.
+    pub fn virtual_keyboard(&self, seat: Option<&str>) -> BackendResult<ZwpVirtualKeyboardV1> {
+        self.check_seat(seat)?;
+        let session = self
+            .session
+            .as_ref()
+            .ok_or_else(|| BackendError::ConnectionFailed("Not connected".to_string()))?;
+        let manager = session.virtual_keyboard.as_ref().ok_or_else(|| {
+            BackendError::InputInjectionFailed(
+                "Virtual keyboard protocol not available".to_string(),
+            )
+        })?;
+
+        let mut keyboards = session
+            .keyboards
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(keyboard) = keyboards.get(&seat.map(str::to_string)) {
+            return Ok(keyboard.clone());
+        }
+        let wl_seat = match seat {
+            Some(name) => session
+                .seat(name)
+                .ok_or_else(|| BackendError::SeatNotFound(name.to_string()))?,
+            None => session.seats.first().ok_or_else(|| {
+                BackendError::InputInjectionFailed("No seat for a virtual keyboard".to_string())
+            })?,
+        };
+        let keyboard = manager.create_virtual_keyboard(wl_seat, &session.queue.handle(), ());
+        virtual_keyboard::send_keymap(&keyboard).map_err(|e| {
+            BackendError::InputInjectionFailed(format!("Failed to send keymap: {e}"))
+        })?;
+        debug!(seat = seat.unwrap_or("primary"), "Created virtual keyboard");
+        keyboards.insert(seat.map(str::to_string), keyboard.clone());
+        Ok(keyboard)
+    }
+
+    /// Lock the pointer of `seat`, `None` for the primary seat, for
+    /// `session_id`, or release the lock the session holds.
+    ///
+    /// Locks are kept across reconnects; releasing one while disconnected
+    /// only forgets it.
+    pub fn set_pointer_lock(
+        &self,
+        session_id: &SessionId,
+        seat: Option<&str>,
+        locked: bool,
+    ) -> BackendResult<()> {
+        let mut locks = self
+            .pointer_locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if !locked {
+            let released = locks.remove(session_id).is_some()
+                && self
+                    .session
+                    .as_ref()
+                    .is_some_and(|session| session.unlock_pointer(session_id));
+            if released {
+                debug!(session = %session_id, "Released pointer lock");
+                self.queue_request();
+            }
+            return Ok(());
+        }
+        if locks.contains_key(session_id) {
+            return Ok(());
+        }
+        self.lock_pointer(session_id, seat)?;
+        locks.insert(session_id.clone(), seat.map(str::to_string));
+        Ok(())
+    }
+
+    /// Create the lock on `seat`'s pointer for `session_id`.
+    fn lock_pointer(&self, session_id: &SessionId, seat: Option<&str>) -> BackendResult<()> {
+        self.check_seat(seat)?;
+        let session = self
+            .session
+            .as_ref()
+            .ok_or_else(|| BackendError::ConnectionFailed("Not connected".to_string()))?;
+        let (Some(compositor), Some(constraints)) =
+            (&session.compositor, &session.pointer_constraints)
+        else {
+            return Err(BackendError::NotAvailable(
+                "Pointer constraints protocol not available".to_string(),
+            ));
+        };
+
+        let wl_seat = match seat {
+            Some(name) => session
+                .seat(name)
+                .ok_or_else(|| BackendError::SeatNotFound(name.to_string()))?,
+            None => session.seats.first().ok_or_else(|| {
+                BackendError::InputInjectionFailed("No seat to lock the pointer of".to_string())
+            })?,
+        };
+        let qh = session.queue.handle();
+        let pointer = session
+            .seat_pointers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(seat.map(str::to_string))
+            .or_insert_with(|| wl_seat.get_pointer(&qh, ()))
+            .clone();
+        let surface = compositor.create_surface(&qh, ());
+        let locked =
+            constraints.lock_pointer(&surface, &pointer, None, Lifetime::Persistent, &qh, ());
+        session
+            .locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(session_id.clone(), (locked, surface));
+        self.queue_request();
+
+        debug!(
+            session = %session_id,
+            seat = seat.unwrap_or("primary"),
+            "Locked pointer"
+        );
+        Ok(())
+    }
+
+    /// Millisecond timestamp for input requests.
+    ///
+    /// Wraps around like the protocol's `u32` timestamps do.
+    pub fn timestamp(&self) -> u32 {
+        let millis = self.epoch.elapsed().as_millis() % (1 << 32);
+        u32::try_from(millis).unwrap_or_default()
+    }
+
+    /// Check whether the compositor socket is currently up.
+    pub fn is_connected(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// Subscribe to disconnect/reconnect notifications.
+    pub fn subscribe(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.events.subscribe()
+    }
+
+    /// Record a protocol request to be sent with the next flush.
+    pub fn queue_request(&self) {
+        self.pending.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Flush all queued requests in one batch.
+    ///
+    /// Call this once per event-loop turn. The batch is terminated with a
+    /// single `wl_display.sync` so the compositor acknowledges it as a
+    /// whole. A broken socket is reported as [`Flushed::Disconnected`]
+    /// for the caller to reconnect; queued requests are discarded since
+    /// their objects died with the socket.
+    pub(crate) fn flush_pending(&mut self) -> Result<Flushed> {
+        let pending = self.pending.swap(0, Ordering::Relaxed);
+        if pending == 0 {
+            return Ok(Flushed::Sent(0));
+        }
+
+        let Some(session) = self.session.as_mut() else {
+            return Ok(Flushed::Disconnected);
+        };
+
+        match Self::send_batch(session) {
+            Ok(()) => {
+                debug!("Flushed {} batched Wayland requests", pending);
+                Ok(Flushed::Sent(pending))
+            },
+            Err(e) if is_disconnect(&e) => {
+                warn!("Wayland compositor connection lost: {}", e);
+                self.session = None;
+                let _ = self.events.send(ConnectionEvent::Disconnected);
+                Ok(Flushed::Disconnected)
+            },
+            Err(e) => Err(e).context("failed to flush Wayland requests"),
+        }
+    }
+
+    /// Detach what a reconnect needs, so it can run without `&mut self`.
+    pub(crate) fn reconnector(&self) -> Reconnect {
+        Reconnect {
+            socket_path: self.socket_path.clone(),
+            policy: self.policy,
+        }
+    }
+
+    /// Adopt the outcome of a [`Reconnect::open`] and report it.
+    pub(crate) fn finish_reconnect(&mut self, reopened: Result<Reopened>) -> Result<()> {
+        match reopened {
+            Ok(Reopened { session, attempts }) => {
+                self.adopt(session);
+                info!(
+                    "Reconnected to Wayland compositor after {} attempt(s)",
+                    attempts
+                );
+                let _ = self.events.send(ConnectionEvent::Reconnected { attempts });
+                Ok(())
+            },
+            Err(e) => {
+                let attempts = self.policy.max_attempts;
+                let _ = self
+                    .events
+                    .send(ConnectionEvent::ReconnectFailed { attempts });
+                Err(e)
+            },
+        }
+    }
+
+    /// Write the batch terminator, flush, and drain any replies.
+    fn send_batch(session: &mut Session) -> Result<(), WaylandError> {
+        let qh = session.queue.handle();
+        session.conn.display().sync(&qh, ());
+        session.conn.flush()?;
+
+        if let Some(guard) = session.conn.prepare_read() {
+            match guard.read() {
+                Ok(_) => {},
+                Err(WaylandError::Io(e)) if e.kind() == ErrorKind::WouldBlock => {},
+                Err(e) => return Err(e),
+            }
+        }
+        session
+            .queue
+            .dispatch_pending(&mut session.state)
+            .map_err(|e| match e {
+                wayland_client::DispatchError::Backend(e) => e,
+                wayland_client::DispatchError::BadMessage { .. } => {
+                    WaylandError::Io(std::io::Error::from(ErrorKind::InvalidData))
+                },
+            })?;
+
+        Ok(())
+    }
+
+    /// Take ownership of a freshly opened session and refresh probes.
+    fn adopt(&mut self, session: Session) {
+        self.protocols = session.protocols();
+        self.seats = session.seat_names();
+        self.session = Some(session);
+
+        debug!("Protocol support: {}", self.protocols);
+        debug!("Seats: {:?}", self.seats);
+
+        // Objects died with the old socket; take the locks again
+        let locks = self
+            .pointer_locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        for (session_id, seat) in locks {
+            if let Err(e) = self.lock_pointer(&session_id, seat.as_deref()) {
+                warn!(session = %session_id, "Failed to restore pointer lock: {}", e);
+            }
+        }
+    }
+
+    /// Resolve `WAYLAND_DISPLAY` to a socket path.
+    ///
+    /// Relative names live in `XDG_RUNTIME_DIR`.
+    fn resolve_socket(display: &str) -> Result<PathBuf> {
+        let display = Path::new(display);
+        if display.is_absolute() {
+            return Ok(display.to_path_buf());
+        }
+        let runtime_dir = std::env::var("XDG_RUNTIME_DIR").context("XDG_RUNTIME_DIR not set")?;
+        Ok(Path::new(&runtime_dir).join(display))
     }
 
     /// Detect compositor name from environment.
@@ -96,31 +779,327 @@ impl WaylandConnection {
 
         "Wayland".to_string()
     }
+}
 
-    /// Probe for available Wayland protocols.
-    ///
-    /// This is a simplified version that makes conservative assumptions.
-    /// A full implementation would query the compositor's registry.
-    async fn probe_protocols() -> (bool, bool, bool) {
-        // Conservative defaults - assume protocols might be available
-        // In reality, we'd query wl_registry and check for:
-        // - zwlr_virtual_pointer_manager_v1
-        // - zwp_virtual_keyboard_manager_v1
-        // - zwlr_screencopy_manager_v1
-
-        // For wlroots-based compositors (Sway, River, etc.), these are usually available
-        let is_wlroots = std::env::var("SWAYSOCK").is_ok()
-            || std::env::var("XDG_CURRENT_DESKTOP")
-                .map(|d| d.contains("sway") || d.contains("river"))
-                .unwrap_or(false);
-
-        if is_wlroots {
-            debug!("Detected wlroots-based compositor - protocols likely available");
-            (true, true, true)
-        } else {
-            // For other compositors, be conservative
-            debug!("Unknown compositor - protocols may not be available");
-            (false, false, false)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+
+    use crate::fake_compositor::{serve, socket_path, u32_at, ALL_GLOBALS};
+
+    #[tokio::test]
+    async fn test_detached_reconnect_leaves_connection_unlocked() {
+        let path = socket_path("detached");
+        let listener = UnixListener::bind(&path).unwrap();
+        let (drop_tx, drop_rx) = mpsc::channel();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            serve(&mut stream, ALL_GLOBALS, Some(1));
+            drop_rx.recv().unwrap();
+            drop(stream);
+            let (mut stream, _) = listener.accept().unwrap();
+            serve(&mut stream, ALL_GLOBALS, None);
+        });
+
+        let policy = ReconnectPolicy {
+            max_attempts: 1,
+            backoff: Duration::from_millis(50),
+        };
+        let conn = WaylandConnection::connect_to(path.clone(), policy)
+            .await
+            .unwrap();
+        let conn = Arc::new(tokio::sync::RwLock::new(conn));
+        drop_tx.send(()).unwrap();
+
+        // Wait for the drop to be noticed
+        let reconnect = loop {
+            let mut guard = conn.write().await;
+            guard.queue_request();
+            if guard.flush_pending().unwrap() == Flushed::Disconnected {
+                break guard.reconnector();
+            }
+            drop(guard);
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        };
+
+        let reopening = tokio::spawn(async move { reconnect.open().await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let guard = conn.try_read().expect("lock is free during backoff");
+        assert!(!guard.is_connected());
+        drop(guard);
+
+        let reopened = reopening.await.unwrap();
+        let mut guard = conn.write().await;
+        guard.finish_reconnect(reopened).unwrap();
+        assert!(guard.is_connected());
+        assert!(guard.has_screencopy());
+
+        drop(guard);
+        drop(conn);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_probe_records_protocol_versions() {
+        let path = socket_path("versions");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            serve(
+                &mut stream,
+                &[
+                    ("wl_seat", 8),
+                    ("wl_output", 4),
+                    ("zwlr_screencopy_manager_v1", 5),
+                    ("wl_compositor", 6),
+                ],
+                None,
+            );
+        });
+
+        let conn = WaylandConnection::connect_to(path.clone(), ReconnectPolicy::default())
+            .await
+            .unwrap();
+        let protocols = conn.protocols();
+
+        assert_eq!(protocols.version(Protocol::Seat), Some(8));
+        assert_eq!(protocols.version(Protocol::Output), Some(4));
+        // Bound at the highest version we support, not the advertised one
+        assert_eq!(protocols.version(Protocol::Screencopy), Some(3));
+        assert!(protocols.has_capture_damage());
+        assert!(protocols.has_axis_value120());
+        assert!(!conn.has_virtual_pointer());
+        assert!(!conn.has_virtual_keyboard());
+
+        drop(conn);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_pointer_events_route_to_selected_seat() {
+        use ion_core::event::InputEvent;
+
+        use crate::input::inject_event;
+
+        let path = socket_path("seats");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            serve(
+                &mut stream,
+                &[("wl_seat", 8), ("wl_seat", 8), ALL_GLOBALS[0]],
+                None,
+            )
+        });
+
+        let mut conn = WaylandConnection::connect_to(path.clone(), ReconnectPolicy::default())
+            .await
+            .unwrap();
+        assert_eq!(conn.seats(), ["seat0", "seat1"]);
+
+        inject_event(&conn, Some("seat1"), &InputEvent::pointer_motion(1.0, 2.0)).unwrap();
+        inject_event(&conn, Some("seat1"), &InputEvent::left_click(true)).unwrap();
+        inject_event(&conn, None, &InputEvent::pointer_motion(3.0, 4.0)).unwrap();
+        assert_eq!(conn.flush_pending().unwrap(), Flushed::Sent(3));
+        drop(conn);
+        let seen = server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        // One virtual pointer per seat: seat1's, then the primary seat's
+        let manager = seen.object("zwlr_virtual_pointer_manager_v1", 0);
+        let created = seen.requests_on(manager);
+        assert_eq!(created.len(), 2);
+        assert_eq!(u32_at(created[0].1, 0), seen.object("wl_seat", 1));
+        assert_eq!(u32_at(created[1].1, 0), 0);
+
+        // motion, frame, button, frame
+        let on_seat1: Vec<u32> = seen
+            .requests_on(u32_at(created[0].1, 4))
+            .into_iter()
+            .map(|(opcode, _)| opcode)
+            .collect();
+        assert_eq!(on_seat1, [0, 4, 2, 4]);
+        assert_eq!(seen.requests_on(u32_at(created[1].1, 4)).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_keys_route_to_selected_seat() {
+        use ion_core::event::{InputEvent, KeyState};
+
+        use crate::input::inject_event;
+
+        let path = socket_path("keyboard-seats");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            serve(
+                &mut stream,
+                &[("wl_seat", 8), ("wl_seat", 8), ALL_GLOBALS[1]],
+                None,
+            )
+        });
+
+        let mut conn = WaylandConnection::connect_to(path.clone(), ReconnectPolicy::default())
+            .await
+            .unwrap();
+        assert!(conn.has_virtual_keyboard());
+
+        for seat in [Some("seat1"), None] {
+            inject_event(&conn, seat, &InputEvent::key(30, KeyState::Pressed)).unwrap();
+            inject_event(&conn, seat, &InputEvent::key(30, KeyState::Released)).unwrap();
+        }
+        assert_eq!(conn.flush_pending().unwrap(), Flushed::Sent(4));
+        drop(conn);
+        let seen = server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        // One keyboard per seat; the primary one is bound to the first seat
+        let manager = seen.object("zwp_virtual_keyboard_manager_v1", 0);
+        let created = seen.requests_on(manager);
+        assert_eq!(created.len(), 2);
+        assert_eq!(u32_at(created[0].1, 0), seen.object("wl_seat", 1));
+        assert_eq!(u32_at(created[1].1, 0), seen.object("wl_seat", 0));
+
+        // keymap, then press and release of evdev key 30
+        for (_, create) in created {
+            let requests = seen.requests_on(u32_at(create, 4));
+            let opcodes: Vec<u32> = requests.iter().map(|(opcode, _)| *opcode).collect();
+            assert_eq!(opcodes, [0, 1, 1]);
+            assert_eq!(u32_at(requests[1].1, 4), 30);
+            assert_eq!(u32_at(requests[1].1, 8), 1);
+            assert_eq!(u32_at(requests[2].1, 8), 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pointer_lock_is_held_on_selected_seat() {
+        let path = socket_path("pointer-lock");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            serve(
+                &mut stream,
+                &[
+                    ("wl_seat", 8),
+                    ("wl_seat", 8),
+                    ("wl_compositor", 6),
+                    ("zwp_pointer_constraints_v1", 1),
+                ],
+                None,
+            )
+        });
+
+        let mut conn = WaylandConnection::connect_to(path.clone(), ReconnectPolicy::default())
+            .await
+            .unwrap();
+        assert!(conn.protocols().can_lock_pointer());
+
+        let session = SessionId::new("/session/locked");
+        conn.set_pointer_lock(&session, Some("seat1"), true)
+            .unwrap();
+        // Locking again is a no-op
+        conn.set_pointer_lock(&session, Some("seat1"), true)
+            .unwrap();
+        assert_eq!(conn.flush_pending().unwrap(), Flushed::Sent(1));
+        conn.set_pointer_lock(&session, Some("seat1"), false)
+            .unwrap();
+        conn.set_pointer_lock(&session, Some("seat1"), false)
+            .unwrap();
+        assert_eq!(conn.flush_pending().unwrap(), Flushed::Sent(1));
+        drop(conn);
+        let seen = server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        // seat1's pointer, locked on a surface of our own
+        let pointers = seen.requests_on(seen.object("wl_seat", 1));
+        assert_eq!(pointers.len(), 1);
+        assert!(seen.requests_on(seen.object("wl_seat", 0)).is_empty());
+        let surfaces = seen.requests_on(seen.object("wl_compositor", 0));
+        assert_eq!(surfaces.len(), 1);
+
+        let locks = seen.requests_on(seen.object("zwp_pointer_constraints_v1", 0));
+        assert_eq!(locks.len(), 1);
+        let (opcode, lock) = locks[0];
+        assert_eq!(opcode, 1);
+        assert_eq!(u32_at(lock, 4), u32_at(surfaces[0].1, 0));
+        assert_eq!(u32_at(lock, 8), u32_at(pointers[0].1, 0));
+        // No region, persistent
+        assert_eq!(u32_at(lock, 12), 0);
+        assert_eq!(u32_at(lock, 16), 2);
+
+        // Released by destroying the lock and its surface
+        assert_eq!(seen.requests_on(u32_at(lock, 0)).len(), 1);
+        assert_eq!(seen.requests_on(u32_at(surfaces[0].1, 0)).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pointer_lock_needs_pointer_constraints() {
+        let path = socket_path("no-pointer-lock");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            serve(&mut stream, &[("wl_seat", 8), ("wl_compositor", 6)], None);
+        });
+
+        let conn = WaylandConnection::connect_to(path.clone(), ReconnectPolicy::default())
+            .await
+            .unwrap();
+        assert!(!conn.protocols().can_lock_pointer());
+
+        let session = SessionId::new("/session/unlocked");
+        assert!(matches!(
+            conn.set_pointer_lock(&session, None, true),
+            Err(BackendError::NotAvailable(_))
+        ));
+        // Nothing was recorded to release
+        conn.set_pointer_lock(&session, None, false).unwrap();
+
+        drop(conn);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_seat_is_rejected() {
+        use ion_core::event::{InputEvent, KeyState};
+
+        use crate::input::inject_event;
+
+        let path = socket_path("unknown-seat");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            serve(&mut stream, &[("wl_seat", 8), ("wl_seat", 8)], None)
+        });
+
+        let conn = WaylandConnection::connect_to(path.clone(), ReconnectPolicy::default())
+            .await
+            .unwrap();
+
+        for event in [
+            InputEvent::pointer_motion(1.0, 1.0),
+            InputEvent::key(30, KeyState::Pressed),
+        ] {
+            let err = inject_event(&conn, Some("seat2"), &event).unwrap_err();
+            assert!(matches!(&err, BackendError::SeatNotFound(seat) if seat == "seat2"));
+            assert_eq!(err.to_string(), "Seat not found: seat2");
         }
+        assert!(conn.virtual_pointer(Some("seat2")).is_err());
+
+        drop(conn);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
     }
 }
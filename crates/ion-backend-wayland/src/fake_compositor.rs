@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! In-process stand-in for a Wayland compositor, for tests.
+//!
+//! Speaks just enough of the wire protocol for a [`WaylandConnection`] to
+//! connect, bind globals, and flush requests, and records what it was sent.
+//!
+//! [`WaylandConnection`]: crate::connection::WaylandConnection
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+pub(crate) const ALL_GLOBALS: &[(&str, u32)] = &[
+    ("zwlr_virtual_pointer_manager_v1", 2),
+    ("zwp_virtual_keyboard_manager_v1", 1),
+    ("zwlr_screencopy_manager_v1", 3),
+];
+
+pub(crate) fn socket_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("ion-wl-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn message(object: u32, opcode: u32, args: &[u8]) -> Vec<u8> {
+    let size = u32::try_from(8 + args.len()).unwrap();
+    let mut msg = Vec::with_capacity(size as usize);
+    msg.extend_from_slice(&object.to_ne_bytes());
+    msg.extend_from_slice(&((size << 16) | opcode).to_ne_bytes());
+    msg.extend_from_slice(args);
+    msg
+}
+
+fn string_arg(value: &str) -> Vec<u8> {
+    let len = u32::try_from(value.len() + 1).unwrap();
+    let mut arg = len.to_ne_bytes().to_vec();
+    arg.extend_from_slice(value.as_bytes());
+    arg.resize(arg.len() + (4 - value.len() % 4), 0);
+    arg
+}
+
+pub(crate) fn u32_at(body: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes(body[offset..offset + 4].try_into().unwrap())
+}
+
+fn global_event(registry: u32, name: u32, interface: &str, version: u32) -> Vec<u8> {
+    let mut args = name.to_ne_bytes().to_vec();
+    args.extend(string_arg(interface));
+    args.extend_from_slice(&version.to_ne_bytes());
+    message(registry, 0, &args)
+}
+
+/// What a client asked of [`serve`].
+#[derive(Debug, Default)]
+pub(crate) struct Served {
+    /// Bound globals in bind order: object ID and interface
+    pub(crate) bound: Vec<(u32, String)>,
+    /// Requests on anything but `wl_display`: object, opcode, body
+    pub(crate) requests: Vec<(u32, u32, Vec<u8>)>,
+}
+
+impl Served {
+    /// Object ID of the `nth` bound `interface`.
+    pub(crate) fn object(&self, interface: &str, nth: usize) -> u32 {
+        self.bound
+            .iter()
+            .filter(|(_, bound)| bound == interface)
+            .nth(nth)
+            .unwrap()
+            .0
+    }
+
+    /// Requests sent on `object`: opcode and body.
+    pub(crate) fn requests_on(&self, object: u32) -> Vec<(u32, &[u8])> {
+        self.requests
+            .iter()
+            .filter(|(target, _, _)| *target == object)
+            .map(|(_, opcode, body)| (*opcode, body.as_slice()))
+            .collect()
+    }
+}
+
+/// Serve one client with a minimal `wl_display`/`wl_registry`.
+///
+/// Bound seats are named `seat0`, `seat1`, ... in bind order.
+///
+/// Returns after `syncs` sync requests have been answered, or when the
+/// client hangs up. Dropping the stream then drops the connection.
+pub(crate) fn serve(
+    stream: &mut UnixStream,
+    globals: &[(&str, u32)],
+    syncs: Option<usize>,
+) -> Served {
+    let mut served = Served::default();
+    let mut registry = None;
+    let mut answered = 0;
+    loop {
+        let mut header = [0u8; 8];
+        if stream.read_exact(&mut header).is_err() {
+            return served;
+        }
+        let object = u32::from_ne_bytes(header[0..4].try_into().unwrap());
+        let word = u32::from_ne_bytes(header[4..8].try_into().unwrap());
+        let opcode = word & 0xffff;
+        let mut body = vec![0u8; (word >> 16) as usize - 8];
+        if stream.read_exact(&mut body).is_err() {
+            return served;
+        }
+        if object != 1 {
+            // wl_registry.bind -> wl_seat.name for seats
+            if registry == Some(object) && opcode == 0 {
+                let len = u32_at(&body, 4) as usize;
+                let interface = String::from_utf8(body[8..7 + len].to_vec()).unwrap();
+                let new_id = u32_at(&body, 12 + len.div_ceil(4) * 4);
+                if interface == "wl_seat" {
+                    let seats = served.bound.iter().filter(|(_, i)| *i == interface);
+                    let name = format!("seat{}", seats.count());
+                    let _ = stream.write_all(&message(new_id, 1, &string_arg(&name)));
+                }
+                served.bound.push((new_id, interface));
+            }
+            served.requests.push((object, opcode, body));
+            continue;
+        }
+        let new_id = u32::from_ne_bytes(body[0..4].try_into().unwrap());
+        match opcode {
+            // wl_display.sync -> wl_callback.done + wl_display.delete_id
+            0 => {
+                let mut out = message(new_id, 0, &0u32.to_ne_bytes());
+                out.extend(message(1, 1, &new_id.to_ne_bytes()));
+                let _ = stream.write_all(&out);
+                answered += 1;
+                if syncs == Some(answered) {
+                    return served;
+                }
+            },
+            // wl_display.get_registry -> wl_registry.global per global
+            1 => {
+                registry = Some(new_id);
+                let mut out = Vec::new();
+                for (name, (interface, version)) in (1u32..).zip(globals) {
+                    out.extend(global_event(new_id, name, interface, *version));
+                }
+                let _ = stream.write_all(&out);
+            },
+            _ => {},
+        }
+    }
+}
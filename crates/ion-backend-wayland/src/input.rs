@@ -4,41 +4,58 @@
 //! Input injection via Wayland protocols.
 
 use tracing::{debug, info, warn};
+use wayland_client::protocol::{wl_keyboard, wl_pointer};
 
 use ion_core::backend::{BackendError, BackendResult};
-use ion_core::event::{ButtonState, InputEvent, KeyState};
+use ion_core::event::{AxisSource, ButtonState, InputEvent, KeyState, ScrollUnit};
+use ion_core::redact::Sensitive;
 
 use crate::connection::WaylandConnection;
 
 /// Inject an input event into the Wayland compositor.
 ///
-/// Uses virtual pointer/keyboard protocols where available.
-pub async fn inject_event(conn: &WaylandConnection, event: InputEvent) -> BackendResult<()> {
-    match event {
+/// Uses virtual pointer/keyboard protocols where available. `seat` names
+/// the seat to inject on, `None` for the primary one; unknown seats are
+/// rejected with [`BackendError::SeatNotFound`].
+pub fn inject_event(
+    conn: &WaylandConnection,
+    seat: Option<&str>,
+    event: &InputEvent,
+) -> BackendResult<()> {
+    conn.check_seat(seat)?;
+    match *event {
         InputEvent::KeyboardKeycode { keycode, state } => {
-            inject_keyboard_keycode(conn, keycode, state).await
-        },
-        InputEvent::KeyboardKeysym { keysym, state } => {
-            inject_keyboard_keysym(conn, keysym, state).await
+            inject_keyboard_keycode(conn, seat, keycode, state)
         },
-        InputEvent::PointerMotion { dx, dy } => inject_pointer_motion(conn, dx, dy).await,
+        InputEvent::KeyboardKeysym { keysym, state } => inject_keyboard_keysym(conn, keysym, state),
+        InputEvent::PointerMotion { dx, dy } => inject_pointer_motion(conn, seat, dx, dy),
         InputEvent::PointerMotionAbsolute { stream, x, y } => {
-            inject_pointer_motion_absolute(conn, stream, x, y).await
+            inject_pointer_motion_absolute(conn, stream, x, y)
         },
         InputEvent::PointerButton { button, state } => {
-            inject_pointer_button(conn, button, state).await
+            inject_pointer_button(conn, seat, button, state)
         },
-        InputEvent::PointerAxis { dx, dy } => inject_pointer_axis(conn, dx, dy).await,
+        InputEvent::PointerAxis {
+            dx,
+            dy,
+            source,
+            unit,
+        } => inject_pointer_axis(conn, dx, dy, source, unit),
         InputEvent::PointerAxisDiscrete { axis, steps } => {
-            inject_pointer_axis_discrete(conn, axis, steps).await
+            inject_pointer_axis_discrete(conn, axis, steps)
         },
         InputEvent::TouchDown { stream, slot, x, y } => {
-            inject_touch_down(conn, stream, slot, x, y).await
+            inject_touch_down(conn, stream, slot, x, y);
+            Ok(())
         },
         InputEvent::TouchMotion { stream, slot, x, y } => {
-            inject_touch_motion(conn, stream, slot, x, y).await
+            inject_touch_motion(conn, stream, slot, x, y);
+            Ok(())
+        },
+        InputEvent::TouchUp { slot } => {
+            inject_touch_up(conn, slot);
+            Ok(())
         },
-        InputEvent::TouchUp { slot } => inject_touch_up(conn, slot).await,
         _ => {
             warn!("Unsupported input event type");
             Ok(())
@@ -46,8 +63,9 @@ pub async fn inject_event(conn: &WaylandConnection, event: InputEvent) -> Backen
     }
 }
 
-async fn inject_keyboard_keycode(
+fn inject_keyboard_keycode(
     conn: &WaylandConnection,
+    seat: Option<&str>,
     keycode: i32,
     state: KeyState,
 ) -> BackendResult<()> {
@@ -59,21 +77,26 @@ async fn inject_keyboard_keycode(
 
     debug!(
         "Injecting keyboard keycode: {}, state: {:?}",
-        keycode, state
+        Sensitive(keycode),
+        state
     );
 
-    // In a full implementation, this would use:
-    // zwp_virtual_keyboard_v1.key(time, keycode, state)
-    // For now, log the event
-    info!(
-        "Would inject keyboard keycode {} (state: {:?}) via zwp_virtual_keyboard_v1",
-        keycode, state
-    );
+    let keycode = u32::try_from(keycode).map_err(|_| {
+        BackendError::InputInjectionFailed(format!("Invalid keycode {}", Sensitive(keycode)))
+    })?;
+    let state = match state {
+        KeyState::Pressed => wl_keyboard::KeyState::Pressed,
+        KeyState::Released => wl_keyboard::KeyState::Released,
+    };
+    let keyboard = conn.virtual_keyboard(seat)?;
+    keyboard.key(conn.timestamp(), keycode, state.into());
+
+    conn.queue_request();
 
     Ok(())
 }
 
-async fn inject_keyboard_keysym(
+fn inject_keyboard_keysym(
     conn: &WaylandConnection,
     keysym: i32,
     state: KeyState,
@@ -84,19 +107,31 @@ async fn inject_keyboard_keysym(
         ));
     }
 
-    debug!("Injecting keyboard keysym: {}, state: {:?}", keysym, state);
+    debug!(
+        "Injecting keyboard keysym: {}, state: {:?}",
+        Sensitive(keysym),
+        state
+    );
 
     // Convert keysym to keycode and inject
     // Full implementation would use xkbcommon to map keysym -> keycode
     info!(
         "Would inject keyboard keysym {} (state: {:?})",
-        keysym, state
+        Sensitive(keysym),
+        state
     );
 
+    conn.queue_request();
+
     Ok(())
 }
 
-async fn inject_pointer_motion(conn: &WaylandConnection, dx: f64, dy: f64) -> BackendResult<()> {
+fn inject_pointer_motion(
+    conn: &WaylandConnection,
+    seat: Option<&str>,
+    dx: f64,
+    dy: f64,
+) -> BackendResult<()> {
     if !conn.has_virtual_pointer() {
         return Err(BackendError::InputInjectionFailed(
             "Virtual pointer protocol not available".to_string(),
@@ -105,13 +140,16 @@ async fn inject_pointer_motion(conn: &WaylandConnection, dx: f64, dy: f64) -> Ba
 
     debug!("Injecting pointer motion: dx={}, dy={}", dx, dy);
 
-    // Full implementation: zwlr_virtual_pointer_v1.motion(time, dx, dy)
-    info!("Would inject pointer motion dx={}, dy={}", dx, dy);
+    let pointer = conn.virtual_pointer(seat)?;
+    pointer.motion(conn.timestamp(), dx, dy);
+    pointer.frame();
+
+    conn.queue_request();
 
     Ok(())
 }
 
-async fn inject_pointer_motion_absolute(
+fn inject_pointer_motion_absolute(
     conn: &WaylandConnection,
     _stream: u32,
     x: f64,
@@ -128,11 +166,14 @@ async fn inject_pointer_motion_absolute(
     // Full implementation: zwlr_virtual_pointer_v1.motion_absolute(time, x, y, ...)
     info!("Would inject absolute pointer motion x={}, y={}", x, y);
 
+    conn.queue_request();
+
     Ok(())
 }
 
-async fn inject_pointer_button(
+fn inject_pointer_button(
     conn: &WaylandConnection,
+    seat: Option<&str>,
     button: i32,
     state: ButtonState,
 ) -> BackendResult<()> {
@@ -144,31 +185,55 @@ async fn inject_pointer_button(
 
     debug!("Injecting pointer button: {}, state: {:?}", button, state);
 
-    // Full implementation: zwlr_virtual_pointer_v1.button(time, button, state)
-    info!(
-        "Would inject pointer button {} (state: {:?})",
-        button, state
-    );
+    let button = u32::try_from(button).map_err(|_| {
+        BackendError::InputInjectionFailed(format!("Invalid pointer button {button}"))
+    })?;
+    let state = match state {
+        ButtonState::Pressed => wl_pointer::ButtonState::Pressed,
+        ButtonState::Released => wl_pointer::ButtonState::Released,
+    };
+    let pointer = conn.virtual_pointer(seat)?;
+    pointer.button(conn.timestamp(), button, state);
+    pointer.frame();
+
+    conn.queue_request();
 
     Ok(())
 }
 
-async fn inject_pointer_axis(conn: &WaylandConnection, dx: f64, dy: f64) -> BackendResult<()> {
+fn inject_pointer_axis(
+    conn: &WaylandConnection,
+    dx: f64,
+    dy: f64,
+    source: AxisSource,
+    unit: ScrollUnit,
+) -> BackendResult<()> {
     if !conn.has_virtual_pointer() {
         return Err(BackendError::InputInjectionFailed(
             "Virtual pointer protocol not available".to_string(),
         ));
     }
 
-    debug!("Injecting pointer axis: dx={}, dy={}", dx, dy);
+    debug!(
+        "Injecting pointer axis: dx={}, dy={}, source={:?}, unit={:?}",
+        dx, dy, source, unit
+    );
+
+    // Full implementation:
+    // zwlr_virtual_pointer_v1.axis_source(source as u32)
+    // zwlr_virtual_pointer_v1.axis(time, axis, value) (lines scaled to pixels)
+    // zwlr_virtual_pointer_v1.frame()
+    info!(
+        "Would inject pointer axis dx={}, dy={} ({:?}, {:?})",
+        dx, dy, source, unit
+    );
 
-    // Full implementation: zwlr_virtual_pointer_v1.axis(time, axis, value)
-    info!("Would inject pointer axis dx={}, dy={}", dx, dy);
+    conn.queue_request();
 
     Ok(())
 }
 
-async fn inject_pointer_axis_discrete(
+fn inject_pointer_axis_discrete(
     conn: &WaylandConnection,
     axis: ion_core::event::Axis,
     steps: i32,
@@ -190,16 +255,12 @@ async fn inject_pointer_axis_discrete(
         axis, steps
     );
 
+    conn.queue_request();
+
     Ok(())
 }
 
-async fn inject_touch_down(
-    _conn: &WaylandConnection,
-    stream: u32,
-    slot: u32,
-    x: f64,
-    y: f64,
-) -> BackendResult<()> {
+fn inject_touch_down(_conn: &WaylandConnection, stream: u32, slot: u32, x: f64, y: f64) {
     debug!(
         "Injecting touch down: stream={}, slot={}, x={}, y={}",
         stream, slot, x, y
@@ -207,31 +268,19 @@ async fn inject_touch_down(
 
     // Touch events would use a touch protocol if available
     info!("Touch events not yet implemented for generic Wayland");
-
-    Ok(())
 }
 
-async fn inject_touch_motion(
-    _conn: &WaylandConnection,
-    stream: u32,
-    slot: u32,
-    x: f64,
-    y: f64,
-) -> BackendResult<()> {
+fn inject_touch_motion(_conn: &WaylandConnection, stream: u32, slot: u32, x: f64, y: f64) {
     debug!(
         "Injecting touch motion: stream={}, slot={}, x={}, y={}",
         stream, slot, x, y
     );
 
     info!("Touch events not yet implemented for generic Wayland");
-
-    Ok(())
 }
 
-async fn inject_touch_up(_conn: &WaylandConnection, slot: u32) -> BackendResult<()> {
+fn inject_touch_up(_conn: &WaylandConnection, slot: u32) {
     debug!("Injecting touch up: slot={}", slot);
 
     info!("Touch events not yet implemented for generic Wayland");
-
-    Ok(())
 }
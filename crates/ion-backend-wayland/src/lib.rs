@@ -11,6 +11,7 @@
 //!
 //! - **wlr-virtual-pointer** - Pointer input injection (wlroots compositors)
 //! - **virtual-keyboard** - Keyboard input injection
+//! - **pointer-constraints** - Pointer lock for relative-only sessions
 //! - **wlr-screencopy** - Screen capture (wlroots)
 //! - **xdg-output** - Output information
 //!
@@ -41,31 +42,41 @@
 
 mod capture;
 mod connection;
+#[cfg(test)]
+mod fake_compositor;
 mod input;
 mod protocols;
 
 pub mod provider;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, PoisonError};
 
 use async_trait::async_trait;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, Notify, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{debug, info, instrument, warn};
 
 use ion_core::backend::{
-    BackendCapabilities, BackendError, BackendResult, CaptureStream, CompositorBackend,
-    DisplayServerType,
+    BackendCapabilities, BackendError, BackendResult, CapabilityNotifier, CaptureStream,
+    CompositorBackend, DisplayServerType,
 };
 use ion_core::event::InputEvent;
 use ion_core::session::SessionId;
 
-use crate::connection::WaylandConnection;
+use crate::connection::{Flushed, WaylandConnection};
+
+pub use crate::connection::{ConnectionEvent, ReconnectPolicy};
+pub use crate::protocols::registry::{Protocol, WaylandProtocols};
 
 /// Generic Wayland compositor backend.
 ///
 /// This backend uses standard Wayland protocols to work with any
 /// Wayland compositor. It's capability-based - it probes what
 /// protocols the compositor supports and adjusts accordingly.
+///
+/// Per-output capture is not implemented: screencopy frames aren't read
+/// back yet, so [`CompositorBackend::supports_output_capture`] is `false`
+/// and the portal starts no per-output streams on this backend.
 #[derive(Debug)]
 pub struct WaylandBackend {
     /// Wayland connection
@@ -74,6 +85,12 @@ pub struct WaylandBackend {
     connected: Arc<RwLock<bool>>,
     /// Discovered capabilities
     capabilities: Arc<RwLock<BackendCapabilities>>,
+    /// Wakes the flush task once requests have been queued
+    flush_notify: Arc<Notify>,
+    /// The running flush task, replaced on every connect
+    flush_task: Mutex<Option<JoinHandle<()>>>,
+    /// Sends capability snapshots when probing finds a change
+    capability_notifier: CapabilityNotifier,
 }
 
 impl WaylandBackend {
@@ -90,9 +107,34 @@ impl WaylandBackend {
                 display_server_type: DisplayServerType::Wayland,
                 backend_name: "Generic Wayland".to_string(),
             })),
+            flush_notify: Arc::new(Notify::new()),
+            flush_task: Mutex::new(None),
+            capability_notifier: CapabilityNotifier::default(),
         }
     }
 
+    /// Subscribe to compositor disconnect/reconnect notifications.
+    ///
+    /// Returns `None` until [`CompositorBackend::connect`] has succeeded.
+    pub async fn connection_events(&self) -> Option<broadcast::Receiver<ConnectionEvent>> {
+        self.connection
+            .read()
+            .await
+            .as_ref()
+            .map(WaylandConnection::subscribe)
+    }
+
+    /// Protocols the compositor offers, with their versions.
+    ///
+    /// Returns `None` until [`CompositorBackend::connect`] has succeeded.
+    pub async fn protocols(&self) -> Option<WaylandProtocols> {
+        self.connection
+            .read()
+            .await
+            .as_ref()
+            .map(|conn| conn.protocols().clone())
+    }
+
     /// Check if Wayland is available.
     fn is_wayland_available() -> bool {
         std::env::var("WAYLAND_DISPLAY").is_ok()
@@ -108,23 +150,123 @@ impl WaylandBackend {
             .as_ref()
             .ok_or_else(|| BackendError::ConnectionFailed("Not connected".to_string()))?;
 
-        // Probe available protocols
-        let has_virtual_pointer = conn.has_virtual_pointer();
-        let has_virtual_keyboard = conn.has_virtual_keyboard();
-        let has_screencopy = conn.has_screencopy();
+        Ok(Self::capabilities_of(conn))
+    }
 
-        debug!(
-            "Probed capabilities: pointer={}, keyboard={}, screencopy={}",
-            has_virtual_pointer, has_virtual_keyboard, has_screencopy
-        );
+    /// Map a connection's protocol probes to backend capabilities.
+    fn capabilities_of(conn: &WaylandConnection) -> BackendCapabilities {
+        let protocols = conn.protocols();
+        debug!("Probed protocols: {}", protocols);
 
-        Ok(BackendCapabilities {
-            can_inject_keyboard: has_virtual_keyboard,
-            can_inject_pointer: has_virtual_pointer,
-            can_capture_screen: has_screencopy,
-            display_server_type: DisplayServerType::Wayland,
-            backend_name: format!("Wayland ({})", conn.compositor_name()),
-        })
+        protocols.capabilities(format!("Wayland ({})", conn.compositor_name()))
+    }
+
+    /// Adopt an open connection: probe it and start the flush task.
+    async fn attach(&self, conn: WaylandConnection) -> BackendResult<()> {
+        *self.connection.write().await = Some(conn);
+        *self.connected.write().await = true;
+
+        // Probe and store capabilities
+        let caps = self.probe_capabilities().await?;
+        info!("✓ Discovered capabilities:");
+        info!("  - Keyboard injection: {}", caps.can_inject_keyboard);
+        info!("  - Pointer injection: {}", caps.can_inject_pointer);
+        info!("  - Screen capture: {}", caps.can_capture_screen);
+
+        *self.capabilities.write().await = caps.clone();
+        self.capability_notifier.notify(caps);
+
+        self.spawn_flush_task();
+
+        Ok(())
+    }
+
+    /// Spawn the task that flushes queued requests once per wakeup.
+    ///
+    /// Injections only queue requests and notify; however many arrive
+    /// before the task runs are written in a single batch. Reconnects
+    /// performed during a flush refresh the cached capabilities, and
+    /// subscribers are told when the compositor came back with different
+    /// globals.
+    ///
+    /// The connection lock is released while a reconnect backs off, so
+    /// injections and queries fail fast instead of queueing behind it.
+    /// Any task left from an earlier connect is aborted.
+    fn spawn_flush_task(&self) {
+        let connection = Arc::clone(&self.connection);
+        let connected = Arc::clone(&self.connected);
+        let capabilities = Arc::clone(&self.capabilities);
+        let notify = Arc::clone(&self.flush_notify);
+        let notifier = self.capability_notifier.clone();
+
+        let task = tokio::spawn(async move {
+            loop {
+                notify.notified().await;
+
+                let mut conn_guard = connection.write().await;
+                let Some(conn) = conn_guard.as_mut() else {
+                    break;
+                };
+                let flushed = conn.flush_pending().unwrap_or_else(|e| {
+                    warn!("Wayland flush failed: {:#}", e);
+                    Flushed::Sent(0)
+                });
+
+                if flushed == Flushed::Disconnected {
+                    let reconnect = conn.reconnector();
+                    drop(conn_guard);
+                    *connected.write().await = false;
+
+                    let reopened = reconnect.open().await;
+
+                    conn_guard = connection.write().await;
+                    let Some(conn) = conn_guard.as_mut() else {
+                        break;
+                    };
+                    if let Err(e) = conn.finish_reconnect(reopened) {
+                        warn!("Wayland flush failed: {:#}", e);
+                    }
+                }
+                let Some(conn) = conn_guard.as_ref() else {
+                    break;
+                };
+
+                let is_connected = conn.is_connected();
+                let probed = Self::capabilities_of(conn);
+                drop(conn_guard);
+
+                let mut cached = capabilities.write().await;
+                if *cached != probed {
+                    *cached = probed.clone();
+                    notifier.notify(probed);
+                }
+                drop(cached);
+
+                *connected.write().await = is_connected;
+            }
+        });
+
+        let previous = self
+            .flush_task
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .replace(task);
+        if let Some(previous) = previous {
+            previous.abort();
+        }
+    }
+}
+
+impl Drop for WaylandBackend {
+    fn drop(&mut self) {
+        if let Some(task) = self
+            .flush_task
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            task.abort();
+        }
     }
 }
 
@@ -176,24 +318,20 @@ impl CompositorBackend for WaylandBackend {
             conn.compositor_name()
         );
 
-        // Store connection
-        *self.connection.write().await = Some(conn);
-        *self.connected.write().await = true;
-
-        // Probe and store capabilities
-        let caps = self.probe_capabilities().await?;
-        info!("✓ Discovered capabilities:");
-        info!("  - Keyboard injection: {}", caps.can_inject_keyboard);
-        info!("  - Pointer injection: {}", caps.can_inject_pointer);
-        info!("  - Screen capture: {}", caps.can_capture_screen);
-
-        *self.capabilities.write().await = caps;
-
-        Ok(())
+        self.attach(conn).await
     }
 
     #[instrument(skip(self, event))]
     async fn inject_input(&self, event: InputEvent) -> BackendResult<()> {
+        self.inject_input_to_seat(None, event).await
+    }
+
+    #[instrument(skip(self, event))]
+    async fn inject_input_to_seat(
+        &self,
+        seat: Option<&str>,
+        event: InputEvent,
+    ) -> BackendResult<()> {
         // Check if connected
         if !*self.connected.read().await {
             return Err(BackendError::ConnectionFailed(
@@ -207,12 +345,43 @@ impl CompositorBackend for WaylandBackend {
             .as_ref()
             .ok_or_else(|| BackendError::ConnectionFailed("No connection available".to_string()))?;
 
-        // Inject event
-        input::inject_event(conn, event).await?;
+        // Inject event; the flush task sends it with the current batch
+        input::inject_event(conn, seat, &event)?;
+        self.flush_notify.notify_one();
 
         Ok(())
     }
 
+    /// Locks the seat's pointer with `zwp_pointer_constraints_v1`.
+    ///
+    /// The compositor enforces the lock while the backend's surface has
+    /// pointer focus.
+    #[instrument(skip(self))]
+    async fn set_pointer_lock(
+        &self,
+        session: &SessionId,
+        seat: Option<&str>,
+        locked: bool,
+    ) -> BackendResult<()> {
+        let conn_guard = self.connection.read().await;
+        let conn = conn_guard
+            .as_ref()
+            .ok_or_else(|| BackendError::ConnectionFailed("No connection available".to_string()))?;
+
+        conn.set_pointer_lock(session, seat, locked)?;
+        self.flush_notify.notify_one();
+
+        Ok(())
+    }
+
+    async fn enumerate_seats(&self) -> BackendResult<Vec<String>> {
+        let conn_guard = self.connection.read().await;
+        let conn = conn_guard
+            .as_ref()
+            .ok_or_else(|| BackendError::ConnectionFailed("Not connected".to_string()))?;
+        Ok(conn.seats().to_vec())
+    }
+
     #[instrument(skip(self, session))]
     async fn start_capture(&self, session: &SessionId) -> BackendResult<CaptureStream> {
         // Check if connected
@@ -238,11 +407,186 @@ impl CompositorBackend for WaylandBackend {
         // Return cached capabilities (updated during connect)
         self.capabilities.blocking_read().clone()
     }
+
+    fn capability_updates(&self) -> broadcast::Receiver<BackendCapabilities> {
+        self.capability_notifier.subscribe()
+    }
+
+    /// Stops the flush task and closes the Wayland connection.
+    async fn disconnect(&self) {
+        let task = self
+            .flush_task
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(task) = task {
+            task.abort();
+        }
+        *self.connection.write().await = None;
+        *self.connected.write().await = false;
+        info!("Disconnected from Wayland compositor");
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::fake_compositor::{serve, socket_path, u32_at, ALL_GLOBALS};
+
+    const RECONNECT_POLICY: ReconnectPolicy = ReconnectPolicy {
+        max_attempts: 2,
+        backoff: Duration::from_millis(1),
+    };
+
+    /// Backend attached to the fake compositor at `path`, flush task running.
+    async fn attached(path: &std::path::Path) -> WaylandBackend {
+        let conn = WaylandConnection::connect_to(path.to_path_buf(), RECONNECT_POLICY)
+            .await
+            .unwrap();
+        let backend = WaylandBackend::new();
+        backend.attach(conn).await.unwrap();
+        backend
+    }
+
+    async fn next_event(events: &mut broadcast::Receiver<ConnectionEvent>) -> ConnectionEvent {
+        tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .expect("connection event in time")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_flush_task_batches_injections() {
+        let path = socket_path("backend-batch");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        // Answers the connect's roundtrip, then the first flushed batch
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            serve(&mut stream, ALL_GLOBALS, Some(2))
+        });
+
+        let backend = attached(&path).await;
+        for dx in [1.0, 2.0, 3.0] {
+            backend
+                .inject_input(InputEvent::pointer_motion(dx, 0.0))
+                .await
+                .unwrap();
+        }
+        let seen = tokio::task::spawn_blocking(move || server.join().unwrap())
+            .await
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        // All three motions, each with its frame, went out in one batch
+        let manager = seen.object("zwlr_virtual_pointer_manager_v1", 0);
+        let created = seen.requests_on(manager);
+        assert_eq!(created.len(), 1);
+        assert_eq!(seen.requests_on(u32_at(created[0].1, 4)).len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_flush_task_reconnects_and_reports_capabilities() {
+        let path = socket_path("backend-reconnect");
+        let listener = UnixListener::bind(&path).unwrap();
+        let (drop_tx, drop_rx) = mpsc::channel();
+        let (dropped_tx, dropped_rx) = mpsc::channel();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            serve(&mut stream, ALL_GLOBALS, Some(1));
+            drop_rx.recv().unwrap();
+            drop(stream);
+            dropped_tx.send(()).unwrap();
+            // The compositor comes back without screencopy
+            let (mut stream, _) = listener.accept().unwrap();
+            serve(&mut stream, &ALL_GLOBALS[..2], None);
+        });
+
+        let backend = attached(&path).await;
+        let mut events = backend.connection_events().await.unwrap();
+        let mut updates = backend.capability_updates();
+        assert!(backend.capabilities.read().await.can_capture_screen);
+
+        drop_tx.send(()).unwrap();
+        dropped_rx.recv().unwrap();
+        backend
+            .inject_input(InputEvent::pointer_motion(1.0, 0.0))
+            .await
+            .unwrap();
+
+        assert_eq!(next_event(&mut events).await, ConnectionEvent::Disconnected);
+        assert_eq!(
+            next_event(&mut events).await,
+            ConnectionEvent::Reconnected { attempts: 1 }
+        );
+        let update = tokio::time::timeout(Duration::from_secs(5), updates.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!update.can_capture_screen);
+        assert!(update.can_inject_pointer);
+        assert!(*backend.connected.read().await);
+
+        // Dropping the backend stops the flush task and hangs up
+        drop(backend);
+        tokio::task::spawn_blocking(move || server.join().unwrap())
+            .await
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_flush_task_marks_backend_disconnected_after_giving_up() {
+        let path = socket_path("backend-give-up");
+        let listener = UnixListener::bind(&path).unwrap();
+        let server_path = path.clone();
+        let (drop_tx, drop_rx) = mpsc::channel();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            serve(&mut stream, ALL_GLOBALS, Some(1));
+            // Compositor exits entirely: no socket to come back to
+            drop_rx.recv().unwrap();
+            std::fs::remove_file(&server_path).unwrap();
+        });
+
+        let backend = attached(&path).await;
+        let mut events = backend.connection_events().await.unwrap();
+        drop_tx.send(()).unwrap();
+        tokio::task::spawn_blocking(move || server.join().unwrap())
+            .await
+            .unwrap();
+
+        backend
+            .inject_input(InputEvent::pointer_motion(1.0, 0.0))
+            .await
+            .unwrap();
+
+        assert_eq!(next_event(&mut events).await, ConnectionEvent::Disconnected);
+        assert_eq!(
+            next_event(&mut events).await,
+            ConnectionEvent::ReconnectFailed { attempts: 2 }
+        );
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while *backend.connected.read().await {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("backend marked disconnected");
+        assert!(matches!(
+            backend
+                .inject_input(InputEvent::pointer_motion(1.0, 0.0))
+                .await,
+            Err(BackendError::ConnectionFailed(_))
+        ));
+    }
 
     #[test]
     fn test_wayland_backend_creation() {
@@ -3,6 +3,7 @@
 
 //! Wayland protocol implementations for input injection and screen capture.
 
+pub mod registry;
 pub mod virtual_keyboard;
 pub mod virtual_pointer;
 // pub mod screencopy; // Next
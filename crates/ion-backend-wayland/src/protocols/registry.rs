@@ -0,0 +1,326 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Protocol globals probed from the Wayland registry.
+//!
+//! [`WaylandProtocols`] records every protocol we know about that the
+//! compositor advertises, together with its version, so features can be
+//! gated on the version that introduced them rather than on presence alone.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ion_core::backend::{BackendCapabilities, DisplayServerType};
+
+/// A Wayland protocol global the backend knows how to use or report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Protocol {
+    /// `wl_seat` (v8 adds high-resolution `axis_value120` scrolling)
+    Seat,
+    /// `wl_output`
+    Output,
+    /// `zxdg_output_manager_v1` - logical output geometry
+    XdgOutput,
+    /// `zwlr_virtual_pointer_manager_v1` - pointer injection
+    VirtualPointer,
+    /// `zwp_virtual_keyboard_manager_v1` - keyboard injection
+    VirtualKeyboard,
+    /// `zwlr_screencopy_manager_v1` - screen capture (v3 adds damage)
+    Screencopy,
+    /// `ext_image_copy_capture_manager_v1` - screen and region capture
+    ImageCopyCapture,
+    /// `zwp_linux_dmabuf_v1` - dmabuf buffer sharing
+    LinuxDmabuf,
+    /// `ext_foreign_toplevel_list_v1` - window enumeration
+    ForeignToplevel,
+    /// `wp_color_manager_v1` - output color spaces and HDR
+    ColorManagement,
+    /// `zwp_pointer_constraints_v1` - pointer lock
+    PointerConstraints,
+}
+
+impl Protocol {
+    /// Every known protocol.
+    pub const ALL: [Self; 11] = [
+        Self::Seat,
+        Self::Output,
+        Self::XdgOutput,
+        Self::VirtualPointer,
+        Self::VirtualKeyboard,
+        Self::Screencopy,
+        Self::ImageCopyCapture,
+        Self::LinuxDmabuf,
+        Self::ForeignToplevel,
+        Self::ColorManagement,
+        Self::PointerConstraints,
+    ];
+
+    /// Interface name the global is advertised under.
+    #[must_use]
+    pub const fn interface(self) -> &'static str {
+        match self {
+            Self::Seat => "wl_seat",
+            Self::Output => "wl_output",
+            Self::XdgOutput => "zxdg_output_manager_v1",
+            Self::VirtualPointer => "zwlr_virtual_pointer_manager_v1",
+            Self::VirtualKeyboard => "zwp_virtual_keyboard_manager_v1",
+            Self::Screencopy => "zwlr_screencopy_manager_v1",
+            Self::ImageCopyCapture => "ext_image_copy_capture_manager_v1",
+            Self::LinuxDmabuf => "zwp_linux_dmabuf_v1",
+            Self::ForeignToplevel => "ext_foreign_toplevel_list_v1",
+            Self::ColorManagement => "wp_color_manager_v1",
+            Self::PointerConstraints => "zwp_pointer_constraints_v1",
+        }
+    }
+
+    /// Look up a protocol by its interface name.
+    #[must_use]
+    pub fn from_interface(interface: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.interface() == interface)
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.interface())
+    }
+}
+
+/// Protocols available on a compositor, with their versions.
+///
+/// For globals the connection binds, the version is the one actually
+/// bound; for the rest it is the advertised version.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WaylandProtocols {
+    versions: BTreeMap<Protocol, u32>,
+}
+
+impl WaylandProtocols {
+    /// Minimum `zwlr_screencopy_manager_v1` version reporting damage.
+    pub const SCREENCOPY_DAMAGE_VERSION: u32 = 3;
+    /// Minimum `wl_seat` version delivering `axis_value120`.
+    pub const AXIS_VALUE120_VERSION: u32 = 8;
+
+    /// Build from `(interface, version)` pairs as listed by the registry.
+    ///
+    /// Unknown interfaces are ignored. A global advertised more than once
+    /// (e.g. several outputs) keeps its highest version.
+    pub fn from_globals<'a>(globals: impl IntoIterator<Item = (&'a str, u32)>) -> Self {
+        let mut protocols = Self::default();
+        for (interface, version) in globals {
+            if let Some(protocol) = Protocol::from_interface(interface) {
+                let entry = protocols.versions.entry(protocol).or_insert(version);
+                *entry = (*entry).max(version);
+            }
+        }
+        protocols
+    }
+
+    /// Record a protocol at `version`, or remove it with `None`.
+    pub fn set(&mut self, protocol: Protocol, version: Option<u32>) {
+        match version {
+            Some(version) => self.versions.insert(protocol, version),
+            None => self.versions.remove(&protocol),
+        };
+    }
+
+    /// Version of a protocol, if available.
+    #[must_use]
+    pub fn version(&self, protocol: Protocol) -> Option<u32> {
+        self.versions.get(&protocol).copied()
+    }
+
+    /// Whether a protocol is available at any version.
+    #[must_use]
+    pub fn has(&self, protocol: Protocol) -> bool {
+        self.versions.contains_key(&protocol)
+    }
+
+    /// Whether a protocol is available at `min_version` or newer.
+    #[must_use]
+    pub fn supports(&self, protocol: Protocol, min_version: u32) -> bool {
+        self.version(protocol).is_some_and(|v| v >= min_version)
+    }
+
+    /// Available protocols and versions, in [`Protocol`] order.
+    pub fn iter(&self) -> impl Iterator<Item = (Protocol, u32)> + '_ {
+        self.versions.iter().map(|(p, v)| (*p, *v))
+    }
+
+    /// Whether pointer events can be injected.
+    #[must_use]
+    pub fn can_inject_pointer(&self) -> bool {
+        self.has(Protocol::VirtualPointer)
+    }
+
+    /// Whether keyboard events can be injected.
+    #[must_use]
+    pub fn can_inject_keyboard(&self) -> bool {
+        self.has(Protocol::VirtualKeyboard)
+    }
+
+    /// Whether the screen can be captured.
+    ///
+    /// Capture only drives screencopy, so an advertised
+    /// [`Protocol::ImageCopyCapture`] alone does not count.
+    #[must_use]
+    pub fn can_capture_screen(&self) -> bool {
+        self.has(Protocol::Screencopy)
+    }
+
+    /// Whether capture can report damaged regions.
+    #[must_use]
+    pub fn has_capture_damage(&self) -> bool {
+        self.supports(Protocol::Screencopy, Self::SCREENCOPY_DAMAGE_VERSION)
+    }
+
+    /// Whether outputs can report their color space and HDR state.
+    ///
+    /// Without it every output is assumed to be sRGB and SDR.
+    #[must_use]
+    pub fn has_color_management(&self) -> bool {
+        self.has(Protocol::ColorManagement)
+    }
+
+    /// Whether the pointer can be locked for a session.
+    #[must_use]
+    pub fn can_lock_pointer(&self) -> bool {
+        self.has(Protocol::PointerConstraints)
+    }
+
+    /// Whether high-resolution (`axis_value120`) scrolling is available.
+    #[must_use]
+    pub fn has_axis_value120(&self) -> bool {
+        self.supports(Protocol::Seat, Self::AXIS_VALUE120_VERSION)
+    }
+
+    /// Backend capabilities these protocols provide.
+    #[must_use]
+    pub fn capabilities(&self, backend_name: String) -> BackendCapabilities {
+        BackendCapabilities {
+            can_inject_keyboard: self.can_inject_keyboard(),
+            can_inject_pointer: self.can_inject_pointer(),
+            can_capture_screen: self.can_capture_screen(),
+            display_server_type: DisplayServerType::Wayland,
+            backend_name,
+        }
+    }
+}
+
+impl fmt::Display for WaylandProtocols {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.versions.is_empty() {
+            return f.write_str("none");
+        }
+        for (i, (protocol, version)) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{protocol} v{version}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caps(globals: &[(&str, u32)]) -> BackendCapabilities {
+        WaylandProtocols::from_globals(globals.iter().copied()).capabilities("test".into())
+    }
+
+    #[test]
+    fn test_interface_round_trip() {
+        for protocol in Protocol::ALL {
+            assert_eq!(
+                Protocol::from_interface(protocol.interface()),
+                Some(protocol)
+            );
+        }
+        assert_eq!(Protocol::from_interface("wl_compositor"), None);
+    }
+
+    #[test]
+    fn test_empty_registry_has_no_capabilities() {
+        let caps = caps(&[("wl_compositor", 6), ("wl_shm", 1)]);
+        assert!(!caps.can_inject_pointer);
+        assert!(!caps.can_inject_keyboard);
+        assert!(!caps.can_capture_screen);
+        assert_eq!(caps.display_server_type, DisplayServerType::Wayland);
+    }
+
+    #[test]
+    fn test_each_protocol_maps_to_its_capability() {
+        let pointer = caps(&[("zwlr_virtual_pointer_manager_v1", 2)]);
+        assert!(pointer.can_inject_pointer);
+        assert!(!pointer.can_inject_keyboard && !pointer.can_capture_screen);
+
+        let keyboard = caps(&[("zwp_virtual_keyboard_manager_v1", 1)]);
+        assert!(keyboard.can_inject_keyboard);
+        assert!(!keyboard.can_inject_pointer && !keyboard.can_capture_screen);
+
+        let screencopy = caps(&[("zwlr_screencopy_manager_v1", 1)]);
+        assert!(screencopy.can_capture_screen);
+
+        // Advertised, but not a protocol capture drives
+        let image_copy = caps(&[("ext_image_copy_capture_manager_v1", 1)]);
+        assert!(!image_copy.can_capture_screen);
+        assert!(!image_copy.can_inject_input());
+    }
+
+    #[test]
+    fn test_version_gates() {
+        let old =
+            WaylandProtocols::from_globals([("wl_seat", 7), ("zwlr_screencopy_manager_v1", 2)]);
+        assert!(!old.has_axis_value120());
+        assert!(!old.has_capture_damage());
+        assert!(old.can_capture_screen());
+
+        let new =
+            WaylandProtocols::from_globals([("wl_seat", 9), ("zwlr_screencopy_manager_v1", 3)]);
+        assert!(new.has_axis_value120());
+        assert!(new.has_capture_damage());
+        assert!(new.supports(Protocol::Seat, 9));
+        assert!(!new.supports(Protocol::Seat, 10));
+    }
+
+    #[test]
+    fn test_color_management_probe() {
+        let sdr = WaylandProtocols::from_globals([("wl_output", 4)]);
+        assert!(!sdr.has_color_management());
+
+        let managed =
+            WaylandProtocols::from_globals([("wl_output", 4), ("wp_color_manager_v1", 1)]);
+        assert!(managed.has_color_management());
+    }
+
+    #[test]
+    fn test_pointer_constraints_probe() {
+        let unconstrained = WaylandProtocols::from_globals([("wl_seat", 8)]);
+        assert!(!unconstrained.can_lock_pointer());
+
+        let constrained =
+            WaylandProtocols::from_globals([("wl_seat", 8), ("zwp_pointer_constraints_v1", 1)]);
+        assert!(constrained.can_lock_pointer());
+    }
+
+    #[test]
+    fn test_repeated_globals_keep_highest_version() {
+        let mut protocols =
+            WaylandProtocols::from_globals([("wl_output", 3), ("wl_output", 4), ("wl_output", 2)]);
+        assert_eq!(protocols.version(Protocol::Output), Some(4));
+
+        protocols.set(Protocol::Output, None);
+        assert!(!protocols.has(Protocol::Output));
+        assert_eq!(protocols.to_string(), "none");
+
+        protocols.set(Protocol::Seat, Some(8));
+        protocols.set(Protocol::VirtualPointer, Some(2));
+        assert_eq!(
+            protocols.to_string(),
+            "wl_seat v8, zwlr_virtual_pointer_manager_v1 v2"
+        );
+    }
+}
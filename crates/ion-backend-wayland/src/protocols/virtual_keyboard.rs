@@ -1,79 +1,99 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 // Copyright © 2024-2025 DataScienceBioLab
 
-//! Virtual keyboard protocol implementation.
+//! Virtual keyboard protocol bindings.
 //!
-//! Implements `zwp_virtual_keyboard_v1` for keyboard input injection.
+//! `zwp_virtual_keyboard_v1` isn't shipped by the protocol crates we
+//! depend on, so the client bindings are generated here from
+//! `protocols/virtual-keyboard-unstable-v1.xml`.
 //!
-//! Note: This protocol is part of wayland-protocols but may not be available
-//! in all versions. For now, this is a placeholder structure showing how
-//! it would be implemented when the protocol is available.
+//! A virtual keyboard must be given a keymap before its first key; see
+//! [`send_keymap`].
 
-use anyhow::Result;
-use tracing::{debug, info};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Seek, Write};
+use std::os::fd::AsFd;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use ion_core::event::KeyState;
+use wayland_client::protocol::wl_keyboard::KeymapFormat;
 
-/// Virtual keyboard manager state (placeholder).
-///
-/// This will be properly implemented once we have access to the
-/// `zwp_virtual_keyboard_v1` protocol bindings.
-#[derive(Debug)]
-pub struct VirtualKeyboardManager {
-    // Will contain actual Wayland protocol objects
-    available: bool,
-}
+pub use self::generated::client::zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1;
+pub use self::generated::client::zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1;
 
-impl VirtualKeyboardManager {
-    /// Create a new virtual keyboard manager.
-    pub fn new() -> Self {
-        Self { available: false }
-    }
+mod generated {
+    #![allow(dead_code, non_camel_case_types, unused_unsafe, unused_variables)]
+    #![allow(non_upper_case_globals, non_snake_case, unused_imports)]
+    #![allow(missing_docs, clippy::all, clippy::pedantic)]
 
-    /// Check if virtual keyboard protocol is available.
-    pub fn is_available(&self) -> bool {
-        self.available
-    }
+    pub mod client {
+        use wayland_client;
+        use wayland_client::protocol::*;
 
-    /// Inject a key press or release (placeholder).
-    pub fn key(&self, keycode: u32, state: KeyState, time: u32) -> Result<()> {
-        debug!(
-            "Would inject key: keycode={}, state={:?}, time={}",
-            keycode, state, time
-        );
-        info!("Virtual keyboard protocol not yet bound - placeholder implementation");
-        Ok(())
-    }
+        pub mod __interfaces {
+            use wayland_client::protocol::__interfaces::*;
+            wayland_scanner::generate_interfaces!("protocols/virtual-keyboard-unstable-v1.xml");
+        }
+        use self::__interfaces::*;
 
-    /// Inject modifier state (placeholder).
-    pub fn modifiers(
-        &self,
-        mods_depressed: u32,
-        mods_latched: u32,
-        mods_locked: u32,
-        group: u32,
-    ) -> Result<()> {
-        debug!(
-            "Would set modifiers: depressed={}, latched={}, locked={}, group={}",
-            mods_depressed, mods_latched, mods_locked, group
-        );
-        Ok(())
+        wayland_scanner::generate_client_code!("protocols/virtual-keyboard-unstable-v1.xml");
     }
 }
 
-impl Default for VirtualKeyboardManager {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Keymap given to every virtual keyboard.
+///
+/// Injected keycodes are evdev codes, which the `evdev` keycodes section
+/// maps; the symbols give them a US layout.
+const KEYMAP: &str = "xkb_keymap {
+    xkb_keycodes { include \"evdev+aliases(qwerty)\" };
+    xkb_types { include \"complete\" };
+    xkb_compat { include \"complete\" };
+    xkb_symbols { include \"pc+us+inet(evdev)\" };
+};
+";
+
+/// Give `keyboard` the keymap it needs before any key is sent.
+pub fn send_keymap(keyboard: &ZwpVirtualKeyboardV1) -> io::Result<()> {
+    let file = keymap_file()?;
+    let size = u32::try_from(KEYMAP.len() + 1)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "keymap too large"))?;
+    keyboard.keymap(KeymapFormat::XkbV1.into(), file.as_fd(), size);
+    Ok(())
+}
+
+/// An unlinked file holding the NUL-terminated keymap.
+fn keymap_file() -> io::Result<File> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    let path = std::env::temp_dir().join(format!(
+        "ion-keymap-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    std::fs::remove_file(&path)?;
+
+    file.write_all(KEYMAP.as_bytes())?;
+    file.write_all(&[0])?;
+    file.rewind()?;
+    Ok(file)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Read;
 
     #[test]
-    fn test_virtual_keyboard_creation() {
-        let manager = VirtualKeyboardManager::new();
-        assert!(!manager.is_available());
+    fn test_keymap_file_is_nul_terminated() {
+        let mut contents = Vec::new();
+        keymap_file().unwrap().read_to_end(&mut contents).unwrap();
+
+        assert_eq!(contents.len(), KEYMAP.len() + 1);
+        assert_eq!(contents.last(), Some(&0));
+        assert!(contents.starts_with(b"xkb_keymap {"));
     }
 }
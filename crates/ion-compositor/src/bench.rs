@@ -0,0 +1,383 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Synthetic capture load generator.
+//!
+//! Drives a capture backend at a fixed resolution and frame rate for a
+//! fixed duration, pushing every frame through an encode stage, and
+//! reports throughput, capture latency, encode time and dropped frames.
+//! Several tier/encoder combinations can be run back to back so they can
+//! be compared side by side. The `ion-bench` binary is a thin CLI over
+//! [`run`].
+//!
+//! There are no video encoders yet, so the encode stage is the pixel
+//! format conversion every encoder path starts with. Allocation is
+//! reported as the bytes of frame buffers produced, which dominates the
+//! streaming loop's memory traffic.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::time::MissedTickBehavior;
+
+use crate::capture::{
+    CaptureError, CaptureFrame, CaptureResult, CaptureTier, CpuCapture, DmabufCapture, FrameFormat,
+    ScreenCapture, ShmCapture,
+};
+
+/// Work done on each captured frame after capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeStage {
+    /// Frames are passed through untouched.
+    Passthrough,
+    /// Frames are converted to the given pixel format.
+    Convert(FrameFormat),
+}
+
+impl EncodeStage {
+    /// Runs the stage on a frame, returning the bytes it allocated.
+    fn apply(self, frame: &CaptureFrame) -> CaptureResult<usize> {
+        match self {
+            Self::Passthrough => Ok(0),
+            Self::Convert(format) if format == frame.format() => Ok(0),
+            Self::Convert(format) => frame
+                .convert_to(format)
+                .map(|converted| converted.data().len())
+                .ok_or_else(|| {
+                    CaptureError::NotAvailable(format!(
+                        "conversion from {} to {format}",
+                        frame.format()
+                    ))
+                }),
+        }
+    }
+}
+
+impl fmt::Display for EncodeStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Passthrough => write!(f, "none"),
+            Self::Convert(format) => write!(f, "{}", format.to_string().to_lowercase()),
+        }
+    }
+}
+
+impl FromStr for EncodeStage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::Passthrough),
+            "bgra8888" => Ok(Self::Convert(FrameFormat::Bgra8888)),
+            "rgba8888" => Ok(Self::Convert(FrameFormat::Rgba8888)),
+            other => Err(format!(
+                "unknown encoder '{other}' (expected none, bgra8888 or rgba8888)"
+            )),
+        }
+    }
+}
+
+/// Capture tiers the load generator can drive.
+///
+/// `PipeWire` needs a live portal session and is not benchmarked here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchTier(pub CaptureTier);
+
+impl BenchTier {
+    fn capture(self, width: u32, height: u32) -> CaptureResult<Box<dyn ScreenCapture>> {
+        match self.0 {
+            CaptureTier::Dmabuf => Ok(Box::new(DmabufCapture::with_defaults(width, height))),
+            CaptureTier::Shm => Ok(Box::new(ShmCapture::with_defaults(width, height))),
+            CaptureTier::Cpu => Ok(Box::new(CpuCapture::with_defaults(width, height))),
+            tier => Err(CaptureError::NotAvailable(format!(
+                "{} cannot be benchmarked",
+                tier.name()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for BenchTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.0 {
+            CaptureTier::None => "none",
+            CaptureTier::Cpu => "cpu",
+            CaptureTier::Shm => "shm",
+            CaptureTier::Dmabuf => "dmabuf",
+            CaptureTier::PipeWire => "pipewire",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for BenchTier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "dmabuf" => Ok(Self(CaptureTier::Dmabuf)),
+            "shm" => Ok(Self(CaptureTier::Shm)),
+            "cpu" => Ok(Self(CaptureTier::Cpu)),
+            other => Err(format!(
+                "unknown tier '{other}' (expected dmabuf, shm or cpu)"
+            )),
+        }
+    }
+}
+
+/// What to benchmark.
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    /// Frame width in pixels.
+    pub width: u32,
+    /// Frame height in pixels.
+    pub height: u32,
+    /// Frame rate the loop tries to sustain.
+    pub target_fps: u32,
+    /// How long each run lasts.
+    pub duration: Duration,
+    /// Tiers to run.
+    pub tiers: Vec<BenchTier>,
+    /// Encode stages to run against each tier.
+    pub encoders: Vec<EncodeStage>,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self {
+            width: 1920,
+            height: 1080,
+            target_fps: 60,
+            duration: Duration::from_secs(10),
+            tiers: vec![BenchTier(CaptureTier::Shm)],
+            encoders: vec![EncodeStage::Passthrough],
+        }
+    }
+}
+
+impl BenchConfig {
+    /// A run short enough for tests and CI smoke checks.
+    #[must_use]
+    pub fn smoke() -> Self {
+        Self {
+            width: 320,
+            height: 240,
+            target_fps: 30,
+            duration: Duration::from_millis(200),
+            ..Self::default()
+        }
+    }
+}
+
+/// Distribution of a per-frame duration, in milliseconds.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LatencyStats {
+    /// Mean.
+    pub mean_ms: f64,
+    /// Median.
+    pub p50_ms: f64,
+    /// 99th percentile.
+    pub p99_ms: f64,
+    /// Maximum.
+    pub max_ms: f64,
+}
+
+impl LatencyStats {
+    fn from_samples(samples: &mut [Duration]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let percentile = |p: usize| samples[(samples.len() * p).div_ceil(100).max(1) - 1];
+        let total: Duration = samples.iter().sum();
+        #[allow(clippy::cast_precision_loss)]
+        let mean_ms = ms(total) / samples.len() as f64;
+
+        Self {
+            mean_ms,
+            p50_ms: ms(percentile(50)),
+            p99_ms: ms(percentile(99)),
+            max_ms: ms(samples[samples.len() - 1]),
+        }
+    }
+}
+
+/// Result of one tier/encoder run.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    /// Capture tier.
+    pub tier: String,
+    /// Encode stage.
+    pub encoder: String,
+    /// Frame width in pixels.
+    pub width: u32,
+    /// Frame height in pixels.
+    pub height: u32,
+    /// Target frame rate.
+    pub target_fps: u32,
+    /// Wall-clock length of the run.
+    pub elapsed_ms: f64,
+    /// Frames captured and encoded.
+    pub frames: u64,
+    /// Frames the target rate called for.
+    pub expected_frames: u64,
+    /// Frame slots missed because the loop fell behind.
+    pub dropped_frames: u64,
+    /// Captures or encodes that returned an error.
+    pub failed_frames: u64,
+    /// Achieved frame rate.
+    pub throughput_fps: f64,
+    /// Capture latency from [`FrameMetadata::capture_latency`](crate::capture::FrameMetadata::capture_latency).
+    pub capture_latency: LatencyStats,
+    /// Time spent in the encode stage.
+    pub encode_time: LatencyStats,
+    /// Bytes of frame buffers allocated by capture and encode.
+    pub allocated_bytes: u64,
+}
+
+/// Results of all runs in a benchmark.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    /// One entry per tier/encoder combination, in run order.
+    pub runs: Vec<RunReport>,
+}
+
+/// Runs every tier/encoder combination in `config`, one after another.
+///
+/// # Errors
+///
+/// Returns an error if a requested tier cannot be benchmarked.
+pub async fn run(config: &BenchConfig) -> CaptureResult<BenchReport> {
+    let mut runs = Vec::with_capacity(config.tiers.len() * config.encoders.len());
+    for &tier in &config.tiers {
+        for &encoder in &config.encoders {
+            runs.push(run_one(config, tier, encoder).await?);
+        }
+    }
+    Ok(BenchReport { runs })
+}
+
+/// Drives one capture backend for the configured duration.
+async fn run_one(
+    config: &BenchConfig,
+    tier: BenchTier,
+    encoder: EncodeStage,
+) -> CaptureResult<RunReport> {
+    let capture = tier.capture(config.width, config.height)?;
+    let target_fps = config.target_fps.max(1);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(1) / target_fps);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    let mut capture_latency = Vec::new();
+    let mut encode_time = Vec::new();
+    let mut failed_frames = 0u64;
+    let mut allocated_bytes = 0u64;
+
+    let start = Instant::now();
+    while start.elapsed() < config.duration {
+        interval.tick().await;
+
+        let frame = match capture.capture_frame().await {
+            Ok(frame) => frame,
+            Err(e) => {
+                tracing::debug!(error = %e, "Benchmark capture failed");
+                failed_frames += 1;
+                continue;
+            },
+        };
+
+        let encode_start = Instant::now();
+        match encoder.apply(&frame) {
+            Ok(bytes) => {
+                encode_time.push(encode_start.elapsed());
+                capture_latency.push(frame.metadata.capture_latency());
+                allocated_bytes += (frame.data().len() + bytes) as u64;
+            },
+            Err(e) => {
+                tracing::debug!(error = %e, "Benchmark encode failed");
+                failed_frames += 1;
+            },
+        }
+    }
+    let elapsed = start.elapsed();
+
+    let frames = capture_latency.len() as u64;
+    // Truncation is intended: only whole frame slots count
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let expected_frames = (elapsed.as_secs_f64() * f64::from(target_fps)) as u64;
+    #[allow(clippy::cast_precision_loss)]
+    let throughput_fps = frames as f64 / elapsed.as_secs_f64();
+
+    Ok(RunReport {
+        tier: tier.to_string(),
+        encoder: encoder.to_string(),
+        width: config.width,
+        height: config.height,
+        target_fps,
+        elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+        frames,
+        expected_frames,
+        dropped_frames: expected_frames.saturating_sub(frames + failed_frames),
+        failed_frames,
+        throughput_fps,
+        capture_latency: LatencyStats::from_samples(&mut capture_latency),
+        encode_time: LatencyStats::from_samples(&mut encode_time),
+        allocated_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn smoke_benchmark_compares_tiers_and_encoders() {
+        let config = BenchConfig {
+            tiers: vec!["shm".parse().unwrap(), "cpu".parse().unwrap()],
+            encoders: vec![EncodeStage::Passthrough, "rgba8888".parse().unwrap()],
+            ..BenchConfig::smoke()
+        };
+        let report = run(&config).await.unwrap();
+
+        assert_eq!(report.runs.len(), 4);
+        for run in &report.runs {
+            assert!(
+                run.frames > 0,
+                "{} / {} produced no frames",
+                run.tier,
+                run.encoder
+            );
+            assert_eq!(run.failed_frames, 0);
+            assert!(run.capture_latency.p99_ms >= run.capture_latency.p50_ms);
+            assert!(run.allocated_bytes >= run.frames * 320 * 240 * 4);
+        }
+        assert_eq!(report.runs[1].encoder, "rgba8888");
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert!(json["runs"][0]["capture_latency"]["p99_ms"].is_number());
+    }
+
+    #[test]
+    fn latency_percentiles() {
+        let mut samples: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        let stats = LatencyStats::from_samples(&mut samples);
+
+        assert!((stats.p50_ms - 50.0).abs() < 1e-9);
+        assert!((stats.p99_ms - 99.0).abs() < 1e-9);
+        assert!((stats.max_ms - 100.0).abs() < 1e-9);
+        assert_eq!(LatencyStats::from_samples(&mut []), LatencyStats::default());
+    }
+
+    #[test]
+    fn parse_names() {
+        assert_eq!("SHM".parse::<BenchTier>(), Ok(BenchTier(CaptureTier::Shm)));
+        assert!("pipewire".parse::<BenchTier>().is_err());
+        assert_eq!("none".parse::<EncodeStage>(), Ok(EncodeStage::Passthrough));
+        assert!("h264".parse::<EncodeStage>().is_err());
+    }
+}
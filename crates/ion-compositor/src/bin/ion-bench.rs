@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Synthetic capture/encode load generator.
+//!
+//! Runs each requested tier/encoder combination under sustained load and
+//! prints a comparison table, or JSON for CI perf gating.
+//!
+//! ```bash
+//! cargo run --bin ion-bench -- --tier shm,cpu --encoder none,rgba8888 --fps 60 --duration 10
+//! cargo run --bin ion-bench -- --smoke --json
+//! ```
+
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::Parser;
+use ion_compositor::bench::{self, BenchConfig, BenchReport, BenchTier, EncodeStage};
+
+#[derive(Parser, Debug)]
+#[command(name = "ion-bench")]
+#[command(about = "Benchmark the capture pipeline under synthetic load")]
+struct Args {
+    /// Frame width in pixels
+    #[arg(long, default_value = "1920")]
+    width: u32,
+
+    /// Frame height in pixels
+    #[arg(long, default_value = "1080")]
+    height: u32,
+
+    /// Target frame rate
+    #[arg(long, default_value = "60")]
+    fps: u32,
+
+    /// Seconds per tier/encoder run
+    #[arg(long, default_value = "10")]
+    duration: f64,
+
+    /// Capture tiers to compare (dmabuf, shm, cpu)
+    #[arg(long, value_delimiter = ',', default_value = "shm")]
+    tier: Vec<BenchTier>,
+
+    /// Encode stages to compare (none, bgra8888, rgba8888)
+    #[arg(long, value_delimiter = ',', default_value = "none")]
+    encoder: Vec<EncodeStage>,
+
+    /// Short low-resolution run, for smoke tests (overrides size, fps and duration)
+    #[arg(long)]
+    smoke: bool,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+impl Args {
+    fn config(&self) -> BenchConfig {
+        let base = if self.smoke {
+            BenchConfig::smoke()
+        } else {
+            BenchConfig {
+                width: self.width,
+                height: self.height,
+                target_fps: self.fps,
+                duration: Duration::from_secs_f64(self.duration.max(0.0)),
+                ..BenchConfig::default()
+            }
+        };
+        BenchConfig {
+            tiers: self.tier.clone(),
+            encoders: self.encoder.clone(),
+            ..base
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter("warn")
+        .with_target(false)
+        .with_writer(std::io::stderr)
+        .init();
+
+    let args = Args::parse();
+    let report = match bench::run(&args.config()).await {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("ion-bench: {e}");
+            return ExitCode::FAILURE;
+        },
+    };
+
+    if args.json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{json}"),
+            Err(e) => {
+                eprintln!("ion-bench: {e}");
+                return ExitCode::FAILURE;
+            },
+        }
+    } else {
+        print_table(&report);
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_table(report: &BenchReport) {
+    println!(
+        "{:<8} {:<9} {:>11} {:>8} {:>7} {:>9} {:>9} {:>10} {:>10}",
+        "tier", "encoder", "size", "fps", "drop", "cap p50", "cap p99", "enc p99", "MiB"
+    );
+    for run in &report.runs {
+        #[allow(clippy::cast_precision_loss)]
+        let mib = run.allocated_bytes as f64 / (1024.0 * 1024.0);
+        println!(
+            "{:<8} {:<9} {:>11} {:>8.1} {:>7} {:>7.2}ms {:>7.2}ms {:>8.2}ms {:>10.1}",
+            run.tier,
+            run.encoder,
+            format!("{}x{}", run.width, run.height),
+            run.throughput_fps,
+            run.dropped_frames,
+            run.capture_latency.p50_ms,
+            run.capture_latency.p99_ms,
+            run.encode_time.p99_ms,
+            mib,
+        );
+    }
+}
@@ -5,24 +5,114 @@
 //!
 //! This module bridges the capture tier system with the session mode system,
 //! providing a unified view of what a remote desktop session can do.
-
+//!
+//! The built-in probes cover screen capture and input injection.
+//! Integrators can register additional [`CapabilityProbe`]s for
+//! environment-specific checks (a GPU quirk list, a policy switch, ...).
+//! Every probe reports one [`Capability`]; a capability counts as available
+//! only if every probe reporting it agrees, so a registered probe can veto
+//! what the built-in probes found but never invent support the compositor
+//! lacks.
+
+use std::fmt;
+use std::sync::Arc;
+
+use ion_core::discovery::Capability;
 use ion_core::mode::{CaptureTierInfo, RemoteDesktopMode, SessionCapabilities};
 use tracing::{debug, info, warn};
 
-use crate::capture::{CaptureTier, TierSelector};
+use crate::capture::{
+    CaptureTier, CpuCapture, DmabufCapture, NullCapture, ScreenCapture, ShmCapture, TierSelector,
+};
 use crate::eis_backend::is_eis_available;
 
+/// Creates the capture backend serving `tier`.
+fn tier_capture(tier: CaptureTier, width: u32, height: u32) -> Box<dyn ScreenCapture> {
+    match tier {
+        CaptureTier::PipeWire | CaptureTier::Dmabuf => {
+            Box::new(DmabufCapture::with_defaults(width, height))
+        },
+        CaptureTier::Shm => Box::new(ShmCapture::with_defaults(width, height)),
+        CaptureTier::Cpu => Box::new(CpuCapture::with_defaults(width, height)),
+        CaptureTier::None => Box::new(NullCapture::new()),
+    }
+}
+
+/// A capability and whether a probe found it available.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbedCapability {
+    /// The capability that was checked
+    pub capability: Capability,
+    /// Whether it is usable in this environment
+    pub available: bool,
+}
+
+impl ProbedCapability {
+    /// Reports `capability` as available.
+    #[must_use]
+    pub const fn available(capability: Capability) -> Self {
+        Self {
+            capability,
+            available: true,
+        }
+    }
+
+    /// Reports `capability` as unavailable.
+    #[must_use]
+    pub const fn unavailable(capability: Capability) -> Self {
+        Self {
+            capability,
+            available: false,
+        }
+    }
+}
+
+/// An environment check run by [`CapabilityProvider::probe`].
+///
+/// Closures can be registered directly with
+/// [`CapabilityProvider::with_probe_fn`].
+pub trait CapabilityProbe: Send + Sync {
+    /// Name used in logs.
+    fn name(&self) -> &str;
+
+    /// Checks the environment.
+    fn probe(&self) -> ProbedCapability;
+}
+
+/// Adapts a closure to [`CapabilityProbe`].
+struct FnProbe<F> {
+    name: String,
+    probe: F,
+}
+
+impl<F> CapabilityProbe for FnProbe<F>
+where
+    F: Fn() -> ProbedCapability + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn probe(&self) -> ProbedCapability {
+        (self.probe)()
+    }
+}
+
 /// Provides capability information for remote desktop sessions.
 ///
 /// This struct probes the environment to determine:
 /// - What screen capture tier is available (if any)
 /// - Whether input injection is available (EIS)
 /// - What session mode can be offered to clients
-#[derive(Debug)]
+///
+/// Registered [`CapabilityProbe`]s run after the built-in ones and can
+/// veto what they found.
 pub struct CapabilityProvider {
     tier_selector: TierSelector,
     capture_tier: Option<CaptureTier>,
     input_available: bool,
+    probes: Vec<Arc<dyn CapabilityProbe>>,
+    probed_capabilities: Vec<ProbedCapability>,
     probed: bool,
 }
 
@@ -36,10 +126,41 @@ impl CapabilityProvider {
             tier_selector: TierSelector::new(),
             capture_tier: None,
             input_available: false,
+            probes: Vec::new(),
+            probed_capabilities: Vec::new(),
             probed: false,
         }
     }
 
+    /// Registers an additional probe, run after the built-in ones.
+    #[must_use]
+    pub fn with_probe<P>(mut self, probe: P) -> Self
+    where
+        P: CapabilityProbe + 'static,
+    {
+        self.register_probe(Arc::new(probe));
+        self
+    }
+
+    /// Registers a closure as an additional probe.
+    #[must_use]
+    pub fn with_probe_fn<F>(self, name: impl Into<String>, probe: F) -> Self
+    where
+        F: Fn() -> ProbedCapability + Send + Sync + 'static,
+    {
+        self.with_probe(FnProbe {
+            name: name.into(),
+            probe,
+        })
+    }
+
+    /// Registers an additional probe, run after the built-in ones.
+    ///
+    /// Takes effect on the next call to `probe()`.
+    pub fn register_probe(&mut self, probe: Arc<dyn CapabilityProbe>) {
+        self.probes.push(probe);
+    }
+
     /// Probes the environment for available capabilities.
     ///
     /// This performs actual capability detection and should be called
@@ -62,6 +183,38 @@ impl CapabilityProvider {
             self.input_available = true;
         }
 
+        let capture_available = self.capture_tier.is_some_and(|t| t.has_capture());
+        let builtin = [
+            (Capability::CaptureScreen, capture_available),
+            (Capability::InjectKeyboard, self.input_available),
+            (Capability::InjectPointer, self.input_available),
+        ];
+        self.probed_capabilities = builtin
+            .into_iter()
+            .map(|(capability, available)| ProbedCapability {
+                capability,
+                available,
+            })
+            .collect();
+
+        // Registered probes can only veto what the built-in probes found
+        for probe in &self.probes {
+            let result = probe.probe();
+            debug!(
+                probe = probe.name(),
+                capability = ?result.capability,
+                available = result.available,
+                "Custom capability probe"
+            );
+            self.probed_capabilities.push(result);
+        }
+        if self.is_capability_available(&Capability::CaptureScreen) == Some(false) {
+            self.capture_tier = Some(CaptureTier::None);
+        }
+        self.input_available = [Capability::InjectKeyboard, Capability::InjectPointer]
+            .iter()
+            .any(|c| self.is_capability_available(c) == Some(true));
+
         self.probed = true;
 
         info!(
@@ -80,6 +233,42 @@ impl CapabilityProvider {
         self.capture_tier.filter(|t| t.has_capture())
     }
 
+    /// Creates a capture backend for the detected tier.
+    ///
+    /// Falls back to [`NullCapture`] when no tier is available (or before
+    /// probing), so input-only sessions still get a backend. `PipeWire`
+    /// negotiates DMA-BUF where it can and is backed by [`DmabufCapture`].
+    #[must_use]
+    pub fn capture_backend(&self, width: u32, height: u32) -> Box<dyn ScreenCapture> {
+        tier_capture(
+            self.capture_tier().unwrap_or(CaptureTier::None),
+            width,
+            height,
+        )
+    }
+
+    /// Returns whether every probe reporting `capability` found it
+    /// available.
+    ///
+    /// Returns `None` if no probe reported it (or before probing).
+    #[must_use]
+    pub fn is_capability_available(&self, capability: &Capability) -> Option<bool> {
+        let mut reports = self
+            .probed_capabilities
+            .iter()
+            .filter(|p| &p.capability == capability)
+            .peekable();
+        reports.peek()?;
+        Some(reports.all(|p| p.available))
+    }
+
+    /// Returns every probe result from the last `probe()`, built-in
+    /// probes first.
+    #[must_use]
+    pub fn probed_capabilities(&self) -> &[ProbedCapability] {
+        &self.probed_capabilities
+    }
+
     /// Returns true if input injection is available.
     #[must_use]
     pub fn input_available(&self) -> bool {
@@ -190,13 +379,41 @@ impl Default for CapabilityProvider {
     }
 }
 
+impl fmt::Debug for CapabilityProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let probes: Vec<&str> = self.probes.iter().map(|p| p.name()).collect();
+        f.debug_struct("CapabilityProvider")
+            .field("tier_selector", &self.tier_selector)
+            .field("capture_tier", &self.capture_tier)
+            .field("input_available", &self.input_available)
+            .field("probes", &probes)
+            .field("probed_capabilities", &self.probed_capabilities)
+            .field("probed", &self.probed)
+            .finish()
+    }
+}
+
 /// Quick probe for best mode without full capability tracking.
 pub async fn detect_best_mode() -> RemoteDesktopMode {
-    let mut provider = CapabilityProvider::new();
+    detect_best_mode_with(CapabilityProvider::new()).await
+}
+
+/// Quick probe for best mode, consulting the probes registered on
+/// `provider`.
+pub async fn detect_best_mode_with(mut provider: CapabilityProvider) -> RemoteDesktopMode {
     provider.probe().await;
     provider.best_mode()
 }
 
+/// Quick probe for the best capture backend.
+///
+/// Returns a [`NullCapture`] when nothing else is available.
+pub async fn detect_best_capture(width: u32, height: u32) -> Box<dyn ScreenCapture> {
+    let mut provider = CapabilityProvider::new();
+    provider.probe().await;
+    provider.capture_backend(width, height)
+}
+
 /// Quick check if input-only mode is available.
 ///
 /// Returns `true` if we can inject input even without screen capture.
@@ -254,6 +471,25 @@ mod tests {
         assert_eq!(provider.best_mode(), RemoteDesktopMode::None);
     }
 
+    #[test]
+    fn unprobed_capture_backend_is_null() {
+        use crate::capture::ScreenCaptureExt;
+
+        let provider = CapabilityProvider::new();
+        let capture = provider.capture_backend(640, 480);
+        assert!(!capture.is_available());
+    }
+
+    #[tokio::test]
+    async fn detect_best_capture_matches_tier() {
+        use crate::capture::ScreenCaptureExt;
+
+        let mut provider = CapabilityProvider::new();
+        provider.probe().await;
+        let capture = detect_best_capture(640, 480).await;
+        assert_eq!(capture.is_available(), provider.capture_tier().is_some());
+    }
+
     #[test]
     fn unprobed_summary_says_not_probed() {
         let provider = CapabilityProvider::new();
@@ -317,6 +553,70 @@ mod tests {
         assert!(!provider.is_probed());
     }
 
+    #[tokio::test]
+    async fn builtin_probes_run_without_registered_probes() {
+        let mut provider = CapabilityProvider::new();
+        assert!(provider.probed_capabilities().is_empty());
+        provider.probe().await;
+
+        let reported: Vec<&Capability> = provider
+            .probed_capabilities()
+            .iter()
+            .map(|p| &p.capability)
+            .collect();
+        assert_eq!(
+            reported,
+            [
+                &Capability::CaptureScreen,
+                &Capability::InjectKeyboard,
+                &Capability::InjectPointer
+            ]
+        );
+        assert_eq!(
+            provider.is_capability_available(&Capability::CaptureScreen),
+            Some(provider.capture_tier().is_some())
+        );
+        assert_eq!(
+            provider.is_capability_available(&Capability::InjectPointer),
+            Some(true)
+        );
+        assert_eq!(
+            provider.is_capability_available(&Capability::Custom("gpu".into())),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn registered_probe_can_veto_capture() {
+        let provider = CapabilityProvider::new()
+            .with_probe_fn("gpu-quirks", || {
+                ProbedCapability::unavailable(Capability::CaptureScreen)
+            })
+            .with_probe_fn("gpu-model", || {
+                ProbedCapability::available(Capability::Custom("gpu".into()))
+            });
+        assert!(format!("{provider:?}").contains("gpu-quirks"));
+
+        let mut provider = provider;
+        provider.probe().await;
+        assert_eq!(provider.best_mode(), RemoteDesktopMode::InputOnly);
+        assert_eq!(provider.capture_tier(), None);
+        assert!(!provider.session_capabilities().capture_available);
+        assert_eq!(
+            provider.is_capability_available(&Capability::Custom("gpu".into())),
+            Some(true)
+        );
+
+        let vetoed = CapabilityProvider::new()
+            .with_probe_fn("no-pointer", || {
+                ProbedCapability::unavailable(Capability::InjectPointer)
+            })
+            .with_probe_fn("no-keyboard", || {
+                ProbedCapability::unavailable(Capability::InjectKeyboard)
+            });
+        assert!(!detect_best_mode_with(vetoed).await.has_input());
+    }
+
     #[tokio::test]
     async fn multiple_probes_work() {
         let mut provider = CapabilityProvider::new();
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Video codecs and the capture formats they encode from cheaply.
+//!
+//! Capturing in a format the encoder does not take costs a CPU conversion
+//! per frame. [`Codec::input_formats`] ranks every [`FrameFormat`] by how
+//! little work it takes to turn into the codec's input, so a capture
+//! backend can pick the cheapest format it offers.
+
+use std::fmt;
+
+use super::FrameFormat;
+
+/// Video codec a capture stream is encoded with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Codec {
+    /// H.264/AVC, 8-bit 4:2:0.
+    H264,
+    /// VP8, 8-bit 4:2:0.
+    Vp8,
+    /// AV1, up to 10-bit 4:2:0.
+    Av1,
+}
+
+/// 8-bit 4:2:0 codecs: NV12 as is, then RGB by falling conversion cost.
+const YUV420_8BIT: [FrameFormat; 8] = [
+    FrameFormat::Nv12,
+    FrameFormat::Xrgb8888,
+    FrameFormat::Xbgr8888,
+    FrameFormat::Bgra8888,
+    FrameFormat::Rgba8888,
+    FrameFormat::Rgb888,
+    FrameFormat::Bgr888,
+    FrameFormat::Xrgb2101010,
+];
+
+/// 10-bit capable codecs keep HDR depth ahead of 8-bit RGB.
+const YUV420_10BIT: [FrameFormat; 8] = [
+    FrameFormat::Nv12,
+    FrameFormat::Xrgb2101010,
+    FrameFormat::Xrgb8888,
+    FrameFormat::Xbgr8888,
+    FrameFormat::Bgra8888,
+    FrameFormat::Rgba8888,
+    FrameFormat::Rgb888,
+    FrameFormat::Bgr888,
+];
+
+impl Codec {
+    /// Returns the codec's short name.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::H264 => "h264",
+            Self::Vp8 => "vp8",
+            Self::Av1 => "av1",
+        }
+    }
+
+    /// Whether the codec can encode 10-bit video, keeping HDR depth.
+    #[must_use]
+    pub const fn supports_10bit(&self) -> bool {
+        matches!(self, Self::Av1)
+    }
+
+    /// Returns every frame format, cheapest to encode from first.
+    ///
+    /// NV12 needs no color conversion for any of these codecs; VP8 takes
+    /// I420, which NV12 reaches by deinterleaving chroma alone. Of the RGB
+    /// formats, 32-bit layouts convert fastest and 24-bit ones need
+    /// repacking first.
+    #[must_use]
+    pub const fn input_formats(&self) -> &'static [FrameFormat] {
+        match self {
+            Self::H264 | Self::Vp8 => &YUV420_8BIT,
+            Self::Av1 => &YUV420_10BIT,
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_format_ranked_once() {
+        for codec in [Codec::H264, Codec::Vp8, Codec::Av1] {
+            let formats = codec.input_formats();
+            assert_eq!(formats[0], FrameFormat::Nv12, "{codec}");
+            for format in formats {
+                assert_eq!(
+                    formats.iter().filter(|f| *f == format).count(),
+                    1,
+                    "{codec} ranks {format} twice"
+                );
+                assert_eq!(FrameFormat::from_fourcc(format.fourcc()), Some(*format));
+            }
+            assert_eq!(formats.len(), 8);
+        }
+    }
+}
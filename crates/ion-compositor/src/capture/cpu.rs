@@ -23,12 +23,13 @@ use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use ion_core::backend::OutputTransform;
 use tokio::sync::{broadcast, Mutex, RwLock};
 use tracing::{debug, info, warn};
 
 use super::{
-    CaptureCapabilities, CaptureFrame, CaptureResult, FrameFormat, FrameMetadataBuilder,
-    ScreenCapture,
+    finish_transform, plan_transform, validate_settings, CaptureCapabilities, CaptureFrame,
+    CaptureResult, FrameFormat, FrameMetadataBuilder, ScreenCapture,
 };
 
 /// Configuration for CPU capture.
@@ -76,6 +77,7 @@ pub struct CpuCapture {
     capabilities: CaptureCapabilities,
     state: Arc<RwLock<CpuCaptureState>>,
     capture_lock: Arc<Mutex<()>>,
+    transform: OutputTransform,
 }
 
 impl CpuCapture {
@@ -103,15 +105,39 @@ impl CpuCapture {
             capabilities,
             state: Arc::new(RwLock::new(state)),
             capture_lock: Arc::new(Mutex::new(())),
+            transform: OutputTransform::Normal,
         }
     }
 
+    /// Creates a new CPU capture backend, validating its settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`super::CaptureError::InvalidRequest`] for zero dimensions
+    /// or a zero target frame rate.
+    pub fn try_new(width: u32, height: u32, config: CpuCaptureConfig) -> CaptureResult<Self> {
+        validate_settings(width, height, config.target_fps)?;
+        Ok(Self::new(width, height, config))
+    }
+
     /// Creates with default configuration.
     #[must_use]
     pub fn with_defaults(width: u32, height: u32) -> Self {
         Self::new(width, height, CpuCaptureConfig::default())
     }
 
+    /// Captures an output with `transform`, delivering upright frames in
+    /// packed formats; planar frames report the transform for the client
+    /// to apply, as [`plan_transform`](super::plan_transform) describes.
+    ///
+    /// The dimensions given at construction are the upright ones, as in
+    /// [`ion_core::backend::OutputInfo`].
+    #[must_use]
+    pub fn with_transform(mut self, transform: OutputTransform) -> Self {
+        self.transform = transform;
+        self
+    }
+
     /// Performs the actual CPU capture.
     async fn do_capture(&self) -> CaptureResult<CaptureFrame> {
         let _guard = self.capture_lock.lock().await;
@@ -137,6 +163,8 @@ impl CpuCapture {
 
         debug!(sequence, "CPU capture (architecture ready)");
 
+        let (width, height, handling) =
+            plan_transform(self.transform, self.config.format, width, height);
         let stride = width * self.config.format.bytes_per_pixel() as u32;
         let data = self.generate_fallback_frame(width, height, sequence);
 
@@ -148,14 +176,14 @@ impl CpuCapture {
             .capture_start(capture_start)
             .build();
 
-        Ok(CaptureFrame::new(metadata, data))
+        finish_transform(CaptureFrame::new(metadata, data), self.transform, handling)
     }
 
     /// Generates a fallback frame for testing.
     fn generate_fallback_frame(&self, width: u32, height: u32, sequence: u64) -> Vec<u8> {
         let bpp = self.config.format.bytes_per_pixel();
         let stride = width as usize * bpp;
-        let mut data = vec![0u8; stride * height as usize];
+        let mut data = vec![0u8; self.config.format.frame_size(stride, height as usize)];
 
         // Simple checkerboard with animation
         let checker_size = 32;
@@ -199,10 +227,23 @@ impl CpuCapture {
                         data[pixel_offset + 1] = g;
                         data[pixel_offset + 2] = r;
                     },
+                    FrameFormat::Xrgb2101010 => {
+                        let pixel = u32::from(r) << 22 | u32::from(g) << 12 | u32::from(b) << 2;
+                        data[pixel_offset..pixel_offset + 4].copy_from_slice(&pixel.to_le_bytes());
+                    },
+                    FrameFormat::Nv12 => {
+                        // Grey checkerboard, so luma is the channel value
+                        data[pixel_offset] = r;
+                    },
                 }
             }
         }
 
+        if self.config.format == FrameFormat::Nv12 {
+            // Neutral chroma plane
+            data[stride * height as usize..].fill(128);
+        }
+
         data
     }
 
@@ -274,7 +315,8 @@ impl ScreenCapture for CpuCapture {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::capture::{CaptureTier, ScreenCaptureExt};
+    use crate::capture::{CaptureError, CaptureTier, Rect, ScreenCaptureExt};
+    use ion_core::backend::TransformHandling;
 
     #[tokio::test]
     async fn cpu_capture_basic() {
@@ -312,6 +354,38 @@ mod tests {
         assert_eq!(frame1.metadata.sequence + 1, frame2.metadata.sequence);
     }
 
+    #[tokio::test]
+    async fn cpu_capture_half_scale_preview() {
+        let capture = CpuCapture::with_defaults(800, 600);
+
+        let preview = capture.capture_region_frame(None, 0.5).await.unwrap();
+        assert_eq!((preview.width(), preview.height()), (400, 300));
+        assert_eq!(preview.data().len(), 400 * 300 * 4);
+
+        let region = Rect::new(100, 50, 200, 100);
+        let preview = capture
+            .capture_region_frame(Some(region), 0.5)
+            .await
+            .unwrap();
+        assert_eq!((preview.width(), preview.height()), (100, 50));
+    }
+
+    #[tokio::test]
+    async fn cpu_capture_preview_rejects_bad_requests() {
+        let capture = CpuCapture::with_defaults(800, 600);
+
+        assert!(matches!(
+            capture.capture_region_frame(None, 2.0).await,
+            Err(CaptureError::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            capture
+                .capture_region_frame(Some(Rect::new(700, 0, 200, 100)), 1.0)
+                .await,
+            Err(CaptureError::InvalidRequest(_))
+        ));
+    }
+
     #[test]
     fn cpu_capabilities() {
         let capture = CpuCapture::with_defaults(100, 100);
@@ -357,6 +431,23 @@ mod tests {
         assert_eq!(hash1, hash2);
     }
 
+    #[test]
+    fn cpu_try_new_rejects_zero_settings() {
+        assert!(CpuCapture::try_new(640, 480, CpuCaptureConfig::default()).is_ok());
+        assert!(matches!(
+            CpuCapture::try_new(0, 480, CpuCaptureConfig::default()),
+            Err(CaptureError::InvalidRequest(_))
+        ));
+        let config = CpuCaptureConfig {
+            target_fps: 0,
+            ..CpuCaptureConfig::default()
+        };
+        assert!(matches!(
+            CpuCapture::try_new(640, 480, config),
+            Err(CaptureError::InvalidRequest(_))
+        ));
+    }
+
     #[tokio::test]
     async fn cpu_start_stream() {
         let capture = CpuCapture::with_defaults(100, 100);
@@ -445,6 +536,33 @@ mod tests {
         assert_eq!(data.len(), 64 * 64 * 3);
     }
 
+    #[tokio::test]
+    async fn cpu_rotated_output_delivered_upright() {
+        let capture =
+            CpuCapture::with_defaults(1080, 1920).with_transform(OutputTransform::Rotate90);
+        let frame = capture.do_capture().await.unwrap();
+
+        assert_eq!(frame.width(), 1080);
+        assert_eq!(frame.height(), 1920);
+        assert_eq!(frame.metadata.stride, 1080 * 4);
+        assert_eq!(frame.metadata.transform, OutputTransform::Rotate90);
+        assert_eq!(frame.metadata.transform_handling, TransformHandling::Cpu);
+    }
+
+    #[tokio::test]
+    async fn cpu_rotated_planar_output_left_to_client() {
+        let config = CpuCaptureConfig {
+            format: FrameFormat::Nv12,
+            ..Default::default()
+        };
+        let capture = CpuCapture::new(64, 32, config).with_transform(OutputTransform::Rotate270);
+        let frame = capture.do_capture().await.unwrap();
+
+        assert_eq!((frame.width(), frame.height()), (32, 64));
+        assert_eq!(frame.metadata.transform, OutputTransform::Rotate270);
+        assert_eq!(frame.metadata.transform_handling, TransformHandling::None);
+    }
+
     #[test]
     fn cpu_is_send_sync() {
         fn assert_send_sync<T: Send + Sync>() {}
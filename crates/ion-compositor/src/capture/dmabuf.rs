@@ -24,12 +24,13 @@ use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 
+use ion_core::backend::{OutputTransform, TransformHandling};
 use tokio::sync::{broadcast, RwLock};
 use tracing::{debug, info};
 
 use super::{
-    CaptureCapabilities, CaptureError, CaptureFrame, CaptureResult, FrameFormat,
-    FrameMetadataBuilder, ScreenCapture,
+    validate_settings, CaptureCapabilities, CaptureError, CaptureFrame, CaptureResult, Codec,
+    FrameFormat, FrameMetadataBuilder, ScreenCapture,
 };
 
 /// DRM format with modifier.
@@ -54,6 +55,26 @@ impl DrmFormat {
     pub const MODIFIER_INVALID: u64 = 0x00ff_ffff_ffff_ffff;
 }
 
+/// Picks the DRM format to capture in from the formats a compositor offers.
+///
+/// Preferred formats are tried in order. Each matches an offered format
+/// with the same modifier or, failing that, the same fourcc with whatever
+/// modifier the compositor offers, since a tiled buffer in a wanted format
+/// beats converting from another one. Returns `None` if no preferred
+/// fourcc is offered at all.
+#[must_use]
+pub fn negotiate_format(preferred: &[DrmFormat], offered: &[DrmFormat]) -> Option<DrmFormat> {
+    preferred
+        .iter()
+        .find_map(|p| {
+            offered
+                .iter()
+                .find(|o| *o == p)
+                .or_else(|| offered.iter().find(|o| o.fourcc == p.fourcc))
+        })
+        .copied()
+}
+
 /// Configuration for DMA-BUF capture.
 #[derive(Debug, Clone)]
 pub struct DmabufCaptureConfig {
@@ -75,13 +96,41 @@ impl Default for DmabufCaptureConfig {
     }
 }
 
+impl DmabufCaptureConfig {
+    /// Configuration for feeding a hardware encoder.
+    ///
+    /// Prefers linear NV12, which most hardware H.264 encoders import
+    /// without a color conversion, then falls back to the RGB defaults.
+    #[must_use]
+    pub fn hardware_encoder() -> Self {
+        let mut config = Self::default();
+        config.preferred_formats.insert(
+            0,
+            DrmFormat::new(FrameFormat::Nv12.fourcc(), DrmFormat::MODIFIER_LINEAR),
+        );
+        config
+    }
+
+    /// Configuration preferring the formats cheapest to encode with
+    /// `codec`, all linear.
+    #[must_use]
+    pub fn for_codec(codec: Codec) -> Self {
+        Self {
+            preferred_formats: codec
+                .input_formats()
+                .iter()
+                .map(|f| DrmFormat::new(f.fourcc(), DrmFormat::MODIFIER_LINEAR))
+                .collect(),
+            ..Self::default()
+        }
+    }
+}
+
 /// Internal state for DMA-BUF capture.
 struct DmabufCaptureState {
     sequence: AtomicU64,
     streaming: AtomicBool,
     dimensions: (u32, u32),
-    #[allow(dead_code)]
-    active_format: DrmFormat,
 }
 
 /// Tier 1 screen capture using DMA-BUF.
@@ -101,7 +150,13 @@ pub struct DmabufCapture {
     #[allow(dead_code)]
     config: DmabufCaptureConfig,
     capabilities: CaptureCapabilities,
+    /// Negotiated DRM format and modifier.
+    active_format: DrmFormat,
+    /// Frame format of the negotiated DRM format.
+    native_format: FrameFormat,
     state: Arc<RwLock<DmabufCaptureState>>,
+    /// Transform of the captured output.
+    transform: OutputTransform,
 }
 
 impl DmabufCapture {
@@ -121,12 +176,8 @@ impl DmabufCapture {
         config: DmabufCaptureConfig,
     ) -> Self {
         // Select the best available format
-        let active_format = config
-            .preferred_formats
-            .iter()
-            .find(|f| formats.contains(f))
-            .copied()
-            .unwrap_or_else(|| {
+        let active_format =
+            negotiate_format(&config.preferred_formats, &formats).unwrap_or_else(|| {
                 formats
                     .first()
                     .copied()
@@ -135,35 +186,61 @@ impl DmabufCapture {
 
         let frame_formats: Vec<FrameFormat> = formats
             .iter()
-            .filter_map(|f| match f.fourcc {
-                x if x == FrameFormat::Bgra8888.fourcc() => Some(FrameFormat::Bgra8888),
-                x if x == FrameFormat::Rgba8888.fourcc() => Some(FrameFormat::Rgba8888),
-                x if x == FrameFormat::Xrgb8888.fourcc() => Some(FrameFormat::Xrgb8888),
-                _ => None,
-            })
+            .filter_map(|f| FrameFormat::from_fourcc(f.fourcc))
             .collect();
 
+        let native_format =
+            FrameFormat::from_fourcc(active_format.fourcc).unwrap_or(FrameFormat::Bgra8888);
+
         let capabilities = CaptureCapabilities::dmabuf(frame_formats);
 
         let state = DmabufCaptureState {
             sequence: AtomicU64::new(0),
             streaming: AtomicBool::new(false),
             dimensions: (width, height),
-            active_format,
         };
 
         info!(
             width,
             height,
             format = ?active_format,
+            %native_format,
             "Created DMA-BUF capture backend"
         );
 
         Self {
             config,
             capabilities,
+            active_format,
+            native_format,
             state: Arc::new(RwLock::new(state)),
+            transform: OutputTransform::Normal,
+        }
+    }
+
+    /// Creates a new DMA-BUF capture backend, validating its settings.
+    ///
+    /// Unlike [`Self::new`], which falls back to the first preferred
+    /// format, the compositor must offer at least one DRM format.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::InvalidRequest`] for zero dimensions or a
+    /// zero target frame rate, and [`CaptureError::NotAvailable`] if the
+    /// compositor offers no formats.
+    pub fn try_new(
+        width: u32,
+        height: u32,
+        formats: Vec<DrmFormat>,
+        config: DmabufCaptureConfig,
+    ) -> CaptureResult<Self> {
+        validate_settings(width, height, config.target_fps)?;
+        if formats.is_empty() {
+            return Err(CaptureError::NotAvailable(
+                "compositor offers no DMA-BUF formats".into(),
+            ));
         }
+        Ok(Self::new(width, height, formats, config))
     }
 
     /// Creates with default configuration.
@@ -181,6 +258,34 @@ impl DmabufCapture {
         )
     }
 
+    /// Captures an output with `transform`.
+    ///
+    /// The dimensions given at construction are the upright ones. Buffers
+    /// stay on the GPU and cannot be rotated here, so frames are delivered
+    /// in the panel's native orientation and report the transform for the
+    /// client to apply.
+    #[must_use]
+    pub fn with_transform(mut self, transform: OutputTransform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Returns the negotiated DRM format and modifier.
+    #[must_use]
+    pub const fn active_format(&self) -> DrmFormat {
+        self.active_format
+    }
+
+    /// Returns the format frames are produced in.
+    ///
+    /// This is the negotiated DRM format, so a consumer that accepts it
+    /// (e.g. a hardware encoder taking NV12) can import buffers as-is.
+    /// Unknown fourcc codes are reported as BGRA.
+    #[must_use]
+    pub const fn native_format(&self) -> FrameFormat {
+        self.native_format
+    }
+
     /// Performs the actual DMA-BUF capture.
     async fn do_capture(&self) -> CaptureResult<CaptureFrame> {
         let capture_start = Instant::now();
@@ -205,14 +310,18 @@ impl DmabufCapture {
 
         debug!(sequence, "DMA-BUF capture (architecture ready)");
 
-        let stride = width * 4;
-        let data = vec![0u8; (stride * height) as usize];
+        let format = self.native_format;
+        let (width, height) = self.transform.apply_to_size(width, height);
+        #[allow(clippy::cast_possible_truncation)] // at most 4 bytes per pixel
+        let stride = width * format.bytes_per_pixel() as u32;
+        let data = vec![0u8; format.frame_size(stride as usize, height as usize)];
 
         let metadata = FrameMetadataBuilder::new()
             .sequence(sequence)
             .dimensions(width, height)
             .stride(stride)
-            .format(FrameFormat::Bgra8888)
+            .format(format)
+            .transform(self.transform, TransformHandling::None)
             .capture_start(capture_start)
             .build();
 
@@ -261,6 +370,18 @@ mod tests {
     use super::*;
     use crate::capture::{CaptureTier, ScreenCaptureExt};
 
+    #[tokio::test]
+    async fn dmabuf_rotated_output_left_to_client() {
+        let capture =
+            DmabufCapture::with_defaults(1080, 1920).with_transform(OutputTransform::Rotate90);
+        let frame = capture.do_capture().await.unwrap();
+
+        assert_eq!((frame.width(), frame.height()), (1920, 1080));
+        assert_eq!(frame.metadata.stride, 1920 * 4);
+        assert_eq!(frame.metadata.transform, OutputTransform::Rotate90);
+        assert_eq!(frame.metadata.transform_handling, TransformHandling::None);
+    }
+
     #[tokio::test]
     async fn dmabuf_capture_basic() {
         let capture = DmabufCapture::with_defaults(1920, 1080);
@@ -309,6 +430,35 @@ mod tests {
         assert_eq!(config.target_fps, 30);
     }
 
+    #[test]
+    fn dmabuf_try_new_validates() {
+        let formats = || {
+            vec![DrmFormat::new(
+                FrameFormat::Bgra8888.fourcc(),
+                DrmFormat::MODIFIER_LINEAR,
+            )]
+        };
+        let config = DmabufCaptureConfig::default;
+
+        assert!(DmabufCapture::try_new(1920, 1080, formats(), config()).is_ok());
+        assert!(matches!(
+            DmabufCapture::try_new(1920, 0, formats(), config()),
+            Err(CaptureError::InvalidRequest(_))
+        ));
+        let zero_fps = DmabufCaptureConfig {
+            target_fps: 0,
+            ..config()
+        };
+        assert!(matches!(
+            DmabufCapture::try_new(1920, 1080, formats(), zero_fps),
+            Err(CaptureError::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            DmabufCapture::try_new(1920, 1080, Vec::new(), config()),
+            Err(CaptureError::NotAvailable(_))
+        ));
+    }
+
     #[tokio::test]
     async fn dmabuf_start_stream_not_available() {
         let capture = DmabufCapture::with_defaults(100, 100);
@@ -396,4 +546,47 @@ mod tests {
 
         assert_eq!(capture.capabilities().tier, CaptureTier::Dmabuf);
     }
+
+    #[tokio::test]
+    async fn dmabuf_reports_nv12_native_format() {
+        let formats = vec![
+            DrmFormat::new(FrameFormat::Bgra8888.fourcc(), DrmFormat::MODIFIER_LINEAR),
+            DrmFormat::new(FrameFormat::Nv12.fourcc(), DrmFormat::MODIFIER_LINEAR),
+        ];
+        let capture = DmabufCapture::new(64, 48, formats, DmabufCaptureConfig::hardware_encoder());
+
+        assert_eq!(capture.native_format(), FrameFormat::Nv12);
+        assert!(capture.capabilities().formats.contains(&FrameFormat::Nv12));
+
+        let frame = capture.do_capture().await.unwrap();
+        assert_eq!(frame.format(), FrameFormat::Nv12);
+        assert_eq!(frame.data().len(), 64 * 48 * 3 / 2);
+    }
+
+    #[test]
+    fn dmabuf_config_for_codec_negotiates_cheapest_format() {
+        let formats = vec![
+            DrmFormat::new(FrameFormat::Bgra8888.fourcc(), DrmFormat::MODIFIER_LINEAR),
+            DrmFormat::new(
+                FrameFormat::Xrgb2101010.fourcc(),
+                DrmFormat::MODIFIER_LINEAR,
+            ),
+        ];
+        let h264 = DmabufCapture::new(
+            64,
+            48,
+            formats.clone(),
+            DmabufCaptureConfig::for_codec(Codec::H264),
+        );
+        assert_eq!(h264.native_format(), FrameFormat::Bgra8888);
+
+        let av1 = DmabufCapture::new(64, 48, formats, DmabufCaptureConfig::for_codec(Codec::Av1));
+        assert_eq!(av1.native_format(), FrameFormat::Xrgb2101010);
+    }
+
+    #[test]
+    fn dmabuf_native_format_defaults_to_rgb() {
+        let capture = DmabufCapture::with_defaults(64, 48);
+        assert_eq!(capture.native_format(), FrameFormat::Bgra8888);
+    }
 }
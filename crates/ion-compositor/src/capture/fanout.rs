@@ -0,0 +1,230 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Fanning a stream out to consumers that join late.
+//!
+//! A `broadcast` receiver only sees what is sent after it subscribes. For
+//! an encoded stream that means inter-frames it cannot decode until the
+//! next scheduled keyframe, up to a full GOP later; for a raw stream,
+//! nothing to show until the next capture. A [`StreamBroadcast`] covers
+//! both:
+//!
+//! - [`StreamBroadcast::encoded`] asks the encoder for a keyframe whenever
+//!   a consumer joins. Joins before the next frame is encoded coalesce
+//!   into one keyframe (see [`CaptureHandle::request_keyframe`]).
+//! - [`StreamBroadcast::raw`] keeps the last frame and hands it to each
+//!   new subscriber before anything else.
+//!
+//! Consumers that join through [`StreamBroadcast::subscribe`] are served
+//! at once. Receivers cloned with `resubscribe` bypass it; the broadcast
+//! notices them from the receiver count on the next send, so their
+//! keyframe follows one frame later.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use ion_core::backend::CaptureHandle;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tracing::debug;
+
+/// How a late subscriber is brought up to date.
+#[derive(Debug)]
+enum LateJoin<T> {
+    /// Replay the last item sent
+    Replay(Mutex<Option<T>>),
+    /// Ask the encoder for a keyframe
+    Keyframe(CaptureHandle),
+}
+
+/// Sending half of a stream that consumers may join at any time.
+#[derive(Debug)]
+pub struct StreamBroadcast<T> {
+    tx: broadcast::Sender<T>,
+    late_join: LateJoin<T>,
+    /// Receivers as of the last send or subscribe
+    receivers: AtomicUsize,
+}
+
+impl<T: Clone> StreamBroadcast<T> {
+    /// Creates a broadcast of raw frames, replaying the last one to each
+    /// new subscriber.
+    ///
+    /// `capacity` is the number of items a receiver may lag behind.
+    #[must_use]
+    pub fn raw(capacity: usize) -> Self {
+        Self::with(capacity, LateJoin::Replay(Mutex::new(None)))
+    }
+
+    /// Creates a broadcast of encoded frames, requesting a keyframe on
+    /// `handle` whenever a consumer joins.
+    ///
+    /// `capacity` is the number of items a receiver may lag behind.
+    #[must_use]
+    pub fn encoded(capacity: usize, handle: CaptureHandle) -> Self {
+        Self::with(capacity, LateJoin::Keyframe(handle))
+    }
+
+    fn with(capacity: usize, late_join: LateJoin<T>) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self {
+            tx,
+            late_join,
+            receivers: AtomicUsize::new(0),
+        }
+    }
+
+    /// Joins the stream.
+    ///
+    /// On a raw stream the subscriber's first item is the last one sent,
+    /// if any. On an encoded stream a keyframe is requested.
+    pub fn subscribe(&self) -> StreamSubscriber<T> {
+        let (rx, pending) = match &self.late_join {
+            LateJoin::Replay(last) => {
+                // Under the lock, so no item is both replayed and received
+                let last = last.lock().unwrap_or_else(PoisonError::into_inner);
+                (self.tx.subscribe(), last.clone())
+            },
+            LateJoin::Keyframe(handle) => {
+                handle.request_keyframe();
+                (self.tx.subscribe(), None)
+            },
+        };
+        self.receivers
+            .store(self.tx.receiver_count(), Ordering::Relaxed);
+        StreamSubscriber { pending, rx }
+    }
+
+    /// Sends an item to every subscriber, returning how many there are.
+    pub fn send(&self, item: T) -> usize {
+        let count = self.tx.receiver_count();
+        let before = self.receivers.swap(count, Ordering::Relaxed);
+        match &self.late_join {
+            LateJoin::Replay(last) => {
+                let mut last = last.lock().unwrap_or_else(PoisonError::into_inner);
+                *last = Some(item.clone());
+                self.tx.send(item).unwrap_or(0)
+            },
+            LateJoin::Keyframe(handle) => {
+                if count > before {
+                    debug!(receivers = count, "Consumer joined, requesting keyframe");
+                    handle.request_keyframe();
+                }
+                self.tx.send(item).unwrap_or(0)
+            },
+        }
+    }
+
+    /// Number of subscribers.
+    #[must_use]
+    pub fn receiver_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
+
+/// Receiving half of a [`StreamBroadcast`].
+#[derive(Debug)]
+pub struct StreamSubscriber<T> {
+    /// Item replayed before anything received
+    pending: Option<T>,
+    rx: broadcast::Receiver<T>,
+}
+
+impl<T: Clone> StreamSubscriber<T> {
+    /// Waits for the next item.
+    ///
+    /// # Errors
+    ///
+    /// As [`broadcast::Receiver::recv`]: the stream closed, or this
+    /// subscriber lagged and missed items.
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        match self.pending.take() {
+            Some(item) => Ok(item),
+            None => self.rx.recv().await,
+        }
+    }
+
+    /// Returns the next item if one is ready.
+    ///
+    /// # Errors
+    ///
+    /// As [`broadcast::Receiver::try_recv`].
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        match self.pending.take() {
+            Some(item) => Ok(item),
+            None => self.rx.try_recv(),
+        }
+    }
+
+    /// Returns the underlying receiver, dropping any item not yet replayed.
+    #[must_use]
+    pub fn into_receiver(self) -> broadcast::Receiver<T> {
+        self.rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ion_core::backend::KeyframeScheduler;
+
+    #[test]
+    fn late_subscriber_gets_keyframe_without_waiting_for_gop() {
+        let handle = CaptureHandle::default();
+        let mut scheduler = KeyframeScheduler::new(handle.clone(), 30);
+        let stream = StreamBroadcast::encoded(64, handle.clone());
+        let _first = stream.subscribe();
+        // (sequence, keyframe) stands in for an encoded frame
+        let mut encode = |sequence: u32| {
+            stream.send((sequence, scheduler.next_is_keyframe()));
+        };
+
+        for sequence in 0..10 {
+            encode(sequence);
+        }
+        let mut late = stream.subscribe();
+        encode(10);
+        assert_eq!(late.try_recv().unwrap(), (10, true));
+
+        // Joins before the next frame share one keyframe
+        let mut a = stream.subscribe();
+        let mut b = stream.subscribe();
+        encode(11);
+        encode(12);
+        for subscriber in [&mut a, &mut b] {
+            assert_eq!(subscriber.try_recv().unwrap(), (11, true));
+            assert_eq!(subscriber.try_recv().unwrap(), (12, false));
+        }
+        assert_eq!(handle.forced_keyframes(), 2);
+
+        // A receiver cloned behind the broadcast's back is noticed on the
+        // next send and gets the keyframe after it
+        let late = late.into_receiver();
+        let mut cloned = late.resubscribe();
+        encode(13);
+        encode(14);
+        assert_eq!(cloned.try_recv().unwrap(), (13, false));
+        assert_eq!(cloned.try_recv().unwrap(), (14, true));
+        assert_eq!(handle.forced_keyframes(), 3);
+    }
+
+    #[tokio::test]
+    async fn late_subscriber_to_raw_stream_gets_last_frame_first() {
+        let stream = StreamBroadcast::raw(8);
+        let mut early = stream.subscribe();
+        assert_eq!(early.try_recv(), Err(TryRecvError::Empty));
+
+        for frame in 1..=3 {
+            stream.send(frame);
+        }
+        let mut late = stream.subscribe();
+        assert_eq!(late.recv().await, Ok(3));
+        assert_eq!(late.try_recv(), Err(TryRecvError::Empty));
+
+        stream.send(4);
+        assert_eq!(late.recv().await, Ok(4));
+        for frame in 1..=4 {
+            assert_eq!(early.recv().await, Ok(frame));
+        }
+    }
+}
@@ -3,9 +3,14 @@
 
 //! Captured frame data structures.
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
 use std::time::{Duration, Instant};
 
+use ion_core::backend::{OutputTransform, TransformHandling};
+
+use super::{CaptureError, CaptureResult};
+
 /// Pixel format for captured frames.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u32)]
@@ -22,15 +27,54 @@ pub enum FrameFormat {
     Rgb888 = 0x3432_4752, // DRM_FORMAT_RGB888
     /// 24-bit BGR (no alpha).
     Bgr888 = 0x5247_4218, // DRM_FORMAT_BGR888
+    /// 32-bit XRGB with 10 bits per color channel (HDR, X ignored).
+    Xrgb2101010 = 0x3033_5258, // DRM_FORMAT_XRGB2101010
+    /// YUV 4:2:0: a full-size Y plane followed by a half-size interleaved
+    /// UV plane. The native input of most hardware H.264 encoders.
+    Nv12 = 0x3231_564E, // DRM_FORMAT_NV12
 }
 
 impl FrameFormat {
     /// Returns the number of bytes per pixel.
+    ///
+    /// For planar formats this is the size of one sample in the first
+    /// plane (luma for NV12); use [`Self::frame_size`] for buffer sizes.
     #[must_use]
     pub const fn bytes_per_pixel(&self) -> usize {
         match self {
-            Self::Bgra8888 | Self::Rgba8888 | Self::Xrgb8888 | Self::Xbgr8888 => 4,
+            Self::Bgra8888
+            | Self::Rgba8888
+            | Self::Xrgb8888
+            | Self::Xbgr8888
+            | Self::Xrgb2101010 => 4,
             Self::Rgb888 | Self::Bgr888 => 3,
+            Self::Nv12 => 1,
+        }
+    }
+
+    /// Returns true if pixel data is split across several planes.
+    #[must_use]
+    pub const fn is_planar(&self) -> bool {
+        self.plane_count() > 1
+    }
+
+    /// Returns the number of planes in a frame of this format.
+    #[must_use]
+    pub const fn plane_count(&self) -> usize {
+        match self {
+            Self::Nv12 => 2,
+            _ => 1,
+        }
+    }
+
+    /// Returns the buffer size in bytes for `height` rows of `stride`
+    /// bytes, including every plane.
+    #[must_use]
+    pub const fn frame_size(&self, stride: usize, height: usize) -> usize {
+        match self {
+            // UV plane has the same stride and half the rows
+            Self::Nv12 => stride * height + stride * height.div_ceil(2),
+            _ => stride * height,
         }
     }
 
@@ -45,6 +89,29 @@ impl FrameFormat {
     pub const fn fourcc(&self) -> u32 {
         *self as u32
     }
+
+    /// Returns the format for a DRM fourcc code, if it is supported.
+    #[must_use]
+    pub const fn from_fourcc(fourcc: u32) -> Option<Self> {
+        const ALL: [FrameFormat; 8] = [
+            FrameFormat::Bgra8888,
+            FrameFormat::Rgba8888,
+            FrameFormat::Xrgb8888,
+            FrameFormat::Xbgr8888,
+            FrameFormat::Rgb888,
+            FrameFormat::Bgr888,
+            FrameFormat::Xrgb2101010,
+            FrameFormat::Nv12,
+        ];
+        let mut i = 0;
+        while i < ALL.len() {
+            if ALL[i].fourcc() == fourcc {
+                return Some(ALL[i]);
+            }
+            i += 1;
+        }
+        None
+    }
 }
 
 impl std::fmt::Display for FrameFormat {
@@ -56,6 +123,8 @@ impl std::fmt::Display for FrameFormat {
             Self::Xbgr8888 => "XBGR8888",
             Self::Rgb888 => "RGB888",
             Self::Bgr888 => "BGR888",
+            Self::Xrgb2101010 => "XRGB2101010",
+            Self::Nv12 => "NV12",
         };
         write!(f, "{name}")
     }
@@ -82,6 +151,13 @@ pub struct FrameMetadata {
     pub pipewire_node: Option<u32>,
     /// Output/monitor index.
     pub output_index: u32,
+    /// Output (connector) name, e.g. `DP-1`, if known.
+    pub output_name: Option<String>,
+    /// Transform of the captured output, applied to the pixels unless
+    /// [`Self::transform_handling`] is [`TransformHandling::None`].
+    pub transform: OutputTransform,
+    /// Who applied [`Self::transform`].
+    pub transform_handling: TransformHandling,
 }
 
 impl FrameMetadata {
@@ -91,10 +167,11 @@ impl FrameMetadata {
         self.capture_end.duration_since(self.capture_start)
     }
 
-    /// Returns the total frame size in bytes.
+    /// Returns the total frame size in bytes, including every plane.
     #[must_use]
     pub fn frame_size(&self) -> usize {
-        self.stride as usize * self.height as usize
+        self.format
+            .frame_size(self.stride as usize, self.height as usize)
     }
 
     /// Returns the age of this frame (time since capture completed).
@@ -105,28 +182,57 @@ impl FrameMetadata {
 }
 
 /// A captured frame with pixel data.
+///
+/// Clones share the pixel data and the results of [`Self::convert_to`].
 #[derive(Debug, Clone)]
 pub struct CaptureFrame {
     /// Frame metadata.
     pub metadata: FrameMetadata,
     /// Raw pixel data (format specified in metadata).
     data: Arc<Vec<u8>>,
+    /// Conversions done so far, dropped with the last clone.
+    conversions: Arc<ConversionCache>,
+}
+
+/// Converted copies of one frame, by target format.
+///
+/// Each format has its own cell, so concurrent requests for one format
+/// convert once while other formats convert in parallel.
+#[derive(Default)]
+struct ConversionCache {
+    formats: Mutex<HashMap<FrameFormat, Arc<OnceLock<Option<CaptureFrame>>>>>,
+}
+
+impl ConversionCache {
+    /// Returns the cell for `format`, creating an empty one if needed.
+    fn cell(&self, format: FrameFormat) -> Arc<OnceLock<Option<CaptureFrame>>> {
+        let mut formats = self.formats.lock().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(formats.entry(format).or_default())
+    }
+}
+
+impl std::fmt::Debug for ConversionCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let formats = self.formats.lock().unwrap_or_else(PoisonError::into_inner);
+        f.debug_set().entries(formats.keys()).finish()
+    }
 }
 
 impl CaptureFrame {
     /// Creates a new capture frame.
     #[must_use]
     pub fn new(metadata: FrameMetadata, data: Vec<u8>) -> Self {
-        Self {
-            metadata,
-            data: Arc::new(data),
-        }
+        Self::with_shared_data(metadata, Arc::new(data))
     }
 
     /// Creates a frame with shared data (zero-copy clone).
     #[must_use]
     pub fn with_shared_data(metadata: FrameMetadata, data: Arc<Vec<u8>>) -> Self {
-        Self { metadata, data }
+        Self {
+            metadata,
+            data,
+            conversions: Arc::default(),
+        }
     }
 
     /// Returns a reference to the pixel data.
@@ -165,8 +271,54 @@ impl CaptureFrame {
         self.metadata.age() < threshold
     }
 
+    /// Returns the pixel bytes of scanline `y`, without stride padding.
+    ///
+    /// For planar formats this is the row of the first (luma) plane.
+    /// Returns `None` if `y` is past the last row, the stride is shorter
+    /// than a row, or the data is truncated.
+    #[must_use]
+    pub fn row(&self, y: u32) -> Option<&[u8]> {
+        if y >= self.metadata.height {
+            return None;
+        }
+        let row_bytes = self.metadata.width as usize * self.metadata.format.bytes_per_pixel();
+        let stride = self.metadata.stride as usize;
+        if stride < row_bytes {
+            return None;
+        }
+        let start = y as usize * stride;
+        self.data.get(start..start + row_bytes)
+    }
+
+    /// Returns an iterator over the scanlines, top row first.
+    ///
+    /// Stops early at the first row the data does not cover; see
+    /// [`Self::row`].
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        (0..self.metadata.height).map_while(|y| self.row(y))
+    }
+
+    /// Returns the pixel at (`x`, `y`) as 8-bit RGBA.
+    ///
+    /// Decoded the same way as [`Self::to_rgba8`]. Returns `None` outside
+    /// the frame, for truncated data, and for planar formats.
+    #[must_use]
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        let format = self.metadata.format;
+        if format.is_planar() || x >= self.metadata.width {
+            return None;
+        }
+        let bpp = format.bytes_per_pixel();
+        let start = x as usize * bpp;
+        let px = self.row(y)?.get(start..start + bpp)?;
+        Some(decode_rgba(format, px))
+    }
+
     /// Converts the frame to a different format (CPU-based).
     ///
+    /// The first request for a format converts; later ones, from this
+    /// frame or any clone of it, return a frame sharing that buffer.
+    ///
     /// Returns `None` if conversion is not supported.
     #[must_use]
     pub fn convert_to(&self, target_format: FrameFormat) -> Option<Self> {
@@ -174,6 +326,14 @@ impl CaptureFrame {
             return Some(self.clone());
         }
 
+        self.conversions
+            .cell(target_format)
+            .get_or_init(|| self.convert_uncached(target_format))
+            .clone()
+    }
+
+    /// Does the conversion behind [`Self::convert_to`].
+    fn convert_uncached(&self, target_format: FrameFormat) -> Option<Self> {
         // Basic BGRA <-> RGBA conversion
         let converted_data = match (self.metadata.format, target_format) {
             (FrameFormat::Bgra8888, FrameFormat::Rgba8888)
@@ -193,6 +353,352 @@ impl CaptureFrame {
 
         Some(Self::new(new_metadata, converted_data))
     }
+
+    /// Returns the pixels as tightly packed 8-bit RGBA, top row first.
+    ///
+    /// Stride padding is dropped. Formats without alpha, including the X
+    /// bits of XRGB/XBGR, come out fully opaque. 10-bit channels are
+    /// truncated to 8 bits.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Encode`] if the pixel data is shorter than
+    /// the metadata describes, or the format is planar.
+    pub fn to_rgba8(&self) -> CaptureResult<Vec<u8>> {
+        let format = self.metadata.format;
+        if format.is_planar() {
+            return Err(CaptureError::Encode(format!(
+                "conversion from planar {format} is not supported"
+            )));
+        }
+        let bpp = format.bytes_per_pixel();
+        let width = self.metadata.width as usize;
+        let height = self.metadata.height as usize;
+        let stride = self.metadata.stride as usize;
+        let row_bytes = width * bpp;
+
+        if row_bytes == 0 || height == 0 {
+            return Ok(Vec::new());
+        }
+        if stride < row_bytes || self.data.len() < stride * (height - 1) + row_bytes {
+            return Err(CaptureError::Encode(format!(
+                "{width}x{height} {format} frame with stride {stride} needs more than {} bytes",
+                self.data.len()
+            )));
+        }
+
+        let mut rgba = Vec::with_capacity(width * height * 4);
+        for row in self.rows() {
+            for px in row.chunks_exact(bpp) {
+                rgba.extend_from_slice(&decode_rgba(format, px));
+            }
+        }
+        Ok(rgba)
+    }
+
+    /// Returns the pixels as tightly packed 8-bit RGB, dropping any alpha.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::to_rgba8`].
+    pub fn to_rgb8(&self) -> CaptureResult<Vec<u8>> {
+        Ok(self
+            .to_rgba8()?
+            .chunks_exact(4)
+            .flat_map(|px| [px[0], px[1], px[2]])
+            .collect())
+    }
+
+    /// Returns the pixels inside `region` as a new, tightly packed frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::InvalidRequest`] if `region` is empty or
+    /// extends past the frame, and [`CaptureError::Encode`] for planar
+    /// formats or truncated pixel data.
+    pub fn crop(&self, region: Rect) -> CaptureResult<Self> {
+        let (width, height) = (self.metadata.width, self.metadata.height);
+        if !region.fits_within(width, height) {
+            return Err(CaptureError::InvalidRequest(format!(
+                "region {region} is outside the {width}x{height} frame"
+            )));
+        }
+        let (bpp, stride) = self.packed_layout()?;
+
+        let x = region.x as usize * bpp;
+        let row_bytes = region.width as usize * bpp;
+        let mut data = Vec::with_capacity(row_bytes * region.height as usize);
+        for row in self
+            .data
+            .chunks(stride)
+            .skip(region.y as usize)
+            .take(region.height as usize)
+        {
+            data.extend_from_slice(&row[x..x + row_bytes]);
+        }
+
+        Ok(Self::new(
+            self.resized_metadata(region.width, region.height),
+            data,
+        ))
+    }
+
+    /// Downscales the frame by `scale` using nearest-neighbour sampling.
+    ///
+    /// Each dimension is rounded and kept at least one pixel. A scale of
+    /// `1.0` returns a tightly packed copy.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::InvalidRequest`] unless `0.0 < scale <= 1.0`,
+    /// and [`CaptureError::Encode`] for planar formats or truncated pixel
+    /// data.
+    pub fn downscale(&self, scale: f32) -> CaptureResult<Self> {
+        if !(scale > 0.0 && scale <= 1.0) {
+            return Err(CaptureError::InvalidRequest(format!(
+                "scale {scale} is not in (0, 1]"
+            )));
+        }
+        let (bpp, stride) = self.packed_layout()?;
+
+        let new_width = scaled_dimension(self.metadata.width, scale);
+        let new_height = scaled_dimension(self.metadata.height, scale);
+
+        let (width, height) = (self.metadata.width as usize, self.metadata.height as usize);
+        let (dst_width, dst_height) = (new_width as usize, new_height as usize);
+        let mut data = Vec::with_capacity(dst_width * dst_height * bpp);
+        for dy in 0..dst_height {
+            let row = &self.data[dy * height / dst_height * stride..];
+            for dx in 0..dst_width {
+                let sx = dx * width / dst_width;
+                data.extend_from_slice(&row[sx * bpp..(sx + 1) * bpp]);
+            }
+        }
+
+        Ok(Self::new(
+            self.resized_metadata(new_width, new_height),
+            data,
+        ))
+    }
+
+    /// Applies `transform` to a frame captured in the output's native
+    /// orientation, returning it upright as a tightly packed copy.
+    ///
+    /// Quarter turns swap width and height. The copy records the transform
+    /// as applied by [`TransformHandling::Cpu`], so clients do not apply
+    /// it again. A normal transform returns the frame as is.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Encode`] for planar formats, which must be
+    /// transformed by the compositor, and for truncated pixel data.
+    pub fn apply_transform(&self, transform: OutputTransform) -> CaptureResult<Self> {
+        if transform == OutputTransform::Normal {
+            return Ok(self.clone());
+        }
+        let (bpp, stride) = self.packed_layout()?;
+
+        let (width, height) = (self.metadata.width, self.metadata.height);
+        let (new_width, new_height) = transform.apply_to_size(width, height);
+        let (width, height) = (width as usize, height as usize);
+        let mut data = Vec::with_capacity(new_width as usize * new_height as usize * bpp);
+        for y in 0..new_height as usize {
+            for x in 0..new_width as usize {
+                let (sx, sy) = transformed_source(transform, x, y, width, height);
+                let offset = sy * stride + sx * bpp;
+                data.extend_from_slice(&self.data[offset..offset + bpp]);
+            }
+        }
+
+        let mut metadata = self.resized_metadata(new_width, new_height);
+        metadata.transform = transform;
+        metadata.transform_handling = TransformHandling::Cpu;
+        Ok(Self::new(metadata, data))
+    }
+
+    /// Bytes per pixel and stride of a packed frame whose data covers
+    /// every row the metadata describes.
+    fn packed_layout(&self) -> CaptureResult<(usize, usize)> {
+        let format = self.metadata.format;
+        if format.is_planar() {
+            return Err(CaptureError::Encode(format!(
+                "resampling planar {format} is not supported"
+            )));
+        }
+        let bpp = format.bytes_per_pixel();
+        let height = self.metadata.height as usize;
+        let stride = self.metadata.stride as usize;
+        let row_bytes = self.metadata.width as usize * bpp;
+
+        if height > 0 && (stride < row_bytes || self.data.len() < stride * (height - 1) + row_bytes)
+        {
+            return Err(CaptureError::Encode(format!(
+                "{}x{height} {format} frame with stride {stride} needs more than {} bytes",
+                self.metadata.width,
+                self.data.len()
+            )));
+        }
+        Ok((bpp, stride))
+    }
+
+    /// Metadata for a tightly packed copy of this frame at a new size.
+    fn resized_metadata(&self, width: u32, height: u32) -> FrameMetadata {
+        let mut metadata = self.metadata.clone();
+        metadata.width = width;
+        metadata.height = height;
+        #[allow(clippy::cast_possible_truncation)] // at most 4 bytes per pixel
+        let bpp = metadata.format.bytes_per_pixel() as u32;
+        metadata.stride = width * bpp;
+        metadata
+    }
+}
+
+/// Decodes one pixel of a packed `format` to 8-bit RGBA.
+///
+/// DRM formats are little-endian, so RGB888 is B, G, R in memory; X bytes
+/// are ignored.
+fn decode_rgba(format: FrameFormat, px: &[u8]) -> [u8; 4] {
+    match format {
+        FrameFormat::Bgra8888 => [px[2], px[1], px[0], px[3]],
+        FrameFormat::Rgba8888 => [px[0], px[1], px[2], px[3]],
+        FrameFormat::Xrgb8888 | FrameFormat::Rgb888 => [px[2], px[1], px[0], u8::MAX],
+        FrameFormat::Xbgr8888 | FrameFormat::Bgr888 => [px[0], px[1], px[2], u8::MAX],
+        FrameFormat::Xrgb2101010 => {
+            let v = u32::from_le_bytes([px[0], px[1], px[2], px[3]]);
+            // Keep the top 8 of each channel's 10 bits
+            let channel = |shift: u32| (v >> (shift + 2)).to_le_bytes()[0];
+            [channel(20), channel(10), channel(0), u8::MAX]
+        },
+        FrameFormat::Nv12 => unreachable!("planar formats have no packed pixels"),
+    }
+}
+
+/// Source pixel of a `width` x `height` buffer that lands at `(x, y)` once
+/// `transform` is applied: mirrored first if flipped, then rotated
+/// counter-clockwise.
+fn transformed_source(
+    transform: OutputTransform,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+) -> (usize, usize) {
+    let (fx, fy) = match transform {
+        OutputTransform::Normal | OutputTransform::Flipped => (x, y),
+        OutputTransform::Rotate90 | OutputTransform::Flipped90 => (width - 1 - y, x),
+        OutputTransform::Rotate180 | OutputTransform::Flipped180 => (width - 1 - x, height - 1 - y),
+        OutputTransform::Rotate270 | OutputTransform::Flipped270 => (y, height - 1 - x),
+    };
+    if transform.is_flipped() {
+        (width - 1 - fx, fy)
+    } else {
+        (fx, fy)
+    }
+}
+
+/// `dimension * scale`, rounded and at least 1 (`scale` is in (0, 1]).
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub(super) fn scaled_dimension(dimension: u32, scale: f32) -> u32 {
+    ((f64::from(dimension) * f64::from(scale)).round() as u32).max(1)
+}
+
+/// A rectangle in frame pixel coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rect {
+    /// Left edge.
+    pub x: u32,
+    /// Top edge.
+    pub y: u32,
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+}
+
+impl Rect {
+    /// Creates a rectangle.
+    #[must_use]
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns true if the rectangle is non-empty and lies entirely within
+    /// a `width` x `height` frame.
+    #[must_use]
+    pub fn fits_within(&self, width: u32, height: u32) -> bool {
+        self.width > 0
+            && self.height > 0
+            && u64::from(self.x) + u64::from(self.width) <= u64::from(width)
+            && u64::from(self.y) + u64::from(self.height) <= u64::from(height)
+    }
+}
+
+impl std::fmt::Display for Rect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}+{}+{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+#[cfg(feature = "image-encode")]
+impl CaptureFrame {
+    /// Encodes the frame as PNG.
+    ///
+    /// Formats with alpha are written as RGBA, everything else as RGB.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Encode`] if the pixel data is truncated or
+    /// the encoder rejects the image.
+    pub fn to_png(&self) -> CaptureResult<Vec<u8>> {
+        use image::codecs::png::PngEncoder;
+
+        let (pixels, color) = if self.format().has_alpha() {
+            (self.to_rgba8()?, image::ExtendedColorType::Rgba8)
+        } else {
+            (self.to_rgb8()?, image::ExtendedColorType::Rgb8)
+        };
+
+        let mut png = Vec::new();
+        self.encode(PngEncoder::new(&mut png), &pixels, color)?;
+        Ok(png)
+    }
+
+    /// Encodes the frame as JPEG at `quality` (1-100).
+    ///
+    /// JPEG has no alpha channel, so alpha is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::Encode`] if the pixel data is truncated or
+    /// the encoder rejects the image (e.g. dimensions above 65535).
+    pub fn to_jpeg(&self, quality: u8) -> CaptureResult<Vec<u8>> {
+        use image::codecs::jpeg::JpegEncoder;
+
+        let pixels = self.to_rgb8()?;
+        let mut jpeg = Vec::new();
+        self.encode(
+            JpegEncoder::new_with_quality(&mut jpeg, quality.clamp(1, 100)),
+            &pixels,
+            image::ExtendedColorType::Rgb8,
+        )?;
+        Ok(jpeg)
+    }
+
+    fn encode(
+        &self,
+        encoder: impl image::ImageEncoder,
+        pixels: &[u8],
+        color: image::ExtendedColorType,
+    ) -> CaptureResult<()> {
+        encoder
+            .write_image(pixels, self.width(), self.height(), color)
+            .map_err(|e| CaptureError::Encode(e.to_string()))
+    }
 }
 
 /// Builder for creating frame metadata.
@@ -206,6 +712,9 @@ pub struct FrameMetadataBuilder {
     capture_start: Option<Instant>,
     pipewire_node: Option<u32>,
     output_index: u32,
+    output_name: Option<String>,
+    transform: OutputTransform,
+    transform_handling: TransformHandling,
 }
 
 impl FrameMetadataBuilder {
@@ -265,6 +774,21 @@ impl FrameMetadataBuilder {
         self
     }
 
+    /// Sets the output name.
+    #[must_use]
+    pub fn output_name(mut self, name: impl Into<String>) -> Self {
+        self.output_name = Some(name.into());
+        self
+    }
+
+    /// Sets the transform of the captured output, and who applied it.
+    #[must_use]
+    pub fn transform(mut self, transform: OutputTransform, handling: TransformHandling) -> Self {
+        self.transform = transform;
+        self.transform_handling = handling;
+        self
+    }
+
     /// Builds the metadata, marking capture as complete.
     #[must_use]
     pub fn build(self) -> FrameMetadata {
@@ -284,6 +808,9 @@ impl FrameMetadataBuilder {
             capture_end: Instant::now(),
             pipewire_node: self.pipewire_node,
             output_index: self.output_index,
+            output_name: self.output_name,
+            transform: self.transform,
+            transform_handling: self.transform_handling,
         }
     }
 }
@@ -300,6 +827,21 @@ mod tests {
         assert_eq!(FrameFormat::Xbgr8888.bytes_per_pixel(), 4);
         assert_eq!(FrameFormat::Rgb888.bytes_per_pixel(), 3);
         assert_eq!(FrameFormat::Bgr888.bytes_per_pixel(), 3);
+        assert_eq!(FrameFormat::Xrgb2101010.bytes_per_pixel(), 4);
+        assert_eq!(FrameFormat::Nv12.bytes_per_pixel(), 1);
+    }
+
+    #[test]
+    fn frame_format_planes() {
+        assert!(FrameFormat::Nv12.is_planar());
+        assert_eq!(FrameFormat::Nv12.plane_count(), 2);
+        assert!(!FrameFormat::Xrgb2101010.is_planar());
+        assert_eq!(FrameFormat::Bgra8888.plane_count(), 1);
+
+        assert_eq!(FrameFormat::Bgra8888.frame_size(16, 4), 64);
+        assert_eq!(FrameFormat::Nv12.frame_size(4, 4), 24);
+        // odd heights round the chroma plane up
+        assert_eq!(FrameFormat::Nv12.frame_size(4, 3), 20);
     }
 
     #[test]
@@ -310,12 +852,29 @@ mod tests {
         assert!(!FrameFormat::Xbgr8888.has_alpha());
         assert!(!FrameFormat::Rgb888.has_alpha());
         assert!(!FrameFormat::Bgr888.has_alpha());
+        assert!(!FrameFormat::Xrgb2101010.has_alpha());
+        assert!(!FrameFormat::Nv12.has_alpha());
     }
 
     #[test]
     fn frame_format_fourcc() {
         assert_eq!(FrameFormat::Bgra8888.fourcc(), 0x3432_4742);
         assert_eq!(FrameFormat::Rgba8888.fourcc(), 0x3432_4152);
+        assert_eq!(FrameFormat::Xrgb2101010.fourcc(), 0x3033_5258);
+        assert_eq!(FrameFormat::Nv12.fourcc(), 0x3231_564E);
+    }
+
+    #[test]
+    fn frame_format_from_fourcc() {
+        assert_eq!(
+            FrameFormat::from_fourcc(0x3231_564E),
+            Some(FrameFormat::Nv12)
+        );
+        assert_eq!(
+            FrameFormat::from_fourcc(FrameFormat::Xrgb2101010.fourcc()),
+            Some(FrameFormat::Xrgb2101010)
+        );
+        assert_eq!(FrameFormat::from_fourcc(0x5659_5559), None);
     }
 
     #[test]
@@ -326,6 +885,8 @@ mod tests {
         assert_eq!(FrameFormat::Xbgr8888.to_string(), "XBGR8888");
         assert_eq!(FrameFormat::Rgb888.to_string(), "RGB888");
         assert_eq!(FrameFormat::Bgr888.to_string(), "BGR888");
+        assert_eq!(FrameFormat::Xrgb2101010.to_string(), "XRGB2101010");
+        assert_eq!(FrameFormat::Nv12.to_string(), "NV12");
     }
 
     #[test]
@@ -356,6 +917,7 @@ mod tests {
         assert_eq!(metadata.stride, 100 * 4);
         assert_eq!(metadata.output_index, 0);
         assert!(metadata.pipewire_node.is_none());
+        assert!(metadata.output_name.is_none());
     }
 
     #[test]
@@ -367,6 +929,7 @@ mod tests {
             .format(FrameFormat::Rgba8888)
             .pipewire_node(42)
             .output_index(1)
+            .output_name("DP-2")
             .build();
 
         assert_eq!(metadata.sequence, 100);
@@ -376,6 +939,7 @@ mod tests {
         assert_eq!(metadata.format, FrameFormat::Rgba8888);
         assert_eq!(metadata.pipewire_node, Some(42));
         assert_eq!(metadata.output_index, 1);
+        assert_eq!(metadata.output_name.as_deref(), Some("DP-2"));
     }
 
     #[test]
@@ -461,6 +1025,35 @@ mod tests {
         assert_eq!(converted.data()[2], 0); // Was Red, now Blue position
     }
 
+    #[test]
+    fn frame_conversion_is_cached_per_frame() {
+        let metadata = FrameMetadataBuilder::new()
+            .dimensions(2, 1)
+            .format(FrameFormat::Bgra8888)
+            .build();
+        let frame = CaptureFrame::new(metadata, vec![0, 1, 2, 255, 10, 11, 12, 255]);
+
+        let first = frame.convert_to(FrameFormat::Rgba8888).unwrap();
+        // Clones share the cache, across threads too
+        let clone = frame.clone();
+        let second = std::thread::spawn(move || clone.convert_to(FrameFormat::Rgba8888))
+            .join()
+            .unwrap()
+            .unwrap();
+        assert!(Arc::ptr_eq(&first.shared_data(), &second.shared_data()));
+        assert_eq!(second.data(), [2, 1, 0, 255, 12, 11, 10, 255]);
+
+        // A new frame over the same pixels has its own cache
+        let other = CaptureFrame::with_shared_data(frame.metadata.clone(), frame.shared_data());
+        let third = other.convert_to(FrameFormat::Rgba8888).unwrap();
+        assert!(!Arc::ptr_eq(&first.shared_data(), &third.shared_data()));
+
+        // The conversion goes away with the frame
+        let converted = Arc::downgrade(&first.shared_data());
+        drop((frame, first, second));
+        assert!(converted.upgrade().is_none());
+    }
+
     #[test]
     fn frame_conversion_same_format() {
         let metadata = FrameMetadataBuilder::new()
@@ -535,4 +1128,281 @@ mod tests {
         assert_send_sync::<FrameMetadata>();
         assert_send_sync::<CaptureFrame>();
     }
+
+    fn pattern_frame(format: FrameFormat, data: Vec<u8>) -> CaptureFrame {
+        pattern_frame_sized(format, 2, 2, data)
+    }
+
+    fn pattern_frame_sized(
+        format: FrameFormat,
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+    ) -> CaptureFrame {
+        let metadata = FrameMetadataBuilder::new()
+            .dimensions(width, height)
+            .format(format)
+            .build();
+        CaptureFrame::new(metadata, data)
+    }
+
+    /// Red, green / blue, half-transparent white.
+    const PATTERN_RGBA: [u8; 16] = [
+        255, 0, 0, 255, 0, 255, 0, 255, //
+        0, 0, 255, 255, 255, 255, 255, 128,
+    ];
+
+    #[test]
+    fn to_rgba8_handles_channel_order() {
+        let bgra = pattern_frame(
+            FrameFormat::Bgra8888,
+            vec![
+                0, 0, 255, 255, 0, 255, 0, 255, 255, 0, 0, 255, 255, 255, 255, 128,
+            ],
+        );
+        assert_eq!(bgra.to_rgba8().unwrap(), PATTERN_RGBA);
+
+        let rgba = pattern_frame(FrameFormat::Rgba8888, PATTERN_RGBA.to_vec());
+        assert_eq!(rgba.to_rgba8().unwrap(), PATTERN_RGBA);
+    }
+
+    #[test]
+    fn to_rgba8_treats_x_byte_as_opaque() {
+        let xrgb = pattern_frame(
+            FrameFormat::Xrgb8888,
+            vec![0, 0, 255, 0, 0, 255, 0, 7, 255, 0, 0, 0, 255, 255, 255, 0],
+        );
+        let xbgr = pattern_frame(
+            FrameFormat::Xbgr8888,
+            vec![255, 0, 0, 0, 0, 255, 0, 7, 0, 0, 255, 0, 255, 255, 255, 0],
+        );
+
+        let mut opaque = PATTERN_RGBA;
+        opaque[15] = 255;
+        assert_eq!(xrgb.to_rgba8().unwrap(), opaque);
+        assert_eq!(xbgr.to_rgba8().unwrap(), opaque);
+    }
+
+    #[test]
+    fn to_rgba8_skips_stride_padding() {
+        let metadata = FrameMetadataBuilder::new()
+            .dimensions(2, 2)
+            .stride(8)
+            .format(FrameFormat::Rgb888)
+            .build();
+        let data = vec![
+            0, 0, 255, 0, 255, 0, 9, 9, //
+            255, 0, 0, 255, 255, 255, 9, 9,
+        ];
+        let frame = CaptureFrame::new(metadata, data);
+
+        let mut opaque = PATTERN_RGBA;
+        opaque[15] = 255;
+        assert_eq!(frame.to_rgba8().unwrap(), opaque);
+
+        let bgr = pattern_frame(
+            FrameFormat::Bgr888,
+            vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255],
+        );
+        assert_eq!(bgr.to_rgb8().unwrap(), frame.to_rgb8().unwrap());
+    }
+
+    #[test]
+    fn to_rgba8_truncates_10bit_channels() {
+        let pixels = [
+            0x3ff_u32 << 20,
+            0x3ff << 10,
+            0x3ff,
+            (0x3ff << 20) | (0x3ff << 10) | 0x3ff,
+        ];
+        let data = pixels.iter().flat_map(|p| p.to_le_bytes()).collect();
+        let frame = pattern_frame(FrameFormat::Xrgb2101010, data);
+
+        let mut opaque = PATTERN_RGBA;
+        opaque[15] = 255;
+        assert_eq!(frame.to_rgba8().unwrap(), opaque);
+    }
+
+    #[test]
+    fn pixel_reads_bgra_through_stride() {
+        // 2x2 BGRA with 4 bytes of padding per row
+        let metadata = FrameMetadataBuilder::new()
+            .dimensions(2, 2)
+            .stride(12)
+            .format(FrameFormat::Bgra8888)
+            .build();
+        let data = vec![
+            0, 0, 255, 255, 0, 255, 0, 255, 9, 9, 9, 9, //
+            255, 0, 0, 255, 255, 255, 255, 128, 9, 9, 9, 9,
+        ];
+        let frame = CaptureFrame::new(metadata, data);
+
+        assert_eq!(frame.pixel(0, 0), Some([255, 0, 0, 255]));
+        assert_eq!(frame.pixel(1, 0), Some([0, 255, 0, 255]));
+        assert_eq!(frame.pixel(0, 1), Some([0, 0, 255, 255]));
+        assert_eq!(frame.pixel(1, 1), Some([255, 255, 255, 128]));
+        assert_eq!(frame.pixel(2, 0), None);
+        assert_eq!(frame.pixel(0, 2), None);
+
+        assert_eq!(
+            frame.row(1),
+            Some(&[255, 0, 0, 255, 255, 255, 255, 128][..])
+        );
+        assert_eq!(frame.row(2), None);
+        assert_eq!(frame.rows().count(), 2);
+        assert!(frame.rows().all(|row| row.len() == 8));
+    }
+
+    #[test]
+    fn pixel_access_rejects_planar_and_truncated_frames() {
+        let nv12 = pattern_frame_sized(FrameFormat::Nv12, 4, 4, (0..24).collect());
+        assert_eq!(nv12.pixel(0, 0), None);
+        assert_eq!(nv12.row(3), Some(&[12, 13, 14, 15][..]));
+
+        let truncated = pattern_frame(FrameFormat::Bgra8888, vec![0; 12]);
+        assert!(truncated.pixel(1, 0).is_some());
+        assert_eq!(truncated.pixel(0, 1), None);
+        assert_eq!(truncated.rows().count(), 1);
+    }
+
+    #[test]
+    fn crop_copies_region_rows() {
+        // 4x2 RGBA frame with each pixel's red channel set to its index
+        let data = (0..8u8).flat_map(|i| [i, 0, 0, 255]).collect();
+        let frame = pattern_frame_sized(FrameFormat::Rgba8888, 4, 2, data);
+
+        let cropped = frame.crop(Rect::new(1, 0, 2, 2)).unwrap();
+        assert_eq!((cropped.width(), cropped.height()), (2, 2));
+        assert_eq!(cropped.metadata.stride, 8);
+        let reds: Vec<u8> = cropped.data().chunks(4).map(|px| px[0]).collect();
+        assert_eq!(reds, [1, 2, 5, 6]);
+    }
+
+    #[test]
+    fn crop_rejects_out_of_bounds_region() {
+        let frame = pattern_frame_sized(FrameFormat::Rgba8888, 4, 2, vec![0; 32]);
+        for region in [
+            Rect::new(3, 0, 2, 1),
+            Rect::new(0, 0, 4, 3),
+            Rect::new(0, 0, 0, 1),
+        ] {
+            assert!(matches!(
+                frame.crop(region),
+                Err(CaptureError::InvalidRequest(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn downscale_halves_dimensions() {
+        let data = (0..16u8).flat_map(|i| [i, 0, 0, 255]).collect();
+        let frame = pattern_frame_sized(FrameFormat::Rgba8888, 4, 4, data);
+
+        let half = frame.downscale(0.5).unwrap();
+        assert_eq!((half.width(), half.height()), (2, 2));
+        let reds: Vec<u8> = half.data().chunks(4).map(|px| px[0]).collect();
+        assert_eq!(reds, [0, 2, 8, 10]);
+
+        // Never collapses to zero pixels
+        let tiny = frame.downscale(0.01).unwrap();
+        assert_eq!((tiny.width(), tiny.height()), (1, 1));
+    }
+
+    #[test]
+    fn transform_turns_native_buffer_upright() {
+        // 3x2 RGBA frame with each pixel's red channel set to its index:
+        //   0 1 2
+        //   3 4 5
+        let data = (0..6u8).flat_map(|i| [i, 0, 0, 255]).collect();
+        let frame = pattern_frame_sized(FrameFormat::Rgba8888, 3, 2, data);
+        let reds = |f: &CaptureFrame| f.data().chunks(4).map(|px| px[0]).collect::<Vec<_>>();
+
+        let rotated = frame.apply_transform(OutputTransform::Rotate90).unwrap();
+        assert_eq!((rotated.width(), rotated.height()), (2, 3));
+        assert_eq!(rotated.metadata.stride, 8);
+        assert_eq!(reds(&rotated), [2, 5, 1, 4, 0, 3]);
+        assert_eq!(rotated.metadata.transform, OutputTransform::Rotate90);
+        assert_eq!(rotated.metadata.transform_handling, TransformHandling::Cpu);
+
+        let cases = [
+            (OutputTransform::Rotate180, [5, 4, 3, 2, 1, 0]),
+            (OutputTransform::Rotate270, [3, 0, 4, 1, 5, 2]),
+            (OutputTransform::Flipped, [2, 1, 0, 5, 4, 3]),
+            (OutputTransform::Flipped90, [0, 3, 1, 4, 2, 5]),
+        ];
+        for (transform, expected) in cases {
+            assert_eq!(reds(&frame.apply_transform(transform).unwrap()), expected);
+        }
+
+        let normal = frame.apply_transform(OutputTransform::Normal).unwrap();
+        assert_eq!(normal.metadata.transform_handling, TransformHandling::None);
+    }
+
+    #[test]
+    fn transform_leaves_planar_formats_to_compositor() {
+        let nv12 = pattern_frame_sized(FrameFormat::Nv12, 4, 4, vec![0; 24]);
+        assert!(matches!(
+            nv12.apply_transform(OutputTransform::Rotate90),
+            Err(CaptureError::Encode(_))
+        ));
+    }
+
+    #[test]
+    fn downscale_rejects_invalid_scale() {
+        let frame = pattern_frame_sized(FrameFormat::Rgba8888, 4, 4, vec![0; 64]);
+        for scale in [1.5, 0.0, -0.5, f32::NAN] {
+            assert!(matches!(
+                frame.downscale(scale),
+                Err(CaptureError::InvalidRequest(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn to_rgba8_rejects_planar_formats() {
+        let frame = pattern_frame(FrameFormat::Nv12, vec![0; 6]);
+        assert_eq!(frame.metadata.frame_size(), 6);
+        assert!(matches!(frame.to_rgba8(), Err(CaptureError::Encode(_))));
+    }
+
+    #[test]
+    fn to_rgba8_rejects_truncated_data() {
+        let frame = pattern_frame(FrameFormat::Bgra8888, vec![0; 12]);
+        assert!(matches!(frame.to_rgba8(), Err(CaptureError::Encode(_))));
+    }
+
+    #[cfg(feature = "image-encode")]
+    #[test]
+    fn png_round_trips_pattern() {
+        let frame = pattern_frame(FrameFormat::Rgba8888, PATTERN_RGBA.to_vec());
+        let png = frame.to_png().unwrap();
+
+        let decoded = image::load_from_memory_with_format(&png, image::ImageFormat::Png).unwrap();
+        assert_eq!(decoded.color(), image::ColorType::Rgba8);
+        assert_eq!(decoded.to_rgba8().into_raw(), PATTERN_RGBA);
+    }
+
+    #[cfg(feature = "image-encode")]
+    #[test]
+    fn png_of_opaque_format_is_rgb() {
+        let frame = pattern_frame(
+            FrameFormat::Xrgb8888,
+            vec![0, 0, 255, 0, 0, 255, 0, 0, 255, 0, 0, 0, 255, 255, 255, 0],
+        );
+        let png = frame.to_png().unwrap();
+
+        let decoded = image::load_from_memory_with_format(&png, image::ImageFormat::Png).unwrap();
+        assert_eq!(decoded.color(), image::ColorType::Rgb8);
+        assert_eq!(decoded.to_rgb8().into_raw(), frame.to_rgb8().unwrap());
+    }
+
+    #[cfg(feature = "image-encode")]
+    #[test]
+    fn jpeg_encodes_frame() {
+        let frame = pattern_frame(FrameFormat::Bgra8888, vec![128; 16]);
+        let jpeg = frame.to_jpeg(90).unwrap();
+
+        let decoded = image::load_from_memory_with_format(&jpeg, image::ImageFormat::Jpeg).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (2, 2));
+    }
 }
@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Smoothing frame arrival on the consumer side.
+//!
+//! The capture side paces frames, but the network does not keep that
+//! pacing: frames arrive in bunches, with gaps in between, and sometimes
+//! out of order. Presenting them as they arrive makes motion stutter. A
+//! [`JitterBuffer`] holds received frames and releases them at their
+//! presentation time plus a fixed delay, in sequence order, so output
+//! follows the sender's cadence as long as jitter stays below that delay.
+//!
+//! The delay is the trade-off: a longer one absorbs more jitter but adds
+//! as much latency. Frames that arrive after their release time, or after
+//! a later frame was already released, are dropped rather than shown out
+//! of order.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use tracing::debug;
+
+/// Tuning of a [`JitterBuffer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JitterConfig {
+    /// How long after its presentation time a frame is released
+    pub target_delay: Duration,
+    /// Frames held at most; past it the oldest is dropped
+    pub max_frames: usize,
+}
+
+impl Default for JitterConfig {
+    fn default() -> Self {
+        Self {
+            target_delay: Duration::from_millis(50),
+            max_frames: 16,
+        }
+    }
+}
+
+/// A frame waiting for its release time.
+#[derive(Debug)]
+struct Pending<T> {
+    release_at: Instant,
+    frame: T,
+}
+
+/// Reorders received frames and releases them on an even cadence.
+///
+/// Feed every frame to [`Self::push`] with its sequence number and
+/// presentation time, and call [`Self::pop`] until it returns `None`
+/// whenever [`Self::next_release`] has passed. Presentation times are on
+/// the sender's clock; only differences between them matter.
+#[derive(Debug)]
+pub struct JitterBuffer<T> {
+    config: JitterConfig,
+    /// Local time and presentation time of the first frame
+    anchor: Option<(Instant, Duration)>,
+    pending: BTreeMap<u64, Pending<T>>,
+    /// Sequence number of the last frame released
+    last_released: Option<u64>,
+    dropped: u64,
+}
+
+impl<T> JitterBuffer<T> {
+    /// Creates an empty buffer.
+    #[must_use]
+    pub fn new(config: JitterConfig) -> Self {
+        Self {
+            config,
+            anchor: None,
+            pending: BTreeMap::new(),
+            last_released: None,
+            dropped: 0,
+        }
+    }
+
+    /// Adds a received frame.
+    ///
+    /// The first frame fixes the mapping from presentation time to local
+    /// time. Returns `false` if the frame was dropped as late: its release
+    /// time has passed, a later frame was already released, or it repeats
+    /// a sequence number already held.
+    pub fn push(
+        &mut self,
+        sequence: u64,
+        presentation_time: Duration,
+        frame: T,
+        now: Instant,
+    ) -> bool {
+        if self.last_released.is_some_and(|last| sequence <= last)
+            || self.pending.contains_key(&sequence)
+        {
+            return self.drop_late(sequence);
+        }
+
+        let (anchor_local, anchor_pts) = *self.anchor.get_or_insert((now, presentation_time));
+        let slot = if presentation_time >= anchor_pts {
+            Some(anchor_local + presentation_time.saturating_sub(anchor_pts))
+        } else {
+            anchor_local.checked_sub(anchor_pts.saturating_sub(presentation_time))
+        };
+        let Some(release_at) = slot
+            .map(|slot| slot + self.config.target_delay)
+            .filter(|&release_at| release_at >= now)
+        else {
+            return self.drop_late(sequence);
+        };
+
+        self.pending.insert(sequence, Pending { release_at, frame });
+        if self.pending.len() > self.config.max_frames {
+            self.pending.pop_first();
+            self.dropped += 1;
+        }
+        true
+    }
+
+    /// Releases the next frame in sequence if its time has come.
+    ///
+    /// Gaps left by frames that never arrived are skipped once the next
+    /// frame is due.
+    pub fn pop(&mut self, now: Instant) -> Option<T> {
+        let entry = self.pending.first_entry()?;
+        if entry.get().release_at > now {
+            return None;
+        }
+        self.last_released = Some(*entry.key());
+        Some(entry.remove().frame)
+    }
+
+    /// When the next frame is due, if any is held.
+    #[must_use]
+    pub fn next_release(&self) -> Option<Instant> {
+        self.pending.values().next().map(|p| p.release_at)
+    }
+
+    /// Frames held.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether no frames are held.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Frames dropped as late or for lack of space.
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    fn drop_late(&mut self, sequence: u64) -> bool {
+        debug!(sequence, "Dropping late frame");
+        self.dropped += 1;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    /// Pops everything due at `now`, recording release times.
+    fn drain(buffer: &mut JitterBuffer<u64>, now: Instant, out: &mut Vec<(u64, Instant)>) {
+        while let Some(sequence) = buffer.pop(now) {
+            out.push((sequence, now));
+        }
+    }
+
+    /// Feeds `arrivals` (sequence, arrival offset) and polls every
+    /// millisecond, as a client sleeping on `next_release` would.
+    fn run(config: JitterConfig, arrivals: &[(u64, u64)]) -> (Vec<(u64, Instant)>, u64) {
+        let start = Instant::now();
+        let mut buffer = JitterBuffer::new(config);
+        let mut out = Vec::new();
+        let end = arrivals.iter().map(|&(_, at)| at).max().unwrap_or(0) + 200;
+        for ms in 0..=end {
+            let now = start + Duration::from_millis(ms);
+            for &(sequence, _) in arrivals.iter().filter(|&&(_, at)| at == ms) {
+                let pts = FRAME * u32::try_from(sequence).unwrap();
+                buffer.push(sequence, pts, sequence, now);
+            }
+            drain(&mut buffer, now, &mut out);
+        }
+        (out, buffer.dropped())
+    }
+
+    #[test]
+    fn reorders_and_paces_jittery_arrivals() {
+        // Sent every 16ms; arrivals bunch up and 2 overtakes 1
+        let arrivals = [(0, 0), (2, 30), (1, 33), (3, 49), (4, 50), (5, 81)];
+        let (out, dropped) = run(JitterConfig::default(), &arrivals);
+
+        let sequences: Vec<u64> = out.iter().map(|&(s, _)| s).collect();
+        assert_eq!(sequences, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(dropped, 0);
+        for pair in out.windows(2) {
+            assert_eq!(pair[1].1 - pair[0].1, FRAME);
+        }
+    }
+
+    #[test]
+    fn drops_frames_later_than_target_delay() {
+        let config = JitterConfig {
+            target_delay: Duration::from_millis(20),
+            ..JitterConfig::default()
+        };
+        // 1 is due at 36ms but arrives at 60ms, after 2 was released
+        let arrivals = [(0, 0), (2, 40), (1, 60), (3, 50)];
+        let (out, dropped) = run(config, &arrivals);
+
+        let sequences: Vec<u64> = out.iter().map(|&(s, _)| s).collect();
+        assert_eq!(sequences, [0, 2, 3]);
+        assert_eq!(dropped, 1);
+    }
+
+    #[test]
+    fn drops_frames_past_their_release_time() {
+        let start = Instant::now();
+        let mut buffer = JitterBuffer::new(JitterConfig::default());
+        assert!(buffer.push(0, Duration::ZERO, 0, start));
+
+        // Due at 66ms, arriving at 100ms
+        let now = start + Duration::from_millis(100);
+        assert!(!buffer.push(1, FRAME, 1, now));
+        assert!(!buffer.push(0, Duration::ZERO, 0, now));
+        assert_eq!(buffer.dropped(), 2);
+        assert_eq!(buffer.pop(now), Some(0));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn bounds_held_frames() {
+        let config = JitterConfig {
+            target_delay: Duration::from_secs(1),
+            max_frames: 2,
+        };
+        let now = Instant::now();
+        let mut buffer = JitterBuffer::new(config);
+        for sequence in 0..3 {
+            buffer.push(
+                sequence,
+                FRAME * u32::try_from(sequence).unwrap(),
+                sequence,
+                now,
+            );
+        }
+
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.dropped(), 1);
+        assert_eq!(
+            buffer.next_release(),
+            Some(now + config.target_delay + FRAME)
+        );
+    }
+}
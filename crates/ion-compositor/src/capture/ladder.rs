@@ -0,0 +1,304 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Bandwidth-driven resolution scaling.
+//!
+//! Lowering frame rate and bitrate only goes so far: below some bitrate
+//! every frame at full resolution turns to mush and latency climbs. On such
+//! links a smaller picture at acceptable quality stays more usable.
+//! [`ResolutionLadder`] steps down through standard heights (1080p, 720p,
+//! 480p) while the available bandwidth cannot carry the current resolution
+//! even at minimum quality, and back up once there is headroom again.
+//!
+//! Both directions only move after the condition has held for a while, and
+//! stepping up needs more bandwidth than staying, so a link hovering around
+//! one threshold does not flap between sizes.
+
+use std::time::{Duration, Instant};
+
+use ion_core::backend::CaptureHandle;
+use tracing::info;
+
+use super::frame::scaled_dimension;
+use super::{CaptureFrame, CaptureResult};
+
+/// Heights the ladder descends through, below the source height.
+pub const STANDARD_HEIGHTS: [u32; 3] = [1080, 720, 480];
+
+/// Thresholds of a [`ResolutionLadder`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LadderConfig {
+    /// Bits per pixel per frame at the lowest quality the encoder accepts
+    pub min_bits_per_pixel: f64,
+    /// Factor by which bandwidth must exceed the next larger rung's
+    /// minimum before stepping up to it
+    pub up_headroom: f64,
+    /// How long the current rung must be unaffordable before stepping down
+    pub down_after: Duration,
+    /// How long the headroom must last before stepping up
+    pub up_after: Duration,
+}
+
+impl Default for LadderConfig {
+    fn default() -> Self {
+        Self {
+            min_bits_per_pixel: 0.02,
+            up_headroom: 1.5,
+            down_after: Duration::from_secs(2),
+            up_after: Duration::from_secs(10),
+        }
+    }
+}
+
+/// The ladder moved to another resolution.
+///
+/// The client must re-allocate its decoder surfaces; a keyframe at the
+/// new size follows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Resized {
+    /// New frame width
+    pub width: u32,
+    /// New frame height
+    pub height: u32,
+    /// New size relative to the source
+    pub scale: f32,
+}
+
+/// Direction the ladder is waiting to move in, and since when.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trend {
+    Down(Instant),
+    Up(Instant),
+}
+
+/// Resolutions a stream may be scaled to, and the one in use.
+#[derive(Debug)]
+pub struct ResolutionLadder {
+    source: (u32, u32),
+    /// Scales relative to the source, largest first
+    rungs: Vec<f32>,
+    current: usize,
+    config: LadderConfig,
+    trend: Option<Trend>,
+    keyframes: Option<CaptureHandle>,
+}
+
+impl ResolutionLadder {
+    /// Creates a ladder for a `width`×`height` source.
+    ///
+    /// The source size is the top rung, followed by every
+    /// [`STANDARD_HEIGHTS`] entry smaller than it.
+    #[must_use]
+    pub fn new(width: u32, height: u32, config: LadderConfig) -> Self {
+        let height = height.max(1);
+        let mut rungs = vec![1.0];
+        #[allow(clippy::cast_possible_truncation)] // ratios in (0, 1)
+        rungs.extend(
+            STANDARD_HEIGHTS
+                .into_iter()
+                .filter(|&h| h < height)
+                .map(|h| (f64::from(h) / f64::from(height)) as f32),
+        );
+        Self {
+            source: (width, height),
+            rungs,
+            current: 0,
+            config,
+            trend: None,
+            keyframes: None,
+        }
+    }
+
+    /// Requests a keyframe on `handle`'s stream whenever the resolution
+    /// changes.
+    #[must_use]
+    pub fn with_keyframe_handle(mut self, handle: CaptureHandle) -> Self {
+        self.keyframes = Some(handle);
+        self
+    }
+
+    /// Returns the scale of the current rung relative to the source.
+    #[must_use]
+    pub fn scale(&self) -> f32 {
+        self.rungs[self.current]
+    }
+
+    /// Returns the current frame size.
+    #[must_use]
+    pub fn resolution(&self) -> (u32, u32) {
+        self.dimensions(self.current)
+    }
+
+    /// Bits per second the current resolution needs at minimum quality.
+    #[must_use]
+    pub fn min_bitrate(&self, fps: u32) -> f64 {
+        self.rung_min_bitrate(self.current, fps)
+    }
+
+    /// Feeds the bandwidth the congestion controller can currently afford.
+    ///
+    /// Call on every bitrate update. Returns the new size when the ladder
+    /// moves, after requesting a keyframe if a handle was given.
+    pub fn update(&mut self, available_bps: f64, fps: u32, now: Instant) -> Option<Resized> {
+        let config = self.config;
+        let can_descend = self.current + 1 < self.rungs.len();
+        let can_ascend = self.current > 0;
+
+        if can_descend && available_bps < self.rung_min_bitrate(self.current, fps) {
+            match self.trend {
+                Some(Trend::Down(since)) if now.duration_since(since) >= config.down_after => {
+                    return Some(self.step(self.current + 1, available_bps));
+                },
+                Some(Trend::Down(_)) => {},
+                _ => self.trend = Some(Trend::Down(now)),
+            }
+        } else if can_ascend
+            && available_bps >= self.rung_min_bitrate(self.current - 1, fps) * config.up_headroom
+        {
+            match self.trend {
+                Some(Trend::Up(since)) if now.duration_since(since) >= config.up_after => {
+                    return Some(self.step(self.current - 1, available_bps));
+                },
+                Some(Trend::Up(_)) => {},
+                _ => self.trend = Some(Trend::Up(now)),
+            }
+        } else {
+            self.trend = None;
+        }
+        None
+    }
+
+    /// Scales `frame` to the current resolution.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`CaptureFrame::downscale`].
+    pub fn apply(&self, frame: &CaptureFrame) -> CaptureResult<CaptureFrame> {
+        if self.current == 0 {
+            return Ok(frame.clone());
+        }
+        frame.downscale(self.scale())
+    }
+
+    fn step(&mut self, to: usize, available_bps: f64) -> Resized {
+        self.current = to;
+        self.trend = None;
+        if let Some(handle) = &self.keyframes {
+            handle.request_keyframe();
+        }
+
+        let (width, height) = self.resolution();
+        info!(width, height, available_bps, "Stream resolution changed");
+        Resized {
+            width,
+            height,
+            scale: self.scale(),
+        }
+    }
+
+    fn dimensions(&self, rung: usize) -> (u32, u32) {
+        let scale = self.rungs[rung];
+        (
+            scaled_dimension(self.source.0, scale),
+            scaled_dimension(self.source.1, scale),
+        )
+    }
+
+    fn rung_min_bitrate(&self, rung: usize, fps: u32) -> f64 {
+        let (width, height) = self.dimensions(rung);
+        f64::from(width) * f64::from(height) * f64::from(fps) * self.config.min_bits_per_pixel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::{FrameFormat, FrameMetadataBuilder};
+    use ion_core::backend::KeyframeScheduler;
+
+    /// Feeds `bps` once a second for `seconds`, collecting resizes.
+    fn run(
+        ladder: &mut ResolutionLadder,
+        clock: &mut Instant,
+        bps: f64,
+        seconds: u32,
+    ) -> Vec<(u32, u32)> {
+        let mut resized = Vec::new();
+        for _ in 0..seconds {
+            *clock += Duration::from_secs(1);
+            if let Some(r) = ladder.update(bps, 30, *clock) {
+                resized.push((r.width, r.height));
+            }
+        }
+        resized
+    }
+
+    #[test]
+    fn sustained_low_bandwidth_steps_down_then_recovers() {
+        let handle = CaptureHandle::default();
+        let mut keyframes = KeyframeScheduler::new(handle.clone(), 1000);
+        assert!(keyframes.next_is_keyframe());
+
+        let mut ladder = ResolutionLadder::new(1920, 1080, LadderConfig::default())
+            .with_keyframe_handle(handle.clone());
+        let mut clock = Instant::now();
+
+        assert!(run(&mut ladder, &mut clock, 5_000_000.0, 5).is_empty());
+        assert_eq!(ladder.resolution(), (1920, 1080));
+
+        // 300 kbit/s carries 480p at minimum quality, but not 720p
+        let down = run(&mut ladder, &mut clock, 300_000.0, 10);
+        assert_eq!(down, [(1280, 720), (853, 480)]);
+        assert!(keyframes.next_is_keyframe());
+        assert!(!keyframes.next_is_keyframe());
+
+        let up = run(&mut ladder, &mut clock, 5_000_000.0, 30);
+        assert_eq!(up, [(1280, 720), (1920, 1080)]);
+        assert_eq!(handle.forced_keyframes(), 1);
+        assert!(keyframes.next_is_keyframe());
+    }
+
+    #[test]
+    fn hovering_bandwidth_does_not_flap() {
+        let mut ladder = ResolutionLadder::new(1920, 1080, LadderConfig::default());
+        let mut clock = Instant::now();
+        let full = ladder.min_bitrate(30);
+
+        // Brief dips are ignored
+        for _ in 0..5 {
+            assert!(run(&mut ladder, &mut clock, full * 0.9, 1).is_empty());
+            assert!(run(&mut ladder, &mut clock, full * 1.1, 1).is_empty());
+        }
+
+        assert_eq!(run(&mut ladder, &mut clock, full * 0.9, 3).len(), 1);
+        // Enough for 1080p but short of the headroom to go back up
+        assert!(run(&mut ladder, &mut clock, full * 1.1, 30).is_empty());
+        assert_eq!(ladder.resolution(), (1280, 720));
+    }
+
+    #[test]
+    fn frames_scaled_to_current_rung() {
+        let mut ladder = ResolutionLadder::new(
+            64,
+            1440,
+            LadderConfig {
+                down_after: Duration::ZERO,
+                ..LadderConfig::default()
+            },
+        );
+        let metadata = FrameMetadataBuilder::new()
+            .dimensions(64, 1440)
+            .stride(64 * 4)
+            .format(FrameFormat::Bgra8888)
+            .build();
+        let frame = CaptureFrame::new(metadata, vec![0; 64 * 4 * 1440]);
+        assert_eq!(ladder.apply(&frame).unwrap().height(), 1440);
+
+        let now = Instant::now();
+        ladder.update(0.0, 30, now);
+        let resized = ladder.update(0.0, 30, now).unwrap();
+        assert_eq!((resized.width, resized.height), (48, 1080));
+        let scaled = ladder.apply(&frame).unwrap();
+        assert_eq!((scaled.width(), scaled.height()), (48, 1080));
+    }
+}
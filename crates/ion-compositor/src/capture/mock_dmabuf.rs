@@ -0,0 +1,309 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Simulated DMA-BUF capture for testing without a GPU.
+//!
+//! [`MockDmabufCapture`] negotiates against a fixed set of DRM formats, as
+//! a compositor on real hardware would offer, and delivers synthetic frames
+//! in the negotiated format. It lets tier selection and format negotiation
+//! be exercised headlessly.
+//!
+//! Available under `cfg(test)` or the `mock-gpu` feature.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::broadcast;
+use tracing::{debug, info};
+
+use super::dmabuf::{negotiate_format, DmabufCaptureConfig, DrmFormat};
+use super::{
+    CaptureCapabilities, CaptureError, CaptureFrame, CaptureResult, FrameFormat,
+    FrameMetadataBuilder, ScreenCapture,
+};
+
+/// Intel X-tiled modifier, standing in for a vendor tiling layout.
+pub const MOCK_TILED_MODIFIER: u64 = 0x0100_0000_0000_0001;
+
+/// Simulated DMA-BUF capture backend.
+///
+/// Reports [`CaptureTier::Dmabuf`](super::CaptureTier::Dmabuf) when
+/// negotiation succeeds. If none of the preferred fourccs is offered, the
+/// backend reports itself unavailable and every capture fails with
+/// [`CaptureError::NotAvailable`].
+pub struct MockDmabufCapture {
+    capabilities: CaptureCapabilities,
+    /// Negotiated format, `None` if negotiation failed
+    active_format: Option<DrmFormat>,
+    dimensions: (u32, u32),
+    sequence: Arc<AtomicU64>,
+    streaming: Arc<AtomicBool>,
+}
+
+impl MockDmabufCapture {
+    /// Formats offered by the simulated compositor.
+    ///
+    /// BGRA comes both linear and tiled, XRGB linear only and NV12 tiled
+    /// only, so a config preferring linear NV12 has to fall back to the
+    /// tiled modifier.
+    #[must_use]
+    pub fn offered_formats() -> Vec<DrmFormat> {
+        vec![
+            DrmFormat::new(FrameFormat::Bgra8888.fourcc(), DrmFormat::MODIFIER_LINEAR),
+            DrmFormat::new(FrameFormat::Bgra8888.fourcc(), MOCK_TILED_MODIFIER),
+            DrmFormat::new(FrameFormat::Xrgb8888.fourcc(), DrmFormat::MODIFIER_LINEAR),
+            DrmFormat::new(FrameFormat::Nv12.fourcc(), MOCK_TILED_MODIFIER),
+        ]
+    }
+
+    /// Creates a mock negotiating `config` against [`Self::offered_formats`].
+    #[must_use]
+    pub fn new(width: u32, height: u32, config: &DmabufCaptureConfig) -> Self {
+        Self::with_formats(width, height, &Self::offered_formats(), config)
+    }
+
+    /// Creates a mock negotiating `config` against `offered`.
+    #[must_use]
+    pub fn with_formats(
+        width: u32,
+        height: u32,
+        offered: &[DrmFormat],
+        config: &DmabufCaptureConfig,
+    ) -> Self {
+        let active_format = negotiate_format(&config.preferred_formats, offered);
+        let capabilities = match active_format {
+            Some(_) => CaptureCapabilities::dmabuf(
+                offered
+                    .iter()
+                    .filter_map(|f| FrameFormat::from_fourcc(f.fourcc))
+                    .collect(),
+            ),
+            None => CaptureCapabilities::none(),
+        };
+        info!(width, height, format = ?active_format, "Created mock DMA-BUF capture");
+
+        Self {
+            capabilities,
+            active_format,
+            dimensions: (width, height),
+            sequence: Arc::new(AtomicU64::new(0)),
+            streaming: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Returns the negotiated DRM format, `None` if negotiation failed.
+    #[must_use]
+    pub const fn active_format(&self) -> Option<DrmFormat> {
+        self.active_format
+    }
+
+    fn frame(&self) -> CaptureResult<CaptureFrame> {
+        let format = self
+            .active_format
+            .and_then(|f| FrameFormat::from_fourcc(f.fourcc))
+            .ok_or_else(|| CaptureError::NotAvailable("no common DRM format".into()))?;
+        Ok(synthetic_frame(
+            format,
+            self.dimensions,
+            self.sequence.fetch_add(1, Ordering::Relaxed),
+        ))
+    }
+}
+
+/// Builds a frame filled with the low byte of its sequence number, so
+/// consumers can tell frames apart.
+fn synthetic_frame(
+    format: FrameFormat,
+    (width, height): (u32, u32),
+    sequence: u64,
+) -> CaptureFrame {
+    let capture_start = Instant::now();
+    #[allow(clippy::cast_possible_truncation)] // at most 4 bytes per pixel
+    let stride = width * format.bytes_per_pixel() as u32;
+    #[allow(clippy::cast_possible_truncation)] // keeps the low byte on purpose
+    let fill = sequence as u8;
+    let data = vec![fill; format.frame_size(stride as usize, height as usize)];
+
+    let metadata = FrameMetadataBuilder::new()
+        .sequence(sequence)
+        .dimensions(width, height)
+        .stride(stride)
+        .format(format)
+        .capture_start(capture_start)
+        .build();
+    CaptureFrame::new(metadata, data)
+}
+
+impl ScreenCapture for MockDmabufCapture {
+    fn capabilities(&self) -> &CaptureCapabilities {
+        &self.capabilities
+    }
+
+    fn capture_frame(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = CaptureResult<CaptureFrame>> + Send + '_>> {
+        Box::pin(async move { self.frame() })
+    }
+
+    fn start_stream(
+        &self,
+        target_fps: u32,
+    ) -> CaptureResult<broadcast::Receiver<Arc<CaptureFrame>>> {
+        let format = self
+            .active_format
+            .and_then(|f| FrameFormat::from_fourcc(f.fourcc))
+            .ok_or_else(|| CaptureError::NotAvailable("no common DRM format".into()))?;
+        let fps = target_fps.clamp(1, self.capabilities.max_fps);
+        let (tx, rx) = broadcast::channel(4);
+
+        self.streaming.store(true, Ordering::Relaxed);
+        let streaming = Arc::clone(&self.streaming);
+        let sequence = Arc::clone(&self.sequence);
+        let dimensions = self.dimensions;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(1) / fps);
+            while streaming.load(Ordering::Relaxed) {
+                interval.tick().await;
+                let frame =
+                    synthetic_frame(format, dimensions, sequence.fetch_add(1, Ordering::Relaxed));
+                if tx.send(Arc::new(frame)).is_err() {
+                    break;
+                }
+            }
+            streaming.store(false, Ordering::Relaxed);
+            debug!("Mock DMA-BUF stream ended");
+        });
+
+        Ok(rx)
+    }
+
+    fn stop_stream(&self) -> CaptureResult<()> {
+        self.streaming.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn is_capturing(&self) -> bool {
+        self.streaming.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::tier::EnvironmentInfo;
+    use crate::capture::{CaptureTier, TierSelector};
+
+    fn gpu_env() -> EnvironmentInfo {
+        EnvironmentInfo {
+            is_vm: false,
+            has_drm: true,
+            wayland_display: Some("wayland-0".to_string()),
+            has_runtime_dir: false,
+            gpu_vendor: Some("Intel".to_string()),
+        }
+    }
+
+    #[test]
+    fn negotiation_prefers_exact_then_falls_back_to_offered_modifier() {
+        let rgb = MockDmabufCapture::new(64, 48, &DmabufCaptureConfig::default());
+        assert_eq!(
+            rgb.active_format(),
+            Some(DrmFormat::new(
+                FrameFormat::Bgra8888.fourcc(),
+                DrmFormat::MODIFIER_LINEAR
+            ))
+        );
+
+        // Linear NV12 is not offered; the tiled NV12 buffer is taken
+        // rather than dropping to RGB
+        let nv12 = MockDmabufCapture::new(64, 48, &DmabufCaptureConfig::hardware_encoder());
+        assert_eq!(
+            nv12.active_format(),
+            Some(DrmFormat::new(
+                FrameFormat::Nv12.fourcc(),
+                MOCK_TILED_MODIFIER
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn negotiation_failure_makes_capture_unavailable() {
+        let offered = [DrmFormat::new(
+            FrameFormat::Rgba8888.fourcc(),
+            DrmFormat::MODIFIER_LINEAR,
+        )];
+        let capture =
+            MockDmabufCapture::with_formats(64, 48, &offered, &DmabufCaptureConfig::default());
+
+        assert_eq!(capture.active_format(), None);
+        assert_eq!(capture.capabilities().tier, CaptureTier::None);
+        assert!(matches!(
+            capture.capture_frame().await,
+            Err(CaptureError::NotAvailable(_))
+        ));
+        assert!(capture.start_stream(30).is_err());
+    }
+
+    #[tokio::test]
+    async fn frames_delivered_in_negotiated_format() {
+        let capture = MockDmabufCapture::new(64, 48, &DmabufCaptureConfig::hardware_encoder());
+        let frame = capture.capture_frame().await.unwrap();
+        assert_eq!(frame.format(), FrameFormat::Nv12);
+        assert_eq!(frame.data().len(), 64 * 48 * 3 / 2);
+
+        let mut rx = capture.start_stream(60).unwrap();
+        assert!(capture.is_capturing());
+        let first = rx.recv().await.unwrap();
+        let second = rx.recv().await.unwrap();
+        assert_eq!(first.metadata.sequence + 1, second.metadata.sequence);
+        assert_eq!(first.format(), FrameFormat::Nv12);
+
+        capture.stop_stream().unwrap();
+        assert!(!capture.is_capturing());
+    }
+
+    #[tokio::test]
+    async fn tier_selector_prefers_available_dmabuf() {
+        let selection = TierSelector::with_env(gpu_env())
+            .with_benchmark(|tier| match tier {
+                CaptureTier::Dmabuf => Some(Box::new(MockDmabufCapture::new(
+                    64,
+                    48,
+                    &DmabufCaptureConfig::default(),
+                ))),
+                _ => None,
+            })
+            .select()
+            .await;
+
+        assert_eq!(selection.tier, CaptureTier::Dmabuf);
+        assert!(selection.latency(CaptureTier::Dmabuf).is_some());
+        assert_eq!(selection.downgraded_from, None);
+    }
+
+    #[tokio::test]
+    async fn tier_selector_skips_dmabuf_without_common_format() {
+        let offered = [DrmFormat::new(
+            FrameFormat::Rgba8888.fourcc(),
+            DrmFormat::MODIFIER_LINEAR,
+        )];
+        let selection = TierSelector::with_env(gpu_env())
+            .with_benchmark(move |tier| match tier {
+                CaptureTier::Dmabuf => Some(Box::new(MockDmabufCapture::with_formats(
+                    64,
+                    48,
+                    &offered,
+                    &DmabufCaptureConfig::default(),
+                ))),
+                _ => None,
+            })
+            .select()
+            .await;
+
+        assert_eq!(selection.tier, CaptureTier::Cpu);
+        assert_eq!(selection.downgraded_from, Some(CaptureTier::Dmabuf));
+    }
+}
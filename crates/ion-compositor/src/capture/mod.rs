@@ -13,6 +13,9 @@
 //! 3. **Tier 3: wl_shm** — Shared memory (works in VMs)
 //! 4. **Tier 4: CPU** — Framebuffer copy (universal fallback)
 //!
+//! When no tier works, [`NullCapture`] stands in so the session can run
+//! input-only without special-casing a missing backend.
+//!
 //! # Architecture
 //!
 //! ```text
@@ -45,22 +48,50 @@
 //! Traditional Wayland remote desktop crashes without GPU dmabuf support.
 //! ionChannel gracefully degrades to lower tiers instead.
 
+mod codec;
 mod cpu;
 mod dmabuf;
+mod fanout;
 mod frame;
+mod jitter;
+mod ladder;
+#[cfg(any(test, feature = "mock-gpu"))]
+mod mock_dmabuf;
+mod null;
+mod pause;
+mod roi;
 mod shm;
+mod static_screen;
+mod stats;
 mod tier;
 
+pub use codec::Codec;
 pub use cpu::CpuCapture;
-pub use dmabuf::DmabufCapture;
-pub use frame::{CaptureFrame, FrameFormat, FrameMetadata, FrameMetadataBuilder};
-pub use shm::ShmCapture;
-pub use tier::{CaptureTier, TierSelector};
+pub use dmabuf::{negotiate_format, DmabufCapture, DmabufCaptureConfig, DrmFormat};
+pub use fanout::{StreamBroadcast, StreamSubscriber};
+pub use frame::{CaptureFrame, FrameFormat, FrameMetadata, FrameMetadataBuilder, Rect};
+pub use jitter::{JitterBuffer, JitterConfig};
+pub use ladder::{LadderConfig, Resized, ResolutionLadder, STANDARD_HEIGHTS};
+#[cfg(any(test, feature = "mock-gpu"))]
+pub use mock_dmabuf::{MockDmabufCapture, MOCK_TILED_MODIFIER};
+pub use null::NullCapture;
+pub use pause::drive_stream;
+pub use roi::{QpMap, RoiConfig, SessionRoi};
+pub use shm::{
+    frame_format_to_wl_shm_format, wl_shm_format, wl_shm_format_to_frame_format, ShmCapture,
+};
+pub use static_screen::{StaticDetector, StreamEvent, DEFAULT_STATIC_THRESHOLD};
+pub use stats::{StreamStats, StreamStatsSnapshot};
+pub use tier::{
+    CaptureFactory, CaptureTier, TierBenchmark, TierSelection, TierSelector, BENCHMARK_FRAMES,
+    DEFAULT_LATENCY_THRESHOLD,
+};
 
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
+use ion_core::backend::{OutputInfo, OutputTransform, TransformHandling};
 use thiserror::Error;
 use tokio::sync::broadcast;
 
@@ -91,6 +122,14 @@ pub enum CaptureError {
     #[error("capture session closed")]
     SessionClosed,
 
+    /// Frame could not be converted or encoded.
+    #[error("frame encoding failed: {0}")]
+    Encode(String),
+
+    /// Capture parameters were out of range (region, scale).
+    #[error("invalid capture request: {0}")]
+    InvalidRequest(String),
+
     /// Internal error.
     #[error("internal error: {0}")]
     Internal(String),
@@ -99,6 +138,70 @@ pub enum CaptureError {
 /// Result type for capture operations.
 pub type CaptureResult<T> = Result<T, CaptureError>;
 
+/// Checks the settings every capture tier is constructed with.
+///
+/// # Errors
+///
+/// Returns [`CaptureError::InvalidRequest`] for a zero width, height or
+/// frame rate.
+pub(crate) fn validate_settings(width: u32, height: u32, fps: u32) -> CaptureResult<()> {
+    if width == 0 || height == 0 {
+        return Err(CaptureError::InvalidRequest(format!(
+            "dimensions must be non-zero, got {width}x{height}"
+        )));
+    }
+    if fps == 0 {
+        return Err(CaptureError::InvalidRequest(
+            "frame rate must be non-zero".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Size to capture a `width` x `height` output with `transform` at, and
+/// who will make the frame upright.
+///
+/// Frames are captured in the panel's native orientation. Packed formats
+/// are rotated on the CPU. Planar formats cannot be rotated cheaply on the
+/// CPU, and the capture protocols have no way to ask the compositor for
+/// an upright frame, so they are delivered as captured and the client
+/// applies the transform.
+pub(crate) fn plan_transform(
+    transform: OutputTransform,
+    format: FrameFormat,
+    width: u32,
+    height: u32,
+) -> (u32, u32, TransformHandling) {
+    if transform == OutputTransform::Normal {
+        (width, height, TransformHandling::None)
+    } else {
+        let (width, height) = transform.apply_to_size(width, height);
+        let handling = if format.is_planar() {
+            TransformHandling::None
+        } else {
+            TransformHandling::Cpu
+        };
+        (width, height, handling)
+    }
+}
+
+/// Makes a frame captured as [`plan_transform`] planned upright if it is
+/// to be rotated here, and records the transform in its metadata.
+pub(crate) fn finish_transform(
+    mut frame: CaptureFrame,
+    transform: OutputTransform,
+    handling: TransformHandling,
+) -> CaptureResult<CaptureFrame> {
+    match handling {
+        TransformHandling::Cpu => frame.apply_transform(transform),
+        TransformHandling::None | TransformHandling::Compositor => {
+            frame.metadata.transform = transform;
+            frame.metadata.transform_handling = handling;
+            Ok(frame)
+        },
+    }
+}
+
 /// Capability information for a capture backend.
 #[derive(Debug, Clone)]
 pub struct CaptureCapabilities {
@@ -205,11 +308,60 @@ pub trait ScreenCapture: Send + Sync {
         target_fps: u32,
     ) -> CaptureResult<broadcast::Receiver<Arc<CaptureFrame>>>;
 
+    /// Captures a single frame, cropped to `region` and downscaled by
+    /// `scale`.
+    ///
+    /// Meant for one-shot previews such as the consent dialog thumbnail,
+    /// where starting a stream would be wasteful. `None` keeps the whole
+    /// output; `scale` must be in `(0, 1]`.
+    ///
+    /// The default implementation captures a full frame and resamples it
+    /// on the CPU; backends able to capture a region directly may
+    /// override it.
+    fn capture_region_frame(
+        &self,
+        region: Option<Rect>,
+        scale: f32,
+    ) -> Pin<Box<dyn Future<Output = CaptureResult<CaptureFrame>> + Send + '_>> {
+        Box::pin(async move {
+            // Reject bad parameters before paying for a capture
+            if !(scale > 0.0 && scale <= 1.0) {
+                return Err(CaptureError::InvalidRequest(format!(
+                    "scale {scale} is not in (0, 1]"
+                )));
+            }
+            let frame = self.capture_frame().await?;
+            let frame = match region {
+                Some(region) => frame.crop(region)?,
+                None => frame,
+            };
+            frame.downscale(scale)
+        })
+    }
+
     /// Stops any active capture stream.
     fn stop_stream(&self) -> CaptureResult<()>;
 
+    /// Stops any active capture stream and waits for it to wind down.
+    ///
+    /// Backends that run their stream on a task override this to join it,
+    /// so no frame is emitted once the future resolves. The default just
+    /// calls [`Self::stop_stream`].
+    fn stop_stream_and_wait(&self) -> Pin<Box<dyn Future<Output = CaptureResult<()>> + Send + '_>> {
+        Box::pin(async move { self.stop_stream() })
+    }
+
     /// Returns true if this backend is currently capturing.
     fn is_capturing(&self) -> bool;
+
+    /// Returns the statistics of the current stream, or of the last one
+    /// once it has stopped.
+    ///
+    /// Each [`Self::start_stream`] starts them afresh. `None` if the
+    /// backend keeps no statistics or has not streamed yet.
+    fn stream_stats(&self) -> Option<Arc<StreamStats>> {
+        None
+    }
 }
 
 /// Extension trait for `ScreenCapture` with convenience methods.
@@ -228,6 +380,38 @@ pub trait ScreenCaptureExt: ScreenCapture {
     fn is_available(&self) -> bool {
         self.tier() != CaptureTier::None
     }
+
+    /// Returns the offered format cheapest to encode with `codec`.
+    ///
+    /// Ranks [`CaptureCapabilities::formats`] by
+    /// [`Codec::input_formats`]. `None` if the backend offers no formats.
+    fn best_format_for(&self, codec: Codec) -> Option<FrameFormat> {
+        let offered = &self.capabilities().formats;
+        codec
+            .input_formats()
+            .iter()
+            .find(|f| offered.contains(f))
+            .copied()
+    }
+
+    /// Returns the offered format to capture `output` in for `codec`.
+    ///
+    /// HDR outputs get [`FrameFormat::Xrgb2101010`] when both the codec
+    /// and this backend support it. Everything else stays 8-bit: SDR
+    /// outputs, and HDR outputs on a codec or tier without 10-bit.
+    fn best_format_for_output(&self, codec: Codec, output: &OutputInfo) -> Option<FrameFormat> {
+        let offered = &self.capabilities().formats;
+        let hdr_format = FrameFormat::Xrgb2101010;
+        if output.hdr && codec.supports_10bit() && offered.contains(&hdr_format) {
+            return Some(hdr_format);
+        }
+        codec
+            .input_formats()
+            .iter()
+            .filter(|&&f| f != hdr_format)
+            .find(|f| offered.contains(f))
+            .copied()
+    }
 }
 
 impl<T: ScreenCapture + ?Sized> ScreenCaptureExt for T {}
@@ -314,6 +498,7 @@ mod tests {
             CaptureError::BufferAllocation("oom".into()),
             CaptureError::Timeout(std::time::Duration::from_secs(5)),
             CaptureError::SessionClosed,
+            CaptureError::InvalidRequest("scale".into()),
             CaptureError::Internal("oops".into()),
         ];
 
@@ -380,6 +565,101 @@ mod tests {
         assert!(debug.contains("CaptureCapabilities"));
     }
 
+    #[test]
+    fn best_format_for_codec_across_tiers() {
+        let planar = DmabufCapture::new(
+            64,
+            48,
+            vec![
+                DrmFormat::new(FrameFormat::Bgra8888.fourcc(), DrmFormat::MODIFIER_LINEAR),
+                DrmFormat::new(FrameFormat::Nv12.fourcc(), DrmFormat::MODIFIER_LINEAR),
+            ],
+            DmabufCaptureConfig::default(),
+        );
+        let hdr = DmabufCapture::new(
+            64,
+            48,
+            vec![
+                DrmFormat::new(FrameFormat::Xrgb8888.fourcc(), DrmFormat::MODIFIER_LINEAR),
+                DrmFormat::new(
+                    FrameFormat::Xrgb2101010.fourcc(),
+                    DrmFormat::MODIFIER_LINEAR,
+                ),
+            ],
+            DmabufCaptureConfig::default(),
+        );
+        let shm = ShmCapture::with_defaults(64, 48);
+        let cpu = CpuCapture::with_defaults(64, 48);
+
+        for codec in [Codec::H264, Codec::Vp8, Codec::Av1] {
+            assert_eq!(planar.best_format_for(codec), Some(FrameFormat::Nv12));
+            assert_eq!(shm.best_format_for(codec), Some(FrameFormat::Xrgb8888));
+            assert_eq!(cpu.best_format_for(codec), Some(FrameFormat::Bgra8888));
+            assert_eq!(NullCapture::new().best_format_for(codec), None);
+        }
+        assert_eq!(
+            hdr.best_format_for(Codec::H264),
+            Some(FrameFormat::Xrgb8888)
+        );
+        assert_eq!(hdr.best_format_for(Codec::Vp8), Some(FrameFormat::Xrgb8888));
+        assert_eq!(
+            hdr.best_format_for(Codec::Av1),
+            Some(FrameFormat::Xrgb2101010)
+        );
+    }
+
+    #[test]
+    fn hdr_output_prefers_10bit_when_tier_supports_it() {
+        use ion_core::backend::{ColorSpace, OutputTransform};
+
+        let hdr_tier = DmabufCapture::new(
+            64,
+            48,
+            vec![
+                DrmFormat::new(FrameFormat::Nv12.fourcc(), DrmFormat::MODIFIER_LINEAR),
+                DrmFormat::new(FrameFormat::Xrgb8888.fourcc(), DrmFormat::MODIFIER_LINEAR),
+                DrmFormat::new(
+                    FrameFormat::Xrgb2101010.fourcc(),
+                    DrmFormat::MODIFIER_LINEAR,
+                ),
+            ],
+            DmabufCaptureConfig::default(),
+        );
+        let hdr_output = OutputInfo {
+            stream: 0,
+            name: "DP-1".to_string(),
+            width: 3840,
+            height: 2160,
+            color_space: ColorSpace::Bt2020,
+            hdr: true,
+            transform: OutputTransform::Normal,
+        };
+        let sdr_output = OutputInfo {
+            color_space: ColorSpace::Srgb,
+            hdr: false,
+            ..hdr_output.clone()
+        };
+
+        assert_eq!(
+            hdr_tier.best_format_for_output(Codec::Av1, &hdr_output),
+            Some(FrameFormat::Xrgb2101010)
+        );
+        // SDR outputs and 8-bit codecs stay 8-bit
+        assert_eq!(
+            hdr_tier.best_format_for_output(Codec::Av1, &sdr_output),
+            Some(FrameFormat::Nv12)
+        );
+        assert_eq!(
+            hdr_tier.best_format_for_output(Codec::H264, &hdr_output),
+            Some(FrameFormat::Nv12)
+        );
+        // A tier without 10-bit formats falls back
+        assert_eq!(
+            ShmCapture::with_defaults(64, 48).best_format_for_output(Codec::Av1, &hdr_output),
+            Some(FrameFormat::Xrgb8888)
+        );
+    }
+
     #[test]
     fn capture_error_is_send_sync() {
         fn assert_send_sync<T: Send + Sync>() {}
@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Input-only placeholder for when no capture tier is available.
+//!
+//! [`NullCapture`] lets callers always hold a `Box<dyn ScreenCapture>`:
+//! single captures fail with [`CaptureError::NotAvailable`] and streams
+//! close without yielding a frame.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+use tracing::debug;
+
+use super::{CaptureCapabilities, CaptureError, CaptureFrame, CaptureResult, ScreenCapture};
+
+/// Capture backend for [`CaptureTier::None`](super::CaptureTier::None).
+#[derive(Debug)]
+pub struct NullCapture {
+    capabilities: CaptureCapabilities,
+}
+
+impl NullCapture {
+    /// Creates a capture backend that never produces frames.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            capabilities: CaptureCapabilities::none(),
+        }
+    }
+}
+
+impl Default for NullCapture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScreenCapture for NullCapture {
+    fn capabilities(&self) -> &CaptureCapabilities {
+        &self.capabilities
+    }
+
+    fn capture_frame(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = CaptureResult<CaptureFrame>> + Send + '_>> {
+        Box::pin(async {
+            Err(CaptureError::NotAvailable(
+                "no screen capture in input-only mode".into(),
+            ))
+        })
+    }
+
+    fn start_stream(
+        &self,
+        _target_fps: u32,
+    ) -> CaptureResult<broadcast::Receiver<Arc<CaptureFrame>>> {
+        // Dropping the only sender closes the stream immediately
+        let (_tx, rx) = broadcast::channel(1);
+        debug!("Input-only mode, capture stream closed immediately");
+        Ok(rx)
+    }
+
+    fn stop_stream(&self) -> CaptureResult<()> {
+        Ok(())
+    }
+
+    fn is_capturing(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::{CaptureTier, ScreenCaptureExt};
+
+    #[tokio::test]
+    async fn null_capture_is_unavailable() {
+        let capture = NullCapture::new();
+
+        assert!(!capture.is_available());
+        assert_eq!(capture.tier(), CaptureTier::None);
+        assert!(!capture.is_capturing());
+        assert!(matches!(
+            capture.capture_frame().await,
+            Err(CaptureError::NotAvailable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn null_stream_closes_without_frames() {
+        let capture = NullCapture::new();
+        let mut rx = capture.start_stream(30).unwrap();
+
+        assert!(matches!(
+            rx.recv().await,
+            Err(broadcast::error::RecvError::Closed)
+        ));
+        capture.stop_stream().unwrap();
+    }
+}
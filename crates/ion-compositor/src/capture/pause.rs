@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Pausing a capture stream without ending it.
+//!
+//! A portal client pausing screen sharing keeps its session, and its
+//! stream: the peer shows a placeholder instead of treating the stream as
+//! ended. [`drive_stream`] runs a [`ScreenCapture`] for such a stream,
+//! following its [`CaptureHandle`]:
+//!
+//! - [`CaptureHandle::pause`] stops the capture with
+//!   [`ScreenCapture::stop_stream_and_wait`], so nothing is captured while
+//!   paused.
+//! - [`CaptureHandle::resume`] restarts it with
+//!   [`ScreenCapture::start_stream`]. The handle has already asked the
+//!   encoder for a keyframe, so the peer can decode from the first frame.
+//! - [`CaptureHandle::stop`] stops the capture and ends the stream.
+//!
+//! Consumers subscribe to one sender for the whole stream and never see
+//! the capture restart.
+
+use std::sync::Arc;
+
+use ion_core::backend::CaptureHandle;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::debug;
+
+use super::{CaptureFrame, CaptureResult, ScreenCapture};
+
+/// Streams frames of `capture` to `frames` until `handle` is stopped,
+/// stopping the capture while the handle is paused.
+///
+/// Returns early if the capture ends its stream on its own.
+///
+/// # Errors
+///
+/// Returns an error if the capture cannot be started or stopped.
+pub async fn drive_stream<C: ScreenCapture + ?Sized>(
+    capture: &C,
+    handle: &CaptureHandle,
+    target_fps: u32,
+    frames: &broadcast::Sender<Arc<CaptureFrame>>,
+) -> CaptureResult<()> {
+    loop {
+        handle.wait_paused(false).await;
+        if handle.is_stopped() {
+            return Ok(());
+        }
+
+        let mut source = capture.start_stream(target_fps)?;
+        loop {
+            tokio::select! {
+                () = handle.wait_paused(true) => break,
+                frame = source.recv() => match frame {
+                    Ok(frame) => {
+                        // No subscribers is fine, one may join later
+                        let _ = frames.send(frame);
+                    },
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!(skipped, "Stream driver lagged behind capture");
+                    },
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+            }
+        }
+
+        capture.stop_stream_and_wait().await?;
+        debug!(stopped = handle.is_stopped(), "Capture stream stopped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ion_core::backend::KeyframeScheduler;
+
+    use super::*;
+    use crate::capture::{DmabufCaptureConfig, MockDmabufCapture};
+
+    async fn next_frame(rx: &mut broadcast::Receiver<Arc<CaptureFrame>>) -> Arc<CaptureFrame> {
+        tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("frame in time")
+            .expect("stream open")
+    }
+
+    #[tokio::test]
+    async fn pause_stops_the_capture_and_resume_restarts_it() {
+        let capture = Arc::new(MockDmabufCapture::new(
+            64,
+            48,
+            &DmabufCaptureConfig::default(),
+        ));
+        let handle = CaptureHandle::default();
+        let (tx, mut rx) = broadcast::channel(16);
+        let driver = {
+            let (capture, handle, tx) = (Arc::clone(&capture), handle.clone(), tx.clone());
+            tokio::spawn(async move { drive_stream(&*capture, &handle, 60, &tx).await })
+        };
+
+        next_frame(&mut rx).await;
+        let mut scheduler = KeyframeScheduler::new(handle.clone(), 1000);
+        assert!(scheduler.next_is_keyframe());
+        assert!(!scheduler.next_is_keyframe());
+        assert!(capture.is_capturing());
+
+        handle.pause();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!capture.is_capturing());
+        // Drain anything sent before the pause took effect
+        while rx.try_recv().is_ok() {}
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(rx.try_recv().is_err(), "no frames while paused");
+
+        handle.resume();
+        next_frame(&mut rx).await;
+        assert!(capture.is_capturing());
+        assert!(
+            scheduler.next_is_keyframe(),
+            "resume starts with a keyframe"
+        );
+
+        handle.stop();
+        driver.await.unwrap().unwrap();
+        assert!(!capture.is_capturing());
+    }
+
+    #[tokio::test]
+    async fn stop_while_paused_ends_the_stream() {
+        let capture = MockDmabufCapture::new(64, 48, &DmabufCaptureConfig::default());
+        let handle = CaptureHandle::default();
+        handle.pause();
+        let (tx, _rx) = broadcast::channel(4);
+
+        let driver = drive_stream(&capture, &handle, 30, &tx);
+        let stopper = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            assert!(!capture.is_capturing(), "paused streams don't capture");
+            handle.stop();
+        };
+        let (result, ()) = tokio::join!(driver, stopper);
+        result.unwrap();
+        assert!(!capture.is_capturing());
+    }
+}
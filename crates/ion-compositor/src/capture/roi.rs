@@ -0,0 +1,297 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Region-of-interest quality maps.
+//!
+//! Clients can mark regions (around the cursor, the focused window) that
+//! should stay sharp while the rest of the frame is encoded more coarsely.
+//! [`QpMap`] turns those regions into per-macroblock quantizer offsets, the
+//! form hardware and software encoders accept for ROI encoding. Encoders
+//! without per-block QP control ignore the map and encode uniformly.
+//!
+//! [`SessionRoi`] keeps the regions each session set, and hands its
+//! encoders the map for every frame.
+
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use ion_core::session::SessionId;
+
+use super::{CaptureError, CaptureResult, Rect};
+
+/// Quantizer offsets applied inside and outside regions of interest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoiConfig {
+    /// QP is lowered by this much inside regions and raised by it outside
+    pub qp_delta: u8,
+    /// Macroblock edge in pixels
+    pub block_size: u32,
+}
+
+impl Default for RoiConfig {
+    fn default() -> Self {
+        Self {
+            qp_delta: 4,
+            block_size: 16,
+        }
+    }
+}
+
+/// Per-macroblock QP offsets for one frame, row-major.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QpMap {
+    cols: u32,
+    rows: u32,
+    offsets: Vec<i8>,
+}
+
+impl QpMap {
+    /// Builds the map for a `width` x `height` frame.
+    ///
+    /// Any macroblock touching a region gets `-qp_delta`, every other
+    /// block `+qp_delta`. With no regions the map is uniform (all zero).
+    /// Region parts outside the frame are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::InvalidRequest`] for a frame without pixels
+    /// or an empty region.
+    pub fn for_regions(
+        width: u32,
+        height: u32,
+        regions: &[Rect],
+        config: &RoiConfig,
+    ) -> CaptureResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(CaptureError::InvalidRequest(format!(
+                "ROI map for an empty {width}x{height} frame"
+            )));
+        }
+        validate_regions(regions)?;
+
+        let block = config.block_size.max(1);
+        let cols = width.div_ceil(block);
+        let rows = height.div_ceil(block);
+        let delta = i8::try_from(config.qp_delta).unwrap_or(i8::MAX);
+
+        if regions.is_empty() {
+            return Ok(Self {
+                cols,
+                rows,
+                offsets: vec![0; cols as usize * rows as usize],
+            });
+        }
+
+        let mut offsets = vec![delta; cols as usize * rows as usize];
+        for region in regions {
+            let first_col = region.x / block;
+            let first_row = region.y / block;
+            let last_col =
+                (region.x.saturating_add(region.width - 1) / block).min(cols.saturating_sub(1));
+            let last_row =
+                (region.y.saturating_add(region.height - 1) / block).min(rows.saturating_sub(1));
+            for row in first_row..=last_row {
+                for col in first_col..=last_col {
+                    offsets[(row * cols + col) as usize] = -delta;
+                }
+            }
+        }
+
+        Ok(Self {
+            cols,
+            rows,
+            offsets,
+        })
+    }
+
+    /// Macroblock columns and rows.
+    #[must_use]
+    pub const fn dimensions(&self) -> (u32, u32) {
+        (self.cols, self.rows)
+    }
+
+    /// Offset for the macroblock at `(col, row)`, if in range.
+    #[must_use]
+    pub fn offset(&self, col: u32, row: u32) -> Option<i8> {
+        if col >= self.cols || row >= self.rows {
+            return None;
+        }
+        self.offsets.get((row * self.cols + col) as usize).copied()
+    }
+
+    /// Offsets in row-major order, as encoders take them.
+    #[must_use]
+    pub fn offsets(&self) -> &[i8] {
+        &self.offsets
+    }
+
+    /// Whether every block is encoded at the same quality.
+    #[must_use]
+    pub fn is_uniform(&self) -> bool {
+        self.offsets.windows(2).all(|w| w[0] == w[1])
+    }
+
+    /// Number of macroblocks inside a region of interest.
+    #[must_use]
+    pub fn roi_blocks(&self) -> usize {
+        self.offsets.iter().filter(|&&o| o < 0).count()
+    }
+}
+
+/// Rejects regions without pixels, which would mark nothing.
+fn validate_regions(regions: &[Rect]) -> CaptureResult<()> {
+    match regions.iter().find(|r| r.width == 0 || r.height == 0) {
+        Some(empty) => Err(CaptureError::InvalidRequest(format!(
+            "empty region of interest {empty}"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Regions of interest each capture session set.
+///
+/// Clones share the same regions, so the session's control path can set
+/// them while its encoders read them.
+#[derive(Debug, Clone, Default)]
+pub struct SessionRoi {
+    config: RoiConfig,
+    regions: Arc<RwLock<HashMap<SessionId, Vec<Rect>>>>,
+}
+
+impl SessionRoi {
+    /// Tracks regions, encoding them with `config`.
+    #[must_use]
+    pub fn new(config: RoiConfig) -> Self {
+        Self {
+            config,
+            regions: Arc::default(),
+        }
+    }
+
+    /// Replaces the session's regions of interest.
+    ///
+    /// No regions brings the session back to uniform quality.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::InvalidRequest`] if any region is empty;
+    /// the session's regions are left as they were.
+    pub fn set_roi(&self, session: &SessionId, regions: Vec<Rect>) -> CaptureResult<()> {
+        validate_regions(&regions)?;
+        let mut all = self.regions.write().unwrap_or_else(PoisonError::into_inner);
+        if regions.is_empty() {
+            all.remove(session);
+        } else {
+            all.insert(session.clone(), regions);
+        }
+        Ok(())
+    }
+
+    /// Forgets a session's regions, e.g. once it closes.
+    pub fn clear(&self, session: &SessionId) {
+        self.regions
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(session);
+    }
+
+    /// The session's regions of interest.
+    #[must_use]
+    pub fn regions(&self, session: &SessionId) -> Vec<Rect> {
+        self.regions
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(session)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The QP map for the session's next `width` x `height` frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::InvalidRequest`] for a frame without
+    /// pixels.
+    pub fn qp_map(&self, session: &SessionId, width: u32, height: u32) -> CaptureResult<QpMap> {
+        QpMap::for_regions(width, height, &self.regions(session), &self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_regions_is_uniform() {
+        let map = QpMap::for_regions(1920, 1080, &[], &RoiConfig::default()).unwrap();
+        assert_eq!(map.dimensions(), (120, 68));
+        assert!(map.is_uniform());
+        assert_eq!(map.roi_blocks(), 0);
+    }
+
+    #[test]
+    fn roi_blocks_get_lower_qp() {
+        let config = RoiConfig::default();
+        // Cursor-sized region straddling four macroblocks
+        let map = QpMap::for_regions(64, 64, &[Rect::new(8, 8, 16, 16)], &config).unwrap();
+
+        assert_eq!(map.dimensions(), (4, 4));
+        assert_eq!(map.roi_blocks(), 4);
+        assert_eq!(map.offset(0, 0), Some(-4));
+        assert_eq!(map.offset(1, 1), Some(-4));
+        assert_eq!(map.offset(2, 0), Some(4));
+        assert_eq!(map.offset(3, 3), Some(4));
+        assert_eq!(map.offset(4, 0), None);
+
+        // Mostly coarser overall: the map spends fewer bits than uniform
+        // high quality while the ROI stays sharp
+        let total: i32 = map.offsets().iter().map(|&o| i32::from(o)).sum();
+        assert!(total > 0);
+    }
+
+    #[test]
+    fn regions_are_clipped_to_frame() {
+        let config = RoiConfig {
+            qp_delta: 6,
+            block_size: 16,
+        };
+        let map = QpMap::for_regions(40, 20, &[Rect::new(30, 10, 500, 500)], &config).unwrap();
+
+        // 40x20 rounds up to 3x2 blocks; the region covers columns 1 and 2
+        assert_eq!(map.dimensions(), (3, 2));
+        assert_eq!(map.offsets(), [6, -6, -6, 6, -6, -6]);
+    }
+
+    #[test]
+    fn empty_frames_and_regions_are_rejected() {
+        let config = RoiConfig::default();
+        let cursor = [Rect::new(0, 0, 16, 16)];
+        assert!(QpMap::for_regions(0, 1080, &cursor, &config).is_err());
+        assert!(QpMap::for_regions(1920, 0, &[], &config).is_err());
+        assert!(QpMap::for_regions(64, 64, &[Rect::new(8, 8, 0, 16)], &config).is_err());
+    }
+
+    #[test]
+    fn session_roi_feeds_qp_map() {
+        let roi = SessionRoi::new(RoiConfig::default());
+        let session = SessionId::new("/test/roi");
+
+        roi.set_roi(&session, vec![Rect::new(0, 0, 16, 16)])
+            .unwrap();
+        let map = roi.qp_map(&session, 64, 64).unwrap();
+        assert_eq!(map.roi_blocks(), 1);
+
+        // A bad update leaves the regions alone
+        assert!(roi
+            .set_roi(
+                &session,
+                vec![Rect::new(0, 0, 16, 16), Rect::new(32, 32, 16, 0)]
+            )
+            .is_err());
+        assert_eq!(roi.regions(&session), [Rect::new(0, 0, 16, 16)]);
+
+        roi.set_roi(&session, Vec::new()).unwrap();
+        assert!(roi.qp_map(&session, 64, 64).unwrap().is_uniform());
+        assert!(roi.qp_map(&session, 0, 64).is_err());
+    }
+}
@@ -19,19 +19,30 @@
 //! - Latency: ~5-15ms (depends on compositor)
 //! - CPU overhead: ~10-20%
 //! - Suitable for 30-60 FPS in most cases
+//!
+//! ## Buffer Formats
+//!
+//! The compositor chooses the `wl_shm` format it fills the buffer with,
+//! which may differ from [`ShmCaptureConfig::preferred_format`]. Frames are
+//! always labelled with the format the compositor actually used (see
+//! [`wl_shm_format_to_frame_format`]); conversion to the preferred format
+//! only happens when [`ShmCaptureConfig::convert_to_preferred`] is set.
 
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError};
 use std::time::{Duration, Instant};
 
-use tokio::sync::{broadcast, Mutex, RwLock};
+use ion_core::backend::OutputTransform;
+use tokio::sync::{broadcast, oneshot, Mutex, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{debug, info, instrument, warn};
 
 use super::{
-    CaptureCapabilities, CaptureError, CaptureFrame, CaptureResult, FrameFormat,
-    FrameMetadataBuilder, ScreenCapture,
+    finish_transform, plan_transform, validate_settings, CaptureCapabilities, CaptureError,
+    CaptureFrame, CaptureResult, FrameFormat, FrameMetadataBuilder, ScreenCapture, StreamBroadcast,
+    StreamStats, StreamSubscriber,
 };
 
 /// Configuration for shared memory capture.
@@ -45,6 +56,21 @@ pub struct ShmCaptureConfig {
     pub preferred_format: FrameFormat,
     /// Capture timeout.
     pub timeout: Duration,
+    /// Convert frames to `preferred_format` when the compositor fills the
+    /// buffer in a different format.
+    pub convert_to_preferred: bool,
+    /// Largest frame buffer, in bytes, that will be allocated.
+    ///
+    /// Guards against compositors reporting absurd output sizes.
+    pub max_frame_bytes: usize,
+}
+
+impl ShmCaptureConfig {
+    /// Default frame size limit: 256 MiB, enough for 8K at 4 bytes per pixel.
+    pub const DEFAULT_MAX_FRAME_BYTES: usize = 256 * 1024 * 1024;
+
+    /// Largest width or height accepted by [`ShmCapture::resize`].
+    pub const MAX_DIMENSION: u32 = 16384;
 }
 
 impl Default for ShmCaptureConfig {
@@ -54,32 +80,127 @@ impl Default for ShmCaptureConfig {
             buffer_count: 2,
             preferred_format: FrameFormat::Bgra8888,
             timeout: Duration::from_millis(100),
+            convert_to_preferred: false,
+            max_frame_bytes: Self::DEFAULT_MAX_FRAME_BYTES,
         }
     }
 }
 
+/// `wl_shm::Format` codes as announced by the compositor.
+///
+/// ARGB8888 and XRGB8888 use the special values 0 and 1; every other
+/// format uses its DRM fourcc code.
+pub mod wl_shm_format {
+    /// 32-bit ARGB, little-endian (B, G, R, A in memory).
+    pub const ARGB8888: u32 = 0;
+    /// 32-bit XRGB, little-endian (B, G, R, X in memory).
+    pub const XRGB8888: u32 = 1;
+    /// 32-bit ABGR, little-endian (R, G, B, A in memory).
+    pub const ABGR8888: u32 = 0x3432_4241;
+    /// 32-bit XBGR, little-endian (R, G, B, X in memory).
+    pub const XBGR8888: u32 = 0x3432_4258;
+    /// 24-bit RGB, little-endian (B, G, R in memory).
+    pub const RGB888: u32 = 0x3432_4752;
+    /// 24-bit BGR, little-endian (R, G, B in memory).
+    pub const BGR888: u32 = 0x3432_4742;
+    /// 32-bit XRGB with 10-bit channels, little-endian.
+    pub const XRGB2101010: u32 = 0x3033_5258;
+    /// YUV 4:2:0, Y plane followed by an interleaved UV plane.
+    pub const NV12: u32 = 0x3231_564E;
+}
+
+/// Maps a `wl_shm::Format` code to the corresponding [`FrameFormat`].
+///
+/// # Errors
+///
+/// Returns [`CaptureError::ProtocolNotSupported`] for formats we cannot
+/// represent, rather than guessing and mislabelling the pixel data.
+pub fn wl_shm_format_to_frame_format(format: u32) -> CaptureResult<FrameFormat> {
+    match format {
+        wl_shm_format::ARGB8888 => Ok(FrameFormat::Bgra8888),
+        wl_shm_format::XRGB8888 => Ok(FrameFormat::Xrgb8888),
+        wl_shm_format::ABGR8888 => Ok(FrameFormat::Rgba8888),
+        wl_shm_format::XBGR8888 => Ok(FrameFormat::Xbgr8888),
+        wl_shm_format::RGB888 => Ok(FrameFormat::Rgb888),
+        wl_shm_format::BGR888 => Ok(FrameFormat::Bgr888),
+        wl_shm_format::XRGB2101010 => Ok(FrameFormat::Xrgb2101010),
+        wl_shm_format::NV12 => Ok(FrameFormat::Nv12),
+        other => Err(CaptureError::ProtocolNotSupported(format!(
+            "unsupported wl_shm format 0x{other:08x}"
+        ))),
+    }
+}
+
+/// Maps a [`FrameFormat`] to the `wl_shm::Format` code that produces it.
+#[must_use]
+pub const fn frame_format_to_wl_shm_format(format: FrameFormat) -> u32 {
+    match format {
+        FrameFormat::Bgra8888 => wl_shm_format::ARGB8888,
+        FrameFormat::Xrgb8888 => wl_shm_format::XRGB8888,
+        FrameFormat::Rgba8888 => wl_shm_format::ABGR8888,
+        FrameFormat::Xbgr8888 => wl_shm_format::XBGR8888,
+        FrameFormat::Rgb888 => wl_shm_format::RGB888,
+        FrameFormat::Bgr888 => wl_shm_format::BGR888,
+        FrameFormat::Xrgb2101010 => wl_shm_format::XRGB2101010,
+        FrameFormat::Nv12 => wl_shm_format::NV12,
+    }
+}
+
+/// Clamps output dimensions to `1..=`[`ShmCaptureConfig::MAX_DIMENSION`].
+fn clamp_dimensions(width: u32, height: u32) -> (u32, u32) {
+    let max = ShmCaptureConfig::MAX_DIMENSION;
+    let clamped = (width.clamp(1, max), height.clamp(1, max));
+    if clamped != (width, height) {
+        warn!(
+            width,
+            height, max, "Output dimensions out of range, clamping"
+        );
+    }
+    clamped
+}
+
+/// Computes `(stride, size)` for a frame, rejecting sizes above `max_bytes`.
+///
+/// # Errors
+///
+/// Returns [`CaptureError::BufferAllocation`] with the attempted size when
+/// the frame would exceed `max_bytes` (or overflow).
+fn frame_layout(
+    width: u32,
+    height: u32,
+    format: FrameFormat,
+    max_bytes: usize,
+) -> CaptureResult<(u32, usize)> {
+    // Widen so that even u32::MAX x u32::MAX cannot overflow
+    let stride = u128::from(width) * format.bytes_per_pixel() as u128;
+    // Planar formats add rows for their extra planes
+    let rows = format.frame_size(1, height as usize) as u128;
+    let size = stride * rows;
+
+    match (u32::try_from(stride), usize::try_from(size)) {
+        (Ok(stride), Ok(size)) if size <= max_bytes => Ok((stride, size)),
+        _ => Err(CaptureError::BufferAllocation(format!(
+            "{width}x{height} frame needs {size} bytes, limit is {max_bytes}"
+        ))),
+    }
+}
+
 /// Internal state for the capture backend.
 struct ShmCaptureState {
     /// Current frame sequence number.
     sequence: AtomicU64,
-    /// Whether a stream is active.
-    streaming: AtomicBool,
-    /// Broadcast sender for streaming frames.
-    stream_tx: Option<broadcast::Sender<Arc<CaptureFrame>>>,
     /// Screen dimensions (width, height).
     dimensions: (u32, u32),
-    /// Current format.
-    format: FrameFormat,
+    /// `wl_shm` format announced by the compositor for its buffers.
+    buffer_format: u32,
 }
 
 impl ShmCaptureState {
     fn new(width: u32, height: u32, format: FrameFormat) -> Self {
         Self {
             sequence: AtomicU64::new(0),
-            streaming: AtomicBool::new(false),
-            stream_tx: None,
             dimensions: (width, height),
-            format,
+            buffer_format: frame_format_to_wl_shm_format(format),
         }
     }
 
@@ -88,6 +209,26 @@ impl ShmCaptureState {
     }
 }
 
+/// A running capture stream.
+struct StreamTask {
+    /// Tells the streaming loop to stop; dropping it does the same
+    stop: oneshot::Sender<()>,
+    /// The streaming loop
+    handle: JoinHandle<()>,
+    /// Where the loop sends frames, for late subscribers; the stream
+    /// closes once both this and the loop are gone
+    frames: Arc<StreamBroadcast<Arc<CaptureFrame>>>,
+}
+
+impl StreamTask {
+    /// Signals the loop to stop, returning its handle to join.
+    fn stop(self) -> JoinHandle<()> {
+        // The loop may already have exited, dropping the receiver
+        let _ = self.stop.send(());
+        self.handle
+    }
+}
+
 /// Tier 2 screen capture using shared memory.
 ///
 /// This backend is designed to work in environments where GPU
@@ -105,11 +246,19 @@ pub struct ShmCapture {
     state: Arc<RwLock<ShmCaptureState>>,
     /// Lock for capture operations (ensures single capture at a time).
     capture_lock: Arc<Mutex<()>>,
+    /// Active stream, if any.
+    stream: std::sync::Mutex<Option<StreamTask>>,
+    /// Statistics of the current or last stream.
+    stats: std::sync::Mutex<Option<Arc<StreamStats>>>,
+    /// Transform of the captured output.
+    transform: OutputTransform,
 }
 
 impl ShmCapture {
     /// Creates a new shared memory capture backend.
     ///
+    /// Dimensions are clamped to [`ShmCaptureConfig::MAX_DIMENSION`].
+    ///
     /// # Arguments
     ///
     /// * `width` - Screen width in pixels
@@ -117,6 +266,7 @@ impl ShmCapture {
     /// * `config` - Capture configuration
     #[must_use]
     pub fn new(width: u32, height: u32, config: ShmCaptureConfig) -> Self {
+        let (width, height) = clamp_dimensions(width, height);
         let capabilities = CaptureCapabilities::shm(vec![
             FrameFormat::Bgra8888,
             FrameFormat::Rgba8888,
@@ -137,9 +287,74 @@ impl ShmCapture {
             capabilities,
             state: Arc::new(RwLock::new(state)),
             capture_lock: Arc::new(Mutex::new(())),
+            stream: std::sync::Mutex::default(),
+            stats: std::sync::Mutex::default(),
+            transform: OutputTransform::Normal,
         }
     }
 
+    /// Captures an output with `transform`, delivering upright frames in
+    /// packed formats; planar frames report the transform for the client
+    /// to apply, as [`plan_transform`](super::plan_transform) describes.
+    ///
+    /// The dimensions given at construction are the upright ones. The
+    /// compositor fills the buffer in the panel's native orientation, and
+    /// each frame is rotated before any format conversion.
+    #[must_use]
+    pub fn with_transform(mut self, transform: OutputTransform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Creates a new shared memory capture backend, validating its
+    /// settings.
+    ///
+    /// Dimensions above [`ShmCaptureConfig::MAX_DIMENSION`] are still
+    /// clamped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::InvalidRequest`] for zero dimensions or a
+    /// zero target frame rate.
+    pub fn try_new(width: u32, height: u32, config: ShmCaptureConfig) -> CaptureResult<Self> {
+        validate_settings(width, height, config.target_fps)?;
+        Ok(Self::new(width, height, config))
+    }
+
+    /// Returns a handle capturing from the same state, without a stream.
+    fn share(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            capabilities: self.capabilities.clone(),
+            state: Arc::clone(&self.state),
+            capture_lock: Arc::clone(&self.capture_lock),
+            stream: std::sync::Mutex::default(),
+            stats: std::sync::Mutex::default(),
+            transform: self.transform,
+        }
+    }
+
+    /// Takes the active stream, leaving none.
+    fn take_stream(&self) -> Option<StreamTask> {
+        self.stream
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    /// Joins the running stream, if any.
+    ///
+    /// Unlike the receiver from [`ScreenCapture::start_stream`], the
+    /// subscriber first gets the most recent frame, so a consumer joining
+    /// mid-stream has a picture at once.
+    pub fn subscribe(&self) -> Option<StreamSubscriber<Arc<CaptureFrame>>> {
+        self.stream
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .map(|stream| stream.frames.subscribe())
+    }
+
     /// Creates with default configuration.
     #[must_use]
     pub fn with_defaults(width: u32, height: u32) -> Self {
@@ -147,12 +362,25 @@ impl ShmCapture {
     }
 
     /// Updates the screen dimensions.
+    ///
+    /// Dimensions are clamped to `1..=`[`ShmCaptureConfig::MAX_DIMENSION`].
     pub async fn resize(&self, width: u32, height: u32) {
+        let (width, height) = clamp_dimensions(width, height);
         let mut state = self.state.write().await;
         state.dimensions = (width, height);
         info!(width, height, "SHM capture resized");
     }
 
+    /// Records the `wl_shm` format the compositor announced for its buffers.
+    ///
+    /// This mirrors the screencopy `buffer` event: subsequent frames are
+    /// labelled with this format, not the preferred one.
+    pub async fn set_buffer_format(&self, wl_format: u32) {
+        let mut state = self.state.write().await;
+        state.buffer_format = wl_format;
+        debug!(wl_format, "SHM buffer format updated");
+    }
+
     /// Performs the actual capture operation.
     ///
     /// In a real implementation, this would:
@@ -169,13 +397,16 @@ impl ShmCapture {
 
         let state = self.state.read().await;
         let (width, height) = state.dimensions;
-        let format = state.format;
+        let buffer_format = state.buffer_format;
         let sequence = state.next_sequence();
         drop(state);
 
-        // Calculate buffer size
-        let stride = width * format.bytes_per_pixel() as u32;
-        let size = (stride * height) as usize;
+        // Label the frame with what the compositor actually filled
+        let format = wl_shm_format_to_frame_format(buffer_format)?;
+        let (width, height, handling) = plan_transform(self.transform, format, width, height);
+
+        // Validate buffer size before allocating anything
+        let (stride, size) = frame_layout(width, height, format, self.config.max_frame_bytes)?;
 
         debug!(
             sequence,
@@ -221,7 +452,28 @@ impl ShmCapture {
             "SHM capture complete"
         );
 
-        Ok(CaptureFrame::new(metadata, data))
+        let frame = finish_transform(CaptureFrame::new(metadata, data), self.transform, handling)?;
+        Ok(self.apply_preferred_format(frame))
+    }
+
+    /// Converts a frame to the preferred format if configured to do so.
+    ///
+    /// Frames are returned unchanged (and correctly labelled) when no
+    /// conversion was requested or the conversion is not supported.
+    fn apply_preferred_format(&self, frame: CaptureFrame) -> CaptureFrame {
+        let preferred = self.config.preferred_format;
+        if !self.config.convert_to_preferred || frame.format() == preferred {
+            return frame;
+        }
+
+        frame.convert_to(preferred).unwrap_or_else(|| {
+            debug!(
+                actual = %frame.format(),
+                preferred = %preferred,
+                "No conversion available, keeping compositor format"
+            );
+            frame
+        })
     }
 
     /// Generates a test pattern for development/testing.
@@ -235,15 +487,19 @@ impl ShmCapture {
         format: FrameFormat,
         sequence: u64,
     ) -> Vec<u8> {
-        let stride = width * format.bytes_per_pixel() as u32;
-        let mut data = vec![0u8; (stride * height) as usize];
+        // Pixel sizes are a few bytes; a format too wide for u32 has no pattern
+        let Ok(bpp) = u32::try_from(format.bytes_per_pixel()) else {
+            return Vec::new();
+        };
+        let stride = width * bpp;
+        let mut data = vec![0u8; format.frame_size(stride as usize, height as usize)];
 
         // Generate a simple gradient with a moving bar
         let bar_position = ((sequence * 10) % width as u64) as u32;
 
         for y in 0..height {
             for x in 0..width {
-                let offset = ((y * stride) + (x * 4)) as usize;
+                let offset = ((y * stride) + (x * bpp)) as usize;
 
                 // Gradient background
                 let r = (x * 255 / width) as u8;
@@ -265,32 +521,62 @@ impl ShmCapture {
                         data[offset + 2] = r;
                         data[offset + 3] = 255;
                     },
-                    FrameFormat::Rgba8888 => {
+                    FrameFormat::Rgba8888 | FrameFormat::Xbgr8888 => {
                         data[offset] = r;
                         data[offset + 1] = g;
                         data[offset + 2] = b;
                         data[offset + 3] = 255;
                     },
-                    _ => {
+                    FrameFormat::Rgb888 => {
+                        data[offset] = b;
+                        data[offset + 1] = g;
+                        data[offset + 2] = r;
+                    },
+                    FrameFormat::Bgr888 => {
+                        data[offset] = r;
+                        data[offset + 1] = g;
+                        data[offset + 2] = b;
+                    },
+                    FrameFormat::Xrgb8888 => {
                         // Default to BGRA order
                         data[offset] = b;
                         data[offset + 1] = g;
                         data[offset + 2] = r;
                         data[offset + 3] = 255;
                     },
+                    FrameFormat::Xrgb2101010 => {
+                        let pixel = u32::from(r) << 22 | u32::from(g) << 12 | u32::from(b) << 2;
+                        data[offset..offset + 4].copy_from_slice(&pixel.to_le_bytes());
+                    },
+                    FrameFormat::Nv12 => {
+                        // BT.601 luma; the chroma plane is filled below
+                        let luma =
+                            (77 * u32::from(r) + 150 * u32::from(g) + 29 * u32::from(b)) >> 8;
+                        data[offset] = u8::try_from(luma).unwrap_or(u8::MAX);
+                    },
                 }
             }
         }
 
+        if format == FrameFormat::Nv12 {
+            // Neutral chroma keeps the pattern grey-scale
+            data[(stride * height) as usize..].fill(128);
+        }
+
         data
     }
 
-    /// Runs the streaming loop.
-    #[allow(dead_code)]
+    /// Runs the streaming loop until `stop` fires or is dropped.
+    ///
+    /// A frame whose capture is interrupted by `stop` is discarded, so
+    /// nothing is sent once the stop has been observed. Captured and
+    /// failed frames are recorded in `stats`.
     async fn streaming_loop(
-        self: Arc<Self>,
+        self,
         target_fps: u32,
-        tx: broadcast::Sender<Arc<CaptureFrame>>,
+        frames: Arc<StreamBroadcast<Arc<CaptureFrame>>>,
+        mut stop: oneshot::Receiver<()>,
+        stats: Arc<StreamStats>,
     ) {
         let frame_duration = Duration::from_secs_f64(1.0 / f64::from(target_fps));
         let mut interval = tokio::time::interval(frame_duration);
@@ -299,23 +585,22 @@ impl ShmCapture {
         info!(target_fps, "Starting SHM capture stream");
 
         loop {
-            interval.tick().await;
-
-            // Check if we should stop
-            let state = self.state.read().await;
-            if !state.streaming.load(Ordering::Relaxed) {
-                break;
-            }
-            drop(state);
-
-            // Capture frame
-            match self.do_capture().await {
+            let result = tokio::select! {
+                biased;
+                _ = &mut stop => break,
+                result = async {
+                    interval.tick().await;
+                    self.do_capture().await
+                } => result,
+            };
+
+            match result {
                 Ok(frame) => {
-                    let frame = Arc::new(frame);
-                    // Ignore send errors (no receivers)
-                    let _ = tx.send(frame);
+                    stats.record_frame(frame.metadata.capture_latency());
+                    frames.send(Arc::new(frame));
                 },
                 Err(e) => {
+                    stats.record_drop(&e);
                     warn!(error = %e, "Frame capture failed, skipping");
                 },
             }
@@ -343,43 +628,71 @@ impl ScreenCapture for ShmCapture {
         // Clamp FPS to reasonable bounds
         let fps = target_fps.clamp(1, self.capabilities.max_fps);
 
-        let (tx, rx) = broadcast::channel(8); // Buffer a few frames
-
-        // Update state
-        let state = self.state.clone();
-        let tx_clone = tx.clone();
-
-        tokio::spawn(async move {
-            let mut state = state.write().await;
-            state.streaming.store(true, Ordering::Relaxed);
-            state.stream_tx = Some(tx_clone);
-        });
+        let frames = Arc::new(StreamBroadcast::raw(8)); // Buffer a few frames
+        let rx = frames.subscribe().into_receiver();
+        let (stop, stop_rx) = oneshot::channel();
+        let stats = Arc::new(StreamStats::new());
+        *self.stats.lock().unwrap_or_else(PoisonError::into_inner) = Some(Arc::clone(&stats));
+        let handle =
+            tokio::spawn(
+                self.share()
+                    .streaming_loop(fps, Arc::clone(&frames), stop_rx, stats),
+            );
+
+        // A new stream replaces the old one, whose loop is left to wind down
+        let previous = self
+            .stream
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .replace(StreamTask {
+                stop,
+                handle,
+                frames,
+            });
+        if let Some(previous) = previous {
+            previous.stop();
+        }
 
-        // Start streaming loop
-        // Note: We need to clone self into an Arc for the spawned task
-        // This is a limitation - in production, ShmCapture itself would be Arc-wrapped
         info!(fps, "Stream started");
-
         Ok(rx)
     }
 
     fn stop_stream(&self) -> CaptureResult<()> {
-        let state = self.state.clone();
-
-        tokio::spawn(async move {
-            let mut state = state.write().await;
-            state.streaming.store(false, Ordering::Relaxed);
-            state.stream_tx = None;
-        });
-
-        info!("Stream stop requested");
+        if let Some(stream) = self.take_stream() {
+            stream.stop();
+            info!("Stream stop requested");
+        }
         Ok(())
     }
 
+    fn stop_stream_and_wait(&self) -> Pin<Box<dyn Future<Output = CaptureResult<()>> + Send + '_>> {
+        let stream = self.take_stream();
+        Box::pin(async move {
+            let Some(stream) = stream else {
+                return Ok(());
+            };
+            stream
+                .stop()
+                .await
+                .map_err(|e| CaptureError::Internal(format!("capture stream task failed: {e}")))?;
+            info!("Stream stopped");
+            Ok(())
+        })
+    }
+
     fn is_capturing(&self) -> bool {
-        // Can't easily check async state synchronously
-        // In production, use a separate atomic flag
-        false
+        self.stream
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .is_some_and(|stream| !stream.handle.is_finished())
+    }
+
+    fn stream_stats(&self) -> Option<Arc<StreamStats>> {
+        self.stats
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
     }
 }
 
@@ -427,11 +740,26 @@ impl ShmCaptureBuilder {
         self
     }
 
+    /// Converts frames to the preferred format when the compositor uses another.
+    #[must_use]
+    pub fn convert_to_preferred(mut self, convert: bool) -> Self {
+        self.config.convert_to_preferred = convert;
+        self
+    }
+
+    /// Sets the largest frame buffer, in bytes, that will be allocated.
+    #[must_use]
+    pub fn max_frame_bytes(mut self, bytes: usize) -> Self {
+        self.config.max_frame_bytes = bytes;
+        self
+    }
+
     /// Builds the capture backend.
     ///
     /// # Panics
     ///
     /// Panics if dimensions are not set.
+    #[deprecated(note = "use `try_build`, which returns an error instead of panicking")]
     #[must_use]
     pub fn build(self) -> ShmCapture {
         let width = self.width.expect("width must be set");
@@ -439,15 +767,18 @@ impl ShmCaptureBuilder {
         ShmCapture::new(width, height, self.config)
     }
 
-    /// Builds the capture backend, returning an error if dimensions not set.
+    /// Builds the capture backend.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CaptureError::InvalidRequest`] if dimensions are not set
+    /// or, as in [`ShmCapture::try_new`], are zero or the target frame
+    /// rate is.
     pub fn try_build(self) -> CaptureResult<ShmCapture> {
-        let width = self
-            .width
-            .ok_or_else(|| CaptureError::Internal("width not set".into()))?;
-        let height = self
-            .height
-            .ok_or_else(|| CaptureError::Internal("height not set".into()))?;
-        Ok(ShmCapture::new(width, height, self.config))
+        let (Some(width), Some(height)) = (self.width, self.height) else {
+            return Err(CaptureError::InvalidRequest("dimensions not set".into()));
+        };
+        ShmCapture::try_new(width, height, self.config)
     }
 }
 
@@ -455,6 +786,21 @@ impl ShmCaptureBuilder {
 mod tests {
     use super::*;
     use crate::capture::{CaptureTier, ScreenCaptureExt};
+    use ion_core::backend::TransformHandling;
+
+    #[tokio::test]
+    async fn shm_rotated_output_delivered_upright() {
+        let capture =
+            ShmCapture::with_defaults(1080, 1920).with_transform(OutputTransform::Flipped270);
+
+        let frame = capture.do_capture().await.unwrap();
+
+        assert_eq!((frame.width(), frame.height()), (1080, 1920));
+        assert_eq!(frame.metadata.stride, 1080 * 4);
+        assert_eq!(frame.data().len(), 1080 * 1920 * 4);
+        assert_eq!(frame.metadata.transform, OutputTransform::Flipped270);
+        assert_eq!(frame.metadata.transform_handling, TransformHandling::Cpu);
+    }
 
     #[tokio::test]
     async fn shm_capture_single_frame() {
@@ -494,6 +840,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[allow(deprecated)]
     async fn shm_capture_builder() {
         let capture = ShmCaptureBuilder::new()
             .dimensions(1280, 720)
@@ -521,6 +868,10 @@ mod tests {
         assert_eq!(config.buffer_count, 2);
         assert_eq!(config.preferred_format, FrameFormat::Bgra8888);
         assert_eq!(config.timeout, Duration::from_millis(100));
+        assert_eq!(
+            config.max_frame_bytes,
+            ShmCaptureConfig::DEFAULT_MAX_FRAME_BYTES
+        );
     }
 
     #[test]
@@ -544,6 +895,108 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn shm_stream_delivers_frames() {
+        let capture = ShmCapture::with_defaults(64, 48);
+        let mut rx = capture.start_stream(60).unwrap();
+        assert!(capture.is_capturing());
+
+        let first = rx.recv().await.unwrap();
+        let second = rx.recv().await.unwrap();
+        assert!(second.metadata.sequence > first.metadata.sequence);
+        capture.stop_stream().unwrap();
+        assert!(!capture.is_capturing());
+    }
+
+    #[tokio::test]
+    async fn shm_no_frames_after_stop_stream_and_wait() {
+        let capture = ShmCapture::with_defaults(64, 48);
+        let mut rx = capture.start_stream(60).unwrap();
+        rx.recv().await.unwrap();
+
+        capture.stop_stream_and_wait().await.unwrap();
+        assert!(!capture.is_capturing());
+
+        // Only frames sent before the stop remain, then the stream closes
+        let buffered = rx.len();
+        for _ in 0..buffered {
+            rx.try_recv().unwrap();
+        }
+        assert_eq!(
+            rx.try_recv().unwrap_err(),
+            broadcast::error::TryRecvError::Closed
+        );
+
+        // Stopping again is a no-op, and a new stream can start
+        capture.stop_stream_and_wait().await.unwrap();
+        let mut rx = capture.start_stream(60).unwrap();
+        assert!(rx.recv().await.is_ok());
+        capture.stop_stream_and_wait().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn shm_late_subscriber_gets_latest_frame_at_once() {
+        let capture = ShmCapture::with_defaults(64, 48);
+        assert!(capture.subscribe().is_none());
+        let mut rx = capture.start_stream(60).unwrap();
+        rx.recv().await.unwrap();
+        let latest = rx.recv().await.unwrap();
+
+        let mut late = capture.subscribe().unwrap();
+        let first = late.try_recv().unwrap();
+        assert!(first.metadata.sequence >= latest.metadata.sequence);
+        capture.stop_stream_and_wait().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn shm_stream_stats_count_captured_and_dropped_frames() {
+        let capture = ShmCapture::with_defaults(64, 48);
+        assert!(capture.stream_stats().is_none());
+        let mut rx = capture.start_stream(60).unwrap();
+        let mut received = 0;
+        for _ in 0..3 {
+            rx.recv().await.unwrap();
+            received += 1;
+        }
+
+        // Hold the next capture back until the loop waits on the lock, then
+        // let exactly one capture fail: the lock is fair, so the loop runs
+        // before the lock is taken back
+        let guard = capture.capture_lock.lock().await;
+        capture.set_buffer_format(0xdead_beef).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drop(guard);
+        let guard = capture.capture_lock.lock().await;
+        capture.set_buffer_format(wl_shm_format::ARGB8888).await;
+        drop(guard);
+
+        for _ in 0..2 {
+            rx.recv().await.unwrap();
+            received += 1;
+        }
+        capture.stop_stream_and_wait().await.unwrap();
+        while rx.try_recv().is_ok() {
+            received += 1;
+        }
+
+        let stats = capture.stream_stats().unwrap().snapshot();
+        assert_eq!(stats.captured, received);
+        assert_eq!(stats.dropped, 1);
+        assert!(stats.last_error.unwrap().contains("0xdeadbeef"));
+        // Every capture takes at least its simulated 5ms
+        let latency = stats.average_latency.unwrap();
+        assert!(latency >= Duration::from_millis(5), "{latency:?}");
+        assert!(latency < capture.config.timeout, "{latency:?}");
+        assert!(stats.p99_latency.unwrap() >= latency);
+        assert!(stats.fps > 0.0);
+
+        // A restarted stream starts from zero
+        let _rx = capture.start_stream(60).unwrap();
+        let restarted = capture.stream_stats().unwrap().snapshot();
+        assert_eq!((restarted.captured, restarted.dropped), (0, 0));
+        capture.stop_stream_and_wait().await.unwrap();
+    }
+
     #[test]
     fn shm_is_capturing() {
         let capture = ShmCapture::with_defaults(100, 100);
@@ -580,12 +1033,29 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn shm_builder_rejects_zero_settings() {
+        for builder in [
+            ShmCaptureBuilder::new().dimensions(0, 1080),
+            ShmCaptureBuilder::new().dimensions(1920, 0),
+            ShmCaptureBuilder::new()
+                .dimensions(1920, 1080)
+                .target_fps(0),
+        ] {
+            assert!(matches!(
+                builder.try_build(),
+                Err(CaptureError::InvalidRequest(_))
+            ));
+        }
+    }
+
     #[tokio::test]
     async fn shm_builder_timeout() {
         let capture = ShmCaptureBuilder::new()
             .dimensions(100, 100)
             .timeout(Duration::from_secs(5))
-            .build();
+            .try_build()
+            .unwrap();
         assert_eq!(capture.config.timeout, Duration::from_secs(5));
     }
 
@@ -673,8 +1143,187 @@ mod tests {
             buffer_count: 4,
             preferred_format: FrameFormat::Rgba8888,
             timeout: Duration::from_millis(50),
+            convert_to_preferred: true,
+            max_frame_bytes: 1024,
         };
         assert_eq!(config.target_fps, 60);
         assert_eq!(config.buffer_count, 4);
     }
+
+    #[test]
+    fn wl_shm_format_mapping_common_formats() {
+        assert_eq!(
+            wl_shm_format_to_frame_format(wl_shm_format::ARGB8888).unwrap(),
+            FrameFormat::Bgra8888
+        );
+        assert_eq!(
+            wl_shm_format_to_frame_format(wl_shm_format::XRGB8888).unwrap(),
+            FrameFormat::Xrgb8888
+        );
+        assert_eq!(
+            wl_shm_format_to_frame_format(wl_shm_format::ABGR8888).unwrap(),
+            FrameFormat::Rgba8888
+        );
+        assert_eq!(
+            wl_shm_format_to_frame_format(wl_shm_format::XBGR8888).unwrap(),
+            FrameFormat::Xbgr8888
+        );
+        assert_eq!(
+            wl_shm_format_to_frame_format(wl_shm_format::RGB888).unwrap(),
+            FrameFormat::Rgb888
+        );
+        assert_eq!(
+            wl_shm_format_to_frame_format(wl_shm_format::BGR888).unwrap(),
+            FrameFormat::Bgr888
+        );
+    }
+
+    #[test]
+    fn wl_shm_format_mapping_unknown() {
+        // DRM_FORMAT_YUYV has no FrameFormat equivalent
+        let result = wl_shm_format_to_frame_format(0x5659_5559);
+        assert!(matches!(result, Err(CaptureError::ProtocolNotSupported(_))));
+    }
+
+    #[test]
+    fn wl_shm_format_mapping_round_trip() {
+        for format in [
+            FrameFormat::Bgra8888,
+            FrameFormat::Rgba8888,
+            FrameFormat::Xrgb8888,
+            FrameFormat::Xbgr8888,
+            FrameFormat::Rgb888,
+            FrameFormat::Bgr888,
+            FrameFormat::Xrgb2101010,
+            FrameFormat::Nv12,
+        ] {
+            let wl = frame_format_to_wl_shm_format(format);
+            assert_eq!(wl_shm_format_to_frame_format(wl).unwrap(), format);
+        }
+    }
+
+    #[tokio::test]
+    async fn shm_frame_labelled_with_compositor_format() {
+        let capture = ShmCapture::with_defaults(16, 16);
+        capture.set_buffer_format(wl_shm_format::XRGB8888).await;
+
+        let frame = capture.do_capture().await.unwrap();
+        assert_eq!(frame.format(), FrameFormat::Xrgb8888);
+    }
+
+    #[tokio::test]
+    async fn shm_frame_24bit_compositor_format() {
+        let capture = ShmCapture::with_defaults(16, 16);
+        capture.set_buffer_format(wl_shm_format::RGB888).await;
+
+        let frame = capture.do_capture().await.unwrap();
+        assert_eq!(frame.format(), FrameFormat::Rgb888);
+        assert_eq!(frame.metadata.stride, 16 * 3);
+        assert_eq!(frame.data().len(), 16 * 16 * 3);
+    }
+
+    #[tokio::test]
+    async fn shm_frame_nv12_compositor_format() {
+        let capture = ShmCapture::with_defaults(16, 15);
+        capture.set_buffer_format(wl_shm_format::NV12).await;
+
+        let frame = capture.do_capture().await.unwrap();
+        assert_eq!(frame.format(), FrameFormat::Nv12);
+        assert_eq!(frame.metadata.stride, 16);
+        assert_eq!(frame.data().len(), 16 * 15 + 16 * 8);
+        assert_eq!(frame.data().len(), frame.metadata.frame_size());
+    }
+
+    #[tokio::test]
+    async fn shm_unknown_compositor_format_errors() {
+        let capture = ShmCapture::with_defaults(16, 16);
+        capture.set_buffer_format(0xdead_beef).await;
+
+        let result = capture.do_capture().await;
+        assert!(matches!(result, Err(CaptureError::ProtocolNotSupported(_))));
+    }
+
+    #[tokio::test]
+    async fn shm_converts_only_when_requested() {
+        let capture = ShmCapture::with_defaults(4, 4);
+        capture.set_buffer_format(wl_shm_format::ABGR8888).await;
+        let frame = capture.do_capture().await.unwrap();
+        assert_eq!(frame.format(), FrameFormat::Rgba8888);
+
+        let capture = ShmCaptureBuilder::new()
+            .dimensions(4, 4)
+            .format(FrameFormat::Bgra8888)
+            .convert_to_preferred(true)
+            .try_build()
+            .unwrap();
+        capture.set_buffer_format(wl_shm_format::ABGR8888).await;
+        let frame = capture.do_capture().await.unwrap();
+        assert_eq!(frame.format(), FrameFormat::Bgra8888);
+    }
+
+    #[tokio::test]
+    async fn shm_unsupported_conversion_keeps_actual_format() {
+        let capture = ShmCaptureBuilder::new()
+            .dimensions(4, 4)
+            .format(FrameFormat::Bgra8888)
+            .convert_to_preferred(true)
+            .try_build()
+            .unwrap();
+        capture.set_buffer_format(wl_shm_format::RGB888).await;
+
+        let frame = capture.do_capture().await.unwrap();
+        assert_eq!(frame.format(), FrameFormat::Rgb888);
+    }
+
+    #[tokio::test]
+    async fn shm_absurd_resolution_rejected() {
+        let capture = ShmCapture::with_defaults(640, 480);
+
+        // Clamped to MAX_DIMENSION, which still exceeds the default limit
+        capture.resize(100_000, 100_000).await;
+        assert_eq!(
+            capture.state.read().await.dimensions,
+            (
+                ShmCaptureConfig::MAX_DIMENSION,
+                ShmCaptureConfig::MAX_DIMENSION
+            )
+        );
+
+        let err = capture.do_capture().await.unwrap_err();
+        assert!(matches!(err, CaptureError::BufferAllocation(_)));
+        assert!(err.to_string().contains("1073741824 bytes"));
+    }
+
+    #[test]
+    fn shm_frame_layout_overflow_rejected() {
+        let err = frame_layout(u32::MAX, u32::MAX, FrameFormat::Bgra8888, usize::MAX);
+        assert!(matches!(err, Err(CaptureError::BufferAllocation(_))));
+    }
+
+    #[tokio::test]
+    async fn shm_4k_resolution_accepted() {
+        let capture = ShmCaptureBuilder::new()
+            .dimensions(3840, 2160)
+            .timeout(Duration::from_secs(1))
+            .try_build()
+            .unwrap();
+
+        let frame = capture.do_capture().await.unwrap();
+        assert_eq!(frame.width(), 3840);
+        assert_eq!(frame.data().len(), 3840 * 2160 * 4);
+    }
+
+    #[tokio::test]
+    async fn shm_custom_frame_limit() {
+        let capture = ShmCaptureBuilder::new()
+            .dimensions(64, 64)
+            .max_frame_bytes(64 * 64 * 4 - 1)
+            .try_build()
+            .unwrap();
+
+        assert!(matches!(
+            capture.do_capture().await,
+            Err(CaptureError::BufferAllocation(_))
+        ));
+    }
 }
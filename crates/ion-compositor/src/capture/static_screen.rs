@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Pausing the encoder while the screen does not change.
+//!
+//! Backends that report damage only deliver frames when something changed.
+//! The rest deliver at the full rate regardless, and encoding the same
+//! picture thirty times a second burns CPU for nothing. A
+//! [`StaticDetector`] fingerprints each frame before encoding; once enough
+//! consecutive frames are identical it pauses the encoder, and the client
+//! keeps showing the last picture. The first frame that differs resumes
+//! encoding with a keyframe.
+
+use ion_core::backend::CaptureHandle;
+use tracing::debug;
+
+use super::CaptureFrame;
+
+/// Identical frames in a row before encoding pauses.
+pub const DEFAULT_STATIC_THRESHOLD: u32 = 30;
+
+/// Change in whether a stream is producing encoded frames.
+///
+/// Forwarded to the client so that a frame rate dropping to zero while
+/// paused is not mistaken for a stall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamEvent {
+    /// The screen is static; no frames follow until it changes
+    Paused,
+    /// The screen changed; frames follow, starting with a keyframe
+    Resumed,
+}
+
+impl StreamEvent {
+    /// Name used in logs and client messages.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Paused => "paused",
+            Self::Resumed => "resumed",
+        }
+    }
+}
+
+impl std::fmt::Display for StreamEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Detects a static screen from consecutive frames.
+#[derive(Debug)]
+pub struct StaticDetector {
+    threshold: u32,
+    last: Option<u64>,
+    /// Frames in a row identical to their predecessor
+    identical: u32,
+    paused: bool,
+    keyframes: Option<CaptureHandle>,
+}
+
+impl StaticDetector {
+    /// Creates a detector that pauses after `threshold` identical frames.
+    ///
+    /// A `threshold` of 0 is treated as 1.
+    #[must_use]
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            last: None,
+            identical: 0,
+            paused: false,
+            keyframes: None,
+        }
+    }
+
+    /// Requests a keyframe on `handle`'s stream when encoding resumes.
+    #[must_use]
+    pub fn with_keyframe_handle(mut self, handle: CaptureHandle) -> Self {
+        self.keyframes = Some(handle);
+        self
+    }
+
+    /// Whether the encoder should skip frames.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Feeds the next captured frame.
+    ///
+    /// Call before encoding each frame, then skip it if
+    /// [`Self::is_paused`]. Returns an event when the paused state changes.
+    pub fn observe(&mut self, frame: &CaptureFrame) -> Option<StreamEvent> {
+        let fingerprint = fingerprint(frame);
+        let unchanged = self.last == Some(fingerprint);
+        self.last = Some(fingerprint);
+
+        if unchanged {
+            self.identical = self.identical.saturating_add(1);
+            if !self.paused && self.identical >= self.threshold {
+                self.paused = true;
+                debug!(frames = self.identical, "Screen static, pausing encoder");
+                return Some(StreamEvent::Paused);
+            }
+            return None;
+        }
+
+        self.identical = 0;
+        if !self.paused {
+            return None;
+        }
+        self.paused = false;
+        if let Some(handle) = &self.keyframes {
+            handle.request_keyframe();
+        }
+        debug!("Screen changed, resuming encoder");
+        Some(StreamEvent::Resumed)
+    }
+}
+
+impl Default for StaticDetector {
+    fn default() -> Self {
+        Self::new(DEFAULT_STATIC_THRESHOLD)
+    }
+}
+
+/// FNV-1a over the frame's pixels, a word at a time, and its geometry.
+///
+/// Not collision resistant, only cheap; a false match costs at most one
+/// stale frame until the next real change.
+fn fingerprint(frame: &CaptureFrame) -> u64 {
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let mix = |hash: u64, word: u64| (hash ^ word).wrapping_mul(PRIME);
+    let data = frame.data();
+    let mut words = data.chunks_exact(8);
+    let mut hash = words.by_ref().fold(0xcbf2_9ce4_8422_2325, |hash, word| {
+        mix(
+            hash,
+            u64::from_le_bytes(word.try_into().unwrap_or_default()),
+        )
+    });
+    let mut tail = [0; 8];
+    tail[..words.remainder().len()].copy_from_slice(words.remainder());
+    hash = mix(hash, u64::from_le_bytes(tail));
+    hash = mix(
+        hash,
+        (u64::from(frame.width()) << 32) | u64::from(frame.height()),
+    );
+    mix(hash, u64::from(frame.metadata.stride))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::{FrameFormat, FrameMetadataBuilder};
+    use ion_core::backend::KeyframeScheduler;
+
+    fn solid(value: u8) -> CaptureFrame {
+        let metadata = FrameMetadataBuilder::new()
+            .dimensions(16, 16)
+            .stride(16 * 4)
+            .format(FrameFormat::Bgra8888)
+            .build();
+        CaptureFrame::new(metadata, vec![value; 16 * 16 * 4])
+    }
+
+    #[test]
+    fn static_source_pauses_and_change_resumes_with_keyframe() {
+        let handle = CaptureHandle::default();
+        let mut keyframes = KeyframeScheduler::new(handle.clone(), 1000);
+        let mut detector = StaticDetector::new(3).with_keyframe_handle(handle.clone());
+
+        let mut encoded = 0;
+        let mut events = Vec::new();
+        let frames = (0..10)
+            .map(|_| solid(0x20))
+            .chain([solid(0x80), solid(0x80)]);
+        for frame in frames {
+            events.extend(detector.observe(&frame));
+            if detector.is_paused() {
+                continue;
+            }
+            let keyframe = keyframes.next_is_keyframe();
+            // The first frame, then the first one after resuming
+            assert_eq!(keyframe, encoded == 0 || encoded == 3, "frame {encoded}");
+            encoded += 1;
+        }
+
+        assert_eq!(events, [StreamEvent::Paused, StreamEvent::Resumed]);
+        // Three frames before the pause, two after
+        assert_eq!(encoded, 5);
+        assert_eq!(handle.forced_keyframes(), 1);
+    }
+
+    #[test]
+    fn single_pixel_change_is_detected() {
+        let mut detector = StaticDetector::new(1);
+        detector.observe(&solid(0));
+        assert_eq!(detector.observe(&solid(0)), Some(StreamEvent::Paused));
+
+        let metadata = solid(0).metadata;
+        let mut data = vec![0; 16 * 16 * 4];
+        data[16 * 16 * 4 - 1] = 1;
+        let changed = CaptureFrame::new(metadata, data);
+        assert_eq!(detector.observe(&changed), Some(StreamEvent::Resumed));
+        assert!(!detector.is_paused());
+    }
+}
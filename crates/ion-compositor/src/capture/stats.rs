@@ -0,0 +1,243 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Per-stream capture statistics.
+//!
+//! The global metrics say how capture is doing overall; when one session
+//! looks bad, operators need the numbers for its stream alone. Backends
+//! that run a streaming loop keep a [`StreamStats`] for the current stream,
+//! reachable through [`ScreenCapture::stream_stats`](super::ScreenCapture::stream_stats),
+//! and start a fresh one whenever the stream is restarted.
+//!
+//! Recording a frame only touches atomics, so the streaming loop never
+//! waits on a reader. Latency percentiles come from a histogram with four
+//! buckets per power of two microseconds, which keeps them within 25%.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Latency histogram buckets; the last one covers `u64::MAX` microseconds.
+const BUCKETS: usize = 252;
+
+/// Weight of each new frame interval in the smoothed frame rate.
+const INTERVAL_SMOOTHING: u64 = 8;
+
+/// Live statistics of one capture stream.
+#[derive(Debug)]
+pub struct StreamStats {
+    started: Instant,
+    captured: AtomicU64,
+    dropped: AtomicU64,
+    encoded_bytes: AtomicU64,
+    latency_total_micros: AtomicU64,
+    latency_buckets: [AtomicU64; BUCKETS],
+    /// Microseconds from `started` to the last frame, plus one; 0 before
+    /// the first frame
+    last_frame_micros: AtomicU64,
+    /// Smoothed microseconds between frames; 0 before the second frame
+    interval_micros: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+impl Default for StreamStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamStats {
+    /// Creates empty statistics for a stream starting now.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            captured: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            encoded_bytes: AtomicU64::new(0),
+            latency_total_micros: AtomicU64::new(0),
+            latency_buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            last_frame_micros: AtomicU64::new(0),
+            interval_micros: AtomicU64::new(0),
+            last_error: Mutex::new(None),
+        }
+    }
+
+    /// Records a frame captured in `latency`.
+    pub fn record_frame(&self, latency: Duration) {
+        let latency = micros(latency);
+        self.captured.fetch_add(1, Ordering::Relaxed);
+        self.latency_total_micros
+            .fetch_add(latency, Ordering::Relaxed);
+        self.latency_buckets[bucket(latency)].fetch_add(1, Ordering::Relaxed);
+
+        let now = micros(self.started.elapsed()) + 1;
+        let previous = self.last_frame_micros.swap(now, Ordering::Relaxed);
+        if previous > 0 {
+            let interval = now.saturating_sub(previous);
+            let smoothed = match self.interval_micros.load(Ordering::Relaxed) {
+                0 => interval,
+                old => (old * (INTERVAL_SMOOTHING - 1) + interval) / INTERVAL_SMOOTHING,
+            };
+            self.interval_micros.store(smoothed, Ordering::Relaxed);
+        }
+    }
+
+    /// Records a frame that could not be captured.
+    pub fn record_drop(&self, error: &impl std::fmt::Display) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        *self
+            .last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(error.to_string());
+    }
+
+    /// Records `bytes` of encoded output, for streams that are encoded.
+    pub fn record_encoded(&self, bytes: usize) {
+        let bytes = u64::try_from(bytes).unwrap_or(u64::MAX);
+        self.encoded_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Returns the statistics as of now.
+    #[must_use]
+    pub fn snapshot(&self) -> StreamStatsSnapshot {
+        let captured = self.captured.load(Ordering::Relaxed);
+        let average_latency = (captured > 0).then(|| {
+            Duration::from_micros(self.latency_total_micros.load(Ordering::Relaxed) / captured)
+        });
+
+        StreamStatsSnapshot {
+            captured,
+            dropped: self.dropped.load(Ordering::Relaxed),
+            encoded_bytes: self.encoded_bytes.load(Ordering::Relaxed),
+            average_latency,
+            p99_latency: self.latency_percentile(99),
+            fps: self.fps(),
+            last_error: self
+                .last_error
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone(),
+            uptime: self.started.elapsed(),
+        }
+    }
+
+    /// Upper bound of the bucket holding the `percent`th percentile.
+    fn latency_percentile(&self, percent: u64) -> Option<Duration> {
+        let counts: Vec<u64> = self
+            .latency_buckets
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect();
+        let total: u64 = counts.iter().sum();
+        let rank = (total * percent).div_ceil(100).max(1);
+        let mut seen = 0;
+        counts.iter().enumerate().find_map(|(index, count)| {
+            seen += count;
+            (seen >= rank).then(|| Duration::from_micros(bucket_upper_bound(index)))
+        })
+    }
+
+    /// Smoothed frame rate, decaying while no frames arrive.
+    fn fps(&self) -> f64 {
+        let interval = self.interval_micros.load(Ordering::Relaxed);
+        let last = self.last_frame_micros.load(Ordering::Relaxed);
+        if interval == 0 || last == 0 {
+            return 0.0;
+        }
+        let since_last = micros(self.started.elapsed()).saturating_sub(last - 1);
+        1.0 / Duration::from_micros(interval.max(since_last)).as_secs_f64()
+    }
+}
+
+/// Point-in-time copy of a stream's [`StreamStats`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StreamStatsSnapshot {
+    /// Frames captured and delivered
+    pub captured: u64,
+    /// Frames that could not be captured
+    pub dropped: u64,
+    /// Encoded bytes produced, if the stream is encoded
+    pub encoded_bytes: u64,
+    /// Mean capture latency; `None` before the first frame
+    pub average_latency: Option<Duration>,
+    /// 99th percentile capture latency, rounded up to its histogram
+    /// bucket; `None` before the first frame
+    pub p99_latency: Option<Duration>,
+    /// Current frame rate
+    pub fps: f64,
+    /// Error that dropped the most recent failed frame
+    pub last_error: Option<String>,
+    /// Time since the stream started
+    pub uptime: Duration,
+}
+
+fn micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+/// Histogram bucket for a latency: exact below 4µs, then four buckets
+/// per power of two.
+fn bucket(micros: u64) -> usize {
+    let index = if micros < 4 {
+        micros
+    } else {
+        let log = micros.ilog2();
+        let sub = (micros >> (log - 2)) & 3;
+        4 * u64::from(log - 1) + sub
+    };
+    usize::try_from(index).unwrap_or(BUCKETS - 1)
+}
+
+/// Smallest latency, in microseconds, above every one in `index`.
+fn bucket_upper_bound(index: usize) -> u64 {
+    let index = index as u64;
+    if index < 4 {
+        return index + 1;
+    }
+    let (log, sub) = (index / 4 + 1, index % 4);
+    (5 + sub).saturating_mul(1 << (log - 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_cover_their_latencies() {
+        for latency in [0, 3, 4, 7, 8, 9, 1000, 4999, 5000, 123_456, u64::MAX / 2] {
+            let index = bucket(latency);
+            assert!(latency < bucket_upper_bound(index), "{latency}");
+            assert!(
+                index == 0 || latency >= bucket_upper_bound(index - 1),
+                "{latency}"
+            );
+        }
+        assert_eq!(bucket(u64::MAX), BUCKETS - 1);
+    }
+
+    #[test]
+    fn snapshot_summarises_recorded_frames() {
+        let stats = StreamStats::new();
+        assert_eq!(stats.snapshot().average_latency, None);
+        assert_eq!(stats.snapshot().p99_latency, None);
+
+        for _ in 0..99 {
+            stats.record_frame(Duration::from_millis(2));
+        }
+        stats.record_frame(Duration::from_millis(50));
+        stats.record_drop(&"compositor went away");
+        stats.record_encoded(1500);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.captured, 100);
+        assert_eq!(snapshot.dropped, 1);
+        assert_eq!(snapshot.encoded_bytes, 1500);
+        assert_eq!(snapshot.average_latency, Some(Duration::from_micros(2480)));
+        let p99 = snapshot.p99_latency.unwrap();
+        assert!(p99 > Duration::from_millis(2) && p99 <= Duration::from_micros(2500));
+        assert_eq!(snapshot.last_error.as_deref(), Some("compositor went away"));
+    }
+}
@@ -2,13 +2,44 @@
 // Copyright © 2024-2025 DataScienceBioLab
 
 //! Capture tier definitions and auto-selection.
+//!
+//! Availability probes can be fooled: a dmabuf path that reports itself
+//! available may still crawl behind broken GPU passthrough. When a
+//! [`TierSelector`] is given a capture factory it times a few frames on
+//! each available tier and moves down to the next one if capture is too
+//! slow or fails outright.
+//!
+//! The factory has to build captures that really go through the
+//! compositor. [`CapabilityProvider`](crate::CapabilityProvider) does not
+//! benchmark: the tier backends in this crate fill frames without asking
+//! the compositor, so timing them says nothing about the tier.
 
 use std::cmp::Ordering;
 use std::env;
+use std::fmt;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
+use tokio::time::Instant;
 use tracing::{debug, info, warn};
 
+use super::ScreenCapture;
+
+/// Frames captured per tier when benchmarking.
+pub const BENCHMARK_FRAMES: usize = 5;
+
+/// Median capture latency above which a tier is abandoned (one frame at 30 fps).
+pub const DEFAULT_LATENCY_THRESHOLD: Duration = Duration::from_millis(33);
+
+/// Longest a single benchmark frame may take before the tier counts as broken.
+const BENCHMARK_FRAME_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Builds a capture backend for a tier so its latency can be measured.
+///
+/// Returning `None` skips the benchmark and trusts the availability probe.
+pub type CaptureFactory = Arc<dyn Fn(CaptureTier) -> Option<Box<dyn ScreenCapture>> + Send + Sync>;
+
 /// Available capture tiers, ordered by quality (best first).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u8)]
@@ -197,25 +228,90 @@ impl EnvironmentInfo {
     }
 }
 
+/// Latency measured for one tier during selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TierBenchmark {
+    /// The tier that was measured.
+    pub tier: CaptureTier,
+    /// Median frame latency, or `None` if capture failed or timed out.
+    pub latency: Option<Duration>,
+}
+
+/// Outcome of tier selection, including any benchmark results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TierSelection {
+    /// The selected tier.
+    pub tier: CaptureTier,
+    /// Benchmarks run, best tier first.
+    pub benchmarks: Vec<TierBenchmark>,
+    /// Best tier that was available but abandoned for performing poorly.
+    pub downgraded_from: Option<CaptureTier>,
+}
+
+impl TierSelection {
+    fn none() -> Self {
+        Self {
+            tier: CaptureTier::None,
+            benchmarks: Vec::new(),
+            downgraded_from: None,
+        }
+    }
+
+    /// Returns the latency measured for `tier`, if it was benchmarked.
+    #[must_use]
+    pub fn latency(&self, tier: CaptureTier) -> Option<Duration> {
+        self.benchmarks
+            .iter()
+            .find(|b| b.tier == tier)
+            .and_then(|b| b.latency)
+    }
+}
+
 /// Automatic tier selector.
-#[derive(Debug)]
 pub struct TierSelector {
     env_info: EnvironmentInfo,
+    benchmark: Option<CaptureFactory>,
+    latency_threshold: Duration,
 }
 
 impl TierSelector {
     /// Creates a new tier selector with auto-detected environment.
     #[must_use]
     pub fn new() -> Self {
-        Self {
-            env_info: EnvironmentInfo::detect(),
-        }
+        Self::with_env(EnvironmentInfo::detect())
     }
 
     /// Creates a tier selector with custom environment info.
     #[must_use]
     pub fn with_env(env_info: EnvironmentInfo) -> Self {
-        Self { env_info }
+        Self {
+            env_info,
+            benchmark: None,
+            latency_threshold: DEFAULT_LATENCY_THRESHOLD,
+        }
+    }
+
+    /// Benchmarks available tiers with captures built by `factory`.
+    ///
+    /// A tier whose median latency exceeds the threshold, or whose
+    /// capture fails, is passed over for the next available tier. The
+    /// lowest available tier is kept regardless, since slow capture
+    /// beats none. `factory` should build captures that read from the
+    /// compositor; timing anything else measures nothing.
+    #[must_use]
+    pub fn with_benchmark<F>(mut self, factory: F) -> Self
+    where
+        F: Fn(CaptureTier) -> Option<Box<dyn ScreenCapture>> + Send + Sync + 'static,
+    {
+        self.benchmark = Some(Arc::new(factory));
+        self
+    }
+
+    /// Sets the median latency above which a benchmarked tier is abandoned.
+    #[must_use]
+    pub fn with_latency_threshold(mut self, threshold: Duration) -> Self {
+        self.latency_threshold = threshold;
+        self
     }
 
     /// Returns the environment info.
@@ -229,36 +325,87 @@ impl TierSelector {
     /// This performs actual capability probing, not just heuristics.
     /// Tries PipeWire first (modern standard), then fallback tiers.
     pub async fn select_best(&self) -> CaptureTier {
+        self.select().await.tier
+    }
+
+    /// Selects the best available capture tier, reporting benchmarks.
+    ///
+    /// Without a benchmark factory this is the first available tier, as
+    /// with [`Self::select_best`].
+    pub async fn select(&self) -> TierSelection {
         // Check prerequisites
         if self.env_info.wayland_display.is_none() {
             warn!("No WAYLAND_DISPLAY set, capture unavailable");
-            return CaptureTier::None;
+            return TierSelection::none();
         }
 
-        // Try PipeWire first (modern standard, works everywhere)
-        if self.try_pipewire().await {
-            info!(tier = %CaptureTier::PipeWire, "Selected capture tier");
-            return CaptureTier::PipeWire;
+        // PipeWire first (modern standard), then direct protocol fallbacks
+        let mut available = Vec::new();
+        for tier in [
+            CaptureTier::PipeWire,
+            CaptureTier::Dmabuf,
+            CaptureTier::Shm,
+            CaptureTier::Cpu,
+        ] {
+            if self.select_tier(tier).await.is_some() {
+                available.push(tier);
+                if self.benchmark.is_none() {
+                    break;
+                }
+            }
         }
 
-        // Fall back to direct protocol implementations
-        if self.try_dmabuf().await {
-            info!(tier = %CaptureTier::Dmabuf, "Selected capture tier");
-            return CaptureTier::Dmabuf;
-        }
+        let Some((&lowest, better)) = available.split_last() else {
+            warn!("No capture tier available, running in input-only mode");
+            return TierSelection::none();
+        };
 
-        if self.try_shm().await {
-            info!(tier = %CaptureTier::Shm, "Selected capture tier");
-            return CaptureTier::Shm;
+        let mut selection = TierSelection::none();
+        selection.tier = lowest;
+        for &tier in better {
+            let Some(latency) = self.benchmark_tier(tier, &mut selection).await else {
+                selection.tier = tier;
+                break;
+            };
+            if latency.is_some_and(|l| l <= self.latency_threshold) {
+                selection.tier = tier;
+                break;
+            }
+            warn!(
+                %tier,
+                ?latency,
+                threshold = ?self.latency_threshold,
+                "Capture tier underperforms, downgrading"
+            );
+            selection.downgraded_from.get_or_insert(tier);
         }
-
-        if self.try_cpu().await {
-            info!(tier = %CaptureTier::Cpu, "Selected capture tier");
-            return CaptureTier::Cpu;
+        if selection.tier == lowest {
+            self.benchmark_tier(lowest, &mut selection).await;
         }
 
-        warn!("No capture tier available, running in input-only mode");
-        CaptureTier::None
+        info!(
+            tier = %selection.tier,
+            latency = ?selection.latency(selection.tier),
+            downgraded_from = ?selection.downgraded_from,
+            "Selected capture tier"
+        );
+        selection
+    }
+
+    /// Benchmarks `tier` and records the result in `selection`.
+    ///
+    /// Returns `None` if the tier was not benchmarked, otherwise the
+    /// measured latency (itself `None` if capture failed).
+    async fn benchmark_tier(
+        &self,
+        tier: CaptureTier,
+        selection: &mut TierSelection,
+    ) -> Option<Option<Duration>> {
+        let capture = self.benchmark.as_ref().and_then(|factory| factory(tier))?;
+        let latency = measure_latency(capture.as_ref()).await;
+        debug!(%tier, ?latency, "Benchmarked capture tier");
+        selection.benchmarks.push(TierBenchmark { tier, latency });
+        Some(latency)
     }
 
     /// Attempts to probe PipeWire support.
@@ -349,6 +496,40 @@ impl TierSelector {
     }
 }
 
+impl fmt::Debug for TierSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TierSelector")
+            .field("env_info", &self.env_info)
+            .field("benchmark", &self.benchmark.is_some())
+            .field("latency_threshold", &self.latency_threshold)
+            .finish()
+    }
+}
+
+/// Median latency of [`BENCHMARK_FRAMES`] captures.
+///
+/// Returns `None` if any capture fails or takes longer than
+/// [`BENCHMARK_FRAME_TIMEOUT`].
+async fn measure_latency(capture: &dyn ScreenCapture) -> Option<Duration> {
+    let mut samples = Vec::with_capacity(BENCHMARK_FRAMES);
+    for _ in 0..BENCHMARK_FRAMES {
+        let start = Instant::now();
+        match tokio::time::timeout(BENCHMARK_FRAME_TIMEOUT, capture.capture_frame()).await {
+            Ok(Ok(_)) => samples.push(start.elapsed()),
+            Ok(Err(e)) => {
+                debug!(error = %e, "Benchmark capture failed");
+                return None;
+            },
+            Err(_) => {
+                debug!("Benchmark capture timed out");
+                return None;
+            },
+        }
+    }
+    samples.sort_unstable();
+    samples.get(samples.len() / 2).copied()
+}
+
 impl Default for TierSelector {
     fn default() -> Self {
         Self::new()
@@ -358,6 +539,12 @@ impl Default for TierSelector {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::capture::{
+        CaptureCapabilities, CaptureError, CaptureFrame, CaptureResult, FrameMetadataBuilder,
+    };
+    use std::future::Future;
+    use std::pin::Pin;
+    use tokio::sync::broadcast;
 
     #[test]
     fn tier_ordering() {
@@ -671,6 +858,111 @@ mod tests {
         assert!(env.dmabuf_likely_works());
     }
 
+    /// Capture that takes `latency` per frame, or fails when `None`.
+    struct FixedLatencyCapture {
+        capabilities: CaptureCapabilities,
+        latency: Option<Duration>,
+    }
+
+    impl ScreenCapture for FixedLatencyCapture {
+        fn capabilities(&self) -> &CaptureCapabilities {
+            &self.capabilities
+        }
+
+        fn capture_frame(
+            &self,
+        ) -> Pin<Box<dyn Future<Output = CaptureResult<CaptureFrame>> + Send + '_>> {
+            Box::pin(async move {
+                let latency = self
+                    .latency
+                    .ok_or_else(|| CaptureError::NotAvailable("broken".into()))?;
+                tokio::time::sleep(latency).await;
+                let metadata = FrameMetadataBuilder::new().dimensions(1, 1).build();
+                Ok(CaptureFrame::new(metadata, vec![0; 4]))
+            })
+        }
+
+        fn start_stream(
+            &self,
+            _target_fps: u32,
+        ) -> CaptureResult<broadcast::Receiver<Arc<CaptureFrame>>> {
+            Err(CaptureError::NotAvailable("benchmark only".into()))
+        }
+
+        fn stop_stream(&self) -> CaptureResult<()> {
+            Ok(())
+        }
+
+        fn is_capturing(&self) -> bool {
+            false
+        }
+    }
+
+    /// Selector where dmabuf and SHM both report available.
+    fn benchmarked_selector(
+        latency: impl Fn(CaptureTier) -> Option<Duration> + Send + Sync + 'static,
+    ) -> TierSelector {
+        let env = EnvironmentInfo {
+            is_vm: true,
+            has_drm: true,
+            wayland_display: Some("wayland-0".to_string()),
+            has_runtime_dir: true,
+            gpu_vendor: Some("NVIDIA".to_string()), // passthrough claims dmabuf
+        };
+        TierSelector::with_env(env)
+            .with_latency_threshold(Duration::from_millis(20))
+            .with_benchmark(move |tier| {
+                Some(Box::new(FixedLatencyCapture {
+                    capabilities: CaptureCapabilities::none(),
+                    latency: latency(tier),
+                }) as Box<dyn ScreenCapture>)
+            })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn tier_selector_downgrades_slow_dmabuf_to_shm() {
+        // PipeWire may be present on the host; make it as slow as dmabuf
+        let selector = benchmarked_selector(|tier| match tier {
+            CaptureTier::PipeWire | CaptureTier::Dmabuf => Some(Duration::from_millis(80)),
+            _ => Some(Duration::from_millis(5)),
+        });
+
+        let selection = selector.select().await;
+        assert_eq!(selection.tier, CaptureTier::Shm);
+        assert!(selection.downgraded_from >= Some(CaptureTier::Dmabuf));
+        assert_eq!(
+            selection.latency(CaptureTier::Dmabuf),
+            Some(Duration::from_millis(80))
+        );
+        assert_eq!(
+            selection.latency(CaptureTier::Shm),
+            Some(Duration::from_millis(5))
+        );
+        // CPU is never reached
+        assert_eq!(selection.latency(CaptureTier::Cpu), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn tier_selector_keeps_fast_tier_and_lowest_fallback() {
+        let fast = benchmarked_selector(|_| Some(Duration::from_millis(5)));
+        let selection = fast.select().await;
+        assert!(selection.tier >= CaptureTier::Dmabuf);
+        assert_eq!(selection.downgraded_from, None);
+
+        // Failing capture is abandoned, but the last tier is kept even if slow
+        let broken = benchmarked_selector(|tier| match tier {
+            CaptureTier::Cpu => Some(Duration::from_millis(80)),
+            _ => None,
+        });
+        let selection = broken.select().await;
+        assert_eq!(selection.tier, CaptureTier::Cpu);
+        assert_eq!(selection.latency(CaptureTier::Shm), None);
+        assert!(selection
+            .benchmarks
+            .iter()
+            .any(|b| b.tier == CaptureTier::Shm));
+    }
+
     #[test]
     fn tier_is_send_sync() {
         fn assert_send_sync<T: Send + Sync>() {}
@@ -92,6 +92,9 @@ impl<T: LocalScreenCapture + 'static> TraitScreenCapture for CaptureAdapter<T> {
             format: convert_format(local_frame.format()),
             sequence: local_frame.metadata.sequence,
             captured_at: Instant::now(), // Approximate
+            output_name: local_frame.metadata.output_name.clone(),
+            transform: local_frame.metadata.transform,
+            transform_handling: local_frame.metadata.transform_handling,
             platform_data: None,
         };
 
@@ -130,6 +133,8 @@ fn convert_format(local: crate::capture::FrameFormat) -> TraitFormat {
         crate::capture::FrameFormat::Xbgr8888 => TraitFormat::Xbgr8888,
         crate::capture::FrameFormat::Rgb888 => TraitFormat::Rgb888,
         crate::capture::FrameFormat::Bgr888 => TraitFormat::Bgr888,
+        crate::capture::FrameFormat::Xrgb2101010 => TraitFormat::Xrgb2101010,
+        crate::capture::FrameFormat::Nv12 => TraitFormat::Nv12,
     }
 }
 
@@ -162,6 +167,8 @@ mod tests {
             crate::capture::FrameFormat::Bgra8888,
             crate::capture::FrameFormat::Rgba8888,
             crate::capture::FrameFormat::Xrgb8888,
+            crate::capture::FrameFormat::Xrgb2101010,
+            crate::capture::FrameFormat::Nv12,
         ];
 
         for fmt in formats {
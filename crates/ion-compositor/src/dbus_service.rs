@@ -10,20 +10,39 @@
 //!
 //! Service name: `com.system76.cosmic.RemoteDesktop`
 //! Object path: `/com/system76/cosmic/RemoteDesktop`
+//!
+//! Every `Inject*` method accepts a `client_timestamp` option (`t`): when
+//! the client sent the event, in microseconds since the Unix epoch. It lets
+//! the input handler measure transit latency and drop stale motion.
+//!
+//! `SetRegionsOfInterest` takes a session's regions as `a(uuuu)` (x, y,
+//! width, height in frame pixels); its encoders keep them sharp and encode
+//! the rest more coarsely.
+//!
+//! `LockPointer` and `UnlockPointer` take and release a session's pointer
+//! lock; while it is held the session's absolute motion is dropped and
+//! the sink constrains the pointer in place.
+//!
+//! `SetPointerTransform` takes a session's relative motion sensitivity
+//! (`d`) and acceleration profile (`u`, `0` flat, `1` adaptive), which
+//! the input handler applies before injecting.
 
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::RwLock;
 use tracing::{debug, info, instrument, warn};
 use zbus::zvariant::{ObjectPath, OwnedValue};
 
-use ion_core::event::{ButtonState, InputEvent, KeyState};
-use ion_core::session::SessionId;
+use ion_core::event::{
+    scroll_options, AccelProfile, ButtonState, InputEvent, KeyState, PointerTransform,
+};
+use ion_core::redact::Sensitive;
+use ion_core::session::{RateProfile, SessionId};
 use ion_core::{DeviceType, Error};
 
-use crate::rate_limiter::RateLimiter;
-use crate::virtual_input::VirtualInputEvent;
+use crate::capture::{CaptureResult, Rect, SessionRoi};
+use crate::virtual_input::{VirtualInputEvent, VirtualInputSender};
 
 /// Session state tracked by the compositor service.
 #[derive(Debug, Clone)]
@@ -34,6 +53,14 @@ struct CompositorSession {
     active: bool,
 }
 
+/// Reads when the client sent an event, in microseconds since the Unix
+/// epoch, from method options.
+fn parse_client_timestamp(options: &HashMap<String, OwnedValue>) -> Option<u64> {
+    options
+        .get(InputEvent::CLIENT_TIMESTAMP_KEY)
+        .and_then(|v| v.downcast_ref::<u64>().ok())
+}
+
 /// D-Bus service for remote desktop input injection.
 ///
 /// This service is called by `xdg-desktop-portal-cosmic` to inject
@@ -41,24 +68,24 @@ struct CompositorSession {
 #[derive(Debug, Clone)]
 pub struct RemoteDesktopService {
     /// Channel to send events to the compositor
-    event_tx: mpsc::Sender<VirtualInputEvent>,
-    /// Rate limiter for input events
-    rate_limiter: RateLimiter,
+    event_tx: VirtualInputSender,
     /// Active sessions
     sessions: Arc<RwLock<HashMap<String, CompositorSession>>>,
+    /// Regions of interest the sessions' encoders honor
+    roi: SessionRoi,
 }
 
 impl RemoteDesktopService {
     /// Creates a new remote desktop service.
     ///
     /// The `event_tx` channel should be connected to a `VirtualInput` handler
-    /// in the compositor.
+    /// in the compositor, whose middleware rate limits the events.
     #[must_use]
-    pub fn new(event_tx: mpsc::Sender<VirtualInputEvent>, rate_limiter: RateLimiter) -> Self {
+    pub fn new(event_tx: VirtualInputSender) -> Self {
         Self {
             event_tx,
-            rate_limiter,
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            roi: SessionRoi::default(),
         }
     }
 
@@ -77,15 +104,74 @@ impl RemoteDesktopService {
         info!(session = session_path, devices = %authorized_devices, "Session registered");
     }
 
+    /// Registers a session with a client-requested rate profile.
+    ///
+    /// The profile applies to the input handler's rate limit, capped at
+    /// its ceiling.
+    pub async fn register_session_with_profile(
+        &self,
+        session_path: &str,
+        authorized_devices: DeviceType,
+        profile: RateProfile,
+    ) {
+        self.register_session(session_path, authorized_devices)
+            .await;
+        self.event_tx
+            .set_rate_profile(&SessionId::new(session_path), profile);
+    }
+
+    /// Sets how a session's relative pointer motion is transformed.
+    ///
+    /// Called through `SetPointerTransform` with the transform the
+    /// client requested when starting the session. The identity
+    /// transform removes it.
+    pub fn set_pointer_transform(&self, session_path: &str, transform: PointerTransform) {
+        self.event_tx
+            .set_pointer_transform(SessionId::new(session_path), transform);
+        debug!(session = session_path, ?transform, "Pointer transform set");
+    }
+
+    /// Locks or releases the pointer for a session.
+    ///
+    /// Called through `LockPointer` and `UnlockPointer` when the client
+    /// locks the pointer. While locked, the session's absolute motion is
+    /// dropped.
+    pub fn set_pointer_lock(&self, session_path: &str, locked: bool) {
+        self.event_tx
+            .set_pointer_lock(SessionId::new(session_path), locked);
+        debug!(session = session_path, locked, "Pointer lock set");
+    }
+
+    /// Sets the regions a session's encoders keep at high quality.
+    ///
+    /// No regions returns the session to uniform quality.
+    ///
+    /// # Errors
+    ///
+    /// Rejects empty regions, leaving the previous ones in place.
+    pub fn set_roi(&self, session_path: &str, regions: Vec<Rect>) -> CaptureResult<()> {
+        let count = regions.len();
+        self.roi.set_roi(&SessionId::new(session_path), regions)?;
+        debug!(session = session_path, count, "Regions of interest set");
+        Ok(())
+    }
+
+    /// Regions of interest of every session, for their encoders.
+    #[must_use]
+    pub fn roi(&self) -> &SessionRoi {
+        &self.roi
+    }
+
     /// Unregisters a session.
     ///
-    /// Called by the portal when a session is closed.
+    /// Called by the portal when a session is closed. The input handler
+    /// forgets the session once it has processed the events it sent.
     pub async fn unregister_session(&self, session_path: &str) {
         let mut sessions = self.sessions.write().await;
         sessions.remove(session_path);
-        self.rate_limiter
-            .remove_session(&SessionId::new(session_path))
-            .await;
+        let session_id = SessionId::new(session_path);
+        self.event_tx.end_session(&session_id);
+        self.roi.clear(&session_id);
         info!(session = session_path, "Session unregistered");
     }
 
@@ -126,15 +212,20 @@ impl RemoteDesktopService {
         Ok(())
     }
 
-    /// Sends an event, checking rate limits.
-    async fn send_event(&self, session_path: &str, event: InputEvent) -> Result<(), Error> {
+    /// Sends an event to the input handler, which rate limits it.
+    ///
+    /// Carries the client's send time, if the options include one.
+    async fn send_event(
+        &self,
+        session_path: &str,
+        options: &HashMap<String, OwnedValue>,
+        event: InputEvent,
+    ) -> Result<(), Error> {
         let session_id = SessionId::new(session_path);
-
-        // Check rate limit
-        self.rate_limiter.check(&session_id).await?;
-
-        // Send event
-        let virtual_event = VirtualInputEvent::new(session_id, event);
+        let mut virtual_event = VirtualInputEvent::new(session_id, event);
+        if let Some(micros) = parse_client_timestamp(options) {
+            virtual_event = virtual_event.with_client_timestamp(micros);
+        }
         self.event_tx
             .send(virtual_event)
             .await
@@ -150,11 +241,11 @@ impl RemoteDesktopService {
 #[zbus::interface(name = "com.system76.cosmic.RemoteDesktop")]
 impl RemoteDesktopService {
     /// Injects relative pointer motion.
-    #[instrument(skip(self, _options))]
+    #[instrument(skip(self, options))]
     async fn inject_pointer_motion(
         &self,
         session_handle: ObjectPath<'_>,
-        _options: HashMap<String, OwnedValue>,
+        options: HashMap<String, OwnedValue>,
         dx: f64,
         dy: f64,
     ) -> zbus::fdo::Result<()> {
@@ -164,6 +255,7 @@ impl RemoteDesktopService {
 
         self.send_event(
             session_handle.as_str(),
+            &options,
             InputEvent::PointerMotion { dx, dy },
         )
         .await
@@ -174,11 +266,11 @@ impl RemoteDesktopService {
     }
 
     /// Injects absolute pointer motion.
-    #[instrument(skip(self, _options))]
+    #[instrument(skip(self, options))]
     async fn inject_pointer_motion_absolute(
         &self,
         session_handle: ObjectPath<'_>,
-        _options: HashMap<String, OwnedValue>,
+        options: HashMap<String, OwnedValue>,
         stream: u32,
         x: f64,
         y: f64,
@@ -189,6 +281,7 @@ impl RemoteDesktopService {
 
         self.send_event(
             session_handle.as_str(),
+            &options,
             InputEvent::PointerMotionAbsolute { stream, x, y },
         )
         .await
@@ -199,11 +292,11 @@ impl RemoteDesktopService {
     }
 
     /// Injects pointer button event.
-    #[instrument(skip(self, _options))]
+    #[instrument(skip(self, options))]
     async fn inject_pointer_button(
         &self,
         session_handle: ObjectPath<'_>,
-        _options: HashMap<String, OwnedValue>,
+        options: HashMap<String, OwnedValue>,
         button: i32,
         state: u32,
     ) -> zbus::fdo::Result<()> {
@@ -213,6 +306,7 @@ impl RemoteDesktopService {
 
         self.send_event(
             session_handle.as_str(),
+            &options,
             InputEvent::PointerButton {
                 button,
                 state: ButtonState::from(state),
@@ -226,11 +320,14 @@ impl RemoteDesktopService {
     }
 
     /// Injects pointer scroll event.
-    #[instrument(skip(self, _options))]
+    ///
+    /// Options `axis_source` and `scroll_unit` (both `u`) select the scroll
+    /// source and unit; they default to wheel and pixels.
+    #[instrument(skip(self, options))]
     async fn inject_pointer_axis(
         &self,
         session_handle: ObjectPath<'_>,
-        _options: HashMap<String, OwnedValue>,
+        options: HashMap<String, OwnedValue>,
         dx: f64,
         dy: f64,
     ) -> zbus::fdo::Result<()> {
@@ -238,20 +335,25 @@ impl RemoteDesktopService {
             .await
             .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
 
-        self.send_event(session_handle.as_str(), InputEvent::PointerAxis { dx, dy })
-            .await
-            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        let (source, unit) = scroll_options(&options);
+        self.send_event(
+            session_handle.as_str(),
+            &options,
+            InputEvent::scroll_with(dx, dy, source, unit),
+        )
+        .await
+        .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
 
-        debug!(dx, dy, "Injected pointer axis");
+        debug!(dx, dy, ?source, ?unit, "Injected pointer axis");
         Ok(())
     }
 
     /// Injects keyboard keycode event.
-    #[instrument(skip(self, _options))]
+    #[instrument(skip(self, options, keycode), fields(keycode = %Sensitive(keycode)))]
     async fn inject_keyboard_keycode(
         &self,
         session_handle: ObjectPath<'_>,
-        _options: HashMap<String, OwnedValue>,
+        options: HashMap<String, OwnedValue>,
         keycode: i32,
         state: u32,
     ) -> zbus::fdo::Result<()> {
@@ -261,6 +363,7 @@ impl RemoteDesktopService {
 
         self.send_event(
             session_handle.as_str(),
+            &options,
             InputEvent::KeyboardKeycode {
                 keycode,
                 state: KeyState::from(state),
@@ -269,16 +372,16 @@ impl RemoteDesktopService {
         .await
         .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
 
-        debug!(keycode, state, "Injected keyboard keycode");
+        debug!(keycode = %Sensitive(keycode), state, "Injected keyboard keycode");
         Ok(())
     }
 
     /// Injects keyboard keysym event.
-    #[instrument(skip(self, _options))]
+    #[instrument(skip(self, options, keysym), fields(keysym = %Sensitive(keysym)))]
     async fn inject_keyboard_keysym(
         &self,
         session_handle: ObjectPath<'_>,
-        _options: HashMap<String, OwnedValue>,
+        options: HashMap<String, OwnedValue>,
         keysym: i32,
         state: u32,
     ) -> zbus::fdo::Result<()> {
@@ -288,6 +391,7 @@ impl RemoteDesktopService {
 
         self.send_event(
             session_handle.as_str(),
+            &options,
             InputEvent::KeyboardKeysym {
                 keysym,
                 state: KeyState::from(state),
@@ -296,7 +400,92 @@ impl RemoteDesktopService {
         .await
         .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
 
-        debug!(keysym, state, "Injected keyboard keysym");
+        debug!(keysym = %Sensitive(keysym), state, "Injected keyboard keysym");
+        Ok(())
+    }
+
+    /// Sets the keyboard modifier state.
+    ///
+    /// Masks follow `wl_keyboard.modifiers` and replace the current state.
+    #[instrument(skip(self, options))]
+    async fn inject_keyboard_modifiers(
+        &self,
+        session_handle: ObjectPath<'_>,
+        options: HashMap<String, OwnedValue>,
+        depressed: u32,
+        latched: u32,
+        locked: u32,
+        group: u32,
+    ) -> zbus::fdo::Result<()> {
+        self.validate_session(session_handle.as_str(), true, false, false)
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+
+        self.send_event(
+            session_handle.as_str(),
+            &options,
+            InputEvent::keyboard_modifiers(depressed, latched, locked, group),
+        )
+        .await
+        .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+
+        debug!(
+            depressed,
+            latched, locked, group, "Injected keyboard modifiers"
+        );
+        Ok(())
+    }
+
+    /// Sets the regions of interest of a session's capture.
+    async fn set_regions_of_interest(
+        &self,
+        session_handle: ObjectPath<'_>,
+        regions: Vec<(u32, u32, u32, u32)>,
+    ) -> zbus::fdo::Result<()> {
+        self.validate_session(session_handle.as_str(), false, false, false)
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+
+        let regions = regions
+            .into_iter()
+            .map(|(x, y, width, height)| Rect::new(x, y, width, height))
+            .collect();
+        self.set_roi(session_handle.as_str(), regions)
+            .map_err(|e| zbus::fdo::Error::InvalidArgs(e.to_string()))
+    }
+
+    /// Locks the pointer for a session's relative-only control.
+    async fn lock_pointer(&self, session_handle: ObjectPath<'_>) -> zbus::fdo::Result<()> {
+        self.validate_session(session_handle.as_str(), false, true, false)
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        self.set_pointer_lock(session_handle.as_str(), true);
+        Ok(())
+    }
+
+    /// Releases a session's pointer lock.
+    async fn unlock_pointer(&self, session_handle: ObjectPath<'_>) -> zbus::fdo::Result<()> {
+        self.validate_session(session_handle.as_str(), false, true, false)
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        self.set_pointer_lock(session_handle.as_str(), false);
+        Ok(())
+    }
+
+    /// Sets how a session's relative pointer motion is transformed.
+    #[zbus(name = "SetPointerTransform")]
+    async fn request_pointer_transform(
+        &self,
+        session_handle: ObjectPath<'_>,
+        sensitivity: f64,
+        accel_profile: u32,
+    ) -> zbus::fdo::Result<()> {
+        self.validate_session(session_handle.as_str(), false, true, false)
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        let transform = PointerTransform::with_sensitivity(sensitivity)
+            .with_accel_profile(AccelProfile::from(accel_profile));
+        self.set_pointer_transform(session_handle.as_str(), transform);
         Ok(())
     }
 
@@ -318,13 +507,12 @@ impl RemoteDesktopService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::rate_limiter::RateLimiterConfig;
+    use crate::rate_limiter::{RateLimitMiddleware, RateLimiterConfig};
+    use crate::virtual_input::{MockVirtualInputSink, VirtualInput};
 
-    async fn create_test_service() -> (RemoteDesktopService, mpsc::Receiver<VirtualInputEvent>) {
-        let (tx, rx) = mpsc::channel(64);
-        let rate_limiter = RateLimiter::new(RateLimiterConfig::permissive());
-        let service = RemoteDesktopService::new(tx, rate_limiter);
-        (service, rx)
+    async fn create_test_service() -> (RemoteDesktopService, VirtualInput) {
+        let (rx, tx) = VirtualInput::new(64);
+        (RemoteDesktopService::new(tx), rx)
     }
 
     #[tokio::test]
@@ -343,6 +531,146 @@ mod tests {
         assert_eq!(service.active_session_count().await, 0);
     }
 
+    #[tokio::test]
+    async fn service_registers_rate_profile() {
+        let (service, rx) = create_test_service().await;
+
+        service
+            .register_session_with_profile(
+                "/test/high",
+                DeviceType::desktop_standard(),
+                RateProfile::High,
+            )
+            .await;
+
+        assert_eq!(service.active_session_count().await, 1);
+        let session_id = SessionId::new("/test/high");
+        assert_eq!(
+            rx.rate_limits(&session_id).max_events_per_sec,
+            RateLimiterConfig::for_profile(RateProfile::High).max_events_per_sec
+        );
+
+        service.unregister_session("/test/high").await;
+        assert_eq!(
+            rx.rate_limits(&session_id).max_events_per_sec,
+            RateLimiterConfig::default().max_events_per_sec
+        );
+    }
+
+    #[tokio::test]
+    async fn service_rate_profile_changes_dispatched_motion() {
+        let (mut rx, tx) = VirtualInput::new(512);
+        let service = RemoteDesktopService::new(tx);
+        service
+            .register_session("/test/normal", DeviceType::POINTER)
+            .await;
+        service
+            .register_session_with_profile("/test/high", DeviceType::POINTER, RateProfile::High)
+            .await;
+
+        let mut dispatched = Vec::new();
+        for session in ["/test/normal", "/test/high"] {
+            for _ in 0..300 {
+                service
+                    .send_event(
+                        session,
+                        &HashMap::new(),
+                        InputEvent::PointerMotion { dx: 1.0, dy: 0.0 },
+                    )
+                    .await
+                    .unwrap();
+            }
+            let mut sink = MockVirtualInputSink::new();
+            dispatched.push(rx.process_pending(&mut sink));
+        }
+
+        let normal = RateLimiterConfig::default().burst_limit as usize;
+        assert_eq!(dispatched, [normal, 300]);
+    }
+
+    #[tokio::test]
+    async fn service_unregister_clears_handler_state() {
+        let rate_limits = RateLimitMiddleware::with_defaults();
+        let (mut rx, tx) = VirtualInput::with_rate_limits(64, rate_limits.clone());
+        let service = RemoteDesktopService::new(tx);
+        service
+            .register_session_with_profile("/test/gone", DeviceType::POINTER, RateProfile::High)
+            .await;
+        service
+            .send_event(
+                "/test/gone",
+                &HashMap::new(),
+                InputEvent::pointer_motion(1.0, 0.0),
+            )
+            .await
+            .unwrap();
+
+        service.unregister_session("/test/gone").await;
+        let mut sink = MockVirtualInputSink::new();
+        assert_eq!(rx.process_pending(&mut sink), 1);
+        assert_eq!(rate_limits.session_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn service_pointer_transform_cleared_on_unregister() {
+        let (service, rx) = create_test_service().await;
+        let session_id = SessionId::new("/test/trackpad");
+
+        service
+            .register_session("/test/trackpad", DeviceType::POINTER)
+            .await;
+        service.set_pointer_transform("/test/trackpad", PointerTransform::with_sensitivity(2.0));
+        assert_eq!(
+            rx.pointer_transform(&session_id),
+            PointerTransform::with_sensitivity(2.0)
+        );
+
+        service.unregister_session("/test/trackpad").await;
+        assert!(rx.pointer_transform(&session_id).is_identity());
+    }
+
+    #[tokio::test]
+    async fn service_pointer_lock_released_on_unregister() {
+        let (service, rx) = create_test_service().await;
+        let session_id = SessionId::new("/test/game");
+
+        service
+            .register_session("/test/game", DeviceType::POINTER)
+            .await;
+        service.set_pointer_lock("/test/game", true);
+        assert!(rx.is_pointer_locked(&session_id));
+
+        service.unregister_session("/test/game").await;
+        assert!(!rx.is_pointer_locked(&session_id));
+    }
+
+    #[tokio::test]
+    async fn service_roi_set_and_cleared_on_unregister() {
+        let (service, _rx) = create_test_service().await;
+        let session_id = SessionId::new("/test/roi");
+
+        service
+            .register_session("/test/roi", DeviceType::POINTER)
+            .await;
+        service
+            .set_roi("/test/roi", vec![Rect::new(0, 0, 16, 16)])
+            .unwrap();
+        assert!(service
+            .set_roi("/test/roi", vec![Rect::new(0, 0, 0, 16)])
+            .is_err());
+        assert_eq!(
+            service
+                .roi()
+                .qp_map(&session_id, 64, 64)
+                .unwrap()
+                .roi_blocks(),
+            1
+        );
+
+        service.unregister_session("/test/roi").await;
+        assert!(service.roi().regions(&session_id).is_empty());
+    }
+
     #[tokio::test]
     async fn service_validates_devices() {
         let (service, _rx) = create_test_service().await;
@@ -436,19 +764,47 @@ mod tests {
 
         // Send an event
         let event = InputEvent::PointerMotion { dx: 10.0, dy: 20.0 };
-        let result = service.send_event("/test/send", event.clone()).await;
+        let result = service
+            .send_event("/test/send", &HashMap::new(), event.clone())
+            .await;
         assert!(result.is_ok());
 
         // Verify event was received
         let received = rx.recv().await.unwrap();
         assert_eq!(received.event, event);
+        assert_eq!(received.client_timestamp, None);
+    }
+
+    #[tokio::test]
+    async fn service_send_event_carries_client_timestamp() {
+        let (service, mut rx) = create_test_service().await;
+        service
+            .register_session("/test/stamped", DeviceType::POINTER)
+            .await;
+
+        let mut options = HashMap::new();
+        options.insert(
+            "client_timestamp".to_string(),
+            OwnedValue::from(1_700_000_000_000_000u64),
+        );
+        service
+            .send_event(
+                "/test/stamped",
+                &options,
+                InputEvent::pointer_motion(1.0, 1.0),
+            )
+            .await
+            .unwrap();
+
+        let received = rx.recv().await.unwrap();
+        assert_eq!(received.client_timestamp, Some(1_700_000_000_000_000));
+        assert!(received.transit_latency().is_some());
     }
 
     #[tokio::test]
     async fn service_send_event_closed_channel() {
-        let (tx, rx) = mpsc::channel(1);
-        let rate_limiter = RateLimiter::new(RateLimiterConfig::permissive());
-        let service = RemoteDesktopService::new(tx, rate_limiter);
+        let (rx, tx) = VirtualInput::new(1);
+        let service = RemoteDesktopService::new(tx);
 
         // Register session
         service
@@ -460,7 +816,9 @@ mod tests {
 
         // Send should fail
         let event = InputEvent::PointerMotion { dx: 1.0, dy: 1.0 };
-        let result = service.send_event("/test/closed", event).await;
+        let result = service
+            .send_event("/test/closed", &HashMap::new(), event)
+            .await;
         assert!(result.is_err());
     }
 
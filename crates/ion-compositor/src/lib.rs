@@ -12,7 +12,9 @@
 //! - **Screen capture** with tiered fallbacks (dmabuf → shm → cpu)
 //! - **Input injection** for virtual keyboard/mouse/touch
 //! - **Rate limiting** and session validation
+//! - **Input middleware** for composable event transforms
 //! - **D-Bus service** for portal communication
+//! - **Load generator** for capture benchmarking (`ion-bench`)
 //!
 //! ## Architecture
 //!
@@ -68,22 +70,32 @@
     clippy::missing_errors_doc
 )]
 
+pub mod bench;
 pub mod capabilities;
 pub mod capture;
 pub mod compat;
 pub mod dbus_service;
 pub mod eis_backend;
+pub mod middleware;
 pub mod rate_limiter;
 pub mod virtual_input;
 
 // Re-exports for convenience
-pub use capabilities::{detect_best_mode, is_input_only_possible, CapabilityProvider};
+pub use capabilities::{
+    detect_best_capture, detect_best_mode, detect_best_mode_with, is_input_only_possible,
+    CapabilityProbe, CapabilityProvider, ProbedCapability,
+};
 pub use capture::{
-    CaptureCapabilities, CaptureError, CaptureFrame, CaptureResult, CaptureTier, CpuCapture,
-    DmabufCapture, FrameFormat, ScreenCapture, ScreenCaptureExt, ShmCapture, TierSelector,
+    CaptureCapabilities, CaptureError, CaptureFrame, CaptureResult, CaptureTier, Codec, CpuCapture,
+    DmabufCapture, FrameFormat, NullCapture, Rect, ScreenCapture, ScreenCaptureExt, ShmCapture,
+    TierSelection, TierSelector,
 };
 pub use compat::{adapt, CaptureAdapter};
 pub use dbus_service::RemoteDesktopService;
 pub use eis_backend::{connect_to_eis, is_eis_available, EisCapabilities, EisError};
-pub use rate_limiter::RateLimiter;
-pub use virtual_input::{VirtualInput, VirtualInputEvent};
+pub use middleware::{InputMiddleware, MiddlewareChain, MiddlewareOutcome};
+pub use rate_limiter::{RateLimitMiddleware, RateLimiter};
+pub use virtual_input::{
+    EventPriority, PointerLockMiddleware, PointerPositioner, PointerTransformMiddleware,
+    VirtualInput, VirtualInputEvent, VirtualInputSender,
+};
@@ -0,0 +1,474 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Composable input event middleware.
+//!
+//! [`VirtualInput`](crate::virtual_input::VirtualInput) runs every event
+//! through a [`MiddlewareChain`] before dispatching it to the sink. Each
+//! stage forwards the event (possibly rewritten), drops it, or splits it
+//! into several events; the next stage sees whatever the previous one
+//! produced, in order. A drop short-circuits the rest of the chain for
+//! that event.
+//!
+//! Stages that hold events back release them from
+//! [`MiddlewareChain::flush`], which the handler calls every time it
+//! processes its queue, so held events are never stuck waiting for the
+//! next event from their session.
+//!
+//! Deployments pick the stages and their order: clamping absolute
+//! coordinates, coalescing small motion, rate limiting
+//! ([`RateLimitMiddleware`](crate::rate_limiter::RateLimitMiddleware)) and
+//! the per-session pointer transform are all stages.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use ion_core::backend::OutputInfo;
+use ion_core::event::InputEvent;
+use ion_core::session::SessionId;
+
+/// What a middleware stage did with an event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MiddlewareOutcome {
+    /// Pass the (possibly rewritten) event on
+    Forward(InputEvent),
+    /// Discard the event; later stages never see it
+    Drop,
+    /// Replace the event with these, in order
+    Split(Vec<InputEvent>),
+}
+
+/// One stage of an input [`MiddlewareChain`].
+///
+/// Stages are shared between threads and may keep per-session state
+/// behind interior mutability.
+pub trait InputMiddleware: Send + Sync {
+    /// Processes one event from `session_id`.
+    fn process(&self, session_id: &SessionId, event: InputEvent) -> MiddlewareOutcome;
+
+    /// Name used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Forgets any state kept for a session that ended.
+    fn remove_session(&self, _session_id: &SessionId) {}
+
+    /// Releases events held back for too long, in dispatch order.
+    fn flush(&self) -> Vec<(SessionId, InputEvent)> {
+        Vec::new()
+    }
+}
+
+/// Ordered input middleware stages.
+#[derive(Clone, Default)]
+pub struct MiddlewareChain {
+    stages: Vec<Arc<dyn InputMiddleware>>,
+}
+
+impl std::fmt::Debug for MiddlewareChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.stages.iter().map(|stage| stage.name()))
+            .finish()
+    }
+}
+
+impl MiddlewareChain {
+    /// Creates an empty chain, which forwards every event unchanged.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a stage.
+    #[must_use]
+    pub fn with(mut self, stage: impl InputMiddleware + 'static) -> Self {
+        self.push(Arc::new(stage));
+        self
+    }
+
+    /// Appends a stage.
+    pub fn push(&mut self, stage: Arc<dyn InputMiddleware>) {
+        self.stages.push(stage);
+    }
+
+    /// Inserts a stage at `index`, shifting later stages back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > self.len()`.
+    pub fn insert(&mut self, index: usize, stage: Arc<dyn InputMiddleware>) {
+        self.stages.insert(index, stage);
+    }
+
+    /// Number of stages.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Whether the chain has no stages.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Stage names, in order.
+    #[must_use]
+    pub fn names(&self) -> Vec<&'static str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    /// Runs an event through every stage, returning the events to
+    /// dispatch in order.
+    #[must_use]
+    pub fn run(&self, session_id: &SessionId, event: InputEvent) -> Vec<InputEvent> {
+        self.run_from(0, session_id, event)
+    }
+
+    /// Releases events the stages held back for too long, running each
+    /// through the stages after the one that held it.
+    #[must_use]
+    pub fn flush(&self) -> Vec<(SessionId, InputEvent)> {
+        let mut flushed = Vec::new();
+        for (index, stage) in self.stages.iter().enumerate() {
+            for (session_id, event) in stage.flush() {
+                let events = self.run_from(index + 1, &session_id, event);
+                flushed.extend(events.into_iter().map(|event| (session_id.clone(), event)));
+            }
+        }
+        flushed
+    }
+
+    fn run_from(&self, start: usize, session_id: &SessionId, event: InputEvent) -> Vec<InputEvent> {
+        let mut events = vec![event];
+        for stage in &self.stages[start..] {
+            let mut next = Vec::with_capacity(events.len());
+            for event in events {
+                match stage.process(session_id, event) {
+                    MiddlewareOutcome::Forward(event) => next.push(event),
+                    MiddlewareOutcome::Drop => {},
+                    MiddlewareOutcome::Split(split) => next.extend(split),
+                }
+            }
+            if next.is_empty() {
+                return next;
+            }
+            events = next;
+        }
+        events
+    }
+
+    /// Forgets a session's state in every stage.
+    pub fn remove_session(&self, session_id: &SessionId) {
+        for stage in &self.stages {
+            stage.remove_session(session_id);
+        }
+    }
+}
+
+/// Clamps absolute pointer and touch coordinates onto their output.
+///
+/// Events for outputs it does not know pass through unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct ClampAbsolute {
+    outputs: HashMap<u32, OutputInfo>,
+}
+
+impl ClampAbsolute {
+    /// Clamps onto `outputs`, by stream ID.
+    #[must_use]
+    pub fn new(outputs: impl IntoIterator<Item = OutputInfo>) -> Self {
+        Self {
+            outputs: outputs.into_iter().map(|o| (o.stream, o)).collect(),
+        }
+    }
+}
+
+impl InputMiddleware for ClampAbsolute {
+    fn process(&self, _session_id: &SessionId, event: InputEvent) -> MiddlewareOutcome {
+        let clamp = |stream: u32, x: f64, y: f64| {
+            self.outputs
+                .get(&stream)
+                .map_or((x, y), |output| output.clamp(x, y))
+        };
+        MiddlewareOutcome::Forward(match event {
+            InputEvent::PointerMotionAbsolute { stream, x, y } => {
+                let (x, y) = clamp(stream, x, y);
+                InputEvent::PointerMotionAbsolute { stream, x, y }
+            },
+            InputEvent::TouchDown { stream, slot, x, y } => {
+                let (x, y) = clamp(stream, x, y);
+                InputEvent::TouchDown { stream, slot, x, y }
+            },
+            InputEvent::TouchMotion { stream, slot, x, y } => {
+                let (x, y) = clamp(stream, x, y);
+                InputEvent::TouchMotion { stream, slot, x, y }
+            },
+            other => other,
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "clamp-absolute"
+    }
+}
+
+/// Holds back relative motion until it adds up to at least `min_delta`.
+///
+/// Clients sending sub-pixel motion at a high rate otherwise cost one
+/// dispatch per event. Any other event from the session first flushes the
+/// motion held back, so nothing is reordered. Motion is never held longer
+/// than the maximum hold, so the last few pixels of a slow drag still
+/// arrive when the client stops moving.
+#[derive(Debug)]
+pub struct CoalesceMotion {
+    min_delta: f64,
+    max_hold: Duration,
+    pending: Mutex<HashMap<SessionId, PendingMotion>>,
+}
+
+/// Motion held back for one session.
+#[derive(Debug, Clone, Copy)]
+struct PendingMotion {
+    dx: f64,
+    dy: f64,
+    /// When the first motion held back arrived
+    since: Instant,
+}
+
+impl PendingMotion {
+    fn event(self) -> Option<InputEvent> {
+        (self.dx != 0.0 || self.dy != 0.0).then_some(InputEvent::PointerMotion {
+            dx: self.dx,
+            dy: self.dy,
+        })
+    }
+}
+
+impl CoalesceMotion {
+    /// Default time motion may be held back.
+    pub const DEFAULT_MAX_HOLD: Duration = Duration::from_millis(10);
+
+    /// Coalesces motion shorter than `min_delta` pixels.
+    #[must_use]
+    pub fn new(min_delta: f64) -> Self {
+        Self {
+            min_delta,
+            max_hold: Self::DEFAULT_MAX_HOLD,
+            pending: Mutex::default(),
+        }
+    }
+
+    /// Sets how long motion may be held back before it is released
+    /// regardless of its length.
+    #[must_use]
+    pub const fn with_max_hold(mut self, max_hold: Duration) -> Self {
+        self.max_hold = max_hold;
+        self
+    }
+
+    fn lock_pending(&self) -> std::sync::MutexGuard<'_, HashMap<SessionId, PendingMotion>> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl InputMiddleware for CoalesceMotion {
+    fn process(&self, session_id: &SessionId, event: InputEvent) -> MiddlewareOutcome {
+        let mut pending = self.lock_pending();
+
+        if let InputEvent::PointerMotion { dx, dy } = event {
+            let total = pending
+                .entry(session_id.clone())
+                .or_insert_with(|| PendingMotion {
+                    dx: 0.0,
+                    dy: 0.0,
+                    since: Instant::now(),
+                });
+            total.dx += dx;
+            total.dy += dy;
+            if total.dx.hypot(total.dy) < self.min_delta && total.since.elapsed() < self.max_hold {
+                return MiddlewareOutcome::Drop;
+            }
+            let total = *total;
+            pending.remove(session_id);
+            return total
+                .event()
+                .map_or(MiddlewareOutcome::Drop, MiddlewareOutcome::Forward);
+        }
+
+        match pending.remove(session_id).and_then(PendingMotion::event) {
+            Some(motion) => MiddlewareOutcome::Split(vec![motion, event]),
+            None => MiddlewareOutcome::Forward(event),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "coalesce-motion"
+    }
+
+    fn remove_session(&self, session_id: &SessionId) {
+        self.lock_pending().remove(session_id);
+    }
+
+    fn flush(&self) -> Vec<(SessionId, InputEvent)> {
+        let mut pending = self.lock_pending();
+        let expired: Vec<SessionId> = pending
+            .iter()
+            .filter(|(_, motion)| motion.since.elapsed() >= self.max_hold)
+            .map(|(session_id, _)| session_id.clone())
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|session_id| {
+                let motion = pending.remove(&session_id)?.event()?;
+                Some((session_id, motion))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limiter::{RateLimitMiddleware, RateLimiterConfig};
+    use ion_core::backend::{ColorSpace, OutputTransform};
+    use ion_core::event::ButtonState;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn output() -> OutputInfo {
+        OutputInfo {
+            stream: 0,
+            name: "DP-1".to_string(),
+            width: 100,
+            height: 50,
+            color_space: ColorSpace::Srgb,
+            hdr: false,
+            transform: OutputTransform::Normal,
+        }
+    }
+
+    #[test]
+    fn clamp_coalesce_rate_limit_chain_preserves_order() {
+        let chain = MiddlewareChain::new()
+            .with(ClampAbsolute::new([output()]))
+            .with(CoalesceMotion::new(1.0))
+            .with(RateLimitMiddleware::new(RateLimiterConfig {
+                max_events_per_sec: 1000,
+                burst_limit: 3,
+                window: Duration::from_secs(30),
+            }));
+        assert_eq!(
+            chain.names(),
+            ["clamp-absolute", "coalesce-motion", "rate-limit"]
+        );
+        let session = SessionId::new("/test/chain");
+
+        let mut dispatched = Vec::new();
+        let mut send = |event| dispatched.extend(chain.run(&session, event));
+        send(InputEvent::PointerMotionAbsolute {
+            stream: 0,
+            x: 250.0,
+            y: -3.0,
+        });
+        // Held back until something else arrives
+        send(InputEvent::pointer_motion(0.25, 0.0));
+        send(InputEvent::pointer_motion(0.25, 0.0));
+        send(InputEvent::left_click(true));
+        send(InputEvent::pointer_motion(2.0, 0.0));
+        // Over the burst limit: motion is dropped, the release is not
+        send(InputEvent::pointer_motion(2.0, 0.0));
+        send(InputEvent::left_click(false));
+
+        assert_eq!(
+            dispatched,
+            [
+                InputEvent::PointerMotionAbsolute {
+                    stream: 0,
+                    x: 100.0,
+                    y: 0.0
+                },
+                InputEvent::pointer_motion(0.5, 0.0),
+                InputEvent::PointerButton {
+                    button: 0x110,
+                    state: ButtonState::Pressed
+                },
+                InputEvent::pointer_motion(2.0, 0.0),
+                InputEvent::PointerButton {
+                    button: 0x110,
+                    state: ButtonState::Released
+                },
+            ]
+        );
+    }
+
+    /// Counts the events that reach it.
+    #[derive(Default)]
+    struct Counter(AtomicUsize);
+
+    impl InputMiddleware for Counter {
+        fn process(&self, _session_id: &SessionId, event: InputEvent) -> MiddlewareOutcome {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            MiddlewareOutcome::Forward(event)
+        }
+
+        fn name(&self) -> &'static str {
+            "counter"
+        }
+    }
+
+    #[test]
+    fn drop_short_circuits_later_stages() {
+        let before = Arc::new(Counter::default());
+        let after = Arc::new(Counter::default());
+        let mut chain = MiddlewareChain::new();
+        chain.push(before.clone());
+        chain.push(Arc::new(CoalesceMotion::new(10.0)));
+        chain.push(after.clone());
+        let session = SessionId::new("/test/drop");
+
+        assert!(chain
+            .run(&session, InputEvent::pointer_motion(1.0, 1.0))
+            .is_empty());
+        assert_eq!(before.0.load(Ordering::SeqCst), 1);
+        assert_eq!(after.0.load(Ordering::SeqCst), 0);
+
+        // A split reaches later stages once per resulting event
+        let keysym = InputEvent::KeyboardKeysym {
+            keysym: 0x61,
+            state: ion_core::event::KeyState::Pressed,
+        };
+        assert_eq!(chain.run(&session, keysym.clone()).len(), 2);
+        assert_eq!(after.0.load(Ordering::SeqCst), 2);
+
+        // Nothing is held back once the session is forgotten
+        chain.remove_session(&session);
+        assert_eq!(chain.run(&session, keysym.clone()), [keysym]);
+    }
+
+    #[test]
+    fn flush_releases_motion_held_past_max_hold() {
+        let after = Arc::new(Counter::default());
+        let mut chain = MiddlewareChain::new();
+        chain.push(Arc::new(
+            CoalesceMotion::new(10.0).with_max_hold(Duration::from_millis(20)),
+        ));
+        chain.push(after.clone());
+        let session = SessionId::new("/test/flush");
+
+        assert!(chain
+            .run(&session, InputEvent::pointer_motion(1.0, 2.0))
+            .is_empty());
+        assert!(chain.flush().is_empty());
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert_eq!(
+            chain.flush(),
+            [(session, InputEvent::pointer_motion(1.0, 2.0))]
+        );
+        // Flushed motion still runs through the later stages
+        assert_eq!(after.0.load(Ordering::SeqCst), 1);
+        assert!(chain.flush().is_empty());
+    }
+}
@@ -5,16 +5,29 @@
 //!
 //! Protects the compositor from event flooding by enforcing
 //! maximum rates per session.
+//!
+//! [`RateLimiter::check_event`] budgets each device category of a session
+//! separately, so a pointer flood uses up the pointer budget but keys and
+//! touches from the same session still go through. [`RateLimitMiddleware`]
+//! runs the same limiter as a stage of the input handler's middleware
+//! chain.
+//!
+//! Sessions may request a [`RateProfile`] (`low`/`normal`/`high`) which
+//! maps to a preset configuration. Presets are capped at the limiter's
+//! ceiling so a client can never request unlimited throughput.
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::time::{Duration, Instant};
 
-use tokio::sync::RwLock;
 use tracing::{debug, warn};
 
 use ion_core::error::{InputError, Result};
-use ion_core::session::SessionId;
+use ion_core::event::InputEvent;
+use ion_core::session::{RateProfile, SessionId};
+
+use crate::middleware::{InputMiddleware, MiddlewareOutcome};
+use crate::virtual_input::EventPriority;
 
 /// Configuration for rate limiting.
 #[derive(Debug, Clone)]
@@ -57,11 +70,41 @@ impl RateLimiterConfig {
             window: Duration::from_secs(1),
         }
     }
+
+    /// Returns the preset configuration for a session rate profile.
+    #[must_use]
+    pub fn for_profile(profile: RateProfile) -> Self {
+        match profile {
+            RateProfile::Low => Self {
+                max_events_per_sec: 250,
+                burst_limit: 25,
+                window: Duration::from_secs(1),
+            },
+            RateProfile::Normal => Self::default(),
+            RateProfile::High => Self {
+                max_events_per_sec: 5000,
+                burst_limit: 500,
+                window: Duration::from_secs(1),
+            },
+        }
+    }
+
+    /// Returns this configuration with its limits capped at `ceiling`.
+    #[must_use]
+    pub fn capped_at(&self, ceiling: &Self) -> Self {
+        Self {
+            max_events_per_sec: self.max_events_per_sec.min(ceiling.max_events_per_sec),
+            burst_limit: self.burst_limit.min(ceiling.burst_limit),
+            window: self.window,
+        }
+    }
 }
 
 /// Per-session rate tracking state.
 #[derive(Debug)]
 struct SessionRateState {
+    /// Limits applied to this session
+    config: RateLimiterConfig,
     /// Timestamps of recent events
     event_times: Vec<Instant>,
     /// Number of events in current burst
@@ -71,8 +114,9 @@ struct SessionRateState {
 }
 
 impl SessionRateState {
-    fn new() -> Self {
+    fn new(config: RateLimiterConfig) -> Self {
         Self {
+            config,
             event_times: Vec::with_capacity(100),
             current_burst: 0,
             burst_reset_time: Instant::now(),
@@ -100,6 +144,54 @@ impl SessionRateState {
         }
     }
 
+    /// Records an event if the session's limits allow it.
+    fn admit(&mut self, session_id: &SessionId) -> Result<()> {
+        let window = self.config.window;
+        let burst_limit = self.config.burst_limit;
+        let max_events_per_sec = self.config.max_events_per_sec;
+
+        // Cleanup old timestamps
+        self.cleanup(window);
+
+        // Check burst limit
+        self.maybe_reset_burst(window);
+        if self.current_burst >= burst_limit {
+            warn!(
+                session = %session_id,
+                burst = self.current_burst,
+                limit = burst_limit,
+                "Burst limit exceeded"
+            );
+            return Err(InputError::RateLimitExceeded {
+                events_per_sec: self.current_burst,
+                max: burst_limit,
+            }
+            .into());
+        }
+
+        // Check overall rate
+        let rate = self.events_per_sec(window);
+        if rate >= max_events_per_sec {
+            warn!(
+                session = %session_id,
+                rate,
+                limit = max_events_per_sec,
+                "Rate limit exceeded"
+            );
+            return Err(InputError::RateLimitExceeded {
+                events_per_sec: rate,
+                max: max_events_per_sec,
+            }
+            .into());
+        }
+
+        // Record the event
+        self.record_event();
+        debug!(session = %session_id, rate, "Event allowed");
+
+        Ok(())
+    }
+
     /// Returns the current events per second rate.
     fn events_per_sec(&self, window: Duration) -> u32 {
         let window_secs = window.as_secs_f64();
@@ -113,27 +205,111 @@ impl SessionRateState {
     }
 }
 
+/// Device category an event is budgeted under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Budget {
+    /// Events checked without saying what they are
+    Session,
+    Pointer,
+    Keyboard,
+    Touch,
+    Gesture,
+}
+
+impl Budget {
+    fn of(event: &InputEvent) -> Self {
+        if event.is_keyboard() {
+            Self::Keyboard
+        } else if event.is_touch() {
+            Self::Touch
+        } else if event.is_gesture() {
+            Self::Gesture
+        } else {
+            Self::Pointer
+        }
+    }
+}
+
+/// Rate tracking for one session, by budget.
+#[derive(Debug)]
+struct SessionBudgets {
+    config: RateLimiterConfig,
+    budgets: HashMap<Budget, SessionRateState>,
+}
+
+impl SessionBudgets {
+    fn new(config: RateLimiterConfig) -> Self {
+        Self {
+            config,
+            budgets: HashMap::new(),
+        }
+    }
+
+    fn set_config(&mut self, config: RateLimiterConfig) {
+        for state in self.budgets.values_mut() {
+            state.config = config.clone();
+        }
+        self.config = config;
+    }
+
+    fn admit(&mut self, budget: Budget, session_id: &SessionId) -> Result<()> {
+        let config = &self.config;
+        self.budgets
+            .entry(budget)
+            .or_insert_with(|| SessionRateState::new(config.clone()))
+            .admit(session_id)
+    }
+
+    /// Events per second across all budgets.
+    fn events_per_sec(&self) -> u32 {
+        self.budgets
+            .values()
+            .map(|state| state.events_per_sec(state.config.window))
+            .sum()
+    }
+}
+
 /// Rate limiter for input events.
 ///
-/// Tracks event rates per session and rejects events that exceed limits.
+/// Tracks event rates per session and device category and rejects events
+/// that exceed limits.
 ///
 /// ## Thread Safety
 ///
 /// `RateLimiter` is `Clone + Send + Sync` and can be safely shared
-/// across async tasks.
+/// across async tasks. Clones share their sessions, as does the
+/// [`RateLimitMiddleware`] built from one.
 #[derive(Debug, Clone)]
 pub struct RateLimiter {
     config: RateLimiterConfig,
-    sessions: Arc<RwLock<HashMap<SessionId, SessionRateState>>>,
+    /// Upper bound for any session-requested profile
+    ceiling: RateLimiterConfig,
+    /// Locked only briefly and never across an await, so the middleware
+    /// can share it synchronously
+    sessions: Arc<Mutex<HashMap<SessionId, SessionBudgets>>>,
 }
 
+// The async methods never wait; they stay async so callers on a runtime
+// keep the same API as before the middleware existed.
+#[allow(clippy::unused_async)]
 impl RateLimiter {
     /// Creates a new rate limiter with the given configuration.
+    ///
+    /// `config` applies to sessions that did not request a profile. The
+    /// ceiling for requested profiles defaults to
+    /// [`RateLimiterConfig::permissive`].
     #[must_use]
     pub fn new(config: RateLimiterConfig) -> Self {
+        Self::with_ceiling(config, RateLimiterConfig::permissive())
+    }
+
+    /// Creates a rate limiter with an explicit ceiling for session profiles.
+    #[must_use]
+    pub fn with_ceiling(config: RateLimiterConfig, ceiling: RateLimiterConfig) -> Self {
         Self {
             config,
-            sessions: Arc::new(RwLock::new(HashMap::new())),
+            ceiling,
+            sessions: Arc::default(),
         }
     }
 
@@ -143,77 +319,220 @@ impl RateLimiter {
         Self::new(RateLimiterConfig::default())
     }
 
+    /// Returns the ceiling applied to session profiles.
+    #[must_use]
+    pub fn ceiling(&self) -> &RateLimiterConfig {
+        &self.ceiling
+    }
+
+    /// Applies a requested rate profile to a session.
+    ///
+    /// The profile's preset is capped at the service ceiling. Returns the
+    /// effective configuration for the session.
+    pub async fn set_session_profile(
+        &self,
+        session_id: &SessionId,
+        profile: RateProfile,
+    ) -> RateLimiterConfig {
+        self.apply_profile(session_id, profile)
+    }
+
+    /// Returns the effective configuration for a session.
+    pub async fn session_config(&self, session_id: &SessionId) -> RateLimiterConfig {
+        self.config_of(session_id)
+    }
+
     /// Checks if an event from the given session is allowed.
     ///
     /// If allowed, records the event and returns `Ok(())`.
-    /// If rate limit exceeded, returns an error.
+    /// If rate limit exceeded, returns an error. Events checked this way
+    /// share one budget per session; see [`Self::check_event`].
     pub async fn check(&self, session_id: &SessionId) -> Result<()> {
-        let mut sessions = self.sessions.write().await;
+        self.admit(session_id, Budget::Session)
+    }
 
-        let state = sessions
+    /// Checks if `event` from the given session is allowed.
+    ///
+    /// Like [`Self::check`], but each device category (pointer, keyboard,
+    /// touch, gestures) has its own budget, so flooding one category
+    /// doesn't lock the session out of the others.
+    pub async fn check_event(&self, session_id: &SessionId, event: &InputEvent) -> Result<()> {
+        self.admit(session_id, Budget::of(event))
+    }
+
+    /// Removes rate tracking state for a session.
+    pub async fn remove_session(&self, session_id: &SessionId) {
+        self.forget(session_id);
+    }
+
+    /// Returns the current event rate for a session.
+    pub async fn current_rate(&self, session_id: &SessionId) -> u32 {
+        self.rate_of(session_id)
+    }
+
+    /// Returns the number of tracked sessions.
+    pub async fn session_count(&self) -> usize {
+        self.lock_sessions().len()
+    }
+
+    fn apply_profile(&self, session_id: &SessionId, profile: RateProfile) -> RateLimiterConfig {
+        let config = RateLimiterConfig::for_profile(profile).capped_at(&self.ceiling);
+        self.lock_sessions()
             .entry(session_id.clone())
-            .or_insert_with(SessionRateState::new);
+            .or_insert_with(|| SessionBudgets::new(config.clone()))
+            .set_config(config.clone());
+        debug!(
+            session = %session_id,
+            %profile,
+            max_events_per_sec = config.max_events_per_sec,
+            "Session rate profile applied"
+        );
+        config
+    }
 
-        // Cleanup old timestamps
-        state.cleanup(self.config.window);
+    fn config_of(&self, session_id: &SessionId) -> RateLimiterConfig {
+        self.lock_sessions()
+            .get(session_id)
+            .map_or_else(|| self.config.clone(), |s| s.config.clone())
+    }
 
-        // Check burst limit
-        state.maybe_reset_burst(self.config.window);
-        if state.current_burst >= self.config.burst_limit {
-            warn!(
-                session = %session_id,
-                burst = state.current_burst,
-                limit = self.config.burst_limit,
-                "Burst limit exceeded"
-            );
-            return Err(InputError::RateLimitExceeded {
-                events_per_sec: state.current_burst,
-                max: self.config.burst_limit,
-            }
-            .into());
-        }
+    fn admit(&self, session_id: &SessionId, budget: Budget) -> Result<()> {
+        self.lock_sessions()
+            .entry(session_id.clone())
+            .or_insert_with(|| SessionBudgets::new(self.config.clone()))
+            .admit(budget, session_id)
+    }
 
-        // Check overall rate
-        let rate = state.events_per_sec(self.config.window);
-        if rate >= self.config.max_events_per_sec {
-            warn!(
-                session = %session_id,
-                rate,
-                limit = self.config.max_events_per_sec,
-                "Rate limit exceeded"
-            );
-            return Err(InputError::RateLimitExceeded {
-                events_per_sec: rate,
-                max: self.config.max_events_per_sec,
-            }
-            .into());
-        }
+    fn forget(&self, session_id: &SessionId) {
+        self.lock_sessions().remove(session_id);
+    }
 
-        // Record the event
-        state.record_event();
-        debug!(session = %session_id, rate, "Event allowed");
+    fn rate_of(&self, session_id: &SessionId) -> u32 {
+        self.lock_sessions()
+            .get(session_id)
+            .map_or(0, SessionBudgets::events_per_sec)
+    }
 
-        Ok(())
+    fn lock_sessions(&self) -> MutexGuard<'_, HashMap<SessionId, SessionBudgets>> {
+        self.sessions.lock().unwrap_or_else(PoisonError::into_inner)
     }
+}
 
-    /// Removes rate tracking state for a session.
-    pub async fn remove_session(&self, session_id: &SessionId) {
-        let mut sessions = self.sessions.write().await;
-        sessions.remove(session_id);
+/// Input middleware that drops motion over a session's rate limit.
+///
+/// A synchronous view of a [`RateLimiter`] for use in a
+/// [`MiddlewareChain`](crate::middleware::MiddlewareChain). Only
+/// [`EventPriority::Low`] events count against the limit and are dropped;
+/// button, key and touch state changes always pass so a release is never
+/// lost.
+///
+/// Clones share their sessions with each other and with the limiter, so a
+/// profile set through one applies to events the others check afterwards.
+#[derive(Debug, Clone)]
+pub struct RateLimitMiddleware {
+    limiter: RateLimiter,
+}
+
+impl RateLimitMiddleware {
+    /// Limits every session to `config`.
+    ///
+    /// Sessions may request another profile through
+    /// [`Self::set_session_profile`]; the ceiling for those defaults to
+    /// [`RateLimiterConfig::permissive`].
+    #[must_use]
+    pub fn new(config: RateLimiterConfig) -> Self {
+        RateLimiter::new(config).into()
+    }
+
+    /// Creates a middleware with an explicit ceiling for session profiles.
+    #[must_use]
+    pub fn with_ceiling(config: RateLimiterConfig, ceiling: RateLimiterConfig) -> Self {
+        RateLimiter::with_ceiling(config, ceiling).into()
+    }
+
+    /// Creates with default configuration.
+    #[must_use]
+    pub fn with_defaults() -> Self {
+        RateLimiter::with_defaults().into()
+    }
+
+    /// Returns the rate limiter this middleware checks events against.
+    #[must_use]
+    pub fn limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    /// Returns the ceiling applied to session profiles.
+    #[must_use]
+    pub fn ceiling(&self) -> &RateLimiterConfig {
+        self.limiter.ceiling()
+    }
+
+    /// Applies a requested rate profile to a session.
+    ///
+    /// See [`RateLimiter::set_session_profile`].
+    pub fn set_session_profile(
+        &self,
+        session_id: &SessionId,
+        profile: RateProfile,
+    ) -> RateLimiterConfig {
+        self.limiter.apply_profile(session_id, profile)
+    }
+
+    /// Returns the effective configuration for a session.
+    #[must_use]
+    pub fn session_config(&self, session_id: &SessionId) -> RateLimiterConfig {
+        self.limiter.config_of(session_id)
+    }
+
+    /// Checks if `event` from the given session is within its limits.
+    ///
+    /// See [`RateLimiter::check_event`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InputError::RateLimitExceeded`] if the event's budget is
+    /// used up.
+    pub fn check_event(&self, session_id: &SessionId, event: &InputEvent) -> Result<()> {
+        self.limiter.admit(session_id, Budget::of(event))
     }
 
     /// Returns the current event rate for a session.
-    pub async fn current_rate(&self, session_id: &SessionId) -> u32 {
-        let sessions = self.sessions.read().await;
-        sessions
-            .get(session_id)
-            .map(|s| s.events_per_sec(self.config.window))
-            .unwrap_or(0)
+    #[must_use]
+    pub fn current_rate(&self, session_id: &SessionId) -> u32 {
+        self.limiter.rate_of(session_id)
     }
 
     /// Returns the number of tracked sessions.
-    pub async fn session_count(&self) -> usize {
-        self.sessions.read().await.len()
+    #[must_use]
+    pub fn session_count(&self) -> usize {
+        self.limiter.lock_sessions().len()
+    }
+}
+
+impl From<RateLimiter> for RateLimitMiddleware {
+    fn from(limiter: RateLimiter) -> Self {
+        Self { limiter }
+    }
+}
+
+impl InputMiddleware for RateLimitMiddleware {
+    fn process(&self, session_id: &SessionId, event: InputEvent) -> MiddlewareOutcome {
+        if EventPriority::of(&event) == EventPriority::High {
+            return MiddlewareOutcome::Forward(event);
+        }
+        match self.check_event(session_id, &event) {
+            Ok(()) => MiddlewareOutcome::Forward(event),
+            Err(_) => MiddlewareOutcome::Drop,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "rate-limit"
+    }
+
+    fn remove_session(&self, session_id: &SessionId) {
+        self.limiter.forget(session_id);
     }
 }
 
@@ -399,10 +718,180 @@ mod tests {
         assert_eq!(limiter.session_count().await, 5);
     }
 
+    #[tokio::test]
+    async fn flooding_one_category_leaves_others_open() {
+        let limiter = RateLimiter::new(RateLimiterConfig {
+            max_events_per_sec: 1000,
+            burst_limit: 5,
+            window: Duration::from_secs(60),
+        });
+        let session = SessionId::new("/test/categories");
+        let motion = InputEvent::pointer_motion(1.0, 0.0);
+
+        for _ in 0..5 {
+            limiter.check_event(&session, &motion).await.unwrap();
+        }
+        assert!(limiter.check_event(&session, &motion).await.is_err());
+        assert!(limiter
+            .check_event(&session, &InputEvent::left_click(false))
+            .await
+            .is_err());
+
+        let release = InputEvent::key(30, ion_core::event::KeyState::Released);
+        assert!(limiter.check_event(&session, &release).await.is_ok());
+    }
+
+    #[test]
+    fn config_for_profile_ordering() {
+        let low = RateLimiterConfig::for_profile(RateProfile::Low);
+        let normal = RateLimiterConfig::for_profile(RateProfile::Normal);
+        let high = RateLimiterConfig::for_profile(RateProfile::High);
+
+        assert!(low.max_events_per_sec < normal.max_events_per_sec);
+        assert!(normal.max_events_per_sec < high.max_events_per_sec);
+        assert_eq!(normal.max_events_per_sec, 1000);
+    }
+
+    #[test]
+    fn config_capped_at_ceiling() {
+        let ceiling = RateLimiterConfig {
+            max_events_per_sec: 2000,
+            burst_limit: 200,
+            window: Duration::from_secs(1),
+        };
+        let capped = RateLimiterConfig::for_profile(RateProfile::High).capped_at(&ceiling);
+        assert_eq!(capped.max_events_per_sec, 2000);
+        assert_eq!(capped.burst_limit, 200);
+    }
+
+    #[tokio::test]
+    async fn high_profile_permits_more_than_normal() {
+        let limiter = RateLimiter::new(RateLimiterConfig::default());
+        let normal = SessionId::new("/test/normal");
+        let high = SessionId::new("/test/high");
+
+        let normal_cfg = limiter
+            .set_session_profile(&normal, RateProfile::Normal)
+            .await;
+        let high_cfg = limiter.set_session_profile(&high, RateProfile::High).await;
+        assert!(high_cfg.max_events_per_sec > normal_cfg.max_events_per_sec);
+        assert!(high_cfg.burst_limit > normal_cfg.burst_limit);
+
+        // Normal burst limit is exhausted, high keeps going
+        for _ in 0..normal_cfg.burst_limit {
+            limiter.check(&normal).await.unwrap();
+            limiter.check(&high).await.unwrap();
+        }
+        assert!(limiter.check(&normal).await.is_err());
+        assert!(limiter.check(&high).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn high_profile_capped_at_service_ceiling() {
+        let ceiling = RateLimiterConfig {
+            max_events_per_sec: 1500,
+            burst_limit: 150,
+            window: Duration::from_secs(60),
+        };
+        let limiter = RateLimiter::with_ceiling(RateLimiterConfig::default(), ceiling);
+        let session = SessionId::new("/test/capped");
+
+        let effective = limiter
+            .set_session_profile(&session, RateProfile::High)
+            .await;
+        assert_eq!(effective.max_events_per_sec, 1500);
+        assert_eq!(effective.burst_limit, 150);
+        assert_eq!(
+            limiter.session_config(&session).await.burst_limit,
+            limiter.ceiling().burst_limit
+        );
+    }
+
+    #[tokio::test]
+    async fn session_config_defaults_without_profile() {
+        let limiter = RateLimiter::new(RateLimiterConfig::strict());
+        let session = SessionId::new("/test/default");
+
+        let config = limiter.session_config(&session).await;
+        assert_eq!(config.max_events_per_sec, 500);
+    }
+
+    #[test]
+    fn middleware_profile_changes_the_limit() {
+        let middleware = RateLimitMiddleware::with_ceiling(
+            RateLimiterConfig::default(),
+            RateLimiterConfig {
+                max_events_per_sec: 2000,
+                burst_limit: 200,
+                window: Duration::from_secs(60),
+            },
+        );
+        let normal = SessionId::new("/test/normal");
+        let high = SessionId::new("/test/high");
+        let effective = middleware.set_session_profile(&high, RateProfile::High);
+        assert_eq!(effective.burst_limit, 200);
+        assert_eq!(middleware.session_config(&normal).burst_limit, 100);
+
+        let forwarded = |session: &SessionId| {
+            (0..300)
+                .filter(|_| {
+                    matches!(
+                        middleware.process(session, InputEvent::pointer_motion(1.0, 0.0)),
+                        MiddlewareOutcome::Forward(_)
+                    )
+                })
+                .count()
+        };
+        assert_eq!(forwarded(&normal), 100);
+        // More than normal, but no more than the ceiling
+        assert_eq!(forwarded(&high), 200);
+
+        middleware.remove_session(&high);
+        assert_eq!(middleware.session_config(&high).burst_limit, 100);
+    }
+
+    #[test]
+    fn middleware_forwards_state_changes_over_the_limit() {
+        let middleware = RateLimitMiddleware::new(RateLimiterConfig {
+            max_events_per_sec: 1000,
+            burst_limit: 5,
+            window: Duration::from_secs(60),
+        });
+        let session = SessionId::new("/test/state-changes");
+        let forwarded = |event| {
+            matches!(
+                middleware.process(&session, event),
+                MiddlewareOutcome::Forward(_)
+            )
+        };
+
+        for _ in 0..5 {
+            assert!(forwarded(InputEvent::pointer_motion(1.0, 0.0)));
+        }
+        assert!(!forwarded(InputEvent::pointer_motion(1.0, 0.0)));
+        assert!(forwarded(InputEvent::left_click(false)));
+    }
+
+    #[tokio::test]
+    async fn middleware_shares_the_limiters_sessions() {
+        let limiter = RateLimiter::with_defaults();
+        let middleware = RateLimitMiddleware::from(limiter.clone());
+        let session = SessionId::new("/test/shared");
+
+        limiter
+            .set_session_profile(&session, RateProfile::Low)
+            .await;
+        assert_eq!(middleware.session_config(&session).burst_limit, 25);
+
+        middleware.remove_session(&session);
+        assert_eq!(limiter.session_count().await, 0);
+    }
+
     #[test]
     fn rate_limiter_is_send_sync() {
         fn assert_send_sync<T: Send + Sync>() {}
         assert_send_sync::<RateLimiter>();
+        assert_send_sync::<RateLimitMiddleware>();
         assert_send_sync::<RateLimiterConfig>();
     }
 }
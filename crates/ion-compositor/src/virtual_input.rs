@@ -10,14 +10,93 @@
 //!
 //! When integrating into `cosmic-comp`, implement the `VirtualInputSink` trait
 //! to bridge events to Smithay's input handling.
+//!
+//! ## Event Priority
+//!
+//! Button, key, touch up/down and gesture events travel on a separate
+//! high-priority queue that is drained before queued motion, so a burst of
+//! pointer motion cannot delay a button release and leave it stuck.
+//! Ordering is preserved within each priority class.
+//!
+//! A high-priority event only overtakes other sessions' motion. Motion its
+//! own session sent before it is released just ahead of it, so the press,
+//! motion and release of a drag stay in sequence. State changes are never
+//! dropped; only motion and scroll ([`EventPriority::Low`]) may be dropped
+//! for arriving late or coalesced by middleware.
+//!
+//! ## Middleware
+//!
+//! Each event runs through a [`MiddlewareChain`] before it reaches the
+//! sink; stages may rewrite, drop or split it. The chain holds the
+//! [`PointerTransformMiddleware`], the [`PointerLockMiddleware`] and a
+//! [`RateLimitMiddleware`]; deployments add clamping or coalescing
+//! stages ahead of the rate limit through [`VirtualInput::with_middleware`].
+//!
+//! A session ended through [`VirtualInputSender::end_session`] is
+//! forgotten by every stage once the events it sent have been processed.
+//!
+//! ## Rate Profiles
+//!
+//! A session may request a [`RateProfile`], set through
+//! [`VirtualInputSender::set_rate_profile`]. The rate limit stage then
+//! holds its motion to that profile's preset, capped at the ceiling the
+//! handler was built with.
+//!
+//! ## Pointer Transforms
+//!
+//! A session may carry a [`PointerTransform`] that scales and accelerates
+//! its relative pointer motion before it reaches the sink. Sessions
+//! without one get their motion unchanged.
+//!
+//! This is the only place the transform is applied: the portal forwards
+//! motion as the client sent it, and hands the transform the client
+//! asked for to [`VirtualInputSender::set_pointer_transform`] when the
+//! session starts.
+//!
+//! ## Pointer Lock
+//!
+//! A session may lock the pointer for relative-only control, as games
+//! and other first-person clients expect. While it holds the lock its
+//! absolute motion is dropped, and the sink is told through
+//! [`VirtualInputSink::set_pointer_lock`] so the compositor can constrain
+//! the pointer in place.
+//!
+//! The sink has one pointer, so its lock follows the session driving it:
+//! the one that last took the lock or sent pointer input. Pointer input
+//! from a session without the lock releases the sink's lock until the
+//! holder moves the pointer again.
+//!
+//! ## Absolute Positioning
+//!
+//! Sinks without absolute pointer motion can still honour absolute
+//! targets through a [`PointerPositioner`], which tracks the pointer and
+//! injects the relative motion that reaches each target.
+//!
+//! ## Transit Latency
+//!
+//! Clients may stamp events with the time they were sent. The handler
+//! records how long stamped events took to arrive and, with a maximum age
+//! set, drops motion that arrived too late to be useful. State changes are
+//! always delivered, however late, so a stale release cannot leave a
+//! button or key held.
 
-use std::time::Instant;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::{SendError, TrySendError};
 use tracing::{debug, instrument};
 
-use ion_core::event::{Axis, ButtonState, InputEvent, KeyState};
-use ion_core::session::SessionId;
+use ion_core::event::{
+    Axis, AxisSource, ButtonState, GesturePhase, InputEvent, KeyState, PointerTransform,
+    ScrollUnit, StampedEvent,
+};
+use ion_core::session::{RateProfile, SessionId};
+
+use crate::middleware::{InputMiddleware, MiddlewareChain, MiddlewareOutcome};
+use crate::rate_limiter::{RateLimitMiddleware, RateLimiterConfig};
 
 /// A virtual input event with metadata.
 ///
@@ -30,6 +109,10 @@ pub struct VirtualInputEvent {
     pub event: InputEvent,
     /// When the event was received
     pub timestamp: Instant,
+    /// When the event was received, on the wall clock client stamps use
+    pub received_at: SystemTime,
+    /// When the client sent the event, in microseconds since the Unix epoch
+    pub client_timestamp: Option<u64>,
 }
 
 impl VirtualInputEvent {
@@ -40,14 +123,325 @@ impl VirtualInputEvent {
             session_id,
             event,
             timestamp: Instant::now(),
+            received_at: SystemTime::now(),
+            client_timestamp: None,
+        }
+    }
+
+    /// Wraps an event a portal session sent towards the compositor,
+    /// keeping the client's stamp.
+    #[must_use]
+    pub fn from_stamped(session_id: SessionId, stamped: StampedEvent) -> Self {
+        let event = Self::new(session_id, stamped.event);
+        match stamped.client_timestamp {
+            Some(micros) => event.with_client_timestamp(micros),
+            None => event,
         }
     }
 
+    /// Sets when the client sent the event, in microseconds since the
+    /// Unix epoch.
+    #[must_use]
+    pub const fn with_client_timestamp(mut self, micros: u64) -> Self {
+        self.client_timestamp = Some(micros);
+        self
+    }
+
     /// Returns the age of this event (time since creation).
     #[must_use]
-    pub fn age(&self) -> std::time::Duration {
+    pub fn age(&self) -> Duration {
         self.timestamp.elapsed()
     }
+
+    /// Returns how long the event took to arrive after the client sent it.
+    ///
+    /// `None` if the client did not stamp the event. A client clock ahead
+    /// of ours yields zero rather than a negative latency.
+    #[must_use]
+    pub fn transit_latency(&self) -> Option<Duration> {
+        let sent = UNIX_EPOCH + Duration::from_micros(self.client_timestamp?);
+        Some(self.received_at.duration_since(sent).unwrap_or_default())
+    }
+
+    /// Returns the queue this event is delivered on.
+    #[must_use]
+    pub const fn priority(&self) -> EventPriority {
+        EventPriority::of(&self.event)
+    }
+}
+
+/// Delivery priority of a virtual input event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventPriority {
+    /// Button, key and touch state changes and gestures; never dropped or
+    /// queued behind another session's motion.
+    High,
+    /// Pointer motion, scroll and touch motion.
+    Low,
+}
+
+impl EventPriority {
+    /// Classifies an input event.
+    ///
+    /// Touch down is high priority alongside touch up so a preempting
+    /// touch up can never overtake the touch down for the same slot.
+    #[must_use]
+    pub const fn of(event: &InputEvent) -> Self {
+        match event {
+            InputEvent::PointerButton { .. }
+            | InputEvent::KeyboardKeycode { .. }
+            | InputEvent::KeyboardKeysym { .. }
+            | InputEvent::KeyboardModifiers { .. }
+            | InputEvent::TouchDown { .. }
+            | InputEvent::TouchUp { .. }
+            | InputEvent::GestureSwipe { .. }
+            | InputEvent::GesturePinch { .. }
+            | InputEvent::GestureHold { .. } => Self::High,
+            _ => Self::Low,
+        }
+    }
+}
+
+/// Sending half of a [`VirtualInput`] handler.
+///
+/// Routes each event to the high- or low-priority queue according to
+/// [`EventPriority::of`].
+#[derive(Debug, Clone)]
+pub struct VirtualInputSender {
+    high: mpsc::Sender<Queued>,
+    low: mpsc::Sender<Queued>,
+    /// Send order across both queues, shared by every clone
+    next_seq: Arc<AtomicU64>,
+    transforms: PointerTransforms,
+    locks: PointerLocks,
+    rate_limits: RateLimitMiddleware,
+    ended: EndedSessions,
+}
+
+/// Non-identity pointer transforms by session, shared with the handler.
+type PointerTransforms = Arc<RwLock<HashMap<SessionId, PointerTransform>>>;
+
+/// Sessions holding the pointer lock, shared with the handler.
+type PointerLocks = Arc<RwLock<PointerLockState>>;
+
+#[derive(Debug, Default)]
+struct PointerLockState {
+    locked: HashSet<SessionId>,
+    /// Session that took the lock since the handler last looked
+    claimed: Option<SessionId>,
+}
+
+/// Sessions ended since the handler last processed its queue.
+type EndedSessions = Arc<Mutex<Vec<SessionId>>>;
+
+/// An event on its way to the handler, numbered in send order.
+#[derive(Debug)]
+struct Queued {
+    seq: u64,
+    event: VirtualInputEvent,
+}
+
+impl VirtualInputSender {
+    /// Numbers `event` and picks the queue it travels on.
+    fn route(&self, event: VirtualInputEvent) -> (&mpsc::Sender<Queued>, Queued) {
+        let queue = match event.priority() {
+            EventPriority::High => &self.high,
+            EventPriority::Low => &self.low,
+        };
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        (queue, Queued { seq, event })
+    }
+
+    /// Sends an event, waiting for queue capacity if necessary.
+    ///
+    /// # Errors
+    ///
+    /// Returns the event back if the handler has been dropped.
+    pub async fn send(&self, event: VirtualInputEvent) -> Result<(), SendError<VirtualInputEvent>> {
+        let (queue, queued) = self.route(event);
+        queue
+            .send(queued)
+            .await
+            .map_err(|SendError(queued)| SendError(queued.event))
+    }
+
+    /// Attempts to send an event without waiting.
+    ///
+    /// Meant for motion, which can be dropped when its queue is full.
+    /// State changes should go through [`send`](Self::send) so they are
+    /// never lost.
+    ///
+    /// # Errors
+    ///
+    /// Returns the event back if its queue is full or the handler has been
+    /// dropped.
+    pub fn try_send(
+        &self,
+        event: VirtualInputEvent,
+    ) -> Result<(), TrySendError<VirtualInputEvent>> {
+        let (queue, queued) = self.route(event);
+        queue.try_send(queued).map_err(|e| match e {
+            TrySendError::Full(queued) => TrySendError::Full(queued.event),
+            TrySendError::Closed(queued) => TrySendError::Closed(queued.event),
+        })
+    }
+
+    /// Returns `true` if the handler has been dropped.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.high.is_closed()
+    }
+
+    /// Sets the transform applied to a session's relative pointer motion.
+    ///
+    /// Takes effect for events dispatched afterwards, including ones
+    /// already queued.
+    pub fn set_pointer_transform(&self, session_id: SessionId, transform: PointerTransform) {
+        let mut transforms = self
+            .transforms
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if transform.is_identity() {
+            transforms.remove(&session_id);
+        } else {
+            transforms.insert(session_id, transform);
+        }
+    }
+
+    /// Removes a session's pointer transform.
+    pub fn clear_pointer_transform(&self, session_id: &SessionId) {
+        self.transforms
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(session_id);
+    }
+
+    /// Locks or releases the pointer for a session.
+    ///
+    /// While locked, the session's absolute pointer motion is dropped and
+    /// only relative motion reaches the sink. Takes effect for events
+    /// dispatched afterwards, including ones already queued.
+    pub fn set_pointer_lock(&self, session_id: SessionId, locked: bool) {
+        let mut locks = self.locks.write().unwrap_or_else(PoisonError::into_inner);
+        if locked {
+            locks.locked.insert(session_id.clone());
+            locks.claimed = Some(session_id);
+        } else {
+            locks.locked.remove(&session_id);
+        }
+    }
+
+    /// Applies a requested rate profile to a session's motion.
+    ///
+    /// The preset is capped at the handler's ceiling. Returns the
+    /// effective configuration for the session.
+    pub fn set_rate_profile(
+        &self,
+        session_id: &SessionId,
+        profile: RateProfile,
+    ) -> RateLimiterConfig {
+        self.rate_limits.set_session_profile(session_id, profile)
+    }
+
+    /// Ends a session.
+    ///
+    /// Its pointer transform, pointer lock and rate profile are dropped
+    /// right away. Every middleware stage forgets the session the next
+    /// time the handler processes its queue, after the events it already
+    /// sent.
+    pub fn end_session(&self, session_id: &SessionId) {
+        self.clear_pointer_transform(session_id);
+        self.set_pointer_lock(session_id.clone(), false);
+        self.rate_limits.remove_session(session_id);
+        self.ended
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(session_id.clone());
+    }
+}
+
+/// Input middleware applying each session's [`PointerTransform`] to its
+/// relative pointer motion.
+///
+/// Shares its transforms with the [`VirtualInputSender`], so transforms
+/// set there apply to events it processes afterwards.
+#[derive(Debug, Clone)]
+pub struct PointerTransformMiddleware {
+    transforms: PointerTransforms,
+}
+
+impl PointerTransformMiddleware {
+    /// Returns the transform for a session's relative pointer motion.
+    #[must_use]
+    pub fn transform(&self, session_id: &SessionId) -> PointerTransform {
+        self.transforms
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(session_id)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl InputMiddleware for PointerTransformMiddleware {
+    fn process(&self, session_id: &SessionId, event: InputEvent) -> MiddlewareOutcome {
+        MiddlewareOutcome::Forward(match event {
+            InputEvent::PointerMotion { dx, dy } => {
+                let (dx, dy) = self.transform(session_id).apply(dx, dy);
+                InputEvent::PointerMotion { dx, dy }
+            },
+            other => other,
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "pointer-transform"
+    }
+}
+
+/// Input middleware dropping absolute pointer motion from sessions that
+/// hold the pointer lock.
+///
+/// Shares its locks with the [`VirtualInputSender`], so locks set there
+/// apply to events it processes afterwards.
+#[derive(Debug, Clone)]
+pub struct PointerLockMiddleware {
+    locks: PointerLocks,
+}
+
+impl PointerLockMiddleware {
+    /// Returns `true` if the session holds the pointer lock.
+    #[must_use]
+    pub fn is_locked(&self, session_id: &SessionId) -> bool {
+        self.locks
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .locked
+            .contains(session_id)
+    }
+
+    /// Takes the session that locked the pointer since the last call.
+    fn take_claimed(&self) -> Option<SessionId> {
+        self.locks
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .claimed
+            .take()
+    }
+}
+
+impl InputMiddleware for PointerLockMiddleware {
+    fn process(&self, session_id: &SessionId, event: InputEvent) -> MiddlewareOutcome {
+        if matches!(event, InputEvent::PointerMotionAbsolute { .. }) && self.is_locked(session_id) {
+            debug!(session = %session_id, "Dropping absolute motion while pointer is locked");
+            return MiddlewareOutcome::Drop;
+        }
+        MiddlewareOutcome::Forward(event)
+    }
+
+    fn name(&self) -> &'static str {
+        "pointer-lock"
+    }
 }
 
 /// Trait for sinking virtual input events into the compositor.
@@ -66,6 +460,10 @@ impl VirtualInputEvent {
 ///         self.common.shell.move_cursor(clamped);
 ///         // Dispatch motion event to focused surface
 ///     }
+///     fn set_pointer_lock(&mut self, locked: bool) {
+///         // Hold the cursor in place, as a locked pointer constraint does
+///         self.common.shell.set_cursor_locked(locked);
+///     }
 ///     // ... other methods
 /// }
 /// ```
@@ -80,7 +478,10 @@ pub trait VirtualInputSink: Send {
     fn inject_pointer_button(&mut self, button: i32, state: ButtonState);
 
     /// Inject smooth scroll.
-    fn inject_pointer_axis(&mut self, dx: f64, dy: f64);
+    ///
+    /// `source` maps to `wl_pointer.axis_source`; `unit` tells the sink
+    /// whether `dx`/`dy` are pixels or lines to be scaled by line height.
+    fn inject_pointer_axis(&mut self, dx: f64, dy: f64, source: AxisSource, unit: ScrollUnit);
 
     /// Inject discrete scroll (wheel clicks).
     fn inject_pointer_axis_discrete(&mut self, axis: Axis, steps: i32);
@@ -91,6 +492,12 @@ pub trait VirtualInputSink: Send {
     /// Inject keyboard key event (by keysym).
     fn inject_keyboard_keysym(&mut self, keysym: i32, state: KeyState);
 
+    /// Set the seat's keyboard modifier state.
+    ///
+    /// Replaces the modifier state outright, as `wl_keyboard.modifiers`
+    /// does, rather than pressing or releasing modifier keys.
+    fn inject_keyboard_modifiers(&mut self, depressed: u32, latched: u32, locked: u32, group: u32);
+
     /// Inject touch down event.
     fn inject_touch_down(&mut self, stream: u32, slot: u32, x: f64, y: f64);
 
@@ -99,6 +506,137 @@ pub trait VirtualInputSink: Send {
 
     /// Inject touch up event.
     fn inject_touch_up(&mut self, slot: u32);
+
+    /// Inject a touchpad swipe gesture stage.
+    ///
+    /// Maps to `zwp_pointer_gesture_swipe_v1`.
+    fn inject_gesture_swipe(&mut self, phase: GesturePhase, fingers: u32, dx: f64, dy: f64);
+
+    /// Inject a touchpad pinch gesture stage.
+    ///
+    /// Maps to `zwp_pointer_gesture_pinch_v1`; `scale` is relative to the
+    /// start of the gesture, `rotation` to the last update.
+    fn inject_gesture_pinch(
+        &mut self,
+        phase: GesturePhase,
+        fingers: u32,
+        dx: f64,
+        dy: f64,
+        scale: f64,
+        rotation: f64,
+    );
+
+    /// Inject a touchpad hold gesture stage.
+    ///
+    /// Maps to `zwp_pointer_gesture_hold_v1`.
+    fn inject_gesture_hold(&mut self, phase: GesturePhase, fingers: u32);
+
+    /// Lock the pointer in place for relative-only motion, or release it.
+    ///
+    /// Maps to `zwp_pointer_constraints_v1.lock_pointer`: while locked the
+    /// cursor stays put and relative motion still reaches the focused
+    /// surface. Sinks without pointer constraints may ignore this; absolute
+    /// motion is already withheld from them while the lock is held.
+    fn set_pointer_lock(&mut self, locked: bool) {
+        let _ = locked;
+    }
+}
+
+/// Bridges absolute pointer targets onto a sink that only moves the
+/// pointer relatively.
+///
+/// Tracks where the pointer was last moved to and turns each absolute
+/// target into the relative motion that reaches it. Coordinates are in the
+/// compositor's global layout.
+///
+/// Until a position is known (see [`set_position`](Self::set_position)),
+/// the first absolute target warps on a best-effort basis: a long motion
+/// towards the top-left pins the pointer against the layout's corner,
+/// which the compositor clamps to `(0, 0)`, and the target is reached from
+/// there.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PointerPositioner {
+    position: Option<(f64, f64)>,
+    bounds: Option<(f64, f64)>,
+}
+
+impl PointerPositioner {
+    /// Motion used to pin the pointer into the top-left corner, longer
+    /// than any layout.
+    pub const WARP_DISTANCE: f64 = 65_536.0;
+
+    /// Creates a positioner with the pointer position unknown.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clamps the tracked position to a `width` × `height` layout, as the
+    /// compositor clamps the pointer.
+    #[must_use]
+    pub fn with_bounds(mut self, width: f64, height: f64) -> Self {
+        self.bounds = Some((width, height));
+        self.position = self.position.map(|(x, y)| self.clamp(x, y));
+        self
+    }
+
+    /// Returns the tracked pointer position, if known.
+    #[must_use]
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.position
+    }
+
+    /// Records where the pointer is, e.g. as reported by the compositor.
+    pub fn set_position(&mut self, x: f64, y: f64) {
+        self.position = Some(self.clamp(x, y));
+    }
+
+    /// Forgets the tracked position, so the next absolute target warps.
+    ///
+    /// Call this when something else may have moved the pointer.
+    pub fn forget(&mut self) {
+        self.position = None;
+    }
+
+    /// Injects relative motion and tracks it.
+    pub fn notify_pointer_motion(&mut self, sink: &mut impl VirtualInputSink, dx: f64, dy: f64) {
+        sink.inject_pointer_motion(dx, dy);
+        if let Some((x, y)) = self.position {
+            self.position = Some(self.clamp(x + dx, y + dy));
+        }
+    }
+
+    /// Moves the pointer to `(x, y)` with relative motion.
+    ///
+    /// Returns the motion that reached the target from the tracked
+    /// position, or from the corner after a warp. Nothing is injected if
+    /// the pointer is already there.
+    pub fn notify_pointer_motion_absolute(
+        &mut self,
+        sink: &mut impl VirtualInputSink,
+        x: f64,
+        y: f64,
+    ) -> (f64, f64) {
+        let (x, y) = self.clamp(x, y);
+        let (from_x, from_y) = self.position.unwrap_or_else(|| {
+            debug!("Pointer position unknown, warping to the corner");
+            sink.inject_pointer_motion(-Self::WARP_DISTANCE, -Self::WARP_DISTANCE);
+            (0.0, 0.0)
+        });
+        let (dx, dy) = (x - from_x, y - from_y);
+        if dx != 0.0 || dy != 0.0 {
+            sink.inject_pointer_motion(dx, dy);
+        }
+        self.position = Some((x, y));
+        (dx, dy)
+    }
+
+    fn clamp(&self, x: f64, y: f64) -> (f64, f64) {
+        match self.bounds {
+            Some((width, height)) => (x.clamp(0.0, width), y.clamp(0.0, height)),
+            None => (x, y),
+        }
+    }
 }
 
 /// Handler for processing virtual input events.
@@ -107,61 +645,288 @@ pub trait VirtualInputSink: Send {
 /// dispatches them to the compositor via a `VirtualInputSink`.
 #[derive(Debug)]
 pub struct VirtualInput {
-    /// Channel for receiving input events
-    rx: mpsc::Receiver<VirtualInputEvent>,
+    /// Button, key and touch state changes and gestures
+    high_rx: mpsc::Receiver<Queued>,
+    /// Motion and scroll
+    low_rx: mpsc::Receiver<Queued>,
+    /// Motion taken off its queue while releasing a session's earlier
+    /// motion, still waiting its turn
+    held: VecDeque<Queued>,
+    /// Events to hand out before looking at either queue
+    ready: VecDeque<VirtualInputEvent>,
+    /// Per-session relative motion transforms
+    pointer_transforms: PointerTransformMiddleware,
+    /// Sessions holding the pointer lock
+    pointer_locks: PointerLockMiddleware,
+    /// Per-session motion rate limits
+    rate_limits: RateLimitMiddleware,
+    /// Lock state last applied to the sink
+    pointer_locked: bool,
+    /// Session driving the sink's pointer, whose lock the sink follows
+    pointer_owner: Option<SessionId>,
+    /// Stages every event runs through before dispatch
+    middleware: MiddlewareChain,
+    /// Sessions the stages still have to forget
+    ended: EndedSessions,
+    /// Late motion older than this is dropped
+    max_age: Option<Duration>,
     /// Statistics
     events_processed: u64,
     last_event_time: Option<Instant>,
+    stale_dropped: u64,
+    transit: TransitStats,
+}
+
+/// Transit latency observed over client-stamped events.
+#[derive(Debug, Default)]
+struct TransitStats {
+    last: Option<Duration>,
+    total: Duration,
+    samples: u32,
+}
+
+impl TransitStats {
+    fn record(&mut self, latency: Duration) {
+        self.last = Some(latency);
+        self.total = self.total.saturating_add(latency);
+        self.samples = self.samples.saturating_add(1);
+    }
+
+    fn average(&self) -> Option<Duration> {
+        (self.samples > 0).then(|| self.total / self.samples)
+    }
 }
 
 impl VirtualInput {
     /// Creates a new virtual input handler.
     ///
-    /// Returns the handler and a sender for submitting events.
+    /// Returns the handler and a sender for submitting events. Each
+    /// priority queue holds up to `buffer_size` events. Sessions are rate
+    /// limited to
+    /// [`RateLimiterConfig::default`] unless they request a profile.
+    #[must_use]
+    pub fn new(buffer_size: usize) -> (Self, VirtualInputSender) {
+        Self::with_rate_limits(
+            buffer_size,
+            RateLimitMiddleware::new(RateLimiterConfig::default()),
+        )
+    }
+
+    /// Creates a handler whose rate limit stage is `rate_limits`.
+    ///
+    /// Its configuration applies to sessions without a profile, and its
+    /// ceiling caps the profiles they request.
+    #[must_use]
+    pub fn with_rate_limits(
+        buffer_size: usize,
+        rate_limits: RateLimitMiddleware,
+    ) -> (Self, VirtualInputSender) {
+        Self::with_middleware(buffer_size, rate_limits, [])
+    }
+
+    /// Creates a handler with extra middleware `stages`.
+    ///
+    /// The stages run in order after the pointer transform and lock and
+    /// before the rate limit, so motion they coalesce counts once against
+    /// it.
     #[must_use]
-    pub fn new(buffer_size: usize) -> (Self, mpsc::Sender<VirtualInputEvent>) {
-        let (tx, rx) = mpsc::channel(buffer_size);
+    pub fn with_middleware(
+        buffer_size: usize,
+        rate_limits: RateLimitMiddleware,
+        stages: impl IntoIterator<Item = Arc<dyn InputMiddleware>>,
+    ) -> (Self, VirtualInputSender) {
+        let (high_tx, high_rx) = mpsc::channel(buffer_size);
+        let (low_tx, low_rx) = mpsc::channel(buffer_size);
+        let transforms = PointerTransforms::default();
+        let pointer_transforms = PointerTransformMiddleware {
+            transforms: Arc::clone(&transforms),
+        };
+        let locks = PointerLocks::default();
+        let pointer_locks = PointerLockMiddleware {
+            locks: Arc::clone(&locks),
+        };
+
+        let mut middleware = MiddlewareChain::new()
+            .with(pointer_transforms.clone())
+            .with(pointer_locks.clone());
+        for stage in stages {
+            middleware.push(stage);
+        }
+        middleware.push(Arc::new(rate_limits.clone()));
+        let ended = EndedSessions::default();
 
         let handler = Self {
-            rx,
+            high_rx,
+            low_rx,
+            held: VecDeque::new(),
+            ready: VecDeque::new(),
+            middleware,
+            ended: Arc::clone(&ended),
+            pointer_transforms,
+            pointer_locks,
+            rate_limits: rate_limits.clone(),
+            pointer_locked: false,
+            pointer_owner: None,
+            max_age: None,
             events_processed: 0,
             last_event_time: None,
+            stale_dropped: 0,
+            transit: TransitStats::default(),
         };
 
-        (handler, tx)
+        let sender = VirtualInputSender {
+            high: high_tx,
+            low: low_tx,
+            next_seq: Arc::default(),
+            transforms,
+            locks,
+            rate_limits,
+            ended,
+        };
+
+        (handler, sender)
     }
 
     /// Creates with default buffer size (256 events).
     #[must_use]
-    pub fn with_defaults() -> (Self, mpsc::Sender<VirtualInputEvent>) {
+    pub fn with_defaults() -> (Self, VirtualInputSender) {
         Self::new(256)
     }
 
+    /// Drops motion whose transit latency exceeds `max_age`.
+    ///
+    /// Only client-stamped, low-priority events are dropped; `None`
+    /// delivers everything.
+    pub fn set_max_age(&mut self, max_age: Option<Duration>) {
+        self.max_age = max_age;
+    }
+
+    /// Returns the middleware every event runs through.
+    #[must_use]
+    pub fn middleware(&self) -> &MiddlewareChain {
+        &self.middleware
+    }
+
     /// Polls for the next event, non-blocking.
+    ///
+    /// High-priority events are returned before queued motion, except
+    /// motion their own session sent before them.
     #[must_use]
     pub fn try_recv(&mut self) -> Option<VirtualInputEvent> {
-        self.rx.try_recv().ok()
+        if let Some(event) = self.ready.pop_front() {
+            return Some(event);
+        }
+        if let Ok(control) = self.high_rx.try_recv() {
+            self.release_before(control);
+            return self.ready.pop_front();
+        }
+        if let Some(queued) = self.held.pop_front() {
+            return Some(queued.event);
+        }
+        self.low_rx.try_recv().ok().map(|queued| queued.event)
     }
 
-    /// Waits for the next event.
+    /// Waits for the next event, preferring high-priority events.
+    ///
+    /// Returns `None` once all senders have been dropped and both queues
+    /// are empty.
     pub async fn recv(&mut self) -> Option<VirtualInputEvent> {
-        self.rx.recv().await
+        if let Some(event) = self.try_recv() {
+            return Some(event);
+        }
+        tokio::select! {
+            biased;
+            Some(control) = self.high_rx.recv() => {
+                self.release_before(control);
+                self.ready.pop_front()
+            },
+            Some(queued) = self.low_rx.recv() => Some(queued.event),
+            else => None,
+        }
+    }
+
+    /// Readies a high-priority event behind the motion its session sent
+    /// before it.
+    ///
+    /// Motion is taken off the low-priority queue up to the first event
+    /// sent after `control`; whatever belongs to other sessions is held
+    /// back, in order, until the high-priority queue is empty.
+    fn release_before(&mut self, control: Queued) {
+        // Motion queued after anything already held was sent later still
+        let past_control = self.held.back().is_some_and(|last| last.seq > control.seq);
+        if !past_control {
+            while let Ok(queued) = self.low_rx.try_recv() {
+                let later = queued.seq > control.seq;
+                self.held.push_back(queued);
+                if later {
+                    break;
+                }
+            }
+        }
+
+        let session_id = &control.event.session_id;
+        let (earlier, held): (VecDeque<_>, VecDeque<_>) = std::mem::take(&mut self.held)
+            .into_iter()
+            .partition(|queued| queued.event.session_id == *session_id && queued.seq < control.seq);
+        self.held = held;
+        self.ready
+            .extend(earlier.into_iter().map(|queued| queued.event));
+        self.ready.push_back(control.event);
     }
 
     /// Processes all pending events with the given sink.
     ///
-    /// Returns the number of events processed.
+    /// High-priority events are dispatched first, including any that
+    /// arrive while motion is being drained, each just behind the motion
+    /// its own session sent before it.
+    ///
+    /// Returns the number of events dispatched after middleware. Stale
+    /// motion dropped under [`set_max_age`](Self::set_max_age) is not
+    /// counted.
+    ///
+    /// The sink's pointer lock is brought in line with the session
+    /// driving the pointer first, and again whenever another session's
+    /// pointer input is dispatched. Motion the middleware held back for
+    /// too long is dispatched last, so call this regularly (once per
+    /// frame, say) even when no events arrive.
     #[instrument(skip(self, sink), level = "trace")]
     pub fn process_pending(&mut self, sink: &mut impl VirtualInputSink) -> usize {
         let mut count = 0;
+        // Taken first: everything these sessions sent is already queued
+        let ended = std::mem::take(&mut *self.ended.lock().unwrap_or_else(PoisonError::into_inner));
+
+        if let Some(claimed) = self.pointer_locks.take_claimed() {
+            self.pointer_owner = Some(claimed);
+        }
+        self.sync_pointer_lock(sink);
 
         while let Some(event) = self.try_recv() {
-            self.dispatch_event(sink, &event);
-            self.events_processed += 1;
+            if self.is_stale(&event) {
+                self.stale_dropped += 1;
+                continue;
+            }
+            for dispatched in self.middleware.run(&event.session_id, event.event) {
+                self.dispatch_for(sink, &event.session_id, &dispatched);
+                self.events_processed += 1;
+                count += 1;
+            }
             self.last_event_time = Some(Instant::now());
+        }
+
+        for (session_id, flushed) in self.middleware.flush() {
+            self.dispatch_for(sink, &session_id, &flushed);
+            self.events_processed += 1;
             count += 1;
         }
 
+        for session_id in &ended {
+            self.middleware.remove_session(session_id);
+            if self.pointer_owner.as_ref() == Some(session_id) {
+                self.pointer_owner = None;
+            }
+            debug!(session = %session_id, "Session forgotten by middleware");
+        }
+
         if count > 0 {
             debug!(count, "Processed virtual input events");
         }
@@ -169,9 +934,53 @@ impl VirtualInput {
         count
     }
 
+    /// Dispatches a session's event, first handing that session the
+    /// pointer if the event is pointer input.
+    fn dispatch_for(
+        &mut self,
+        sink: &mut impl VirtualInputSink,
+        session_id: &SessionId,
+        event: &InputEvent,
+    ) {
+        if event.is_pointer() && self.pointer_owner.as_ref() != Some(session_id) {
+            self.pointer_owner = Some(session_id.clone());
+            self.sync_pointer_lock(sink);
+        }
+        Self::dispatch_event(sink, event);
+    }
+
+    /// Locks the sink's pointer if the session driving it holds the lock,
+    /// and releases it otherwise.
+    fn sync_pointer_lock(&mut self, sink: &mut impl VirtualInputSink) {
+        let locked = self
+            .pointer_owner
+            .as_ref()
+            .is_some_and(|owner| self.pointer_locks.is_locked(owner));
+        if locked != self.pointer_locked {
+            debug!(locked, owner = ?self.pointer_owner, "Pointer lock changed");
+            sink.set_pointer_lock(locked);
+            self.pointer_locked = locked;
+        }
+    }
+
+    /// Records the event's transit latency and reports whether it is motion
+    /// older than the maximum age.
+    fn is_stale(&mut self, event: &VirtualInputEvent) -> bool {
+        let Some(latency) = event.transit_latency() else {
+            return false;
+        };
+        self.transit.record(latency);
+        let stale = event.priority() == EventPriority::Low
+            && self.max_age.is_some_and(|max_age| latency > max_age);
+        if stale {
+            debug!(?latency, session = %event.session_id, "Dropping stale input event");
+        }
+        stale
+    }
+
     /// Dispatches a single event to the sink.
-    fn dispatch_event(&self, sink: &mut impl VirtualInputSink, event: &VirtualInputEvent) {
-        match &event.event {
+    fn dispatch_event(sink: &mut impl VirtualInputSink, event: &InputEvent) {
+        match event {
             InputEvent::PointerMotion { dx, dy } => {
                 sink.inject_pointer_motion(*dx, *dy);
             },
@@ -181,8 +990,13 @@ impl VirtualInput {
             InputEvent::PointerButton { button, state } => {
                 sink.inject_pointer_button(*button, *state);
             },
-            InputEvent::PointerAxis { dx, dy } => {
-                sink.inject_pointer_axis(*dx, *dy);
+            InputEvent::PointerAxis {
+                dx,
+                dy,
+                source,
+                unit,
+            } => {
+                sink.inject_pointer_axis(*dx, *dy, *source, *unit);
             },
             InputEvent::PointerAxisDiscrete { axis, steps } => {
                 sink.inject_pointer_axis_discrete(*axis, *steps);
@@ -193,6 +1007,14 @@ impl VirtualInput {
             InputEvent::KeyboardKeysym { keysym, state } => {
                 sink.inject_keyboard_keysym(*keysym, *state);
             },
+            InputEvent::KeyboardModifiers {
+                depressed,
+                latched,
+                locked,
+                group,
+            } => {
+                sink.inject_keyboard_modifiers(*depressed, *latched, *locked, *group);
+            },
             InputEvent::TouchDown { stream, slot, x, y } => {
                 sink.inject_touch_down(*stream, *slot, *x, *y);
             },
@@ -202,6 +1024,27 @@ impl VirtualInput {
             InputEvent::TouchUp { slot } => {
                 sink.inject_touch_up(*slot);
             },
+            InputEvent::GestureSwipe {
+                phase,
+                fingers,
+                dx,
+                dy,
+            } => {
+                sink.inject_gesture_swipe(*phase, *fingers, *dx, *dy);
+            },
+            InputEvent::GesturePinch {
+                phase,
+                fingers,
+                dx,
+                dy,
+                scale,
+                rotation,
+            } => {
+                sink.inject_gesture_pinch(*phase, *fingers, *dx, *dy, *scale, *rotation);
+            },
+            InputEvent::GestureHold { phase, fingers } => {
+                sink.inject_gesture_hold(*phase, *fingers);
+            },
             // Handle future variants gracefully
             _ => {
                 tracing::warn!("Unknown input event variant, ignoring");
@@ -209,6 +1052,24 @@ impl VirtualInput {
         }
     }
 
+    /// Returns the transform for a session's relative pointer motion.
+    #[must_use]
+    pub fn pointer_transform(&self, session_id: &SessionId) -> PointerTransform {
+        self.pointer_transforms.transform(session_id)
+    }
+
+    /// Returns `true` if the session holds the pointer lock.
+    #[must_use]
+    pub fn is_pointer_locked(&self, session_id: &SessionId) -> bool {
+        self.pointer_locks.is_locked(session_id)
+    }
+
+    /// Returns the rate limits a session's motion is held to.
+    #[must_use]
+    pub fn rate_limits(&self, session_id: &SessionId) -> RateLimiterConfig {
+        self.rate_limits.session_config(session_id)
+    }
+
     /// Returns the total number of events processed.
     #[must_use]
     pub fn events_processed(&self) -> u64 {
@@ -217,21 +1078,44 @@ impl VirtualInput {
 
     /// Returns the time since the last event was processed.
     #[must_use]
-    pub fn time_since_last_event(&self) -> Option<std::time::Duration> {
+    pub fn time_since_last_event(&self) -> Option<Duration> {
         self.last_event_time.map(|t| t.elapsed())
     }
+
+    /// Returns the number of stale events dropped.
+    #[must_use]
+    pub fn stale_dropped(&self) -> u64 {
+        self.stale_dropped
+    }
+
+    /// Returns the transit latency of the last client-stamped event.
+    #[must_use]
+    pub fn last_transit_latency(&self) -> Option<Duration> {
+        self.transit.last
+    }
+
+    /// Returns the mean transit latency of client-stamped events.
+    #[must_use]
+    pub fn average_transit_latency(&self) -> Option<Duration> {
+        self.transit.average()
+    }
 }
 
 /// A mock sink for testing.
 #[cfg(test)]
 pub struct MockVirtualInputSink {
     pub events: Vec<InputEvent>,
+    /// Lock state last set through `set_pointer_lock`
+    pub pointer_locked: bool,
 }
 
 #[cfg(test)]
 impl MockVirtualInputSink {
     pub fn new() -> Self {
-        Self { events: Vec::new() }
+        Self {
+            events: Vec::new(),
+            pointer_locked: false,
+        }
     }
 }
 
@@ -251,8 +1135,9 @@ impl VirtualInputSink for MockVirtualInputSink {
             .push(InputEvent::PointerButton { button, state });
     }
 
-    fn inject_pointer_axis(&mut self, dx: f64, dy: f64) {
-        self.events.push(InputEvent::PointerAxis { dx, dy });
+    fn inject_pointer_axis(&mut self, dx: f64, dy: f64, source: AxisSource, unit: ScrollUnit) {
+        self.events
+            .push(InputEvent::scroll_with(dx, dy, source, unit));
     }
 
     fn inject_pointer_axis_discrete(&mut self, axis: Axis, steps: i32) {
@@ -270,6 +1155,12 @@ impl VirtualInputSink for MockVirtualInputSink {
             .push(InputEvent::KeyboardKeysym { keysym, state });
     }
 
+    fn inject_keyboard_modifiers(&mut self, depressed: u32, latched: u32, locked: u32, group: u32) {
+        self.events.push(InputEvent::keyboard_modifiers(
+            depressed, latched, locked, group,
+        ));
+    }
+
     fn inject_touch_down(&mut self, stream: u32, slot: u32, x: f64, y: f64) {
         self.events
             .push(InputEvent::TouchDown { stream, slot, x, y });
@@ -283,11 +1174,49 @@ impl VirtualInputSink for MockVirtualInputSink {
     fn inject_touch_up(&mut self, slot: u32) {
         self.events.push(InputEvent::TouchUp { slot });
     }
-}
 
-#[cfg(test)]
-mod tests {
+    fn inject_gesture_swipe(&mut self, phase: GesturePhase, fingers: u32, dx: f64, dy: f64) {
+        self.events.push(InputEvent::GestureSwipe {
+            phase,
+            fingers,
+            dx,
+            dy,
+        });
+    }
+
+    fn inject_gesture_pinch(
+        &mut self,
+        phase: GesturePhase,
+        fingers: u32,
+        dx: f64,
+        dy: f64,
+        scale: f64,
+        rotation: f64,
+    ) {
+        self.events.push(InputEvent::GesturePinch {
+            phase,
+            fingers,
+            dx,
+            dy,
+            scale,
+            rotation,
+        });
+    }
+
+    fn inject_gesture_hold(&mut self, phase: GesturePhase, fingers: u32) {
+        self.events.push(InputEvent::GestureHold { phase, fingers });
+    }
+
+    fn set_pointer_lock(&mut self, locked: bool) {
+        self.pointer_locked = locked;
+    }
+}
+
+#[cfg(test)]
+mod tests {
     use super::*;
+    use crate::middleware::CoalesceMotion;
+    use ion_core::event::AccelProfile;
 
     #[tokio::test]
     async fn virtual_input_basic_flow() {
@@ -314,9 +1243,9 @@ mod tests {
         assert_eq!(count, 2);
         assert_eq!(handler.events_processed(), 2);
 
-        // Verify events
+        // Verify events arrive in the order they were sent
         assert_eq!(sink.events.len(), 2);
-        assert!(sink.events[0].is_pointer());
+        assert!(matches!(sink.events[0], InputEvent::PointerMotion { .. }));
         assert!(matches!(
             sink.events[1],
             InputEvent::PointerButton {
@@ -467,7 +1396,7 @@ mod tests {
                 button: 1,
                 state: ButtonState::Pressed,
             },
-            InputEvent::PointerAxis { dx: 0.0, dy: -10.0 },
+            InputEvent::scroll(0.0, -10.0),
             InputEvent::PointerAxisDiscrete {
                 axis: Axis::Vertical,
                 steps: -1,
@@ -506,6 +1435,716 @@ mod tests {
         assert_eq!(sink.events.len(), 10);
     }
 
+    #[tokio::test]
+    async fn modifier_state_stays_ordered_with_keys() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+
+        // Ctrl+C by explicit mask, with motion in between
+        let events = [
+            InputEvent::keyboard_modifiers(0x4, 0, 0, 0),
+            InputEvent::pointer_motion(1.0, 0.0),
+            InputEvent::key(46, KeyState::Pressed),
+            InputEvent::key(46, KeyState::Released),
+            InputEvent::keyboard_modifiers(0, 0, 0, 0),
+        ];
+        for event in events.clone() {
+            tx.send(VirtualInputEvent::new(SessionId::new("/test"), event))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(handler.process_pending(&mut sink), 5);
+        let keyboard: Vec<_> = sink.events.iter().filter(|e| e.is_keyboard()).collect();
+        assert_eq!(keyboard, [&events[0], &events[2], &events[3], &events[4]]);
+    }
+
+    #[tokio::test]
+    async fn finger_and_wheel_scroll_reach_sink_distinctly() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+
+        tx.send(VirtualInputEvent::new(
+            SessionId::new("/test"),
+            InputEvent::scroll(0.0, 3.0),
+        ))
+        .await
+        .unwrap();
+        tx.send(VirtualInputEvent::new(
+            SessionId::new("/test"),
+            InputEvent::scroll_with(0.0, 3.0, AxisSource::Finger, ScrollUnit::Pixels),
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(handler.process_pending(&mut sink), 2);
+        assert!(matches!(
+            sink.events[0],
+            InputEvent::PointerAxis {
+                source: AxisSource::Wheel,
+                ..
+            }
+        ));
+        assert!(matches!(
+            sink.events[1],
+            InputEvent::PointerAxis {
+                source: AxisSource::Finger,
+                ..
+            }
+        ));
+        assert_ne!(sink.events[0], sink.events[1]);
+    }
+
+    #[tokio::test]
+    async fn line_scroll_unit_reaches_sink() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+
+        tx.send(VirtualInputEvent::new(
+            SessionId::new("/test"),
+            InputEvent::scroll_with(0.0, 2.0, AxisSource::Wheel, ScrollUnit::Lines),
+        ))
+        .await
+        .unwrap();
+
+        handler.process_pending(&mut sink);
+        assert!(matches!(
+            sink.events[0],
+            InputEvent::PointerAxis {
+                unit: ScrollUnit::Lines,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn button_release_preempts_queued_motion() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+        let flooder = SessionId::new("/test/flood");
+
+        for i in 0..50 {
+            if i == 25 {
+                tx.send(VirtualInputEvent::new(
+                    SessionId::new("/test/control"),
+                    InputEvent::left_click(false),
+                ))
+                .await
+                .unwrap();
+            }
+            tx.send(VirtualInputEvent::new(
+                flooder.clone(),
+                InputEvent::pointer_motion(f64::from(i), 0.0),
+            ))
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(handler.process_pending(&mut sink), 51);
+        assert_eq!(sink.events[0], InputEvent::left_click(false));
+
+        // Motion keeps its relative order behind the release
+        let motion_dx: Vec<f64> = sink.events[1..]
+            .iter()
+            .map(|e| match e {
+                InputEvent::PointerMotion { dx, .. } => *dx,
+                other => panic!("unexpected event {other:?}"),
+            })
+            .collect();
+        assert_eq!(motion_dx, (0..50).map(f64::from).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn control_event_follows_its_own_sessions_motion() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+        let dragger = SessionId::new("/test/drag");
+        let other = SessionId::new("/test/other");
+
+        for (session, event) in [
+            (&dragger, InputEvent::pointer_motion(1.0, 0.0)),
+            (&other, InputEvent::pointer_motion(2.0, 0.0)),
+            (&dragger, InputEvent::pointer_motion(3.0, 0.0)),
+            (&dragger, InputEvent::left_click(false)),
+            (&other, InputEvent::pointer_motion(4.0, 0.0)),
+        ] {
+            tx.send(VirtualInputEvent::new(session.clone(), event))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(handler.process_pending(&mut sink), 5);
+        assert_eq!(
+            sink.events,
+            [
+                InputEvent::pointer_motion(1.0, 0.0),
+                InputEvent::pointer_motion(3.0, 0.0),
+                InputEvent::left_click(false),
+                InputEvent::pointer_motion(2.0, 0.0),
+                InputEvent::pointer_motion(4.0, 0.0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn drag_stays_in_order() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+        let session = SessionId::new("/test");
+
+        let mut drag = vec![InputEvent::left_click(true)];
+        drag.extend((0..50).map(|i| InputEvent::pointer_motion(f64::from(i), 0.0)));
+        drag.push(InputEvent::left_click(false));
+        for event in drag.clone() {
+            tx.send(VirtualInputEvent::new(session.clone(), event))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(handler.process_pending(&mut sink), 52);
+        assert_eq!(sink.events, drag);
+    }
+
+    #[tokio::test]
+    async fn mixed_events_keep_send_order() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+
+        let events = [
+            InputEvent::pointer_motion(1.0, 1.0),
+            InputEvent::TouchDown {
+                stream: 0,
+                slot: 0,
+                x: 1.0,
+                y: 1.0,
+            },
+            InputEvent::TouchMotion {
+                stream: 0,
+                slot: 0,
+                x: 2.0,
+                y: 2.0,
+            },
+            InputEvent::left_click(true),
+            InputEvent::TouchUp { slot: 0 },
+            InputEvent::scroll(0.0, 10.0),
+            InputEvent::left_click(false),
+        ];
+        for event in events.clone() {
+            tx.send(VirtualInputEvent::new(SessionId::new("/test"), event))
+                .await
+                .unwrap();
+        }
+
+        handler.process_pending(&mut sink);
+        assert_eq!(sink.events, events);
+    }
+
+    #[tokio::test]
+    async fn recv_preserves_send_order() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+
+        tx.send(VirtualInputEvent::new(
+            SessionId::new("/test"),
+            InputEvent::pointer_motion(1.0, 2.0),
+        ))
+        .await
+        .unwrap();
+        tx.send(VirtualInputEvent::new(
+            SessionId::new("/test"),
+            InputEvent::KeyboardKeycode {
+                keycode: 30,
+                state: KeyState::Released,
+            },
+        ))
+        .await
+        .unwrap();
+        drop(tx);
+
+        assert!(handler.recv().await.unwrap().event.is_pointer());
+        assert!(handler.recv().await.unwrap().event.is_keyboard());
+        assert!(handler.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn recv_prefers_high_priority() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+
+        tx.send(VirtualInputEvent::new(
+            SessionId::new("/test/pointer"),
+            InputEvent::pointer_motion(1.0, 2.0),
+        ))
+        .await
+        .unwrap();
+        tx.send(VirtualInputEvent::new(
+            SessionId::new("/test/keyboard"),
+            InputEvent::key(30, KeyState::Released),
+        ))
+        .await
+        .unwrap();
+        drop(tx);
+
+        assert!(handler.recv().await.unwrap().event.is_keyboard());
+        assert!(handler.recv().await.unwrap().event.is_pointer());
+        assert!(handler.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn full_motion_queue_leaves_room_for_state_changes() {
+        let (mut handler, tx) = VirtualInput::new(2);
+        let session = SessionId::new("/test");
+
+        for dx in [1.0, 2.0] {
+            tx.try_send(VirtualInputEvent::new(
+                session.clone(),
+                InputEvent::pointer_motion(dx, 0.0),
+            ))
+            .unwrap();
+        }
+        assert!(matches!(
+            tx.try_send(VirtualInputEvent::new(
+                session.clone(),
+                InputEvent::pointer_motion(3.0, 0.0),
+            )),
+            Err(TrySendError::Full(_))
+        ));
+
+        // A release has its own queue, and still follows the motion
+        tx.try_send(VirtualInputEvent::new(
+            session.clone(),
+            InputEvent::left_click(false),
+        ))
+        .unwrap();
+        assert!(handler.recv().await.unwrap().event.is_pointer());
+
+        let mut sink = MockVirtualInputSink::new();
+        assert_eq!(handler.process_pending(&mut sink), 2);
+        assert_eq!(sink.events[1], InputEvent::left_click(false));
+        assert!(!tx.is_closed());
+        drop(handler);
+        assert!(tx.is_closed());
+    }
+
+    #[tokio::test]
+    async fn pointer_transform_scales_session_motion() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+        let trackpad = SessionId::new("/test/trackpad");
+        let other = SessionId::new("/test/other");
+
+        tx.set_pointer_transform(trackpad.clone(), PointerTransform::with_sensitivity(2.0));
+        for session in [&trackpad, &other] {
+            tx.send(VirtualInputEvent::new(
+                session.clone(),
+                InputEvent::pointer_motion(3.0, -1.5),
+            ))
+            .await
+            .unwrap();
+        }
+        handler.process_pending(&mut sink);
+
+        assert_eq!(
+            sink.events,
+            [
+                InputEvent::pointer_motion(6.0, -3.0),
+                InputEvent::pointer_motion(3.0, -1.5),
+            ]
+        );
+
+        tx.clear_pointer_transform(&trackpad);
+        assert!(handler.pointer_transform(&trackpad).is_identity());
+    }
+
+    #[tokio::test]
+    async fn portal_session_transform_reaches_the_sink() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+        let (portal_tx, mut portal_rx) = mpsc::channel(4);
+        let session_id = SessionId::new("/test/portal/trackpad");
+        let session =
+            ion_core::session::SessionHandle::new(session_id.clone(), "app".into(), portal_tx);
+        session
+            .select_devices(ion_core::DeviceType::POINTER)
+            .await
+            .unwrap();
+        session.start().await.unwrap();
+        tx.set_pointer_transform(session_id.clone(), PointerTransform::with_sensitivity(2.0));
+
+        session
+            .send_stamped_event(InputEvent::pointer_motion(3.0, -1.5), Some(7))
+            .await
+            .unwrap();
+        let stamped = portal_rx.recv().await.unwrap();
+        tx.send(VirtualInputEvent::from_stamped(session_id, stamped))
+            .await
+            .unwrap();
+        handler.process_pending(&mut sink);
+
+        assert_eq!(sink.events, [InputEvent::pointer_motion(6.0, -3.0)]);
+    }
+
+    #[tokio::test]
+    async fn flat_identity_transform_leaves_motion_unchanged() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+        let session = SessionId::new("/test/flat");
+
+        tx.set_pointer_transform(
+            session.clone(),
+            PointerTransform::default().with_accel_profile(AccelProfile::Flat),
+        );
+        tx.send(VirtualInputEvent::new(
+            session,
+            InputEvent::pointer_motion(-7.25, 12.5),
+        ))
+        .await
+        .unwrap();
+        handler.process_pending(&mut sink);
+
+        assert_eq!(sink.events, [InputEvent::pointer_motion(-7.25, 12.5)]);
+    }
+
+    #[tokio::test]
+    async fn pointer_lock_makes_sink_relative_only() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+        let game = SessionId::new("/test/game");
+
+        tx.set_pointer_lock(game.clone(), true);
+        for event in [
+            InputEvent::pointer_motion_absolute(0, 100.0, 50.0),
+            InputEvent::pointer_motion(4.0, -2.0),
+        ] {
+            tx.send(VirtualInputEvent::new(game.clone(), event))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(handler.process_pending(&mut sink), 1);
+        assert!(sink.pointer_locked);
+        assert!(handler.is_pointer_locked(&game));
+        assert_eq!(sink.events, [InputEvent::pointer_motion(4.0, -2.0)]);
+    }
+
+    #[tokio::test]
+    async fn sink_pointer_lock_follows_the_session_driving_it() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+        let game = SessionId::new("/test/game");
+        let other = SessionId::new("/test/other");
+
+        tx.set_pointer_lock(game.clone(), true);
+        handler.process_pending(&mut sink);
+        assert!(sink.pointer_locked);
+
+        // Another session's pointer input gets an unlocked pointer
+        for event in [
+            InputEvent::key(30, KeyState::Pressed),
+            InputEvent::pointer_motion_absolute(0, 100.0, 50.0),
+        ] {
+            tx.send(VirtualInputEvent::new(other.clone(), event))
+                .await
+                .unwrap();
+        }
+        assert_eq!(handler.process_pending(&mut sink), 2);
+        assert!(!sink.pointer_locked);
+        assert!(handler.is_pointer_locked(&game));
+        assert!(!handler.is_pointer_locked(&other));
+
+        // The holder moving again locks it again
+        tx.send(VirtualInputEvent::new(
+            game.clone(),
+            InputEvent::pointer_motion(4.0, -2.0),
+        ))
+        .await
+        .unwrap();
+        assert_eq!(handler.process_pending(&mut sink), 1);
+        assert!(sink.pointer_locked);
+        assert_eq!(
+            sink.events,
+            [
+                InputEvent::key(30, KeyState::Pressed),
+                InputEvent::pointer_motion_absolute(0, 100.0, 50.0),
+                InputEvent::pointer_motion(4.0, -2.0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn pointer_unlock_restores_absolute_motion() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+        let game = SessionId::new("/test/game");
+
+        tx.set_pointer_lock(game.clone(), true);
+        handler.process_pending(&mut sink);
+        assert!(sink.pointer_locked);
+
+        tx.set_pointer_lock(game.clone(), false);
+        tx.send(VirtualInputEvent::new(
+            game.clone(),
+            InputEvent::pointer_motion_absolute(0, 100.0, 50.0),
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(handler.process_pending(&mut sink), 1);
+        assert!(!sink.pointer_locked);
+        assert!(!handler.is_pointer_locked(&game));
+        assert_eq!(
+            sink.events,
+            [InputEvent::pointer_motion_absolute(0, 100.0, 50.0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn rate_profile_changes_dispatched_motion() {
+        let ceiling = RateLimiterConfig {
+            max_events_per_sec: 3000,
+            burst_limit: 300,
+            window: Duration::from_secs(60),
+        };
+        let (mut handler, tx) = VirtualInput::with_rate_limits(
+            1024,
+            RateLimitMiddleware::with_ceiling(RateLimiterConfig::default(), ceiling),
+        );
+        let normal = SessionId::new("/test/normal");
+        let high = SessionId::new("/test/automation");
+        assert_eq!(
+            tx.set_rate_profile(&high, RateProfile::High).burst_limit,
+            300
+        );
+        assert_eq!(handler.rate_limits(&normal).burst_limit, 100);
+
+        let mut dispatched = Vec::new();
+        for session in [&normal, &high] {
+            for _ in 0..400 {
+                tx.send(VirtualInputEvent::new(
+                    session.clone(),
+                    InputEvent::pointer_motion(1.0, 0.0),
+                ))
+                .await
+                .unwrap();
+            }
+            let mut sink = MockVirtualInputSink::new();
+            dispatched.push(handler.process_pending(&mut sink));
+        }
+        assert_eq!(dispatched, [100, 300]);
+
+        tx.end_session(&high);
+        assert_eq!(handler.rate_limits(&high).burst_limit, 100);
+    }
+
+    #[tokio::test]
+    async fn ended_session_is_forgotten_after_its_events() {
+        let rate_limits = RateLimitMiddleware::with_defaults();
+        let coalesce = Arc::new(CoalesceMotion::new(10.0).with_max_hold(Duration::from_secs(60)));
+        let (mut handler, tx) = VirtualInput::with_middleware(
+            16,
+            rate_limits.clone(),
+            [coalesce as Arc<dyn InputMiddleware>],
+        );
+        assert_eq!(
+            handler.middleware().names(),
+            [
+                "pointer-transform",
+                "pointer-lock",
+                "coalesce-motion",
+                "rate-limit"
+            ]
+        );
+        let session = SessionId::new("/test/ended");
+        let mut sink = MockVirtualInputSink::new();
+
+        tx.send(VirtualInputEvent::new(
+            session.clone(),
+            InputEvent::pointer_motion(3.0, 0.0),
+        ))
+        .await
+        .unwrap();
+        tx.send(VirtualInputEvent::new(
+            session.clone(),
+            InputEvent::left_click(true),
+        ))
+        .await
+        .unwrap();
+        tx.send(VirtualInputEvent::new(
+            session.clone(),
+            InputEvent::pointer_motion(3.0, 0.0),
+        ))
+        .await
+        .unwrap();
+        tx.end_session(&session);
+        assert_eq!(handler.process_pending(&mut sink), 2);
+        assert_eq!(rate_limits.session_count(), 0);
+
+        // Motion held back for the ended session is gone with it
+        tx.send(VirtualInputEvent::new(
+            session.clone(),
+            InputEvent::left_click(false),
+        ))
+        .await
+        .unwrap();
+        assert_eq!(handler.process_pending(&mut sink), 1);
+        assert_eq!(
+            sink.events,
+            [
+                InputEvent::pointer_motion(3.0, 0.0),
+                InputEvent::left_click(true),
+                InputEvent::left_click(false),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn trailing_motion_is_flushed_without_another_event() {
+        let coalesce = CoalesceMotion::new(10.0).with_max_hold(Duration::from_millis(20));
+        let (mut handler, tx) = VirtualInput::with_middleware(
+            16,
+            RateLimitMiddleware::with_defaults(),
+            [Arc::new(coalesce) as Arc<dyn InputMiddleware>],
+        );
+        let session = SessionId::new("/test/trailing");
+        let mut sink = MockVirtualInputSink::new();
+
+        tx.send(VirtualInputEvent::new(
+            session,
+            InputEvent::pointer_motion(2.0, 1.0),
+        ))
+        .await
+        .unwrap();
+        assert_eq!(handler.process_pending(&mut sink), 0);
+
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        assert_eq!(handler.process_pending(&mut sink), 1);
+        assert_eq!(sink.events, [InputEvent::pointer_motion(2.0, 1.0)]);
+    }
+
+    fn micros_ago(ago: Duration) -> u64 {
+        let sent = SystemTime::now() - ago;
+        u64::try_from(sent.duration_since(UNIX_EPOCH).unwrap().as_micros()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn stale_motion_dropped_fresh_passes() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+        let session = SessionId::new("/test/latency");
+        handler.set_max_age(Some(Duration::from_millis(500)));
+
+        for (dx, ago) in [(1.0, Duration::from_secs(5)), (2.0, Duration::ZERO)] {
+            tx.send(
+                VirtualInputEvent::new(session.clone(), InputEvent::pointer_motion(dx, 0.0))
+                    .with_client_timestamp(micros_ago(ago)),
+            )
+            .await
+            .unwrap();
+        }
+        // Unstamped motion is never considered stale
+        tx.send(VirtualInputEvent::new(
+            session.clone(),
+            InputEvent::pointer_motion(3.0, 0.0),
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(handler.process_pending(&mut sink), 2);
+        assert_eq!(
+            sink.events,
+            [
+                InputEvent::pointer_motion(2.0, 0.0),
+                InputEvent::pointer_motion(3.0, 0.0),
+            ]
+        );
+        assert_eq!(handler.stale_dropped(), 1);
+        assert!(handler.last_transit_latency().unwrap() < Duration::from_millis(500));
+        assert!(handler.average_transit_latency().unwrap() >= Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn stale_gestures_reach_the_sink() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+        handler.set_max_age(Some(Duration::from_millis(500)));
+        let gestures = [
+            InputEvent::GestureSwipe {
+                phase: GesturePhase::Update,
+                fingers: 3,
+                dx: 4.0,
+                dy: 0.0,
+            },
+            InputEvent::GesturePinch {
+                phase: GesturePhase::Update,
+                fingers: 2,
+                dx: 0.0,
+                dy: 1.0,
+                scale: 1.5,
+                rotation: 10.0,
+            },
+            InputEvent::GestureHold {
+                phase: GesturePhase::End,
+                fingers: 4,
+            },
+        ];
+
+        for gesture in &gestures {
+            let event = VirtualInputEvent::new(SessionId::new("/test"), gesture.clone())
+                .with_client_timestamp(micros_ago(Duration::from_secs(5)));
+            assert_eq!(event.priority(), EventPriority::High);
+            tx.send(event).await.unwrap();
+        }
+
+        assert_eq!(handler.process_pending(&mut sink), 3);
+        assert_eq!(sink.events, gestures);
+        assert_eq!(handler.stale_dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn stale_state_changes_still_delivered() {
+        let (mut handler, tx) = VirtualInput::with_defaults();
+        let mut sink = MockVirtualInputSink::new();
+        handler.set_max_age(Some(Duration::from_millis(500)));
+
+        tx.send(
+            VirtualInputEvent::new(SessionId::new("/test"), InputEvent::left_click(false))
+                .with_client_timestamp(micros_ago(Duration::from_secs(5))),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(handler.process_pending(&mut sink), 1);
+        assert_eq!(sink.events, [InputEvent::left_click(false)]);
+        assert_eq!(handler.stale_dropped(), 0);
+        assert!(handler.last_transit_latency().unwrap() >= Duration::from_secs(4));
+    }
+
+    #[test]
+    fn transit_latency_requires_client_timestamp() {
+        let event = VirtualInputEvent::new(
+            SessionId::new("/test"),
+            InputEvent::pointer_motion(0.0, 0.0),
+        );
+        assert_eq!(event.transit_latency(), None);
+
+        // A client clock running ahead never yields negative latency
+        let ahead = micros_ago(Duration::ZERO) + 10_000_000;
+        assert_eq!(
+            event.with_client_timestamp(ahead).transit_latency(),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn transit_latency_measured_at_receipt() {
+        let mut event = VirtualInputEvent::new(
+            SessionId::new("/test"),
+            InputEvent::pointer_motion(0.0, 0.0),
+        );
+        event.received_at = UNIX_EPOCH + Duration::from_secs(1_700_000_002);
+        let event = event.with_client_timestamp(1_700_000_000_000_000);
+
+        assert_eq!(event.transit_latency(), Some(Duration::from_secs(2)));
+    }
+
     #[test]
     fn mock_sink_new() {
         let sink = MockVirtualInputSink::new();
@@ -519,7 +2158,7 @@ mod tests {
         sink.inject_pointer_motion(1.0, 2.0);
         sink.inject_pointer_motion_absolute(0, 100.0, 200.0);
         sink.inject_pointer_button(1, ButtonState::Pressed);
-        sink.inject_pointer_axis(0.0, -10.0);
+        sink.inject_pointer_axis(0.0, -10.0, AxisSource::Wheel, ScrollUnit::Pixels);
         sink.inject_pointer_axis_discrete(Axis::Vertical, -1);
         sink.inject_keyboard_keycode(30, KeyState::Pressed);
         sink.inject_keyboard_keysym(0x61, KeyState::Released);
@@ -530,6 +2169,79 @@ mod tests {
         assert_eq!(sink.events.len(), 10);
     }
 
+    #[test]
+    fn pointer_positioner_bridges_absolute_targets() {
+        let mut sink = MockVirtualInputSink::new();
+        let mut positioner = PointerPositioner::new();
+        positioner.set_position(100.0, 100.0);
+
+        assert_eq!(
+            positioner.notify_pointer_motion_absolute(&mut sink, 150.0, 80.0),
+            (50.0, -20.0)
+        );
+        // Relative motion from elsewhere moves the tracked position
+        positioner.notify_pointer_motion(&mut sink, 10.0, 10.0);
+        assert_eq!(
+            positioner.notify_pointer_motion_absolute(&mut sink, 0.0, 0.0),
+            (-160.0, -90.0)
+        );
+        // Already there: nothing to inject
+        assert_eq!(
+            positioner.notify_pointer_motion_absolute(&mut sink, 0.0, 0.0),
+            (0.0, 0.0)
+        );
+
+        assert_eq!(
+            sink.events,
+            [
+                InputEvent::pointer_motion(50.0, -20.0),
+                InputEvent::pointer_motion(10.0, 10.0),
+                InputEvent::pointer_motion(-160.0, -90.0),
+            ]
+        );
+        assert_eq!(positioner.position(), Some((0.0, 0.0)));
+    }
+
+    #[test]
+    fn pointer_positioner_warps_from_unknown_position() {
+        let mut sink = MockVirtualInputSink::new();
+        let mut positioner = PointerPositioner::new().with_bounds(1920.0, 1080.0);
+
+        // Untracked motion leaves the position unknown
+        positioner.notify_pointer_motion(&mut sink, 5.0, 5.0);
+        assert_eq!(positioner.position(), None);
+
+        let warp = -PointerPositioner::WARP_DISTANCE;
+        assert_eq!(
+            positioner.notify_pointer_motion_absolute(&mut sink, 300.0, 200.0),
+            (300.0, 200.0)
+        );
+        // Targets and motion are clamped to the layout, as the compositor
+        // clamps the pointer
+        assert_eq!(
+            positioner.notify_pointer_motion_absolute(&mut sink, 2500.0, 100.0),
+            (1620.0, -100.0)
+        );
+        positioner.notify_pointer_motion(&mut sink, 100.0, 0.0);
+        assert_eq!(positioner.position(), Some((1920.0, 100.0)));
+
+        positioner.forget();
+        positioner.notify_pointer_motion_absolute(&mut sink, 10.0, 10.0);
+
+        assert_eq!(
+            sink.events,
+            [
+                InputEvent::pointer_motion(5.0, 5.0),
+                InputEvent::pointer_motion(warp, warp),
+                InputEvent::pointer_motion(300.0, 200.0),
+                InputEvent::pointer_motion(1620.0, -100.0),
+                InputEvent::pointer_motion(100.0, 0.0),
+                InputEvent::pointer_motion(warp, warp),
+                InputEvent::pointer_motion(10.0, 10.0),
+            ]
+        );
+    }
+
     #[test]
     fn virtual_input_is_send_sync() {
         fn assert_send<T: Send>() {}
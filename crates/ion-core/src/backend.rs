@@ -7,12 +7,22 @@
 //! different display servers (Wayland compositors, X11, virtual displays, etc.)
 //! through a unified interface.
 
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use thiserror::Error;
+use tokio::sync::{broadcast, mpsc, Notify};
+use tokio::task::JoinHandle;
 
-use crate::event::InputEvent;
+pub use ion_traits::capture::{
+    CaptureFrame, FrameFormat, FrameMetadata, OutputTransform, TransformHandling,
+};
+
+use crate::event::{InputEvent, InputEventKind, PointerTransform};
+use crate::mode::CaptureTierInfo;
 use crate::session::SessionId;
 
 /// Errors that can occur in compositor backend operations.
@@ -38,6 +48,10 @@ pub enum BackendError {
     #[error("Invalid session: {0}")]
     InvalidSession(String),
 
+    /// Input was aimed at a seat the compositor does not have
+    #[error("Seat not found: {0}")]
+    SeatNotFound(String),
+
     /// Backend-specific error
     #[error("Backend error: {0}")]
     Other(String),
@@ -60,7 +74,7 @@ pub enum DisplayServerType {
 }
 
 /// Capabilities provided by a compositor backend.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BackendCapabilities {
     /// Can inject keyboard events
     pub can_inject_keyboard: bool,
@@ -74,16 +88,666 @@ pub struct BackendCapabilities {
     pub backend_name: String,
 }
 
+impl BackendCapabilities {
+    /// Whether any kind of input can be injected.
+    #[must_use]
+    pub fn can_inject_input(&self) -> bool {
+        self.can_inject_keyboard || self.can_inject_pointer
+    }
+
+    /// Basic event kinds implied by the input flags: every keyboard kind
+    /// with keyboard support and every pointer kind with pointer support.
+    #[must_use]
+    pub fn input_events(&self) -> Vec<InputEventKind> {
+        let mut kinds = Vec::new();
+        if self.can_inject_pointer {
+            kinds.extend(InputEventKind::POINTER);
+        }
+        if self.can_inject_keyboard {
+            kinds.extend(InputEventKind::KEYBOARD);
+        }
+        kinds
+    }
+
+    /// Union of two backends' capabilities.
+    ///
+    /// The display server type is taken from `self` unless it is unknown.
+    #[must_use]
+    pub fn merge(&self, other: &Self) -> Self {
+        Self {
+            can_inject_keyboard: self.can_inject_keyboard || other.can_inject_keyboard,
+            can_inject_pointer: self.can_inject_pointer || other.can_inject_pointer,
+            can_capture_screen: self.can_capture_screen || other.can_capture_screen,
+            display_server_type: if self.display_server_type == DisplayServerType::Unknown {
+                other.display_server_type
+            } else {
+                self.display_server_type
+            },
+            backend_name: format!("{} + {}", self.backend_name, other.backend_name),
+        }
+    }
+}
+
+/// Sends [`BackendCapabilities`] snapshots to subscribers when they
+/// change.
+///
+/// Backends keep one and call [`Self::notify`] from whatever watches their
+/// environment (`NameOwnerChanged`, Wayland registry globals), returning
+/// [`Self::subscribe`] from [`CompositorBackend::capability_updates`].
+#[derive(Debug, Clone)]
+pub struct CapabilityNotifier(broadcast::Sender<BackendCapabilities>);
+
+impl CapabilityNotifier {
+    /// Snapshots buffered per subscriber before the oldest is dropped.
+    pub const CAPACITY: usize = 16;
+
+    /// Receive every snapshot sent from now on.
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<BackendCapabilities> {
+        self.0.subscribe()
+    }
+
+    /// Send `capabilities` to every subscriber.
+    ///
+    /// Returns how many subscribers it reached.
+    pub fn notify(&self, capabilities: BackendCapabilities) -> usize {
+        self.0.send(capabilities).unwrap_or(0)
+    }
+}
+
+impl Default for CapabilityNotifier {
+    fn default() -> Self {
+        Self(broadcast::channel(Self::CAPACITY).0)
+    }
+}
+
+/// The compositor withdrew a session's access, e.g. because the user
+/// revoked it from a settings panel.
+///
+/// Sent by [`CompositorBackend::session_revocations`]; the portal closes
+/// the session in response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionRevocation {
+    /// Portal session handle the compositor was given
+    pub session_id: SessionId,
+    /// Why the compositor revoked it, for logs
+    pub reason: String,
+}
+
+/// Position and size of a surface, in the compositor's global logical
+/// coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SurfaceGeometry {
+    /// Left edge
+    pub x: i32,
+    /// Top edge
+    pub y: i32,
+    /// Width
+    pub width: u32,
+    /// Height
+    pub height: u32,
+}
+
+/// The compositor moved input focus to another surface.
+///
+/// Sent by [`CompositorBackend::focus_changes`] so a remote operator can
+/// tell which window their input lands in.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FocusChanged {
+    /// Title of the focused surface; empty if it has none
+    pub surface_title: String,
+    /// App ID of the focused surface; empty if unknown
+    pub app_id: String,
+    /// Where the surface is, if the compositor reports it
+    pub geometry: Option<SurfaceGeometry>,
+}
+
+/// Color space an output is driven in.
+///
+/// Reported by the compositor's color management protocol; outputs
+/// without color information are taken to be sRGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColorSpace {
+    /// sRGB / BT.709 primaries
+    #[default]
+    Srgb,
+    /// Display P3 primaries
+    DisplayP3,
+    /// BT.2020 primaries, as used for HDR
+    Bt2020,
+}
+
+impl ColorSpace {
+    /// Short name, as advertised to clients.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Srgb => "srgb",
+            Self::DisplayP3 => "display-p3",
+            Self::Bt2020 => "bt2020",
+        }
+    }
+
+    /// Whether the gamut is wider than sRGB.
+    #[must_use]
+    pub const fn is_wide_gamut(self) -> bool {
+        !matches!(self, Self::Srgb)
+    }
+}
+
+impl std::fmt::Display for ColorSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Geometry of a compositor output.
+///
+/// Sizes are in the logical coordinate space used by absolute pointer
+/// and touch events, which are relative to the output's stream. They
+/// describe the output upright, after its [`OutputTransform`]: a portrait
+/// panel rotated by 90° reports its short side as the width.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputInfo {
+    /// Stream ID the output is exposed as
+    pub stream: u32,
+    /// Output (connector) name, e.g. `DP-1`
+    pub name: String,
+    /// Logical width
+    pub width: u32,
+    /// Logical height
+    pub height: u32,
+    /// Color space the output is driven in; sRGB when unknown
+    pub color_space: ColorSpace,
+    /// Whether the output runs in HDR; false when unknown
+    pub hdr: bool,
+    /// How the panel is rotated or flipped; normal when unknown
+    pub transform: OutputTransform,
+}
+
+impl OutputInfo {
+    /// Whether `(x, y)` lies within the output (edges inclusive).
+    #[must_use]
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        (0.0..=f64::from(self.width)).contains(&x) && (0.0..=f64::from(self.height)).contains(&y)
+    }
+
+    /// Clamp `(x, y)` onto the output.
+    #[must_use]
+    pub fn clamp(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            x.clamp(0.0, f64::from(self.width)),
+            y.clamp(0.0, f64::from(self.height)),
+        )
+    }
+}
+
+/// How the cursor is delivered with captured frames.
+///
+/// Mirrors the `ScreenCast` portal's cursor modes, including their bit
+/// values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[repr(u32)]
+pub enum CursorMode {
+    /// Cursor left out of the capture
+    Hidden = 1,
+    /// Cursor composited into the frames
+    #[default]
+    Embedded = 2,
+    /// Frames left cursor-free, with the cursor sent as [`CursorUpdate`]s
+    Metadata = 4,
+}
+
+impl CursorMode {
+    /// Portal option key carrying the cursor mode (`u`).
+    pub const OPTION_KEY: &'static str = "cursor_mode";
+
+    /// The mode a backend can deliver when `self` is requested.
+    ///
+    /// Backends that cannot composite the cursor into frames (such as a
+    /// plain screencopy without cursor overlay) send it as metadata
+    /// instead of dropping it.
+    #[must_use]
+    pub const fn resolve(self, can_embed: bool) -> Self {
+        match self {
+            Self::Embedded if !can_embed => Self::Metadata,
+            mode => mode,
+        }
+    }
+}
+
+impl From<u32> for CursorMode {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::Hidden,
+            4 => Self::Metadata,
+            _ => Self::Embedded,
+        }
+    }
+}
+
+/// Cursor position sent beside the frames in [`CursorMode::Metadata`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorUpdate {
+    /// X position in the output's logical coordinates
+    pub x: f64,
+    /// Y position in the output's logical coordinates
+    pub y: f64,
+    /// Sequence number of the first frame the position applies to
+    pub sequence: u64,
+}
+
+/// Parameters for a one-shot capture.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CaptureRequest {
+    /// Output stream to capture; `None` means the primary output
+    pub stream: Option<u32>,
+    /// Preferred pixel format; backends may return a different one
+    pub format: Option<FrameFormat>,
+    /// How to deliver the cursor
+    ///
+    /// A single frame has no side channel, so [`CursorMode::Metadata`]
+    /// yields a cursor-free frame.
+    pub cursor_mode: CursorMode,
+}
+
+impl CaptureRequest {
+    /// Portal option key selecting the stream to capture.
+    pub const STREAM_OPTION_KEY: &'static str = "stream";
+}
+
+/// Parameters for a continuous capture of one output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputCaptureRequest {
+    /// Output stream to capture
+    pub stream: u32,
+    /// Target frame rate
+    pub target_fps: u32,
+    /// Preferred capture tier; backends may pick another
+    pub tier: Option<CaptureTierInfo>,
+    /// Preferred pixel format; backends may return a different one
+    pub format: Option<FrameFormat>,
+    /// How to deliver the cursor
+    pub cursor_mode: CursorMode,
+}
+
+impl OutputCaptureRequest {
+    /// Frame rate used when the client doesn't ask for one.
+    pub const DEFAULT_FPS: u32 = 30;
+
+    /// 10-bit format preferred for HDR outputs.
+    pub const HDR_FORMAT: FrameFormat = FrameFormat::Xrgb2101010;
+
+    /// Request a capture of `stream` at the default frame rate.
+    #[must_use]
+    pub const fn new(stream: u32) -> Self {
+        Self {
+            stream,
+            target_fps: Self::DEFAULT_FPS,
+            tier: None,
+            format: None,
+            cursor_mode: CursorMode::Embedded,
+        }
+    }
+
+    /// Set the target frame rate.
+    #[must_use]
+    pub const fn with_fps(mut self, target_fps: u32) -> Self {
+        self.target_fps = target_fps;
+        self
+    }
+
+    /// Request a capture of `output` in a format suited to it.
+    ///
+    /// HDR outputs prefer [`Self::HDR_FORMAT`], which backends whose tier
+    /// cannot deliver it replace with an 8-bit one. SDR outputs leave the
+    /// format to the backend.
+    #[must_use]
+    pub const fn for_output(output: &OutputInfo) -> Self {
+        let request = Self::new(output.stream);
+        if output.hdr {
+            request.with_format(Self::HDR_FORMAT)
+        } else {
+            request
+        }
+    }
+
+    /// Set the preferred pixel format.
+    #[must_use]
+    pub const fn with_format(mut self, format: FrameFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Set the preferred capture tier.
+    #[must_use]
+    pub const fn with_tier(mut self, tier: CaptureTierInfo) -> Self {
+        self.tier = Some(tier);
+        self
+    }
+
+    /// Set how the cursor is delivered.
+    #[must_use]
+    pub const fn with_cursor_mode(mut self, cursor_mode: CursorMode) -> Self {
+        self.cursor_mode = cursor_mode;
+        self
+    }
+}
+
+/// Controls a running capture stream.
+///
+/// Cloned handles refer to the same stream; the producer checks
+/// [`Self::is_stopped`] and [`Self::is_paused`] before each frame, and its
+/// encoder consults a
+/// [`KeyframeScheduler`] built on the handle.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureHandle(Arc<CaptureControl>);
+
+#[derive(Debug, Default)]
+struct CaptureControl {
+    stopped: AtomicBool,
+    /// Wakes waiters on stop, pause and resume
+    notify: Notify,
+    paused: AtomicBool,
+    keyframe_requested: AtomicBool,
+    forced_keyframes: AtomicU64,
+}
+
+impl CaptureHandle {
+    /// Ask the stream to stop producing frames.
+    pub fn stop(&self) {
+        self.0.stopped.store(true, Ordering::SeqCst);
+        self.0.notify.notify_waiters();
+    }
+
+    /// Wait until [`Self::stop`] is called.
+    ///
+    /// For producers that release resources elsewhere, e.g. in the
+    /// compositor, once the stream ends.
+    pub async fn stopped(&self) {
+        loop {
+            let notified = self.0.notify.notified();
+            if self.is_stopped() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Whether [`Self::stop`] has been called.
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        self.0.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Ask the stream to deliver no frames until [`Self::resume`].
+    ///
+    /// Unlike [`Self::stop`] the stream stays open, so the client can show
+    /// a placeholder instead of treating it as ended.
+    pub fn pause(&self) {
+        if !self.0.paused.swap(true, Ordering::SeqCst) {
+            self.0.notify.notify_waiters();
+        }
+    }
+
+    /// Resume a paused stream, starting with a keyframe.
+    pub fn resume(&self) {
+        if self.0.paused.swap(false, Ordering::SeqCst) {
+            self.request_keyframe();
+            self.0.notify.notify_waiters();
+        }
+    }
+
+    /// Wait until the stream is paused, if `paused`, or running, if not.
+    ///
+    /// Also returns once the stream is stopped, so producers that stop
+    /// their source while paused are not left waiting.
+    pub async fn wait_paused(&self, paused: bool) {
+        loop {
+            let notified = self.0.notify.notified();
+            if self.is_paused() == paused || self.is_stopped() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Whether the stream is paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.0.paused.load(Ordering::SeqCst)
+    }
+
+    /// Ask for the next encoded frame to be a keyframe.
+    ///
+    /// Used when a client joins mid-stream or lost frames. Requests made
+    /// before the next frame is encoded coalesce into one keyframe.
+    pub fn request_keyframe(&self) {
+        self.0.keyframe_requested.store(true, Ordering::SeqCst);
+    }
+
+    /// Keyframes encoded early because of [`Self::request_keyframe`].
+    #[must_use]
+    pub fn forced_keyframes(&self) -> u64 {
+        self.0.forced_keyframes.load(Ordering::Relaxed)
+    }
+
+    fn take_keyframe_request(&self) -> bool {
+        self.0.keyframe_requested.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// Decides which encoded frames of a stream are keyframes.
+///
+/// Keyframes are emitted every `gop_interval` frames, and immediately
+/// after [`CaptureHandle::request_keyframe`].
+#[derive(Debug)]
+pub struct KeyframeScheduler {
+    handle: CaptureHandle,
+    gop_interval: u32,
+    /// Frames encoded since the last keyframe, `None` before the first
+    since_keyframe: Option<u32>,
+}
+
+impl KeyframeScheduler {
+    /// Keyframe interval used when the encoder doesn't set one.
+    pub const DEFAULT_GOP_INTERVAL: u32 = 60;
+
+    /// Create a scheduler for the stream controlled by `handle`.
+    ///
+    /// The first frame is always a keyframe. A `gop_interval` of 0 is
+    /// treated as 1.
+    #[must_use]
+    pub fn new(handle: CaptureHandle, gop_interval: u32) -> Self {
+        Self {
+            handle,
+            gop_interval: gop_interval.max(1),
+            since_keyframe: None,
+        }
+    }
+
+    /// Whether the frame about to be encoded must be a keyframe.
+    ///
+    /// Call exactly once per encoded frame.
+    pub fn next_is_keyframe(&mut self) -> bool {
+        let requested = self.handle.take_keyframe_request();
+        let scheduled = match self.since_keyframe {
+            None => true,
+            Some(since) => since + 1 >= self.gop_interval,
+        };
+        if scheduled || requested {
+            if !scheduled {
+                self.handle
+                    .0
+                    .forced_keyframes
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+            self.since_keyframe = Some(0);
+            true
+        } else {
+            self.since_keyframe = self.since_keyframe.map(|since| since + 1);
+            false
+        }
+    }
+}
+
 /// Stream of captured screen frames.
 ///
-/// This is a placeholder for now - will be properly implemented with
-/// `PipeWire` streams for Wayland and appropriate mechanism for X11.
+/// Streams started with [`CompositorBackend::start_capture_output`] cover a
+/// single output and deliver frames through [`Self::next_frame`]; several
+/// may run at once for the same session. Streams from
+/// [`CompositorBackend::start_capture`] are still placeholders without
+/// frames until the `PipeWire` path lands.
+///
+/// Dropping the stream stops it; use [`Self::into_handle`] to keep it
+/// running without the stream.
 pub struct CaptureStream {
     /// Session this stream belongs to
     pub session_id: SessionId,
-    // TODO: Add actual stream implementation
-    // For Wayland: PipeWire stream
-    // For X11: Different mechanism
+    /// Output being captured, if the stream covers a single output
+    pub output: Option<OutputInfo>,
+    /// Frame rate the producer targets
+    pub target_fps: u32,
+    /// Tier the backend captures with, if known
+    pub tier: Option<CaptureTierInfo>,
+    /// `PipeWire` node the stream is exported as, if any
+    pub node_id: Option<u32>,
+    /// How the cursor is delivered; may differ from the requested mode
+    /// when the backend cannot honor it
+    pub cursor_mode: CursorMode,
+    frames: Option<mpsc::Receiver<CaptureFrame>>,
+    cursor: Option<mpsc::Receiver<CursorUpdate>>,
+    handle: CaptureHandle,
+}
+
+impl CaptureStream {
+    /// Create a stream that carries no frames.
+    #[must_use]
+    pub fn new(session_id: SessionId) -> Self {
+        Self {
+            session_id,
+            output: None,
+            target_fps: 0,
+            tier: None,
+            node_id: None,
+            cursor_mode: CursorMode::default(),
+            frames: None,
+            cursor: None,
+            handle: CaptureHandle::default(),
+        }
+    }
+
+    /// Create a stream for `output` fed by `frames`.
+    ///
+    /// The producer must stop once `handle` is stopped, and skip frames
+    /// while it is paused.
+    #[must_use]
+    pub fn for_output(
+        session_id: SessionId,
+        output: OutputInfo,
+        request: &OutputCaptureRequest,
+        frames: mpsc::Receiver<CaptureFrame>,
+        handle: CaptureHandle,
+    ) -> Self {
+        Self {
+            session_id,
+            output: Some(output),
+            target_fps: request.target_fps,
+            tier: request.tier,
+            node_id: None,
+            cursor_mode: request.cursor_mode,
+            frames: Some(frames),
+            cursor: None,
+            handle,
+        }
+    }
+
+    /// Set the `PipeWire` node the stream is exported as.
+    #[must_use]
+    pub fn with_node_id(mut self, node_id: u32) -> Self {
+        self.node_id = Some(node_id);
+        self
+    }
+
+    /// Deliver the cursor as `updates` beside the frames.
+    ///
+    /// Sets [`Self::cursor_mode`] to [`CursorMode::Metadata`].
+    #[must_use]
+    pub fn with_cursor_updates(mut self, updates: mpsc::Receiver<CursorUpdate>) -> Self {
+        self.cursor_mode = CursorMode::Metadata;
+        self.cursor = Some(updates);
+        self
+    }
+
+    /// Output stream ID, if the stream covers a single output.
+    #[must_use]
+    pub fn stream(&self) -> Option<u32> {
+        self.output.as_ref().map(|o| o.stream)
+    }
+
+    /// Handle that stops this stream.
+    #[must_use]
+    pub fn handle(&self) -> CaptureHandle {
+        self.handle.clone()
+    }
+
+    /// Give up the stream but leave it running, controlled by the
+    /// returned handle.
+    ///
+    /// For streams consumed elsewhere, e.g. through their `PipeWire` node.
+    #[must_use]
+    pub fn into_handle(mut self) -> CaptureHandle {
+        std::mem::take(&mut self.handle)
+    }
+
+    /// Stop producing frames.
+    pub fn stop(&self) {
+        self.handle.stop();
+    }
+
+    /// Force the next encoded frame to be a keyframe.
+    ///
+    /// See [`CaptureHandle::request_keyframe`].
+    pub fn request_keyframe(&self) {
+        self.handle.request_keyframe();
+    }
+
+    /// Wait for the next frame.
+    ///
+    /// Returns `None` once the stream has stopped and its buffered
+    /// frames are drained, or if the stream carries no frames.
+    pub async fn next_frame(&mut self) -> Option<CaptureFrame> {
+        self.frames.as_mut()?.recv().await
+    }
+
+    /// Wait for the next cursor update.
+    ///
+    /// Returns `None` once the stream has stopped, or if the cursor is
+    /// not delivered as metadata.
+    pub async fn next_cursor(&mut self) -> Option<CursorUpdate> {
+        self.cursor.as_mut()?.recv().await
+    }
+}
+
+impl Drop for CaptureStream {
+    fn drop(&mut self) {
+        self.handle.stop();
+    }
+}
+
+impl std::fmt::Debug for CaptureStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CaptureStream")
+            .field("session_id", &self.session_id)
+            .field("output", &self.output)
+            .field("target_fps", &self.target_fps)
+            .field("tier", &self.tier)
+            .field("node_id", &self.node_id)
+            .field("cursor_mode", &self.cursor_mode)
+            .field("stopped", &self.handle.is_stopped())
+            .field("paused", &self.handle.is_paused())
+            .finish_non_exhaustive()
+    }
 }
 
 /// Compositor backend trait.
@@ -157,6 +821,30 @@ pub trait CompositorBackend: Send + Sync {
     /// the appropriate mechanism for the display server.
     async fn inject_input(&self, event: InputEvent) -> BackendResult<()>;
 
+    /// Inject an input event on a specific seat.
+    ///
+    /// `None` targets the compositor's primary seat, as
+    /// [`Self::inject_input`] does. The default is for backends that only
+    /// know the primary seat and fails for any named one.
+    async fn inject_input_to_seat(
+        &self,
+        seat: Option<&str>,
+        event: InputEvent,
+    ) -> BackendResult<()> {
+        match seat {
+            None => self.inject_input(event).await,
+            Some(seat) => Err(BackendError::SeatNotFound(seat.to_string())),
+        }
+    }
+
+    /// List the names of the compositor's seats.
+    ///
+    /// Backends that can't tell seats apart return an empty list; only the
+    /// primary seat can be targeted then.
+    async fn enumerate_seats(&self) -> BackendResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
     /// Start capturing screen content for a session.
     ///
     /// Returns a stream of captured frames. The implementation depends
@@ -165,6 +853,139 @@ pub trait CompositorBackend: Send + Sync {
 
     /// Get the capabilities of this backend.
     fn capabilities(&self) -> BackendCapabilities;
+
+    /// Subscribe to capability changes.
+    ///
+    /// Each message is a full snapshot taken after a change, e.g. when the
+    /// compositor's D-Bus service appears or screen capture is lost. The
+    /// default is for backends whose capabilities never change: its
+    /// channel is closed from the start.
+    fn capability_updates(&self) -> broadcast::Receiver<BackendCapabilities> {
+        broadcast::channel(1).1
+    }
+
+    /// Subscribe to sessions the compositor revokes on its own.
+    ///
+    /// The default is for backends whose compositor cannot revoke
+    /// sessions: its channel is closed from the start.
+    fn session_revocations(&self) -> broadcast::Receiver<SessionRevocation> {
+        broadcast::channel(1).1
+    }
+
+    /// Subscribe to input focus moving to another surface.
+    ///
+    /// The default is for backends that cannot see the compositor's
+    /// focus: its channel is closed from the start, so no change is ever
+    /// reported.
+    fn focus_changes(&self) -> broadcast::Receiver<FocusChanged> {
+        broadcast::channel(1).1
+    }
+
+    /// The surface that has input focus, if the backend knows.
+    async fn current_focus(&self) -> Option<FocusChanged> {
+        None
+    }
+
+    /// Capture exactly one frame for a session.
+    ///
+    /// Unlike [`Self::start_capture`] no stream outlives the call: any
+    /// capture resources are torn down before returning. The COSMIC and
+    /// Wayland backends don't implement it yet and keep this default.
+    async fn capture_single(
+        &self,
+        session: &SessionId,
+        request: CaptureRequest,
+    ) -> BackendResult<CaptureFrame> {
+        let _ = (session, request);
+        Err(BackendError::NotAvailable(
+            "single-frame capture".to_string(),
+        ))
+    }
+
+    /// Whether [`Self::capture_single`] is implemented.
+    ///
+    /// Defaults to `false`, matching the default `capture_single`.
+    fn supports_single_capture(&self) -> bool {
+        false
+    }
+
+    /// List the compositor's outputs.
+    ///
+    /// Backends that can't report output geometry return an empty list,
+    /// which callers treat as "bounds unknown".
+    async fn enumerate_outputs(&self) -> BackendResult<Vec<OutputInfo>> {
+        Ok(Vec::new())
+    }
+
+    /// Input event kinds this backend can inject.
+    ///
+    /// Defaults to [`BackendCapabilities::input_events`]; backends with
+    /// partial pointer support, or touch and gesture support, override it.
+    fn supported_input_events(&self) -> Vec<InputEventKind> {
+        self.capabilities().input_events()
+    }
+
+    /// Whether [`Self::start_capture_output`] is implemented.
+    ///
+    /// Callers check this before starting per-output streams. Defaults to
+    /// `false`, matching the default `start_capture_output`.
+    fn supports_output_capture(&self) -> bool {
+        false
+    }
+
+    /// Start continuous capture of a single output.
+    ///
+    /// May be called once per output on the same session; each call
+    /// returns an independent stream, and backends must not serialize
+    /// frames of one output behind another.
+    async fn start_capture_output(
+        &self,
+        session: &SessionId,
+        request: OutputCaptureRequest,
+    ) -> BackendResult<CaptureStream> {
+        let _ = (session, request);
+        Err(BackendError::NotAvailable("per-output capture".to_string()))
+    }
+
+    /// Lock the pointer for a session's relative input, or release it.
+    ///
+    /// While locked the compositor constrains the pointer of `seat`
+    /// (`None` for the primary seat) so it stays hidden and held in place,
+    /// as `zwp_pointer_constraints_v1` locks it, and continuous relative
+    /// motion never runs into a screen edge. The default is for backends
+    /// without pointer constraints.
+    async fn set_pointer_lock(
+        &self,
+        session: &SessionId,
+        seat: Option<&str>,
+        locked: bool,
+    ) -> BackendResult<()> {
+        let _ = (session, seat, locked);
+        Err(BackendError::NotAvailable("pointer lock".to_string()))
+    }
+
+    /// Set how the compositor transforms a session's relative pointer
+    /// motion; the identity transform removes it.
+    ///
+    /// The compositor applies it before injecting, so motion sent through
+    /// [`inject_input`](Self::inject_input) arrives untransformed. The
+    /// default is for backends without a compositor-side transform.
+    async fn set_pointer_transform(
+        &self,
+        session: &SessionId,
+        transform: PointerTransform,
+    ) -> BackendResult<()> {
+        let _ = (session, transform);
+        Err(BackendError::NotAvailable("pointer transform".to_string()))
+    }
+
+    /// Disconnect from the compositor, stopping every task the backend
+    /// runs to forward updates.
+    ///
+    /// Called when the backend is replaced; subscriptions handed out
+    /// earlier stop receiving. The default is for backends that spawn
+    /// nothing.
+    async fn disconnect(&self) {}
 }
 
 /// Factory for creating appropriate compositor backends.
@@ -192,15 +1013,77 @@ impl BackendFactory {
         Ok(Box::new(MockBackend::default()))
     }
 
-    /// Detect the display server type.
-    pub fn detect_display_server() -> DisplayServerType {
-        // Check for Wayland
-        if std::env::var("WAYLAND_DISPLAY").is_ok() {
-            return DisplayServerType::Wayland;
+    /// Pick the best of `candidates`, composing two if necessary.
+    ///
+    /// Candidates are considered in order. The first available backend
+    /// that covers both input and capture wins; otherwise the first input
+    /// backend is paired with the first capture backend in a
+    /// [`CompositeBackend`]. Failing that, the first available backend is
+    /// returned on its own.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if none of the candidates is available.
+    pub async fn create_best(
+        candidates: Vec<Box<dyn CompositorBackend>>,
+    ) -> BackendResult<Box<dyn CompositorBackend>> {
+        let mut available = Vec::with_capacity(candidates.len());
+        for backend in candidates {
+            if backend.is_available().await {
+                available.push(backend);
+            }
+        }
+
+        let caps: Vec<_> = available.iter().map(|b| b.capabilities()).collect();
+        if let Some(idx) = caps
+            .iter()
+            .position(|c| c.can_inject_input() && c.can_capture_screen)
+        {
+            return Ok(available.swap_remove(idx));
+        }
+
+        let input = caps.iter().position(BackendCapabilities::can_inject_input);
+        let capture = caps.iter().position(|c| c.can_capture_screen);
+        match (input, capture) {
+            (Some(input), Some(capture)) => {
+                // No backend covers both, so the indices differ; remove the
+                // later one first to keep the earlier index valid
+                let (capture, input) = if capture > input {
+                    let capture = available.remove(capture);
+                    (capture, available.remove(input))
+                } else {
+                    let input = available.remove(input);
+                    (available.remove(capture), input)
+                };
+                Ok(Box::new(CompositeBackend::new(input, capture)))
+            },
+            _ if !available.is_empty() => Ok(available.remove(0)),
+            _ => Err(BackendError::NotAvailable(
+                "no candidate backend is available".to_string(),
+            )),
+        }
+    }
+
+    /// Detect the display server type.
+    pub fn detect_display_server() -> DisplayServerType {
+        Self::detect_display_server_with(|name| std::env::var_os(name))
+    }
+
+    /// Detect the display server type from variables looked up by `env`.
+    ///
+    /// Lets callers re-run detection against something other than the
+    /// process environment, such as a session's imported environment.
+    pub fn detect_display_server_with<F>(env: F) -> DisplayServerType
+    where
+        F: Fn(&str) -> Option<std::ffi::OsString>,
+    {
+        // Check for Wayland
+        if env("WAYLAND_DISPLAY").is_some() {
+            return DisplayServerType::Wayland;
         }
 
         // Check for X11
-        if std::env::var("DISPLAY").is_ok() {
+        if env("DISPLAY").is_some() {
             return DisplayServerType::X11;
         }
 
@@ -208,23 +1091,375 @@ impl BackendFactory {
     }
 }
 
+/// Debounces display server redetection.
+///
+/// Feed it the result of every periodic detection; it reports a switch
+/// only once a different display server has been seen `settle` times in a
+/// row, so a variable that flickers while a session starts up doesn't
+/// bounce the service between backends. [`DisplayServerType::Unknown`]
+/// never causes a switch: losing both variables leaves the current
+/// backend in place.
+#[derive(Debug, Clone)]
+pub struct DisplayRedetector {
+    current: DisplayServerType,
+    settle: u32,
+    /// Differing display server and how many times in a row it was seen
+    pending: Option<(DisplayServerType, u32)>,
+}
+
+impl DisplayRedetector {
+    /// Start from the display server the backend was selected for.
+    ///
+    /// A `settle` of 0 is treated as 1.
+    #[must_use]
+    pub fn new(current: DisplayServerType, settle: u32) -> Self {
+        Self {
+            current,
+            settle: settle.max(1),
+            pending: None,
+        }
+    }
+
+    /// The display server the current backend was selected for.
+    #[must_use]
+    pub fn current(&self) -> DisplayServerType {
+        self.current
+    }
+
+    /// Record one detection.
+    ///
+    /// Returns the new display server when the change has settled and
+    /// backend selection should be re-run.
+    pub fn observe(&mut self, detected: DisplayServerType) -> Option<DisplayServerType> {
+        if detected == self.current || detected == DisplayServerType::Unknown {
+            self.pending = None;
+            return None;
+        }
+        let seen = match self.pending {
+            Some((pending, seen)) if pending == detected => seen + 1,
+            _ => 1,
+        };
+        if seen < self.settle {
+            self.pending = Some((detected, seen));
+            return None;
+        }
+        self.pending = None;
+        self.current = detected;
+        Some(detected)
+    }
+}
+
+/// Backend that takes input and capture from two different backends.
+///
+/// Matches environments such as COSMIC where input injection (libei) and
+/// screen capture (`PipeWire` portal) are provided by separate services.
+pub struct CompositeBackend {
+    input: Box<dyn CompositorBackend>,
+    capture: Box<dyn CompositorBackend>,
+    /// Tasks forwarding either backend's updates, aborted on disconnect
+    forwarders: std::sync::Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl CompositeBackend {
+    /// Delegate input to `input` and capture to `capture`.
+    #[must_use]
+    pub fn new(input: Box<dyn CompositorBackend>, capture: Box<dyn CompositorBackend>) -> Self {
+        Self {
+            input,
+            capture,
+            forwarders: std::sync::Mutex::default(),
+        }
+    }
+
+    /// Keeps a forwarding task to abort on disconnect, forgetting those
+    /// that already ended.
+    fn keep_forwarder(&self, task: JoinHandle<()>) {
+        let mut forwarders = self
+            .forwarders
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        forwarders.retain(|task| !task.is_finished());
+        forwarders.push(task);
+    }
+
+    /// Aborts every forwarding task.
+    fn abort_forwarders(&self) {
+        let forwarders = std::mem::take(
+            &mut *self
+                .forwarders
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner),
+        );
+        forwarders.iter().for_each(JoinHandle::abort);
+    }
+}
+
+impl Drop for CompositeBackend {
+    fn drop(&mut self) {
+        self.abort_forwarders();
+    }
+}
+
+#[async_trait]
+impl CompositorBackend for CompositeBackend {
+    async fn is_available(&self) -> bool {
+        self.input.is_available().await && self.capture.is_available().await
+    }
+
+    async fn connect(&mut self) -> BackendResult<()> {
+        self.input.connect().await?;
+        self.capture.connect().await
+    }
+
+    async fn inject_input(&self, event: InputEvent) -> BackendResult<()> {
+        self.input.inject_input(event).await
+    }
+
+    async fn inject_input_to_seat(
+        &self,
+        seat: Option<&str>,
+        event: InputEvent,
+    ) -> BackendResult<()> {
+        self.input.inject_input_to_seat(seat, event).await
+    }
+
+    async fn enumerate_seats(&self) -> BackendResult<Vec<String>> {
+        self.input.enumerate_seats().await
+    }
+
+    async fn start_capture(&self, session: &SessionId) -> BackendResult<CaptureStream> {
+        self.capture.start_capture(session).await
+    }
+
+    /// Input capabilities of the input backend merged with the capture
+    /// capability of the capture backend.
+    fn capabilities(&self) -> BackendCapabilities {
+        combine_roles(self.input.capabilities(), self.capture.capabilities())
+    }
+
+    /// Re-combines the snapshots whenever either backend reports a change.
+    ///
+    /// Spawns a forwarding task per backend, so must be called within a
+    /// Tokio runtime. The tasks end once the receiver is dropped, or on
+    /// [`CompositorBackend::disconnect`].
+    fn capability_updates(&self) -> broadcast::Receiver<BackendCapabilities> {
+        let notifier = CapabilityNotifier::default();
+        let latest = Arc::new(std::sync::Mutex::new((
+            self.input.capabilities(),
+            self.capture.capabilities(),
+        )));
+        for (mut updates, is_input) in [
+            (self.input.capability_updates(), true),
+            (self.capture.capability_updates(), false),
+        ] {
+            let (notifier, latest) = (notifier.clone(), Arc::clone(&latest));
+            self.keep_forwarder(tokio::spawn(async move {
+                loop {
+                    let caps = match updates.recv().await {
+                        Ok(caps) => caps,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    let combined = {
+                        let mut latest = latest
+                            .lock()
+                            .unwrap_or_else(std::sync::PoisonError::into_inner);
+                        if is_input {
+                            latest.0 = caps;
+                        } else {
+                            latest.1 = caps;
+                        }
+                        combine_roles(latest.0.clone(), latest.1.clone())
+                    };
+                    if notifier.notify(combined) == 0 {
+                        break;
+                    }
+                }
+            }));
+        }
+        notifier.subscribe()
+    }
+
+    /// Revocations from either backend.
+    ///
+    /// Spawns a forwarding task per backend, so must be called within a
+    /// Tokio runtime. The tasks end once the receiver is dropped, or on
+    /// [`CompositorBackend::disconnect`].
+    fn session_revocations(&self) -> broadcast::Receiver<SessionRevocation> {
+        let (tx, rx) = broadcast::channel(CapabilityNotifier::CAPACITY);
+        for mut revocations in [
+            self.input.session_revocations(),
+            self.capture.session_revocations(),
+        ] {
+            let tx = tx.clone();
+            self.keep_forwarder(tokio::spawn(async move {
+                loop {
+                    match revocations.recv().await {
+                        Ok(revocation) => {
+                            if tx.send(revocation).is_err() {
+                                break;
+                            }
+                        },
+                        Err(broadcast::error::RecvError::Lagged(_)) => {},
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            }));
+        }
+        rx
+    }
+
+    /// Focus as the input backend sees it, since that decides where
+    /// input lands.
+    fn focus_changes(&self) -> broadcast::Receiver<FocusChanged> {
+        self.input.focus_changes()
+    }
+
+    async fn current_focus(&self) -> Option<FocusChanged> {
+        self.input.current_focus().await
+    }
+
+    fn supported_input_events(&self) -> Vec<InputEventKind> {
+        self.input.supported_input_events()
+    }
+
+    async fn capture_single(
+        &self,
+        session: &SessionId,
+        request: CaptureRequest,
+    ) -> BackendResult<CaptureFrame> {
+        self.capture.capture_single(session, request).await
+    }
+
+    fn supports_single_capture(&self) -> bool {
+        self.capture.supports_single_capture()
+    }
+
+    fn supports_output_capture(&self) -> bool {
+        self.capture.supports_output_capture()
+    }
+
+    async fn start_capture_output(
+        &self,
+        session: &SessionId,
+        request: OutputCaptureRequest,
+    ) -> BackendResult<CaptureStream> {
+        self.capture.start_capture_output(session, request).await
+    }
+
+    async fn set_pointer_lock(
+        &self,
+        session: &SessionId,
+        seat: Option<&str>,
+        locked: bool,
+    ) -> BackendResult<()> {
+        self.input.set_pointer_lock(session, seat, locked).await
+    }
+
+    async fn set_pointer_transform(
+        &self,
+        session: &SessionId,
+        transform: PointerTransform,
+    ) -> BackendResult<()> {
+        self.input.set_pointer_transform(session, transform).await
+    }
+
+    async fn enumerate_outputs(&self) -> BackendResult<Vec<OutputInfo>> {
+        let outputs = self.capture.enumerate_outputs().await?;
+        if outputs.is_empty() {
+            return self.input.enumerate_outputs().await;
+        }
+        Ok(outputs)
+    }
+
+    /// Stops forwarding, then disconnects both backends.
+    async fn disconnect(&self) {
+        self.abort_forwarders();
+        self.input.disconnect().await;
+        self.capture.disconnect().await;
+    }
+}
+
+/// Input flags of `input` merged with the capture flag of `capture`.
+fn combine_roles(input: BackendCapabilities, capture: BackendCapabilities) -> BackendCapabilities {
+    let input = BackendCapabilities {
+        can_capture_screen: false,
+        ..input
+    };
+    let capture = BackendCapabilities {
+        can_inject_keyboard: false,
+        can_inject_pointer: false,
+        ..capture
+    };
+    input.merge(&capture)
+}
+
 /// Mock backend for testing.
 ///
 /// Records all operations and allows tests to verify behavior
-/// without requiring a real compositor.
+/// without requiring a real compositor. Pointer motion moves a tracked
+/// cursor, drawn into captured frames as a white square of
+/// [`Self::CURSOR_SIZE`] pixels on a grey background.
 #[derive(Debug, Default)]
 pub struct MockBackend {
     events: Arc<tokio::sync::Mutex<Vec<InputEvent>>>,
     connected: Arc<tokio::sync::RwLock<bool>>,
+    outputs: Vec<OutputInfo>,
+    active_captures: Arc<AtomicUsize>,
+    next_node_id: Arc<AtomicU32>,
+    cursor: Arc<std::sync::Mutex<(f64, f64)>>,
+    no_cursor_overlay: bool,
+    capture_lost: Arc<AtomicBool>,
+    capability_notifier: CapabilityNotifier,
+    pointer_locks: Arc<std::sync::Mutex<HashSet<SessionId>>>,
+    pointer_transforms: Arc<std::sync::Mutex<HashMap<SessionId, PointerTransform>>>,
 }
 
 impl MockBackend {
+    /// Side length of the cursor drawn into frames, in pixels.
+    pub const CURSOR_SIZE: u32 = 8;
+
     /// Create a new mock backend.
     #[must_use]
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Behave like a backend that cannot composite the cursor into
+    /// frames, so [`CursorMode::Embedded`] falls back to metadata.
+    #[must_use]
+    pub fn without_cursor_overlay(mut self) -> Self {
+        self.no_cursor_overlay = true;
+        self
+    }
+
+    /// Simulate screen capture appearing or disappearing, e.g. after a
+    /// compositor restart.
+    ///
+    /// Subscribers of [`CompositorBackend::capability_updates`] get the new
+    /// snapshot if availability changed.
+    pub fn set_capture_available(&self, available: bool) {
+        if self.capture_lost.swap(!available, Ordering::SeqCst) == available {
+            self.capability_notifier.notify(self.capabilities());
+        }
+    }
+
+    /// Current cursor position, moved by injected pointer motion.
+    #[must_use]
+    pub fn cursor_position(&self) -> (f64, f64) {
+        *self
+            .cursor
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Report these outputs from [`CompositorBackend::enumerate_outputs`].
+    #[must_use]
+    pub fn with_outputs(mut self, outputs: Vec<OutputInfo>) -> Self {
+        self.outputs = outputs;
+        self
+    }
+
     /// Get all events that were injected.
     pub async fn received_events(&self) -> Vec<InputEvent> {
         self.events.lock().await.clone()
@@ -234,6 +1469,49 @@ impl MockBackend {
     pub async fn clear_events(&self) {
         self.events.lock().await.clear();
     }
+
+    /// Number of capture streams currently running.
+    #[must_use]
+    pub fn active_captures(&self) -> usize {
+        self.active_captures.load(Ordering::SeqCst)
+    }
+
+    /// Whether `session` holds the pointer lock.
+    #[must_use]
+    pub fn is_pointer_locked(&self, session: &SessionId) -> bool {
+        self.pointer_locks
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .contains(session)
+    }
+
+    /// The transform set for `session`'s relative motion, identity if
+    /// none.
+    #[must_use]
+    pub fn pointer_transform(&self, session: &SessionId) -> PointerTransform {
+        self.pointer_transforms
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(session)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Keeps [`MockBackend::active_captures`] accurate while a capture runs.
+struct MockCaptureGuard(Arc<AtomicUsize>);
+
+impl MockCaptureGuard {
+    fn start(active: &Arc<AtomicUsize>) -> Self {
+        active.fetch_add(1, Ordering::SeqCst);
+        Self(Arc::clone(active))
+    }
+}
+
+impl Drop for MockCaptureGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 #[async_trait]
@@ -249,26 +1527,282 @@ impl CompositorBackend for MockBackend {
     }
 
     async fn inject_input(&self, event: InputEvent) -> BackendResult<()> {
+        {
+            let mut cursor = self
+                .cursor
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            match event {
+                InputEvent::PointerMotion { dx, dy } => *cursor = (cursor.0 + dx, cursor.1 + dy),
+                InputEvent::PointerMotionAbsolute { x, y, .. } => *cursor = (x, y),
+                _ => {},
+            }
+        }
         let mut events = self.events.lock().await;
         events.push(event);
         Ok(())
     }
 
     async fn start_capture(&self, session: &SessionId) -> BackendResult<CaptureStream> {
-        Ok(CaptureStream {
-            session_id: session.clone(),
-        })
+        Ok(CaptureStream::new(session.clone()))
     }
 
     fn capabilities(&self) -> BackendCapabilities {
         BackendCapabilities {
             can_inject_keyboard: true,
             can_inject_pointer: true,
-            can_capture_screen: true,
+            can_capture_screen: !self.capture_lost.load(Ordering::SeqCst),
             display_server_type: DisplayServerType::Virtual,
             backend_name: "Mock (testing)".to_string(),
         }
     }
+
+    fn capability_updates(&self) -> broadcast::Receiver<BackendCapabilities> {
+        self.capability_notifier.subscribe()
+    }
+
+    async fn capture_single(
+        &self,
+        session: &SessionId,
+        request: CaptureRequest,
+    ) -> BackendResult<CaptureFrame> {
+        let output = match request.stream {
+            Some(stream) => Some(
+                self.outputs
+                    .iter()
+                    .find(|o| o.stream == stream)
+                    .ok_or_else(|| {
+                        BackendError::CaptureFailed(format!(
+                            "unknown stream {stream} for {session}"
+                        ))
+                    })?,
+            ),
+            None => self.outputs.first(),
+        };
+        let (width, height) = output.map_or((64, 64), |o| (o.width, o.height));
+        let cursor_mode = request.cursor_mode.resolve(!self.no_cursor_overlay);
+        let (x, y) = self.cursor_position();
+
+        mock_frame(
+            width,
+            height,
+            request.format.unwrap_or(FrameFormat::Bgra8888),
+            0,
+            output.map(|o| o.name.clone()),
+            output.map_or(OutputTransform::Normal, |o| o.transform),
+            (cursor_mode == CursorMode::Embedded).then(|| {
+                (
+                    x.clamp(0.0, f64::from(width)),
+                    y.clamp(0.0, f64::from(height)),
+                )
+            }),
+        )
+    }
+
+    async fn enumerate_outputs(&self) -> BackendResult<Vec<OutputInfo>> {
+        Ok(self.outputs.clone())
+    }
+
+    /// Records every event, so touch is supported alongside the basics.
+    fn supported_input_events(&self) -> Vec<InputEventKind> {
+        let mut kinds = self.capabilities().input_events();
+        kinds.push(InputEventKind::Touch);
+        kinds
+    }
+
+    fn supports_single_capture(&self) -> bool {
+        true
+    }
+
+    fn supports_output_capture(&self) -> bool {
+        true
+    }
+
+    /// Spawns one producer task per call, so outputs tick independently.
+    /// Frames are dropped rather than queued when the consumer lags, and
+    /// the consumer is then owed a keyframe. In [`CursorMode::Metadata`]
+    /// an update is sent whenever the cursor moved since the last frame.
+    async fn start_capture_output(
+        &self,
+        session: &SessionId,
+        mut request: OutputCaptureRequest,
+    ) -> BackendResult<CaptureStream> {
+        let output = self
+            .outputs
+            .iter()
+            .find(|o| o.stream == request.stream)
+            .cloned()
+            .ok_or_else(|| {
+                BackendError::CaptureFailed(format!(
+                    "unknown stream {} for {session}",
+                    request.stream
+                ))
+            })?;
+
+        let (tx, rx) = mpsc::channel(4);
+        let handle = CaptureHandle::default();
+        let guard = MockCaptureGuard::start(&self.active_captures);
+        let interval = Duration::from_secs(1) / request.target_fps.max(1);
+        let format = request.format.unwrap_or(FrameFormat::Bgra8888);
+        let (width, height) = (output.width, output.height);
+        let transform = output.transform;
+        let output_name = output.name.clone();
+        let producer = handle.clone();
+
+        request.cursor_mode = request.cursor_mode.resolve(!self.no_cursor_overlay);
+        let cursor_mode = request.cursor_mode;
+        let (cursor_tx, cursor_rx) = if cursor_mode == CursorMode::Metadata {
+            let (tx, rx) = mpsc::channel(16);
+            (Some(tx), Some(rx))
+        } else {
+            (None, None)
+        };
+        let cursor = Arc::clone(&self.cursor);
+        let bounds = output.clone();
+
+        tokio::spawn(async move {
+            let mut sequence = 0;
+            let mut last_sent = None;
+            while !producer.is_stopped() {
+                if producer.is_paused() {
+                    tokio::time::sleep(interval).await;
+                    continue;
+                }
+                let (x, y) = *cursor
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner);
+                let position = bounds.clamp(x, y);
+                if let Some(cursor_tx) = &cursor_tx {
+                    if last_sent != Some(position) {
+                        let (x, y) = position;
+                        let update = CursorUpdate { x, y, sequence };
+                        if cursor_tx.try_send(update).is_ok() {
+                            last_sent = Some(position);
+                        }
+                    }
+                }
+
+                let name = Some(output_name.clone());
+                let embedded = (cursor_mode == CursorMode::Embedded).then_some(position);
+                let Ok(frame) =
+                    mock_frame(width, height, format, sequence, name, transform, embedded)
+                else {
+                    break;
+                };
+                match tx.try_send(frame) {
+                    Ok(()) => {},
+                    Err(mpsc::error::TrySendError::Full(_)) => producer.request_keyframe(),
+                    Err(mpsc::error::TrySendError::Closed(_)) => break,
+                }
+                sequence += 1;
+                tokio::time::sleep(interval).await;
+            }
+            // Before `tx` closes, so a drained stream is no longer counted
+            drop(guard);
+        });
+
+        // Node 0 is the PipeWire core, so fake node ids start at 1
+        let node_id = self.next_node_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let stream = CaptureStream::for_output(session.clone(), output, &request, rx, handle)
+            .with_node_id(node_id);
+        Ok(match cursor_rx {
+            Some(updates) => stream.with_cursor_updates(updates),
+            None => stream,
+        })
+    }
+
+    /// Records the lock; see [`MockBackend::is_pointer_locked`].
+    async fn set_pointer_lock(
+        &self,
+        session: &SessionId,
+        _seat: Option<&str>,
+        locked: bool,
+    ) -> BackendResult<()> {
+        let mut locks = self
+            .pointer_locks
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if locked {
+            locks.insert(session.clone());
+        } else {
+            locks.remove(session);
+        }
+        Ok(())
+    }
+
+    /// Records the transform; see [`MockBackend::pointer_transform`].
+    async fn set_pointer_transform(
+        &self,
+        session: &SessionId,
+        transform: PointerTransform,
+    ) -> BackendResult<()> {
+        let mut transforms = self
+            .pointer_transforms
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if transform.is_identity() {
+            transforms.remove(session);
+        } else {
+            transforms.insert(session.clone(), transform);
+        }
+        Ok(())
+    }
+}
+
+/// Solid grey frame of the given size, with the cursor drawn at `cursor`
+/// if given.
+///
+/// The mock stands in for a compositor that renders transformed outputs
+/// upright, so the frame has the output's logical size.
+fn mock_frame(
+    width: u32,
+    height: u32,
+    format: FrameFormat,
+    sequence: u64,
+    output_name: Option<String>,
+    transform: OutputTransform,
+    cursor: Option<(f64, f64)>,
+) -> BackendResult<CaptureFrame> {
+    let row_bytes = width as usize * format.bytes_per_pixel();
+    let stride = u32::try_from(row_bytes)
+        .map_err(|_| BackendError::CaptureFailed("frame too wide".to_string()))?;
+    let metadata = FrameMetadata {
+        width,
+        height,
+        stride,
+        format,
+        sequence,
+        captured_at: Instant::now(),
+        output_name,
+        transform,
+        transform_handling: if transform == OutputTransform::Normal {
+            TransformHandling::None
+        } else {
+            TransformHandling::Compositor
+        },
+        platform_data: None,
+    };
+    let mut data = vec![0x80; row_bytes * height as usize];
+    if let Some((x, y)) = cursor {
+        draw_mock_cursor(&mut data, &metadata, x, y);
+    }
+    Ok(CaptureFrame::new(metadata, data))
+}
+
+/// Fill the [`MockBackend::CURSOR_SIZE`] square at `(x, y)` with white,
+/// clipped to the frame.
+fn draw_mock_cursor(data: &mut [u8], metadata: &FrameMetadata, x: f64, y: f64) {
+    // Positions are clamped onto the output, so never negative
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let (left, top) = (x as u32, y as u32);
+    let bpp = metadata.format.bytes_per_pixel();
+    let right = (left + MockBackend::CURSOR_SIZE).min(metadata.width);
+    let bottom = (top + MockBackend::CURSOR_SIZE).min(metadata.height);
+    for row in top..bottom {
+        let start = row as usize * metadata.stride as usize + left as usize * bpp;
+        let end = row as usize * metadata.stride as usize + right as usize * bpp;
+        data[start..end].fill(0xFF);
+    }
 }
 
 #[cfg(test)]
@@ -341,6 +1875,37 @@ mod tests {
         assert!(caps.can_inject_pointer);
         assert!(caps.can_capture_screen);
         assert_eq!(caps.display_server_type, DisplayServerType::Virtual);
+
+        let kinds = backend.supported_input_events();
+        assert!(kinds.contains(&InputEventKind::KeyboardModifiers));
+        assert!(kinds.contains(&InputEventKind::PointerAxisDiscrete));
+        assert!(kinds.contains(&InputEventKind::Touch));
+        assert!(!kinds.contains(&InputEventKind::Gesture));
+    }
+
+    #[tokio::test]
+    async fn test_mock_notifies_capability_changes() {
+        let backend = MockBackend::new();
+        let mut updates = backend.capability_updates();
+
+        backend.set_capture_available(false);
+        let caps = updates.recv().await.unwrap();
+        assert!(!caps.can_capture_screen);
+        assert!(caps.can_inject_input());
+        assert!(!backend.capabilities().can_capture_screen);
+
+        // No change, no update
+        backend.set_capture_available(false);
+        backend.set_capture_available(true);
+        assert!(updates.recv().await.unwrap().can_capture_screen);
+        assert!(updates.try_recv().is_err());
+
+        // Backends that never change close the channel straight away
+        let mut fixed = RoleBackend::pointer_only().capability_updates();
+        assert!(matches!(
+            fixed.recv().await,
+            Err(broadcast::error::RecvError::Closed)
+        ));
     }
 
     #[tokio::test]
@@ -349,9 +1914,732 @@ mod tests {
         assert!(backend.is_available().await);
     }
 
+    #[tokio::test]
+    async fn test_mock_capture_single_returns_a_frame() {
+        let backend = MockBackend::new();
+        let frame = backend
+            .capture_single(&SessionId::new("/test/shot"), CaptureRequest::default())
+            .await
+            .unwrap();
+
+        assert_eq!(frame.width(), 64);
+        assert_eq!(frame.format(), FrameFormat::Bgra8888);
+        assert_eq!(frame.metadata.output_name, None);
+        assert_eq!(
+            frame.data().len(),
+            frame.stride() as usize * frame.height() as usize
+        );
+    }
+
+    fn dual_output_backend() -> MockBackend {
+        MockBackend::new().with_outputs(vec![
+            OutputInfo {
+                stream: 0,
+                name: "DP-1".to_string(),
+                width: 64,
+                height: 48,
+                color_space: ColorSpace::Srgb,
+                hdr: false,
+                transform: OutputTransform::Normal,
+            },
+            OutputInfo {
+                stream: 1,
+                name: "HDMI-A-1".to_string(),
+                width: 32,
+                height: 16,
+                color_space: ColorSpace::Srgb,
+                hdr: false,
+                transform: OutputTransform::Normal,
+            },
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_mock_captures_outputs_concurrently() {
+        let backend = dual_output_backend();
+        let session = SessionId::new("/test/dual");
+
+        let mut left = backend
+            .start_capture_output(&session, OutputCaptureRequest::new(0).with_fps(60))
+            .await
+            .unwrap();
+        let mut right = backend
+            .start_capture_output(
+                &session,
+                OutputCaptureRequest::new(1)
+                    .with_fps(15)
+                    .with_tier(CaptureTierInfo::Cpu),
+            )
+            .await
+            .unwrap();
+        assert_eq!(backend.active_captures(), 2);
+        assert_eq!((left.target_fps, right.target_fps), (60, 15));
+        assert_eq!(right.tier, Some(CaptureTierInfo::Cpu));
+        assert!(left.node_id.is_some_and(|id| id > 0));
+        assert_ne!(left.node_id, right.node_id);
+
+        let (a, b) = tokio::join!(left.next_frame(), right.next_frame());
+        let (a, b) = (a.unwrap(), b.unwrap());
+        assert_eq!((a.width(), a.height()), (64, 48));
+        assert_eq!((b.width(), b.height()), (32, 16));
+
+        // Stopping one leaves the other running
+        left.stop();
+        while left.next_frame().await.is_some() {}
+        assert!(right.next_frame().await.is_some());
+        assert_eq!(backend.active_captures(), 1);
+
+        right.stop();
+        while right.next_frame().await.is_some() {}
+        assert_eq!(backend.active_captures(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dropping_stream_stops_it() {
+        let backend = dual_output_backend();
+        let session = SessionId::new("/test/drop");
+
+        let dropped = backend
+            .start_capture_output(&session, OutputCaptureRequest::new(0))
+            .await
+            .unwrap();
+        let handle = dropped.handle();
+        drop(dropped);
+        assert!(handle.is_stopped());
+
+        let detached = backend
+            .start_capture_output(&session, OutputCaptureRequest::new(1))
+            .await
+            .unwrap()
+            .into_handle();
+        assert!(!detached.is_stopped());
+        detached.stop();
+    }
+
+    #[tokio::test]
+    async fn test_stopped_resolves_once_stopped() {
+        let handle = CaptureHandle::default();
+        let waiter = tokio::spawn({
+            let handle = handle.clone();
+            async move { handle.stopped().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        handle.stop();
+        tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .expect("stopped() should resolve")
+            .unwrap();
+        // Already stopped: resolves at once
+        handle.stopped().await;
+    }
+
+    /// Pixel at `(x, y)` in a 4-byte-per-pixel frame.
+    fn pixel(frame: &CaptureFrame, x: usize, y: usize) -> &[u8] {
+        let start = y * frame.stride() as usize + x * 4;
+        &frame.data()[start..start + 4]
+    }
+
+    #[tokio::test]
+    async fn test_mock_embeds_cursor_at_tracked_position() {
+        let backend = dual_output_backend();
+        let session = SessionId::new("/test/cursor");
+        backend
+            .inject_input(InputEvent::pointer_motion_absolute(0, 20.0, 10.0))
+            .await
+            .unwrap();
+        backend
+            .inject_input(InputEvent::pointer_motion(2.0, 3.0))
+            .await
+            .unwrap();
+        assert_eq!(backend.cursor_position(), (22.0, 13.0));
+
+        let mut stream = backend
+            .start_capture_output(&session, OutputCaptureRequest::new(0))
+            .await
+            .unwrap();
+        assert_eq!(stream.cursor_mode, CursorMode::Embedded);
+        let frame = stream.next_frame().await.unwrap();
+
+        assert_eq!(pixel(&frame, 22, 13), [0xFF; 4]);
+        assert_eq!(pixel(&frame, 29, 20), [0xFF; 4]);
+        assert_eq!(pixel(&frame, 30, 13), [0x80; 4]);
+        assert_eq!(pixel(&frame, 0, 0), [0x80; 4]);
+        assert!(stream.next_cursor().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mock_metadata_cursor_is_sent_separately() {
+        let backend = dual_output_backend();
+        let session = SessionId::new("/test/cursor");
+        backend
+            .inject_input(InputEvent::pointer_motion_absolute(0, 20.0, 10.0))
+            .await
+            .unwrap();
+
+        let mut stream = backend
+            .start_capture_output(
+                &session,
+                OutputCaptureRequest::new(0).with_cursor_mode(CursorMode::Metadata),
+            )
+            .await
+            .unwrap();
+        assert_eq!(stream.cursor_mode, CursorMode::Metadata);
+        let frame = stream.next_frame().await.unwrap();
+        assert!(frame.data().iter().all(|&b| b == 0x80));
+
+        let update = stream.next_cursor().await.unwrap();
+        assert_eq!((update.x, update.y, update.sequence), (20.0, 10.0, 0));
+
+        // Past the edge of the 64x48 output
+        backend
+            .inject_input(InputEvent::pointer_motion_absolute(0, 100.0, 5.0))
+            .await
+            .unwrap();
+        let update = stream.next_cursor().await.unwrap();
+        assert_eq!((update.x, update.y), (64.0, 5.0));
+        assert!(update.sequence > 0);
+    }
+
+    #[tokio::test]
+    async fn test_mock_embedded_cursor_falls_back_to_metadata() {
+        let backend = dual_output_backend().without_cursor_overlay();
+        let session = SessionId::new("/test/cursor");
+
+        let mut stream = backend
+            .start_capture_output(&session, OutputCaptureRequest::new(1))
+            .await
+            .unwrap();
+        assert_eq!(stream.cursor_mode, CursorMode::Metadata);
+        let frame = stream.next_frame().await.unwrap();
+        assert!(frame.data().iter().all(|&b| b == 0x80));
+        assert!(stream.next_cursor().await.is_some());
+
+        let hidden = backend
+            .capture_single(
+                &session,
+                CaptureRequest {
+                    cursor_mode: CursorMode::Hidden,
+                    ..CaptureRequest::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(hidden.data().iter().all(|&b| b == 0x80));
+
+        let embedded = MockBackend::new()
+            .capture_single(&session, CaptureRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(pixel(&embedded, 0, 0), [0xFF; 4]);
+        assert_eq!(CursorMode::from(4), CursorMode::Metadata);
+        assert_eq!(CursorMode::from(0), CursorMode::Embedded);
+    }
+
+    #[tokio::test]
+    async fn test_mock_frames_carry_output_name() {
+        let backend = dual_output_backend();
+        let session = SessionId::new("/test/names");
+
+        let mut streams = Vec::new();
+        for output in backend.enumerate_outputs().await.unwrap() {
+            let stream = backend
+                .start_capture_output(&session, OutputCaptureRequest::new(output.stream))
+                .await
+                .unwrap();
+            streams.push((output.name, stream));
+        }
+        for (name, stream) in &mut streams {
+            let frame = stream.next_frame().await.unwrap();
+            assert_eq!(frame.metadata.output_name.as_ref(), Some(&*name));
+            stream.stop();
+        }
+        assert_eq!(streams[1].0, "HDMI-A-1");
+
+        // Single captures name the output too, the primary one by default
+        let primary = backend
+            .capture_single(&session, CaptureRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(primary.metadata.output_name.as_deref(), Some("DP-1"));
+        let second = backend
+            .capture_single(
+                &session,
+                CaptureRequest {
+                    stream: Some(1),
+                    ..CaptureRequest::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(second.metadata.output_name.as_deref(), Some("HDMI-A-1"));
+    }
+
+    #[test]
+    fn test_requested_keyframe_is_next_and_coalesced() {
+        let handle = CaptureHandle::default();
+        let mut scheduler = KeyframeScheduler::new(handle.clone(), 4);
+        let mut frames = |n| {
+            (0..n)
+                .map(|_| scheduler.next_is_keyframe())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(frames(2), [true, false]);
+
+        handle.request_keyframe();
+        handle.request_keyframe();
+        // The GOP restarts at the forced keyframe
+        assert_eq!(frames(5), [true, false, false, false, true]);
+        assert_eq!(handle.forced_keyframes(), 1);
+
+        // A request landing on a scheduled keyframe isn't an extra one
+        assert_eq!(frames(3), [false, false, false]);
+        handle.request_keyframe();
+        assert_eq!(frames(2), [true, false]);
+        assert_eq!(handle.forced_keyframes(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_capture_lag_requests_keyframe() {
+        let backend = MockBackend::new().with_outputs(vec![OutputInfo {
+            stream: 0,
+            name: "Virtual-1".to_string(),
+            width: 8,
+            height: 8,
+            color_space: ColorSpace::Srgb,
+            hdr: false,
+            transform: OutputTransform::Normal,
+        }]);
+        let session = SessionId::new("/test/lag");
+        let stream = backend
+            .start_capture_output(&session, OutputCaptureRequest::new(0).with_fps(1000))
+            .await
+            .unwrap();
+        let mut scheduler = KeyframeScheduler::new(stream.handle(), u32::MAX);
+        assert!(scheduler.next_is_keyframe());
+        assert!(!scheduler.next_is_keyframe());
+
+        // Nobody reads frames, so the channel fills up and frames drop
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !scheduler.next_is_keyframe() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(stream.handle().forced_keyframes(), 1);
+        stream.stop();
+    }
+
+    #[tokio::test]
+    async fn test_mock_hdr_output_prefers_10bit_capture() {
+        let backend = MockBackend::new().with_outputs(vec![
+            OutputInfo {
+                stream: 0,
+                name: "DP-1".to_string(),
+                width: 8,
+                height: 8,
+                color_space: ColorSpace::Bt2020,
+                hdr: true,
+                transform: OutputTransform::Normal,
+            },
+            OutputInfo {
+                stream: 1,
+                name: "HDMI-A-1".to_string(),
+                width: 8,
+                height: 8,
+                color_space: ColorSpace::default(),
+                hdr: false,
+                transform: OutputTransform::Normal,
+            },
+        ]);
+        let outputs = backend.enumerate_outputs().await.unwrap();
+        assert_eq!(outputs[0].color_space, ColorSpace::Bt2020);
+        assert!(outputs[0].hdr && outputs[0].color_space.is_wide_gamut());
+        assert_eq!(outputs[1].color_space, ColorSpace::Srgb);
+        assert!(!outputs[1].hdr);
+
+        let session = SessionId::new("/test/hdr");
+        for (output, format) in outputs
+            .iter()
+            .zip([FrameFormat::Xrgb2101010, FrameFormat::Bgra8888])
+        {
+            let request = OutputCaptureRequest::for_output(output);
+            let mut stream = backend
+                .start_capture_output(&session, request)
+                .await
+                .unwrap();
+            let frame = stream.next_frame().await.unwrap();
+            assert_eq!(frame.metadata.format, format, "{}", output.name);
+            stream.stop();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_capture_output_unknown_stream() {
+        let result = dual_output_backend()
+            .start_capture_output(&SessionId::new("/test/dual"), OutputCaptureRequest::new(7))
+            .await;
+        assert!(matches!(result, Err(BackendError::CaptureFailed(_))));
+    }
+
+    /// Backend with configurable capabilities that counts what it is asked to do.
+    #[derive(Default)]
+    struct RoleBackend {
+        name: &'static str,
+        pointer: bool,
+        capture: bool,
+        available: bool,
+        injected: Arc<AtomicUsize>,
+        captures: Arc<AtomicUsize>,
+    }
+
+    impl RoleBackend {
+        fn pointer_only() -> Self {
+            Self {
+                name: "libei",
+                pointer: true,
+                available: true,
+                ..Self::default()
+            }
+        }
+
+        fn capture_only() -> Self {
+            Self {
+                name: "pipewire",
+                capture: true,
+                available: true,
+                ..Self::default()
+            }
+        }
+    }
+
+    #[async_trait]
+    impl CompositorBackend for RoleBackend {
+        async fn is_available(&self) -> bool {
+            self.available
+        }
+
+        async fn connect(&mut self) -> BackendResult<()> {
+            Ok(())
+        }
+
+        async fn inject_input(&self, _event: InputEvent) -> BackendResult<()> {
+            self.injected.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn start_capture(&self, session: &SessionId) -> BackendResult<CaptureStream> {
+            self.captures.fetch_add(1, Ordering::SeqCst);
+            Ok(CaptureStream::new(session.clone()))
+        }
+
+        fn capabilities(&self) -> BackendCapabilities {
+            BackendCapabilities {
+                can_inject_keyboard: false,
+                can_inject_pointer: self.pointer,
+                can_capture_screen: self.capture,
+                display_server_type: DisplayServerType::Wayland,
+                backend_name: self.name.to_string(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_capabilities_merge_is_union() {
+        let input = RoleBackend::pointer_only().capabilities();
+        let capture = RoleBackend::capture_only().capabilities();
+        let merged = input.merge(&capture);
+
+        assert!(merged.can_inject_pointer);
+        assert!(!merged.can_inject_keyboard);
+        assert!(merged.can_capture_screen);
+        assert_eq!(merged.display_server_type, DisplayServerType::Wayland);
+        assert_eq!(merged.backend_name, "libei + pipewire");
+    }
+
+    #[tokio::test]
+    async fn test_composite_backend_delegates() {
+        let input = RoleBackend::pointer_only();
+        let capture = RoleBackend::capture_only();
+        let (input_injected, input_captures) = (input.injected.clone(), input.captures.clone());
+        let (capture_injected, capture_captures) =
+            (capture.injected.clone(), capture.captures.clone());
+
+        let backend = CompositeBackend::new(Box::new(input), Box::new(capture));
+        assert!(backend.is_available().await);
+
+        let caps = backend.capabilities();
+        assert!(caps.can_inject_pointer);
+        assert!(!caps.can_inject_keyboard);
+        assert!(caps.can_capture_screen);
+        assert_eq!(backend.supported_input_events(), InputEventKind::POINTER);
+
+        backend
+            .inject_input(InputEvent::PointerMotion { dx: 1.0, dy: 1.0 })
+            .await
+            .unwrap();
+        backend
+            .start_capture(&SessionId::new("/test/composite"))
+            .await
+            .unwrap();
+
+        assert_eq!(input_injected.load(Ordering::SeqCst), 1);
+        assert_eq!(input_captures.load(Ordering::SeqCst), 0);
+        assert_eq!(capture_injected.load(Ordering::SeqCst), 0);
+        assert_eq!(capture_captures.load(Ordering::SeqCst), 1);
+    }
+
+    /// Mock the test keeps a handle to after boxing it.
+    struct SharedMock(Arc<MockBackend>);
+
+    #[async_trait]
+    impl CompositorBackend for SharedMock {
+        async fn is_available(&self) -> bool {
+            true
+        }
+
+        async fn connect(&mut self) -> BackendResult<()> {
+            Ok(())
+        }
+
+        async fn inject_input(&self, event: InputEvent) -> BackendResult<()> {
+            self.0.inject_input(event).await
+        }
+
+        async fn start_capture(&self, session: &SessionId) -> BackendResult<CaptureStream> {
+            self.0.start_capture(session).await
+        }
+
+        fn capabilities(&self) -> BackendCapabilities {
+            self.0.capabilities()
+        }
+
+        fn capability_updates(&self) -> broadcast::Receiver<BackendCapabilities> {
+            self.0.capability_updates()
+        }
+
+        async fn set_pointer_lock(
+            &self,
+            session: &SessionId,
+            seat: Option<&str>,
+            locked: bool,
+        ) -> BackendResult<()> {
+            self.0.set_pointer_lock(session, seat, locked).await
+        }
+
+        async fn set_pointer_transform(
+            &self,
+            session: &SessionId,
+            transform: PointerTransform,
+        ) -> BackendResult<()> {
+            self.0.set_pointer_transform(session, transform).await
+        }
+
+        fn supports_output_capture(&self) -> bool {
+            self.0.supports_output_capture()
+        }
+    }
+
+    #[test]
+    fn test_composite_output_capture_follows_capture_backend() {
+        let mock = || Box::new(SharedMock(Arc::new(MockBackend::new())));
+
+        let backend = CompositeBackend::new(Box::new(RoleBackend::pointer_only()), mock());
+        assert!(backend.supports_output_capture());
+
+        let backend = CompositeBackend::new(mock(), Box::new(RoleBackend::capture_only()));
+        assert!(!backend.supports_output_capture());
+    }
+
+    #[tokio::test]
+    async fn test_composite_forwards_capability_changes() {
+        let handle = Arc::new(MockBackend::new());
+        let backend = CompositeBackend::new(
+            Box::new(RoleBackend::pointer_only()),
+            Box::new(SharedMock(Arc::clone(&handle))),
+        );
+        let mut updates = backend.capability_updates();
+
+        handle.set_capture_available(false);
+        let caps = updates.recv().await.unwrap();
+        assert!(!caps.can_capture_screen);
+        assert!(caps.can_inject_pointer);
+        assert!(!caps.can_inject_keyboard);
+    }
+
+    #[tokio::test]
+    async fn test_composite_disconnect_stops_forwarding() {
+        let handle = Arc::new(MockBackend::new());
+        let backend = CompositeBackend::new(
+            Box::new(RoleBackend::pointer_only()),
+            Box::new(SharedMock(Arc::clone(&handle))),
+        );
+        let mut updates = backend.capability_updates();
+        let mut revocations = backend.session_revocations();
+
+        // The forwarders hold the only senders, so aborting them closes
+        // both channels
+        backend.disconnect().await;
+        handle.set_capture_available(false);
+        assert_eq!(
+            updates.recv().await,
+            Err(broadcast::error::RecvError::Closed)
+        );
+        assert_eq!(
+            revocations.recv().await,
+            Err(broadcast::error::RecvError::Closed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_composite_locks_pointer_on_input_backend() {
+        let handle = Arc::new(MockBackend::new());
+        let session = SessionId::new("/test/lock");
+        let backend = CompositeBackend::new(
+            Box::new(SharedMock(Arc::clone(&handle))),
+            Box::new(RoleBackend::capture_only()),
+        );
+
+        backend
+            .set_pointer_lock(&session, None, true)
+            .await
+            .unwrap();
+        assert!(handle.is_pointer_locked(&session));
+        backend
+            .set_pointer_lock(&session, None, false)
+            .await
+            .unwrap();
+        assert!(!handle.is_pointer_locked(&session));
+
+        let unsupported = RoleBackend::pointer_only()
+            .set_pointer_lock(&session, None, true)
+            .await;
+        assert!(matches!(unsupported, Err(BackendError::NotAvailable(_))));
+    }
+
+    #[tokio::test]
+    async fn test_composite_sets_pointer_transform_on_input_backend() {
+        let handle = Arc::new(MockBackend::new());
+        let session = SessionId::new("/test/trackpad");
+        let backend = CompositeBackend::new(
+            Box::new(SharedMock(Arc::clone(&handle))),
+            Box::new(RoleBackend::capture_only()),
+        );
+        let transform = PointerTransform::with_sensitivity(2.0);
+
+        backend
+            .set_pointer_transform(&session, transform)
+            .await
+            .unwrap();
+        assert_eq!(handle.pointer_transform(&session), transform);
+        backend
+            .set_pointer_transform(&session, PointerTransform::IDENTITY)
+            .await
+            .unwrap();
+        assert!(handle.pointer_transform(&session).is_identity());
+
+        let unsupported = RoleBackend::pointer_only()
+            .set_pointer_transform(&session, transform)
+            .await;
+        assert!(matches!(unsupported, Err(BackendError::NotAvailable(_))));
+    }
+
+    #[tokio::test]
+    async fn test_create_best_composes_when_needed() {
+        let unavailable = RoleBackend {
+            name: "offline",
+            pointer: true,
+            capture: true,
+            ..RoleBackend::default()
+        };
+        let backend = BackendFactory::create_best(vec![
+            Box::new(unavailable),
+            Box::new(RoleBackend::capture_only()),
+            Box::new(RoleBackend::pointer_only()),
+        ])
+        .await
+        .unwrap();
+
+        let caps = backend.capabilities();
+        assert_eq!(caps.backend_name, "libei + pipewire");
+        assert!(caps.can_inject_pointer && caps.can_capture_screen);
+    }
+
+    #[tokio::test]
+    async fn test_create_best_prefers_full_backend() {
+        let backend = BackendFactory::create_best(vec![
+            Box::new(RoleBackend::pointer_only()),
+            Box::new(MockBackend::new()),
+        ])
+        .await
+        .unwrap();
+        assert_eq!(backend.capabilities().backend_name, "Mock (testing)");
+
+        assert!(BackendFactory::create_best(Vec::new()).await.is_err());
+    }
+
+    #[test]
+    fn test_output_info_bounds() {
+        let output = OutputInfo {
+            stream: 0,
+            name: "DP-1".to_string(),
+            width: 1920,
+            height: 1080,
+            color_space: ColorSpace::Srgb,
+            hdr: false,
+            transform: OutputTransform::Normal,
+        };
+
+        assert!(output.contains(0.0, 0.0));
+        assert!(output.contains(1920.0, 1080.0));
+        assert!(!output.contains(-1.0, 10.0));
+        assert!(!output.contains(10.0, 5000.0));
+        assert_eq!(output.clamp(-5.0, 5000.0), (0.0, 1080.0));
+    }
+
     #[test]
     fn test_display_server_detection() {
         // Just test that it doesn't panic
         let _display_type = BackendFactory::detect_display_server();
     }
+
+    #[test]
+    fn test_display_server_detection_with_env() {
+        let detect = |vars: &[&str]| {
+            BackendFactory::detect_display_server_with(|name| {
+                vars.contains(&name).then(|| "set".into())
+            })
+        };
+        assert_eq!(detect(&[]), DisplayServerType::Unknown);
+        assert_eq!(detect(&["DISPLAY"]), DisplayServerType::X11);
+        assert_eq!(
+            detect(&["DISPLAY", "WAYLAND_DISPLAY"]),
+            DisplayServerType::Wayland
+        );
+    }
+
+    #[test]
+    fn test_display_redetector_debounces() {
+        let mut redetector = DisplayRedetector::new(DisplayServerType::X11, 3);
+
+        // A flicker shorter than the settle count is ignored
+        assert_eq!(redetector.observe(DisplayServerType::Wayland), None);
+        assert_eq!(redetector.observe(DisplayServerType::Wayland), None);
+        assert_eq!(redetector.observe(DisplayServerType::X11), None);
+        assert_eq!(redetector.observe(DisplayServerType::Wayland), None);
+        assert_eq!(redetector.observe(DisplayServerType::Unknown), None);
+        assert_eq!(redetector.current(), DisplayServerType::X11);
+
+        for _ in 0..2 {
+            assert_eq!(redetector.observe(DisplayServerType::Wayland), None);
+        }
+        assert_eq!(
+            redetector.observe(DisplayServerType::Wayland),
+            Some(DisplayServerType::Wayland)
+        );
+        assert_eq!(redetector.current(), DisplayServerType::Wayland);
+        assert_eq!(redetector.observe(DisplayServerType::Wayland), None);
+    }
 }
@@ -10,10 +10,13 @@ use bitflags::bitflags;
 bitflags! {
     /// Available device types for remote desktop sessions.
     ///
-    /// These flags match the portal specification:
+    /// The first three flags match the portal specification:
     /// - `KEYBOARD = 1`
     /// - `POINTER = 2`
     /// - `TOUCHSCREEN = 4`
+    ///
+    /// `GESTURE = 8` is an ionChannel extension, so that touchpad gestures
+    /// are consented to separately from plain pointer input.
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct DeviceType: u32 {
         /// Keyboard input device
@@ -22,6 +25,8 @@ bitflags! {
         const POINTER = 2;
         /// Touchscreen input device
         const TOUCHSCREEN = 4;
+        /// Touchpad gestures (swipe, pinch, hold)
+        const GESTURE = 8;
     }
 }
 
@@ -35,7 +40,10 @@ impl DeviceType {
     /// Returns all available device types.
     #[must_use]
     pub const fn all_devices() -> Self {
-        Self::KEYBOARD.union(Self::POINTER).union(Self::TOUCHSCREEN)
+        Self::KEYBOARD
+            .union(Self::POINTER)
+            .union(Self::TOUCHSCREEN)
+            .union(Self::GESTURE)
     }
 
     /// Checks if keyboard is enabled.
@@ -55,6 +63,12 @@ impl DeviceType {
     pub const fn has_touchscreen(self) -> bool {
         self.contains(Self::TOUCHSCREEN)
     }
+
+    /// Checks if touchpad gestures are enabled.
+    #[must_use]
+    pub const fn has_gesture(self) -> bool {
+        self.contains(Self::GESTURE)
+    }
 }
 
 impl Default for DeviceType {
@@ -88,6 +102,9 @@ impl std::fmt::Display for DeviceType {
         if self.has_touchscreen() {
             parts.push("touchscreen");
         }
+        if self.has_gesture() {
+            parts.push("gestures");
+        }
         if parts.is_empty() {
             write!(f, "none")
         } else {
@@ -127,7 +144,7 @@ mod tests {
     fn device_type_from_u32_truncate() {
         // Unknown bits should be truncated
         let devices = DeviceType::from(0xFF);
-        assert_eq!(devices.bits(), 7); // Only KEYBOARD | POINTER | TOUCHSCREEN
+        assert_eq!(devices.bits(), 15); // Only the four known flags
     }
 
     #[test]
@@ -149,7 +166,7 @@ mod tests {
     fn device_type_display_all() {
         assert_eq!(
             DeviceType::all_devices().to_string(),
-            "keyboard, pointer, touchscreen"
+            "keyboard, pointer, touchscreen, gestures"
         );
     }
 
@@ -175,6 +192,7 @@ mod tests {
         assert!(devices.has_keyboard());
         assert!(devices.has_pointer());
         assert!(!devices.has_touchscreen());
+        assert!(!devices.has_gesture());
         assert_eq!(devices.bits(), 3);
     }
 
@@ -184,7 +202,8 @@ mod tests {
         assert!(devices.has_keyboard());
         assert!(devices.has_pointer());
         assert!(devices.has_touchscreen());
-        assert_eq!(devices.bits(), 7);
+        assert!(devices.has_gesture());
+        assert_eq!(devices.bits(), 15);
     }
 
     #[test]
@@ -55,6 +55,26 @@ pub enum SessionError {
     #[error("session has been closed")]
     Closed,
 
+    /// The requested session mode needs capabilities that are unavailable
+    #[error("session mode not available: {0}")]
+    ModeUnavailable(&'static str),
+
+    /// Another session holds exclusive input control
+    #[error("session {0} holds exclusive control")]
+    ExclusiveHeld(String),
+
+    /// The maximum number of sessions is already open
+    #[error("maximum sessions reached ({0})")]
+    LimitReached(usize),
+
+    /// No new sessions are accepted while shutting down
+    #[error("not accepting sessions while shutting down")]
+    ShuttingDown,
+
+    /// The session sent its lifetime maximum of events
+    #[error("event budget exhausted ({0} events)")]
+    EventBudgetExhausted(u64),
+
     /// Invalid session state transition
     #[error("invalid session state: expected {expected}, got {actual}")]
     InvalidState {
@@ -65,6 +85,28 @@ pub enum SessionError {
     },
 }
 
+/// Why a string was rejected by
+/// [`SessionId::new_validated`](crate::session::SessionId::new_validated).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum InvalidSessionId {
+    /// Longer than [`SessionId::MAX_LEN`](crate::session::SessionId::MAX_LEN)
+    #[error("session id is {0} bytes long")]
+    TooLong(usize),
+
+    /// Does not start with `/`
+    #[error("session id is not an absolute object path")]
+    NotAbsolute,
+
+    /// Contains `//` or ends with `/`
+    #[error("session id has an empty path element")]
+    EmptyElement,
+
+    /// Contains a character outside `[A-Za-z0-9_/]`
+    #[error("session id contains {0:?}")]
+    InvalidChar(char),
+}
+
 /// Input injection errors.
 #[derive(Debug, Error)]
 #[non_exhaustive]
@@ -98,6 +140,14 @@ pub enum InputError {
     /// Stream not found (for absolute positioning)
     #[error("stream not found: {0}")]
     StreamNotFound(u32),
+
+    /// The event queue stayed full for the whole send timeout
+    #[error("event queue full, send would block (waited {0:?})")]
+    WouldBlock(std::time::Duration),
+
+    /// No key event can type this character
+    #[error("no keysym types {0:?}")]
+    UnrepresentableChar(char),
 }
 
 /// Portal communication errors.
@@ -123,6 +173,17 @@ pub enum PortalError {
     /// Permission denied
     #[error("permission denied")]
     PermissionDenied,
+
+    /// Method newer than the version the portal reports
+    #[error("{method} requires portal version {required}, this portal reports {reported}")]
+    UnsupportedVersion {
+        /// The method called
+        method: &'static str,
+        /// Version that introduced the method
+        required: u32,
+        /// Version the portal reports
+        reported: u32,
+    },
 }
 
 #[cfg(test)]
@@ -208,6 +269,13 @@ mod tests {
         assert!(err.to_string().contains("42"));
     }
 
+    #[test]
+    fn input_error_would_block() {
+        let err = InputError::WouldBlock(std::time::Duration::from_millis(250));
+        assert!(err.to_string().contains("would block"));
+        assert!(err.to_string().contains("250ms"));
+    }
+
     #[test]
     fn portal_error_connection() {
         let err = PortalError::Connection("timeout".into());
@@ -11,11 +11,69 @@
 //! - All coordinates are `f64` for sub-pixel precision
 //! - Button/key codes use `i32` to match Linux evdev
 //! - Stream IDs are `u32` to match `PipeWire` node IDs
+//!
+//! ## Wire format
+//!
+//! With the `serde` feature (on by default), events serialize internally
+//! tagged with `snake_case` names, e.g.
+//! `{"type": "pointer_motion", "dx": 1.5, "dy": 0.0}`, and state enums as
+//! lowercase strings. Non-finite coordinates, which JSON cannot express,
+//! are written as `"NaN"`, `"Infinity"` and `"-Infinity"`.
 
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use zvariant::OwnedValue;
+
+/// Serde helpers for `f64` fields that may not be finite.
+#[cfg(feature = "serde")]
+mod wire_f64 {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// A float as it appears on the wire.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Wire {
+        Number(f64),
+        Sentinel(String),
+    }
+
+    #[allow(clippy::trivially_copy_pass_by_ref)] // signature required by serde
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if value.is_finite() {
+            serializer.serialize_f64(*value)
+        } else if value.is_nan() {
+            serializer.serialize_str("NaN")
+        } else if value.is_sign_positive() {
+            serializer.serialize_str("Infinity")
+        } else {
+            serializer.serialize_str("-Infinity")
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        match Wire::deserialize(deserializer)? {
+            Wire::Number(value) => Ok(value),
+            Wire::Sentinel(s) => match s.as_str() {
+                "NaN" => Ok(f64::NAN),
+                "Infinity" => Ok(f64::INFINITY),
+                "-Infinity" => Ok(f64::NEG_INFINITY),
+                other => Err(D::Error::custom(format!("invalid number '{other}'"))),
+            },
+        }
+    }
+}
 
 /// Key press/release state.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
 #[repr(u32)]
 pub enum KeyState {
     /// Key was released
@@ -51,7 +109,12 @@ impl From<u32> for KeyState {
 }
 
 /// Mouse button press/release state.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
 #[repr(u32)]
 pub enum ButtonState {
     /// Button was released
@@ -87,7 +150,12 @@ impl From<u32> for ButtonState {
 }
 
 /// Scroll axis direction.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
 #[repr(u32)]
 pub enum Axis {
     /// Vertical scroll (up/down)
@@ -106,18 +174,339 @@ impl From<u32> for Axis {
     }
 }
 
+/// Physical source of a scroll event.
+///
+/// Mirrors `wl_pointer.axis_source`; applications use it to decide
+/// between kinetic scrolling (finger) and stepped scrolling (wheel).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[repr(u32)]
+pub enum AxisSource {
+    /// Mouse wheel (discrete steps)
+    #[default]
+    Wheel = 0,
+    /// Finger on a touchpad (kinetic, has a stop event)
+    Finger = 1,
+    /// Continuous source such as a trackpoint
+    Continuous = 2,
+    /// Sideways tilt of a mouse wheel
+    WheelTilt = 3,
+}
+
+impl AxisSource {
+    /// Portal option key carrying the scroll source (`u`).
+    pub const OPTION_KEY: &'static str = "axis_source";
+}
+
+impl From<u32> for AxisSource {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::Finger,
+            2 => Self::Continuous,
+            3 => Self::WheelTilt,
+            _ => Self::Wheel,
+        }
+    }
+}
+
+/// Unit of a smooth scroll amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[repr(u32)]
+pub enum ScrollUnit {
+    /// Logical pixels (surface-local coordinate space)
+    #[default]
+    Pixels = 0,
+    /// Text lines, scaled by the compositor's line height
+    Lines = 1,
+}
+
+impl ScrollUnit {
+    /// Portal option key carrying the scroll unit (`u`).
+    pub const OPTION_KEY: &'static str = "scroll_unit";
+}
+
+impl From<u32> for ScrollUnit {
+    fn from(value: u32) -> Self {
+        if value == 1 {
+            Self::Lines
+        } else {
+            Self::Pixels
+        }
+    }
+}
+
+/// Reads the scroll source and unit from D-Bus method options.
+///
+/// Shared by the portal and the compositor service, which take the same
+/// `axis_source` and `scroll_unit` keys. Absent or mistyped values
+/// default to wheel scrolling in pixels.
+#[must_use]
+pub fn scroll_options<S: BuildHasher>(
+    options: &HashMap<String, OwnedValue, S>,
+) -> (AxisSource, ScrollUnit) {
+    let get = |key: &str| options.get(key).and_then(|v| v.downcast_ref::<u32>().ok());
+    (
+        get(AxisSource::OPTION_KEY).map_or_else(AxisSource::default, AxisSource::from),
+        get(ScrollUnit::OPTION_KEY).map_or_else(ScrollUnit::default, ScrollUnit::from),
+    )
+}
+
+/// Acceleration curve applied to relative pointer motion.
+///
+/// Mirrors libinput's pointer acceleration profiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[repr(u32)]
+pub enum AccelProfile {
+    /// Constant factor regardless of speed
+    #[default]
+    Flat = 0,
+    /// Faster motion travels proportionally further
+    Adaptive = 1,
+}
+
+impl AccelProfile {
+    /// Portal option key carrying the acceleration profile (`u`).
+    pub const OPTION_KEY: &'static str = "accel_profile";
+
+    /// Speed, in units per event, below which adaptive motion is unchanged.
+    pub const ADAPTIVE_THRESHOLD: f64 = 2.0;
+    /// Extra factor per unit of speed above the threshold.
+    pub const ADAPTIVE_GAIN: f64 = 0.1;
+    /// Largest factor the adaptive curve applies.
+    pub const ADAPTIVE_MAX_FACTOR: f64 = 3.0;
+
+    /// Factor applied to a motion of the given speed.
+    #[must_use]
+    pub fn factor(self, speed: f64) -> f64 {
+        match self {
+            Self::Flat => 1.0,
+            Self::Adaptive => {
+                let excess = (speed - Self::ADAPTIVE_THRESHOLD).max(0.0);
+                (1.0 + excess * Self::ADAPTIVE_GAIN).min(Self::ADAPTIVE_MAX_FACTOR)
+            },
+        }
+    }
+}
+
+impl From<u32> for AccelProfile {
+    fn from(value: u32) -> Self {
+        if value == 1 {
+            Self::Adaptive
+        } else {
+            Self::Flat
+        }
+    }
+}
+
+/// Per-session mapping of relative pointer motion.
+///
+/// Lets clients that only send raw deltas, such as a remote trackpad,
+/// pick a sensitivity and acceleration curve. The default is the
+/// identity, which leaves motion untouched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PointerTransform {
+    /// Factor applied to every delta
+    pub sensitivity: f64,
+    /// Acceleration curve applied after scaling, if any
+    pub accel_profile: Option<AccelProfile>,
+}
+
+impl PointerTransform {
+    /// Portal option key carrying the sensitivity (`d`).
+    pub const SENSITIVITY_KEY: &'static str = "pointer_sensitivity";
+
+    /// The transform that leaves motion unchanged.
+    pub const IDENTITY: Self = Self {
+        sensitivity: 1.0,
+        accel_profile: None,
+    };
+
+    /// Creates a transform with the given sensitivity and no acceleration.
+    ///
+    /// Non-finite or non-positive sensitivities fall back to `1.0`.
+    #[must_use]
+    pub fn with_sensitivity(sensitivity: f64) -> Self {
+        let sensitivity = if sensitivity.is_finite() && sensitivity > 0.0 {
+            sensitivity
+        } else {
+            1.0
+        };
+        Self {
+            sensitivity,
+            accel_profile: None,
+        }
+    }
+
+    /// Sets the acceleration curve.
+    #[must_use]
+    pub const fn with_accel_profile(mut self, profile: AccelProfile) -> Self {
+        self.accel_profile = Some(profile);
+        self
+    }
+
+    /// Returns true if the transform leaves every motion unchanged.
+    #[must_use]
+    #[allow(clippy::float_cmp)] // exact identity, not approximate
+    pub fn is_identity(&self) -> bool {
+        self.sensitivity == 1.0 && matches!(self.accel_profile, None | Some(AccelProfile::Flat))
+    }
+
+    /// Maps a relative motion delta.
+    #[must_use]
+    pub fn apply(&self, dx: f64, dy: f64) -> (f64, f64) {
+        let (dx, dy) = (dx * self.sensitivity, dy * self.sensitivity);
+        let factor = self
+            .accel_profile
+            .map_or(1.0, |profile| profile.factor(dx.hypot(dy)));
+        (dx * factor, dy * factor)
+    }
+}
+
+impl Default for PointerTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Stage of a touchpad gesture, as in Wayland's `pointer-gestures`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[repr(u32)]
+pub enum GesturePhase {
+    /// Fingers went down and the gesture was recognized
+    Begin = 0,
+    /// The gesture moved
+    Update = 1,
+    /// Fingers lifted, completing the gesture
+    End = 2,
+    /// The gesture was aborted; its effect should be undone
+    Cancel = 3,
+}
+
+impl TryFrom<u32> for GesturePhase {
+    type Error = u32;
+
+    /// Reads a phase as numbered on the wire, handing back unknown ones.
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Begin),
+            1 => Ok(Self::Update),
+            2 => Ok(Self::End),
+            3 => Ok(Self::Cancel),
+            other => Err(other),
+        }
+    }
+}
+
+/// Kind of input a backend can inject, for capability negotiation.
+///
+/// Mostly one kind per [`InputEvent`] variant; touch events share one
+/// kind, as do gestures, since a backend takes all of them or none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputEventKind {
+    /// [`InputEvent::PointerMotion`]
+    PointerMotion,
+    /// [`InputEvent::PointerMotionAbsolute`]
+    PointerMotionAbsolute,
+    /// [`InputEvent::PointerButton`]
+    PointerButton,
+    /// [`InputEvent::PointerAxis`]
+    PointerAxis,
+    /// [`InputEvent::PointerAxisDiscrete`]
+    PointerAxisDiscrete,
+    /// [`InputEvent::KeyboardKeycode`]
+    KeyboardKeycode,
+    /// [`InputEvent::KeyboardKeysym`]
+    KeyboardKeysym,
+    /// [`InputEvent::KeyboardModifiers`]
+    KeyboardModifiers,
+    /// [`InputEvent::TouchDown`], [`InputEvent::TouchMotion`] and
+    /// [`InputEvent::TouchUp`]
+    Touch,
+    /// [`InputEvent::GestureSwipe`], [`InputEvent::GesturePinch`] and
+    /// [`InputEvent::GestureHold`]
+    Gesture,
+}
+
+impl InputEventKind {
+    /// Pointer kinds, as injected by any backend with pointer support.
+    pub const POINTER: [Self; 5] = [
+        Self::PointerMotion,
+        Self::PointerMotionAbsolute,
+        Self::PointerButton,
+        Self::PointerAxis,
+        Self::PointerAxisDiscrete,
+    ];
+
+    /// Keyboard kinds, as injected by any backend with keyboard support.
+    pub const KEYBOARD: [Self; 3] = [
+        Self::KeyboardKeycode,
+        Self::KeyboardKeysym,
+        Self::KeyboardModifiers,
+    ];
+
+    /// Short name, as advertised to clients.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::PointerMotion => "pointer_motion",
+            Self::PointerMotionAbsolute => "pointer_motion_absolute",
+            Self::PointerButton => "pointer_button",
+            Self::PointerAxis => "pointer_axis",
+            Self::PointerAxisDiscrete => "pointer_axis_discrete",
+            Self::KeyboardKeycode => "keyboard_keycode",
+            Self::KeyboardKeysym => "keyboard_keysym",
+            Self::KeyboardModifiers => "keyboard_modifiers",
+            Self::Touch => "touch",
+            Self::Gesture => "gesture",
+        }
+    }
+}
+
+impl std::fmt::Display for InputEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 /// Input events that can be injected into the compositor.
 ///
 /// These events are sent from the portal to the compositor
 /// for injection into the Wayland input pipeline.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
 #[non_exhaustive]
 pub enum InputEvent {
     /// Relative pointer motion (delta)
     PointerMotion {
         /// Horizontal delta (positive = right)
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
         dx: f64,
         /// Vertical delta (positive = down)
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
         dy: f64,
     },
 
@@ -126,8 +515,10 @@ pub enum InputEvent {
         /// `PipeWire` stream ID (maps to output)
         stream: u32,
         /// X coordinate within stream bounds
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
         x: f64,
         /// Y coordinate within stream bounds
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
         y: f64,
     },
 
@@ -142,9 +533,17 @@ pub enum InputEvent {
     /// Smooth scroll (continuous)
     PointerAxis {
         /// Horizontal scroll amount
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
         dx: f64,
         /// Vertical scroll amount
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
         dy: f64,
+        /// Scroll source (defaults to wheel)
+        #[cfg_attr(feature = "serde", serde(default))]
+        source: AxisSource,
+        /// Unit of `dx`/`dy` (defaults to pixels)
+        #[cfg_attr(feature = "serde", serde(default))]
+        unit: ScrollUnit,
     },
 
     /// Discrete scroll (wheel clicks)
@@ -171,6 +570,22 @@ pub enum InputEvent {
         state: KeyState,
     },
 
+    /// Explicit keyboard modifier state, as in `wl_keyboard.modifiers`
+    ///
+    /// Masks are XKB modifier masks for the seat's keymap. The state
+    /// replaces the seat's current one outright, so client and compositor
+    /// cannot drift apart the way they can with modifier key events.
+    KeyboardModifiers {
+        /// Modifiers currently held down
+        depressed: u32,
+        /// Modifiers active for the next key only (sticky keys)
+        latched: u32,
+        /// Modifiers toggled on (Caps Lock, Num Lock)
+        locked: u32,
+        /// Active layout group
+        group: u32,
+    },
+
     /// Touch down event (finger placed)
     TouchDown {
         /// `PipeWire` stream ID (maps to output)
@@ -178,8 +593,10 @@ pub enum InputEvent {
         /// Touch slot (finger ID)
         slot: u32,
         /// X coordinate
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
         x: f64,
         /// Y coordinate
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
         y: f64,
     },
 
@@ -190,8 +607,10 @@ pub enum InputEvent {
         /// Touch slot (finger ID)
         slot: u32,
         /// X coordinate
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
         x: f64,
         /// Y coordinate
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
         y: f64,
     },
 
@@ -200,9 +619,55 @@ pub enum InputEvent {
         /// Touch slot (finger ID)
         slot: u32,
     },
+
+    /// Touchpad swipe gesture
+    GestureSwipe {
+        /// Stage of the gesture
+        phase: GesturePhase,
+        /// Fingers on the touchpad
+        fingers: u32,
+        /// Horizontal delta since the last update
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
+        dx: f64,
+        /// Vertical delta since the last update
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
+        dy: f64,
+    },
+
+    /// Touchpad pinch (and rotate) gesture
+    GesturePinch {
+        /// Stage of the gesture
+        phase: GesturePhase,
+        /// Fingers on the touchpad
+        fingers: u32,
+        /// Horizontal delta of the fingers' center since the last update
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
+        dx: f64,
+        /// Vertical delta of the fingers' center since the last update
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
+        dy: f64,
+        /// Absolute scale relative to the start of the gesture
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
+        scale: f64,
+        /// Degrees rotated clockwise since the last update
+        #[cfg_attr(feature = "serde", serde(with = "wire_f64"))]
+        rotation: f64,
+    },
+
+    /// Touchpad hold gesture (fingers resting without moving)
+    GestureHold {
+        /// Stage of the gesture; holds have no updates
+        phase: GesturePhase,
+        /// Fingers on the touchpad
+        fingers: u32,
+    },
 }
 
 impl InputEvent {
+    /// Portal option key carrying when the client sent the event (`t`),
+    /// in microseconds since the Unix epoch.
+    pub const CLIENT_TIMESTAMP_KEY: &'static str = "client_timestamp";
+
     /// Creates a relative pointer motion event.
     #[must_use]
     pub const fn pointer_motion(dx: f64, dy: f64) -> Self {
@@ -234,21 +699,63 @@ impl InputEvent {
         }
     }
 
+    /// Creates a wheel scroll event measured in pixels.
+    #[must_use]
+    pub const fn scroll(dx: f64, dy: f64) -> Self {
+        Self::PointerAxis {
+            dx,
+            dy,
+            source: AxisSource::Wheel,
+            unit: ScrollUnit::Pixels,
+        }
+    }
+
+    /// Creates a scroll event with an explicit source and unit.
+    #[must_use]
+    pub const fn scroll_with(dx: f64, dy: f64, source: AxisSource, unit: ScrollUnit) -> Self {
+        Self::PointerAxis {
+            dx,
+            dy,
+            source,
+            unit,
+        }
+    }
+
     /// Creates a keyboard keycode event.
     #[must_use]
     pub const fn key(keycode: i32, state: KeyState) -> Self {
         Self::KeyboardKeycode { keycode, state }
     }
 
+    /// Creates a keyboard modifier state event.
+    #[must_use]
+    pub const fn keyboard_modifiers(depressed: u32, latched: u32, locked: u32, group: u32) -> Self {
+        Self::KeyboardModifiers {
+            depressed,
+            latched,
+            locked,
+            group,
+        }
+    }
+
     /// Returns true if this is a keyboard event.
     #[must_use]
     pub const fn is_keyboard(&self) -> bool {
         matches!(
             self,
-            Self::KeyboardKeycode { .. } | Self::KeyboardKeysym { .. }
+            Self::KeyboardKeycode { .. }
+                | Self::KeyboardKeysym { .. }
+                | Self::KeyboardModifiers { .. }
         )
     }
 
+    /// Returns a view of this event for logging, with keyboard values
+    /// redacted unless [`crate::redact::LOG_KEYS_ENV`] opts in.
+    #[must_use]
+    pub const fn redacted(&self) -> crate::redact::RedactedEvent<'_> {
+        crate::redact::RedactedEvent(self)
+    }
+
     /// Returns true if this is a pointer event.
     #[must_use]
     pub const fn is_pointer(&self) -> bool {
@@ -262,6 +769,27 @@ impl InputEvent {
         )
     }
 
+    /// Returns the kind of this event.
+    #[must_use]
+    pub const fn kind(&self) -> InputEventKind {
+        match self {
+            Self::PointerMotion { .. } => InputEventKind::PointerMotion,
+            Self::PointerMotionAbsolute { .. } => InputEventKind::PointerMotionAbsolute,
+            Self::PointerButton { .. } => InputEventKind::PointerButton,
+            Self::PointerAxis { .. } => InputEventKind::PointerAxis,
+            Self::PointerAxisDiscrete { .. } => InputEventKind::PointerAxisDiscrete,
+            Self::KeyboardKeycode { .. } => InputEventKind::KeyboardKeycode,
+            Self::KeyboardKeysym { .. } => InputEventKind::KeyboardKeysym,
+            Self::KeyboardModifiers { .. } => InputEventKind::KeyboardModifiers,
+            Self::TouchDown { .. } | Self::TouchMotion { .. } | Self::TouchUp { .. } => {
+                InputEventKind::Touch
+            },
+            Self::GestureSwipe { .. } | Self::GesturePinch { .. } | Self::GestureHold { .. } => {
+                InputEventKind::Gesture
+            },
+        }
+    }
+
     /// Returns true if this is a touch event.
     #[must_use]
     pub const fn is_touch(&self) -> bool {
@@ -270,12 +798,81 @@ impl InputEvent {
             Self::TouchDown { .. } | Self::TouchMotion { .. } | Self::TouchUp { .. }
         )
     }
+
+    /// Returns true if this is a touchpad gesture.
+    #[must_use]
+    pub const fn is_gesture(&self) -> bool {
+        matches!(self.kind(), InputEventKind::Gesture)
+    }
+}
+
+/// An input event with when the client sent it, if the client said.
+///
+/// This is what a session hands on towards the compositor, so the stamp
+/// read from [`InputEvent::CLIENT_TIMESTAMP_KEY`] survives to the sink,
+/// along with the seat the session injects on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StampedEvent {
+    /// The event itself
+    pub event: InputEvent,
+    /// When the client sent it, in microseconds since the Unix epoch
+    pub client_timestamp: Option<u64>,
+    /// Seat to inject on; `None` is the primary seat
+    pub seat: Option<String>,
+}
+
+impl StampedEvent {
+    /// Pairs `event` with the client's send time, if any.
+    #[must_use]
+    pub const fn new(event: InputEvent, client_timestamp: Option<u64>) -> Self {
+        Self {
+            event,
+            client_timestamp,
+            seat: None,
+        }
+    }
+
+    /// Targets the event at `seat`, `None` for the primary seat.
+    #[must_use]
+    pub fn with_seat(mut self, seat: Option<String>) -> Self {
+        self.seat = seat;
+        self
+    }
+}
+
+impl From<InputEvent> for StampedEvent {
+    fn from(event: InputEvent) -> Self {
+        Self::new(event, None)
+    }
+}
+
+/// Compares the event alone, whatever its stamp.
+impl PartialEq<InputEvent> for StampedEvent {
+    fn eq(&self, other: &InputEvent) -> bool {
+        self.event == *other
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn event_kinds() {
+        assert_eq!(
+            InputEvent::keyboard_modifiers(1, 0, 2, 0).kind(),
+            InputEventKind::KeyboardModifiers
+        );
+        assert_eq!(
+            InputEvent::TouchUp { slot: 0 }.kind(),
+            InputEventKind::Touch
+        );
+        assert_eq!(
+            InputEventKind::PointerAxisDiscrete.to_string(),
+            "pointer_axis_discrete"
+        );
+    }
+
     #[test]
     fn key_state_from_bool() {
         assert_eq!(KeyState::from(true), KeyState::Pressed);
@@ -397,7 +994,7 @@ mod tests {
 
     #[test]
     fn pointer_axis_events() {
-        let smooth = InputEvent::PointerAxis { dx: 0.0, dy: -10.0 };
+        let smooth = InputEvent::scroll(0.0, -10.0);
         let discrete = InputEvent::PointerAxisDiscrete {
             axis: Axis::Vertical,
             steps: -1,
@@ -407,6 +1004,128 @@ mod tests {
         assert!(discrete.is_pointer());
     }
 
+    #[test]
+    fn scroll_defaults_to_wheel_pixels() {
+        let event = InputEvent::scroll(0.0, 5.0);
+        assert_eq!(
+            event,
+            InputEvent::PointerAxis {
+                dx: 0.0,
+                dy: 5.0,
+                source: AxisSource::Wheel,
+                unit: ScrollUnit::Pixels,
+            }
+        );
+    }
+
+    #[test]
+    fn scroll_with_source_and_unit() {
+        let event = InputEvent::scroll_with(0.0, 3.0, AxisSource::Finger, ScrollUnit::Lines);
+        assert!(matches!(
+            event,
+            InputEvent::PointerAxis {
+                source: AxisSource::Finger,
+                unit: ScrollUnit::Lines,
+                ..
+            }
+        ));
+        assert!(event.is_pointer());
+    }
+
+    #[test]
+    fn axis_source_from_u32() {
+        assert_eq!(AxisSource::from(0u32), AxisSource::Wheel);
+        assert_eq!(AxisSource::from(1u32), AxisSource::Finger);
+        assert_eq!(AxisSource::from(2u32), AxisSource::Continuous);
+        assert_eq!(AxisSource::from(3u32), AxisSource::WheelTilt);
+        assert_eq!(AxisSource::from(99u32), AxisSource::Wheel);
+        assert_eq!(AxisSource::default(), AxisSource::Wheel);
+    }
+
+    #[test]
+    fn scroll_unit_from_u32() {
+        assert_eq!(ScrollUnit::from(0u32), ScrollUnit::Pixels);
+        assert_eq!(ScrollUnit::from(1u32), ScrollUnit::Lines);
+        assert_eq!(ScrollUnit::from(7u32), ScrollUnit::Pixels);
+        assert_eq!(ScrollUnit::default(), ScrollUnit::Pixels);
+    }
+
+    #[test]
+    fn scroll_options_parsed() {
+        let mut options = HashMap::new();
+        assert_eq!(
+            scroll_options(&options),
+            (AxisSource::Wheel, ScrollUnit::Pixels)
+        );
+
+        options.insert("axis_source".to_string(), OwnedValue::from(1u32));
+        options.insert("scroll_unit".to_string(), OwnedValue::from(1u32));
+        assert_eq!(
+            scroll_options(&options),
+            (AxisSource::Finger, ScrollUnit::Lines)
+        );
+
+        // Mistyped values fall back to the defaults
+        options.insert("axis_source".to_string(), OwnedValue::from(true));
+        assert_eq!(scroll_options(&options).0, AxisSource::Wheel);
+    }
+
+    #[test]
+    fn accel_profile_from_u32() {
+        assert_eq!(AccelProfile::from(0u32), AccelProfile::Flat);
+        assert_eq!(AccelProfile::from(1u32), AccelProfile::Adaptive);
+        assert_eq!(AccelProfile::from(5u32), AccelProfile::Flat);
+    }
+
+    #[test]
+    fn gesture_phase_from_u32() {
+        assert_eq!(GesturePhase::try_from(0u32), Ok(GesturePhase::Begin));
+        assert_eq!(GesturePhase::try_from(3u32), Ok(GesturePhase::Cancel));
+        assert_eq!(GesturePhase::try_from(4u32), Err(4));
+    }
+
+    #[test]
+    fn pointer_transform_scales_deltas() {
+        let transform = PointerTransform::with_sensitivity(2.0);
+        assert_eq!(transform.apply(3.0, -4.5), (6.0, -9.0));
+        assert!(!transform.is_identity());
+
+        assert_eq!(
+            PointerTransform::with_sensitivity(0.0),
+            PointerTransform::IDENTITY
+        );
+        assert_eq!(
+            PointerTransform::with_sensitivity(f64::NAN),
+            PointerTransform::IDENTITY
+        );
+    }
+
+    #[test]
+    fn flat_identity_transform_leaves_motion_unchanged() {
+        let flat = PointerTransform::default().with_accel_profile(AccelProfile::Flat);
+        assert!(flat.is_identity());
+        for (dx, dy) in [(0.5, 0.0), (3.0, 4.0), (-40.0, 25.0)] {
+            assert_eq!(flat.apply(dx, dy), (dx, dy));
+        }
+    }
+
+    #[test]
+    fn adaptive_profile_accelerates_fast_motion() {
+        let adaptive = PointerTransform::default().with_accel_profile(AccelProfile::Adaptive);
+        assert!(!adaptive.is_identity());
+
+        // Slow motion is below the threshold
+        assert_eq!(adaptive.apply(1.0, 1.0), (1.0, 1.0));
+
+        // Speed 10: factor 1 + (10 - 2) * 0.1
+        let (dx, dy) = adaptive.apply(6.0, 8.0);
+        assert!((dx - 10.8).abs() < 1e-9 && (dy - 14.4).abs() < 1e-9);
+
+        // Very fast motion is capped
+        let (dx, _) = adaptive.apply(1000.0, 0.0);
+        assert!((dx - 3000.0).abs() < 1e-9);
+    }
+
     #[test]
     fn keyboard_keysym() {
         let event = InputEvent::KeyboardKeysym {
@@ -418,6 +1137,14 @@ mod tests {
         assert!(!event.is_touch());
     }
 
+    #[test]
+    fn keyboard_modifiers() {
+        let event = InputEvent::keyboard_modifiers(0x4, 0, 0x2, 0);
+        assert!(event.is_keyboard());
+        assert!(!event.is_pointer());
+        assert!(!event.is_touch());
+    }
+
     #[test]
     fn event_clone() {
         let event = InputEvent::pointer_motion(5.0, 10.0);
@@ -439,6 +1166,8 @@ mod tests {
         assert_send_sync::<KeyState>();
         assert_send_sync::<ButtonState>();
         assert_send_sync::<Axis>();
+        assert_send_sync::<AxisSource>();
+        assert_send_sync::<ScrollUnit>();
     }
 
     #[test]
@@ -450,4 +1179,89 @@ mod tests {
         assert_eq!(Axis::Vertical as u32, 0);
         assert_eq!(Axis::Horizontal as u32, 1);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn every_variant_round_trips() {
+        let events = [
+            InputEvent::pointer_motion(1.5, -2.0),
+            InputEvent::pointer_motion_absolute(3, 100.0, 200.25),
+            InputEvent::pointer_button(0x111, ButtonState::Released),
+            InputEvent::scroll_with(0.0, 3.0, AxisSource::WheelTilt, ScrollUnit::Lines),
+            InputEvent::PointerAxisDiscrete {
+                axis: Axis::Horizontal,
+                steps: -2,
+            },
+            InputEvent::key(30, KeyState::Pressed),
+            InputEvent::KeyboardKeysym {
+                keysym: 0xff0d,
+                state: KeyState::Released,
+            },
+            InputEvent::TouchDown {
+                stream: 1,
+                slot: 2,
+                x: 10.0,
+                y: 20.0,
+            },
+            InputEvent::TouchMotion {
+                stream: 1,
+                slot: 2,
+                x: 11.0,
+                y: 21.0,
+            },
+            InputEvent::TouchUp { slot: 2 },
+        ];
+
+        for event in events {
+            let json = serde_json::to_string(&event).unwrap();
+            let back: InputEvent = serde_json::from_str(&json).unwrap();
+            assert_eq!(back, event, "{json}");
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn wire_format_is_tagged_with_string_states() {
+        let json = serde_json::to_value(InputEvent::pointer_motion(1.5, -2.0)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "pointer_motion", "dx": 1.5, "dy": -2.0})
+        );
+
+        let json = serde_json::to_value(InputEvent::scroll(0.0, 1.0)).unwrap();
+        assert_eq!(json["source"], "wheel");
+        assert_eq!(json["unit"], "pixels");
+
+        let click: InputEvent = serde_json::from_str(
+            r#"{"type": "pointer_button", "button": 272, "state": "pressed"}"#,
+        )
+        .unwrap();
+        assert_eq!(click, InputEvent::left_click(true));
+
+        // Scroll source and unit may be omitted
+        let scroll: InputEvent =
+            serde_json::from_str(r#"{"type": "pointer_axis", "dx": 0, "dy": 2}"#).unwrap();
+        assert_eq!(scroll, InputEvent::scroll(0.0, 2.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn non_finite_coordinates_round_trip() {
+        let event = InputEvent::pointer_motion_absolute(0, f64::INFINITY, f64::NEG_INFINITY);
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains(r#""x":"Infinity""#), "{json}");
+        assert!(json.contains(r#""y":"-Infinity""#), "{json}");
+        assert_eq!(serde_json::from_str::<InputEvent>(&json).unwrap(), event);
+
+        let json = serde_json::to_string(&InputEvent::pointer_motion(f64::NAN, 1.0)).unwrap();
+        assert!(json.contains(r#""dx":"NaN""#), "{json}");
+        let InputEvent::PointerMotion { dx, dy } = serde_json::from_str(&json).unwrap() else {
+            panic!("expected pointer motion from {json}");
+        };
+        assert!(dx.is_nan());
+        assert!((dy - 1.0).abs() < f64::EPSILON);
+
+        let bogus = r#"{"type": "pointer_motion", "dx": "fast", "dy": 0}"#;
+        assert!(serde_json::from_str::<InputEvent>(bogus).is_err());
+    }
 }
@@ -34,14 +34,24 @@ pub mod discovery;
 pub mod error;
 pub mod event;
 pub mod mode;
+pub mod recording;
+pub mod redact;
 pub mod session;
 
 // Re-exports for convenience
 pub use backend::{
-    BackendCapabilities, BackendError, BackendResult, CompositorBackend, DisplayServerType,
+    BackendCapabilities, BackendError, BackendResult, CapabilityNotifier, CaptureHandle,
+    CaptureRequest, ColorSpace, CompositeBackend, CompositorBackend, CursorMode, CursorUpdate,
+    DisplayRedetector, DisplayServerType, FocusChanged, KeyframeScheduler, OutputCaptureRequest,
+    OutputInfo, OutputTransform, SessionRevocation, SurfaceGeometry,
 };
 pub use device::DeviceType;
 pub use error::{Error, Result};
-pub use event::{Axis, ButtonState, InputEvent, KeyState};
+pub use event::{
+    AccelProfile, Axis, AxisSource, ButtonState, GesturePhase, InputEvent, InputEventKind,
+    KeyState, PointerTransform, ScrollUnit, StampedEvent,
+};
 pub use mode::{CaptureTierInfo, RemoteDesktopMode, SessionCapabilities};
-pub use session::{SessionHandle, SessionId};
+pub use recording::{CaptureRecorder, RecordingInfo, RecordingSummary};
+pub use redact::Sensitive;
+pub use session::{HeldInputs, RateProfile, SessionHandle, SessionId, SessionModeChanged};
@@ -13,14 +13,18 @@
 //! | ViewOnly | ✅ | ❌ | Monitoring, screen share |
 //! | None | ❌ | ❌ | Session exists but inactive |
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::device::DeviceType;
+
 /// Remote desktop session operating mode.
 ///
 /// Represents the combination of capabilities available for a session.
 /// ionChannel gracefully degrades to lower modes when full capability
 /// isn't available.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(u32)]
 pub enum RemoteDesktopMode {
     /// No capabilities active (session paused/inactive).
@@ -63,6 +67,20 @@ impl RemoteDesktopMode {
         matches!(self, Self::InputOnly | Self::Full)
     }
 
+    /// Input devices this mode allows: every type when it provides input,
+    /// none otherwise.
+    ///
+    /// Authorized devices are always intersected with this mask, so a
+    /// session never holds input devices its mode cannot use.
+    #[must_use]
+    pub const fn capabilities_mask(&self) -> DeviceType {
+        if self.has_input() {
+            DeviceType::all()
+        } else {
+            DeviceType::empty()
+        }
+    }
+
     /// Returns true if this mode has any active capability.
     #[must_use]
     pub const fn is_active(&self) -> bool {
@@ -80,6 +98,26 @@ impl RemoteDesktopMode {
         }
     }
 
+    /// Returns true if `caps` provides everything this mode needs.
+    #[must_use]
+    pub const fn is_supported_by(&self, caps: &SessionCapabilities) -> bool {
+        (!self.has_capture() || caps.capture_available)
+            && (!self.has_input() || caps.input_available)
+    }
+
+    /// Returns this mode without the capabilities `caps` lacks.
+    ///
+    /// Never adds a capability, so `Full` restricted to input-only
+    /// capabilities becomes `InputOnly`, but `InputOnly` stays `InputOnly`
+    /// under full capabilities.
+    #[must_use]
+    pub const fn restricted_to(&self, caps: &SessionCapabilities) -> Self {
+        Self::from_capabilities(
+            self.has_capture() && caps.capture_available,
+            self.has_input() && caps.input_available,
+        )
+    }
+
     /// Creates the best mode given available capabilities.
     #[must_use]
     pub const fn from_capabilities(has_capture: bool, has_input: bool) -> Self {
@@ -137,6 +175,21 @@ pub enum CaptureTierInfo {
     Cpu,
 }
 
+impl CaptureTierInfo {
+    /// All tiers, best first.
+    pub const ALL: [Self; 3] = [Self::Dmabuf, Self::Shm, Self::Cpu];
+
+    /// Short name, as advertised to clients.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Dmabuf => "dmabuf",
+            Self::Shm => "shm",
+            Self::Cpu => "cpu",
+        }
+    }
+}
+
 impl SessionCapabilities {
     /// Full capabilities (both capture and input).
     #[must_use]
@@ -204,6 +257,20 @@ mod tests {
         assert!(!RemoteDesktopMode::None.has_input());
     }
 
+    #[test]
+    fn mode_capabilities_mask() {
+        assert_eq!(
+            RemoteDesktopMode::Full.capabilities_mask(),
+            DeviceType::all()
+        );
+        assert_eq!(
+            RemoteDesktopMode::InputOnly.capabilities_mask(),
+            DeviceType::all()
+        );
+        assert!(RemoteDesktopMode::ViewOnly.capabilities_mask().is_empty());
+        assert!(RemoteDesktopMode::None.capabilities_mask().is_empty());
+    }
+
     #[test]
     fn mode_is_active() {
         assert!(RemoteDesktopMode::Full.is_active());
@@ -212,6 +279,27 @@ mod tests {
         assert!(!RemoteDesktopMode::None.is_active());
     }
 
+    #[test]
+    fn mode_restricted_to_capabilities() {
+        let input_only = SessionCapabilities::input_only();
+        assert!(!RemoteDesktopMode::Full.is_supported_by(&input_only));
+        assert!(RemoteDesktopMode::InputOnly.is_supported_by(&input_only));
+        assert!(RemoteDesktopMode::None.is_supported_by(&SessionCapabilities::none()));
+
+        assert_eq!(
+            RemoteDesktopMode::Full.restricted_to(&input_only),
+            RemoteDesktopMode::InputOnly
+        );
+        assert_eq!(
+            RemoteDesktopMode::ViewOnly.restricted_to(&input_only),
+            RemoteDesktopMode::None
+        );
+        assert_eq!(
+            RemoteDesktopMode::InputOnly.restricted_to(&SessionCapabilities::full()),
+            RemoteDesktopMode::InputOnly
+        );
+    }
+
     #[test]
     fn mode_name() {
         assert_eq!(RemoteDesktopMode::None.name(), "None");
@@ -0,0 +1,431 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Recording capture streams to disk.
+//!
+//! A [`CaptureRecorder`] sits between a [`CaptureStream`] and its consumer,
+//! passing every frame through and, while a recording is running, also
+//! writing it to a file. Recordings are evidence for debugging and failed
+//! validation runs, so the container is deliberately simple: raw frames,
+//! each with its timestamp and geometry, behind a header holding the frame
+//! count. There is no encoder in the tree yet, so frames are stored as
+//! captured.
+//!
+//! ## Container layout
+//!
+//! All integers are little-endian.
+//!
+//! | Field | Size |
+//! |-------|------|
+//! | [`MAGIC`] | 6 |
+//! | version ([`VERSION`]) | 2 |
+//! | frame count, written on finalize | 8 |
+//!
+//! followed by one record per frame:
+//!
+//! | Field | Size |
+//! |-------|------|
+//! | microseconds since the first frame | 8 |
+//! | width, height, stride | 4 each |
+//! | DRM fourcc of the pixel format | 4 |
+//! | data length | 4 |
+//! | pixel data | length |
+//!
+//! A file whose header still counts zero frames was not finalized, e.g.
+//! because the process died; its records are intact up to the last
+//! complete one.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::backend::{CaptureFrame, CaptureStream, FrameFormat};
+
+/// First bytes of every recording.
+pub const MAGIC: [u8; 6] = *b"IONREC";
+
+/// Container version written by this crate.
+pub const VERSION: u16 = 1;
+
+const HEADER_LEN: u64 = 16;
+const COUNT_OFFSET: u64 = 8;
+const RECORD_LEN: usize = 28;
+
+/// Outcome of a finished recording.
+#[derive(Debug)]
+pub struct RecordingSummary {
+    /// File the frames were written to
+    pub path: PathBuf,
+    /// Frames written
+    pub frames: u64,
+    /// Why the recording ended early, if it did
+    pub error: Option<io::Error>,
+}
+
+/// Passes a capture stream through, recording it on request.
+#[derive(Debug)]
+pub struct CaptureRecorder {
+    stream: CaptureStream,
+    recording: Option<Recording>,
+    /// A recording that failed while frames were flowing
+    failed: Option<RecordingSummary>,
+}
+
+#[derive(Debug)]
+struct Recording {
+    path: PathBuf,
+    file: BufWriter<File>,
+    frames: u64,
+    first: Option<Instant>,
+}
+
+impl CaptureRecorder {
+    /// Wraps `stream` without recording.
+    #[must_use]
+    pub fn new(stream: CaptureStream) -> Self {
+        Self {
+            stream,
+            recording: None,
+            failed: None,
+        }
+    }
+
+    /// The wrapped stream.
+    #[must_use]
+    pub fn stream(&self) -> &CaptureStream {
+        &self.stream
+    }
+
+    /// Unwraps the stream, finalizing any running recording.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the recording could not be finalized.
+    pub fn into_inner(mut self) -> io::Result<CaptureStream> {
+        self.stop_recording()?;
+        Ok(self.stream)
+    }
+
+    /// Whether frames are currently being written.
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Starts writing frames to `path`, replacing any existing file.
+    ///
+    /// A running recording is finalized first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created or the previous
+    /// recording cannot be finalized.
+    pub fn start_recording(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.stop_recording()?;
+        self.failed = None;
+
+        let path = path.as_ref().to_path_buf();
+        let mut file = BufWriter::new(File::create(&path)?);
+        file.write_all(&MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        file.write_all(&0u64.to_le_bytes())?;
+        self.recording = Some(Recording {
+            path,
+            file,
+            frames: 0,
+            first: None,
+        });
+        Ok(())
+    }
+
+    /// Finalizes the running recording.
+    ///
+    /// Returns `None` if nothing was being recorded. A recording that
+    /// failed on a write is finalized as far as possible when it fails and
+    /// reported here, with the error, on the next call.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header or buffered frames cannot be
+    /// written.
+    pub fn stop_recording(&mut self) -> io::Result<Option<RecordingSummary>> {
+        match self.recording.take() {
+            Some(recording) => recording.finish().map(Some),
+            None => Ok(self.failed.take()),
+        }
+    }
+
+    /// Waits for the next frame of the stream, recording it if a
+    /// recording is running.
+    ///
+    /// A failed write (e.g. a full disk) ends the recording; frames keep
+    /// flowing.
+    pub async fn next_frame(&mut self) -> Option<CaptureFrame> {
+        let frame = self.stream.next_frame().await?;
+        let written = match &mut self.recording {
+            Some(recording) => recording.write_frame(&frame),
+            None => Ok(()),
+        };
+        if let Err(error) = written {
+            if let Some(recording) = self.recording.take() {
+                let (path, frames) = (recording.path.clone(), recording.frames);
+                // Keep what made it to disk readable
+                let _ = recording.finish();
+                self.failed = Some(RecordingSummary {
+                    path,
+                    frames,
+                    error: Some(error),
+                });
+            }
+        }
+        Some(frame)
+    }
+}
+
+impl Recording {
+    fn write_frame(&mut self, frame: &CaptureFrame) -> io::Result<()> {
+        let captured = frame.metadata.captured_at;
+        let first = *self.first.get_or_insert(captured);
+        let micros = u64::try_from(captured.saturating_duration_since(first).as_micros())
+            .unwrap_or(u64::MAX);
+        let len = u32::try_from(frame.data().len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "frame too large"))?;
+
+        let mut record = [0u8; RECORD_LEN];
+        record[..8].copy_from_slice(&micros.to_le_bytes());
+        record[8..12].copy_from_slice(&frame.width().to_le_bytes());
+        record[12..16].copy_from_slice(&frame.height().to_le_bytes());
+        record[16..20].copy_from_slice(&frame.stride().to_le_bytes());
+        record[20..24].copy_from_slice(&fourcc(frame.format()).to_le_bytes());
+        record[24..].copy_from_slice(&len.to_le_bytes());
+        self.file.write_all(&record)?;
+        self.file.write_all(frame.data())?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<RecordingSummary> {
+        self.file.flush()?;
+        let file = self.file.get_mut();
+        file.seek(SeekFrom::Start(COUNT_OFFSET))?;
+        file.write_all(&self.frames.to_le_bytes())?;
+        file.sync_data()?;
+        Ok(RecordingSummary {
+            path: self.path,
+            frames: self.frames,
+            error: None,
+        })
+    }
+}
+
+/// What a recording file contains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingInfo {
+    /// Container version
+    pub version: u16,
+    /// Frame count from the header, zero if never finalized
+    pub frame_count: u64,
+    /// Complete frame records found in the file
+    pub frames_found: u64,
+    /// Size of the first frame
+    pub dimensions: Option<(u32, u32)>,
+    /// Time between the first and last frame
+    pub duration: Duration,
+}
+
+impl RecordingInfo {
+    /// Reads the header and walks every frame record of `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a recording.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let len = file.get_ref().metadata()?.len();
+
+        let mut header = [0u8; 16];
+        file.read_exact(&mut header)?;
+        if header[..6] != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a capture recording",
+            ));
+        }
+        let mut info = Self {
+            version: u16::from_le_bytes([header[6], header[7]]),
+            frame_count: u64::from_le_bytes(header[8..].try_into().unwrap_or_default()),
+            frames_found: 0,
+            dimensions: None,
+            duration: Duration::ZERO,
+        };
+
+        let mut offset = HEADER_LEN;
+        let mut record = [0u8; RECORD_LEN];
+        let record_len = RECORD_LEN as u64;
+        while offset + record_len <= len {
+            file.read_exact(&mut record)?;
+            let field =
+                |at: usize| u32::from_le_bytes(record[at..at + 4].try_into().unwrap_or_default());
+            let data_len = u64::from(field(24));
+            if offset + record_len + data_len > len {
+                break;
+            }
+            file.seek(SeekFrom::Current(
+                i64::try_from(data_len).unwrap_or(i64::MAX),
+            ))?;
+            offset += record_len + data_len;
+
+            info.frames_found += 1;
+            info.dimensions.get_or_insert((field(8), field(12)));
+            info.duration = Duration::from_micros(u64::from_le_bytes(
+                record[..8].try_into().unwrap_or_default(),
+            ));
+        }
+        Ok(info)
+    }
+
+    /// Whether the file was finalized and holds every frame it claims.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.frame_count > 0 && self.frame_count == self.frames_found
+    }
+}
+
+/// DRM fourcc of `format`, as used in the recording's frame records.
+fn fourcc(format: FrameFormat) -> u32 {
+    let code = match format {
+        FrameFormat::Bgra8888 => b"AR24",
+        FrameFormat::Rgba8888 => b"AB24",
+        FrameFormat::Xrgb8888 => b"XR24",
+        FrameFormat::Xbgr8888 => b"XB24",
+        FrameFormat::Rgb888 => b"RG24",
+        FrameFormat::Bgr888 => b"BG24",
+        FrameFormat::Xrgb2101010 => b"XR30",
+        FrameFormat::Nv12 => b"NV12",
+        FrameFormat::Yuy2 => b"YUYV",
+        _ => return 0,
+    };
+    u32::from_le_bytes(*code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{
+        CaptureHandle, ColorSpace, FrameMetadata, OutputCaptureRequest, OutputInfo,
+        OutputTransform, TransformHandling,
+    };
+    use crate::session::SessionId;
+    use tokio::sync::mpsc;
+
+    fn frame(sequence: u64, captured_at: Instant) -> CaptureFrame {
+        CaptureFrame::new(
+            FrameMetadata {
+                width: 4,
+                height: 2,
+                stride: 16,
+                format: FrameFormat::Bgra8888,
+                sequence,
+                captured_at,
+                output_name: None,
+                transform: OutputTransform::Normal,
+                transform_handling: TransformHandling::None,
+                platform_data: None,
+            },
+            vec![0x40; 32],
+        )
+    }
+
+    fn stream() -> (CaptureStream, mpsc::Sender<CaptureFrame>) {
+        let (tx, rx) = mpsc::channel(32);
+        let output = OutputInfo {
+            stream: 0,
+            name: "TEST-1".into(),
+            width: 4,
+            height: 2,
+            color_space: ColorSpace::Srgb,
+            hdr: false,
+            transform: OutputTransform::Normal,
+        };
+        let stream = CaptureStream::for_output(
+            SessionId::new("/test/record"),
+            output,
+            &OutputCaptureRequest::new(0),
+            rx,
+            CaptureHandle::default(),
+        );
+        (stream, tx)
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ion-{}-{name}.ionrec", std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn records_frames_and_finalizes_header() {
+        let (stream, tx) = stream();
+        let mut recorder = CaptureRecorder::new(stream);
+        let path = temp_path("frames");
+        let start = Instant::now();
+
+        tx.send(frame(0, start)).await.unwrap();
+        recorder.next_frame().await.unwrap();
+
+        recorder.start_recording(&path).unwrap();
+        for i in 1..=5 {
+            tx.send(frame(i, start + Duration::from_millis(i * 33)))
+                .await
+                .unwrap();
+            assert_eq!(recorder.next_frame().await.unwrap().sequence(), i);
+        }
+        let summary = recorder.stop_recording().unwrap().unwrap();
+        assert_eq!(summary.frames, 5);
+        assert!(summary.error.is_none());
+        assert!(!recorder.is_recording());
+
+        let info = RecordingInfo::read(&path).unwrap();
+        assert_eq!(info.version, VERSION);
+        assert_eq!((info.frame_count, info.frames_found), (5, 5));
+        assert!(info.is_complete());
+        assert_eq!(info.dimensions, Some((4, 2)));
+        assert_eq!(info.duration, Duration::from_millis(4 * 33));
+
+        // Frames keep flowing after the recording stopped
+        tx.send(frame(6, start)).await.unwrap();
+        assert!(recorder.next_frame().await.is_some());
+        assert_eq!(RecordingInfo::read(&path).unwrap().frames_found, 5);
+        assert!(recorder.stop_recording().unwrap().is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn full_disk_stops_recording_but_not_stream() {
+        let (stream, tx) = stream();
+        let mut recorder = CaptureRecorder::new(stream);
+        // Every write to /dev/full fails with ENOSPC
+        recorder.start_recording("/dev/full").unwrap();
+
+        // The first frames may still fit in the write buffer
+        let mut delivered = 0;
+        for i in 0..400 {
+            tx.send(frame(i, Instant::now())).await.unwrap();
+            if recorder.next_frame().await.is_some() {
+                delivered += 1;
+            }
+            if !recorder.is_recording() {
+                break;
+            }
+        }
+        assert!(!recorder.is_recording());
+        assert!(delivered > 0);
+
+        let summary = recorder.stop_recording().unwrap().unwrap();
+        let error = summary.error.unwrap();
+        assert_eq!(error.raw_os_error(), Some(28)); // ENOSPC
+
+        tx.send(frame(0, Instant::now())).await.unwrap();
+        assert!(recorder.next_frame().await.is_some());
+    }
+}
@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Redaction of sensitive input in logs.
+//!
+//! Keyboard keycodes and keysyms are enough to reconstruct what a user
+//! typed, passwords included. They are logged through [`Sensitive`], which
+//! prints `<redacted>` unless `ION_LOG_KEYS=1` is set. Pointer coordinates
+//! and button numbers are not sensitive and are logged as-is.
+
+use std::fmt;
+
+use crate::event::InputEvent;
+
+/// Environment variable that opts in to logging keyboard values.
+pub const LOG_KEYS_ENV: &str = "ION_LOG_KEYS";
+
+/// Placeholder logged in place of a sensitive value.
+pub const REDACTED: &str = "<redacted>";
+
+/// Whether keyboard values may be logged (`ION_LOG_KEYS=1`).
+///
+/// Checked each time a value is formatted, which only happens when a log
+/// record is actually emitted.
+#[must_use]
+pub fn log_keys_enabled() -> bool {
+    std::env::var(LOG_KEYS_ENV).is_ok_and(|v| v == "1")
+}
+
+/// A value that is only shown in logs when [`log_keys_enabled`].
+///
+/// ```
+/// use ion_core::redact::Sensitive;
+///
+/// # std::env::remove_var(ion_core::redact::LOG_KEYS_ENV);
+/// assert_eq!(Sensitive(30).to_string(), "<redacted>");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Sensitive<T>(pub T);
+
+impl<T: fmt::Display> fmt::Display for Sensitive<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if log_keys_enabled() {
+            self.0.fmt(f)
+        } else {
+            f.write_str(REDACTED)
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Sensitive<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if log_keys_enabled() {
+            self.0.fmt(f)
+        } else {
+            f.write_str(REDACTED)
+        }
+    }
+}
+
+/// Debug view of an [`InputEvent`] with keyboard values redacted.
+///
+/// Created by [`InputEvent::redacted`].
+pub struct RedactedEvent<'a>(pub(crate) &'a InputEvent);
+
+impl fmt::Debug for RedactedEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            InputEvent::KeyboardKeycode { keycode, state } => f
+                .debug_struct("KeyboardKeycode")
+                .field("keycode", &Sensitive(keycode))
+                .field("state", state)
+                .finish(),
+            InputEvent::KeyboardKeysym { keysym, state } => f
+                .debug_struct("KeyboardKeysym")
+                .field("keysym", &Sensitive(keysym))
+                .field("state", state)
+                .finish(),
+            event => event.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::KeyState;
+
+    #[test]
+    fn redacted_event_hides_keys_only() {
+        let key = InputEvent::KeyboardKeycode {
+            keycode: 30,
+            state: KeyState::Pressed,
+        };
+        let debug = format!("{:?}", key.redacted());
+        assert!(debug.contains(REDACTED));
+        assert!(!debug.contains("30"));
+
+        let motion = InputEvent::pointer_motion(5.0, 10.0);
+        assert_eq!(format!("{:?}", motion.redacted()), format!("{motion:?}"));
+    }
+}
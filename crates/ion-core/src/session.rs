@@ -11,15 +11,30 @@
 //! Sessions use `Arc` for shared ownership and `tokio::sync` primitives
 //! for interior mutability. This allows safe concurrent access from
 //! multiple async tasks.
+//!
+//! ## Event Ordering
+//!
+//! Each session delivers its events in submission order: an event whose
+//! [`SessionHandle::send_event`] returned before another was submitted
+//! reaches the compositor first, even when several tasks share one
+//! handle. Sends are serialized by the session's lock, which is held until
+//! the event is in the channel, and the channel is FIFO. Events from
+//! different tasks may interleave, but never reorder within a task.
+//!
+//! Sessions are independent: there is no ordering between the events of
+//! two sessions.
 
+use std::collections::BTreeSet;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::mpsc::error::SendTimeoutError;
+use tokio::sync::{broadcast, mpsc, RwLock};
 
 use crate::device::DeviceType;
-use crate::error::{Result, SessionError};
-use crate::event::InputEvent;
+use crate::error::{InputError, InvalidSessionId, Result, SessionError};
+use crate::event::{ButtonState, GesturePhase, InputEvent, KeyState, StampedEvent};
+use crate::mode::{RemoteDesktopMode, SessionCapabilities};
 
 /// Unique identifier for a session.
 ///
@@ -29,12 +44,50 @@ use crate::event::InputEvent;
 pub struct SessionId(Arc<str>);
 
 impl SessionId {
+    /// Longest ID [`Self::new_validated`] accepts, in bytes.
+    pub const MAX_LEN: usize = 255;
+
     /// Creates a new session ID.
+    ///
+    /// Accepts any string; use [`Self::new_validated`] for IDs that come
+    /// from clients.
     #[must_use]
     pub fn new(id: impl Into<String>) -> Self {
         Self(id.into().into())
     }
 
+    /// Creates a session ID from a client-supplied handle.
+    ///
+    /// The handle must be a D-Bus object path (`/` followed by
+    /// `/`-separated elements of `[A-Za-z0-9_]`) of at most
+    /// [`Self::MAX_LEN`] bytes. That rules out traversal sequences, control
+    /// characters and oversized strings before the ID reaches logs or is
+    /// exported on the bus. Malformed handles are rejected rather than
+    /// normalized, so the ID always matches the path the client holds.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first rule `id` breaks.
+    pub fn new_validated(id: &str) -> std::result::Result<Self, InvalidSessionId> {
+        if id.len() > Self::MAX_LEN {
+            return Err(InvalidSessionId::TooLong(id.len()));
+        }
+        let Some(rest) = id.strip_prefix('/') else {
+            return Err(InvalidSessionId::NotAbsolute);
+        };
+        if let Some(c) = id
+            .chars()
+            .find(|&c| c != '/' && c != '_' && !c.is_ascii_alphanumeric())
+        {
+            return Err(InvalidSessionId::InvalidChar(c));
+        }
+        // The root path `/` is the only one allowed to end in a slash
+        if !rest.is_empty() && rest.split('/').any(str::is_empty) {
+            return Err(InvalidSessionId::EmptyElement);
+        }
+        Ok(Self::new(id))
+    }
+
     /// Returns the session ID as a string slice.
     #[must_use]
     pub fn as_str(&self) -> &str {
@@ -92,14 +145,227 @@ impl std::fmt::Display for SessionState {
     }
 }
 
+/// Requested input rate profile for a session.
+///
+/// Clients pass a profile name in the `rate_profile` portal option.
+/// The compositor maps it to a preset rate-limit configuration, capped
+/// at a service-wide ceiling so no profile grants unlimited input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RateProfile {
+    /// Low-rate interactive use (e.g. occasional clicks).
+    Low,
+    /// Typical desktop use.
+    #[default]
+    Normal,
+    /// High-throughput automation.
+    High,
+}
+
+impl RateProfile {
+    /// Portal option key carrying the requested profile.
+    pub const OPTION_KEY: &'static str = "rate_profile";
+
+    /// Parses a profile name, falling back to `Normal` for unknown names.
+    #[must_use]
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "low" => Self::Low,
+            "high" => Self::High,
+            _ => Self::Normal,
+        }
+    }
+
+    /// Returns the profile name as used in portal options.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high",
+        }
+    }
+}
+
+impl std::fmt::Display for RateProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Notification that an active session's mode changed at runtime.
+///
+/// Sent when capabilities are lost (or restored) mid-session, so the
+/// client can e.g. stop expecting frames after a drop to `InputOnly`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionModeChanged {
+    /// Session whose mode changed
+    pub session_id: SessionId,
+    /// Mode before the change
+    pub previous: RemoteDesktopMode,
+    /// Mode after the change
+    pub mode: RemoteDesktopMode,
+}
+
 /// Internal session data protected by `RwLock`.
 #[derive(Debug)]
 struct SessionInner {
     state: SessionState,
     authorized_devices: DeviceType,
     app_id: String,
+    /// App ID established from the client process, not its own claim
+    verified_app_id: Option<String>,
     created_at: Instant,
     event_count: u64,
+    rate_profile: RateProfile,
+    /// Seat input is injected on; `None` is the primary seat
+    seat: Option<String>,
+    held: HeldInputs,
+    mode: RemoteDesktopMode,
+    /// What the backend can currently provide; bounds `mode`
+    capabilities: SessionCapabilities,
+}
+
+impl SessionInner {
+    /// Selected devices the current mode allows.
+    fn effective_devices(&self) -> DeviceType {
+        self.authorized_devices & self.mode.capabilities_mask()
+    }
+}
+
+/// Keys and buttons currently pressed, and gestures in progress, through
+/// a session.
+///
+/// Tracked so that closing a session can release them instead of
+/// leaving stuck modifiers, buttons or half-finished gestures in the
+/// compositor.
+#[derive(Debug, Default)]
+pub struct HeldInputs {
+    keycodes: BTreeSet<i32>,
+    keysyms: BTreeSet<i32>,
+    buttons: BTreeSet<i32>,
+    /// Locked modifiers and group of an explicit state with modifiers
+    /// depressed or latched, kept when those are released
+    modifiers: Option<(u32, u32)>,
+    /// Fingers of the swipe, pinch and hold gestures begun but not ended
+    swipe: Option<u32>,
+    pinch: Option<u32>,
+    hold: Option<u32>,
+}
+
+impl HeldInputs {
+    /// Records the presses and releases in `event`.
+    pub fn track(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::KeyboardKeycode { keycode, state } => {
+                Self::update(&mut self.keycodes, keycode, state == KeyState::Pressed);
+            },
+            InputEvent::KeyboardKeysym { keysym, state } => {
+                Self::update(&mut self.keysyms, keysym, state == KeyState::Pressed);
+            },
+            InputEvent::PointerButton { button, state } => {
+                Self::update(&mut self.buttons, button, state == ButtonState::Pressed);
+            },
+            InputEvent::KeyboardModifiers {
+                depressed,
+                latched,
+                locked,
+                group,
+            } => {
+                self.modifiers = (depressed | latched != 0).then_some((locked, group));
+            },
+            InputEvent::GestureSwipe { phase, fingers, .. } => {
+                Self::update_gesture(&mut self.swipe, phase, fingers);
+            },
+            InputEvent::GesturePinch { phase, fingers, .. } => {
+                Self::update_gesture(&mut self.pinch, phase, fingers);
+            },
+            InputEvent::GestureHold { phase, fingers } => {
+                Self::update_gesture(&mut self.hold, phase, fingers);
+            },
+            _ => {},
+        }
+    }
+
+    fn update_gesture(gesture: &mut Option<u32>, phase: GesturePhase, fingers: u32) {
+        match phase {
+            GesturePhase::Begin => *gesture = Some(fingers),
+            GesturePhase::Update => {},
+            GesturePhase::End | GesturePhase::Cancel => *gesture = None,
+        }
+    }
+
+    fn update(set: &mut BTreeSet<i32>, code: i32, pressed: bool) {
+        if pressed {
+            set.insert(code);
+        } else {
+            set.remove(&code);
+        }
+    }
+
+    /// Returns how many keys, buttons and gestures are held.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.keycodes.len()
+            + self.keysyms.len()
+            + self.buttons.len()
+            + usize::from(self.modifiers.is_some())
+            + [self.swipe, self.pinch, self.hold]
+                .iter()
+                .filter(|g| g.is_some())
+                .count()
+    }
+
+    /// Returns true if nothing is held.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drains held inputs into the matching release events.
+    ///
+    /// Gestures in progress are cancelled rather than ended, so the
+    /// compositor undoes their effect.
+    pub fn drain_releases(&mut self) -> Vec<InputEvent> {
+        let mut releases = Vec::with_capacity(self.len());
+        for keycode in std::mem::take(&mut self.keycodes) {
+            releases.push(InputEvent::key(keycode, KeyState::Released));
+        }
+        for keysym in std::mem::take(&mut self.keysyms) {
+            releases.push(InputEvent::KeyboardKeysym {
+                keysym,
+                state: KeyState::Released,
+            });
+        }
+        for button in std::mem::take(&mut self.buttons) {
+            releases.push(InputEvent::pointer_button(button, ButtonState::Released));
+        }
+        if let Some((locked, group)) = self.modifiers.take() {
+            releases.push(InputEvent::keyboard_modifiers(0, 0, locked, group));
+        }
+        let phase = GesturePhase::Cancel;
+        if let Some(fingers) = self.swipe.take() {
+            releases.push(InputEvent::GestureSwipe {
+                phase,
+                fingers,
+                dx: 0.0,
+                dy: 0.0,
+            });
+        }
+        if let Some(fingers) = self.pinch.take() {
+            releases.push(InputEvent::GesturePinch {
+                phase,
+                fingers,
+                dx: 0.0,
+                dy: 0.0,
+                scale: 1.0,
+                rotation: 0.0,
+            });
+        }
+        if let Some(fingers) = self.hold.take() {
+            releases.push(InputEvent::GestureHold { phase, fingers });
+        }
+        releases
+    }
 }
 
 /// A handle to a remote desktop session.
@@ -116,26 +382,72 @@ pub struct SessionHandle {
     id: SessionId,
     inner: Arc<RwLock<SessionInner>>,
     /// Channel for sending input events to the compositor
-    event_tx: mpsc::Sender<InputEvent>,
+    event_tx: mpsc::Sender<StampedEvent>,
+    /// How long [`Self::send_event`] waits for room in a full channel
+    send_timeout: Option<Duration>,
+    /// Events the session may send over its lifetime
+    event_budget: Option<u64>,
+    /// Told the session's ID once its event budget is spent
+    budget_tx: Option<broadcast::Sender<SessionId>>,
+    /// Mode change notifications
+    mode_tx: broadcast::Sender<SessionModeChanged>,
 }
 
 impl SessionHandle {
     /// Creates a new session with the given ID and event channel.
     #[must_use]
-    pub fn new(id: SessionId, app_id: String, event_tx: mpsc::Sender<InputEvent>) -> Self {
+    pub fn new(id: SessionId, app_id: String, event_tx: mpsc::Sender<StampedEvent>) -> Self {
         Self {
             id,
             inner: Arc::new(RwLock::new(SessionInner {
                 state: SessionState::Created,
                 authorized_devices: DeviceType::empty(),
                 app_id,
+                verified_app_id: None,
                 created_at: Instant::now(),
                 event_count: 0,
+                rate_profile: RateProfile::default(),
+                seat: None,
+                held: HeldInputs::default(),
+                mode: RemoteDesktopMode::Full,
+                capabilities: SessionCapabilities::full(),
             })),
             event_tx,
+            send_timeout: None,
+            event_budget: None,
+            budget_tx: None,
+            mode_tx: broadcast::channel(8).0,
         }
     }
 
+    /// Bounds how long [`Self::send_event`] waits for room in a full
+    /// event channel.
+    ///
+    /// Without a timeout, sends wait until the channel drains.
+    #[must_use]
+    pub fn with_send_timeout(mut self, timeout: Duration) -> Self {
+        self.send_timeout = Some(timeout);
+        self
+    }
+
+    /// Caps the events the session may send over its whole lifetime.
+    ///
+    /// Once the `max_events`th event is sent, held keys and buttons are
+    /// released, later sends fail, and the session's ID goes to
+    /// `exhausted` for whoever owns the session to close it. This catches
+    /// slow-drip input that stays under the rate limit. Without a budget,
+    /// sessions are unlimited.
+    #[must_use]
+    pub fn with_event_budget(
+        mut self,
+        max_events: u64,
+        exhausted: broadcast::Sender<SessionId>,
+    ) -> Self {
+        self.event_budget = Some(max_events);
+        self.budget_tx = Some(exhausted);
+        self
+    }
+
     /// Returns the session ID.
     #[must_use]
     pub fn id(&self) -> &SessionId {
@@ -147,14 +459,32 @@ impl SessionHandle {
         self.inner.read().await.app_id.clone()
     }
 
+    /// Returns the app ID established from the client process, if any.
+    ///
+    /// Unlike [`Self::app_id`], which the client supplies, this is what
+    /// the sandbox the client runs in reports, so it can key decisions
+    /// that outlive the session. `None` for unsandboxed clients.
+    pub async fn verified_app_id(&self) -> Option<String> {
+        self.inner.read().await.verified_app_id.clone()
+    }
+
+    /// Sets the app ID established from the client process.
+    pub async fn set_verified_app_id(&self, app_id: Option<String>) {
+        self.inner.write().await.verified_app_id = app_id.filter(|id| !id.is_empty());
+    }
+
     /// Returns the current session state.
     pub async fn state(&self) -> SessionState {
         self.inner.read().await.state
     }
 
-    /// Returns the authorized device types.
+    /// Returns the authorized device types, limited to what the current
+    /// mode allows.
+    ///
+    /// A session in a mode without input has no authorized devices, even
+    /// if some were selected.
     pub async fn authorized_devices(&self) -> DeviceType {
-        self.inner.read().await.authorized_devices
+        self.inner.read().await.effective_devices()
     }
 
     /// Returns the number of events processed.
@@ -162,8 +492,115 @@ impl SessionHandle {
         self.inner.read().await.event_count
     }
 
+    /// Returns the number of keys and buttons currently held down and
+    /// gestures in progress.
+    pub async fn held_input_count(&self) -> usize {
+        self.inner.read().await.held.len()
+    }
+
+    /// Returns the requested input rate profile.
+    pub async fn rate_profile(&self) -> RateProfile {
+        self.inner.read().await.rate_profile
+    }
+
+    /// Sets the requested input rate profile.
+    pub async fn set_rate_profile(&self, profile: RateProfile) {
+        self.inner.write().await.rate_profile = profile;
+    }
+
+    /// Returns the seat input is injected on, `None` for the primary seat.
+    pub async fn seat(&self) -> Option<String> {
+        self.inner.read().await.seat.clone()
+    }
+
+    /// Sets the seat input is injected on.
+    pub async fn set_seat(&self, seat: Option<String>) {
+        self.inner.write().await.seat = seat;
+    }
+
+    /// Returns the session's operating mode.
+    pub async fn mode(&self) -> RemoteDesktopMode {
+        self.inner.read().await.mode
+    }
+
+    /// Returns the capabilities the session's mode is bounded by.
+    pub async fn capabilities(&self) -> SessionCapabilities {
+        self.inner.read().await.capabilities
+    }
+
+    /// Subscribes to mode changes of the active session.
+    pub fn subscribe_mode(&self) -> broadcast::Receiver<SessionModeChanged> {
+        self.mode_tx.subscribe()
+    }
+
+    /// Changes the session's operating mode.
+    ///
+    /// The mode may only use capabilities from [`Self::capabilities`], so
+    /// a session cannot escalate to capture the backend cannot provide.
+    /// Changing the mode of an active session notifies
+    /// [`Self::subscribe_mode`] subscribers; losing input releases any
+    /// keys or buttons still held.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session is closed or the mode needs
+    /// unavailable capabilities.
+    pub async fn set_mode(&self, mode: RemoteDesktopMode) -> Result<()> {
+        let mut inner = self.inner.write().await;
+
+        if inner.state == SessionState::Closed {
+            return Err(SessionError::Closed.into());
+        }
+        if !mode.is_supported_by(&inner.capabilities) {
+            return Err(SessionError::ModeUnavailable(mode.name()).into());
+        }
+
+        self.apply_mode(&mut inner, mode);
+        Ok(())
+    }
+
+    /// Updates what the backend can provide, degrading the mode to fit.
+    ///
+    /// Capabilities that become available again are not taken up
+    /// automatically; call [`Self::set_mode`] to escalate.
+    ///
+    /// Returns the resulting mode.
+    pub async fn set_capabilities(&self, capabilities: SessionCapabilities) -> RemoteDesktopMode {
+        let mut inner = self.inner.write().await;
+        inner.capabilities = capabilities;
+        let mode = inner.mode.restricted_to(&capabilities);
+        self.apply_mode(&mut inner, mode);
+        mode
+    }
+
+    fn apply_mode(&self, inner: &mut SessionInner, mode: RemoteDesktopMode) {
+        let previous = inner.mode;
+        if previous == mode {
+            return;
+        }
+        inner.mode = mode;
+
+        if inner.state == SessionState::Active {
+            if previous.has_input() && !mode.has_input() {
+                for release in inner.held.drain_releases() {
+                    let release = StampedEvent::from(release).with_seat(inner.seat.clone());
+                    let _ = self.event_tx.try_send(release);
+                }
+            }
+            // No subscribers is fine
+            let _ = self.mode_tx.send(SessionModeChanged {
+                session_id: self.id.clone(),
+                previous,
+                mode,
+            });
+        }
+    }
+
     /// Sets the authorized devices after user consent.
     ///
+    /// Devices the current mode does not allow are dropped; see
+    /// [`RemoteDesktopMode::capabilities_mask`].
+    ///
     /// # Errors
     ///
     /// Returns an error if the session is not in the `Created` state.
@@ -178,7 +615,7 @@ impl SessionHandle {
             .into());
         }
 
-        inner.authorized_devices = devices;
+        inner.authorized_devices = devices & inner.mode.capabilities_mask();
         inner.state = SessionState::DevicesSelected;
         Ok(())
     }
@@ -205,15 +642,43 @@ impl SessionHandle {
 
     /// Sends an input event through this session.
     ///
+    /// Concurrent calls are serialized, so events are delivered in the
+    /// order they were submitted (see [Event Ordering](self#event-ordering)).
+    ///
     /// # Errors
     ///
     /// Returns an error if:
     /// - The session is not active
     /// - The event type is not authorized
     /// - The event channel is closed
+    /// - The event channel stayed full for the whole send timeout
+    ///   ([`InputError::WouldBlock`])
+    /// - The session spent its event budget
+    ///   ([`SessionError::EventBudgetExhausted`])
     pub async fn send_event(&self, event: InputEvent) -> Result<()> {
+        self.send_stamped_event(event, None).await
+    }
+
+    /// Sends an input event along with when the client sent it.
+    ///
+    /// As [`Self::send_event`], but the compositor also gets
+    /// `client_timestamp` (microseconds since the Unix epoch) to account
+    /// for transit delay.
+    ///
+    /// # Errors
+    ///
+    /// As for [`Self::send_event`].
+    pub async fn send_stamped_event(
+        &self,
+        event: InputEvent,
+        client_timestamp: Option<u64>,
+    ) -> Result<()> {
         let mut inner = self.inner.write().await;
 
+        if let Some(budget) = self.event_budget.filter(|&b| inner.event_count >= b) {
+            return Err(SessionError::EventBudgetExhausted(budget).into());
+        }
+
         // Check session is active
         if inner.state != SessionState::Active {
             return Err(SessionError::InvalidState {
@@ -223,8 +688,12 @@ impl SessionHandle {
             .into());
         }
 
+        if !inner.mode.has_input() {
+            return Err(SessionError::Unauthorized.into());
+        }
+
         // Check device type is authorized
-        let authorized = inner.authorized_devices;
+        let authorized = inner.effective_devices();
         if event.is_keyboard() && !authorized.has_keyboard() {
             return Err(crate::error::InputError::DeviceNotAuthorized("keyboard".into()).into());
         }
@@ -234,20 +703,67 @@ impl SessionHandle {
         if event.is_touch() && !authorized.has_touchscreen() {
             return Err(crate::error::InputError::DeviceNotAuthorized("touchscreen".into()).into());
         }
+        if event.is_gesture() && !authorized.has_gesture() {
+            return Err(crate::error::InputError::DeviceNotAuthorized("gesture".into()).into());
+        }
 
-        // Send event
-        self.event_tx
-            .send(event)
-            .await
-            .map_err(|_| crate::error::Error::ChannelClosed)?;
+        // Send event, still under the lock so concurrent senders cannot
+        // overtake each other between the checks and the channel
+        let event_for_tracking = event.clone();
+        let event = StampedEvent::new(event, client_timestamp).with_seat(inner.seat.clone());
+        match self.send_timeout {
+            Some(timeout) => {
+                self.event_tx
+                    .send_timeout(event, timeout)
+                    .await
+                    .map_err(|e| match e {
+                        SendTimeoutError::Timeout(_) => InputError::WouldBlock(timeout).into(),
+                        SendTimeoutError::Closed(_) => crate::error::Error::ChannelClosed,
+                    })?;
+            },
+            None => self
+                .event_tx
+                .send(event)
+                .await
+                .map_err(|_| crate::error::Error::ChannelClosed)?,
+        }
 
+        inner.held.track(&event_for_tracking);
         inner.event_count += 1;
+
+        if self.event_budget == Some(inner.event_count) {
+            // Nothing more gets through, so nothing may stay held
+            for release in inner.held.drain_releases() {
+                let release = StampedEvent::from(release).with_seat(inner.seat.clone());
+                let _ = self.event_tx.try_send(release);
+            }
+            if let Some(budget_tx) = &self.budget_tx {
+                // No subscribers is fine
+                let _ = budget_tx.send(self.id.clone());
+            }
+        }
         Ok(())
     }
 
     /// Closes the session.
+    ///
+    /// Any keys or buttons still held are released first, and gestures
+    /// in progress cancelled, so a session that ends mid-keypress or
+    /// mid-swipe does not leave stuck input behind.
     pub async fn close(&self) {
         let mut inner = self.inner.write().await;
+        self.close_locked(&mut inner);
+    }
+
+    fn close_locked(&self, inner: &mut SessionInner) {
+        if inner.state == SessionState::Active {
+            for release in inner.held.drain_releases() {
+                // Best effort: never block close on a full or closed channel
+                let release = StampedEvent::from(release).with_seat(inner.seat.clone());
+                let _ = self.event_tx.try_send(release);
+            }
+        }
+
         inner.state = SessionState::Closed;
     }
 
@@ -298,7 +814,7 @@ mod tests {
 
         // Receive event
         let event = rx.recv().await.unwrap();
-        assert!(event.is_pointer());
+        assert!(event.event.is_pointer());
 
         // Close session
         session.close().await;
@@ -327,12 +843,248 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn send_timeout_reports_would_block() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let session = SessionHandle::new(SessionId::new("/test/full"), "app".into(), tx)
+            .with_send_timeout(Duration::from_millis(20));
+        session.select_devices(DeviceType::POINTER).await.unwrap();
+        session.start().await.unwrap();
+
+        session
+            .send_event(InputEvent::pointer_motion(1.0, 1.0))
+            .await
+            .unwrap();
+        let result = session
+            .send_event(InputEvent::pointer_motion(2.0, 2.0))
+            .await;
+        assert!(matches!(
+            result,
+            Err(crate::error::Error::Input(InputError::WouldBlock(_)))
+        ));
+        assert_eq!(session.event_count().await, 1);
+
+        rx.recv().await.unwrap();
+        session
+            .send_event(InputEvent::pointer_motion(3.0, 3.0))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn exhausted_event_budget_releases_and_reports() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let (budget_tx, mut exhausted) = broadcast::channel(1);
+        let session = SessionHandle::new(SessionId::new("/test/budget"), "app".into(), tx)
+            .with_event_budget(2, budget_tx);
+        session.select_devices(DeviceType::all()).await.unwrap();
+        session.start().await.unwrap();
+
+        session
+            .send_event(InputEvent::key(42, KeyState::Pressed))
+            .await
+            .unwrap();
+        assert!(exhausted.try_recv().is_err());
+        session
+            .send_event(InputEvent::key(30, KeyState::Pressed))
+            .await
+            .unwrap();
+        assert_eq!(exhausted.try_recv().unwrap(), *session.id());
+        assert_eq!(session.held_input_count().await, 0);
+
+        // Both keys are released at once, and nothing else gets through
+        let result = session
+            .send_event(InputEvent::key(31, KeyState::Pressed))
+            .await;
+        assert!(matches!(
+            result,
+            Err(crate::error::Error::Session(
+                SessionError::EventBudgetExhausted(2)
+            ))
+        ));
+        let mut received = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            received.push(event.event);
+        }
+        assert_eq!(received.len(), 4);
+        assert!(received[2..].iter().all(|e| matches!(
+            e,
+            InputEvent::KeyboardKeycode {
+                state: KeyState::Released,
+                ..
+            }
+        )));
+    }
+
+    #[tokio::test]
+    async fn close_releases_held_inputs() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test/held"), "app".into(), tx);
+        session.select_devices(DeviceType::all()).await.unwrap();
+        session.start().await.unwrap();
+
+        // Shift held, 'a' pressed and released, left button held
+        session
+            .send_event(InputEvent::key(42, KeyState::Pressed))
+            .await
+            .unwrap();
+        session
+            .send_event(InputEvent::key(30, KeyState::Pressed))
+            .await
+            .unwrap();
+        session
+            .send_event(InputEvent::key(30, KeyState::Released))
+            .await
+            .unwrap();
+        session
+            .send_event(InputEvent::left_click(true))
+            .await
+            .unwrap();
+        assert_eq!(session.held_input_count().await, 2);
+
+        for _ in 0..4 {
+            rx.recv().await.unwrap();
+        }
+
+        session.close().await;
+        assert_eq!(session.held_input_count().await, 0);
+
+        let first = rx.recv().await.unwrap().event;
+        let second = rx.recv().await.unwrap().event;
+        assert!(matches!(
+            first,
+            InputEvent::KeyboardKeycode {
+                keycode: 42,
+                state: KeyState::Released
+            }
+        ));
+        assert!(matches!(
+            second,
+            InputEvent::PointerButton {
+                state: ButtonState::Released,
+                ..
+            }
+        ));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn close_cancels_gestures_in_progress() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test/gesture"), "app".into(), tx);
+        session.select_devices(DeviceType::all()).await.unwrap();
+        session.start().await.unwrap();
+
+        // A pinch still going, a hold already lifted
+        for event in [
+            InputEvent::GesturePinch {
+                phase: GesturePhase::Begin,
+                fingers: 2,
+                dx: 0.0,
+                dy: 0.0,
+                scale: 1.0,
+                rotation: 0.0,
+            },
+            InputEvent::GestureHold {
+                phase: GesturePhase::Begin,
+                fingers: 3,
+            },
+            InputEvent::GestureHold {
+                phase: GesturePhase::End,
+                fingers: 3,
+            },
+        ] {
+            session.send_event(event).await.unwrap();
+            rx.recv().await.unwrap();
+        }
+        assert_eq!(session.held_input_count().await, 1);
+
+        session.close().await;
+        assert_eq!(session.held_input_count().await, 0);
+        assert!(matches!(
+            rx.recv().await.unwrap().event,
+            InputEvent::GesturePinch {
+                phase: GesturePhase::Cancel,
+                fingers: 2,
+                ..
+            }
+        ));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn close_clears_explicit_modifiers_but_keeps_locks() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test/mods"), "app".into(), tx);
+        session.select_devices(DeviceType::KEYBOARD).await.unwrap();
+        session.start().await.unwrap();
+
+        // Ctrl held with Caps Lock on
+        session
+            .send_event(InputEvent::keyboard_modifiers(0x4, 0, 0x2, 1))
+            .await
+            .unwrap();
+        assert_eq!(session.held_input_count().await, 1);
+        rx.recv().await.unwrap();
+
+        session.close().await;
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            InputEvent::keyboard_modifiers(0, 0, 0x2, 1)
+        );
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn close_inactive_session_sends_nothing() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test/idle"), "app".into(), tx);
+
+        session.close().await;
+        assert!(rx.try_recv().is_err());
+    }
+
     #[test]
     fn session_id_new() {
         let id = SessionId::new("/org/freedesktop/portal/session/1");
         assert_eq!(id.as_str(), "/org/freedesktop/portal/session/1");
     }
 
+    #[test]
+    fn session_id_validated_accepts_object_paths() {
+        for path in [
+            "/",
+            "/org/freedesktop/portal/desktop/session/1_42/ion_7",
+            "/a",
+        ] {
+            assert_eq!(SessionId::new_validated(path).unwrap().as_str(), path);
+        }
+    }
+
+    #[test]
+    fn session_id_validated_rejects_malformed() {
+        use crate::error::InvalidSessionId;
+
+        let cases = [
+            ("../../../etc/passwd", InvalidSessionId::NotAbsolute),
+            ("/session/../../etc", InvalidSessionId::InvalidChar('.')),
+            ("/\0null\0bytes", InvalidSessionId::InvalidChar('\0')),
+            ("/id\nwith\nnewlines", InvalidSessionId::InvalidChar('\n')),
+            ("/a//b", InvalidSessionId::EmptyElement),
+            ("/a/", InvalidSessionId::EmptyElement),
+            ("", InvalidSessionId::NotAbsolute),
+        ];
+        for (id, expected) in cases {
+            assert_eq!(SessionId::new_validated(id), Err(expected), "{id:?}");
+        }
+
+        let long = format!("/{}", "a".repeat(10 * 1024));
+        assert_eq!(
+            SessionId::new_validated(&long),
+            Err(InvalidSessionId::TooLong(long.len()))
+        );
+    }
+
     #[test]
     fn session_id_display() {
         let id = SessionId::new("test-session");
@@ -376,6 +1128,66 @@ mod tests {
         assert_eq!(SessionState::Closed.name(), "Closed");
     }
 
+    #[test]
+    fn rate_profile_from_name() {
+        assert_eq!(RateProfile::from_name("low"), RateProfile::Low);
+        assert_eq!(RateProfile::from_name("normal"), RateProfile::Normal);
+        assert_eq!(RateProfile::from_name("HIGH"), RateProfile::High);
+        assert_eq!(RateProfile::from_name("unlimited"), RateProfile::Normal);
+        assert_eq!(RateProfile::from_name(""), RateProfile::Normal);
+    }
+
+    #[test]
+    fn rate_profile_name_round_trip() {
+        for profile in [RateProfile::Low, RateProfile::Normal, RateProfile::High] {
+            assert_eq!(RateProfile::from_name(profile.name()), profile);
+            assert_eq!(profile.to_string(), profile.name());
+        }
+        assert_eq!(RateProfile::default(), RateProfile::Normal);
+    }
+
+    #[tokio::test]
+    async fn session_rate_profile() {
+        let (tx, _rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test"), "app".into(), tx);
+        assert_eq!(session.rate_profile().await, RateProfile::Normal);
+
+        session.set_rate_profile(RateProfile::High).await;
+        assert_eq!(session.rate_profile().await, RateProfile::High);
+    }
+
+    #[tokio::test]
+    async fn session_seat_defaults_to_primary() {
+        let (tx, _rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test"), "app".into(), tx);
+        assert_eq!(session.seat().await, None);
+
+        session.set_seat(Some("seat1".into())).await;
+        assert_eq!(session.seat().await.as_deref(), Some("seat1"));
+    }
+
+    #[tokio::test]
+    async fn events_and_releases_carry_the_session_seat() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test/seat"), "app".into(), tx);
+        session.set_seat(Some("seat1".into())).await;
+        session.select_devices(DeviceType::all()).await.unwrap();
+        session.start().await.unwrap();
+
+        session
+            .send_event(InputEvent::key(30, KeyState::Pressed))
+            .await
+            .unwrap();
+        session.close().await;
+
+        let press = rx.recv().await.unwrap();
+        let release = rx.recv().await.unwrap();
+        assert_eq!(release, InputEvent::key(30, KeyState::Released));
+        for event in [press, release] {
+            assert_eq!(event.seat.as_deref(), Some("seat1"));
+        }
+    }
+
     #[test]
     fn session_state_display() {
         assert_eq!(SessionState::Created.to_string(), "Created");
@@ -504,9 +1316,157 @@ mod tests {
         assert_eq!(session.event_count().await, 3);
 
         // Receive all events
-        assert!(rx.recv().await.unwrap().is_pointer());
-        assert!(rx.recv().await.unwrap().is_keyboard());
-        assert!(rx.recv().await.unwrap().is_touch());
+        assert!(rx.recv().await.unwrap().event.is_pointer());
+        assert!(rx.recv().await.unwrap().event.is_keyboard());
+        assert!(rx.recv().await.unwrap().event.is_touch());
+    }
+
+    #[tokio::test]
+    async fn gestures_need_their_own_device_type() {
+        let (tx, mut denied_rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test/gesture"), "app".into(), tx);
+        let swipe = InputEvent::GestureSwipe {
+            phase: crate::event::GesturePhase::Begin,
+            fingers: 3,
+            dx: 0.0,
+            dy: 0.0,
+        };
+
+        // Pointer access alone does not cover touchpad gestures
+        session
+            .select_devices(DeviceType::desktop_standard())
+            .await
+            .unwrap();
+        session.start().await.unwrap();
+        let err = session.send_event(swipe.clone()).await.unwrap_err();
+        assert!(err.to_string().contains("gesture"));
+
+        assert!(denied_rx.try_recv().is_err());
+
+        let (tx, mut granted_rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test/gesture2"), "app".into(), tx);
+        session
+            .select_devices(DeviceType::POINTER | DeviceType::GESTURE)
+            .await
+            .unwrap();
+        session.start().await.unwrap();
+        session.send_event(swipe).await.unwrap();
+        assert!(granted_rx.recv().await.unwrap().event.is_gesture());
+    }
+
+    #[tokio::test]
+    async fn capture_loss_degrades_to_input_only() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test/mode"), "app".into(), tx);
+        session.select_devices(DeviceType::all()).await.unwrap();
+        session.start().await.unwrap();
+        let mut changes = session.subscribe_mode();
+
+        let mode = session
+            .set_capabilities(SessionCapabilities::input_only())
+            .await;
+        assert_eq!(mode, RemoteDesktopMode::InputOnly);
+        assert_eq!(
+            changes.try_recv().unwrap(),
+            SessionModeChanged {
+                session_id: SessionId::new("/test/mode"),
+                previous: RemoteDesktopMode::Full,
+                mode: RemoteDesktopMode::InputOnly,
+            }
+        );
+
+        // Capture cannot come back until the backend provides it
+        assert!(matches!(
+            session.set_mode(RemoteDesktopMode::Full).await,
+            Err(crate::Error::Session(SessionError::ModeUnavailable(_)))
+        ));
+        assert_eq!(session.mode().await, RemoteDesktopMode::InputOnly);
+
+        session
+            .send_event(InputEvent::pointer_motion(1.0, 1.0))
+            .await
+            .unwrap();
+        assert!(rx.recv().await.unwrap().event.is_pointer());
+    }
+
+    #[tokio::test]
+    async fn losing_input_releases_held_keys() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test/view"), "app".into(), tx);
+        session.select_devices(DeviceType::KEYBOARD).await.unwrap();
+        session.start().await.unwrap();
+        session
+            .send_event(InputEvent::key(30, KeyState::Pressed))
+            .await
+            .unwrap();
+        rx.recv().await.unwrap();
+
+        session.set_mode(RemoteDesktopMode::ViewOnly).await.unwrap();
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            InputEvent::key(30, KeyState::Released)
+        );
+        assert!(session
+            .send_event(InputEvent::key(30, KeyState::Pressed))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn view_only_session_has_no_input_devices() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test/viewonly"), "app".into(), tx);
+        session.set_mode(RemoteDesktopMode::ViewOnly).await.unwrap();
+        session
+            .select_devices(DeviceType::KEYBOARD | DeviceType::POINTER)
+            .await
+            .unwrap();
+        session.start().await.unwrap();
+
+        assert_eq!(session.authorized_devices().await, DeviceType::empty());
+        assert!(session
+            .send_event(InputEvent::key(30, KeyState::Pressed))
+            .await
+            .is_err());
+        assert!(session
+            .send_event(InputEvent::pointer_motion(1.0, 1.0))
+            .await
+            .is_err());
+        assert!(rx.try_recv().is_err());
+
+        // Devices were never granted, so input stays off once it is back
+        session.set_mode(RemoteDesktopMode::Full).await.unwrap();
+        assert_eq!(session.authorized_devices().await, DeviceType::empty());
+    }
+
+    #[tokio::test]
+    async fn mode_without_input_hides_selected_devices() {
+        let (tx, _rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test/hide"), "app".into(), tx);
+        session.select_devices(DeviceType::KEYBOARD).await.unwrap();
+        session.start().await.unwrap();
+
+        session.set_mode(RemoteDesktopMode::ViewOnly).await.unwrap();
+        assert_eq!(session.authorized_devices().await, DeviceType::empty());
+        session.set_mode(RemoteDesktopMode::Full).await.unwrap();
+        assert_eq!(session.authorized_devices().await, DeviceType::KEYBOARD);
+    }
+
+    #[tokio::test]
+    async fn mode_set_before_start_is_silent() {
+        let (tx, _rx) = mpsc::channel(16);
+        let session = SessionHandle::new(SessionId::new("/test/quiet"), "app".into(), tx);
+        let mut changes = session.subscribe_mode();
+
+        session
+            .set_mode(RemoteDesktopMode::InputOnly)
+            .await
+            .unwrap();
+        assert_eq!(session.mode().await, RemoteDesktopMode::InputOnly);
+        assert!(changes.try_recv().is_err());
+
+        session.close().await;
+        assert!(session.set_mode(RemoteDesktopMode::Full).await.is_err());
     }
 
     #[test]
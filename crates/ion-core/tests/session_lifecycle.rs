@@ -124,7 +124,7 @@ async fn can_send_events_when_active() {
 
     // Verify event received
     let received = rx.try_recv().expect("should receive event");
-    match received {
+    match received.event {
         InputEvent::PointerMotion { dx, dy } => {
             assert_eq!(dx, 10.0);
             assert_eq!(dy, 5.0);
@@ -2,11 +2,17 @@
 //!
 //! Capability-based deployment with no hardcoded paths or commands.
 //! Each component discovers its own deployment requirements.
+//!
+//! A deployment is first resolved into a [`DeployPlan`] — the ordered list
+//! of actions it will take — and then executed. A dry run stops after
+//! resolving the plan, so the plan shown is exactly what a real run does.
 
-use crate::discovery::VmInfo;
-use crate::ssh::SshConnection;
+use crate::discovery::{VmAddr, VmInfo};
+use crate::ssh::{SshCapabilities, SshConnection};
 use anyhow::{Context, Result};
-use std::path::Path;
+use async_trait::async_trait;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
 /// Deployment configuration discovered from environment
@@ -34,13 +40,172 @@ impl DeploymentConfig {
             source_dir,
             target_dir,
             build_command: "cargo build --release --all".to_string(),
-            install_command: "sudo cp target/release/xdg-desktop-portal-cosmic /usr/libexec/".to_string(),
+            install_command: "sudo cp target/release/xdg-desktop-portal-cosmic /usr/libexec/"
+                .to_string(),
         })
     }
 }
 
+/// Remote operations a deployment needs
+///
+/// Implemented by [`SshConnection`]; abstracted so deployments can be
+/// exercised without a VM.
+#[async_trait]
+pub trait RemoteShell: Send {
+    /// Execute command and return output
+    async fn execute(&mut self, command: &str) -> Result<String>;
+
+    /// Copy a local file to the remote system
+    async fn transfer_file(&mut self, local_path: &Path, remote_path: &str) -> Result<()>;
+}
+
+#[async_trait]
+impl RemoteShell for SshConnection {
+    async fn execute(&mut self, command: &str) -> Result<String> {
+        SshConnection::execute(self, command).await
+    }
+
+    async fn transfer_file(&mut self, local_path: &Path, remote_path: &str) -> Result<()> {
+        SshConnection::transfer_file(self, local_path, remote_path).await
+    }
+}
+
+/// A single step of a deployment, in execution order
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DeployAction {
+    /// Create a directory on the VM
+    CreateDir { path: String },
+    /// Copy a local file to the VM
+    Transfer { local: PathBuf, remote: String },
+    /// Build the project on the VM
+    Build { command: String },
+    /// Install the portal into the system
+    Install { command: String },
+    /// Check the deployment (read-only)
+    Verify { command: String },
+}
+
+impl DeployAction {
+    /// Human-readable one-line description
+    pub fn describe(&self) -> String {
+        match self {
+            Self::CreateDir { path } => format!("mkdir -p {}", path),
+            Self::Transfer { local, remote } => {
+                format!("copy {} -> {}", local.display(), remote)
+            },
+            Self::Build { command } => format!("build: {}", command),
+            Self::Install { command } => format!("install: {}", command),
+            Self::Verify { command } => format!("verify: {}", command),
+        }
+    }
+}
+
+/// Everything a deployment will do to a VM
+#[derive(Debug, Clone, Serialize)]
+pub struct DeployPlan {
+    pub target: String,
+    pub ip: String,
+    pub username: String,
+    pub source_dir: String,
+    pub target_dir: String,
+    /// Whether files are copied over SFTP; otherwise rsync is suggested
+    pub sftp_available: bool,
+    pub actions: Vec<DeployAction>,
+}
+
+impl DeployPlan {
+    /// Resolve the actions for deploying `config` to `target`
+    pub fn resolve(
+        target: &VmInfo,
+        username: &str,
+        capabilities: &SshCapabilities,
+        config: &DeploymentConfig,
+        skip_build: bool,
+        skip_portal: bool,
+    ) -> Result<Self> {
+        let mut actions = Vec::new();
+
+        // 1. Transfer files (if SFTP available)
+        if capabilities.supports_sftp {
+            actions.push(DeployAction::CreateDir {
+                path: config.target_dir.clone(),
+            });
+            for (local, relative_path) in discover_files_to_transfer(&config.source_dir)? {
+                let remote = format!("{}/{}", config.target_dir, relative_path);
+                if let Some(parent) = Path::new(&remote).parent().and_then(Path::to_str) {
+                    let dir = DeployAction::CreateDir {
+                        path: parent.to_string(),
+                    };
+                    if !actions.contains(&dir) {
+                        actions.push(dir);
+                    }
+                }
+                actions.push(DeployAction::Transfer { local, remote });
+            }
+        }
+
+        // 2. Build on VM (if not skipped)
+        if !skip_build {
+            actions.push(DeployAction::Build {
+                command: format!("cd {} && {}", config.target_dir, config.build_command),
+            });
+        }
+
+        // 3. Deploy portal (if not skipped)
+        if !skip_portal {
+            actions.push(DeployAction::Install {
+                command: format!("cd {} && {}", config.target_dir, config.install_command),
+            });
+        }
+
+        // 4. Verify deployment
+        actions.push(DeployAction::Verify {
+            command: "ls -lh /usr/libexec/xdg-desktop-portal-cosmic".to_string(),
+        });
+
+        Ok(Self {
+            target: target.name.clone(),
+            ip: target.ip.clone(),
+            username: username.to_string(),
+            source_dir: config.source_dir.clone(),
+            target_dir: config.target_dir.clone(),
+            sftp_available: capabilities.supports_sftp,
+            actions,
+        })
+    }
+
+    /// Print the plan for review
+    pub fn log(&self) {
+        info!(
+            "Plan for {} ({}@{}): {} -> {}",
+            self.target, self.username, self.ip, self.source_dir, self.target_dir
+        );
+        if !self.sftp_available {
+            info!("SFTP not available, skipping file transfer");
+            // rsync needs IPv6 hosts bracketed to tell the path apart
+            let host = VmAddr::parse(&self.ip).map_or_else(|| self.ip.clone(), |a| a.url_host());
+            info!(
+                "  Suggestion: rsync -avz {} {}@{}:{}",
+                self.source_dir, self.username, host, self.target_dir
+            );
+        }
+        for (i, action) in self.actions.iter().enumerate() {
+            info!("  {}. {}", i + 1, action.describe());
+        }
+    }
+}
+
 /// Deploy ionChannel to target VM with capability-based approach
-pub async fn deploy_to_vm(target: &VmInfo, skip_build: bool, skip_portal: bool) -> Result<()> {
+///
+/// With `dry_run` the SSH connection is still made and probed, but the
+/// resolved plan is only logged and returned; nothing on the VM changes.
+pub async fn deploy_to_vm(
+    target: &VmInfo,
+    skip_build: bool,
+    skip_portal: bool,
+    dry_run: bool,
+) -> Result<DeployPlan> {
     let username = target.username.as_deref().unwrap_or("ubuntu");
 
     info!("Deploying to {} ({}@{})", target.name, username, target.ip);
@@ -49,67 +214,97 @@ pub async fn deploy_to_vm(target: &VmInfo, skip_build: bool, skip_portal: bool)
     let mut ssh = SshConnection::connect(&target.ip, username).await?;
 
     // Probe what the VM can do
-    let capabilities = ssh.capabilities()
-        .context("Failed to probe SSH capabilities")?;
+    let capabilities = ssh
+        .capabilities()
+        .context("Failed to probe SSH capabilities")?
+        .clone();
 
     info!("Remote capabilities: {:?}", capabilities);
 
     // Discover deployment configuration
     let config = DeploymentConfig::discover()?;
+    let plan = DeployPlan::resolve(
+        target,
+        username,
+        &capabilities,
+        &config,
+        skip_build,
+        skip_portal,
+    )?;
+
+    run_plan(&mut ssh, &plan, dry_run).await?;
+
+    Ok(plan)
+}
 
-    // 1. Transfer files (if SFTP available)
-    if capabilities.supports_sftp {
-        transfer_files_sftp(&mut ssh, &config).await?;
-    } else {
-        info!("SFTP not available, skipping file transfer");
-        info!("  Suggestion: rsync -avz {} {}@{}:{}", 
-            config.source_dir, username, target.ip, config.target_dir);
-    }
+/// Execute a resolved plan, or only log it when `dry_run` is set
+pub async fn run_plan(shell: &mut dyn RemoteShell, plan: &DeployPlan, dry_run: bool) -> Result<()> {
+    plan.log();
 
-    // 2. Build on VM (if not skipped)
-    if !skip_build {
-        build_on_vm(&mut ssh, &config).await?;
+    if dry_run {
+        info!("Dry run: no changes made");
+        return Ok(());
     }
 
-    // 3. Deploy portal (if not skipped)
-    if !skip_portal {
-        deploy_portal(&mut ssh, &config).await?;
+    for action in &plan.actions {
+        run_action(shell, action).await?;
     }
 
-    // 4. Verify deployment
-    verify_deployment(&mut ssh).await?;
-
     info!("✓ Deployment complete!");
 
     Ok(())
 }
 
-/// Transfer files via SFTP
-async fn transfer_files_sftp(ssh: &mut SshConnection, config: &DeploymentConfig) -> Result<()> {
-    info!("Transferring files via SFTP...");
-
-    // Create target directory
-    ssh.execute(&format!("mkdir -p {}", config.target_dir)).await?;
-
-    // Transfer key files (discovered from local)
-    let files_to_transfer = discover_files_to_transfer(&config.source_dir)?;
-
-    for (local_path, relative_path) in files_to_transfer {
-        let remote_path = format!("{}/{}", config.target_dir, relative_path);
-        
-        debug!("Transferring {} -> {}", local_path.display(), remote_path);
-        
-        // Create remote directory if needed
-        if let Some(parent) = Path::new(&remote_path).parent() {
-            if let Some(parent_str) = parent.to_str() {
-                ssh.execute(&format!("mkdir -p {}", parent_str)).await.ok();
+/// Execute one plan step
+async fn run_action(shell: &mut dyn RemoteShell, action: &DeployAction) -> Result<()> {
+    match action {
+        DeployAction::CreateDir { path } => {
+            // Parent directories may already exist
+            shell.execute(&format!("mkdir -p {}", path)).await.ok();
+        },
+        DeployAction::Transfer { local, remote } => {
+            debug!("Transferring {} -> {}", local.display(), remote);
+            shell.transfer_file(local, remote).await?;
+        },
+        DeployAction::Build { command } => {
+            info!("Building on remote VM...");
+            info!("Executing: {}", command);
+            let output = shell.execute(command).await?;
+
+            debug!("Build output:\n{}", output);
+
+            // Check if build succeeded
+            if output.contains("error") || output.contains("failed") {
+                anyhow::bail!("Build failed:\n{}", output);
             }
-        }
 
-        ssh.transfer_file(&local_path, &remote_path).await?;
-    }
+            info!("✓ Build complete");
+        },
+        DeployAction::Install { command } => {
+            info!("Deploying portal to system...");
+            info!("Executing: {}", command);
+            let output = shell.execute(command).await?;
 
-    info!("✓ File transfer complete");
+            debug!("Install output:\n{}", output);
+
+            info!("✓ Portal deployed");
+        },
+        DeployAction::Verify { command } => {
+            info!("Verifying deployment...");
+
+            // Check if portal binary exists
+            let output = shell.execute(command).await?;
+
+            if output.contains("No such file") {
+                anyhow::bail!("Portal binary not found after deployment");
+            }
+
+            debug!("Portal binary: {}", output.trim());
+
+            // Check if D-Bus service is registered (would need active session)
+            info!("✓ Deployment verified");
+        },
+    }
 
     Ok(())
 }
@@ -146,59 +341,103 @@ fn discover_files_to_transfer(source_dir: &str) -> Result<Vec<(std::path::PathBu
     Ok(files)
 }
 
-/// Build project on remote VM
-async fn build_on_vm(ssh: &mut SshConnection, config: &DeploymentConfig) -> Result<()> {
-    info!("Building on remote VM...");
-
-    // Change to project directory and build
-    let command = format!("cd {} && {}", config.target_dir, config.build_command);
-    
-    info!("Executing: {}", command);
-    let output = ssh.execute(&command).await?;
-    
-    debug!("Build output:\n{}", output);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Check if build succeeded
-    if output.contains("error") || output.contains("failed") {
-        anyhow::bail!("Build failed:\n{}", output);
+    /// Records every remote operation instead of performing it
+    #[derive(Default)]
+    struct RecordingShell {
+        calls: Vec<String>,
     }
 
-    info!("✓ Build complete");
-
-    Ok(())
-}
-
-/// Deploy portal to system
-async fn deploy_portal(ssh: &mut SshConnection, config: &DeploymentConfig) -> Result<()> {
-    info!("Deploying portal to system...");
+    #[async_trait]
+    impl RemoteShell for RecordingShell {
+        async fn execute(&mut self, command: &str) -> Result<String> {
+            self.calls.push(format!("exec {}", command));
+            Ok(String::new())
+        }
 
-    let command = format!("cd {} && {}", config.target_dir, config.install_command);
-    
-    info!("Executing: {}", command);
-    let output = ssh.execute(&command).await?;
-    
-    debug!("Install output:\n{}", output);
+        async fn transfer_file(&mut self, local_path: &Path, remote_path: &str) -> Result<()> {
+            self.calls
+                .push(format!("copy {} {}", local_path.display(), remote_path));
+            Ok(())
+        }
+    }
 
-    info!("✓ Portal deployed");
+    fn test_plan(source_dir: &Path) -> DeployPlan {
+        let target = VmInfo {
+            name: "test-vm".to_string(),
+            ip: "192.168.122.10".to_string(),
+            discovery_method: "manual".to_string(),
+            username: None,
+            services: Vec::new(),
+        };
+        let capabilities = SshCapabilities {
+            supports_sftp: true,
+            supports_exec: true,
+            supports_shell: true,
+            server_version: "OpenSSH".to_string(),
+        };
+        let config = DeploymentConfig {
+            source_dir: source_dir.to_str().unwrap().to_string(),
+            target_dir: "~/ionChannel".to_string(),
+            build_command: "cargo build --release --all".to_string(),
+            install_command: "sudo cp target/release/portal /usr/libexec/".to_string(),
+        };
+        DeployPlan::resolve(&target, "ubuntu", &capabilities, &config, false, false).unwrap()
+    }
 
-    Ok(())
-}
+    #[tokio::test]
+    async fn test_dry_run_performs_no_remote_operations() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::write(source.path().join("Cargo.toml"), "[workspace]").unwrap();
+        let plan = test_plan(source.path());
 
-/// Verify deployment succeeded
-async fn verify_deployment(ssh: &mut SshConnection) -> Result<()> {
-    info!("Verifying deployment...");
+        let mut shell = RecordingShell::default();
+        run_plan(&mut shell, &plan, true).await.unwrap();
 
-    // Check if portal binary exists
-    let output = ssh.execute("ls -lh /usr/libexec/xdg-desktop-portal-cosmic").await?;
-    
-    if output.contains("No such file") {
-        anyhow::bail!("Portal binary not found after deployment");
+        assert!(
+            shell.calls.is_empty(),
+            "dry run touched the VM: {:?}",
+            shell.calls
+        );
     }
 
-    debug!("Portal binary: {}", output.trim());
-
-    // Check if D-Bus service is registered (would need active session)
-    info!("✓ Deployment verified");
+    #[tokio::test]
+    async fn test_real_run_follows_plan_order() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::write(source.path().join("Cargo.toml"), "[workspace]").unwrap();
+        let plan = test_plan(source.path());
+
+        assert!(matches!(plan.actions[0], DeployAction::CreateDir { .. }));
+        assert!(matches!(plan.actions[1], DeployAction::Transfer { .. }));
+        assert!(matches!(plan.actions[2], DeployAction::Build { .. }));
+        assert!(matches!(plan.actions[3], DeployAction::Install { .. }));
+        assert!(matches!(plan.actions[4], DeployAction::Verify { .. }));
+
+        let mut shell = RecordingShell::default();
+        run_plan(&mut shell, &plan, false).await.unwrap();
+
+        assert_eq!(shell.calls.len(), plan.actions.len());
+        assert_eq!(shell.calls[0], "exec mkdir -p ~/ionChannel");
+        assert!(shell.calls[1].ends_with("~/ionChannel/Cargo.toml"));
+        assert!(shell.calls[2].contains("cargo build"));
+        assert!(shell.calls[3].contains("sudo cp"));
+        assert!(shell.calls[4].contains("ls -lh"));
+    }
 
-    Ok(())
+    #[test]
+    fn test_plan_serializes_to_json() {
+        let source = tempfile::tempdir().unwrap();
+        let plan = test_plan(source.path());
+
+        let json = serde_json::to_value(&plan).unwrap();
+        assert_eq!(json["target"], "test-vm");
+        assert_eq!(json["actions"][0]["action"], "create_dir");
+        assert_eq!(
+            json["actions"].as_array().unwrap().len(),
+            plan.actions.len()
+        );
+    }
 }
@@ -13,9 +13,10 @@ use futures::stream::{self, StreamExt};
 use mdns_sd::{ServiceDaemon, ServiceEvent};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::net::{IpAddr, Ipv4Addr};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::time::Duration;
-use surge_ping::{Client, Config, PingIdentifier, PingSequence};
+use surge_ping::{Client, Config, PingIdentifier, PingSequence, ICMP};
 use tracing::{debug, info};
 
 const MAX_PARALLEL_PINGS: usize = 50;
@@ -31,6 +32,110 @@ pub struct VmInfo {
     pub services: Vec<String>, // Services discovered on this VM
 }
 
+impl VmInfo {
+    /// Parsed address, if `ip` is an IP address rather than a hostname
+    pub fn addr(&self) -> Option<VmAddr> {
+        VmAddr::parse(&self.ip)
+    }
+}
+
+/// A VM's IP address, with the interface scope link-local IPv6 needs
+///
+/// Accepts `192.168.122.10`, `2001:db8::10`, `[2001:db8::10]` and scoped
+/// link-local addresses such as `fe80::1%virbr0`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VmAddr {
+    pub ip: IpAddr,
+    /// Interface (or numeric scope ID) for link-local IPv6
+    pub scope: Option<String>,
+}
+
+impl VmAddr {
+    pub fn new(ip: IpAddr) -> Self {
+        Self { ip, scope: None }
+    }
+
+    /// Parse an address, optionally bracketed and scoped
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let s = s
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .unwrap_or(s);
+
+        match s.split_once('%') {
+            Some((addr, scope)) if !scope.is_empty() => {
+                let ip = addr.parse::<Ipv6Addr>().ok()?;
+                Some(Self {
+                    ip: IpAddr::V6(ip),
+                    scope: Some(scope.to_string()),
+                })
+            }
+            Some(_) => None,
+            None => s.parse().ok().map(Self::new),
+        }
+    }
+
+    /// Whether this is an IPv6 link-local (`fe80::/10`) address
+    pub fn is_link_local(&self) -> bool {
+        match self.ip {
+            IpAddr::V4(ip) => ip.is_link_local(),
+            IpAddr::V6(ip) => ip.segments()[0] & 0xffc0 == 0xfe80,
+        }
+    }
+
+    /// Whether the address can be connected to as-is
+    ///
+    /// IPv6 link-local addresses are ambiguous without an interface scope.
+    pub fn is_routable(&self) -> bool {
+        !(self.ip.is_ipv6() && self.is_link_local() && self.scope.is_none())
+    }
+
+    /// Address family label for reporting
+    pub fn family(&self) -> &'static str {
+        match self.ip {
+            IpAddr::V4(_) => "ipv4",
+            IpAddr::V6(_) if self.is_link_local() => "ipv6-link-local",
+            IpAddr::V6(_) => "ipv6",
+        }
+    }
+
+    /// Host as passed to the resolver: `fe80::1%virbr0`, never bracketed
+    pub fn host(&self) -> String {
+        match &self.scope {
+            Some(scope) => format!("{}%{}", self.ip, scope),
+            None => self.ip.to_string(),
+        }
+    }
+
+    /// Host as written in URLs, `scp`/`rsync` targets and `host:port`
+    ///
+    /// IPv6 addresses are bracketed so their colons aren't taken for a
+    /// port separator.
+    pub fn url_host(&self) -> String {
+        match self.ip {
+            IpAddr::V4(_) => self.host(),
+            IpAddr::V6(_) => format!("[{}]", self.host()),
+        }
+    }
+
+    /// `host:port` connect string
+    pub fn authority(&self, port: u16) -> String {
+        format!("{}:{}", self.url_host(), port)
+    }
+}
+
+impl fmt::Display for VmAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.host())
+    }
+}
+
+/// Discovery method label with the address family, e.g. `mdns/ipv6`
+fn method_label(method: &str, addr: &VmAddr) -> String {
+    format!("{}/{}", method, addr.family())
+}
+
 /// Discovery method trait - each method has self-knowledge
 trait DiscoveryMethod {
     fn name(&self) -> &str;
@@ -119,19 +224,25 @@ impl VmDiscovery {
                                 
                                 // Extract IP addresses
                                 for addr in info.get_addresses() {
-                                    if let IpAddr::V4(ipv4) = addr {
-                                        let name = info.get_hostname().trim_end_matches('.').to_string();
-                                        
-                                        // For mDNS, check if name looks like a VM
-                                        if Self::is_vm_name(&name) {
-                                            vms.push(VmInfo {
-                                                name: name.clone(),
-                                                ip: ipv4.to_string(),
-                                                discovery_method: "mdns".to_string(),
-                                                username: None,
-                                                services: vec![service_type.to_string()],
-                                            });
-                                        }
+                                    let addr = VmAddr::new(*addr);
+                                    // mDNS doesn't say which interface a
+                                    // link-local address was seen on
+                                    if !addr.is_routable() {
+                                        debug!("Skipping unscoped link-local address {}", addr);
+                                        continue;
+                                    }
+
+                                    let name = info.get_hostname().trim_end_matches('.').to_string();
+
+                                    // For mDNS, check if name looks like a VM
+                                    if Self::is_vm_name(&name) {
+                                        vms.push(VmInfo {
+                                            name: name.clone(),
+                                            ip: addr.to_string(),
+                                            discovery_method: method_label("mdns", &addr),
+                                            username: None,
+                                            services: vec![service_type.to_string()],
+                                        });
                                     }
                                 }
                             }
@@ -181,10 +292,11 @@ impl VmDiscovery {
                 if let (Some(host), Some(hostname)) = (current_host.take(), current_hostname.take())
                 {
                     if Self::is_vm_name(&host) {
+                        let (ip, discovery_method) = Self::ssh_config_target(hostname);
                         vms.push(VmInfo {
                             name: host,
-                            ip: hostname,
-                            discovery_method: "ssh-config".to_string(),
+                            ip,
+                            discovery_method,
                             username: current_user.take(),
                             services: if let Some(port) = current_port.take() {
                                 vec![format!("ssh:{}", port)]
@@ -211,10 +323,11 @@ impl VmDiscovery {
         // Save last host
         if let (Some(host), Some(hostname)) = (current_host, current_hostname) {
             if Self::is_vm_name(&host) {
+                let (ip, discovery_method) = Self::ssh_config_target(hostname);
                 vms.push(VmInfo {
                     name: host,
-                    ip: hostname,
-                    discovery_method: "ssh-config".to_string(),
+                    ip,
+                    discovery_method,
                     username: current_user,
                     services: vec!["ssh".to_string()],
                 });
@@ -226,6 +339,16 @@ impl VmDiscovery {
         Ok(vms)
     }
 
+    /// Normalize an SSH config `HostName`, labelling IPs with their family
+    ///
+    /// Hostnames are kept as written and resolved at connect time.
+    fn ssh_config_target(hostname: String) -> (String, String) {
+        match VmAddr::parse(&hostname) {
+            Some(addr) => (addr.to_string(), method_label("ssh-config", &addr)),
+            None => (hostname, "ssh-config".to_string()),
+        }
+    }
+
     /// Parallel network scan for VMs
     async fn discover_network_scan(&self) -> Result<Vec<VmInfo>> {
         debug!("Starting parallel network scan...");
@@ -245,13 +368,22 @@ impl VmDiscovery {
 
         debug!("Scanning {} addresses in parallel", all_hosts.len());
 
-        // Parallel ping sweep
-        let client = Client::new(&Config::default())?;
-        
+        // Parallel ping sweep, ICMPv6 for IPv6 targets
+        let client_v4 = Client::new(&Config::default())?;
+        let client_v6 = if all_hosts.iter().any(IpAddr::is_ipv6) {
+            Some(Client::new(&Config::builder().kind(ICMP::V6).build())?)
+        } else {
+            None
+        };
+
         let live_hosts: Vec<IpAddr> = stream::iter(all_hosts)
             .map(|ip| {
-                let client_clone = client.clone();
+                let client_clone = match ip {
+                    IpAddr::V4(_) => Some(client_v4.clone()),
+                    IpAddr::V6(_) => client_v6.clone(),
+                };
                 async move {
+                    let client_clone = client_clone?;
                     // Use a random identifier for each ping
                     let id = (ip.to_string().as_bytes()[0] as u16) * 256;
                     let mut pinger = client_clone.pinger(ip, PingIdentifier(id)).await;
@@ -276,17 +408,14 @@ impl VmDiscovery {
         // Convert to VmInfo (would probe further to determine if VM)
         let vms: Vec<VmInfo> = live_hosts
             .into_iter()
-            .filter_map(|ip| {
-                if let IpAddr::V4(ipv4) = ip {
-                    Some(VmInfo {
-                        name: format!("vm-{}", ipv4.to_string().replace('.', "-")),
-                        ip: ipv4.to_string(),
-                        discovery_method: "network-scan".to_string(),
-                        username: None,
-                        services: vec!["ping".to_string()],
-                    })
-                } else {
-                    None
+            .map(|ip| {
+                let addr = VmAddr::new(ip);
+                VmInfo {
+                    name: format!("vm-{}", ip.to_string().replace(['.', ':'], "-")),
+                    ip: addr.to_string(),
+                    discovery_method: method_label("network-scan", &addr),
+                    username: None,
+                    services: vec!["ping".to_string()],
                 }
             })
             .collect();
@@ -336,8 +465,10 @@ impl VmDiscovery {
         let mut by_ip: std::collections::HashMap<String, VmInfo> = std::collections::HashMap::new();
 
         for vm in vms {
+            // Compare parsed addresses so `[2001:db8::1]` and `2001:db8::1` merge
+            let key = vm.addr().map_or_else(|| vm.ip.clone(), |addr| addr.to_string());
             by_ip
-                .entry(vm.ip.clone())
+                .entry(key)
                 .and_modify(|existing| {
                     // Merge services
                     for service in &vm.services {
@@ -398,6 +529,55 @@ mod tests {
         assert_eq!(ips.len(), 254); // 1-254
     }
 
+    #[test]
+    fn test_parse_ipv4() {
+        let addr = VmAddr::parse("192.168.122.10").unwrap();
+        assert_eq!(addr.ip, IpAddr::V4(Ipv4Addr::new(192, 168, 122, 10)));
+        assert_eq!(addr.family(), "ipv4");
+        assert_eq!(addr.authority(22), "192.168.122.10:22");
+    }
+
+    #[test]
+    fn test_parse_global_ipv6() {
+        let addr = VmAddr::parse("2001:db8::10").unwrap();
+        assert_eq!(addr.family(), "ipv6");
+        assert!(addr.is_routable());
+        assert_eq!(addr.host(), "2001:db8::10");
+        assert_eq!(addr.authority(22), "[2001:db8::10]:22");
+        assert_eq!(VmAddr::parse("[2001:db8::10]"), Some(addr));
+    }
+
+    #[test]
+    fn test_parse_scoped_link_local() {
+        let addr = VmAddr::parse("fe80::5054:ff:fe12:3456%virbr0").unwrap();
+        assert_eq!(addr.family(), "ipv6-link-local");
+        assert_eq!(addr.scope.as_deref(), Some("virbr0"));
+        assert!(addr.is_routable());
+        assert_eq!(addr.host(), "fe80::5054:ff:fe12:3456%virbr0");
+        assert_eq!(addr.authority(2222), "[fe80::5054:ff:fe12:3456%virbr0]:2222");
+
+        let unscoped = VmAddr::parse("fe80::1").unwrap();
+        assert!(!unscoped.is_routable());
+    }
+
+    #[test]
+    fn test_parse_rejects_hostnames() {
+        assert_eq!(VmAddr::parse("pop-os-vm.local"), None);
+        assert_eq!(VmAddr::parse("192.168.1.1%eth0"), None);
+        assert_eq!(VmAddr::parse("fe80::1%"), None);
+    }
+
+    #[test]
+    fn test_ssh_config_target_labels_family() {
+        let (ip, method) = VmDiscovery::ssh_config_target("[2001:db8::20]".to_string());
+        assert_eq!(ip, "2001:db8::20");
+        assert_eq!(method, "ssh-config/ipv6");
+
+        let (ip, method) = VmDiscovery::ssh_config_target("cosmic-vm.lan".to_string());
+        assert_eq!(ip, "cosmic-vm.lan");
+        assert_eq!(method, "ssh-config");
+    }
+
     #[test]
     fn test_deduplication() {
         let vms = vec![
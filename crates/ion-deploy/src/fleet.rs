@@ -0,0 +1,280 @@
+//! Parallel deployment to several VMs
+//!
+//! Each host is deployed independently, on its own SSH connection, with at
+//! most `jobs` deployments in flight. A failing host never aborts the
+//! others; every outcome is collected into a [`FleetReport`].
+
+use crate::deploy::{self, DeployPlan};
+use crate::discovery::VmInfo;
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::future::Future;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Deployments run at once unless `--jobs` says otherwise
+pub const DEFAULT_JOBS: usize = 4;
+
+/// Delay before the first retry; doubles with each further attempt
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// How a fleet deployment is scheduled
+#[derive(Debug, Clone)]
+pub struct FleetOptions {
+    /// Maximum concurrent deployments (at least one)
+    pub jobs: usize,
+    /// Extra attempts per host after a failure
+    pub retries: u32,
+    /// Delay before the first retry
+    pub retry_backoff: Duration,
+}
+
+impl Default for FleetOptions {
+    fn default() -> Self {
+        Self {
+            jobs: DEFAULT_JOBS,
+            retries: 0,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+        }
+    }
+}
+
+/// Result of deploying to one host
+#[derive(Debug, Clone, Serialize)]
+pub struct HostOutcome {
+    pub target: String,
+    pub ip: String,
+    /// Attempts made, including the successful one
+    pub attempts: u32,
+    /// Error of the last attempt, if every attempt failed
+    pub error: Option<String>,
+    /// Plan that was executed, if the deployment succeeded
+    pub plan: Option<DeployPlan>,
+}
+
+impl HostOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Per-host outcomes of a fleet deployment, in target order
+#[derive(Debug, Clone, Serialize)]
+pub struct FleetReport {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub hosts: Vec<HostOutcome>,
+}
+
+impl FleetReport {
+    fn new(hosts: Vec<HostOutcome>) -> Self {
+        let succeeded = hosts.iter().filter(|h| h.succeeded()).count();
+        Self {
+            succeeded,
+            failed: hosts.len() - succeeded,
+            hosts,
+        }
+    }
+
+    /// Whether every host was deployed
+    pub fn all_succeeded(&self) -> bool {
+        self.failed == 0
+    }
+
+    /// Hosts whose deployment failed
+    pub fn failures(&self) -> impl Iterator<Item = &HostOutcome> {
+        self.hosts.iter().filter(|h| !h.succeeded())
+    }
+}
+
+/// Deploy ionChannel to every target with [`deploy::deploy_to_vm`]
+pub async fn deploy_all(
+    targets: Vec<VmInfo>,
+    options: &FleetOptions,
+    skip_build: bool,
+    skip_portal: bool,
+    dry_run: bool,
+) -> FleetReport {
+    deploy_with(targets, options, |target| async move {
+        deploy::deploy_to_vm(&target, skip_build, skip_portal, dry_run).await
+    })
+    .await
+}
+
+/// Run `deploy` for every target with bounded concurrency
+///
+/// Outcomes are reported in the order of `targets`, whatever order the
+/// deployments finish in.
+pub async fn deploy_with<F, Fut>(
+    targets: Vec<VmInfo>,
+    options: &FleetOptions,
+    deploy: F,
+) -> FleetReport
+where
+    F: Fn(VmInfo) -> Fut,
+    Fut: Future<Output = Result<DeployPlan>>,
+{
+    let jobs = options.jobs.max(1);
+    info!("Deploying to {} host(s), {} at a time", targets.len(), jobs);
+
+    let hosts: Vec<HostOutcome> = stream::iter(targets)
+        .map(|target| deploy_host(target, options, &deploy))
+        .buffered(jobs)
+        .collect()
+        .await;
+
+    FleetReport::new(hosts)
+}
+
+/// Deploy to one host, retrying with exponential backoff
+async fn deploy_host<F, Fut>(target: VmInfo, options: &FleetOptions, deploy: &F) -> HostOutcome
+where
+    F: Fn(VmInfo) -> Fut,
+    Fut: Future<Output = Result<DeployPlan>>,
+{
+    let mut backoff = options.retry_backoff;
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match deploy(target.clone()).await {
+            Ok(plan) => {
+                info!("✓ {} ({}) deployed", target.name, target.ip);
+                return HostOutcome {
+                    target: target.name,
+                    ip: target.ip,
+                    attempts,
+                    error: None,
+                    plan: Some(plan),
+                };
+            },
+            Err(e) if attempts <= options.retries => {
+                warn!(
+                    "{} ({}) failed on attempt {}: {:#}; retrying in {:?}",
+                    target.name, target.ip, attempts, e, backoff
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            },
+            Err(e) => {
+                warn!("✗ {} ({}) failed: {:#}", target.name, target.ip, e);
+                return HostOutcome {
+                    target: target.name,
+                    ip: target.ip,
+                    attempts,
+                    error: Some(format!("{:#}", e)),
+                    plan: None,
+                };
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    fn target(name: &str) -> VmInfo {
+        VmInfo {
+            name: name.to_string(),
+            ip: format!("192.0.2.{}", name.trim_start_matches("vm")),
+            discovery_method: "manual".to_string(),
+            username: None,
+            services: Vec::new(),
+        }
+    }
+
+    fn plan(target: &VmInfo) -> DeployPlan {
+        DeployPlan {
+            target: target.name.clone(),
+            ip: target.ip.clone(),
+            username: "ubuntu".to_string(),
+            source_dir: ".".to_string(),
+            target_dir: "~/ionChannel".to_string(),
+            sftp_available: true,
+            actions: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bounded_concurrency_and_isolated_failures() {
+        let targets: Vec<VmInfo> = (1..=6).map(|i| target(&format!("vm{}", i))).collect();
+        let options = FleetOptions {
+            jobs: 2,
+            ..FleetOptions::default()
+        };
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        let report = deploy_with(targets, &options, |target| {
+            let (in_flight, peak) = (&in_flight, &peak);
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                if target.name == "vm2" || target.name == "vm5" {
+                    anyhow::bail!("ssh: connection refused");
+                }
+                Ok(plan(&target))
+            }
+        })
+        .await;
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(report.succeeded, 4);
+        assert_eq!(report.failed, 2);
+        assert!(!report.all_succeeded());
+        let names: Vec<&str> = report.hosts.iter().map(|h| h.target.as_str()).collect();
+        assert_eq!(names, ["vm1", "vm2", "vm3", "vm4", "vm5", "vm6"]);
+        let failed: Vec<&str> = report.failures().map(|h| h.target.as_str()).collect();
+        assert_eq!(failed, ["vm2", "vm5"]);
+        assert!(report.hosts[1]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("connection refused"));
+        assert!(report.hosts[2].plan.is_some());
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["failed"], 2);
+        assert_eq!(json["hosts"][4]["target"], "vm5");
+    }
+
+    #[tokio::test]
+    async fn test_retries_each_host_independently() {
+        let options = FleetOptions {
+            jobs: 2,
+            retries: 2,
+            retry_backoff: Duration::ZERO,
+        };
+        let calls = Mutex::new(HashMap::<String, u32>::new());
+
+        let report = deploy_with(vec![target("vm1"), target("vm2")], &options, |target| {
+            let attempt = {
+                let mut calls = calls.lock().unwrap();
+                let count = calls.entry(target.name.clone()).or_default();
+                *count += 1;
+                *count
+            };
+            async move {
+                // vm1 recovers on its second attempt; vm2 never does
+                if target.name == "vm1" && attempt >= 2 {
+                    Ok(plan(&target))
+                } else {
+                    anyhow::bail!("attempt {} failed", attempt)
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(report.hosts[0].attempts, 2);
+        assert!(report.hosts[0].succeeded());
+        assert_eq!(report.hosts[1].attempts, 3);
+        assert_eq!(report.hosts[1].error.as_deref(), Some("attempt 3 failed"));
+        assert_eq!(report.succeeded, 1);
+    }
+}
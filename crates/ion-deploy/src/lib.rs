@@ -8,5 +8,6 @@ pub mod cloud_init;
 pub mod config;
 pub mod deploy;
 pub mod discovery;
+pub mod fleet;
 pub mod ssh;
 pub mod ssh_keys;
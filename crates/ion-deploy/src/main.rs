@@ -3,7 +3,7 @@
 //! Pure Rust tool for VM discovery, deployment, and testing.
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use console::style;
 use tracing::Level;
 
@@ -12,6 +12,7 @@ mod cloud_init;
 mod config;
 mod deploy;
 mod discovery;
+mod fleet;
 mod ssh;
 mod ssh_keys;
 
@@ -44,12 +45,28 @@ enum Commands {
         force: bool,
     },
 
-    /// Deploy ionChannel to a VM
+    /// Deploy ionChannel to a VM, or to several in parallel
     Deploy {
         /// VM IP address (auto-discovered if not provided)
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with_all = ["all", "targets"])]
         ip: Option<String>,
 
+        /// Deploy to every discovered VM
+        #[arg(long, conflicts_with = "targets")]
+        all: bool,
+
+        /// Deploy to these VM addresses (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        targets: Vec<String>,
+
+        /// Maximum deployments running at once
+        #[arg(short, long, default_value_t = fleet::DEFAULT_JOBS)]
+        jobs: usize,
+
+        /// Retries per host after a failed deployment
+        #[arg(long, default_value_t = 0)]
+        retries: u32,
+
         /// SSH username
         #[arg(short, long)]
         user: Option<String>,
@@ -61,6 +78,14 @@ enum Commands {
         /// Skip portal deployment
         #[arg(long)]
         skip_portal: bool,
+
+        /// Show what would be deployed without changing the VM
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output format for the deployment plan
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
     },
 
     /// Test connection to VM
@@ -88,6 +113,27 @@ enum Commands {
     },
 }
 
+/// How to print results
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// Prints a progress line: to stdout, or to stderr in JSON mode so that
+/// stdout carries only the JSON document.
+macro_rules! progress {
+    ($format:expr) => {
+        progress!($format, "")
+    };
+    ($format:expr, $($arg:tt)*) => {
+        match $format {
+            OutputFormat::Json => eprintln!($($arg)*),
+            OutputFormat::Text => println!($($arg)*),
+        }
+    };
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -99,29 +145,41 @@ async fn main() -> Result<()> {
         Level::INFO
     };
 
-    tracing_subscriber::fmt()
+    // In JSON mode only the result goes to stdout
+    let format = match &cli.command {
+        Commands::Deploy { format, .. } => *format,
+        _ => OutputFormat::Text,
+    };
+
+    let subscriber = tracing_subscriber::fmt()
         .with_max_level(log_level)
-        .with_target(false)
-        .init();
+        .with_target(false);
+    match format {
+        OutputFormat::Json => subscriber.with_writer(std::io::stderr).init(),
+        OutputFormat::Text => subscriber.init(),
+    }
 
     // Load or create config
     let config_path = cli.config.as_deref();
     let mut config = config::Config::load(config_path)?;
 
-    println!(
+    progress!(
+        format,
         "{}",
         style("════════════════════════════════════════════════════════════════").cyan()
     );
-    println!(
+    progress!(
+        format,
         "  {} {}",
         style("🤖").cyan(),
         style("ionChannel Deployment Tool").bold().cyan()
     );
-    println!(
+    progress!(
+        format,
         "{}",
         style("════════════════════════════════════════════════════════════════").cyan()
     );
-    println!();
+    progress!(format);
 
     match cli.command {
         Commands::Discover { force } => {
@@ -130,15 +188,37 @@ async fn main() -> Result<()> {
 
         Commands::Deploy {
             ip,
+            all,
+            targets,
+            jobs,
+            retries,
             user,
             skip_build,
             skip_portal,
+            dry_run,
+            format,
         } => {
-            deploy_to_vm(&mut config, ip, user, skip_build, skip_portal).await?;
+            let options = DeployOptions {
+                skip_build,
+                skip_portal,
+                dry_run,
+                format,
+            };
+            if all || !targets.is_empty() {
+                let fleet_options = fleet::FleetOptions {
+                    jobs,
+                    retries,
+                    ..fleet::FleetOptions::default()
+                };
+                let targets = fleet_targets(&config, all, targets, user)?;
+                deploy_to_fleet(targets, &fleet_options, options).await?;
+            } else {
+                deploy_to_vm(&mut config, ip, user, options).await?;
+            }
         },
 
         Commands::Test { ip, user } => {
-            test_vm_connection(&config, &ip, user.as_deref()).await?;
+            test_vm_connection(&config, &ip, user.as_deref(), format).await?;
         },
 
         Commands::Config { reset } => {
@@ -151,7 +231,7 @@ async fn main() -> Result<()> {
         },
 
         Commands::Info { ip } => {
-            get_vm_info(&config, ip.as_deref()).await?;
+            get_vm_info(&config, ip.as_deref(), format).await?;
         },
     }
 
@@ -200,15 +280,26 @@ async fn discover_vms(config: &mut config::Config, _force: bool) -> Result<()> {
     Ok(())
 }
 
+/// Flags controlling a deployment
+struct DeployOptions {
+    skip_build: bool,
+    skip_portal: bool,
+    dry_run: bool,
+    format: OutputFormat,
+}
+
 async fn deploy_to_vm(
     config: &mut config::Config,
     ip: Option<String>,
     user: Option<String>,
-    skip_build: bool,
-    skip_portal: bool,
+    options: DeployOptions,
 ) -> Result<()> {
-    println!("{} Starting deployment...", style("[Phase 1/4]").blue());
-    println!();
+    progress!(
+        options.format,
+        "{} Starting deployment...",
+        style("[Phase 1/4]").blue()
+    );
+    progress!(options.format);
 
     // Get target VM
     let target = if let Some(ip_addr) = ip {
@@ -220,7 +311,8 @@ async fn deploy_to_vm(
             services: Vec::new(),
         }
     } else if let Some(last_vm) = &config.last_vm {
-        println!(
+        progress!(
+            options.format,
             "Using last VM: {} ({})",
             style(&last_vm.name).bold(),
             style(&last_vm.ip).dim()
@@ -233,38 +325,89 @@ async fn deploy_to_vm(
         anyhow::bail!("No VM specified. Run 'ion-deploy discover' first or use --ip");
     };
 
-    println!("Target: {} ({})", style(&target.name).bold(), target.ip);
-    println!();
+    progress!(
+        options.format,
+        "Target: {} ({})",
+        style(&target.name).bold(),
+        target.ip
+    );
+    progress!(options.format);
 
     // Test connection first
-    println!("{} Testing connection...", style("[Phase 2/4]").blue());
-    test_vm_connection(config, &target.ip, target.username.as_deref()).await?;
-    println!();
+    progress!(
+        options.format,
+        "{} Testing connection...",
+        style("[Phase 2/4]").blue()
+    );
+    test_vm_connection(
+        config,
+        &target.ip,
+        target.username.as_deref(),
+        options.format,
+    )
+    .await?;
+    progress!(options.format);
 
     // Deploy
-    println!("{} Deploying...", style("[Phase 3/4]").blue());
-    deploy::deploy_to_vm(&target, skip_build, skip_portal).await?;
-    println!();
+    if options.dry_run {
+        progress!(
+            options.format,
+            "{} Deploying (dry run)...",
+            style("[Phase 3/4]").blue()
+        );
+    } else {
+        progress!(
+            options.format,
+            "{} Deploying...",
+            style("[Phase 3/4]").blue()
+        );
+    }
+    let plan = deploy::deploy_to_vm(
+        &target,
+        options.skip_build,
+        options.skip_portal,
+        options.dry_run,
+    )
+    .await?;
+    print_plan(&plan, options.format)?;
+    progress!(options.format);
 
     // Get RustDesk info
-    println!("{} Getting connection info...", style("[Phase 4/4]").blue());
-    get_vm_info(config, Some(&target.ip)).await?;
+    progress!(
+        options.format,
+        "{} Getting connection info...",
+        style("[Phase 4/4]").blue()
+    );
+    get_vm_info(config, Some(&target.ip), options.format).await?;
+
+    if options.dry_run {
+        progress!(options.format);
+        progress!(
+            options.format,
+            "{} Dry run complete, no changes made",
+            style("✓").green()
+        );
+        return Ok(());
+    }
 
     // Save as last used
     config.last_vm = Some(target);
     config.save()?;
 
-    println!();
-    println!(
+    progress!(options.format);
+    progress!(
+        options.format,
         "{}",
         style("════════════════════════════════════════════════════════════════").green()
     );
-    println!(
+    progress!(
+        options.format,
         " {} {}",
         style("🎉").bold(),
         style("DEPLOYMENT COMPLETE!").bold().green()
     );
-    println!(
+    progress!(
+        options.format,
         "{}",
         style("════════════════════════════════════════════════════════════════").green()
     );
@@ -272,18 +415,126 @@ async fn deploy_to_vm(
     Ok(())
 }
 
-async fn test_vm_connection(_config: &config::Config, ip: &str, user: Option<&str>) -> Result<()> {
+/// VMs selected by `--all` or `--targets`
+fn fleet_targets(
+    config: &config::Config,
+    all: bool,
+    addresses: Vec<String>,
+    user: Option<String>,
+) -> Result<Vec<discovery::VmInfo>> {
+    let mut targets = if all {
+        config.discovered_vms.clone()
+    } else {
+        addresses
+            .into_iter()
+            .map(|ip| discovery::VmInfo {
+                name: ip.clone(),
+                ip,
+                discovery_method: "manual".to_string(),
+                username: None,
+                services: Vec::new(),
+            })
+            .collect()
+    };
+    if targets.is_empty() {
+        anyhow::bail!("No VMs to deploy to. Run 'ion-deploy discover' first or use --targets");
+    }
+    if let Some(user) = user {
+        for target in &mut targets {
+            target.username = Some(user.clone());
+        }
+    }
+    Ok(targets)
+}
+
+async fn deploy_to_fleet(
+    targets: Vec<discovery::VmInfo>,
+    fleet_options: &fleet::FleetOptions,
+    options: DeployOptions,
+) -> Result<()> {
+    progress!(
+        options.format,
+        "{} Deploying to {} VM(s), {} at a time...",
+        style("[Fleet]").blue(),
+        targets.len(),
+        fleet_options.jobs.max(1)
+    );
+    progress!(options.format);
+
+    let report = fleet::deploy_all(
+        targets,
+        fleet_options,
+        options.skip_build,
+        options.skip_portal,
+        options.dry_run,
+    )
+    .await;
+
+    match options.format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Text => {
+            for host in &report.hosts {
+                match &host.error {
+                    None => println!(
+                        "  {} {} ({})",
+                        style("✓").green(),
+                        style(&host.target).bold(),
+                        style(&host.ip).dim()
+                    ),
+                    Some(error) => println!(
+                        "  {} {} ({}): {}",
+                        style("✗").red(),
+                        style(&host.target).bold(),
+                        style(&host.ip).dim(),
+                        error
+                    ),
+                }
+            }
+            println!();
+            println!(
+                "{} succeeded, {} failed",
+                style(report.succeeded).green(),
+                style(report.failed).red()
+            );
+        },
+    }
+
+    if !report.all_succeeded() {
+        let failed: Vec<&str> = report.failures().map(|h| h.target.as_str()).collect();
+        anyhow::bail!("Deployment failed on: {}", failed.join(", "));
+    }
+    Ok(())
+}
+
+fn print_plan(plan: &deploy::DeployPlan, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(plan)?),
+        OutputFormat::Text => {
+            for (i, action) in plan.actions.iter().enumerate() {
+                println!("  {}. {}", style(i + 1).cyan(), action.describe());
+            }
+        },
+    }
+    Ok(())
+}
+
+async fn test_vm_connection(
+    _config: &config::Config,
+    ip: &str,
+    user: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
     let default_user = std::env::var("USER")
         .or_else(|_| std::env::var("USERNAME"))
         .unwrap_or_else(|_| "ubuntu".to_string());
     let username = user.unwrap_or(&default_user);
 
-    println!("Testing connection to {}@{}...", username, ip);
+    progress!(format, "Testing connection to {}@{}...", username, ip);
 
     let can_connect = ssh::test_connection(ip, username).await?;
 
     if can_connect {
-        println!("{} Connection successful", style("✓").green());
+        progress!(format, "{} Connection successful", style("✓").green());
     } else {
         anyhow::bail!("Cannot connect to {}@{}", username, ip);
     }
@@ -291,17 +542,21 @@ async fn test_vm_connection(_config: &config::Config, ip: &str, user: Option<&st
     Ok(())
 }
 
-async fn get_vm_info(_config: &config::Config, ip: Option<&str>) -> Result<()> {
+async fn get_vm_info(
+    _config: &config::Config,
+    ip: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
     let target_ip = ip.ok_or_else(|| anyhow::anyhow!("No VM IP specified"))?;
 
-    println!("Getting RustDesk info from {}...", target_ip);
+    progress!(format, "Getting RustDesk info from {}...", target_ip);
 
     // This would query the VM for RustDesk ID
     // For now, placeholder
-    println!();
-    println!("{}", style("Connection Info:").bold());
-    println!("  VM IP:        {}", style(target_ip).cyan());
-    println!("  RustDesk ID:  {}", style("[Query VM]").dim());
+    progress!(format);
+    progress!(format, "{}", style("Connection Info:").bold());
+    progress!(format, "  VM IP:        {}", style(target_ip).cyan());
+    progress!(format, "  RustDesk ID:  {}", style("[Query VM]").dim());
 
     Ok(())
 }
@@ -15,6 +15,8 @@ use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
+use crate::discovery::VmAddr;
+
 /// SSH connection capabilities discovered at runtime
 #[derive(Debug, Clone)]
 pub struct SshCapabilities {
@@ -54,14 +56,14 @@ impl SshConnection {
         // Discover SSH port (try standard, then probe)
         let port = Self::discover_ssh_port(ip).await?;
         
-        debug!("Connecting to {}:{} as {}", ip, port, username);
+        debug!("Connecting to {} as {}", socket_authority(ip, port), username);
 
         let config = client::Config::default();
         let sh = Client {};
 
-        // Try SSH agent keys first, then look for default keys
-        let addr = format!("{}:{}", ip, port);
-        let socket_addr = addr
+        // Resolve via (host, port) so IPv6 needs no brackets and the
+        // resolver handles link-local scopes like `fe80::1%virbr0`
+        let socket_addr = (resolver_host(ip).as_str(), port)
             .to_socket_addrs()?
             .next()
             .context("Failed to resolve address")?;
@@ -118,10 +120,10 @@ impl SshConnection {
 
     /// Test if TCP port is open
     async fn test_tcp_port(ip: &str, port: u16) -> Result<()> {
-        let addr = format!("{}:{}", ip, port);
+        let host = resolver_host(ip);
         tokio::time::timeout(
             Duration::from_secs(2),
-            tokio::net::TcpStream::connect(&addr),
+            tokio::net::TcpStream::connect((host.as_str(), port)),
         )
         .await
         .context("Connection timeout")?
@@ -318,6 +320,22 @@ impl Drop for SshConnection {
     }
 }
 
+/// `host:port` connect string, bracketing IPv6 addresses
+///
+/// `2001:db8::1` becomes `[2001:db8::1]:22`; hostnames and IPv4 addresses
+/// are joined as-is.
+pub fn socket_authority(host: &str, port: u16) -> String {
+    match VmAddr::parse(host) {
+        Some(addr) => addr.authority(port),
+        None => format!("{}:{}", host, port),
+    }
+}
+
+/// Host as the resolver expects it: unbracketed, scope kept
+fn resolver_host(host: &str) -> String {
+    VmAddr::parse(host).map_or_else(|| host.to_string(), |addr| addr.host())
+}
+
 /// Test if SSH connection is possible (high-level API)
 pub async fn test_connection(ip: &str, username: &str) -> Result<bool> {
     debug!("Testing SSH connection to {}@{}", username, ip);
@@ -365,4 +383,34 @@ mod tests {
     async fn test_capability_probing() {
         // Test that we correctly probe capabilities
     }
+
+    #[test]
+    fn test_socket_authority() {
+        assert_eq!(socket_authority("192.168.122.10", 22), "192.168.122.10:22");
+        assert_eq!(socket_authority("2001:db8::10", 22), "[2001:db8::10]:22");
+        assert_eq!(socket_authority("[2001:db8::10]", 2222), "[2001:db8::10]:2222");
+        assert_eq!(
+            socket_authority("fe80::5054:ff:fe12:3456%virbr0", 22),
+            "[fe80::5054:ff:fe12:3456%virbr0]:22"
+        );
+        assert_eq!(socket_authority("cosmic-vm.local", 22), "cosmic-vm.local:22");
+    }
+
+    #[test]
+    fn test_resolver_host_strips_brackets() {
+        assert_eq!(resolver_host("[2001:db8::10]"), "2001:db8::10");
+        assert_eq!(resolver_host("fe80::1%virbr0"), "fe80::1%virbr0");
+        assert_eq!(resolver_host("cosmic-vm.local"), "cosmic-vm.local");
+    }
+
+    #[test]
+    fn test_resolve_ipv6_literal() {
+        let addr = (resolver_host("[::1]").as_str(), 22)
+            .to_socket_addrs()
+            .unwrap()
+            .next()
+            .unwrap();
+        assert!(addr.is_ipv6());
+        assert_eq!(addr.port(), 22);
+    }
 }
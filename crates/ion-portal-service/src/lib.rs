@@ -0,0 +1,1221 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! The ionChannel RemoteDesktop portal service, as a library.
+//!
+//! [`run_service`] assembles the service from a [`ServiceConfig`]: backend
+//! selection, the session manager, the portal and health objects on D-Bus,
+//! and event forwarding to the compositor. The `xdg-desktop-portal-cosmic`
+//! binary runs it with [`ServiceConfig::from_env`]; tests inject a mock
+//! backend, transport and bus connection instead.
+//!
+//! ## Shutdown
+//!
+//! [`ServiceHandle::shutdown`] stops accepting sessions, closes active
+//! ones (releasing held keys and buttons), removes the portal object and
+//! releases its D-Bus name. Session cleanup is bounded by
+//! [`ServiceConfig::shutdown_timeout`] so a wedged close cannot hang the
+//! process.
+//!
+//! ## Health
+//!
+//! `org.ionchannel.Health.HealthCheck` on the portal object reports
+//! whether the service is ready, degraded (input only) or unhealthy. When
+//! [`HEALTH_SOCKET_ENV`] names a path, `GET /health` on that Unix socket
+//! returns the same report as JSON, with status 503 while unhealthy.
+//!
+//! ## Display server changes
+//!
+//! When the service selected its own backend, the display server is
+//! re-detected every [`REDETECT_INTERVAL`]. The environment is fixed at
+//! startup, so Wayland is detected by its socket under `XDG_RUNTIME_DIR`
+//! existing rather than by `WAYLAND_DISPLAY` being set. Once a different
+//! one has been seen [`REDETECT_SETTLE`] times in a row, backend selection
+//! runs again and the portal switches over; started sessions keep running
+//! and get `StreamsChanged` with their new capture streams. An injected
+//! backend is kept as is.
+//!
+//! ## Session owners
+//!
+//! Each session is owned by the bus connection that created it. When
+//! that connection leaves the bus its sessions are closed. If it owned a
+//! well-known name that another connection took over, its sessions move
+//! to the new owner first; if it released the name they are left
+//! ownerless, and are reaped every [`ORPHAN_REAP_INTERVAL`] once their
+//! grace period is over.
+//!
+//! With an idle timeout set in [`ServiceConfig::sessions`], sessions that
+//! sent no input for that long are closed too, checked every
+//! [`IDLE_REAP_INTERVAL`] or every timeout if that is shorter.
+//!
+//! ## Compositor
+//!
+//! Input from sessions is forwarded to the compositor through
+//! [`ServiceConfig::transport`], by default over the Unix socket named by
+//! [`COMPOSITOR_SOCKET_ENV`]; see `ion_portal::transport` for the framing.
+//! Without a transport the portal injects them through its backend, on
+//! the seat each session was created for. Pointer locks sessions take go
+//! the same way.
+
+use std::ffi::OsString;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use zbus::{Connection, InterfaceRef};
+
+use ion_backend_cosmic::CosmicBackend;
+use ion_backend_wayland::WaylandBackend;
+use ion_core::backend::{
+    BackendCapabilities, BackendFactory, CompositorBackend, DisplayRedetector, DisplayServerType,
+};
+use ion_core::event::StampedEvent;
+use ion_core::session::SessionId;
+use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
+use ion_portal::transport::{CompositorTransport, UnixSocketTransport};
+use ion_portal::{HealthMonitor, HealthStatus, RemoteDesktopPortal};
+
+/// D-Bus name owned by the portal service.
+pub const BUS_NAME: &str = "org.freedesktop.impl.portal.desktop.cosmic";
+
+/// Object path the portal is registered at.
+pub const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+/// Default maximum time allowed for closing sessions during shutdown.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Environment variable naming the Unix socket to serve `/health` on.
+pub const HEALTH_SOCKET_ENV: &str = "ION_HEALTH_SOCKET";
+
+/// Longest request line the health socket reads, in bytes.
+const MAX_REQUEST_LINE: u64 = 1024;
+
+/// Environment variable naming the compositor service's input socket.
+pub const COMPOSITOR_SOCKET_ENV: &str = "ION_COMPOSITOR_SOCKET";
+
+/// How often sessions without an owner are checked for reaping.
+pub const ORPHAN_REAP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often sessions are checked for going idle, at most.
+pub const IDLE_REAP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the display server is re-detected.
+pub const REDETECT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Consecutive detections a display server change must last before the
+/// backend is switched.
+pub const REDETECT_SETTLE: u32 = 3;
+
+/// Wayland socket name used when `WAYLAND_DISPLAY` is unset, as libwayland
+/// does.
+const DEFAULT_WAYLAND_DISPLAY: &str = "wayland-0";
+
+/// How the service is assembled.
+///
+/// Every collaborator left `None` is created the way the installed service
+/// creates it.
+pub struct ServiceConfig {
+    /// Backend for input and capture; selected for the current display
+    /// server when `None`.
+    pub backend: Option<Arc<dyn CompositorBackend>>,
+    /// Where session input and pointer locks are forwarded; the portal's
+    /// backend takes them when `None`.
+    pub transport: Option<Arc<dyn CompositorTransport>>,
+    /// Bus connection to serve on; the session bus when `None`.
+    pub connection: Option<Connection>,
+    /// D-Bus name to own.
+    pub bus_name: String,
+    /// Session manager settings.
+    pub sessions: SessionManagerConfig,
+    /// Unix socket to serve `/health` on, if any.
+    pub health_socket: Option<PathBuf>,
+    /// Maximum time allowed for closing sessions during shutdown.
+    pub shutdown_timeout: Duration,
+    /// Clamp absolute pointer motion outside its output instead of
+    /// rejecting it.
+    pub clamp_absolute: bool,
+}
+
+impl Default for ServiceConfig {
+    fn default() -> Self {
+        Self {
+            backend: None,
+            transport: None,
+            connection: None,
+            bus_name: BUS_NAME.to_string(),
+            sessions: SessionManagerConfig::default(),
+            health_socket: None,
+            shutdown_timeout: SHUTDOWN_TIMEOUT,
+            clamp_absolute: false,
+        }
+    }
+}
+
+impl std::fmt::Debug for ServiceConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceConfig")
+            .field("has_backend", &self.backend.is_some())
+            .field("transport", &self.transport.as_ref().map(|t| t.name()))
+            .field("has_connection", &self.connection.is_some())
+            .field("bus_name", &self.bus_name)
+            .field("sessions", &self.sessions)
+            .field("health_socket", &self.health_socket)
+            .field("shutdown_timeout", &self.shutdown_timeout)
+            .field("clamp_absolute", &self.clamp_absolute)
+            .finish()
+    }
+}
+
+impl ServiceConfig {
+    /// The installed service's configuration: health and compositor
+    /// sockets from [`HEALTH_SOCKET_ENV`] and [`COMPOSITOR_SOCKET_ENV`],
+    /// everything else defaulted.
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self {
+            transport: std::env::var_os(COMPOSITOR_SOCKET_ENV).map(|path| {
+                Arc::new(UnixSocketTransport::new(path)) as Arc<dyn CompositorTransport>
+            }),
+            health_socket: std::env::var_os(HEALTH_SOCKET_ENV).map(PathBuf::from),
+            ..Self::default()
+        }
+    }
+}
+
+/// A running service.
+///
+/// Dropping the handle leaves the service running; call
+/// [`Self::shutdown`] to stop it.
+pub struct ServiceHandle {
+    conn: Connection,
+    bus_name: String,
+    manager: SessionManager,
+    health: HealthMonitor,
+    health_socket: Option<PathBuf>,
+    shutdown_timeout: Duration,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for ServiceHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceHandle")
+            .field("bus_name", &self.bus_name)
+            .field("session_manager", &self.manager)
+            .field("health_socket", &self.health_socket)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ServiceHandle {
+    /// The connection the portal is served on.
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// The D-Bus name the service owns.
+    #[must_use]
+    pub fn bus_name(&self) -> &str {
+        &self.bus_name
+    }
+
+    /// The session manager behind the portal.
+    #[must_use]
+    pub fn session_manager(&self) -> &SessionManager {
+        &self.manager
+    }
+
+    /// The portal as served on [`Self::connection`].
+    ///
+    /// # Errors
+    ///
+    /// Fails once the service has shut down and removed the portal.
+    pub async fn portal(&self) -> Result<InterfaceRef<RemoteDesktopPortal>> {
+        Ok(self
+            .conn
+            .object_server()
+            .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+            .await?)
+    }
+
+    /// The health monitor served next to the portal.
+    #[must_use]
+    pub fn health(&self) -> &HealthMonitor {
+        &self.health
+    }
+
+    /// Runs the full shutdown sequence and stops the service's background
+    /// tasks.
+    ///
+    /// Event forwarding is left to drain, so releases of keys the closed
+    /// sessions still held reach the compositor.
+    pub async fn shutdown(self) -> Result<()> {
+        let result = shutdown(
+            &self.conn,
+            &self.bus_name,
+            &self.manager,
+            self.shutdown_timeout,
+        )
+        .await;
+        for task in &self.tasks {
+            task.abort();
+        }
+        if let Some(path) = self.health_socket {
+            let _ = std::fs::remove_file(path);
+        }
+        result
+    }
+}
+
+/// Answers one HTTP request on the health socket.
+///
+/// `GET /health` returns the [`HealthMonitor`] report as JSON; any other
+/// path is a 404, and a request line longer than [`MAX_REQUEST_LINE`] a
+/// 400.
+async fn answer_health<S>(stream: S, monitor: &HealthMonitor) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut stream = BufReader::new(stream);
+    let mut request_line = String::new();
+    (&mut stream)
+        .take(MAX_REQUEST_LINE)
+        .read_line(&mut request_line)
+        .await?;
+
+    let (status, body) = if !request_line.ends_with('\n') {
+        ("400 Bad Request", r#"{"error":"bad request"}"#.to_string())
+    } else if request_line.split_whitespace().nth(1) == Some("/health") {
+        let report = monitor.report().await;
+        let status = if report.status == HealthStatus::Unhealthy {
+            "503 Service Unavailable"
+        } else {
+            "200 OK"
+        };
+        (status, serde_json::to_string(&report)?)
+    } else {
+        ("404 Not Found", r#"{"error":"not found"}"#.to_string())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let stream = stream.get_mut();
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Serves the health endpoint until the listener fails.
+async fn serve_health(listener: UnixListener, monitor: HealthMonitor) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                warn!("Health socket stopped accepting: {}", e);
+                return;
+            },
+        };
+        let monitor = monitor.clone();
+        tokio::spawn(async move {
+            if let Err(e) = answer_health(stream, &monitor).await {
+                debug!("Health request failed: {}", e);
+            }
+        });
+    }
+}
+
+/// Binds the health socket at `path`, replacing a stale one.
+///
+/// A socket something still listens on, or a file that is not a socket,
+/// is left alone and binding fails.
+fn bind_health_socket(path: &Path) -> Result<UnixListener> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if !metadata.file_type().is_socket() => {
+            anyhow::bail!("{} exists and is not a socket", path.display());
+        },
+        Ok(_) => match std::os::unix::net::UnixStream::connect(path) {
+            Ok(_) => anyhow::bail!("{} is in use by another process", path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                std::fs::remove_file(path)?;
+            },
+            Err(e) => return Err(e.into()),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
+        Err(e) => return Err(e.into()),
+    }
+    Ok(UnixListener::bind(path)?)
+}
+
+/// Stops accepting sessions and closes the active ones.
+///
+/// Returns `false` if closing did not finish within `timeout`.
+async fn close_sessions(manager: &SessionManager, timeout: Duration) -> bool {
+    manager.stop_accepting();
+
+    let count = manager.session_count().await;
+    info!("Closing {} active session(s)", count);
+
+    if tokio::time::timeout(timeout, manager.close_all())
+        .await
+        .is_err()
+    {
+        warn!("Timed out after {:?} closing sessions", timeout);
+        return false;
+    }
+
+    info!("✓ All sessions closed");
+    true
+}
+
+/// Runs the full shutdown sequence.
+///
+/// Closes sessions, removes the portal object from the object server and
+/// releases `bus_name`.
+async fn shutdown(
+    conn: &Connection,
+    bus_name: &str,
+    manager: &SessionManager,
+    timeout: Duration,
+) -> Result<()> {
+    info!("🛑 Shutting down ionChannel portal service");
+
+    close_sessions(manager, timeout).await;
+
+    if conn
+        .object_server()
+        .remove::<RemoteDesktopPortal, _>(PORTAL_PATH)
+        .await?
+    {
+        info!("✓ Portal unregistered from {}", PORTAL_PATH);
+    }
+    conn.object_server()
+        .remove::<HealthMonitor, _>(PORTAL_PATH)
+        .await?;
+
+    if conn.release_name(bus_name).await? {
+        info!("✓ Released D-Bus name {}", bus_name);
+    }
+
+    info!("✅ Shutdown complete");
+    Ok(())
+}
+
+/// Applies backend capability changes to the portal and health report.
+///
+/// Runs until the backend's update channel closes. Clients are told to
+/// re-read the portal's capability properties after every change.
+async fn follow_capability_updates(
+    conn: Connection,
+    mut updates: broadcast::Receiver<BackendCapabilities>,
+    health: HealthMonitor,
+) -> Result<()> {
+    let iface = conn
+        .object_server()
+        .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+        .await?;
+    loop {
+        let caps = match updates.recv().await {
+            Ok(caps) => caps,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        };
+        health.set_backend(Some(caps.clone())).await;
+
+        let mut portal = iface.get_mut().await;
+        let mode = portal.apply_capabilities(&caps).await;
+        info!("✓ Backend capabilities changed, portal mode now {}", mode);
+
+        let ctxt = iface.signal_context();
+        portal.capabilities_changed(ctxt).await?;
+        portal.supported_input_events_changed(ctxt).await?;
+    }
+}
+
+/// Backends that can serve `display`, in priority order.
+///
+/// 1. COSMIC (compositor-specific, best integration)
+/// 2. Generic Wayland (works with any Wayland compositor)
+///
+/// Both need a Wayland compositor, so there are none for X11. An
+/// undetected display server gets every candidate to probe.
+fn backend_candidates(display: DisplayServerType) -> Vec<Box<dyn CompositorBackend>> {
+    match display {
+        // TODO: Add X11 backend when implemented
+        DisplayServerType::X11 => Vec::new(),
+        DisplayServerType::Wayland | DisplayServerType::Virtual | DisplayServerType::Unknown => {
+            vec![
+                Box::new(CosmicBackend::new()),
+                Box::new(WaylandBackend::new()),
+            ]
+        },
+    }
+}
+
+/// Picks the best backend for `display`.
+///
+/// Candidates are tried in priority order (capability-based selection);
+/// if none covers both input and capture, they are composed.
+async fn select_backend(display: DisplayServerType) -> Result<Box<dyn CompositorBackend>> {
+    BackendFactory::create_best(backend_candidates(display))
+        .await
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "No compatible backend found for {display:?}. Supported: COSMIC, Wayland compositors"
+            )
+        })
+}
+
+/// Follows a backend's capability changes, revocations and focus.
+fn follow_backend(
+    conn: &Connection,
+    iface: InterfaceRef<RemoteDesktopPortal>,
+    health: &HealthMonitor,
+    backend: &dyn CompositorBackend,
+) -> Vec<JoinHandle<()>> {
+    let capability_updates = backend.capability_updates();
+    let (watch_conn, watch_health) = (conn.clone(), health.clone());
+    vec![
+        tokio::spawn(async move {
+            if let Err(e) =
+                follow_capability_updates(watch_conn, capability_updates, watch_health).await
+            {
+                warn!("Stopped following backend capability changes: {:#}", e);
+            }
+        }),
+        tokio::spawn(RemoteDesktopPortal::follow_revocations(
+            iface.clone(),
+            backend.session_revocations(),
+        )),
+        tokio::spawn(RemoteDesktopPortal::follow_focus(
+            iface,
+            backend.focus_changes(),
+        )),
+    ]
+}
+
+/// The portal's current backend, with the tasks following it.
+///
+/// The tasks are aborted when it is dropped, so a replaced backend can no
+/// longer touch the portal or the health report.
+struct FollowedBackend {
+    backend: Arc<dyn CompositorBackend>,
+    followers: Vec<JoinHandle<()>>,
+}
+
+impl FollowedBackend {
+    /// Starts following `backend` (see [`follow_backend`]).
+    fn follow(
+        conn: &Connection,
+        iface: InterfaceRef<RemoteDesktopPortal>,
+        health: &HealthMonitor,
+        backend: Arc<dyn CompositorBackend>,
+    ) -> Self {
+        let followers = follow_backend(conn, iface, health, backend.as_ref());
+        Self { backend, followers }
+    }
+
+    /// Stops following the backend.
+    fn unfollow(&mut self) {
+        self.followers.drain(..).for_each(|task| task.abort());
+    }
+
+    /// Stops following the backend and disconnects it.
+    async fn release(mut self) {
+        self.unfollow();
+        self.backend.disconnect().await;
+    }
+}
+
+impl Drop for FollowedBackend {
+    fn drop(&mut self) {
+        self.unfollow();
+    }
+}
+
+/// Path of the Wayland socket clients would connect to.
+///
+/// `None` when the name is relative and there is no runtime directory to
+/// resolve it against.
+fn wayland_socket<F>(env: &F) -> Option<PathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let name =
+        PathBuf::from(env("WAYLAND_DISPLAY").unwrap_or_else(|| DEFAULT_WAYLAND_DISPLAY.into()));
+    if name.is_absolute() {
+        return Some(name);
+    }
+    env("XDG_RUNTIME_DIR").map(|dir| PathBuf::from(dir).join(name))
+}
+
+/// Detects the display server from variables looked up by `env`, treating
+/// Wayland as present only while its socket exists.
+///
+/// Unlike the variables themselves, the socket appears and goes away with
+/// the compositor. Without a runtime directory the socket can't be found
+/// and `WAYLAND_DISPLAY` is trusted as is.
+fn probe_display_server<F>(env: F) -> DisplayServerType
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let wayland = match wayland_socket(&env) {
+        Some(socket) => socket.exists().then(|| socket.into_os_string()),
+        None => env("WAYLAND_DISPLAY"),
+    };
+    BackendFactory::detect_display_server_with(|name| match name {
+        "WAYLAND_DISPLAY" => wayland.clone(),
+        _ => env(name),
+    })
+}
+
+/// Switches backends whenever the display server changes.
+///
+/// `display` is the display server `current` was selected for. A backend
+/// is selected for the new display server; if none serves it, `current`
+/// is kept. The old backend stops being followed before the switch and is
+/// disconnected once the portal has moved off it. Runs until the portal
+/// is removed from the object server.
+async fn follow_display_server(
+    conn: Connection,
+    health: HealthMonitor,
+    display: DisplayServerType,
+    mut current: FollowedBackend,
+) -> Result<()> {
+    let iface = conn
+        .object_server()
+        .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+        .await?;
+    let mut redetector = DisplayRedetector::new(display, REDETECT_SETTLE);
+    let mut ticks = tokio::time::interval(REDETECT_INTERVAL);
+    loop {
+        ticks.tick().await;
+        let detected = probe_display_server(|name| std::env::var_os(name));
+        let Some(changed) = redetector.observe(detected) else {
+            continue;
+        };
+        info!(
+            "Display server changed to {:?}, reselecting backend",
+            changed
+        );
+
+        let backend = match select_backend(changed).await {
+            Ok(backend) => Arc::<dyn CompositorBackend>::from(backend),
+            Err(e) => {
+                warn!("Keeping current backend: {:#}", e);
+                continue;
+            },
+        };
+        current.unfollow();
+        let caps = backend.capabilities();
+        health.set_backend(Some(caps.clone())).await;
+
+        let mut portal = iface.get_mut().await;
+        let migrated = portal.switch_backend(Arc::clone(&backend)).await;
+        info!(
+            "✓ Switched to backend {}, {} session(s) moved their capture",
+            caps.backend_name,
+            migrated.len()
+        );
+
+        let ctxt = iface.signal_context();
+        RemoteDesktopPortal::announce_streams(ctxt, &migrated).await;
+        portal.capabilities_changed(ctxt).await?;
+        portal.supported_input_events_changed(ctxt).await?;
+        drop(portal);
+
+        let previous = std::mem::replace(
+            &mut current,
+            FollowedBackend::follow(&conn, iface.clone(), &health, backend),
+        );
+        previous.release().await;
+    }
+}
+
+/// Follows the service's backend.
+///
+/// A backend selected for the display server `selected_for` is switched
+/// whenever that changes; an injected one (`None`) is kept as is.
+fn follow_service_backend(
+    conn: &Connection,
+    iface: InterfaceRef<RemoteDesktopPortal>,
+    health: &HealthMonitor,
+    backend: Arc<dyn CompositorBackend>,
+    selected_for: Option<DisplayServerType>,
+) -> Vec<JoinHandle<()>> {
+    let Some(display) = selected_for else {
+        return follow_backend(conn, iface, health, backend.as_ref());
+    };
+    let followed = FollowedBackend::follow(conn, iface, health, backend);
+    let (conn, health) = (conn.clone(), health.clone());
+    vec![tokio::spawn(async move {
+        if let Err(e) = follow_display_server(conn, health, display, followed).await {
+            warn!("Stopped following display server changes: {:#}", e);
+        }
+    })]
+}
+
+/// Forwards events from sessions to the compositor until every session
+/// sender is gone.
+///
+/// Events go through `transport` if there is one, and are otherwise
+/// injected by the portal's current backend.
+async fn forward_events(
+    mut events: mpsc::Receiver<(SessionId, StampedEvent)>,
+    transport: Option<Arc<dyn CompositorTransport>>,
+    portal: InterfaceRef<RemoteDesktopPortal>,
+) {
+    while let Some((session_id, event)) = events.recv().await {
+        info!(
+            "Event from session {}: {:?}",
+            session_id,
+            event.event.redacted()
+        );
+        let forwarded = match &transport {
+            Some(transport) => transport
+                .send(&session_id, &event)
+                .await
+                .map_err(|e| e.to_string()),
+            None => portal
+                .get()
+                .await
+                .inject(event)
+                .await
+                .map_err(|e| e.to_string()),
+        };
+        if let Err(e) = forwarded {
+            warn!(session = %session_id, "Failed to forward event to compositor: {}", e);
+        }
+    }
+}
+
+/// Assembles and starts the portal service.
+///
+/// Returns once the portal is registered and the bus name is owned; the
+/// service then runs in background tasks until
+/// [`ServiceHandle::shutdown`].
+///
+/// # Errors
+///
+/// Fails if no backend is available, the bus cannot be reached, or the
+/// portal cannot be registered or its name acquired.
+pub async fn run_service(config: ServiceConfig) -> Result<ServiceHandle> {
+    info!("🚀 Starting ionChannel RemoteDesktop portal service");
+
+    // Use the injected backend, or detect and create the best available
+    let display_type = probe_display_server(|name| std::env::var_os(name));
+    info!("Display server detected: {:?}", display_type);
+
+    let selected = config.backend.is_none();
+    let backend: Arc<dyn CompositorBackend> = match config.backend {
+        Some(backend) => backend,
+        None => Arc::from(select_backend(display_type).await?),
+    };
+
+    let caps = backend.capabilities();
+    info!("✓ Backend created: {}", caps.backend_name);
+    info!("  - Keyboard injection: {}", caps.can_inject_keyboard);
+    info!("  - Pointer injection: {}", caps.can_inject_pointer);
+    info!("  - Screen capture: {}", caps.can_capture_screen);
+
+    // Create session manager
+    let idle_timeout = config.sessions.idle_timeout;
+    let (manager, event_rx) = SessionManager::new(config.sessions);
+    info!("✓ Session manager created");
+
+    let health = HealthMonitor::new(manager.clone());
+    health.set_backend(Some(caps.clone())).await;
+
+    // Create portal with backend (the manager clone shares session state)
+    let mut portal = RemoteDesktopPortal::with_backend(manager.clone(), Arc::clone(&backend))
+        .with_clamp_absolute(config.clamp_absolute);
+    if let Some(transport) = &config.transport {
+        portal = portal.with_transport(Arc::clone(transport));
+    }
+    let outputs = portal.refresh_outputs().await;
+    info!("✓ RemoteDesktop portal created ({} output(s))", outputs);
+
+    let conn = match config.connection {
+        Some(conn) => conn,
+        None => Connection::session().await?,
+    };
+    info!("✓ Connected to D-Bus session bus");
+
+    // Register portal at standard path
+    conn.object_server().at(PORTAL_PATH, portal).await?;
+    conn.object_server().at(PORTAL_PATH, health.clone()).await?;
+    info!("✓ Portal registered at {}", PORTAL_PATH);
+
+    let portal_iface = conn
+        .object_server()
+        .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+        .await?;
+    let mut tasks = follow_service_backend(
+        &conn,
+        portal_iface.clone(),
+        &health,
+        backend,
+        selected.then_some(display_type),
+    );
+
+    let owner_changes = zbus::fdo::DBusProxy::new(&conn)
+        .await?
+        .receive_name_owner_changed()
+        .await?;
+    tasks.push(tokio::spawn(RemoteDesktopPortal::follow_owners(
+        portal_iface.clone(),
+        owner_changes,
+    )));
+    tasks.push(tokio::spawn(RemoteDesktopPortal::follow_orphans(
+        portal_iface.clone(),
+        ORPHAN_REAP_INTERVAL,
+    )));
+    tasks.push(tokio::spawn(RemoteDesktopPortal::follow_event_budgets(
+        portal_iface.clone(),
+        manager.budget_exhaustions(),
+    )));
+    if let Some(timeout) = idle_timeout.filter(|t| !t.is_zero()) {
+        tasks.push(tokio::spawn(RemoteDesktopPortal::follow_idle(
+            portal_iface.clone(),
+            IDLE_REAP_INTERVAL.min(timeout),
+        )));
+    }
+
+    if let Some(path) = &config.health_socket {
+        tasks.push(tokio::spawn(serve_health(
+            bind_health_socket(path)?,
+            health.clone(),
+        )));
+        info!("✓ Health endpoint at {}", path.display());
+    }
+
+    conn.request_name(config.bus_name.as_str()).await?;
+    info!("✓ Acquired D-Bus name {}", config.bus_name);
+
+    info!(
+        "✅ ionChannel portal service ready! (health: {})",
+        health.report().await.status
+    );
+    info!("   Backend: {}", caps.backend_name);
+    info!("   Display: {:?}", display_type);
+    info!("   D-Bus name: {}", config.bus_name);
+    info!("   Object path: {}", PORTAL_PATH);
+
+    // Forward events from sessions to the compositor service
+    if let Some(transport) = &config.transport {
+        info!("   Compositor transport: {}", transport.name());
+    } else {
+        info!("   Compositor transport: none, injecting through the backend");
+    }
+    tokio::spawn(forward_events(event_rx, config.transport, portal_iface));
+
+    Ok(ServiceHandle {
+        conn,
+        bus_name: config.bus_name,
+        manager,
+        health,
+        health_socket: config.health_socket,
+        shutdown_timeout: config.shutdown_timeout,
+        tasks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ion_core::{DeviceType, InputEvent, KeyState};
+
+    #[tokio::test]
+    async fn close_sessions_closes_all_and_refuses_new() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let session = manager
+            .create_session(SessionId::new("/test/shutdown/1"), "app".into())
+            .await
+            .unwrap();
+        manager
+            .create_session(SessionId::new("/test/shutdown/2"), "app".into())
+            .await
+            .unwrap();
+
+        assert!(close_sessions(&manager, SHUTDOWN_TIMEOUT).await);
+
+        assert_eq!(manager.session_count().await, 0);
+        assert!(session.is_closed().await);
+        assert!(manager
+            .create_session(SessionId::new("/test/shutdown/3"), "app".into())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn close_sessions_releases_held_keys() {
+        let (manager, mut rx) = SessionManager::new(SessionManagerConfig::default());
+        let session = manager
+            .create_session(SessionId::new("/test/shutdown/keys"), "app".into())
+            .await
+            .unwrap();
+        session.select_devices(DeviceType::KEYBOARD).await.unwrap();
+        session.start().await.unwrap();
+        session
+            .send_event(InputEvent::key(29, KeyState::Pressed))
+            .await
+            .unwrap();
+        rx.recv().await.unwrap();
+
+        assert!(close_sessions(&manager, SHUTDOWN_TIMEOUT).await);
+
+        let (_, release) = rx.recv().await.unwrap();
+        assert!(matches!(
+            release.event,
+            InputEvent::KeyboardKeycode {
+                keycode: 29,
+                state: KeyState::Released
+            }
+        ));
+    }
+
+    async fn get(monitor: &HealthMonitor, path: &str) -> String {
+        let (mut client, server) = tokio::io::duplex(8192);
+        client
+            .write_all(format!("GET {path} HTTP/1.1\r\n\r\n").as_bytes())
+            .await
+            .unwrap();
+        answer_health(server, monitor).await.unwrap();
+        let mut response = String::new();
+        tokio::io::AsyncReadExt::read_to_string(&mut client, &mut response)
+            .await
+            .unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn health_endpoint_reports_json() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let monitor = HealthMonitor::new(manager);
+
+        let response = get(&monitor, "/health").await;
+        assert!(response.starts_with("HTTP/1.1 503"));
+        assert!(response.contains(r#""status":"unhealthy""#));
+
+        let backend = ion_core::backend::MockBackend::new();
+        monitor.set_backend(Some(backend.capabilities())).await;
+        let response = get(&monitor, "/health").await;
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains(r#""status":"ready""#));
+        assert!(response.contains(r#""active_sessions":0"#));
+
+        assert!(get(&monitor, "/metrics").await.starts_with("HTTP/1.1 404"));
+    }
+
+    #[tokio::test]
+    async fn health_endpoint_bounds_request_line() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let monitor = HealthMonitor::new(manager);
+
+        let path = format!("/health{}", "x".repeat(2048));
+        assert!(get(&monitor, &path).await.starts_with("HTTP/1.1 400"));
+    }
+
+    #[tokio::test]
+    async fn bind_health_socket_replaces_only_stale_socket() {
+        let dir = std::env::temp_dir().join(format!("ion-health-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let stale = dir.join("stale.sock");
+        drop(std::os::unix::net::UnixListener::bind(&stale).unwrap());
+        assert!(bind_health_socket(&stale).is_ok());
+
+        let live = dir.join("live.sock");
+        let _listener = std::os::unix::net::UnixListener::bind(&live).unwrap();
+        assert!(bind_health_socket(&live).is_err());
+        assert!(live.exists());
+
+        let file = dir.join("not-a-socket");
+        std::fs::write(&file, b"keep").unwrap();
+        assert!(bind_health_socket(&file).is_err());
+        assert_eq!(std::fs::read(&file).unwrap(), b"keep");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn probe_display_server_follows_wayland_socket() {
+        let runtime_dir = std::env::temp_dir().join(format!("ion-probe-{}", std::process::id()));
+        std::fs::create_dir_all(&runtime_dir).unwrap();
+        let socket = runtime_dir.join(DEFAULT_WAYLAND_DISPLAY);
+        let _ = std::fs::remove_file(&socket);
+        let env = |name: &str| match name {
+            "XDG_RUNTIME_DIR" => Some(runtime_dir.clone().into_os_string()),
+            "DISPLAY" => Some(":0".into()),
+            _ => None,
+        };
+
+        assert_eq!(probe_display_server(env), DisplayServerType::X11);
+
+        std::fs::write(&socket, b"").unwrap();
+        assert_eq!(probe_display_server(env), DisplayServerType::Wayland);
+
+        std::fs::remove_dir_all(&runtime_dir).unwrap();
+        assert_eq!(probe_display_server(env), DisplayServerType::X11);
+    }
+
+    #[test]
+    fn probe_display_server_without_runtime_dir_trusts_env() {
+        let env = |name: &str| (name == "WAYLAND_DISPLAY").then(|| "wayland-1".into());
+        assert_eq!(probe_display_server(env), DisplayServerType::Wayland);
+        assert_eq!(probe_display_server(|_| None), DisplayServerType::Unknown);
+    }
+
+    #[tokio::test]
+    async fn no_backend_is_selected_for_x11() {
+        assert!(backend_candidates(DisplayServerType::X11).is_empty());
+        assert!(select_backend(DisplayServerType::X11).await.is_err());
+        assert_eq!(backend_candidates(DisplayServerType::Wayland).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn shutdown_releases_bus_name() {
+        if std::env::var("DBUS_SESSION_BUS_ADDRESS").is_err() {
+            eprintln!("Skipping: no D-Bus session bus");
+            return;
+        }
+        let Ok(conn) = Connection::session().await else {
+            eprintln!("Skipping: cannot connect to D-Bus session bus");
+            return;
+        };
+
+        let bus_name = format!("org.ionchannel.test.Shutdown{}", std::process::id());
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let portal = RemoteDesktopPortal::new(manager.clone());
+        conn.object_server().at(PORTAL_PATH, portal).await.unwrap();
+        conn.request_name(bus_name.as_str()).await.unwrap();
+        manager
+            .create_session(SessionId::new("/test/shutdown/dbus"), "app".into())
+            .await
+            .unwrap();
+
+        shutdown(&conn, &bus_name, &manager, SHUTDOWN_TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(manager.session_count().await, 0);
+        let dbus = zbus::fdo::DBusProxy::new(&conn).await.unwrap();
+        let owned = dbus
+            .name_has_owner(bus_name.as_str().try_into().unwrap())
+            .await
+            .unwrap();
+        assert!(!owned);
+    }
+
+    /// Mock that counts how often it was disconnected.
+    struct Tracked {
+        mock: Arc<ion_core::backend::MockBackend>,
+        disconnects: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl CompositorBackend for Tracked {
+        async fn is_available(&self) -> bool {
+            true
+        }
+
+        async fn connect(&mut self) -> ion_core::backend::BackendResult<()> {
+            Ok(())
+        }
+
+        async fn inject_input(&self, event: InputEvent) -> ion_core::backend::BackendResult<()> {
+            self.mock.inject_input(event).await
+        }
+
+        async fn start_capture(
+            &self,
+            session: &SessionId,
+        ) -> ion_core::backend::BackendResult<ion_core::backend::CaptureStream> {
+            self.mock.start_capture(session).await
+        }
+
+        fn capabilities(&self) -> BackendCapabilities {
+            self.mock.capabilities()
+        }
+
+        fn capability_updates(&self) -> broadcast::Receiver<BackendCapabilities> {
+            self.mock.capability_updates()
+        }
+
+        async fn disconnect(&self) {
+            self.disconnects
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn released_backend_is_unfollowed_and_disconnected() {
+        let bus = ion_test_substrate::mock_bus::MockBus::spawn()
+            .await
+            .unwrap();
+        let conn = bus.connect_on_runtime().await.unwrap();
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let mock = Arc::new(ion_core::backend::MockBackend::new());
+        let backend = Arc::new(Tracked {
+            mock: Arc::clone(&mock),
+            disconnects: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let portal = RemoteDesktopPortal::with_backend(manager.clone(), backend.clone());
+        conn.object_server().at(PORTAL_PATH, portal).await.unwrap();
+        let iface = conn
+            .object_server()
+            .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+            .await
+            .unwrap();
+        let health = HealthMonitor::new(manager);
+        health.set_backend(Some(backend.capabilities())).await;
+        let followed = FollowedBackend::follow(&conn, iface, &health, backend.clone());
+
+        // While followed, the backend's changes reach the health report
+        mock.set_capture_available(false);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while health.report().await.can_capture_screen {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("capability change followed");
+
+        // Once released they no longer do, and the backend is disconnected
+        followed.release().await;
+        assert_eq!(
+            backend
+                .disconnects
+                .load(std::sync::atomic::Ordering::SeqCst),
+            1
+        );
+        mock.set_capture_available(true);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!health.report().await.can_capture_screen);
+    }
+
+    #[tokio::test]
+    async fn events_without_transport_are_injected_by_the_backend() {
+        let bus = ion_test_substrate::mock_bus::MockBus::spawn()
+            .await
+            .unwrap();
+        let conn = bus.connect_on_runtime().await.unwrap();
+        let (manager, event_rx) = SessionManager::new(SessionManagerConfig::default());
+        let mock = Arc::new(ion_core::backend::MockBackend::new());
+        let portal = RemoteDesktopPortal::with_backend(manager.clone(), mock.clone());
+        conn.object_server().at(PORTAL_PATH, portal).await.unwrap();
+        let iface = conn
+            .object_server()
+            .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+            .await
+            .unwrap();
+        tokio::spawn(forward_events(event_rx, None, iface));
+
+        let session = manager
+            .create_session(SessionId::new("/test/forward"), "app".into())
+            .await
+            .unwrap();
+        session.select_devices(DeviceType::all()).await.unwrap();
+        session.start().await.unwrap();
+        session
+            .send_event(InputEvent::key(30, KeyState::Pressed))
+            .await
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while mock.received_events().await.is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("event injected");
+        assert_eq!(
+            mock.received_events().await,
+            [InputEvent::key(30, KeyState::Pressed)]
+        );
+    }
+
+    #[tokio::test]
+    async fn exhausted_sessions_are_closed_and_reported() {
+        use futures::StreamExt;
+        use ion_portal::session_manager::EVENT_BUDGET_REASON;
+        use zbus::zvariant::ObjectPath;
+
+        let bus = ion_test_substrate::mock_bus::MockBus::spawn()
+            .await
+            .unwrap();
+        let (manager, mut rx) = SessionManager::new(SessionManagerConfig {
+            max_events_per_session: Some(2),
+            ..Default::default()
+        });
+        let portal = RemoteDesktopPortal::with_backend(
+            manager.clone(),
+            Arc::new(ion_core::backend::MockBackend::new()),
+        );
+        let server = bus.serve_on_runtime(PORTAL_PATH, portal).await.unwrap();
+        let iface = server
+            .object_server()
+            .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+            .await
+            .unwrap();
+        tokio::spawn(RemoteDesktopPortal::follow_event_budgets(
+            iface,
+            manager.budget_exhaustions(),
+        ));
+
+        let client = bus.connect().await.unwrap();
+        let proxy = zbus::Proxy::new(
+            &client,
+            server.unique_name().unwrap().to_owned(),
+            PORTAL_PATH,
+            "org.freedesktop.impl.portal.RemoteDesktop",
+        )
+        .await
+        .unwrap();
+        let mut closed = proxy.receive_signal("SessionClosed").await.unwrap();
+
+        let id = SessionId::new(format!("{PORTAL_PATH}/session/budget"));
+        let session = manager
+            .create_session(id.clone(), "app".into())
+            .await
+            .unwrap();
+        session.select_devices(DeviceType::KEYBOARD).await.unwrap();
+        session.start().await.unwrap();
+        for key in [29, 30] {
+            session
+                .send_event(InputEvent::key(key, KeyState::Pressed))
+                .await
+                .unwrap();
+        }
+
+        let signal = tokio::time::timeout(Duration::from_secs(5), closed.next())
+            .await
+            .expect("SessionClosed should be emitted")
+            .unwrap();
+        let body = signal.body();
+        let (handle, reason): (ObjectPath<'_>, String) = body.deserialize().unwrap();
+        assert_eq!(handle.as_str(), id.as_str());
+        assert_eq!(reason, EVENT_BUDGET_REASON);
+        assert!(session.is_closed().await);
+        assert_eq!(manager.session_count().await, 0);
+
+        let mut released = Vec::new();
+        for _ in 0..4 {
+            let (_, event) = rx.recv().await.unwrap();
+            if let InputEvent::KeyboardKeycode {
+                keycode,
+                state: KeyState::Released,
+            } = event.event
+            {
+                released.push(keycode);
+            }
+        }
+        assert_eq!(released, [29, 30]);
+    }
+}
@@ -19,18 +19,42 @@
 //! Phase 3 (Future): Protocol abstraction
 //! - Support multiple RDP protocols
 //! - Universal RDP system for ecoPrimals
+//!
+//! ## Service
+//!
+//! The service itself lives in the library half of this crate; see
+//! `ion_portal_service::run_service`. This binary configures it from the
+//! environment and runs it until SIGINT/SIGTERM.
+//!
+//! ## Remote administration
+//!
+//! Built with the `remote-admin` feature, the service can also serve the
+//! management methods over TCP with mutual TLS; see `remote_admin`. It
+//! stays off unless `ION_ADMIN_ADDR` and the certificate paths are set.
+
+#[cfg(feature = "remote-admin")]
+mod remote_admin;
 
 use anyhow::Result;
-use std::sync::Arc;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
-use zbus::Connection;
 
-use ion_backend_cosmic::CosmicBackend;
-use ion_backend_wayland::WaylandBackend;
-use ion_core::backend::{BackendFactory, CompositorBackend};
-use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
-use ion_portal::RemoteDesktopPortal;
+use ion_portal_service::{run_service, ServiceConfig};
+
+/// Waits for SIGINT or SIGTERM.
+async fn wait_for_shutdown_signal() -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            result?;
+            info!("Received SIGINT");
+        },
+        _ = sigterm.recv() => info!("Received SIGTERM"),
+    }
+    Ok(())
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -41,78 +65,22 @@ async fn main() -> Result<()> {
         )
         .init();
 
-    info!("🚀 Starting ionChannel RemoteDesktop portal service");
-
-    // Detect and create best available backend
-    let display_type = BackendFactory::detect_display_server();
-    info!("Display server detected: {:?}", display_type);
-
-    // Try backends in priority order (capability-based selection)
-    let backend: Box<dyn CompositorBackend> = {
-        // Priority 1: COSMIC (compositor-specific, best integration)
-        let cosmic = CosmicBackend::new();
-        if cosmic.is_available().await {
-            info!("✓ COSMIC backend available - using COSMIC-specific integration");
-            Box::new(cosmic)
-        }
-        // Priority 2: Generic Wayland (works with any Wayland compositor)
-        else {
-            let wayland = WaylandBackend::new();
-            if wayland.is_available().await {
-                info!("✓ Generic Wayland backend available");
-                Box::new(wayland)
-            }
-            // Priority 3: X11 backend (future)
-            // TODO: Add X11 backend when implemented
-            else {
-                return Err(anyhow::anyhow!(
-                    "No compatible backend found. Supported: COSMIC, Wayland compositors"
-                ));
-            }
-        }
-    };
-
-    let caps = backend.capabilities();
-    info!("✓ Backend created: {}", caps.backend_name);
-    info!("  - Keyboard injection: {}", caps.can_inject_keyboard);
-    info!("  - Pointer injection: {}", caps.can_inject_pointer);
-    info!("  - Screen capture: {}", caps.can_capture_screen);
-
-    // Create session manager
-    let config = SessionManagerConfig::default();
-    let (manager, mut event_rx) = SessionManager::new(config);
-    info!("✓ Session manager created");
-
-    // Create portal with backend
-    let portal = RemoteDesktopPortal::with_backend(manager, Arc::from(backend));
-    info!("✓ RemoteDesktop portal created");
-
-    // Connect to session D-Bus
-    let conn = Connection::session().await?;
-    info!("✓ Connected to D-Bus session bus");
-
-    // Register portal at standard path
-    let path = "/org/freedesktop/portal/desktop";
-    conn.object_server().at(path, portal).await?;
-    info!("✓ Portal registered at {}", path);
-
-    info!("✅ ionChannel portal service ready!");
-    info!("   Backend: {}", caps.backend_name);
-    info!("   Display: {:?}", display_type);
-    info!("   D-Bus name: org.freedesktop.impl.portal.desktop.cosmic");
-    info!("   Object path: {}", path);
-
-    // Handle events from sessions
-    tokio::spawn(async move {
-        while let Some((session_id, event)) = event_rx.recv().await {
-            info!("Event from session {}: {:?}", session_id, event);
-            // TODO: Forward to compositor service
-            // This is where we'll connect to cosmic-comp or other display servers
-        }
-    });
-
-    // Keep service running
-    std::future::pending::<()>().await;
-
-    Ok(())
+    let service = run_service(ServiceConfig::from_env()).await?;
+
+    #[cfg(feature = "remote-admin")]
+    if let Some(config) = remote_admin::AdminConfig::from_env()? {
+        let acceptor = config.acceptor()?;
+        let listener = tokio::net::TcpListener::bind(config.addr).await?;
+        let state = remote_admin::AdminState::new(
+            service.session_manager().clone(),
+            service.health().clone(),
+            service.portal().await?,
+        );
+        tokio::spawn(remote_admin::serve(listener, acceptor, state));
+        info!("✓ Remote admin endpoint at {} (mutual TLS)", config.addr);
+    }
+
+    // Run until asked to stop
+    wait_for_shutdown_signal().await?;
+    service.shutdown().await
 }
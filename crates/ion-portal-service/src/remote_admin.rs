@@ -0,0 +1,502 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Session management over TCP and TLS.
+//!
+//! Operators who administer the service from another machine may not want
+//! to tunnel the session bus. When [`ADDR_ENV`] is set, the service also
+//! listens on TCP for the management methods only: `ActiveSessions`,
+//! `Revoke` and `Health`. Input injection and capture are never served
+//! here.
+//!
+//! Every connection must complete a TLS handshake and present a client
+//! certificate issued by the CA in [`CLIENT_CA_ENV`]; plaintext and
+//! anonymous clients are dropped. Requests and responses are one JSON
+//! object per line:
+//!
+//! ```text
+//! → {"method":"Revoke","session":"/org/freedesktop/portal/desktop/session/1"}
+//! ← {"revoked":true}
+//! ```
+//!
+//! The handshake must finish within [`HANDSHAKE_TIMEOUT`], at most
+//! [`MAX_CONNECTIONS`] connections are served at once, and a request line
+//! longer than [`MAX_LINE_LEN`] closes the connection.
+//!
+//! A revoked session is closed through the portal, so its client gets
+//! `SessionClosed` with [`ADMIN_REVOKED_REASON`] and the user is told, as
+//! for any other close.
+//!
+//! [`ADMIN_REVOKED_REASON`]: ion_portal::session_manager::ADMIN_REVOKED_REASON
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, info, warn};
+use zbus::InterfaceRef;
+
+use ion_core::session::SessionId;
+use ion_portal::session_manager::SessionManager;
+use ion_portal::{HealthMonitor, RemoteDesktopPortal};
+
+/// Environment variable with the address to listen on, e.g. `0.0.0.0:7420`.
+pub const ADDR_ENV: &str = "ION_ADMIN_ADDR";
+
+/// Environment variable naming the server certificate chain (PEM).
+pub const CERT_ENV: &str = "ION_ADMIN_CERT";
+
+/// Environment variable naming the server private key (PEM).
+pub const KEY_ENV: &str = "ION_ADMIN_KEY";
+
+/// Environment variable naming the CA that issues client certificates (PEM).
+pub const CLIENT_CA_ENV: &str = "ION_ADMIN_CLIENT_CA";
+
+/// How long a peer has to complete the TLS handshake.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connections served at once; further peers are dropped on accept.
+pub const MAX_CONNECTIONS: usize = 16;
+
+/// Longest request line accepted, in bytes.
+pub const MAX_LINE_LEN: usize = 64 * 1024;
+
+/// Where and how to serve the management endpoint.
+#[derive(Debug, Clone)]
+pub struct AdminConfig {
+    /// Address to listen on
+    pub addr: SocketAddr,
+    /// Server certificate chain
+    pub cert: PathBuf,
+    /// Server private key
+    pub key: PathBuf,
+    /// CA whose client certificates are accepted
+    pub client_ca: PathBuf,
+}
+
+impl AdminConfig {
+    /// Reads the configuration from the environment.
+    ///
+    /// Returns `None` unless [`ADDR_ENV`] is set. Once it is, the
+    /// certificate, key and client CA must all be given as well.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(addr) = std::env::var_os(ADDR_ENV) else {
+            return Ok(None);
+        };
+        let addr = addr
+            .to_str()
+            .and_then(|a| a.parse().ok())
+            .ok_or_else(|| anyhow!("{ADDR_ENV} is not a socket address"))?;
+        let path = |var: &str| {
+            std::env::var_os(var)
+                .map(PathBuf::from)
+                .ok_or_else(|| anyhow!("{ADDR_ENV} is set but {var} is not"))
+        };
+        Ok(Some(Self {
+            addr,
+            cert: path(CERT_ENV)?,
+            key: path(KEY_ENV)?,
+            client_ca: path(CLIENT_CA_ENV)?,
+        }))
+    }
+
+    /// Loads the certificates and builds the TLS acceptor.
+    pub fn acceptor(&self) -> Result<TlsAcceptor> {
+        let certs = read_certs(&self.cert)?;
+        let key = PrivateKeyDer::from_pem_file(&self.key)
+            .with_context(|| format!("reading {}", self.key.display()))?;
+        let client_ca = read_certs(&self.client_ca)?;
+        Ok(TlsAcceptor::from(server_config(certs, key, client_ca)?))
+    }
+}
+
+fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    CertificateDer::pem_file_iter(path)
+        .and_then(Iterator::collect)
+        .with_context(|| format!("reading {}", path.display()))
+}
+
+/// TLS configuration that requires a client certificate issued by one of
+/// `client_ca`.
+fn server_config(
+    certs: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+    client_ca: Vec<CertificateDer<'static>>,
+) -> Result<Arc<ServerConfig>> {
+    let provider = Arc::new(ring::default_provider());
+    let mut roots = RootCertStore::empty();
+    for cert in client_ca {
+        roots.add(cert)?;
+    }
+    let verifier =
+        WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone()).build()?;
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(certs, key)?;
+    Ok(Arc::new(config))
+}
+
+/// Handles shared with the D-Bus side of the service.
+#[derive(Clone)]
+pub struct AdminState {
+    manager: SessionManager,
+    health: HealthMonitor,
+    portal: InterfaceRef<RemoteDesktopPortal>,
+}
+
+impl AdminState {
+    /// Serves `manager`'s sessions and `health`'s report, revoking
+    /// sessions through `portal`.
+    pub fn new(
+        manager: SessionManager,
+        health: HealthMonitor,
+        portal: InterfaceRef<RemoteDesktopPortal>,
+    ) -> Self {
+        Self {
+            manager,
+            health,
+            portal,
+        }
+    }
+
+    /// Answers one request line.
+    async fn answer(&self, line: &str) -> Value {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return json!({ "error": format!("invalid request: {e}") }),
+        };
+        match request["method"].as_str().unwrap_or_default() {
+            "ActiveSessions" => {
+                let mut sessions = Vec::new();
+                for id in self.manager.session_ids().await {
+                    if let Some(session) = self.manager.get_session(&id).await {
+                        sessions.push(json!({
+                            "id": id.as_str(),
+                            "app_id": session.app_id().await,
+                            "state": session.state().await.name(),
+                        }));
+                    }
+                }
+                json!({ "sessions": sessions })
+            },
+            "Revoke" => {
+                let Some(id) = request["session"].as_str() else {
+                    return json!({ "error": "missing session" });
+                };
+                let id = match SessionId::new_validated(id) {
+                    Ok(id) => id,
+                    Err(e) => return json!({ "error": e.to_string() }),
+                };
+                let revoked = self
+                    .portal
+                    .get()
+                    .await
+                    .revoke_by_admin(self.portal.signal_context(), &id)
+                    .await;
+                info!(session = %id, revoked, "Session revoked remotely");
+                json!({ "revoked": revoked })
+            },
+            "Health" => serde_json::to_value(self.health.report().await)
+                .unwrap_or_else(|e| json!({ "error": e.to_string() })),
+            other => json!({ "error": format!("unknown method {other:?}") }),
+        }
+    }
+}
+
+/// Answers requests on an authenticated connection until it closes.
+async fn serve_connection<S>(stream: S, state: &AdminState) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut stream = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        let limit = MAX_LINE_LEN as u64 + 1;
+        if (&mut stream).take(limit).read_line(&mut line).await? == 0 {
+            break;
+        }
+        if line.len() > MAX_LINE_LEN {
+            let mut response = json!({ "error": "request too long" }).to_string();
+            response.push('\n');
+            stream.get_mut().write_all(response.as_bytes()).await?;
+            break;
+        }
+        let mut response = state.answer(line.trim()).await.to_string();
+        response.push('\n');
+        stream.get_mut().write_all(response.as_bytes()).await?;
+        line.clear();
+    }
+    stream.get_mut().shutdown().await
+}
+
+/// Serves the management endpoint until the listener fails.
+pub async fn serve(listener: TcpListener, acceptor: TlsAcceptor, state: AdminState) {
+    let slots = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    loop {
+        let (tcp, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("Admin endpoint stopped accepting: {}", e);
+                return;
+            },
+        };
+        let Ok(slot) = slots.clone().try_acquire_owned() else {
+            warn!(%peer, "Rejected admin connection: too many connections");
+            continue;
+        };
+        let (acceptor, state) = (acceptor.clone(), state.clone());
+        tokio::spawn(async move {
+            let _slot = slot;
+            let tls = match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(tcp)).await {
+                Ok(Ok(tls)) => tls,
+                Ok(Err(e)) => {
+                    warn!(%peer, "Rejected admin connection: {}", e);
+                    return;
+                },
+                Err(_) => {
+                    warn!(%peer, "Rejected admin connection: handshake timed out");
+                    return;
+                },
+            };
+            if let Err(e) = serve_connection(tls, &state).await {
+                debug!(%peer, "Admin connection failed: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use ion_core::backend::MockBackend;
+    use ion_portal::session_manager::ADMIN_REVOKED_REASON;
+    use ion_portal_service::{run_service, ServiceConfig, ServiceHandle, PORTAL_PATH};
+    use ion_test_substrate::mock_bus::MockBus;
+    use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, IsCa, KeyPair};
+    use tokio::net::TcpStream;
+    use tokio_rustls::rustls::pki_types::ServerName;
+    use tokio_rustls::rustls::ClientConfig;
+    use tokio_rustls::TlsConnector;
+
+    /// A CA with a server certificate for `localhost` and a client
+    /// certificate, both issued by it.
+    struct Pki {
+        ca: CertificateDer<'static>,
+        server: (CertificateDer<'static>, PrivateKeyDer<'static>),
+        client: (CertificateDer<'static>, PrivateKeyDer<'static>),
+    }
+
+    fn pki() -> Pki {
+        let mut params = CertificateParams::new(Vec::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = CertifiedIssuer::self_signed(params, KeyPair::generate().unwrap()).unwrap();
+        let issue = |name: &str| {
+            let key = KeyPair::generate().unwrap();
+            let cert = CertificateParams::new(vec![name.to_string()])
+                .unwrap()
+                .signed_by(&key, &ca)
+                .unwrap();
+            let key = PrivateKeyDer::try_from(key.serialize_der()).unwrap();
+            (cert.der().clone(), key)
+        };
+        Pki {
+            server: issue("localhost"),
+            client: issue("operator"),
+            ca: ca.der().clone(),
+        }
+    }
+
+    /// The service on a private bus, with the admin endpoint in front.
+    struct Admin {
+        bus: MockBus,
+        service: ServiceHandle,
+        addr: SocketAddr,
+    }
+
+    impl Admin {
+        fn manager(&self) -> &SessionManager {
+            self.service.session_manager()
+        }
+    }
+
+    async fn start(pki: &Pki) -> Admin {
+        let bus = MockBus::spawn().await.unwrap();
+        let service = run_service(ServiceConfig {
+            backend: Some(Arc::new(MockBackend::new())),
+            connection: Some(bus.connect_on_runtime().await.unwrap()),
+            bus_name: "org.ionchannel.test.RemoteAdmin".to_string(),
+            ..ServiceConfig::default()
+        })
+        .await
+        .unwrap();
+        let state = AdminState::new(
+            service.session_manager().clone(),
+            service.health().clone(),
+            service.portal().await.unwrap(),
+        );
+        let (cert, key) = (pki.server.0.clone(), pki.server.1.clone_key());
+        let acceptor =
+            TlsAcceptor::from(server_config(vec![cert], key, vec![pki.ca.clone()]).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, acceptor, state));
+        Admin { bus, service, addr }
+    }
+
+    /// Sends one request over TLS, with the client certificate if given.
+    async fn request(
+        addr: SocketAddr,
+        pki: &Pki,
+        client_cert: bool,
+        line: &str,
+    ) -> std::io::Result<String> {
+        let mut roots = RootCertStore::empty();
+        roots.add(pki.ca.clone()).unwrap();
+        let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+        let config = if client_cert {
+            let (cert, key) = (pki.client.0.clone(), pki.client.1.clone_key());
+            builder.with_client_auth_cert(vec![cert], key).unwrap()
+        } else {
+            builder.with_no_client_auth()
+        };
+
+        let tcp = TcpStream::connect(addr).await?;
+        let name = ServerName::try_from("localhost").unwrap();
+        let tls = TlsConnector::from(Arc::new(config))
+            .connect(name, tcp)
+            .await?;
+        let mut stream = BufReader::new(tls);
+        stream
+            .get_mut()
+            .write_all(format!("{line}\n").as_bytes())
+            .await?;
+        let mut response = String::new();
+        stream.read_line(&mut response).await?;
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn requires_client_certificate() {
+        let pki = pki();
+        let admin = start(&pki).await;
+        let addr = admin.addr;
+        admin
+            .manager()
+            .create_session(SessionId::new("/test/admin"), "app".into())
+            .await
+            .unwrap();
+
+        let sessions = request(addr, &pki, true, r#"{"method":"ActiveSessions"}"#)
+            .await
+            .unwrap();
+        assert!(sessions.contains(r#""id":"/test/admin""#), "{sessions}");
+
+        // Without a certificate the server aborts the handshake
+        let anonymous = request(addr, &pki, false, r#"{"method":"ActiveSessions"}"#).await;
+        assert!(anonymous.map_or(true, |r| r.is_empty()));
+
+        // Plaintext is not TLS and gets no answer
+        let mut plain = TcpStream::connect(addr).await.unwrap();
+        plain
+            .write_all(b"{\"method\":\"ActiveSessions\"}\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        let read = BufReader::new(plain).read_line(&mut response).await;
+        assert!(!response.contains("sessions"), "{read:?} {response}");
+
+        admin.service.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn closes_connection_on_oversized_line() {
+        let pki = pki();
+        let admin = start(&pki).await;
+
+        let long = format!(r#"{{"method":"{}"}}"#, "x".repeat(MAX_LINE_LEN));
+        let response = request(admin.addr, &pki, true, &long).await.unwrap();
+        assert!(response.contains("request too long"), "{response}");
+
+        admin.service.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn serves_management_methods_only() {
+        let pki = pki();
+        let admin = start(&pki).await;
+        let addr = admin.addr;
+        let id = SessionId::new("/test/admin/revoke");
+        let session = admin
+            .manager()
+            .create_session(id.clone(), "app".into())
+            .await
+            .unwrap();
+
+        let injected = request(
+            addr,
+            &pki,
+            true,
+            r#"{"method":"NotifyKeyboardKeycode","session":"/test/admin/revoke","keycode":30,"state":1}"#,
+        )
+        .await
+        .unwrap();
+        assert!(injected.contains("unknown method"), "{injected}");
+        assert_eq!(session.event_count().await, 0);
+
+        let health = request(addr, &pki, true, r#"{"method":"Health"}"#)
+            .await
+            .unwrap();
+        assert!(health.contains(r#""active_sessions":1"#), "{health}");
+
+        let client = admin.bus.connect().await.unwrap();
+        let proxy = zbus::Proxy::new(
+            &client,
+            "org.ionchannel.test.RemoteAdmin",
+            PORTAL_PATH,
+            "org.freedesktop.impl.portal.RemoteDesktop",
+        )
+        .await
+        .unwrap();
+        let mut closed = proxy.receive_signal("SessionClosed").await.unwrap();
+
+        let revoked = request(
+            addr,
+            &pki,
+            true,
+            r#"{"method":"Revoke","session":"/test/admin/revoke"}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(revoked.trim(), r#"{"revoked":true}"#);
+        assert!(admin.manager().get_session(&id).await.is_none());
+
+        // The client hears about it like any other close
+        let signal = tokio::time::timeout(std::time::Duration::from_secs(5), closed.next())
+            .await
+            .expect("SessionClosed should be emitted")
+            .unwrap();
+        let body = signal.body();
+        let (path, reason): (zbus::zvariant::ObjectPath<'_>, String) = body.deserialize().unwrap();
+        assert_eq!(path.as_str(), "/test/admin/revoke");
+        assert_eq!(reason, ADMIN_REVOKED_REASON);
+
+        admin.service.shutdown().await.unwrap();
+    }
+}
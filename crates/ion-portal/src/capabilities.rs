@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! ionChannel-specific capabilities advertised to clients.
+//!
+//! The spec properties only describe device types and the interface
+//! version. [`PortalCapabilities`] backs the extra `Capabilities`
+//! property so clients can see which capture tiers, codecs and extensions
+//! the running portal offers instead of probing by trial and error.
+
+use std::collections::HashMap;
+
+use ion_core::backend::CompositorBackend;
+use ion_core::event::InputEventKind;
+use ion_core::mode::CaptureTierInfo;
+use zbus::zvariant::{OwnedValue, Value};
+
+/// Extended capabilities of the portal and its active backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortalCapabilities {
+    /// Capture tiers that can be requested, best first
+    pub tiers: Vec<CaptureTierInfo>,
+    /// Encoded stream formats offered; empty while frames are raw pixels
+    pub codecs: Vec<String>,
+    /// Whether clipboard transfer is supported
+    pub clipboard: bool,
+    /// Whether touchpad gestures can be injected
+    pub gestures: bool,
+    /// Whether `CaptureScreenshot` can capture a single frame
+    pub screenshot: bool,
+    /// Most capture streams a session can open (one per output)
+    pub max_streams: u32,
+}
+
+impl PortalCapabilities {
+    /// Queries `backend` for what it can currently offer.
+    ///
+    /// `preferred_tier` is the tier capture detection settled on; it and
+    /// the tiers below it are advertised. `None` advertises every tier and
+    /// lets the backend choose. Without screen capture no tiers or streams
+    /// are advertised, and neither are screenshots.
+    pub async fn query(
+        backend: &dyn CompositorBackend,
+        preferred_tier: Option<CaptureTierInfo>,
+    ) -> Self {
+        let can_capture = backend.capabilities().can_capture_screen;
+
+        let tiers = if can_capture {
+            CaptureTierInfo::ALL
+                .into_iter()
+                .skip_while(|t| preferred_tier.is_some_and(|p| p != *t))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        // Backends that can't enumerate outputs still capture the primary one
+        let max_streams = if can_capture {
+            let outputs = backend.enumerate_outputs().await.map_or(0, |o| o.len());
+            u32::try_from(outputs).unwrap_or(u32::MAX).max(1)
+        } else {
+            0
+        };
+
+        Self {
+            tiers,
+            codecs: Vec::new(),
+            clipboard: false,
+            gestures: backend
+                .supported_input_events()
+                .contains(&InputEventKind::Gesture),
+            screenshot: can_capture && backend.supports_single_capture(),
+            max_streams,
+        }
+    }
+
+    /// Serializes as the `a{sv}` value of the `Capabilities` property.
+    #[must_use]
+    pub fn to_dict(&self) -> HashMap<String, OwnedValue> {
+        let tiers: Vec<&str> = self.tiers.iter().map(|t| t.name()).collect();
+        let codecs: Vec<&str> = self.codecs.iter().map(String::as_str).collect();
+        let entries = [
+            ("tiers", Value::from(tiers)),
+            ("codecs", Value::from(codecs)),
+            ("clipboard", Value::from(self.clipboard)),
+            ("gestures", Value::from(self.gestures)),
+            ("screenshot", Value::from(self.screenshot)),
+            ("max_streams", Value::from(self.max_streams)),
+        ];
+        entries
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value.try_to_owned().ok()?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ion_core::backend::{ColorSpace, MockBackend, OutputInfo, OutputTransform};
+
+    fn output(stream: u32) -> OutputInfo {
+        OutputInfo {
+            stream,
+            name: format!("DP-{stream}"),
+            width: 1920,
+            height: 1080,
+            color_space: ColorSpace::Srgb,
+            hdr: false,
+            transform: OutputTransform::Normal,
+        }
+    }
+
+    #[tokio::test]
+    async fn tiers_start_at_preferred_tier() {
+        let backend = MockBackend::new();
+
+        let all = PortalCapabilities::query(&backend, None).await;
+        assert_eq!(all.tiers, CaptureTierInfo::ALL);
+
+        let shm = PortalCapabilities::query(&backend, Some(CaptureTierInfo::Shm)).await;
+        assert_eq!(shm.tiers, [CaptureTierInfo::Shm, CaptureTierInfo::Cpu]);
+    }
+
+    #[tokio::test]
+    async fn max_streams_counts_outputs() {
+        let backend = MockBackend::new().with_outputs(vec![output(0), output(1)]);
+        let caps = PortalCapabilities::query(&backend, None).await;
+        assert_eq!(caps.max_streams, 2);
+
+        let dict = caps.to_dict();
+        assert_eq!(u32::try_from(&dict["max_streams"]).unwrap(), 2);
+        assert!(!bool::try_from(&dict["clipboard"]).unwrap());
+        assert!(bool::try_from(&dict["screenshot"]).unwrap());
+        let tiers: Vec<String> = dict["tiers"].try_clone().unwrap().try_into().unwrap();
+        assert_eq!(tiers, ["dmabuf", "shm", "cpu"]);
+    }
+}
@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Bookkeeping for the capture streams of running sessions.
+//!
+//! Shared by [`PortalCore`] and [`RemoteDesktopPortal`], so both track
+//! streams, pauses and closes the same way: however a session closes, its
+//! streams are stopped and its pause is forgotten.
+//!
+//! [`PortalCore`]: crate::core::PortalCore
+//! [`RemoteDesktopPortal`]: crate::portal::RemoteDesktopPortal
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use ion_core::backend::CaptureHandle;
+use ion_core::session::SessionId;
+
+use crate::session_manager::{CloseOutcome, SessionManager};
+
+/// Running capture streams per session, by output stream ID, and the
+/// sessions whose capture is paused.
+///
+/// Clones share the same table.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SessionCaptures {
+    inner: Arc<Mutex<Captures>>,
+}
+
+#[derive(Debug, Default)]
+struct Captures {
+    streams: HashMap<SessionId, HashMap<u32, CaptureHandle>>,
+    paused: HashSet<SessionId>,
+}
+
+impl SessionCaptures {
+    fn lock(&self) -> MutexGuard<'_, Captures> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Records a running stream of `output`.
+    ///
+    /// The stream is paused if the session's capture is. Returns `false`,
+    /// leaving the stream untouched, if the output already has a running
+    /// stream.
+    pub(crate) fn insert(&self, session: &SessionId, output: u32, handle: CaptureHandle) -> bool {
+        let mut captures = self.lock();
+        let paused = captures.paused.contains(session);
+        let streams = captures.streams.entry(session.clone()).or_default();
+        if streams.get(&output).is_some_and(|h| !h.is_stopped()) {
+            return false;
+        }
+        if paused {
+            handle.pause();
+        }
+        streams.insert(output, handle);
+        true
+    }
+
+    /// Whether `output` has a running stream for the session.
+    pub(crate) fn is_capturing(&self, session: &SessionId, output: u32) -> bool {
+        self.get(session, output).is_some_and(|h| !h.is_stopped())
+    }
+
+    /// The stream of `output` for the session, if any.
+    pub(crate) fn get(&self, session: &SessionId, output: u32) -> Option<CaptureHandle> {
+        self.lock()
+            .streams
+            .get(session)
+            .and_then(|streams| streams.get(&output))
+            .cloned()
+    }
+
+    /// Forgets the stream of `output`, returning it to be stopped.
+    pub(crate) fn remove(&self, session: &SessionId, output: u32) -> Option<CaptureHandle> {
+        self.lock()
+            .streams
+            .get_mut(session)
+            .and_then(|streams| streams.remove(&output))
+    }
+
+    /// Output stream IDs with a running stream for the session, sorted.
+    pub(crate) fn outputs(&self, session: &SessionId) -> Vec<u32> {
+        let captures = self.lock();
+        let mut outputs: Vec<u32> = captures
+            .streams
+            .get(session)
+            .into_iter()
+            .flatten()
+            .filter(|(_, handle)| !handle.is_stopped())
+            .map(|(&output, _)| output)
+            .collect();
+        outputs.sort_unstable();
+        outputs
+    }
+
+    /// Every stream of every session, with its output stream ID.
+    pub(crate) fn all(&self) -> Vec<(u32, CaptureHandle)> {
+        self.lock()
+            .streams
+            .values()
+            .flatten()
+            .map(|(&output, handle)| (output, handle.clone()))
+            .collect()
+    }
+
+    /// Stops every stream of a session and forgets its pause.
+    pub(crate) fn stop(&self, session: &SessionId) {
+        let mut captures = self.lock();
+        if let Some(streams) = captures.streams.remove(session) {
+            streams.values().for_each(CaptureHandle::stop);
+        }
+        captures.paused.remove(session);
+    }
+
+    /// Closes a session in `manager` and stops its streams.
+    pub(crate) async fn close(
+        &self,
+        manager: &SessionManager,
+        session: &SessionId,
+    ) -> CloseOutcome {
+        self.stop(session);
+        manager.close_session(session).await
+    }
+
+    /// Stops every stream.
+    ///
+    /// Returns the sessions that had streams, for restarting them on
+    /// another backend. Pauses are kept, so the restarted streams come up
+    /// paused.
+    pub(crate) fn stop_all(&self) -> Vec<SessionId> {
+        let mut captures = self.lock();
+        let streams = std::mem::take(&mut captures.streams);
+        drop(captures);
+        streams
+            .into_iter()
+            .map(|(session, streams)| {
+                streams.values().for_each(CaptureHandle::stop);
+                session
+            })
+            .collect()
+    }
+
+    /// Pauses or resumes a session's capture, including its running
+    /// streams.
+    ///
+    /// Streams inserted while paused start paused. Returns `false` if the
+    /// session already was in that state.
+    pub(crate) fn set_paused(&self, session: &SessionId, paused: bool) -> bool {
+        let mut captures = self.lock();
+        let changed = if paused {
+            captures.paused.insert(session.clone())
+        } else {
+            captures.paused.remove(session)
+        };
+        let streams = captures.streams.get(session).filter(|_| changed);
+        for handle in streams.into_iter().flat_map(HashMap::values) {
+            if paused {
+                handle.pause();
+            } else {
+                handle.resume();
+            }
+        }
+        changed
+    }
+
+    /// Whether the session's capture is paused.
+    pub(crate) fn is_paused(&self, session: &SessionId) -> bool {
+        self.lock().paused.contains(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stop_forgets_streams_and_pause() {
+        let captures = SessionCaptures::default();
+        let id = SessionId::new("/test/captures");
+        let handle = CaptureHandle::default();
+
+        assert!(captures.set_paused(&id, true));
+        assert!(captures.insert(&id, 0, handle.clone()));
+        assert!(handle.is_paused());
+        assert!(!captures.insert(&id, 0, CaptureHandle::default()));
+        assert_eq!(captures.outputs(&id), vec![0]);
+
+        captures.stop(&id);
+        assert!(handle.is_stopped());
+        assert!(captures.outputs(&id).is_empty());
+        assert!(!captures.is_paused(&id));
+    }
+}
@@ -0,0 +1,317 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Clipboard transfer policy.
+//!
+//! Regulated deployments may need to log, block or redact clipboard
+//! content crossing the remote boundary. A [`ClipboardInterceptor`] sees
+//! each selection before it is handed over and decides its fate; the
+//! default [`AllowAll`] lets everything through. Every decision is kept in
+//! a bounded [`ClipboardHistory`] for auditing, without the content itself.
+//!
+//! Text that cannot be typed as key events is pasted through the desktop's
+//! [`Clipboard`] instead.
+
+use std::collections::VecDeque;
+use std::fmt;
+#[cfg(feature = "clipboard")]
+use std::io::Read;
+#[cfg(feature = "clipboard")]
+use std::os::fd::OwnedFd;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::SystemTime;
+
+#[cfg(feature = "clipboard")]
+use ion_core::error::PortalError;
+use ion_core::session::SessionId;
+#[cfg(feature = "clipboard")]
+use ion_core::Result;
+#[cfg(feature = "clipboard")]
+use tracing::{info, warn};
+
+/// Most bytes of a selection shown to an interceptor.
+pub const PREVIEW_BYTES: usize = 4096;
+
+/// Decisions kept by [`ClipboardHistory`] before the oldest is dropped.
+pub const HISTORY_LEN: usize = 256;
+
+/// Largest selection read from a file descriptor.
+pub const MAX_SELECTION_BYTES: usize = 64 * 1024 * 1024;
+
+/// What to do with a selection crossing the remote boundary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterceptDecision {
+    /// Transfer the content unchanged
+    Allow,
+    /// Refuse the transfer
+    Block,
+    /// Transfer the given bytes in place of the content
+    Redact(Vec<u8>),
+}
+
+impl InterceptDecision {
+    /// Name used in logs and the history.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Block => "block",
+            Self::Redact(_) => "redact",
+        }
+    }
+}
+
+impl fmt::Display for InterceptDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Inspects clipboard selections before they are transferred.
+///
+/// Called on the portal's clipboard path, so implementations should be
+/// quick; anything slow (remote DLP scanning) belongs behind a cache.
+pub trait ClipboardInterceptor: Send + Sync {
+    /// Decides whether a selection may be transferred.
+    ///
+    /// `preview` holds at most [`PREVIEW_BYTES`] of the content.
+    fn on_set_selection(
+        &self,
+        session: &SessionId,
+        mime_type: &str,
+        preview: &[u8],
+    ) -> InterceptDecision;
+}
+
+/// Interceptor that allows every transfer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllowAll;
+
+impl ClipboardInterceptor for AllowAll {
+    fn on_set_selection(&self, _: &SessionId, _: &str, _: &[u8]) -> InterceptDecision {
+        InterceptDecision::Allow
+    }
+}
+
+/// Content of a clipboard selection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    /// MIME type of the content
+    pub mime_type: String,
+    /// The content itself
+    pub content: Vec<u8>,
+}
+
+/// The desktop clipboard, as far as pasting text needs it.
+pub trait Clipboard: Send + Sync {
+    /// Returns the current selection, if any.
+    fn selection(&self) -> Option<Selection>;
+
+    /// Replaces the current selection; `None` clears it.
+    fn set_selection(&self, selection: Option<Selection>);
+}
+
+/// Clipboard kept in memory, for tests and headless sessions.
+#[derive(Debug, Default)]
+pub struct MemoryClipboard {
+    selection: Mutex<Option<Selection>>,
+}
+
+impl Clipboard for MemoryClipboard {
+    fn selection(&self) -> Option<Selection> {
+        self.selection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn set_selection(&self, selection: Option<Selection>) {
+        *self
+            .selection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = selection;
+    }
+}
+
+/// One intercepted transfer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardRecord {
+    /// Session the selection came from
+    pub session: SessionId,
+    /// MIME type of the selection
+    pub mime_type: String,
+    /// Size of the content in bytes
+    pub len: usize,
+    /// Name of the decision (`allow`, `block` or `redact`)
+    pub decision: &'static str,
+    /// When the decision was made
+    pub at: SystemTime,
+}
+
+/// Most recent clipboard decisions, oldest first.
+#[derive(Debug, Default)]
+pub struct ClipboardHistory {
+    records: Mutex<VecDeque<ClipboardRecord>>,
+}
+
+impl ClipboardHistory {
+    /// Records a decision, dropping the oldest past [`HISTORY_LEN`].
+    pub fn record(&self, record: ClipboardRecord) {
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+        if records.len() == HISTORY_LEN {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Returns the recorded decisions, oldest first.
+    #[must_use]
+    pub fn records(&self) -> Vec<ClipboardRecord> {
+        self.records
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+/// Passes a selection from `session` through `interceptor`, recording
+/// the decision in `history`.
+///
+/// Returns the content to hand over: unchanged, or the replacement if the
+/// interceptor redacted it.
+///
+/// # Errors
+///
+/// Returns [`PortalError::PermissionDenied`] if the interceptor blocks the
+/// transfer.
+#[cfg(feature = "clipboard")]
+pub(crate) fn intercept(
+    interceptor: &dyn ClipboardInterceptor,
+    history: &ClipboardHistory,
+    session: &SessionId,
+    mime_type: &str,
+    content: Vec<u8>,
+) -> Result<Vec<u8>> {
+    let preview = &content[..content.len().min(PREVIEW_BYTES)];
+    let decision = interceptor.on_set_selection(session, mime_type, preview);
+    history.record(ClipboardRecord {
+        session: session.clone(),
+        mime_type: mime_type.to_string(),
+        len: content.len(),
+        decision: decision.name(),
+        at: SystemTime::now(),
+    });
+
+    match decision {
+        InterceptDecision::Allow => Ok(content),
+        InterceptDecision::Redact(replacement) => {
+            info!(%session, mime_type, "Clipboard selection redacted");
+            Ok(replacement)
+        },
+        InterceptDecision::Block => {
+            warn!(%session, mime_type, "Clipboard selection blocked");
+            Err(PortalError::PermissionDenied.into())
+        },
+    }
+}
+
+/// Reads a selection passed as a file descriptor, up to
+/// [`MAX_SELECTION_BYTES`].
+#[cfg(feature = "clipboard")]
+pub(crate) fn read_selection(fd: OwnedFd) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    std::fs::File::from(fd)
+        .take(MAX_SELECTION_BYTES as u64 + 1)
+        .read_to_end(&mut content)
+        .map_err(|e| PortalError::MethodCall(format!("cannot read selection: {e}")))?;
+    if content.len() > MAX_SELECTION_BYTES {
+        return Err(PortalError::MethodCall(format!(
+            "selection larger than {MAX_SELECTION_BYTES} bytes"
+        ))
+        .into());
+    }
+    Ok(content)
+}
+
+/// Where selections set by clients go, and the policy they pass first.
+#[derive(Clone)]
+pub(crate) struct SelectionTarget {
+    pub(crate) interceptor: Arc<dyn ClipboardInterceptor>,
+    pub(crate) history: Arc<ClipboardHistory>,
+    /// Desktop clipboard receiving the selections, if any
+    pub(crate) clipboard: Option<Arc<dyn Clipboard>>,
+}
+
+impl Default for SelectionTarget {
+    fn default() -> Self {
+        Self {
+            interceptor: Arc::new(AllowAll),
+            history: Arc::default(),
+            clipboard: None,
+        }
+    }
+}
+
+impl SelectionTarget {
+    /// Passes a selection through the interceptor and makes what may
+    /// cross the desktop's selection.
+    ///
+    /// Returns the content handed over.
+    ///
+    /// # Errors
+    ///
+    /// As [`intercept`].
+    #[cfg(feature = "clipboard")]
+    pub(crate) fn transfer(
+        &self,
+        session: &SessionId,
+        mime_type: &str,
+        content: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let content = intercept(
+            self.interceptor.as_ref(),
+            &self.history,
+            session,
+            mime_type,
+            content,
+        )?;
+        if let Some(clipboard) = &self.clipboard {
+            clipboard.set_selection(Some(Selection {
+                mime_type: mime_type.to_string(),
+                content: content.clone(),
+            }));
+        }
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allow_all_allows() {
+        let decision = AllowAll.on_set_selection(&SessionId::new("/s"), "text/plain", b"hi");
+        assert_eq!(decision, InterceptDecision::Allow);
+        assert_eq!(InterceptDecision::Redact(Vec::new()).to_string(), "redact");
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let history = ClipboardHistory::default();
+        for len in 0..=HISTORY_LEN {
+            history.record(ClipboardRecord {
+                session: SessionId::new("/s"),
+                mime_type: "text/plain".into(),
+                len,
+                decision: "allow",
+                at: SystemTime::now(),
+            });
+        }
+        let records = history.records();
+        assert_eq!(records.len(), HISTORY_LEN);
+        assert_eq!(records[0].len, 1);
+    }
+}
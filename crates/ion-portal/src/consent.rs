@@ -6,6 +6,7 @@
 //! Provides abstraction for user consent prompts before granting
 //! remote desktop access. Supports pluggable UI backends.
 
+use std::collections::HashSet;
 use std::fmt;
 use std::sync::Arc;
 use std::time::Duration;
@@ -20,6 +21,9 @@ use tracing::{debug, info, warn};
 pub enum ConsentResult {
     /// User granted permission
     Granted,
+    /// User granted permission and asked not to be prompted again for
+    /// this scope (see [`crate::consent_memory`])
+    GrantedRemember,
     /// User denied permission
     Denied,
     /// User cancelled the dialog
@@ -32,6 +36,7 @@ impl fmt::Display for ConsentResult {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Granted => write!(f, "granted"),
+            Self::GrantedRemember => write!(f, "granted (remember)"),
             Self::Denied => write!(f, "denied"),
             Self::Cancelled => write!(f, "cancelled"),
             Self::Timeout => write!(f, "timeout"),
@@ -43,7 +48,7 @@ impl ConsentResult {
     /// Returns true if consent was granted.
     #[must_use]
     pub const fn is_granted(self) -> bool {
-        matches!(self, Self::Granted)
+        matches!(self, Self::Granted | Self::GrantedRemember)
     }
 }
 
@@ -52,8 +57,11 @@ impl ConsentResult {
 pub struct ConsentRequest {
     /// Session requesting access
     pub session_id: SessionId,
-    /// Application requesting access
+    /// Application requesting access, as the client claims
     pub app_id: String,
+    /// App ID established from the client's sandbox, `None` if it could
+    /// not be (see [`crate::sender`])
+    pub verified_app_id: Option<String>,
     /// Device types being requested
     pub device_types: DeviceType,
     /// Whether screen capture is included
@@ -72,6 +80,129 @@ impl fmt::Display for ConsentRequest {
     }
 }
 
+impl ConsentRequest {
+    /// Advisory risk of granting this request.
+    ///
+    /// Seeing the screen and typing together amounts to full control of
+    /// the desktop; either alone is a moderate risk, and pointer or touch
+    /// input without capture is low. The score never affects authorization,
+    /// it only lets dialogs pick sensible defaults and styling.
+    #[must_use]
+    pub fn risk_score(&self) -> RiskLevel {
+        let keyboard = self.device_types.contains(DeviceType::KEYBOARD);
+        match (self.include_screen_capture, keyboard) {
+            (true, true) => RiskLevel::High,
+            (true, false) | (false, true) => RiskLevel::Medium,
+            (false, false) => RiskLevel::Low,
+        }
+    }
+
+    /// [`Self::risk_score`] adjusted by the app's reputation.
+    ///
+    /// Trusted apps are scored one level lower; untrusted apps are
+    /// always high risk.
+    #[must_use]
+    pub fn risk_score_with(&self, source: &dyn ReputationSource) -> RiskLevel {
+        let base = self.risk_score();
+        match source.reputation(&self.app_id) {
+            Reputation::Trusted => base.lowered(),
+            Reputation::Unknown => base,
+            Reputation::Untrusted => RiskLevel::High,
+        }
+    }
+}
+
+/// Advisory risk of a consent request, lowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RiskLevel {
+    /// Pointer or touch input only
+    Low,
+    /// Either screen capture or keyboard input
+    Medium,
+    /// Screen capture together with keyboard input
+    High,
+}
+
+impl RiskLevel {
+    /// One level lower, saturating at [`Self::Low`].
+    #[must_use]
+    pub const fn lowered(self) -> Self {
+        match self {
+            Self::High => Self::Medium,
+            Self::Medium | Self::Low => Self::Low,
+        }
+    }
+}
+
+impl fmt::Display for RiskLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Low => write!(f, "low"),
+            Self::Medium => write!(f, "medium"),
+            Self::High => write!(f, "high"),
+        }
+    }
+}
+
+/// Reputation of an application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Reputation {
+    /// Known-good application
+    Trusted,
+    /// No information
+    #[default]
+    Unknown,
+    /// Known-bad application
+    Untrusted,
+}
+
+/// Pluggable lookup of application reputation for risk scoring.
+pub trait ReputationSource: Send + Sync {
+    /// Reputation of `app_id`.
+    fn reputation(&self, app_id: &str) -> Reputation;
+}
+
+/// Reputation source backed by fixed lists of app IDs.
+#[derive(Debug, Clone, Default)]
+pub struct StaticReputation {
+    trusted: HashSet<String>,
+    untrusted: HashSet<String>,
+}
+
+impl StaticReputation {
+    /// Creates an empty source; every app is [`Reputation::Unknown`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks `app_id` as trusted.
+    #[must_use]
+    pub fn trust(mut self, app_id: impl Into<String>) -> Self {
+        self.trusted.insert(app_id.into());
+        self
+    }
+
+    /// Marks `app_id` as untrusted.
+    #[must_use]
+    pub fn distrust(mut self, app_id: impl Into<String>) -> Self {
+        self.untrusted.insert(app_id.into());
+        self
+    }
+}
+
+impl ReputationSource for StaticReputation {
+    fn reputation(&self, app_id: &str) -> Reputation {
+        if self.untrusted.contains(app_id) {
+            Reputation::Untrusted
+        } else if self.trusted.contains(app_id) {
+            Reputation::Trusted
+        } else {
+            Reputation::Unknown
+        }
+    }
+}
+
 /// Trait for consent dialog providers.
 ///
 /// Implementations can provide different UI backends:
@@ -169,6 +300,7 @@ impl ConsentProvider for AutoApproveProvider {
                     app = %request.app_id,
                     devices = %request.device_types,
                     capture = request.include_screen_capture,
+                    risk = %request.risk_score(),
                     "AUTO-APPROVING consent request (development mode)"
                 );
             }
@@ -250,7 +382,11 @@ impl ConsentProvider for CliConsentProvider {
             println!("╠════════════════════════════════════════════════════════════════╣");
             println!("║ Application:  {:<48} ║", request.app_id);
             println!("║ Session:      {:<48} ║", request.session_id);
-            println!("║ Devices:      {:<48} ║", request.device_types);
+            let devices = request.device_types - DeviceType::GESTURE;
+            println!("║ Devices:      {devices:<48} ║");
+            if request.device_types.has_gesture() {
+                println!("║ Gestures:     {:<48} ║", "trackpad gestures");
+            }
             println!(
                 "║ Screen Cap:   {:<48} ║",
                 if request.include_screen_capture {
@@ -259,6 +395,7 @@ impl ConsentProvider for CliConsentProvider {
                     "NO"
                 }
             );
+            println!("║ Risk:         {:<48} ║", request.risk_score());
             println!("╠════════════════════════════════════════════════════════════════╣");
             println!("║ Grant remote desktop access to this application?              ║");
             println!("║                                                                ║");
@@ -382,6 +519,7 @@ mod tests {
         ConsentRequest {
             session_id: SessionId::new("/test/session"),
             app_id: "com.example.test".to_string(),
+            verified_app_id: None,
             device_types: DeviceType::KEYBOARD | DeviceType::POINTER,
             include_screen_capture: true,
             parent_window: None,
@@ -391,6 +529,10 @@ mod tests {
     #[test]
     fn consent_result_display() {
         assert_eq!(ConsentResult::Granted.to_string(), "granted");
+        assert_eq!(
+            ConsentResult::GrantedRemember.to_string(),
+            "granted (remember)"
+        );
         assert_eq!(ConsentResult::Denied.to_string(), "denied");
         assert_eq!(ConsentResult::Cancelled.to_string(), "cancelled");
         assert_eq!(ConsentResult::Timeout.to_string(), "timeout");
@@ -399,6 +541,7 @@ mod tests {
     #[test]
     fn consent_result_is_granted() {
         assert!(ConsentResult::Granted.is_granted());
+        assert!(ConsentResult::GrantedRemember.is_granted());
         assert!(!ConsentResult::Denied.is_granted());
         assert!(!ConsentResult::Cancelled.is_granted());
         assert!(!ConsentResult::Timeout.is_granted());
@@ -412,6 +555,61 @@ mod tests {
         assert!(s.contains("devices="));
     }
 
+    fn request_for(devices: DeviceType, capture: bool) -> ConsentRequest {
+        ConsentRequest {
+            device_types: devices,
+            include_screen_capture: capture,
+            ..test_request()
+        }
+    }
+
+    #[test]
+    fn risk_score_by_devices_and_capture() {
+        let cases = [
+            (DeviceType::all(), true, RiskLevel::High),
+            (DeviceType::KEYBOARD, true, RiskLevel::High),
+            (DeviceType::POINTER, true, RiskLevel::Medium),
+            (DeviceType::empty(), true, RiskLevel::Medium),
+            (DeviceType::KEYBOARD, false, RiskLevel::Medium),
+            (DeviceType::POINTER, false, RiskLevel::Low),
+            (DeviceType::TOUCHSCREEN, false, RiskLevel::Low),
+            (DeviceType::empty(), false, RiskLevel::Low),
+        ];
+
+        for (devices, capture, expected) in cases {
+            assert_eq!(
+                request_for(devices, capture).risk_score(),
+                expected,
+                "devices={devices} capture={capture}"
+            );
+        }
+    }
+
+    #[test]
+    fn risk_score_reputation() {
+        let source = StaticReputation::new()
+            .trust("com.example.test")
+            .distrust("com.example.evil");
+        let request = test_request();
+        assert_eq!(request.risk_score(), RiskLevel::High);
+
+        // Known-good app is downgraded one level
+        assert_eq!(request.risk_score_with(&source), RiskLevel::Medium);
+
+        let evil = ConsentRequest {
+            app_id: "com.example.evil".to_string(),
+            ..request_for(DeviceType::POINTER, false)
+        };
+        assert_eq!(evil.risk_score_with(&source), RiskLevel::High);
+
+        let unknown = ConsentRequest {
+            app_id: "com.example.other".to_string(),
+            ..test_request()
+        };
+        assert_eq!(unknown.risk_score_with(&source), RiskLevel::High);
+        assert_eq!(RiskLevel::Low.lowered(), RiskLevel::Low);
+    }
+
     #[tokio::test]
     async fn auto_approve_instant() {
         let provider = AutoApproveProvider::instant();
@@ -0,0 +1,483 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Server-side memory of consent the user asked to have remembered.
+//!
+//! When the user answers a prompt with [`ConsentResult::GrantedRemember`],
+//! the granted scope (app, devices, screen capture) is kept for a TTL and
+//! later requests from the same app that fit inside it are granted without
+//! prompting. Unlike restore tokens, which clients present, this is the
+//! user's own preference and lives on the portal side.
+//!
+//! Narrower requests (fewer devices, or no capture) are covered by a
+//! remembered grant; anything wider prompts again.
+//!
+//! Grants are keyed on [`ConsentRequest::verified_app_id`], the app ID the
+//! client's sandbox reports, never on the `app_id` the client passes in,
+//! which any process can claim. Requests without a verified app ID are
+//! always prompted for and never remembered.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use ion_core::device::DeviceType;
+
+use crate::consent::{ConsentProvider, ConsentRequest, ConsentResult};
+
+/// Seconds in a day.
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// How long a remembered grant lasts unless configured otherwise (30 days).
+pub const DEFAULT_REMEMBER_TTL: Duration = Duration::from_secs(30 * DAY_SECS);
+
+/// File name of the store inside the portal data directory.
+const STORE_FILE: &str = "consent.json";
+
+/// A remembered grant, as stored on disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct RememberedGrant {
+    /// Granted [`DeviceType`] bits
+    devices: u32,
+    /// Whether screen capture was granted
+    capture: bool,
+    /// Expiry in seconds since the Unix epoch
+    expires_at: u64,
+}
+
+impl RememberedGrant {
+    fn covers(&self, request: &ConsentRequest) -> bool {
+        DeviceType::from(self.devices).contains(request.device_types)
+            && (self.capture || !request.include_screen_capture)
+    }
+
+    fn is_expired(&self, now: SystemTime) -> bool {
+        unix_secs(now) >= self.expires_at
+    }
+}
+
+/// The app ID grants for `request` are stored under, if it has one.
+fn grant_key(request: &ConsentRequest) -> Option<&str> {
+    request
+        .verified_app_id
+        .as_deref()
+        .filter(|app_id| !app_id.is_empty())
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Remembered consent grants by verified app ID.
+///
+/// Optionally backed by a JSON file, rewritten after every change.
+#[derive(Debug)]
+pub struct ConsentMemory {
+    ttl: Duration,
+    path: Option<PathBuf>,
+    grants: Mutex<HashMap<String, RememberedGrant>>,
+}
+
+impl ConsentMemory {
+    /// Creates an empty, in-memory store with [`DEFAULT_REMEMBER_TTL`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            ttl: DEFAULT_REMEMBER_TTL,
+            path: None,
+            grants: Mutex::default(),
+        }
+    }
+
+    /// Loads the store at `path`, starting empty if the file doesn't
+    /// exist yet. Expired grants are dropped.
+    pub fn load(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let mut grants: HashMap<String, RememberedGrant> = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        let now = SystemTime::now();
+        grants.retain(|_, grant| !grant.is_expired(now));
+        debug!(path = %path.display(), count = grants.len(), "Consent memory loaded");
+
+        Ok(Self {
+            ttl: DEFAULT_REMEMBER_TTL,
+            path: Some(path),
+            grants: Mutex::new(grants),
+        })
+    }
+
+    /// Sets how long new grants are remembered.
+    #[must_use]
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Default store location: `consent.json` in the portal data
+    /// directory, `$XDG_DATA_HOME/ionchannel` (or
+    /// `~/.local/share/ionchannel`).
+    ///
+    /// Returns `None` if neither variable is set.
+    #[must_use]
+    pub fn default_path() -> Option<PathBuf> {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share"))
+            })?;
+        Some(data_home.join("ionchannel").join(STORE_FILE))
+    }
+
+    /// Whether a remembered, unexpired grant covers `request`.
+    #[must_use]
+    pub fn covers(&self, request: &ConsentRequest) -> bool {
+        self.covers_at(request, SystemTime::now())
+    }
+
+    /// Remembers `request`'s scope for the configured TTL, replacing any
+    /// earlier grant for the same app.
+    ///
+    /// Does nothing if the request has no verified app ID.
+    pub fn remember(&self, request: &ConsentRequest) {
+        self.remember_at(request, SystemTime::now());
+    }
+
+    /// Forgets the grant for the verified `app_id`, if any.
+    pub fn forget(&self, app_id: &str) {
+        if self.grants().remove(app_id).is_some() {
+            self.persist();
+        }
+    }
+
+    /// Number of remembered grants, including expired ones not yet pruned.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.grants().len()
+    }
+
+    /// Whether no grants are remembered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn covers_at(&self, request: &ConsentRequest, now: SystemTime) -> bool {
+        let Some(app_id) = grant_key(request) else {
+            return false;
+        };
+        let mut grants = self.grants();
+        let Some(grant) = grants.get(app_id) else {
+            return false;
+        };
+        if grant.is_expired(now) {
+            grants.remove(app_id);
+            drop(grants);
+            debug!(app = %app_id, "Remembered consent expired");
+            self.persist();
+            return false;
+        }
+        grant.covers(request)
+    }
+
+    fn remember_at(&self, request: &ConsentRequest, now: SystemTime) {
+        let Some(app_id) = grant_key(request) else {
+            debug!(app = %request.app_id, "Not remembering consent for an unverified app");
+            return;
+        };
+        let grant = RememberedGrant {
+            devices: request.device_types.bits(),
+            capture: request.include_screen_capture,
+            expires_at: unix_secs(now).saturating_add(self.ttl.as_secs()),
+        };
+        self.grants().insert(app_id.to_string(), grant);
+        info!(
+            app = %app_id,
+            devices = %request.device_types,
+            capture = request.include_screen_capture,
+            ttl_secs = self.ttl.as_secs(),
+            "Consent remembered"
+        );
+        self.persist();
+    }
+
+    /// Writes the store to its file, if it has one.
+    ///
+    /// Failures are logged; a grant that isn't saved only means the user
+    /// is asked again after a restart.
+    fn persist(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let json = match serde_json::to_vec_pretty(&*self.grants()) {
+            Ok(json) => json,
+            Err(e) => {
+                warn!(error = %e, "Failed to serialize consent memory");
+                return;
+            },
+        };
+        // Write then rename, so a crash never leaves a truncated store
+        let tmp = path.with_extension("json.tmp");
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&tmp, json))
+            .and_then(|()| fs::rename(&tmp, path));
+        if let Err(e) = result {
+            warn!(path = %path.display(), error = %e, "Failed to save consent memory");
+        }
+    }
+
+    fn grants(&self) -> MutexGuard<'_, HashMap<String, RememberedGrant>> {
+        self.grants.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for ConsentMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Consent provider that skips the prompt for remembered scopes.
+///
+/// Wraps any [`ConsentProvider`]. Requests covered by the
+/// [`ConsentMemory`] are granted immediately; everything else is passed to
+/// the inner provider, and a [`ConsentResult::GrantedRemember`] answer is
+/// remembered.
+pub struct RememberingConsentProvider {
+    inner: Arc<dyn ConsentProvider>,
+    memory: Arc<ConsentMemory>,
+}
+
+impl RememberingConsentProvider {
+    /// Wraps `inner`, remembering grants in `memory`.
+    #[must_use]
+    pub fn new(inner: Arc<dyn ConsentProvider>, memory: Arc<ConsentMemory>) -> Self {
+        Self { inner, memory }
+    }
+
+    /// The store grants are remembered in.
+    #[must_use]
+    pub fn memory(&self) -> &Arc<ConsentMemory> {
+        &self.memory
+    }
+}
+
+impl std::fmt::Debug for RememberingConsentProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RememberingConsentProvider")
+            .field("memory", &self.memory)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ConsentProvider for RememberingConsentProvider {
+    fn request_consent(
+        &self,
+        request: ConsentRequest,
+        timeout: Duration,
+    ) -> Pin<Box<dyn std::future::Future<Output = ConsentResult> + Send + '_>> {
+        Box::pin(async move {
+            if self.memory.covers(&request) {
+                info!(
+                    session = %request.session_id,
+                    app = %request.app_id,
+                    "Consent granted from remembered choice"
+                );
+                return ConsentResult::Granted;
+            }
+
+            let result = self.inner.request_consent(request.clone(), timeout).await;
+            if result == ConsentResult::GrantedRemember {
+                self.memory.remember(&request);
+            }
+            result
+        })
+    }
+
+    fn show_session_info(
+        &self,
+        session_id: &ion_core::session::SessionId,
+        app_id: &str,
+    ) -> Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
+        self.inner.show_session_info(session_id, app_id)
+    }
+
+    fn notify_session_ended(
+        &self,
+        session_id: &ion_core::session::SessionId,
+        reason: &str,
+    ) -> Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
+        self.inner.notify_session_ended(session_id, reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ion_core::session::SessionId;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Answers every prompt with `answer`, counting prompts.
+    struct CountingProvider {
+        answer: ConsentResult,
+        prompts: AtomicUsize,
+    }
+
+    impl ConsentProvider for CountingProvider {
+        fn request_consent(
+            &self,
+            _request: ConsentRequest,
+            _timeout: Duration,
+        ) -> Pin<Box<dyn std::future::Future<Output = ConsentResult> + Send + '_>> {
+            self.prompts.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { self.answer })
+        }
+    }
+
+    fn request(devices: DeviceType, capture: bool) -> ConsentRequest {
+        ConsentRequest {
+            session_id: SessionId::new("/test/consent"),
+            app_id: "com.example.viewer".to_string(),
+            verified_app_id: Some("com.example.viewer".to_string()),
+            device_types: devices,
+            include_screen_capture: capture,
+            parent_window: None,
+        }
+    }
+
+    fn remembering(memory: ConsentMemory) -> (RememberingConsentProvider, Arc<CountingProvider>) {
+        let inner = Arc::new(CountingProvider {
+            answer: ConsentResult::GrantedRemember,
+            prompts: AtomicUsize::new(0),
+        });
+        let provider = RememberingConsentProvider::new(inner.clone(), Arc::new(memory));
+        (provider, inner)
+    }
+
+    #[tokio::test]
+    async fn remembered_scope_skips_prompt_until_widened() {
+        let (provider, inner) = remembering(ConsentMemory::new());
+        let timeout = Duration::from_secs(1);
+        let desktop = DeviceType::KEYBOARD | DeviceType::POINTER;
+
+        let first = provider
+            .request_consent(request(desktop, false), timeout)
+            .await;
+        assert_eq!(first, ConsentResult::GrantedRemember);
+        assert_eq!(inner.prompts.load(Ordering::SeqCst), 1);
+
+        // Identical and narrower scopes are granted without asking
+        for scope in [request(desktop, false), request(DeviceType::POINTER, false)] {
+            let result = provider.request_consent(scope, timeout).await;
+            assert_eq!(result, ConsentResult::Granted);
+        }
+        assert_eq!(inner.prompts.load(Ordering::SeqCst), 1);
+
+        // More devices, or screen capture, prompt again
+        provider
+            .request_consent(request(DeviceType::all(), false), timeout)
+            .await;
+        provider
+            .request_consent(request(desktop, true), timeout)
+            .await;
+        assert_eq!(inner.prompts.load(Ordering::SeqCst), 3);
+
+        // Another app never benefits from the grant
+        let other = ConsentRequest {
+            verified_app_id: Some("com.example.other".to_string()),
+            ..request(DeviceType::POINTER, false)
+        };
+        assert!(!provider.memory().covers(&other));
+
+        // Nor does one merely claiming to be the app
+        let claimed = ConsentRequest {
+            verified_app_id: None,
+            ..request(DeviceType::POINTER, false)
+        };
+        assert!(!provider.memory().covers(&claimed));
+    }
+
+    #[tokio::test]
+    async fn unverified_grant_is_not_remembered() {
+        let (provider, inner) = remembering(ConsentMemory::new());
+        for verified_app_id in [None, Some(String::new())] {
+            let unverified = ConsentRequest {
+                verified_app_id,
+                ..request(DeviceType::POINTER, false)
+            };
+            for _ in 0..2 {
+                let result = provider
+                    .request_consent(unverified.clone(), Duration::from_secs(1))
+                    .await;
+                assert_eq!(result, ConsentResult::GrantedRemember);
+            }
+        }
+        assert_eq!(inner.prompts.load(Ordering::SeqCst), 4);
+        assert!(provider.memory().is_empty());
+    }
+
+    #[tokio::test]
+    async fn plain_grant_is_not_remembered() {
+        let inner = Arc::new(CountingProvider {
+            answer: ConsentResult::Granted,
+            prompts: AtomicUsize::new(0),
+        });
+        let provider = RememberingConsentProvider::new(inner.clone(), Arc::default());
+        for _ in 0..2 {
+            provider
+                .request_consent(request(DeviceType::POINTER, false), Duration::from_secs(1))
+                .await;
+        }
+        assert_eq!(inner.prompts.load(Ordering::SeqCst), 2);
+        assert!(provider.memory().is_empty());
+    }
+
+    #[test]
+    fn remembered_grant_expires() {
+        let memory = ConsentMemory::new().with_ttl(Duration::from_secs(90));
+        let scope = request(DeviceType::POINTER, true);
+        let now = SystemTime::now();
+
+        memory.remember_at(&scope, now);
+        assert!(memory.covers_at(&scope, now + Duration::from_secs(89)));
+        assert!(!memory.covers_at(&scope, now + Duration::from_secs(90)));
+        // Expired grants are pruned on lookup
+        assert!(memory.is_empty());
+    }
+
+    #[test]
+    fn store_survives_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ionchannel").join(STORE_FILE);
+        let scope = request(DeviceType::KEYBOARD, false);
+
+        let memory = ConsentMemory::load(&path).unwrap();
+        assert!(memory.is_empty());
+        memory.remember(&scope);
+
+        let reloaded = ConsentMemory::load(&path).unwrap();
+        assert!(reloaded.covers(&scope));
+        reloaded.forget("com.example.viewer");
+        assert!(ConsentMemory::load(&path).unwrap().is_empty());
+
+        fs::write(&path, b"not json").unwrap();
+        assert_eq!(
+            ConsentMemory::load(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
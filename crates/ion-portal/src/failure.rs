@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Why a portal request failed, in a form clients can act on.
+//!
+//! The spec only has one response code for failure, `2` ("other"), so a
+//! missing capture backend looks exactly like a bug to the client. Failed
+//! responses keep that code but carry an `error_detail` entry in their
+//! results: an `a{sv}` with a stable `reason` code (`s`) from
+//! [`FailureReason::code`] and a human-readable `message` (`s`). Clients
+//! switch on the reason, for example to tell the user their compositor
+//! cannot capture the screen, and may show the message as is.
+
+use std::collections::HashMap;
+
+use ion_core::backend::BackendCapabilities;
+use ion_core::error::SessionError;
+use ion_core::mode::RemoteDesktopMode;
+use ion_core::Error;
+use zbus::zvariant::{OwnedValue, Value};
+
+/// Result key holding the failure detail.
+pub const ERROR_DETAIL_KEY: &str = "error_detail";
+
+/// Stable reason a request failed.
+///
+/// The codes are part of the client interface: new reasons may be added,
+/// but existing codes keep their meaning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FailureReason {
+    /// The session handle is not a valid object path for a session
+    InvalidSessionHandle,
+    /// The requested seat does not exist
+    SeatUnavailable,
+    /// The maximum number of sessions is already open
+    SessionLimit,
+    /// The portal is shutting down
+    ShuttingDown,
+    /// A session with this handle already exists
+    SessionExists,
+    /// No session with this handle exists
+    SessionNotFound,
+    /// The session is closed or not in a state for this request
+    InvalidState,
+    /// The user did not allow access
+    ConsentDenied,
+    /// Another session holds exclusive input control
+    ExclusiveHeld,
+    /// No compositor backend can capture the screen or inject input
+    NoBackend,
+    /// The compositor backend cannot capture the screen
+    NoCaptureBackend,
+    /// The compositor backend cannot inject input
+    NoInputBackend,
+    /// Anything else, most likely a bug
+    Internal,
+}
+
+impl FailureReason {
+    /// The stable code sent to clients.
+    #[must_use]
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::InvalidSessionHandle => "invalid_session_handle",
+            Self::SeatUnavailable => "seat_unavailable",
+            Self::SessionLimit => "session_limit",
+            Self::ShuttingDown => "shutting_down",
+            Self::SessionExists => "session_exists",
+            Self::SessionNotFound => "session_not_found",
+            Self::InvalidState => "invalid_state",
+            Self::ConsentDenied => "consent_denied",
+            Self::ExclusiveHeld => "exclusive_held",
+            Self::NoBackend => "no_backend",
+            Self::NoCaptureBackend => "no_capture_backend",
+            Self::NoInputBackend => "no_input_backend",
+            Self::Internal => "internal",
+        }
+    }
+
+    /// The capability gap that keeps `capabilities` from providing `mode`.
+    ///
+    /// A portal left with no capabilities at all runs in
+    /// [`RemoteDesktopMode::None`], which counts as having no backend.
+    /// Otherwise capture is checked before input.
+    #[must_use]
+    pub fn missing_capability(
+        mode: RemoteDesktopMode,
+        capabilities: &BackendCapabilities,
+    ) -> Option<Self> {
+        if mode == RemoteDesktopMode::None {
+            Some(Self::NoBackend)
+        } else if mode.has_capture() && !capabilities.can_capture_screen {
+            Some(Self::NoCaptureBackend)
+        } else if mode.has_input() && !capabilities.can_inject_input() {
+            Some(Self::NoInputBackend)
+        } else {
+            None
+        }
+    }
+
+    /// Classifies an error from the session layer.
+    ///
+    /// An unavailable mode is blamed on the gap found by
+    /// [`Self::missing_capability`].
+    #[must_use]
+    pub fn from_error(
+        error: &Error,
+        mode: RemoteDesktopMode,
+        capabilities: &BackendCapabilities,
+    ) -> Self {
+        match error {
+            Error::Session(SessionError::NotFound(_)) => Self::SessionNotFound,
+            Error::Session(SessionError::AlreadyExists(_)) => Self::SessionExists,
+            Error::Session(SessionError::Unauthorized) => Self::ConsentDenied,
+            Error::Session(SessionError::Closed | SessionError::InvalidState { .. }) => {
+                Self::InvalidState
+            },
+            Error::Session(SessionError::ExclusiveHeld(_)) => Self::ExclusiveHeld,
+            Error::Session(SessionError::LimitReached(_)) => Self::SessionLimit,
+            Error::Session(SessionError::ShuttingDown) => Self::ShuttingDown,
+            Error::Session(SessionError::ModeUnavailable(_)) => {
+                Self::missing_capability(mode, capabilities).unwrap_or(Self::Internal)
+            },
+            _ => Self::Internal,
+        }
+    }
+
+    /// Results of a failed response: just the `error_detail` entry.
+    #[must_use]
+    pub fn results(self, message: &str) -> HashMap<String, OwnedValue> {
+        let detail = HashMap::from([
+            ("reason", Value::from(self.code())),
+            ("message", Value::from(message)),
+        ]);
+        Value::from(detail)
+            .try_to_owned()
+            .map(|detail| HashMap::from([(ERROR_DETAIL_KEY.to_string(), detail)]))
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ion_core::backend::DisplayServerType;
+
+    fn capabilities(capture: bool, input: bool) -> BackendCapabilities {
+        BackendCapabilities {
+            can_inject_keyboard: input,
+            can_inject_pointer: input,
+            can_capture_screen: capture,
+            display_server_type: DisplayServerType::Wayland,
+            backend_name: "test".into(),
+        }
+    }
+
+    #[test]
+    fn unavailable_mode_blames_missing_half() {
+        let err = Error::Session(SessionError::ModeUnavailable("full"));
+        let full = RemoteDesktopMode::Full;
+
+        assert_eq!(
+            FailureReason::from_error(&err, full, &capabilities(false, true)),
+            FailureReason::NoCaptureBackend
+        );
+        assert_eq!(
+            FailureReason::from_error(&err, full, &capabilities(true, false)),
+            FailureReason::NoInputBackend
+        );
+        assert_eq!(
+            FailureReason::from_error(&err, full, &capabilities(true, true)),
+            FailureReason::Internal
+        );
+        assert_eq!(
+            FailureReason::missing_capability(RemoteDesktopMode::None, &capabilities(false, false)),
+            Some(FailureReason::NoBackend)
+        );
+        assert_eq!(
+            FailureReason::missing_capability(
+                RemoteDesktopMode::InputOnly,
+                &capabilities(false, true)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn results_carry_reason_and_message() {
+        let results = FailureReason::SessionLimit.results("too many");
+        let detail = results[ERROR_DETAIL_KEY]
+            .downcast_ref::<zbus::zvariant::Dict>()
+            .unwrap();
+
+        let reason: String = detail.get(&"reason").unwrap().unwrap();
+        let message: String = detail.get(&"message").unwrap().unwrap();
+        assert_eq!(reason, "session_limit");
+        assert_eq!(message, "too many");
+    }
+}
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Liveness and readiness reporting for the portal service.
+//!
+//! [`HealthMonitor`] tracks the connected backend and answers the
+//! `HealthCheck` D-Bus method, so supervisors such as systemd or a
+//! container runtime can tell whether the portal is usable without
+//! parsing logs.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::sync::RwLock;
+use zbus::zvariant::{OwnedValue, Value};
+
+use ion_core::backend::BackendCapabilities;
+
+use crate::session_manager::SessionManager;
+
+/// Overall health of the portal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// A backend is connected and can inject input and capture the screen
+    Ready,
+    /// Input can be injected but the screen cannot be captured
+    Degraded,
+    /// No backend is connected, or it cannot inject input
+    Unhealthy,
+}
+
+impl HealthStatus {
+    /// Name reported over D-Bus and in JSON.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Ready => "ready",
+            Self::Degraded => "degraded",
+            Self::Unhealthy => "unhealthy",
+        }
+    }
+}
+
+impl fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Point-in-time health of the portal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    /// Overall status derived from the fields below
+    pub status: HealthStatus,
+    /// Name of the connected backend, if any
+    pub backend: Option<String>,
+    /// Whether the backend can inject keyboard or pointer input
+    pub can_inject_input: bool,
+    /// Whether the backend can capture the screen
+    pub can_capture_screen: bool,
+    /// Sessions currently open
+    pub active_sessions: usize,
+}
+
+impl HealthReport {
+    /// Derives a report from the connected backend's capabilities.
+    #[must_use]
+    pub fn evaluate(backend: Option<&BackendCapabilities>, active_sessions: usize) -> Self {
+        let can_inject_input = backend.is_some_and(BackendCapabilities::can_inject_input);
+        let can_capture_screen = backend.is_some_and(|caps| caps.can_capture_screen);
+        let status = match (can_inject_input, can_capture_screen) {
+            (false, _) => HealthStatus::Unhealthy,
+            (true, false) => HealthStatus::Degraded,
+            (true, true) => HealthStatus::Ready,
+        };
+        Self {
+            status,
+            backend: backend.map(|caps| caps.backend_name.clone()),
+            can_inject_input,
+            can_capture_screen,
+            active_sessions,
+        }
+    }
+
+    /// Serializes the details as the `a{sv}` half of `HealthCheck`.
+    #[must_use]
+    pub fn details(&self) -> HashMap<String, OwnedValue> {
+        let mut entries = vec![
+            ("backend_connected", Value::from(self.backend.is_some())),
+            ("can_inject_input", Value::from(self.can_inject_input)),
+            ("can_capture_screen", Value::from(self.can_capture_screen)),
+            (
+                "active_sessions",
+                Value::from(u32::try_from(self.active_sessions).unwrap_or(u32::MAX)),
+            ),
+        ];
+        if let Some(name) = &self.backend {
+            entries.push(("backend", Value::from(name.as_str())));
+        }
+        entries
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value.try_to_owned().ok()?)))
+            .collect()
+    }
+}
+
+/// Tracks backend state and reports portal health.
+///
+/// Clones share state, so the service can update the monitor while a
+/// clone is served on D-Bus.
+#[derive(Clone)]
+pub struct HealthMonitor {
+    session_manager: SessionManager,
+    /// Capabilities of the connected backend, `None` while disconnected
+    backend: Arc<RwLock<Option<BackendCapabilities>>>,
+}
+
+impl HealthMonitor {
+    /// Creates a monitor with no backend connected.
+    #[must_use]
+    pub fn new(session_manager: SessionManager) -> Self {
+        Self {
+            session_manager,
+            backend: Arc::default(),
+        }
+    }
+
+    /// Records that a backend connected, or disconnected with `None`.
+    pub async fn set_backend(&self, capabilities: Option<BackendCapabilities>) {
+        *self.backend.write().await = capabilities;
+    }
+
+    /// Records that screen capture was lost or regained.
+    ///
+    /// Has no effect while no backend is connected.
+    pub async fn set_capture_available(&self, available: bool) {
+        if let Some(caps) = self.backend.write().await.as_mut() {
+            caps.can_capture_screen = available;
+        }
+    }
+
+    /// Current health of the portal.
+    pub async fn report(&self) -> HealthReport {
+        let active_sessions = self.session_manager.session_count().await;
+        HealthReport::evaluate(self.backend.read().await.as_ref(), active_sessions)
+    }
+}
+
+impl fmt::Debug for HealthMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HealthMonitor").finish_non_exhaustive()
+    }
+}
+
+/// D-Bus interface implementation.
+#[zbus::interface(name = "org.ionchannel.Health")]
+impl HealthMonitor {
+    /// Returns the status name and its details.
+    async fn health_check(&self) -> (String, HashMap<String, OwnedValue>) {
+        let report = self.report().await;
+        (report.status.name().to_string(), report.details())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_manager::SessionManagerConfig;
+    use ion_core::backend::{CompositorBackend, MockBackend};
+    use ion_core::session::SessionId;
+
+    fn monitor() -> HealthMonitor {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        HealthMonitor::new(manager)
+    }
+
+    #[tokio::test]
+    async fn status_follows_backend_and_capture() {
+        let monitor = monitor();
+        let report = monitor.report().await;
+        assert_eq!(report.status, HealthStatus::Unhealthy);
+        assert_eq!(report.backend, None);
+
+        monitor
+            .set_backend(Some(MockBackend::new().capabilities()))
+            .await;
+        assert_eq!(monitor.report().await.status, HealthStatus::Ready);
+
+        monitor.set_capture_available(false).await;
+        let report = monitor.report().await;
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert!(report.can_inject_input);
+        assert!(!report.can_capture_screen);
+
+        monitor.set_backend(None).await;
+        assert_eq!(monitor.report().await.status, HealthStatus::Unhealthy);
+    }
+
+    #[tokio::test]
+    async fn health_check_reports_sessions_and_details() {
+        let monitor = monitor();
+        monitor
+            .set_backend(Some(MockBackend::new().capabilities()))
+            .await;
+        monitor
+            .session_manager
+            .create_session(SessionId::new("/test/health"), "app".into())
+            .await
+            .unwrap();
+
+        let (status, details) = monitor.health_check().await;
+        assert_eq!(status, "ready");
+        assert_eq!(u32::try_from(&details["active_sessions"]).unwrap(), 1);
+        assert!(bool::try_from(&details["backend_connected"]).unwrap());
+        let backend: String = details["backend"].try_clone().unwrap().try_into().unwrap();
+        assert_eq!(backend, MockBackend::new().capabilities().backend_name);
+    }
+}
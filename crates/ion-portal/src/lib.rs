@@ -42,12 +42,42 @@
     clippy::missing_errors_doc
 )]
 
+pub mod capabilities;
+mod captures;
+pub mod clipboard;
+pub mod clock;
 pub mod consent;
+pub mod consent_memory;
 pub mod core;
+pub mod failure;
+pub mod health;
+pub mod observer;
+pub mod options;
 pub mod portal;
+pub mod sender;
 pub mod session_manager;
+pub mod text;
+pub mod transport;
+pub mod version;
 
 // Re-exports
-pub use core::PortalCore;
+pub use capabilities::PortalCapabilities;
+pub use clipboard::{
+    Clipboard, ClipboardInterceptor, InterceptDecision, MemoryClipboard, Selection,
+};
+pub use clock::{Clock, MockClock, SystemClock};
+pub use consent_memory::{ConsentMemory, RememberingConsentProvider};
+pub use core::{PortalCore, PortalCoreBuilder, SessionGuard};
+pub use failure::FailureReason;
+pub use health::{HealthMonitor, HealthReport, HealthStatus};
+pub use observer::{ObserverRegistry, SessionObserver};
+pub use options::{PersistMode, PortalOptions};
 pub use portal::RemoteDesktopPortal;
-pub use session_manager::SessionManager;
+pub use session_manager::{
+    CloseOutcome, OverflowPolicy, SessionManager, SessionManagerConfig, TakeoverPolicy,
+};
+pub use text::TextInjectionStrategy;
+pub use transport::{
+    CompositorMessage, CompositorTransport, InProcessTransport, UnixSocketTransport,
+};
+pub use version::PORTAL_VERSION;
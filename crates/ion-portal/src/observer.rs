@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Session lifecycle notifications.
+//!
+//! Only input events flow on the compositor channel. Metrics, audit logs
+//! and UI that want to know when sessions come and go register a
+//! [`SessionObserver`] with the [`SessionManager`]'s [`ObserverRegistry`]
+//! instead, and are told about every creation, start and close.
+//!
+//! [`SessionManager`]: crate::session_manager::SessionManager
+
+use std::fmt;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use ion_core::session::SessionId;
+
+/// Receives session lifecycle transitions.
+///
+/// Callbacks run inline on the session manager's path, after the
+/// transition, so a session's callbacks arrive in order. They must not
+/// block: anything slow belongs behind a bounded channel the observer
+/// drains elsewhere. Every method defaults to doing nothing.
+pub trait SessionObserver: Send + Sync {
+    /// A session was created for `app_id`.
+    fn on_created(&self, _session: &SessionId, _app_id: &str) {}
+
+    /// A session was started.
+    fn on_started(&self, _session: &SessionId) {}
+
+    /// A session was closed, by its client or by the manager.
+    fn on_closed(&self, _session: &SessionId) {}
+}
+
+/// Observers registered with a session manager.
+///
+/// Shared between clones of the manager, so registering on any clone
+/// reaches them all.
+#[derive(Clone, Default)]
+pub struct ObserverRegistry {
+    observers: Arc<RwLock<Vec<Arc<dyn SessionObserver>>>>,
+}
+
+impl ObserverRegistry {
+    /// Adds an observer; it sees transitions from now on.
+    pub fn register(&self, observer: Arc<dyn SessionObserver>) {
+        self.observers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(observer);
+    }
+
+    /// Removes `observer`, as registered; returns whether it was.
+    pub fn unregister(&self, observer: &Arc<dyn SessionObserver>) -> bool {
+        let mut observers = self
+            .observers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let before = observers.len();
+        observers.retain(|o| !Arc::ptr_eq(o, observer));
+        observers.len() < before
+    }
+
+    /// Number of registered observers.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.observers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Whether no observers are registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn created(&self, session: &SessionId, app_id: &str) {
+        self.notify(|o| o.on_created(session, app_id));
+    }
+
+    pub(crate) fn started(&self, session: &SessionId) {
+        self.notify(|o| o.on_started(session));
+    }
+
+    pub(crate) fn closed(&self, session: &SessionId) {
+        self.notify(|o| o.on_closed(session));
+    }
+
+    fn notify(&self, f: impl Fn(&dyn SessionObserver)) {
+        // Snapshot so an observer may register another without deadlock
+        let observers = self
+            .observers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        for observer in &observers {
+            f(observer.as_ref());
+        }
+    }
+}
+
+impl fmt::Debug for ObserverRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObserverRegistry")
+            .field("observers", &self.len())
+            .finish()
+    }
+}
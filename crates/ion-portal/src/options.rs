@@ -0,0 +1,338 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Typed access to portal option maps.
+//!
+//! Portal methods receive their options as `a{sv}` dictionaries. Clients
+//! routinely omit keys or send them with the wrong type (an `i` where
+//! the spec says `u`, say). [`PortalOptions`] treats both the same way:
+//! the accessor returns `None` so the caller applies its documented
+//! default, and a type mismatch is logged so the client bug is visible.
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+use tracing::warn;
+use zbus::zvariant::{Array, OwnedValue, Value};
+
+use ion_core::event::InputEvent;
+
+/// An option was present but had the wrong D-Bus type.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("option '{key}' has type '{actual}', expected '{expected}'")]
+pub struct OptionTypeError {
+    /// Option key
+    pub key: String,
+    /// Expected D-Bus signature
+    pub expected: &'static str,
+    /// Signature the client sent
+    pub actual: String,
+}
+
+/// How long a session's permissions should be remembered.
+///
+/// Mirrors the `persist_mode` option of the `RemoteDesktop` portal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PersistMode {
+    /// Do not persist (the default)
+    #[default]
+    DoNotPersist,
+    /// Persist while the application is running
+    Application,
+    /// Persist until explicitly revoked
+    Explicit,
+}
+
+impl PersistMode {
+    /// Portal option key carrying the persist mode.
+    pub const OPTION_KEY: &'static str = "persist_mode";
+
+    /// Parses the wire value, or `None` if it is out of range.
+    #[must_use]
+    pub const fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::DoNotPersist),
+            1 => Some(Self::Application),
+            2 => Some(Self::Explicit),
+            _ => None,
+        }
+    }
+}
+
+/// Read-only, typed view of a portal option map.
+#[derive(Debug, Clone, Copy)]
+pub struct PortalOptions<'a> {
+    options: &'a HashMap<String, OwnedValue>,
+}
+
+impl<'a> PortalOptions<'a> {
+    /// Option key carrying the requested device types.
+    pub const TYPES_KEY: &'static str = "types";
+
+    /// Option key carrying a restore token from a previous session.
+    pub const RESTORE_TOKEN_KEY: &'static str = "restore_token";
+
+    /// Option key requesting exclusive input control (ionChannel extension).
+    pub const EXCLUSIVE_KEY: &'static str = "exclusive";
+
+    /// Option key naming the seat to inject input on (ionChannel extension).
+    pub const SEAT_KEY: &'static str = "seat";
+
+    /// Wraps an option map.
+    #[must_use]
+    pub const fn new(options: &'a HashMap<String, OwnedValue>) -> Self {
+        Self { options }
+    }
+
+    /// Looks up `key` as a `u32`, logging a type mismatch.
+    #[must_use]
+    pub fn get_u32(self, key: &str) -> Option<u32> {
+        Self::logged(self.try_u32(key))
+    }
+
+    /// Looks up `key` as a `u64`, logging a type mismatch.
+    #[must_use]
+    pub fn get_u64(self, key: &str) -> Option<u64> {
+        Self::logged(self.try_u64(key))
+    }
+
+    /// Looks up `key` as a `bool`, logging a type mismatch.
+    #[must_use]
+    pub fn get_bool(self, key: &str) -> Option<bool> {
+        Self::logged(self.try_bool(key))
+    }
+
+    /// Looks up `key` as an `f64`, logging a type mismatch.
+    #[must_use]
+    pub fn get_f64(self, key: &str) -> Option<f64> {
+        Self::logged(self.try_f64(key))
+    }
+
+    /// Looks up `key` as a string, logging a type mismatch.
+    #[must_use]
+    pub fn get_string(self, key: &str) -> Option<String> {
+        Self::logged(self.try_string(key))
+    }
+
+    /// Looks up `key` as an array of `u32`, logging a type mismatch.
+    #[must_use]
+    pub fn get_u32_array(self, key: &str) -> Option<Vec<u32>> {
+        Self::logged(self.try_u32_array(key))
+    }
+
+    /// Looks up `key` as a `u32`.
+    ///
+    /// Returns `Ok(None)` if the key is absent.
+    pub fn try_u32(self, key: &str) -> Result<Option<u32>, OptionTypeError> {
+        self.typed(key, "u", |v| v.downcast_ref::<u32>().ok())
+    }
+
+    /// Looks up `key` as a `u64`.
+    ///
+    /// Returns `Ok(None)` if the key is absent.
+    pub fn try_u64(self, key: &str) -> Result<Option<u64>, OptionTypeError> {
+        self.typed(key, "t", |v| v.downcast_ref::<u64>().ok())
+    }
+
+    /// Looks up `key` as a `bool`.
+    ///
+    /// Returns `Ok(None)` if the key is absent.
+    pub fn try_bool(self, key: &str) -> Result<Option<bool>, OptionTypeError> {
+        self.typed(key, "b", |v| v.downcast_ref::<bool>().ok())
+    }
+
+    /// Looks up `key` as an `f64`.
+    ///
+    /// Returns `Ok(None)` if the key is absent.
+    pub fn try_f64(self, key: &str) -> Result<Option<f64>, OptionTypeError> {
+        self.typed(key, "d", |v| v.downcast_ref::<f64>().ok())
+    }
+
+    /// Looks up `key` as a string.
+    ///
+    /// Returns `Ok(None)` if the key is absent.
+    pub fn try_string(self, key: &str) -> Result<Option<String>, OptionTypeError> {
+        self.typed(key, "s", |v| {
+            v.downcast_ref::<&str>().ok().map(str::to_string)
+        })
+    }
+
+    /// Looks up `key` as an array of `u32`.
+    ///
+    /// An `av` whose elements are all `u32` is accepted too. Returns
+    /// `Ok(None)` if the key is absent.
+    pub fn try_u32_array(self, key: &str) -> Result<Option<Vec<u32>>, OptionTypeError> {
+        self.typed(key, "au", |v| {
+            let array = v.downcast_ref::<&Array>().ok()?;
+            // Checked up front, or an empty array of anything would pass
+            if !matches!(array.element_signature().as_str(), "u" | "v") {
+                return None;
+            }
+            array
+                .inner()
+                .iter()
+                .map(|e| e.downcast_ref::<u32>().ok())
+                .collect()
+        })
+    }
+
+    /// Restore token from a previous session, if any.
+    #[must_use]
+    pub fn restore_token(self) -> Option<String> {
+        self.get_string(Self::RESTORE_TOKEN_KEY)
+    }
+
+    /// When the client sent an input event, in microseconds since the
+    /// Unix epoch, if it said.
+    #[must_use]
+    pub fn client_timestamp(self) -> Option<u64> {
+        self.get_u64(InputEvent::CLIENT_TIMESTAMP_KEY)
+    }
+
+    /// Requested persist mode.
+    ///
+    /// Absent, mistyped, or out-of-range values mean
+    /// [`PersistMode::DoNotPersist`].
+    #[must_use]
+    pub fn persist_mode(self) -> PersistMode {
+        let Some(value) = self.get_u32(PersistMode::OPTION_KEY) else {
+            return PersistMode::default();
+        };
+        PersistMode::from_u32(value).unwrap_or_else(|| {
+            warn!(value, "Ignoring unknown persist_mode");
+            PersistMode::default()
+        })
+    }
+
+    fn typed<T>(
+        self,
+        key: &str,
+        expected: &'static str,
+        convert: impl FnOnce(&Value<'_>) -> Option<T>,
+    ) -> Result<Option<T>, OptionTypeError> {
+        let Some(value) = self.options.get(key) else {
+            return Ok(None);
+        };
+        convert(value).map(Some).ok_or_else(|| OptionTypeError {
+            key: key.to_string(),
+            expected,
+            actual: value.value_signature().to_string(),
+        })
+    }
+
+    fn logged<T>(result: Result<Option<T>, OptionTypeError>) -> Option<T> {
+        result.unwrap_or_else(|e| {
+            warn!(
+                key = %e.key,
+                expected = e.expected,
+                actual = %e.actual,
+                "Ignoring mistyped portal option"
+            );
+            None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options<const N: usize>(entries: [(&str, OwnedValue); N]) -> HashMap<String, OwnedValue> {
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect()
+    }
+
+    fn owned(value: Value<'_>) -> OwnedValue {
+        OwnedValue::try_from(value).unwrap()
+    }
+
+    #[test]
+    fn typed_values_are_read() {
+        let map = options([
+            ("types", OwnedValue::from(3u32)),
+            ("multiple", OwnedValue::from(true)),
+            ("restore_token", owned(Value::from("abc"))),
+            ("streams", owned(Value::from(vec![0u32, 2]))),
+            ("persist_mode", OwnedValue::from(2u32)),
+            ("pointer_sensitivity", OwnedValue::from(1.5f64)),
+            (
+                "client_timestamp",
+                OwnedValue::from(1_700_000_000_000_000u64),
+            ),
+        ]);
+        let opts = PortalOptions::new(&map);
+
+        assert_eq!(opts.get_u32("types"), Some(3));
+        assert_eq!(opts.get_bool("multiple"), Some(true));
+        assert_eq!(opts.restore_token().as_deref(), Some("abc"));
+        assert_eq!(opts.get_u32_array("streams"), Some(vec![0, 2]));
+        assert_eq!(opts.persist_mode(), PersistMode::Explicit);
+        assert_eq!(opts.get_f64("pointer_sensitivity"), Some(1.5));
+        assert_eq!(opts.client_timestamp(), Some(1_700_000_000_000_000));
+        assert_eq!(opts.try_u64("types").unwrap_err().actual, "u");
+        assert_eq!(opts.try_f64("types").unwrap_err().actual, "u");
+    }
+
+    #[test]
+    fn missing_options_default() {
+        let map = HashMap::new();
+        let opts = PortalOptions::new(&map);
+
+        assert_eq!(opts.try_u32("types"), Ok(None));
+        assert_eq!(opts.get_bool("multiple"), None);
+        assert_eq!(opts.restore_token(), None);
+        assert_eq!(opts.get_u32_array("streams"), None);
+        assert_eq!(opts.persist_mode(), PersistMode::DoNotPersist);
+    }
+
+    #[test]
+    fn mistyped_options_are_reported_and_default() {
+        let map = options([
+            ("types", OwnedValue::from(3i32)),
+            ("multiple", OwnedValue::from(1u32)),
+            ("restore_token", OwnedValue::from(7u32)),
+            ("streams", owned(Value::from(vec!["a", "b"]))),
+            ("persist_mode", owned(Value::from("always"))),
+        ]);
+        let opts = PortalOptions::new(&map);
+
+        let err = opts.try_u32("types").unwrap_err();
+        assert_eq!(err.key, "types");
+        assert_eq!(err.expected, "u");
+        assert_eq!(err.actual, "i");
+        assert!(err.to_string().contains("expected 'u'"));
+
+        assert_eq!(opts.try_bool("multiple").unwrap_err().actual, "u");
+        assert_eq!(opts.try_string("restore_token").unwrap_err().actual, "u");
+        assert_eq!(opts.try_u32_array("streams").unwrap_err().actual, "as");
+
+        assert_eq!(opts.get_u32("types"), None);
+        assert_eq!(opts.get_bool("multiple"), None);
+        assert_eq!(opts.restore_token(), None);
+        assert_eq!(opts.get_u32_array("streams"), None);
+        assert_eq!(opts.persist_mode(), PersistMode::DoNotPersist);
+    }
+
+    #[test]
+    fn empty_array_of_wrong_type_is_mistyped() {
+        let map = options([
+            ("streams", owned(Value::from(Vec::<i32>::new()))),
+            ("types", owned(Value::from(Vec::<u32>::new()))),
+        ]);
+        let opts = PortalOptions::new(&map);
+
+        assert_eq!(opts.try_u32_array("streams").unwrap_err().actual, "ai");
+        assert_eq!(opts.get_u32_array("types"), Some(Vec::new()));
+    }
+
+    #[test]
+    fn persist_mode_out_of_range_defaults() {
+        let map = options([("persist_mode", OwnedValue::from(9u32))]);
+        assert_eq!(
+            PortalOptions::new(&map).persist_mode(),
+            PersistMode::DoNotPersist
+        );
+    }
+}
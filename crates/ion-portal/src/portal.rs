@@ -7,21 +7,52 @@
 //! per the xdg-desktop-portal specification.
 
 use std::collections::HashMap;
-use std::sync::Arc;
-
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use futures::StreamExt;
+use tokio::sync::{broadcast, RwLock};
 use tracing::{debug, error, info, instrument, warn};
-use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+use zbus::names::UniqueName;
+use zbus::zvariant::{ObjectPath, OwnedFd, OwnedValue, Value};
+use zbus::{InterfaceRef, SignalContext};
 
-use ion_core::backend::CompositorBackend;
+use ion_core::backend::{
+    BackendCapabilities, BackendError, BackendResult, CaptureFrame, CaptureRequest,
+    CompositorBackend, CursorMode, FocusChanged, OutputInfo, SessionRevocation,
+};
 use ion_core::device::DeviceType;
-use ion_core::event::{ButtonState, InputEvent, KeyState};
-use ion_core::mode::RemoteDesktopMode;
-use ion_core::session::SessionId;
-
+use ion_core::event::{
+    scroll_options, AccelProfile, ButtonState, GesturePhase, InputEvent, InputEventKind, KeyState,
+    PointerTransform, StampedEvent,
+};
+use ion_core::mode::{CaptureTierInfo, RemoteDesktopMode, SessionCapabilities};
+use ion_core::redact::Sensitive;
+use ion_core::session::{RateProfile, SessionHandle, SessionId, SessionState};
+
+use crate::capabilities::PortalCapabilities;
+use crate::captures::SessionCaptures;
+#[cfg(feature = "clipboard")]
+use crate::clipboard::read_selection;
+use crate::clipboard::{Clipboard, ClipboardInterceptor, ClipboardRecord, SelectionTarget};
 use crate::consent::{
     AutoApproveProvider, ConsentProvider, ConsentRequest, DEFAULT_CONSENT_TIMEOUT,
 };
-use crate::session_manager::SessionManager;
+use crate::core::{bound_absolute, start_output_streams, StreamInfo};
+use crate::failure::FailureReason;
+use crate::observer::{ObserverRegistry, SessionObserver};
+use crate::options::PortalOptions;
+use crate::sender::sender_app_id;
+use crate::session_manager::{
+    SessionManager, ADMIN_REVOKED_REASON, EVENT_BUDGET_REASON, IDLE_REASON, ORPHANED_REASON,
+    OWNER_LEFT_REASON, PREEMPTED_REASON, REVOKED_REASON,
+};
+use crate::transport::CompositorTransport;
+use crate::version::{self, CLIPBOARD_VERSION, GESTURE_VERSION, PORTAL_VERSION};
 
 /// Portal response codes per xdg-desktop-portal spec.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -38,6 +69,281 @@ pub enum ResponseCode {
 /// Result type for portal methods.
 pub type PortalResult<T> = (u32, T);
 
+/// `ScreenCast` source type of a whole monitor.
+const SOURCE_TYPE_MONITOR: u32 = 1;
+
+/// Serializes streams like the `ScreenCast` `streams` result, `a(ua{sv})`.
+///
+/// Each stream carries its `PipeWire` node ID with `size` and
+/// `source_type` properties, plus the ionChannel `output_index` that
+/// absolute input events refer to and the output's `color_space` (`s`)
+/// and `hdr` (`b`), so clients know when to tone map. `size` is upright;
+/// `applied_transform` (`s`) names the output transform the frames
+/// already have applied, so clients must not rotate them again.
+fn streams_value(streams: &[StreamInfo]) -> Value<'static> {
+    let streams: Vec<(u32, HashMap<&str, Value<'static>>)> = streams
+        .iter()
+        .map(|s| {
+            let size = (
+                i32::try_from(s.width).unwrap_or(i32::MAX),
+                i32::try_from(s.height).unwrap_or(i32::MAX),
+            );
+            let properties = HashMap::from([
+                ("size", Value::from(size)),
+                ("source_type", Value::from(SOURCE_TYPE_MONITOR)),
+                ("output_index", Value::from(s.output_index)),
+                ("color_space", Value::from(s.color_space.name())),
+                ("hdr", Value::from(s.hdr)),
+                ("applied_transform", Value::from(s.transform.name())),
+            ]);
+            (s.node_id, properties)
+        })
+        .collect();
+    Value::from(streams)
+}
+
+/// Serializes a focused surface for `FocusChanged` and `CurrentFocus`.
+///
+/// Keys are `surface_title` (`s`), `app_id` (`s`) and, when the
+/// compositor reports it, `geometry` (`(iiuu)`: x, y, width, height).
+fn focus_dict(focus: &FocusChanged) -> HashMap<String, OwnedValue> {
+    let mut dict = HashMap::from([
+        (
+            "surface_title".to_string(),
+            Value::from(focus.surface_title.as_str())
+                .try_to_owned()
+                .unwrap(),
+        ),
+        (
+            "app_id".to_string(),
+            Value::from(focus.app_id.as_str()).try_to_owned().unwrap(),
+        ),
+    ]);
+    if let Some(g) = focus.geometry {
+        dict.insert(
+            "geometry".to_string(),
+            Value::from((g.x, g.y, g.width, g.height))
+                .try_to_owned()
+                .unwrap(),
+        );
+    }
+    dict
+}
+
+/// Reads the requested rate profile from portal options.
+///
+/// Absent, mistyped, or unknown values fall back to [`RateProfile::Normal`].
+fn parse_rate_profile(options: &HashMap<String, OwnedValue>) -> RateProfile {
+    PortalOptions::new(options)
+        .get_string(RateProfile::OPTION_KEY)
+        .map_or_else(RateProfile::default, |name| RateProfile::from_name(&name))
+}
+
+/// Reads the requested relative pointer transform from portal options.
+///
+/// Without a sensitivity or acceleration profile, motion is left as-is.
+fn parse_pointer_transform(options: &HashMap<String, OwnedValue>) -> PointerTransform {
+    let options = PortalOptions::new(options);
+    let transform = options
+        .get_f64(PointerTransform::SENSITIVITY_KEY)
+        .map_or_else(
+            PointerTransform::default,
+            PointerTransform::with_sensitivity,
+        );
+    match options.get_u32(AccelProfile::OPTION_KEY) {
+        Some(profile) => transform.with_accel_profile(AccelProfile::from(profile)),
+        None => transform,
+    }
+}
+
+/// Reads a gesture phase passed over D-Bus.
+fn gesture_phase(phase: u32) -> zbus::fdo::Result<GesturePhase> {
+    GesturePhase::try_from(phase)
+        .map_err(|phase| zbus::fdo::Error::InvalidArgs(format!("unknown gesture phase {phase}")))
+}
+
+/// Reads the one-shot capture parameters from portal options.
+fn parse_capture_request(options: &HashMap<String, OwnedValue>) -> CaptureRequest {
+    let options = PortalOptions::new(options);
+    CaptureRequest {
+        stream: options.get_u32(CaptureRequest::STREAM_OPTION_KEY),
+        cursor_mode: options
+            .get_u32(CursorMode::OPTION_KEY)
+            .map_or_else(CursorMode::default, CursorMode::from),
+        ..CaptureRequest::default()
+    }
+}
+
+/// Writes a frame's pixels, rows tightly packed, to an anonymous file.
+///
+/// The file is unlinked before any pixels are written, so the returned
+/// descriptor is the only handle to it. Does blocking file I/O, so run it
+/// on the blocking pool.
+fn frame_to_fd(frame: &CaptureFrame) -> std::io::Result<OwnedFd> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+
+    let dir = std::env::var_os("XDG_RUNTIME_DIR").map_or_else(std::env::temp_dir, PathBuf::from);
+    let path = dir.join(format!(
+        "ion-screenshot-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)?;
+    std::fs::remove_file(&path)?;
+
+    let row = frame.width() as usize * frame.format().bytes_per_pixel();
+    let stride = frame.stride() as usize;
+    if frame.format().is_yuv() || stride == row {
+        file.write_all(frame.data())?;
+    } else {
+        for line in frame.data().chunks(stride).take(frame.height() as usize) {
+            file.write_all(&line[..row.min(line.len())])?;
+        }
+    }
+    file.seek(SeekFrom::Start(0))?;
+
+    Ok(std::os::fd::OwnedFd::from(file).into())
+}
+
+/// Where a session's pointer lock was taken, so it is released there too.
+#[derive(Clone)]
+enum LockTarget {
+    /// The compositor service the session's input goes to
+    Transport(Arc<dyn CompositorTransport>),
+    /// The backend, on the session's seat
+    Backend(Arc<dyn CompositorBackend>, Option<String>),
+}
+
+impl LockTarget {
+    async fn set(&self, session: &SessionId, locked: bool) -> zbus::fdo::Result<()> {
+        match self {
+            Self::Transport(transport) => transport
+                .set_pointer_lock(session, locked)
+                .await
+                .map_err(|e| zbus::fdo::Error::Failed(e.to_string())),
+            Self::Backend(backend, seat) => backend
+                .set_pointer_lock(session, seat.as_deref(), locked)
+                .await
+                .map_err(|e| match e {
+                    BackendError::NotAvailable(_) => zbus::fdo::Error::NotSupported(e.to_string()),
+                    _ => zbus::fdo::Error::Failed(e.to_string()),
+                }),
+        }
+    }
+
+    /// Releases `session`'s lock in the background.
+    fn release(self, session: SessionId) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!(session = %session, "No runtime to release the pointer lock on");
+            return;
+        };
+        runtime.spawn(async move {
+            match self.set(&session, false).await {
+                Ok(()) => info!(session = %session, "Pointer lock released"),
+                Err(e) => warn!(session = %session, error = %e, "Failed to release pointer lock"),
+            }
+        });
+    }
+}
+
+type LockMap = Arc<Mutex<HashMap<SessionId, LockTarget>>>;
+
+/// Releases the locks of sessions the session manager closes: by
+/// revocation, preemption, its owner leaving the bus or shutdown.
+struct LockReleaser(LockMap);
+
+impl SessionObserver for LockReleaser {
+    fn on_closed(&self, session: &SessionId) {
+        let target = self
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(session);
+        if let Some(target) = target {
+            target.release(session.clone());
+        }
+    }
+}
+
+/// Keeps the [`LockReleaser`] registered while any portal clone lives.
+struct LockRegistration {
+    locks: LockMap,
+    releaser: Arc<dyn SessionObserver>,
+    observers: ObserverRegistry,
+}
+
+impl Drop for LockRegistration {
+    fn drop(&mut self) {
+        self.observers.unregister(&self.releaser);
+        let locks = std::mem::take(&mut *self.locks.lock().unwrap_or_else(PoisonError::into_inner));
+        for (session, target) in locks {
+            target.release(session);
+        }
+    }
+}
+
+/// Sessions holding the pointer lock, with where they took it.
+///
+/// Locks are released when their session closes, and when the last
+/// portal clone is dropped.
+#[derive(Clone)]
+struct PointerLocks {
+    locks: LockMap,
+    _registration: Arc<LockRegistration>,
+}
+
+impl PointerLocks {
+    /// Creates an empty set that releases locks of sessions `manager`
+    /// closes.
+    fn watching(manager: &SessionManager) -> Self {
+        let locks = LockMap::default();
+        let releaser: Arc<dyn SessionObserver> = Arc::new(LockReleaser(Arc::clone(&locks)));
+        manager.observers().register(Arc::clone(&releaser));
+        Self {
+            locks: Arc::clone(&locks),
+            _registration: Arc::new(LockRegistration {
+                locks,
+                releaser,
+                observers: manager.observers().clone(),
+            }),
+        }
+    }
+
+    fn is_locked(&self, session: &SessionId) -> bool {
+        self.locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(session)
+    }
+
+    fn locked_by(&self, session: &SessionId) -> Option<LockTarget> {
+        self.locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(session)
+            .cloned()
+    }
+
+    fn insert(&self, session: SessionId, target: LockTarget) {
+        self.locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(session, target);
+    }
+
+    fn remove(&self, session: &SessionId) -> Option<LockTarget> {
+        self.locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(session)
+    }
+}
+
 /// `RemoteDesktop` portal interface.
 ///
 /// This struct implements the D-Bus interface for remote desktop functionality.
@@ -52,6 +358,22 @@ pub struct RemoteDesktopPortal {
     consent_provider: Arc<dyn ConsentProvider>,
     /// Compositor backend for input injection and screen capture
     backend: Arc<dyn CompositorBackend>,
+    /// Separate compositor service session input is carried to, if any
+    transport: Option<Arc<dyn CompositorTransport>>,
+    /// Capture streams started with each session
+    captures: SessionCaptures,
+    /// Capture tier chosen by capability detection, if any
+    capture_tier: Option<CaptureTierInfo>,
+    /// Sessions holding the pointer lock
+    pointer_locks: PointerLocks,
+    /// Output geometry by stream, for bounding absolute motion
+    outputs: Arc<RwLock<HashMap<u32, OutputInfo>>>,
+    /// Clamp out-of-bounds absolute motion instead of rejecting it
+    clamp_absolute: bool,
+    /// Desktop clipboard and policy for `SetSelection`
+    clipboard: SelectionTarget,
+    /// Version reported to clients, at most [`PORTAL_VERSION`]
+    version: u32,
 }
 
 impl RemoteDesktopPortal {
@@ -76,12 +398,40 @@ impl RemoteDesktopPortal {
         session_manager: SessionManager,
         backend: Arc<dyn CompositorBackend>,
     ) -> Self {
+        let pointer_locks = PointerLocks::watching(&session_manager);
         Self {
             session_manager,
             session_mode: RemoteDesktopMode::Full,
             consent_provider: Arc::new(AutoApproveProvider::instant()),
             backend,
+            transport: None,
+            captures: SessionCaptures::default(),
+            capture_tier: None,
+            pointer_locks,
+            outputs: Arc::default(),
+            clamp_absolute: false,
+            clipboard: SelectionTarget::default(),
+            version: PORTAL_VERSION,
+        }
+    }
+
+    /// Creates a portal with a specific compositor backend, checking that
+    /// the backend is usable first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BackendError::NotAvailable`] if the backend is not
+    /// available on this system.
+    pub async fn try_with_backend(
+        session_manager: SessionManager,
+        backend: Arc<dyn CompositorBackend>,
+    ) -> BackendResult<Self> {
+        if !backend.is_available().await {
+            return Err(BackendError::NotAvailable(
+                backend.capabilities().backend_name,
+            ));
         }
+        Ok(Self::with_backend(session_manager, backend))
     }
 
     /// Creates a portal with specific session mode.
@@ -93,11 +443,20 @@ impl RemoteDesktopPortal {
         mode: RemoteDesktopMode,
         backend: Arc<dyn CompositorBackend>,
     ) -> Self {
+        let pointer_locks = PointerLocks::watching(&session_manager);
         Self {
             session_manager,
             session_mode: mode,
             consent_provider: Arc::new(AutoApproveProvider::instant()),
             backend,
+            transport: None,
+            captures: SessionCaptures::default(),
+            capture_tier: None,
+            pointer_locks,
+            outputs: Arc::default(),
+            clamp_absolute: false,
+            clipboard: SelectionTarget::default(),
+            version: PORTAL_VERSION,
         }
     }
 
@@ -111,24 +470,108 @@ impl RemoteDesktopPortal {
         consent_provider: Arc<dyn ConsentProvider>,
         backend: Arc<dyn CompositorBackend>,
     ) -> Self {
+        let pointer_locks = PointerLocks::watching(&session_manager);
         Self {
             session_manager,
             session_mode: mode,
             consent_provider,
             backend,
+            transport: None,
+            captures: SessionCaptures::default(),
+            capture_tier: None,
+            pointer_locks,
+            outputs: Arc::default(),
+            clamp_absolute: false,
+            clipboard: SelectionTarget::default(),
+            version: PORTAL_VERSION,
         }
     }
 
+    /// Takes pointer locks through `transport`, the way session input
+    /// reaches a separate compositor service, instead of the backend.
+    #[must_use]
+    pub fn with_transport(mut self, transport: Arc<dyn CompositorTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Reports `version` instead of [`PORTAL_VERSION`], for clients that
+    /// mishandle newer versions.
+    ///
+    /// Methods introduced after `version` are refused. A version above
+    /// what this build implements is lowered to [`PORTAL_VERSION`].
+    #[must_use]
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version.min(PORTAL_VERSION);
+        self
+    }
+
+    /// Clamps absolute motion outside its output to the output's edge
+    /// instead of rejecting it.
+    #[must_use]
+    pub fn with_clamp_absolute(mut self, clamp: bool) -> Self {
+        self.clamp_absolute = clamp;
+        self
+    }
+
+    /// Sets the desktop clipboard that `SetSelection` hands selections
+    /// to.
+    ///
+    /// Without one, clients are told clipboard transfer is unsupported.
+    #[must_use]
+    pub fn with_clipboard(mut self, clipboard: Arc<dyn Clipboard>) -> Self {
+        self.clipboard.clipboard = Some(clipboard);
+        self
+    }
+
+    /// Sets the policy applied to clipboard selections before transfer.
+    ///
+    /// The default allows every transfer.
+    #[must_use]
+    pub fn with_clipboard_interceptor(
+        mut self,
+        interceptor: Arc<dyn ClipboardInterceptor>,
+    ) -> Self {
+        self.clipboard.interceptor = interceptor;
+        self
+    }
+
+    /// Recent clipboard decisions, oldest first.
+    #[must_use]
+    pub fn clipboard_history(&self) -> Vec<ClipboardRecord> {
+        self.clipboard.history.records()
+    }
+
+    /// Reloads output geometry from the backend.
+    ///
+    /// Absolute motion is checked against it; see
+    /// [`Self::with_clamp_absolute`]. Called when capabilities change,
+    /// and should be called once before serving. If the backend cannot
+    /// enumerate outputs the known geometry is cleared, and motion passes
+    /// through unchecked. Returns the number of outputs now known.
+    pub async fn refresh_outputs(&self) -> usize {
+        let outputs = self.backend.enumerate_outputs().await.unwrap_or_else(|e| {
+            warn!(error = %e, "Failed to enumerate outputs");
+            Vec::new()
+        });
+        let count = outputs.len();
+        *self.outputs.write().await = outputs.into_iter().map(|o| (o.stream, o)).collect();
+
+        debug!(count, "Output geometry refreshed");
+        count
+    }
+
     /// Helper to request consent for device access.
     async fn request_consent_for_devices(
         &self,
-        session_id: SessionId,
+        session: &SessionHandle,
         app_id: String,
         device_types: DeviceType,
     ) -> bool {
         let request = ConsentRequest {
-            session_id,
+            session_id: session.id().clone(),
             app_id,
+            verified_app_id: session.verified_app_id().await,
             device_types,
             include_screen_capture: self.session_mode.has_capture(),
             parent_window: None,
@@ -142,6 +585,174 @@ impl RemoteDesktopPortal {
         result.is_granted()
     }
 
+    /// Tells the client and the user that the portal closed a session.
+    async fn report_closed(&self, ctxt: &SignalContext<'_>, session_id: &SessionId, reason: &str) {
+        self.captures.stop(session_id);
+        match ObjectPath::try_from(session_id.as_str()) {
+            Ok(path) => {
+                if let Err(e) = Self::session_closed(ctxt, path, reason).await {
+                    warn!(session = %session_id, error = %e, "Failed to emit SessionClosed");
+                }
+            },
+            Err(e) => warn!(session = %session_id, error = %e, "Invalid session path"),
+        }
+        self.consent_provider
+            .notify_session_ended(session_id, reason)
+            .await;
+    }
+
+    /// Emits `SessionModeChanged` whenever the session's mode changes,
+    /// stopping its capture streams once capture is gone.
+    fn forward_mode_changes(&self, ctxt: &SignalContext<'_>, session: &SessionHandle) {
+        let mut changes = session.subscribe_mode();
+        let ctxt = ctxt.to_owned();
+        let captures = self.captures.clone();
+        tokio::spawn(async move {
+            loop {
+                let change = match changes.recv().await {
+                    Ok(change) => change,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if !change.mode.has_capture() {
+                    captures.stop(&change.session_id);
+                }
+                let Ok(path) = ObjectPath::try_from(change.session_id.as_str()) else {
+                    continue;
+                };
+                if let Err(e) = Self::session_mode_changed(&ctxt, path, change.mode.into()).await {
+                    warn!(session = %change.session_id, error = %e, "Failed to emit SessionModeChanged");
+                }
+                info!(
+                    session = %change.session_id,
+                    from = %change.previous,
+                    to = %change.mode,
+                    "Session mode changed"
+                );
+            }
+        });
+    }
+
+    /// Pauses or resumes the capture streams of a started session.
+    async fn set_capture_paused(
+        &self,
+        ctxt: &SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+        paused: bool,
+    ) -> zbus::fdo::Result<()> {
+        let session_id = SessionId::new(session_handle.as_str());
+
+        let Some(session) = self.session_manager.get_session(&session_id).await else {
+            return Err(zbus::fdo::Error::Failed("Session not found".into()));
+        };
+        if session.state().await != SessionState::Active {
+            return Err(zbus::fdo::Error::Failed("Session not started".into()));
+        }
+        let mode = session.mode().await;
+        if !mode.has_capture() {
+            return Err(zbus::fdo::Error::NotSupported(format!(
+                "screen capture not available in {mode} mode"
+            )));
+        }
+
+        if self.captures.set_paused(&session_id, paused) {
+            if let Err(e) = Self::capture_paused_changed(ctxt, session_handle, paused).await {
+                warn!(session = %session_id, error = %e, "Failed to emit CapturePausedChanged");
+            }
+            info!(session = %session_id, paused, "Capture pause changed");
+        }
+        Ok(())
+    }
+
+    /// Locks or releases the pointer of a started session.
+    ///
+    /// Only locking needs input and an authorized pointer; a session that
+    /// has lost either can still release its lock.
+    async fn set_pointer_lock(
+        &self,
+        ctxt: &SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+        locked: bool,
+    ) -> zbus::fdo::Result<()> {
+        let session_id = SessionId::new(session_handle.as_str());
+
+        let Some(session) = self.session_manager.get_session(&session_id).await else {
+            return Err(zbus::fdo::Error::Failed("Session not found".into()));
+        };
+        if session.state().await != SessionState::Active {
+            return Err(zbus::fdo::Error::Failed("Session not started".into()));
+        }
+        if self.pointer_locks.is_locked(&session_id) == locked {
+            return Ok(());
+        }
+        if locked {
+            let mode = session.mode().await;
+            if !mode.has_input() {
+                return Err(zbus::fdo::Error::NotSupported(format!(
+                    "input not available in {mode} mode"
+                )));
+            }
+            if !session.authorized_devices().await.has_pointer() {
+                return Err(zbus::fdo::Error::AccessDenied(
+                    "Pointer not authorized for this session".into(),
+                ));
+            }
+        }
+
+        // Release where the lock was taken, even after a backend switch
+        let target = match self.pointer_locks.locked_by(&session_id) {
+            Some(target) => target,
+            None => match &self.transport {
+                Some(transport) => LockTarget::Transport(Arc::clone(transport)),
+                None => LockTarget::Backend(Arc::clone(&self.backend), session.seat().await),
+            },
+        };
+        target.set(&session_id, locked).await?;
+        if locked {
+            self.pointer_locks.insert(session_id.clone(), target);
+        } else {
+            self.pointer_locks.remove(&session_id);
+        }
+
+        if let Err(e) = Self::pointer_lock_changed(ctxt, session_handle, locked).await {
+            warn!(session = %session_id, error = %e, "Failed to emit PointerLockChanged");
+        }
+        info!(session = %session_id, locked, "Pointer lock changed");
+        Ok(())
+    }
+
+    /// Hands a session's pointer transform to the compositor, through the
+    /// transport its input takes or else the backend.
+    ///
+    /// Motion is forwarded as the client sent it, so a compositor that
+    /// cannot transform it leaves it unchanged.
+    async fn send_pointer_transform(&self, session_id: &SessionId, transform: PointerTransform) {
+        if transform.is_identity() {
+            return;
+        }
+        let sent = match &self.transport {
+            Some(transport) => transport
+                .set_pointer_transform(session_id, transform)
+                .await
+                .map_err(|e| e.to_string()),
+            None => self
+                .backend
+                .set_pointer_transform(session_id, transform)
+                .await
+                .map_err(|e| e.to_string()),
+        };
+        match sent {
+            Ok(()) => debug!(session = %session_id, ?transform, "Pointer transform set"),
+            Err(e) => warn!(session = %session_id, error = %e, "Pointer transform not applied"),
+        }
+    }
+
+    /// Returns `true` if the session holds the pointer lock.
+    #[must_use]
+    pub fn is_pointer_locked(&self, session_id: &SessionId) -> bool {
+        self.pointer_locks.is_locked(session_id)
+    }
+
     /// Returns a reference to the session manager.
     #[must_use]
     pub fn session_manager(&self) -> &SessionManager {
@@ -158,6 +769,547 @@ impl RemoteDesktopPortal {
     pub fn set_session_mode(&mut self, mode: RemoteDesktopMode) {
         self.session_mode = mode;
     }
+
+    /// Injects a session's event through the current backend, on the seat
+    /// the session was created for.
+    ///
+    /// This is where events from the session manager go when no
+    /// [`CompositorTransport`] carries them to a separate compositor
+    /// service.
+    pub async fn inject(&self, event: StampedEvent) -> BackendResult<()> {
+        self.backend
+            .inject_input_to_seat(event.seat.as_deref(), event.event)
+            .await
+    }
+
+    /// Switches to another compositor backend (e.g., on failover).
+    ///
+    /// Applies to sessions started afterwards. When the portal is already
+    /// served, emit `capabilities_changed` and
+    /// `supported_input_events_changed` so clients re-read both.
+    pub fn set_backend(&mut self, backend: Arc<dyn CompositorBackend>) {
+        self.backend = backend;
+    }
+
+    /// Adopts a capability snapshot from
+    /// [`CompositorBackend::capability_updates`].
+    ///
+    /// New sessions get the mode the capabilities allow; existing ones are
+    /// degraded to fit, which emits `SessionModeChanged` for each session
+    /// whose mode drops. Sessions left without input release their pointer
+    /// lock. Output geometry is reloaded. Returns the new portal mode. As
+    /// with [`Self::set_backend`], emit `capabilities_changed` afterwards.
+    pub async fn apply_capabilities(&mut self, caps: &BackendCapabilities) -> RemoteDesktopMode {
+        self.session_mode =
+            RemoteDesktopMode::from_capabilities(caps.can_capture_screen, caps.can_inject_input());
+        let capabilities = SessionCapabilities {
+            capture_available: caps.can_capture_screen,
+            input_available: caps.can_inject_input(),
+            capture_tier: self.capture_tier,
+        };
+        for id in self.session_manager.session_ids().await {
+            let Some(session) = self.session_manager.get_session(&id).await else {
+                continue;
+            };
+            if session.set_capabilities(capabilities).await.has_input() {
+                continue;
+            }
+            if let Some(target) = self.pointer_locks.remove(&id) {
+                match target.set(&id, false).await {
+                    Ok(()) => info!(session = %id, "Pointer lock released with input"),
+                    Err(e) => warn!(session = %id, error = %e, "Failed to release pointer lock"),
+                }
+            }
+        }
+        self.refresh_outputs().await;
+        info!(backend = %caps.backend_name, mode = %self.session_mode, "Backend capabilities changed");
+        self.session_mode
+    }
+
+    /// Moves the portal onto a backend re-selected at runtime, e.g. after
+    /// the display server changed.
+    ///
+    /// Sessions survive the switch: their input already goes through the
+    /// session manager, and their modes are degraded as in
+    /// [`Self::apply_capabilities`] if the new backend offers less. Capture
+    /// streams belong to the old backend, so they are stopped, and started
+    /// sessions that keep capture get fresh streams from the new one,
+    /// paused if their capture was.
+    /// Returns those sessions with their new streams, to be announced with
+    /// `streams_changed`; as with [`Self::set_backend`], emit
+    /// `capabilities_changed` too.
+    pub async fn switch_backend(
+        &mut self,
+        backend: Arc<dyn CompositorBackend>,
+    ) -> Vec<(SessionId, Vec<StreamInfo>)> {
+        let caps = backend.capabilities();
+        self.set_backend(backend);
+        self.apply_capabilities(&caps).await;
+
+        let mut migrated = Vec::new();
+        for session_id in self.captures.stop_all() {
+            let Some(session) = self.session_manager.get_session(&session_id).await else {
+                continue;
+            };
+            if session.state().await != SessionState::Active || !session.mode().await.has_capture()
+            {
+                continue;
+            }
+            let mut streams = Vec::new();
+            for (info, handle) in start_output_streams(self.backend.as_ref(), &session_id).await {
+                self.captures.insert(&session_id, info.output_index, handle);
+                streams.push(info);
+            }
+            migrated.push((session_id, streams));
+        }
+        migrated
+    }
+
+    /// Tells clients which streams their sessions moved to after
+    /// [`Self::switch_backend`].
+    pub async fn announce_streams(
+        ctxt: &SignalContext<'_>,
+        migrated: &[(SessionId, Vec<StreamInfo>)],
+    ) {
+        for (session_id, streams) in migrated {
+            let Ok(path) = ObjectPath::try_from(session_id.as_str()) else {
+                continue;
+            };
+            if let Err(e) = Self::streams_changed(ctxt, path, streams_value(streams)).await {
+                warn!(session = %session_id, error = %e, "Failed to emit StreamsChanged");
+            }
+        }
+    }
+
+    /// Closes a session and reports it with `reason`.
+    ///
+    /// Held keys and buttons are released, capture stops, the client gets
+    /// `SessionClosed` and the user is told. Returns `false` if the
+    /// session was already gone.
+    async fn close_and_report(
+        &self,
+        ctxt: &SignalContext<'_>,
+        session_id: &SessionId,
+        reason: &str,
+    ) -> bool {
+        if !self
+            .captures
+            .close(&self.session_manager, session_id)
+            .await
+            .existed()
+        {
+            debug!(session = %session_id, reason, "Session to close was not open");
+            return false;
+        }
+        self.report_closed(ctxt, session_id, reason).await;
+        true
+    }
+
+    /// Closes a session the compositor revoked.
+    ///
+    /// Held keys and buttons are released, capture stops, and the client
+    /// gets `SessionClosed` with [`REVOKED_REASON`]. Returns `false` if
+    /// the session was already gone.
+    pub async fn revoke_session(
+        &self,
+        ctxt: &SignalContext<'_>,
+        revocation: &SessionRevocation,
+    ) -> bool {
+        let session_id = &revocation.session_id;
+        if !self
+            .close_and_report(ctxt, session_id, REVOKED_REASON)
+            .await
+        {
+            return false;
+        }
+        info!(session = %session_id, reason = %revocation.reason, "Session revoked by compositor");
+        true
+    }
+
+    /// Closes a session at an operator's request.
+    ///
+    /// As with [`Self::revoke_session`], but the client gets
+    /// `SessionClosed` with [`ADMIN_REVOKED_REASON`]. Returns `false` if
+    /// the session was already gone.
+    pub async fn revoke_by_admin(&self, ctxt: &SignalContext<'_>, session_id: &SessionId) -> bool {
+        if !self
+            .close_and_report(ctxt, session_id, ADMIN_REVOKED_REASON)
+            .await
+        {
+            return false;
+        }
+        info!(session = %session_id, "Session revoked by administrator");
+        true
+    }
+
+    /// Closes every session the compositor revokes through
+    /// [`CompositorBackend::session_revocations`].
+    ///
+    /// Runs until the backend's revocation channel closes.
+    pub async fn follow_revocations(
+        iface: InterfaceRef<Self>,
+        mut revocations: broadcast::Receiver<SessionRevocation>,
+    ) {
+        loop {
+            let revocation = match revocations.recv().await {
+                Ok(revocation) => revocation,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(missed, "Missed session revocations");
+                    continue;
+                },
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            let portal = iface.get().await;
+            portal
+                .revoke_session(iface.signal_context(), &revocation)
+                .await;
+        }
+    }
+
+    /// Closes a session that spent its event budget.
+    ///
+    /// As with [`Self::revoke_session`], but the client gets
+    /// `SessionClosed` with [`EVENT_BUDGET_REASON`]. Returns `false` if
+    /// the session was already gone.
+    pub async fn close_exhausted(&self, ctxt: &SignalContext<'_>, session_id: &SessionId) -> bool {
+        if !self
+            .close_and_report(ctxt, session_id, EVENT_BUDGET_REASON)
+            .await
+        {
+            return false;
+        }
+        info!(session = %session_id, "Session closed after spending its event budget");
+        true
+    }
+
+    /// Closes every session reported by
+    /// [`SessionManager::budget_exhaustions`].
+    ///
+    /// Runs until the session manager is dropped.
+    pub async fn follow_event_budgets(
+        iface: InterfaceRef<Self>,
+        mut exhausted: broadcast::Receiver<SessionId>,
+    ) {
+        loop {
+            let session_id = match exhausted.recv().await {
+                Ok(session_id) => session_id,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(missed, "Missed event budget exhaustions");
+                    continue;
+                },
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            let portal = iface.get().await;
+            portal
+                .close_exhausted(iface.signal_context(), &session_id)
+                .await;
+        }
+    }
+
+    /// Closes the sessions owned by a connection that left the bus.
+    ///
+    /// Each gets `SessionClosed` with [`OWNER_LEFT_REASON`] and the user
+    /// is told, as for any other close. Returns the closed sessions.
+    pub async fn close_owned_by(
+        &self,
+        ctxt: &SignalContext<'_>,
+        unique_name: &str,
+    ) -> Vec<SessionId> {
+        let closed = self
+            .session_manager
+            .close_sessions_owned_by(unique_name)
+            .await;
+        for session_id in &closed {
+            self.report_closed(ctxt, session_id, OWNER_LEFT_REASON)
+                .await;
+        }
+        closed
+    }
+
+    /// Closes sessions that stayed ownerless past the session manager's
+    /// orphan grace, reporting them with [`ORPHANED_REASON`].
+    ///
+    /// Returns the reaped sessions.
+    pub async fn reap_orphans(&self, ctxt: &SignalContext<'_>) -> Vec<SessionId> {
+        let reaped = self.session_manager.reap_orphans().await;
+        for session_id in &reaped {
+            self.report_closed(ctxt, session_id, ORPHANED_REASON).await;
+        }
+        reaped
+    }
+
+    /// Closes sessions that sent no input for the session manager's idle
+    /// timeout, reporting them with [`IDLE_REASON`].
+    ///
+    /// Returns the reaped sessions.
+    pub async fn reap_idle(&self, ctxt: &SignalContext<'_>) -> Vec<SessionId> {
+        let reaped = self.session_manager.reap_idle().await;
+        for session_id in &reaped {
+            self.report_closed(ctxt, session_id, IDLE_REASON).await;
+        }
+        reaped
+    }
+
+    /// Closes a connection's sessions when it leaves the bus.
+    ///
+    /// When a well-known name changes hands, as when the portal frontend
+    /// is restarted with `--replace`, the old owner's sessions move to the
+    /// new one so its departure does not close them. A released name
+    /// leaves them orphaned until the grace period runs out.
+    ///
+    /// `changes` is the bus's `NameOwnerChanged` stream; runs until it
+    /// ends.
+    pub async fn follow_owners(
+        iface: InterfaceRef<Self>,
+        mut changes: zbus::fdo::NameOwnerChangedStream<'static>,
+    ) {
+        while let Some(change) = changes.next().await {
+            let Ok(args) = change.args() else {
+                continue;
+            };
+            let portal = iface.get().await;
+            match args.name() {
+                zbus::names::BusName::Unique(name) => {
+                    if args.new_owner().is_none() {
+                        portal
+                            .close_owned_by(iface.signal_context(), name.as_str())
+                            .await;
+                    }
+                },
+                zbus::names::BusName::WellKnown(_) => {
+                    let Some(old) = args.old_owner().as_ref() else {
+                        continue;
+                    };
+                    let new = args.new_owner().as_ref().map(UniqueName::as_str);
+                    portal
+                        .session_manager
+                        .transfer_ownership(old.as_str(), new)
+                        .await;
+                },
+            }
+        }
+    }
+
+    /// Reaps orphaned sessions every `interval`.
+    ///
+    /// Runs until the task is aborted.
+    pub async fn follow_orphans(iface: InterfaceRef<Self>, interval: std::time::Duration) {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            let portal = iface.get().await;
+            portal.reap_orphans(iface.signal_context()).await;
+        }
+    }
+
+    /// Reaps idle sessions every `interval`.
+    ///
+    /// Runs until the task is aborted.
+    pub async fn follow_idle(iface: InterfaceRef<Self>, interval: std::time::Duration) {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            let portal = iface.get().await;
+            portal.reap_idle(iface.signal_context()).await;
+        }
+    }
+
+    /// Tells every started session with input where its input now lands.
+    ///
+    /// Emits `FocusChanged` per session, addressed to the session's owner
+    /// so other clients on the bus don't learn what the user is working
+    /// in. Ownerless sessions are skipped. Returns how many were told.
+    pub async fn report_focus(&self, ctxt: &SignalContext<'_>, focus: &FocusChanged) -> usize {
+        let dict = focus_dict(focus);
+        let mut reported = 0;
+        for id in self.session_manager.session_ids().await {
+            let Some(session) = self.session_manager.get_session(&id).await else {
+                continue;
+            };
+            if session.state().await != SessionState::Active || !session.mode().await.has_input() {
+                continue;
+            }
+            // Where a session's input lands is only its owner's business
+            let Some(owner) = self.session_manager.owner_of(&id).await else {
+                continue;
+            };
+            let Ok(path) = ObjectPath::try_from(id.as_str()) else {
+                continue;
+            };
+            let sent = ctxt
+                .connection()
+                .emit_signal(
+                    Some(owner.as_str()),
+                    ctxt.path(),
+                    <Self as zbus::Interface>::name(),
+                    "FocusChanged",
+                    &(path, &dict),
+                )
+                .await;
+            match sent {
+                Ok(()) => reported += 1,
+                Err(e) => warn!(session = %id, error = %e, "Failed to emit FocusChanged"),
+            }
+        }
+        debug!(app_id = %focus.app_id, sessions = reported, "Focus changed");
+        reported
+    }
+
+    /// Reports every focus change from
+    /// [`CompositorBackend::focus_changes`] to the clients.
+    ///
+    /// Runs until the backend's focus channel closes, at once for
+    /// backends that cannot report focus.
+    pub async fn follow_focus(
+        iface: InterfaceRef<Self>,
+        mut changes: broadcast::Receiver<FocusChanged>,
+    ) {
+        loop {
+            let focus = match changes.recv().await {
+                Ok(focus) => focus,
+                // Only the latest focus matters
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            let portal = iface.get().await;
+            portal.report_focus(iface.signal_context(), &focus).await;
+        }
+    }
+
+    /// Records the capture tier capability detection settled on.
+    pub fn set_capture_tier(&mut self, tier: Option<CaptureTierInfo>) {
+        self.capture_tier = tier;
+    }
+
+    /// Extended capabilities of the active backend.
+    pub async fn portal_capabilities(&self) -> PortalCapabilities {
+        let mut capabilities =
+            PortalCapabilities::query(self.backend.as_ref(), self.capture_tier).await;
+        capabilities.clipboard = cfg!(feature = "clipboard")
+            && self.clipboard.clipboard.is_some()
+            && self.offers(CLIPBOARD_VERSION);
+        capabilities.gestures &= self.offers(GESTURE_VERSION);
+        capabilities
+    }
+
+    /// Whether the reported version includes methods introduced in
+    /// `required`.
+    fn offers(&self, required: u32) -> bool {
+        self.version >= required
+    }
+
+    /// Fails with `NotSupported` unless the reported version includes
+    /// `method`, introduced in `required`.
+    fn require_version(&self, required: u32, method: &'static str) -> zbus::fdo::Result<()> {
+        version::require(self.version, required, method)
+            .map_err(|e| zbus::fdo::Error::NotSupported(e.to_string()))
+    }
+
+    /// Sends a gesture through a session, stamped as the options say.
+    async fn send_gesture(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        options: &HashMap<String, OwnedValue>,
+        event: InputEvent,
+    ) -> zbus::fdo::Result<()> {
+        let session_id = SessionId::new(session_handle.as_str());
+        let Some(session) = self.session_manager.get_session(&session_id).await else {
+            return Err(zbus::fdo::Error::Failed("Session not found".into()));
+        };
+
+        let client_timestamp = PortalOptions::new(options).client_timestamp();
+        session
+            .send_stamped_event(event, client_timestamp)
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))
+    }
+
+    /// Hands a selection from a started session with input to the desktop
+    /// clipboard.
+    #[cfg(feature = "clipboard")]
+    async fn transfer_selection(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        mime_type: &str,
+        content: impl std::future::Future<Output = ion_core::Result<Vec<u8>>>,
+    ) -> zbus::fdo::Result<()> {
+        self.require_version(CLIPBOARD_VERSION, "SetSelection")?;
+        if self.clipboard.clipboard.is_none() {
+            return Err(zbus::fdo::Error::NotSupported(
+                "no desktop clipboard to transfer to".into(),
+            ));
+        }
+        let session_id = SessionId::new(session_handle.as_str());
+        let Some(session) = self.session_manager.get_session(&session_id).await else {
+            return Err(zbus::fdo::Error::Failed("Session not found".into()));
+        };
+        if session.state().await != SessionState::Active {
+            return Err(zbus::fdo::Error::Failed("Session not started".into()));
+        }
+        if !session.mode().await.has_input() {
+            warn!(session = %session_id, mime_type, "Clipboard write refused without input");
+            return Err(zbus::fdo::Error::AccessDenied(
+                "Session has no input access".into(),
+            ));
+        }
+
+        let content = content
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        let len = content.len();
+        self.clipboard
+            .transfer(&session_id, mime_type, content)
+            .map_err(|e| zbus::fdo::Error::AccessDenied(e.to_string()))?;
+        debug!(session = %session_id, mime_type, len, "Clipboard selection transferred");
+        Ok(())
+    }
+
+    /// A failed response whose `error_detail` tells the client why.
+    fn failure(
+        reason: FailureReason,
+        message: impl std::fmt::Display,
+    ) -> PortalResult<HashMap<String, OwnedValue>> {
+        (
+            ResponseCode::Other as u32,
+            reason.results(&message.to_string()),
+        )
+    }
+
+    /// A failed response for an error from the session layer.
+    fn error_response(&self, error: &ion_core::Error) -> PortalResult<HashMap<String, OwnedValue>> {
+        let reason =
+            FailureReason::from_error(error, self.session_mode, &self.backend.capabilities());
+        Self::failure(reason, error)
+    }
+
+    /// Fails if the backend cannot provide the portal's mode at all.
+    fn check_capabilities(&self) -> Option<PortalResult<HashMap<String, OwnedValue>>> {
+        let caps = self.backend.capabilities();
+        let reason = FailureReason::missing_capability(self.session_mode, &caps)?;
+        warn!(backend = %caps.backend_name, mode = %self.session_mode, %reason, "Capability missing");
+        Some(Self::failure(
+            reason,
+            format!(
+                "backend {} cannot provide {} mode",
+                caps.backend_name, self.session_mode
+            ),
+        ))
+    }
+
+    /// Fails unless the backend has a seat named `seat`.
+    async fn check_seat(&self, seat: &str) -> BackendResult<()> {
+        if self
+            .backend
+            .enumerate_seats()
+            .await?
+            .iter()
+            .any(|s| s == seat)
+        {
+            Ok(())
+        } else {
+            Err(BackendError::SeatNotFound(seat.to_string()))
+        }
+    }
 }
 
 /// D-Bus interface implementation.
@@ -167,10 +1319,14 @@ impl RemoteDesktopPortal {
 #[zbus::interface(name = "org.freedesktop.impl.portal.RemoteDesktop")]
 impl RemoteDesktopPortal {
     /// Creates a new remote desktop session.
-    #[instrument(skip(self, _connection, options), fields(app_id = %app_id))]
+    ///
+    /// A failed response carries an `error_detail` entry saying why; see
+    /// [`crate::failure`].
+    #[instrument(skip(self, connection, options), fields(app_id = %app_id))]
     async fn create_session(
         &self,
-        #[zbus(connection)] _connection: &zbus::Connection,
+        #[zbus(connection)] connection: &zbus::Connection,
+        #[zbus(header)] header: zbus::message::Header<'_>,
         handle: ObjectPath<'_>,
         session_handle: ObjectPath<'_>,
         app_id: String,
@@ -179,30 +1335,76 @@ impl RemoteDesktopPortal {
         info!("CreateSession called");
         debug!(?handle, ?session_handle, ?options, "Session parameters");
 
-        let session_id = SessionId::new(session_handle.as_str());
+        let session_id = match SessionId::new_validated(session_handle.as_str()) {
+            Ok(id) => id,
+            Err(e) => {
+                warn!(error = %e, "Rejected session handle");
+                return Self::failure(FailureReason::InvalidSessionHandle, e);
+            },
+        };
+        let rate_profile = parse_rate_profile(&options);
+        let exclusive = PortalOptions::new(&options)
+            .get_bool(PortalOptions::EXCLUSIVE_KEY)
+            .unwrap_or(false);
+        let seat = PortalOptions::new(&options).get_string(PortalOptions::SEAT_KEY);
+        if let Some(seat) = &seat {
+            if let Err(e) = self.check_seat(seat).await {
+                warn!(seat = %seat, error = %e, "Rejected seat");
+                return Self::failure(FailureReason::SeatUnavailable, e);
+            }
+        }
 
-        match self
-            .session_manager
-            .create_session(session_id, app_id)
-            .await
-        {
+        let created = if exclusive {
+            self.session_manager
+                .create_exclusive_session(session_id, app_id)
+                .await
+        } else {
+            self.session_manager
+                .create_session(session_id, app_id)
+                .await
+        };
+
+        match created {
             Ok(session) => {
+                if let Some(sender) = header.sender() {
+                    self.session_manager
+                        .set_owner(session.id(), sender.as_str())
+                        .await;
+                    let verified = sender_app_id(connection, sender.as_str()).await;
+                    session.set_verified_app_id(verified).await;
+                }
+                session.set_rate_profile(rate_profile).await;
+
                 let mut result = HashMap::new();
+                if let Some(seat) = &seat {
+                    result.insert(
+                        PortalOptions::SEAT_KEY.to_string(),
+                        Value::from(seat.as_str()).try_to_owned().unwrap(),
+                    );
+                }
+                session.set_seat(seat).await;
                 result.insert(
                     "session_id".to_string(),
                     Value::from(session.id().as_str()).try_to_owned().unwrap(),
                 );
+                result.insert(
+                    RateProfile::OPTION_KEY.to_string(),
+                    Value::from(rate_profile.name()).try_to_owned().unwrap(),
+                );
                 info!(session = %session.id(), "Session created successfully");
                 (ResponseCode::Success as u32, result)
             },
             Err(e) => {
                 error!(error = %e, "Failed to create session");
-                (ResponseCode::Other as u32, HashMap::new())
+                self.error_response(&e)
             },
         }
     }
 
     /// Selects which device types the session should have access to.
+    ///
+    /// Fails with an `error_detail` reason of `no_capture_backend` or
+    /// `no_input_backend` if the backend cannot provide the portal's mode.
     #[instrument(skip(self, _connection, options))]
     async fn select_devices(
         &self,
@@ -218,26 +1420,46 @@ impl RemoteDesktopPortal {
 
         let Some(session) = self.session_manager.get_session(&session_id).await else {
             warn!(session = %session_id, "Session not found");
-            return (ResponseCode::Other as u32, HashMap::new());
+            return Self::failure(
+                FailureReason::SessionNotFound,
+                format!("session not found: {session_id}"),
+            );
         };
+        if let Some(failed) = self.check_capabilities() {
+            return failed;
+        }
 
         // Parse requested device types from options
-        let requested_types = options
-            .get("types")
-            .and_then(|v| v.downcast_ref::<u32>().ok())
+        let requested_types = PortalOptions::new(&options)
+            .get_u32(PortalOptions::TYPES_KEY)
             .unwrap_or(DeviceType::desktop_standard().bits());
 
-        let device_types = DeviceType::from(requested_types);
+        let mut device_types = DeviceType::from(requested_types);
         debug!(?device_types, "Requested device types");
 
+        // Drop gestures the backend cannot inject before asking, so the
+        // dialog lists only what will actually be granted
+        if device_types.has_gesture()
+            && !self
+                .backend
+                .supported_input_events()
+                .contains(&InputEventKind::Gesture)
+        {
+            debug!("Backend cannot inject gestures, not granting them");
+            device_types.remove(DeviceType::GESTURE);
+        }
+
+        // Likewise input devices when the mode provides no input
+        device_types &= self.session_mode.capabilities_mask();
+
         // Request user consent before granting device access
         let consent_result = self
-            .request_consent_for_devices(session_id.clone(), app_id.clone(), device_types)
+            .request_consent_for_devices(&session, app_id.clone(), device_types)
             .await;
 
         if !consent_result {
             warn!(session = %session_id, "User denied device access");
-            return (ResponseCode::Other as u32, HashMap::new());
+            return Self::failure(FailureReason::ConsentDenied, "access was not granted");
         }
 
         match session.select_devices(device_types).await {
@@ -247,7 +1469,7 @@ impl RemoteDesktopPortal {
             },
             Err(e) => {
                 error!(error = %e, "Failed to select devices");
-                (ResponseCode::Other as u32, HashMap::new())
+                self.error_response(&e)
             },
         }
     }
@@ -259,27 +1481,65 @@ impl RemoteDesktopPortal {
     /// - `session_mode`: Operating mode (0=None, 1=ViewOnly, 2=InputOnly, 3=Full)
     /// - `capture_available`: Whether screen capture is available
     /// - `input_available`: Whether input injection is available
-    #[instrument(skip(self, _connection, _options))]
+    /// - `streams`: Captured outputs as in the `ScreenCast` `Start` response,
+    ///   only in modes with screen capture
+    ///
+    /// If the session requested exclusive control and preempts another
+    /// session, `SessionClosed` is emitted for that session with reason
+    /// `"preempted"`.
+    ///
+    /// The `pointer_sensitivity` (`d`) and `accel_profile` (`u`, `0` flat,
+    /// `1` adaptive) options ask the compositor to transform the session's
+    /// relative pointer motion.
+    ///
+    /// A failed response carries an `error_detail` entry with a stable
+    /// reason code instead; see [`crate::failure`].
+    #[instrument(skip(self, ctxt, options))]
     async fn start(
         &self,
-        #[zbus(connection)] _connection: &zbus::Connection,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
         handle: ObjectPath<'_>,
         session_handle: ObjectPath<'_>,
         app_id: String,
         parent_window: String,
-        _options: HashMap<String, OwnedValue>,
+        options: HashMap<String, OwnedValue>,
     ) -> PortalResult<HashMap<String, OwnedValue>> {
         info!("Start called");
 
+        let pointer_transform = parse_pointer_transform(&options);
+        // Persistence isn't supported yet; every session asks for consent
+        let options = PortalOptions::new(&options);
+        debug!(
+            persist_mode = ?options.persist_mode(),
+            has_restore_token = options.restore_token().is_some(),
+            "Persistence options"
+        );
+
         let session_id = SessionId::new(session_handle.as_str());
 
         let Some(session) = self.session_manager.get_session(&session_id).await else {
             warn!(session = %session_id, "Session not found");
-            return (ResponseCode::Other as u32, HashMap::new());
+            return Self::failure(
+                FailureReason::SessionNotFound,
+                format!("session not found: {session_id}"),
+            );
         };
+        if let Some(failed) = self.check_capabilities() {
+            return failed;
+        }
+
+        if let Err(e) = session.set_mode(self.session_mode).await {
+            error!(session = %session_id, error = %e, "Failed to set session mode");
+            return self.error_response(&e);
+        }
+
+        match self.session_manager.start_session(&session).await {
+            Ok(preempted) => {
+                if let Some(preempted) = preempted {
+                    self.report_closed(&ctxt, &preempted, PREEMPTED_REASON)
+                        .await;
+                }
 
-        match session.start().await {
-            Ok(()) => {
                 let mut result = HashMap::new();
 
                 // Standard portal response: authorized devices
@@ -300,6 +1560,28 @@ impl RemoteDesktopPortal {
                     OwnedValue::from(mode.has_input()),
                 );
 
+                if mode.has_capture() {
+                    let mut streams = Vec::new();
+                    for (info, handle) in
+                        start_output_streams(self.backend.as_ref(), &session_id).await
+                    {
+                        self.captures.insert(&session_id, info.output_index, handle);
+                        streams.push(info);
+                    }
+                    match OwnedValue::try_from(streams_value(&streams)) {
+                        Ok(value) => {
+                            result.insert("streams".to_string(), value);
+                        },
+                        Err(e) => warn!(error = %e, "Failed to serialize streams"),
+                    }
+                }
+
+                if mode.has_input() {
+                    self.send_pointer_transform(&session_id, pointer_transform)
+                        .await;
+                }
+                self.forward_mode_changes(&ctxt, &session);
+
                 info!(
                     session = %session_id,
                     mode = %mode,
@@ -309,17 +1591,114 @@ impl RemoteDesktopPortal {
             },
             Err(e) => {
                 error!(error = %e, "Failed to start session");
-                (ResponseCode::Other as u32, HashMap::new())
+                self.error_response(&e)
             },
         }
     }
 
+    /// Emitted when the portal closes a session on its own, e.g. when an
+    /// exclusive session is preempted.
+    #[zbus(signal)]
+    async fn session_closed(
+        ctxt: &SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+        reason: &str,
+    ) -> zbus::Result<()>;
+
+    /// Emitted when a started session's mode changes at runtime, e.g.
+    /// dropping to `InputOnly` when the compositor loses screen capture.
+    /// `mode` uses the `session_mode` values of the `Start` results.
+    #[zbus(signal)]
+    async fn session_mode_changed(
+        ctxt: &SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+        mode: u32,
+    ) -> zbus::Result<()>;
+
+    /// Emitted when a session's screen sharing is paused or resumed, so
+    /// the peer can show a placeholder instead of a stalled stream.
+    #[zbus(signal)]
+    async fn capture_paused_changed(
+        ctxt: &SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+        paused: bool,
+    ) -> zbus::Result<()>;
+
+    /// ionChannel extension: emitted when a session takes or releases the
+    /// pointer lock through `LockPointer` or `UnlockPointer`.
+    ///
+    /// Not emitted when the lock goes with a closing session.
+    #[zbus(signal)]
+    async fn pointer_lock_changed(
+        ctxt: &SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+        locked: bool,
+    ) -> zbus::Result<()>;
+
+    /// ionChannel extension: emitted when a started session's capture
+    /// moved to new streams, e.g. after the service switched backends.
+    ///
+    /// `streams` has the `a(ua{sv})` layout of the `Start` results; the
+    /// old `PipeWire` nodes are gone.
+    #[zbus(signal)]
+    async fn streams_changed(
+        ctxt: &SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+        streams: Value<'_>,
+    ) -> zbus::Result<()>;
+
+    /// ionChannel extension: emitted to the owners of started sessions
+    /// with input when the compositor moves input focus to another
+    /// surface.
+    ///
+    /// `focus` is an `a{sv}` as returned by `CurrentFocus`. Never emitted
+    /// by backends that cannot see the compositor's focus. Sent by
+    /// [`Self::report_focus`]; declared here for introspection.
+    #[zbus(signal)]
+    async fn focus_changed(
+        ctxt: &SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+        focus: &HashMap<String, OwnedValue>,
+    ) -> zbus::Result<()>;
+
+    /// ionChannel extension: the surface injected input currently lands
+    /// in.
+    ///
+    /// An `a{sv}` with `surface_title` (`s`), `app_id` (`s`) and, if
+    /// known, `geometry` (`(iiuu)`: x, y, width, height). Empty when the
+    /// backend cannot tell. Only sessions with input may ask.
+    #[instrument(skip(self))]
+    async fn current_focus(
+        &self,
+        session_handle: ObjectPath<'_>,
+    ) -> zbus::fdo::Result<HashMap<String, OwnedValue>> {
+        let session_id = SessionId::new(session_handle.as_str());
+        let Some(session) = self.session_manager.get_session(&session_id).await else {
+            return Err(zbus::fdo::Error::Failed("Session not found".into()));
+        };
+        if session.state().await != SessionState::Active {
+            return Err(zbus::fdo::Error::Failed("Session not started".into()));
+        }
+        if !session.mode().await.has_input() {
+            return Err(zbus::fdo::Error::AccessDenied(
+                "Session has no input access".into(),
+            ));
+        }
+        Ok(self
+            .backend
+            .current_focus()
+            .await
+            .as_ref()
+            .map(focus_dict)
+            .unwrap_or_default())
+    }
+
     /// Notifies the compositor of relative pointer motion.
-    #[instrument(skip(self, _options))]
+    #[instrument(skip(self, options))]
     async fn notify_pointer_motion(
         &self,
         session_handle: ObjectPath<'_>,
-        _options: HashMap<String, OwnedValue>,
+        options: HashMap<String, OwnedValue>,
         dx: f64,
         dy: f64,
     ) -> zbus::fdo::Result<()> {
@@ -329,8 +1708,9 @@ impl RemoteDesktopPortal {
             return Err(zbus::fdo::Error::Failed("Session not found".into()));
         };
 
+        let client_timestamp = PortalOptions::new(&options).client_timestamp();
         session
-            .send_event(InputEvent::PointerMotion { dx, dy })
+            .send_stamped_event(InputEvent::PointerMotion { dx, dy }, client_timestamp)
             .await
             .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
 
@@ -338,11 +1718,15 @@ impl RemoteDesktopPortal {
     }
 
     /// Notifies the compositor of absolute pointer motion.
-    #[instrument(skip(self, _options))]
+    ///
+    /// Ignored while the session holds the pointer lock. Coordinates
+    /// outside the stream's output are clamped or rejected depending on
+    /// [`Self::with_clamp_absolute`].
+    #[instrument(skip(self, options))]
     async fn notify_pointer_motion_absolute(
         &self,
         session_handle: ObjectPath<'_>,
-        _options: HashMap<String, OwnedValue>,
+        options: HashMap<String, OwnedValue>,
         stream: u32,
         x: f64,
         y: f64,
@@ -352,9 +1736,21 @@ impl RemoteDesktopPortal {
         let Some(session) = self.session_manager.get_session(&session_id).await else {
             return Err(zbus::fdo::Error::Failed("Session not found".into()));
         };
+        if self.pointer_locks.is_locked(&session_id) {
+            debug!(session = %session_id, "Pointer locked, ignoring absolute motion");
+            return Ok(());
+        }
+        let outputs = self.outputs.read().await;
+        let (x, y) = bound_absolute(&outputs, self.clamp_absolute, stream, x, y)
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        drop(outputs);
 
+        let client_timestamp = PortalOptions::new(&options).client_timestamp();
         session
-            .send_event(InputEvent::PointerMotionAbsolute { stream, x, y })
+            .send_stamped_event(
+                InputEvent::PointerMotionAbsolute { stream, x, y },
+                client_timestamp,
+            )
             .await
             .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
 
@@ -362,11 +1758,11 @@ impl RemoteDesktopPortal {
     }
 
     /// Notifies the compositor of a pointer button event.
-    #[instrument(skip(self, _options))]
+    #[instrument(skip(self, options))]
     async fn notify_pointer_button(
         &self,
         session_handle: ObjectPath<'_>,
-        _options: HashMap<String, OwnedValue>,
+        options: HashMap<String, OwnedValue>,
         button: i32,
         state: u32,
     ) -> zbus::fdo::Result<()> {
@@ -376,11 +1772,15 @@ impl RemoteDesktopPortal {
             return Err(zbus::fdo::Error::Failed("Session not found".into()));
         };
 
+        let client_timestamp = PortalOptions::new(&options).client_timestamp();
         session
-            .send_event(InputEvent::PointerButton {
-                button,
-                state: ButtonState::from(state),
-            })
+            .send_stamped_event(
+                InputEvent::PointerButton {
+                    button,
+                    state: ButtonState::from(state),
+                },
+                client_timestamp,
+            )
             .await
             .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
 
@@ -388,11 +1788,14 @@ impl RemoteDesktopPortal {
     }
 
     /// Notifies the compositor of pointer scroll/axis events.
-    #[instrument(skip(self, _options))]
+    ///
+    /// Options `axis_source` and `scroll_unit` (both `u`) describe the
+    /// scroll; they default to wheel and pixels.
+    #[instrument(skip(self, options))]
     async fn notify_pointer_axis(
         &self,
         session_handle: ObjectPath<'_>,
-        _options: HashMap<String, OwnedValue>,
+        options: HashMap<String, OwnedValue>,
         dx: f64,
         dy: f64,
     ) -> zbus::fdo::Result<()> {
@@ -402,8 +1805,13 @@ impl RemoteDesktopPortal {
             return Err(zbus::fdo::Error::Failed("Session not found".into()));
         };
 
+        let (source, unit) = scroll_options(&options);
+        let client_timestamp = PortalOptions::new(&options).client_timestamp();
         session
-            .send_event(InputEvent::PointerAxis { dx, dy })
+            .send_stamped_event(
+                InputEvent::scroll_with(dx, dy, source, unit),
+                client_timestamp,
+            )
             .await
             .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
 
@@ -411,11 +1819,11 @@ impl RemoteDesktopPortal {
     }
 
     /// Notifies the compositor of a keyboard keycode event.
-    #[instrument(skip(self, _options))]
+    #[instrument(skip(self, options, keycode), fields(keycode = %Sensitive(keycode)))]
     async fn notify_keyboard_keycode(
         &self,
         session_handle: ObjectPath<'_>,
-        _options: HashMap<String, OwnedValue>,
+        options: HashMap<String, OwnedValue>,
         keycode: i32,
         state: u32,
     ) -> zbus::fdo::Result<()> {
@@ -425,41 +1833,314 @@ impl RemoteDesktopPortal {
             return Err(zbus::fdo::Error::Failed("Session not found".into()));
         };
 
+        let client_timestamp = PortalOptions::new(&options).client_timestamp();
         session
-            .send_event(InputEvent::KeyboardKeycode {
-                keycode,
-                state: KeyState::from(state),
-            })
+            .send_stamped_event(
+                InputEvent::KeyboardKeycode {
+                    keycode,
+                    state: KeyState::from(state),
+                },
+                client_timestamp,
+            )
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Notifies the compositor of a keyboard keysym event.
+    #[instrument(skip(self, options, keysym), fields(keysym = %Sensitive(keysym)))]
+    async fn notify_keyboard_keysym(
+        &self,
+        session_handle: ObjectPath<'_>,
+        options: HashMap<String, OwnedValue>,
+        keysym: i32,
+        state: u32,
+    ) -> zbus::fdo::Result<()> {
+        let session_id = SessionId::new(session_handle.as_str());
+
+        let Some(session) = self.session_manager.get_session(&session_id).await else {
+            return Err(zbus::fdo::Error::Failed("Session not found".into()));
+        };
+
+        let client_timestamp = PortalOptions::new(&options).client_timestamp();
+        session
+            .send_stamped_event(
+                InputEvent::KeyboardKeysym {
+                    keysym,
+                    state: KeyState::from(state),
+                },
+                client_timestamp,
+            )
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Sets the keyboard modifier state.
+    ///
+    /// Masks follow `wl_keyboard.modifiers` and replace the seat's current
+    /// state, so the client's view of the modifiers cannot drift from the
+    /// compositor's.
+    #[instrument(skip(self, options))]
+    async fn notify_keyboard_modifiers(
+        &self,
+        session_handle: ObjectPath<'_>,
+        options: HashMap<String, OwnedValue>,
+        depressed: u32,
+        latched: u32,
+        locked: u32,
+        group: u32,
+    ) -> zbus::fdo::Result<()> {
+        let session_id = SessionId::new(session_handle.as_str());
+
+        let Some(session) = self.session_manager.get_session(&session_id).await else {
+            return Err(zbus::fdo::Error::Failed("Session not found".into()));
+        };
+
+        let client_timestamp = PortalOptions::new(&options).client_timestamp();
+        session
+            .send_stamped_event(
+                InputEvent::keyboard_modifiers(depressed, latched, locked, group),
+                client_timestamp,
+            )
             .await
             .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
 
         Ok(())
     }
 
-    /// Notifies the compositor of a keyboard keysym event.
-    #[instrument(skip(self, _options))]
-    async fn notify_keyboard_keysym(
+    /// ionChannel extension: notifies the compositor of a touchpad swipe
+    /// gesture.
+    ///
+    /// `phase` follows [`GesturePhase`]: 0 begin, 1 update, 2 end,
+    /// 3 cancel. Needs the session to hold gestures.
+    #[instrument(skip(self, options))]
+    async fn notify_gesture_swipe(
+        &self,
+        session_handle: ObjectPath<'_>,
+        options: HashMap<String, OwnedValue>,
+        phase: u32,
+        fingers: u32,
+        dx: f64,
+        dy: f64,
+    ) -> zbus::fdo::Result<()> {
+        self.require_version(GESTURE_VERSION, "NotifyGestureSwipe")?;
+        let phase = gesture_phase(phase)?;
+        self.send_gesture(
+            &session_handle,
+            &options,
+            InputEvent::GestureSwipe {
+                phase,
+                fingers,
+                dx,
+                dy,
+            },
+        )
+        .await
+    }
+
+    /// ionChannel extension: notifies the compositor of a touchpad pinch
+    /// gesture.
+    ///
+    /// `scale` is relative to the start of the gesture, `rotation` is in
+    /// degrees clockwise since the last update.
+    #[instrument(skip(self, options))]
+    #[allow(clippy::too_many_arguments)]
+    async fn notify_gesture_pinch(
+        &self,
+        session_handle: ObjectPath<'_>,
+        options: HashMap<String, OwnedValue>,
+        phase: u32,
+        fingers: u32,
+        dx: f64,
+        dy: f64,
+        scale: f64,
+        rotation: f64,
+    ) -> zbus::fdo::Result<()> {
+        self.require_version(GESTURE_VERSION, "NotifyGesturePinch")?;
+        let phase = gesture_phase(phase)?;
+        self.send_gesture(
+            &session_handle,
+            &options,
+            InputEvent::GesturePinch {
+                phase,
+                fingers,
+                dx,
+                dy,
+                scale,
+                rotation,
+            },
+        )
+        .await
+    }
+
+    /// ionChannel extension: notifies the compositor of a touchpad hold
+    /// gesture.
+    #[instrument(skip(self, options))]
+    async fn notify_gesture_hold(
+        &self,
+        session_handle: ObjectPath<'_>,
+        options: HashMap<String, OwnedValue>,
+        phase: u32,
+        fingers: u32,
+    ) -> zbus::fdo::Result<()> {
+        self.require_version(GESTURE_VERSION, "NotifyGestureHold")?;
+        let phase = gesture_phase(phase)?;
+        self.send_gesture(
+            &session_handle,
+            &options,
+            InputEvent::GestureHold { phase, fingers },
+        )
+        .await
+    }
+
+    /// Captures a single frame without starting a stream.
+    ///
+    /// Returns `(format, width, height, fd)`; the fd holds the pixels with
+    /// rows tightly packed. Only available in modes with screen capture,
+    /// and on backends that capture single frames, which the `screenshot`
+    /// capability reports. The COSMIC and Wayland backends don't yet, so
+    /// there this fails with `NotSupported`.
+    #[instrument(skip(self, options))]
+    async fn capture_screenshot(
+        &self,
+        session_handle: ObjectPath<'_>,
+        options: HashMap<String, OwnedValue>,
+    ) -> zbus::fdo::Result<(String, u32, u32, OwnedFd)> {
+        let mode = self.session_mode;
+        if !mode.has_capture() {
+            return Err(zbus::fdo::Error::NotSupported(format!(
+                "screen capture not available in {mode} mode"
+            )));
+        }
+        if !self.backend.supports_single_capture() {
+            return Err(zbus::fdo::Error::NotSupported(
+                "screenshots not available on this backend".into(),
+            ));
+        }
+
+        let session_id = SessionId::new(session_handle.as_str());
+
+        let Some(session) = self.session_manager.get_session(&session_id).await else {
+            return Err(zbus::fdo::Error::Failed("Session not found".into()));
+        };
+        if session.state().await != SessionState::Active {
+            return Err(zbus::fdo::Error::Failed("Session not started".into()));
+        }
+        let mode = session.mode().await;
+        if !mode.has_capture() {
+            return Err(zbus::fdo::Error::NotSupported(format!(
+                "screen capture not available in {mode} mode"
+            )));
+        }
+
+        let frame = self
+            .backend
+            .capture_single(&session_id, parse_capture_request(&options))
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        let (format, width, height) = (frame.format().to_string(), frame.width(), frame.height());
+        let fd = tokio::task::spawn_blocking(move || frame_to_fd(&frame))
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?
+            .map_err(|e| zbus::fdo::Error::IOError(e.to_string()))?;
+
+        debug!(session = %session_id, width, height, "Screenshot captured");
+        Ok((format, width, height, fd))
+    }
+
+    /// ionChannel extension: makes `content` the desktop's clipboard
+    /// selection, as `mime_type`.
+    ///
+    /// Refused for sessions without input, such as `ViewOnly` ones, and
+    /// when the clipboard policy blocks the transfer. Large selections are
+    /// better passed with `SetSelectionFd`.
+    #[cfg(feature = "clipboard")]
+    #[instrument(skip(self, content), fields(len = content.len()))]
+    async fn set_selection(
+        &self,
+        session_handle: ObjectPath<'_>,
+        mime_type: String,
+        content: Vec<u8>,
+    ) -> zbus::fdo::Result<()> {
+        self.transfer_selection(&session_handle, &mime_type, async { Ok(content) })
+            .await
+    }
+
+    /// ionChannel extension: like `SetSelection`, reading the selection
+    /// from `fd` to end of file.
+    ///
+    /// Clients pass large selections as a pipe or memfd; at most
+    /// [`MAX_SELECTION_BYTES`](crate::clipboard::MAX_SELECTION_BYTES) are
+    /// accepted.
+    #[cfg(feature = "clipboard")]
+    #[instrument(skip(self, fd))]
+    async fn set_selection_fd(
+        &self,
+        session_handle: ObjectPath<'_>,
+        mime_type: String,
+        fd: OwnedFd,
+    ) -> zbus::fdo::Result<()> {
+        let content = async move {
+            tokio::task::spawn_blocking(move || read_selection(fd.into()))
+                .await
+                .map_err(|e| ion_core::Error::Internal(e.to_string()))?
+        };
+        self.transfer_selection(&session_handle, &mime_type, content)
+            .await
+    }
+
+    /// Pauses screen sharing for a started session.
+    ///
+    /// The session's streams stay open but deliver no frames; input keeps
+    /// working. Emits `CapturePausedChanged` unless already paused.
+    #[instrument(skip(self, ctxt))]
+    async fn pause_capture(
         &self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
         session_handle: ObjectPath<'_>,
-        _options: HashMap<String, OwnedValue>,
-        keysym: i32,
-        state: u32,
     ) -> zbus::fdo::Result<()> {
-        let session_id = SessionId::new(session_handle.as_str());
+        self.set_capture_paused(&ctxt, session_handle, true).await
+    }
 
-        let Some(session) = self.session_manager.get_session(&session_id).await else {
-            return Err(zbus::fdo::Error::Failed("Session not found".into()));
-        };
+    /// Resumes a session's paused screen sharing, starting each stream
+    /// with a keyframe. Emits `CapturePausedChanged` unless not paused.
+    #[instrument(skip(self, ctxt))]
+    async fn resume_capture(
+        &self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.set_capture_paused(&ctxt, session_handle, false).await
+    }
 
-        session
-            .send_event(InputEvent::KeyboardKeysym {
-                keysym,
-                state: KeyState::from(state),
-            })
-            .await
-            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+    /// ionChannel extension: locks the pointer for relative-only control,
+    /// e.g. for games that steer the camera with the mouse.
+    ///
+    /// The compositor holds the pointer in place; `NotifyPointerMotion`
+    /// deltas still apply while `NotifyPointerMotionAbsolute` is ignored.
+    /// Emits `PointerLockChanged` unless already locked. The lock is
+    /// released when the session closes.
+    #[instrument(skip(self, ctxt))]
+    async fn lock_pointer(
+        &self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.set_pointer_lock(&ctxt, session_handle, true).await
+    }
 
-        Ok(())
+    /// ionChannel extension: releases the pointer lock, restoring absolute
+    /// motion. Emits `PointerLockChanged` unless not locked.
+    #[instrument(skip(self, ctxt))]
+    async fn unlock_pointer(
+        &self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.set_pointer_lock(&ctxt, session_handle, false).await
     }
 
     /// Returns the available device types.
@@ -468,10 +2149,37 @@ impl RemoteDesktopPortal {
         DeviceType::desktop_standard().bits()
     }
 
+    /// ionChannel extension: input event kinds clients may send.
+    ///
+    /// Kind names such as `pointer_axis_discrete`, `keyboard_modifiers`,
+    /// `touch` and `gesture`, as the active backend supports them. Empty
+    /// in modes without input.
+    #[zbus(property)]
+    async fn supported_input_events(&self) -> Vec<String> {
+        if !self.session_mode.has_input() {
+            return Vec::new();
+        }
+        self.backend
+            .supported_input_events()
+            .into_iter()
+            .map(|kind| kind.name().to_string())
+            .collect()
+    }
+
     /// Returns the portal version.
     #[zbus(property, name = "version")]
     async fn version(&self) -> u32 {
-        2
+        self.version
+    }
+
+    /// ionChannel extension: capture tiers, codecs and extras on offer.
+    ///
+    /// An `a{sv}` with `tiers` (`as`, best first), `codecs` (`as`),
+    /// `clipboard` (`b`), `gestures` (`b`), `screenshot` (`b`) and
+    /// `max_streams` (`u`).
+    #[zbus(property)]
+    async fn capabilities(&self) -> HashMap<String, OwnedValue> {
+        self.portal_capabilities().await.to_dict()
     }
 }
 
@@ -479,10 +2187,11 @@ impl RemoteDesktopPortal {
 mod tests {
     use super::*;
     use crate::session_manager::SessionManagerConfig;
+    use ion_core::backend::{CaptureHandle, DisplayServerType};
 
     fn create_test_portal() -> (
         RemoteDesktopPortal,
-        tokio::sync::mpsc::Receiver<(SessionId, InputEvent)>,
+        tokio::sync::mpsc::Receiver<(SessionId, StampedEvent)>,
     ) {
         let (manager, rx) = SessionManager::new(SessionManagerConfig::default());
         let portal = RemoteDesktopPortal::new(manager);
@@ -493,7 +2202,7 @@ mod tests {
         mode: RemoteDesktopMode,
     ) -> (
         RemoteDesktopPortal,
-        tokio::sync::mpsc::Receiver<(SessionId, InputEvent)>,
+        tokio::sync::mpsc::Receiver<(SessionId, StampedEvent)>,
     ) {
         let (manager, rx) = SessionManager::new(SessionManagerConfig::default());
         let portal = RemoteDesktopPortal::with_mode(
@@ -508,7 +2217,22 @@ mod tests {
     async fn portal_properties() {
         let (portal, _rx) = create_test_portal();
         assert_eq!(portal.available_device_types().await, 3); // keyboard | pointer
-        assert_eq!(portal.version().await, 2);
+        assert_eq!(portal.version().await, PORTAL_VERSION);
+    }
+
+    #[tokio::test]
+    async fn pinned_version_hides_newer_capabilities() {
+        let (portal, _rx) = create_test_portal();
+        let portal = portal
+            .with_clipboard(Arc::new(crate::clipboard::MemoryClipboard::default()))
+            .with_version(version::BASE_VERSION);
+        assert_eq!(portal.version().await, version::BASE_VERSION);
+        let capabilities = portal.portal_capabilities().await;
+        assert!(!capabilities.clipboard);
+        assert!(!capabilities.gestures);
+
+        let portal = portal.with_version(PORTAL_VERSION + 1);
+        assert_eq!(portal.version().await, PORTAL_VERSION);
     }
 
     #[test]
@@ -541,6 +2265,27 @@ mod tests {
         assert_eq!(portal.session_mode(), RemoteDesktopMode::None);
     }
 
+    #[tokio::test]
+    async fn dropped_portals_stop_observing_sessions() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let before = manager.observers().len();
+
+        let portal = RemoteDesktopPortal::new(manager.clone());
+        let clone = portal.clone();
+        let other = RemoteDesktopPortal::with_mode(
+            manager.clone(),
+            RemoteDesktopMode::InputOnly,
+            Arc::new(ion_core::backend::MockBackend::new()),
+        );
+        assert_eq!(manager.observers().len(), before + 2);
+
+        drop(portal);
+        assert_eq!(manager.observers().len(), before + 2, "a clone still lives");
+        drop(clone);
+        drop(other);
+        assert_eq!(manager.observers().len(), before);
+    }
+
     #[tokio::test]
     async fn portal_session_manager_is_accessible() {
         let (portal, _rx) = create_test_portal();
@@ -549,6 +2294,86 @@ mod tests {
         assert_eq!(manager.session_count().await, 0);
     }
 
+    #[test]
+    fn rate_profile_option_parsing() {
+        let mut options = HashMap::new();
+        assert_eq!(parse_rate_profile(&options), RateProfile::Normal);
+
+        options.insert(
+            "rate_profile".to_string(),
+            Value::from("high").try_to_owned().unwrap(),
+        );
+        assert_eq!(parse_rate_profile(&options), RateProfile::High);
+
+        options.insert(
+            "rate_profile".to_string(),
+            Value::from("unlimited").try_to_owned().unwrap(),
+        );
+        assert_eq!(parse_rate_profile(&options), RateProfile::Normal);
+
+        // Wrong type falls back to normal
+        options.insert("rate_profile".to_string(), OwnedValue::from(7u32));
+        assert_eq!(parse_rate_profile(&options), RateProfile::Normal);
+    }
+
+    #[test]
+    fn pointer_transform_option_parsing() {
+        let mut options = HashMap::new();
+        assert!(parse_pointer_transform(&options).is_identity());
+
+        options.insert("pointer_sensitivity".to_string(), OwnedValue::from(2.0f64));
+        assert_eq!(
+            parse_pointer_transform(&options),
+            PointerTransform::with_sensitivity(2.0)
+        );
+
+        options.insert("accel_profile".to_string(), OwnedValue::from(1u32));
+        assert_eq!(
+            parse_pointer_transform(&options).accel_profile,
+            Some(AccelProfile::Adaptive)
+        );
+
+        // Wrong type falls back to identity sensitivity
+        options.insert("pointer_sensitivity".to_string(), OwnedValue::from(2u32));
+        assert_eq!(
+            parse_pointer_transform(&options),
+            PointerTransform::default().with_accel_profile(AccelProfile::Adaptive)
+        );
+    }
+
+    #[test]
+    fn streams_value_matches_screencast_signature() {
+        let value = streams_value(&[StreamInfo {
+            node_id: 42,
+            output_index: 1,
+            width: 1920,
+            height: 1080,
+            color_space: ion_core::backend::ColorSpace::Bt2020,
+            hdr: true,
+            transform: ion_core::backend::OutputTransform::Rotate90,
+        }]);
+        assert_eq!(value.value_signature(), "a(ua{sv})");
+
+        let streams: Vec<(u32, HashMap<String, OwnedValue>)> =
+            OwnedValue::try_from(value).unwrap().try_into().unwrap();
+        let (node_id, properties) = &streams[0];
+        assert_eq!(*node_id, 42);
+        assert_eq!(
+            <(i32, i32)>::try_from(properties["size"].try_clone().unwrap()).unwrap(),
+            (1920, 1080)
+        );
+        assert_eq!(u32::try_from(&properties["output_index"]).unwrap(), 1);
+        assert_eq!(
+            String::try_from(properties["color_space"].try_clone().unwrap()).unwrap(),
+            "bt2020"
+        );
+        assert!(bool::try_from(&properties["hdr"]).unwrap());
+        assert_eq!(
+            String::try_from(properties["applied_transform"].try_clone().unwrap()).unwrap(),
+            "90"
+        );
+    }
+
     #[test]
     fn response_codes_have_correct_values() {
         assert_eq!(ResponseCode::Success as u32, 0);
@@ -630,7 +2455,10 @@ mod tests {
         // Verify event was received
         let (received_id, received_event) = rx.recv().await.unwrap();
         assert_eq!(received_id, session_id);
-        assert!(matches!(received_event, InputEvent::PointerMotion { .. }));
+        assert!(matches!(
+            received_event.event,
+            InputEvent::PointerMotion { .. }
+        ));
     }
 
     #[tokio::test]
@@ -651,6 +2479,610 @@ mod tests {
         assert!(result.is_err());
     }
 
+    async fn start_screenshot_session(portal: &RemoteDesktopPortal, path: &str) {
+        let session = portal
+            .session_manager()
+            .create_session(SessionId::new(path), "test".to_string())
+            .await
+            .unwrap();
+        session.select_devices(DeviceType::POINTER).await.unwrap();
+        session.start().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn capture_screenshot_in_capture_modes() {
+        for mode in [RemoteDesktopMode::Full, RemoteDesktopMode::ViewOnly] {
+            let (portal, _rx) = create_portal_with_mode(mode);
+            start_screenshot_session(&portal, "/test/shot").await;
+
+            let path = ObjectPath::try_from("/test/shot").unwrap();
+            let (format, width, height, fd) = portal
+                .capture_screenshot(path, HashMap::new())
+                .await
+                .unwrap();
+
+            assert_eq!(format, "BGRA8888");
+            let pixels = std::fs::File::from(std::os::fd::OwnedFd::from(fd))
+                .metadata()
+                .unwrap()
+                .len();
+            assert_eq!(pixels, u64::from(width * height * 4));
+        }
+    }
+
+    #[tokio::test]
+    async fn capture_screenshot_rejected_without_capture() {
+        for mode in [RemoteDesktopMode::InputOnly, RemoteDesktopMode::None] {
+            let (portal, _rx) = create_portal_with_mode(mode);
+            start_screenshot_session(&portal, "/test/shot").await;
+
+            let path = ObjectPath::try_from("/test/shot").unwrap();
+            let result = portal.capture_screenshot(path, HashMap::new()).await;
+            assert!(matches!(result, Err(zbus::fdo::Error::NotSupported(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn capture_screenshot_rejected_without_single_capture() {
+        let backend = DisplayBackend(
+            ion_core::backend::MockBackend::new(),
+            DisplayServerType::Wayland,
+        );
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let portal = RemoteDesktopPortal::with_backend(manager, Arc::new(backend));
+        start_screenshot_session(&portal, "/test/shot").await;
+
+        let caps = portal.capabilities().await;
+        assert!(!bool::try_from(&caps["screenshot"]).unwrap());
+        let path = ObjectPath::try_from("/test/shot").unwrap();
+        let result = portal.capture_screenshot(path, HashMap::new()).await;
+        assert!(matches!(result, Err(zbus::fdo::Error::NotSupported(_))));
+    }
+
+    #[cfg(feature = "clipboard")]
+    #[tokio::test]
+    async fn set_selection_reaches_the_desktop_clipboard() {
+        let clipboard = Arc::new(crate::clipboard::MemoryClipboard::default());
+        let (portal, _rx) = create_portal_with_mode(RemoteDesktopMode::Full);
+        let portal = portal.with_clipboard(Arc::clone(&clipboard) as Arc<dyn Clipboard>);
+        start_screenshot_session(&portal, "/test/clip").await;
+
+        let path = ObjectPath::try_from("/test/clip").unwrap();
+        portal
+            .set_selection(path, "text/plain".into(), b"hello".to_vec())
+            .await
+            .unwrap();
+
+        let selection = clipboard.selection().unwrap();
+        assert_eq!(selection.mime_type, "text/plain");
+        assert_eq!(selection.content, b"hello");
+        assert_eq!(portal.clipboard_history().len(), 1);
+    }
+
+    #[cfg(feature = "clipboard")]
+    #[tokio::test]
+    async fn set_selection_refused_without_input() {
+        let clipboard = Arc::new(crate::clipboard::MemoryClipboard::default());
+        let (portal, _rx) = create_portal_with_mode(RemoteDesktopMode::ViewOnly);
+        let portal = portal.with_clipboard(Arc::clone(&clipboard) as Arc<dyn Clipboard>);
+        let session = portal
+            .session_manager()
+            .create_session(SessionId::new("/test/clip"), "test".to_string())
+            .await
+            .unwrap();
+        session.set_mode(RemoteDesktopMode::ViewOnly).await.unwrap();
+        session.select_devices(DeviceType::POINTER).await.unwrap();
+        session.start().await.unwrap();
+
+        let path = ObjectPath::try_from("/test/clip").unwrap();
+        let result = portal
+            .set_selection(path, "text/plain".into(), b"hello".to_vec())
+            .await;
+
+        assert!(matches!(result, Err(zbus::fdo::Error::AccessDenied(_))));
+        assert!(clipboard.selection().is_none());
+    }
+
+    #[tokio::test]
+    async fn gesture_methods_send_through_the_session() {
+        let (portal, mut rx) = create_test_portal();
+        let session = portal
+            .session_manager()
+            .create_session(SessionId::new("/test/gesture"), "test".to_string())
+            .await
+            .unwrap();
+        session.select_devices(DeviceType::GESTURE).await.unwrap();
+        session.start().await.unwrap();
+        let path = || ObjectPath::try_from("/test/gesture").unwrap();
+
+        portal
+            .notify_gesture_swipe(path(), HashMap::new(), 0, 3, 4.0, 0.0)
+            .await
+            .unwrap();
+        portal
+            .notify_gesture_pinch(path(), HashMap::new(), 1, 2, 0.0, 0.0, 1.5, 10.0)
+            .await
+            .unwrap();
+        portal
+            .notify_gesture_hold(path(), HashMap::new(), 3, 4)
+            .await
+            .unwrap();
+        let unknown = portal
+            .notify_gesture_hold(path(), HashMap::new(), 9, 4)
+            .await;
+        assert!(matches!(unknown, Err(zbus::fdo::Error::InvalidArgs(_))));
+
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            received.push(rx.recv().await.unwrap().1.event);
+        }
+        assert_eq!(
+            received,
+            [
+                InputEvent::GestureSwipe {
+                    phase: GesturePhase::Begin,
+                    fingers: 3,
+                    dx: 4.0,
+                    dy: 0.0,
+                },
+                InputEvent::GesturePinch {
+                    phase: GesturePhase::Update,
+                    fingers: 2,
+                    dx: 0.0,
+                    dy: 0.0,
+                    scale: 1.5,
+                    rotation: 10.0,
+                },
+                InputEvent::GestureHold {
+                    phase: GesturePhase::Cancel,
+                    fingers: 4,
+                },
+            ]
+        );
+
+        // Pointer access alone does not cover gestures
+        let pointer = portal
+            .session_manager()
+            .create_session(SessionId::new("/test/pointer"), "test".to_string())
+            .await
+            .unwrap();
+        pointer.select_devices(DeviceType::POINTER).await.unwrap();
+        pointer.start().await.unwrap();
+        let path = ObjectPath::try_from("/test/pointer").unwrap();
+        assert!(portal
+            .notify_gesture_hold(path, HashMap::new(), 0, 3)
+            .await
+            .is_err());
+    }
+
+    /// Backend that can inject input but not capture.
+    struct InputOnlyBackend(ion_core::backend::MockBackend);
+
+    #[async_trait::async_trait]
+    impl CompositorBackend for InputOnlyBackend {
+        async fn is_available(&self) -> bool {
+            true
+        }
+
+        async fn connect(&mut self) -> ion_core::backend::BackendResult<()> {
+            self.0.connect().await
+        }
+
+        async fn inject_input(&self, event: InputEvent) -> ion_core::backend::BackendResult<()> {
+            self.0.inject_input(event).await
+        }
+
+        async fn start_capture(
+            &self,
+            _session: &SessionId,
+        ) -> ion_core::backend::BackendResult<ion_core::backend::CaptureStream> {
+            Err(ion_core::backend::BackendError::CaptureFailed(
+                "input only".into(),
+            ))
+        }
+
+        fn capabilities(&self) -> ion_core::backend::BackendCapabilities {
+            ion_core::backend::BackendCapabilities {
+                can_capture_screen: false,
+                backend_name: "input-only".to_string(),
+                ..self.0.capabilities()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn capabilities_follow_backend_failover() {
+        let outputs = ["DP-1", "HDMI-1"]
+            .into_iter()
+            .zip(0..)
+            .map(|(name, stream)| ion_core::backend::OutputInfo {
+                stream,
+                name: name.to_string(),
+                width: 1920,
+                height: 1080,
+                color_space: ion_core::backend::ColorSpace::Srgb,
+                hdr: false,
+                transform: ion_core::backend::OutputTransform::Normal,
+            })
+            .collect();
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let mut portal = RemoteDesktopPortal::with_backend(
+            manager,
+            Arc::new(ion_core::backend::MockBackend::new().with_outputs(outputs)),
+        );
+        portal.set_capture_tier(Some(CaptureTierInfo::Shm));
+
+        let caps = portal.capabilities().await;
+        let tiers: Vec<String> = caps["tiers"].try_clone().unwrap().try_into().unwrap();
+        assert_eq!(tiers, ["shm", "cpu"]);
+        assert_eq!(u32::try_from(&caps["max_streams"]).unwrap(), 2);
+        assert!(portal
+            .supported_input_events()
+            .await
+            .contains(&"touch".to_string()));
+
+        portal.set_backend(Arc::new(InputOnlyBackend(
+            ion_core::backend::MockBackend::new(),
+        )));
+
+        let caps = portal.capabilities().await;
+        let tiers: Vec<String> = caps["tiers"].try_clone().unwrap().try_into().unwrap();
+        assert!(tiers.is_empty());
+        assert_eq!(u32::try_from(&caps["max_streams"]).unwrap(), 0);
+        let events = portal.supported_input_events().await;
+        assert!(events.contains(&"keyboard_modifiers".to_string()));
+        assert!(!events.contains(&"touch".to_string()));
+        // Spec properties are unaffected
+        assert_eq!(portal.available_device_types().await, 3);
+        assert_eq!(portal.version().await, PORTAL_VERSION);
+    }
+
+    /// Mock backend serving one display server.
+    struct DisplayBackend(ion_core::backend::MockBackend, DisplayServerType);
+
+    #[async_trait::async_trait]
+    impl CompositorBackend for DisplayBackend {
+        async fn is_available(&self) -> bool {
+            true
+        }
+
+        async fn connect(&mut self) -> BackendResult<()> {
+            self.0.connect().await
+        }
+
+        async fn inject_input(&self, event: InputEvent) -> BackendResult<()> {
+            self.0.inject_input(event).await
+        }
+
+        async fn start_capture(
+            &self,
+            session: &SessionId,
+        ) -> BackendResult<ion_core::backend::CaptureStream> {
+            self.0.start_capture(session).await
+        }
+
+        async fn enumerate_outputs(&self) -> BackendResult<Vec<ion_core::backend::OutputInfo>> {
+            self.0.enumerate_outputs().await
+        }
+
+        async fn start_capture_output(
+            &self,
+            session: &SessionId,
+            request: ion_core::backend::OutputCaptureRequest,
+        ) -> BackendResult<ion_core::backend::CaptureStream> {
+            self.0.start_capture_output(session, request).await
+        }
+
+        fn supports_output_capture(&self) -> bool {
+            self.0.supports_output_capture()
+        }
+
+        fn capabilities(&self) -> BackendCapabilities {
+            BackendCapabilities {
+                display_server_type: self.1,
+                backend_name: format!("{:?} mock", self.1),
+                ..self.0.capabilities()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn wayland_appearing_switches_from_x11_backend() {
+        use ion_core::backend::{BackendFactory, DisplayRedetector, OutputInfo};
+        use std::sync::Mutex;
+
+        let env = Mutex::new(HashMap::from([("DISPLAY", ":0")]));
+        let detect = || {
+            let env = env.lock().unwrap();
+            BackendFactory::detect_display_server_with(|name| env.get(name).map(Into::into))
+        };
+        let select = |display: DisplayServerType| -> Arc<dyn CompositorBackend> {
+            let output = OutputInfo {
+                stream: 0,
+                name: "DP-1".to_string(),
+                width: 1920,
+                height: 1080,
+                color_space: ion_core::backend::ColorSpace::Srgb,
+                hdr: false,
+                transform: ion_core::backend::OutputTransform::Normal,
+            };
+            let mock = ion_core::backend::MockBackend::new().with_outputs(vec![output]);
+            Arc::new(DisplayBackend(mock, display))
+        };
+
+        let display = detect();
+        assert_eq!(display, DisplayServerType::X11);
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let mut portal = RemoteDesktopPortal::with_backend(manager, select(display));
+        let id = SessionId::new("/test/redetect");
+        start_screenshot_session(&portal, id.as_str()).await;
+        let started = start_output_streams(portal.backend.as_ref(), &id).await;
+        let (old_streams, handles): (Vec<_>, Vec<_>) = started.into_iter().unzip();
+        for (info, handle) in old_streams.iter().zip(&handles) {
+            portal
+                .captures
+                .insert(&id, info.output_index, handle.clone());
+        }
+
+        let mut redetector = DisplayRedetector::new(display, 2);
+        env.lock().unwrap().insert("WAYLAND_DISPLAY", "wayland-0");
+        assert_eq!(redetector.observe(detect()), None);
+        let display = redetector.observe(detect()).expect("switch after settling");
+        assert_eq!(display, DisplayServerType::Wayland);
+
+        let migrated = portal.switch_backend(select(display)).await;
+        assert_eq!(
+            portal.backend.capabilities().display_server_type,
+            DisplayServerType::Wayland
+        );
+
+        // The session keeps going on the new backend's streams
+        assert!(handles.iter().all(CaptureHandle::is_stopped));
+        assert_eq!(migrated.len(), 1);
+        let (session_id, streams) = &migrated[0];
+        assert_eq!(session_id, &id);
+        assert_eq!(streams.len(), 1);
+        assert_eq!(streams[0].output_index, old_streams[0].output_index);
+        let session = portal.session_manager().get_session(&id).await.unwrap();
+        assert_eq!(session.state().await, SessionState::Active);
+        assert_eq!(portal.captures.outputs(&id).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn paused_capture_stays_paused_across_switch() {
+        let select = || -> Arc<dyn CompositorBackend> {
+            let output = ion_core::backend::OutputInfo {
+                stream: 0,
+                name: "DP-1".to_string(),
+                width: 1920,
+                height: 1080,
+                color_space: ion_core::backend::ColorSpace::Srgb,
+                hdr: false,
+                transform: ion_core::backend::OutputTransform::Normal,
+            };
+            Arc::new(ion_core::backend::MockBackend::new().with_outputs(vec![output]))
+        };
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let mut portal = RemoteDesktopPortal::with_backend(manager, select());
+        let id = SessionId::new("/test/switch/paused");
+        start_screenshot_session(&portal, id.as_str()).await;
+        for (info, handle) in start_output_streams(portal.backend.as_ref(), &id).await {
+            portal.captures.insert(&id, info.output_index, handle);
+        }
+        assert!(portal.captures.set_paused(&id, true));
+
+        let migrated = portal.switch_backend(select()).await;
+
+        // The user's pause carries over to the new backend's streams
+        assert_eq!(migrated.len(), 1);
+        assert!(portal.captures.is_paused(&id));
+        let handle = portal.captures.get(&id, 0).expect("stream restarted");
+        assert!(!handle.is_stopped());
+        assert!(handle.is_paused());
+    }
+
+    /// Backend whose compositor is not running.
+    struct OfflineBackend;
+
+    #[async_trait::async_trait]
+    impl CompositorBackend for OfflineBackend {
+        async fn is_available(&self) -> bool {
+            false
+        }
+
+        async fn connect(&mut self) -> BackendResult<()> {
+            Err(BackendError::NotAvailable("offline".into()))
+        }
+
+        async fn inject_input(&self, _event: InputEvent) -> BackendResult<()> {
+            Err(BackendError::NotAvailable("offline".into()))
+        }
+
+        async fn start_capture(
+            &self,
+            _session: &SessionId,
+        ) -> BackendResult<ion_core::backend::CaptureStream> {
+            Err(BackendError::NotAvailable("offline".into()))
+        }
+
+        fn capabilities(&self) -> BackendCapabilities {
+            BackendCapabilities {
+                backend_name: "offline".to_string(),
+                ..ion_core::backend::MockBackend::new().capabilities()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn try_with_backend_rejects_unavailable_backend() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let result =
+            RemoteDesktopPortal::try_with_backend(manager.clone(), Arc::new(OfflineBackend)).await;
+        assert!(matches!(result, Err(BackendError::NotAvailable(name)) if name == "offline"));
+
+        let backend = Arc::new(ion_core::backend::MockBackend::new());
+        assert!(RemoteDesktopPortal::try_with_backend(manager, backend)
+            .await
+            .is_ok());
+    }
+
+    /// Mock with seats `seat0` and `seat1` that records where input went.
+    #[derive(Default)]
+    struct TwoSeatBackend {
+        mock: ion_core::backend::MockBackend,
+        injected: Mutex<Vec<(Option<String>, InputEvent)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl CompositorBackend for TwoSeatBackend {
+        async fn is_available(&self) -> bool {
+            true
+        }
+
+        async fn connect(&mut self) -> BackendResult<()> {
+            self.mock.connect().await
+        }
+
+        async fn inject_input(&self, event: InputEvent) -> BackendResult<()> {
+            self.inject_input_to_seat(None, event).await
+        }
+
+        async fn inject_input_to_seat(
+            &self,
+            seat: Option<&str>,
+            event: InputEvent,
+        ) -> BackendResult<()> {
+            if let Some(seat) = seat.filter(|s| !["seat0", "seat1"].contains(s)) {
+                return Err(BackendError::SeatNotFound(seat.to_string()));
+            }
+            self.injected
+                .lock()
+                .unwrap()
+                .push((seat.map(str::to_string), event));
+            Ok(())
+        }
+
+        async fn enumerate_seats(&self) -> BackendResult<Vec<String>> {
+            Ok(vec!["seat0".to_string(), "seat1".to_string()])
+        }
+
+        async fn start_capture(
+            &self,
+            session: &SessionId,
+        ) -> BackendResult<ion_core::backend::CaptureStream> {
+            self.mock.start_capture(session).await
+        }
+
+        fn capabilities(&self) -> BackendCapabilities {
+            self.mock.capabilities()
+        }
+    }
+
+    #[tokio::test]
+    async fn session_input_is_injected_on_its_seat() {
+        let (manager, mut rx) = SessionManager::new(SessionManagerConfig::default());
+        let backend = Arc::new(TwoSeatBackend::default());
+        let portal = RemoteDesktopPortal::with_backend(manager, backend.clone());
+        assert!(portal.check_seat("seat1").await.is_ok());
+
+        // As CreateSession does for a valid "seat" option
+        let session = portal
+            .session_manager()
+            .create_session(SessionId::new("/test/seat"), "app".into())
+            .await
+            .unwrap();
+        session.set_seat(Some("seat1".into())).await;
+        session.select_devices(DeviceType::all()).await.unwrap();
+        session.start().await.unwrap();
+
+        let path = ObjectPath::try_from("/test/seat").unwrap();
+        portal
+            .notify_keyboard_keycode(path.clone(), HashMap::new(), 30, 1)
+            .await
+            .unwrap();
+        portal
+            .notify_pointer_motion(path, HashMap::new(), 1.0, 2.0)
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            let (_, event) = rx.recv().await.unwrap();
+            portal.inject(event).await.unwrap();
+        }
+
+        assert_eq!(
+            *backend.injected.lock().unwrap(),
+            [
+                (Some("seat1".into()), InputEvent::key(30, KeyState::Pressed)),
+                (Some("seat1".into()), InputEvent::pointer_motion(1.0, 2.0)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn input_for_an_unknown_seat_is_rejected() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let portal =
+            RemoteDesktopPortal::with_backend(manager, Arc::new(TwoSeatBackend::default()));
+        let event = StampedEvent::from(InputEvent::key(30, KeyState::Pressed));
+        assert!(matches!(
+            portal.inject(event.clone().with_seat(Some("seat2".into()))).await,
+            Err(BackendError::SeatNotFound(seat)) if seat == "seat2"
+        ));
+        assert!(portal.check_seat("seat2").await.is_err());
+
+        // Backends without seat support only offer the primary seat
+        let (portal, _rx) = create_test_portal();
+        assert!(portal.inject(event.clone()).await.is_ok());
+        assert!(matches!(
+            portal.inject(event.with_seat(Some("seat0".into()))).await,
+            Err(BackendError::SeatNotFound(_))
+        ));
+        assert!(portal.check_seat("seat0").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn capture_screenshot_rejected_after_capture_loss() {
+        let (portal, _rx) = create_portal_with_mode(RemoteDesktopMode::Full);
+        start_screenshot_session(&portal, "/test/shot").await;
+        let session = portal
+            .session_manager()
+            .get_session(&SessionId::new("/test/shot"))
+            .await
+            .unwrap();
+        session
+            .set_capabilities(ion_core::SessionCapabilities::input_only())
+            .await;
+
+        let path = ObjectPath::try_from("/test/shot").unwrap();
+        let result = portal.capture_screenshot(path, HashMap::new()).await;
+        assert!(matches!(result, Err(zbus::fdo::Error::NotSupported(_))));
+    }
+
+    #[tokio::test]
+    async fn capture_loss_reported_by_backend_degrades_sessions() {
+        let backend = Arc::new(ion_core::backend::MockBackend::new());
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let mut portal = RemoteDesktopPortal::with_backend(manager, backend.clone());
+        start_screenshot_session(&portal, "/test/live").await;
+        let mut updates = backend.capability_updates();
+
+        backend.set_capture_available(false);
+        let caps = updates.recv().await.unwrap();
+        assert!(!caps.can_capture_screen);
+
+        assert_eq!(
+            portal.apply_capabilities(&caps).await,
+            RemoteDesktopMode::InputOnly
+        );
+        assert_eq!(portal.session_mode(), RemoteDesktopMode::InputOnly);
+        let session = portal
+            .session_manager()
+            .get_session(&SessionId::new("/test/live"))
+            .await
+            .unwrap();
+        assert_eq!(session.mode().await, RemoteDesktopMode::InputOnly);
+    }
+
     #[tokio::test]
     async fn session_device_authorization() {
         let (portal, mut rx) = create_test_portal();
@@ -708,13 +3140,13 @@ mod tests {
                 button: 1,
                 state: ButtonState::Pressed,
             },
-            InputEvent::PointerAxis { dx: 0.0, dy: -10.0 },
+            InputEvent::scroll(0.0, -10.0),
         ];
 
         for event in events {
             session.send_event(event.clone()).await.unwrap();
             let (_, received) = rx.recv().await.unwrap();
-            assert!(std::mem::discriminant(&event) == std::mem::discriminant(&received));
+            assert!(std::mem::discriminant(&event) == std::mem::discriminant(&received.event));
         }
     }
 
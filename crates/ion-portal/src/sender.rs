@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! App IDs of D-Bus clients, established from their sandbox.
+//!
+//! The `app_id` argument of portal calls is whatever the client says it
+//! is. Decisions that outlive a session, like remembered consent, need an
+//! ID the client cannot pick: the sender's unique name is mapped to its
+//! process, and the process to the sandbox it runs in.
+//!
+//! - Flatpak apps: `name` in the `[Application]` group of the
+//!   `/.flatpak-info` file Flatpak places in every sandbox.
+//! - Snaps: `snap.<name>`, from the `snap.<name>.<app>` unit in the
+//!   process's cgroup.
+//!
+//! Unsandboxed clients have no verified app ID; they could claim any.
+
+use std::fs;
+
+use tracing::debug;
+use zbus::fdo::DBusProxy;
+use zbus::names::BusName;
+use zbus::Connection;
+
+/// Returns the verified app ID of the client owning `sender`, a unique
+/// bus name.
+///
+/// Returns `None` if the client is not sandboxed, or its process can't be
+/// looked up.
+pub async fn sender_app_id(connection: &Connection, sender: &str) -> Option<String> {
+    let name = BusName::try_from(sender).ok()?;
+    let pid = match DBusProxy::new(connection).await {
+        Ok(proxy) => proxy.get_connection_unix_process_id(name).await,
+        Err(e) => Err(e.into()),
+    };
+    match pid {
+        Ok(pid) => pid_app_id(pid),
+        Err(e) => {
+            debug!(sender, error = %e, "Could not look up sender process");
+            None
+        },
+    }
+}
+
+/// Returns the app ID of the sandbox process `pid` runs in, if any.
+#[must_use]
+pub fn pid_app_id(pid: u32) -> Option<String> {
+    if let Ok(info) = fs::read_to_string(format!("/proc/{pid}/root/.flatpak-info")) {
+        return flatpak_app_id(&info);
+    }
+    let cgroup = fs::read_to_string(format!("/proc/{pid}/cgroup")).ok()?;
+    snap_app_id(&cgroup)
+}
+
+/// Parses the app ID out of a `.flatpak-info` key file.
+fn flatpak_app_id(info: &str) -> Option<String> {
+    let mut in_application = false;
+    for line in info.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_application = line == "[Application]";
+        } else if in_application {
+            if let Some(name) = line.strip_prefix("name=") {
+                return Some(name.trim().to_string()).filter(|name| !name.is_empty());
+            }
+        }
+    }
+    None
+}
+
+/// Parses the snap name out of `/proc/<pid>/cgroup`.
+fn snap_app_id(cgroup: &str) -> Option<String> {
+    cgroup
+        .lines()
+        .filter_map(|line| line.rsplit(':').next())
+        .flat_map(|path| path.split('/'))
+        .filter_map(|unit| unit.strip_prefix("snap."))
+        .find_map(|unit| unit.split('.').next().filter(|name| !name.is_empty()))
+        .map(|name| format!("snap.{name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flatpak_app_id_comes_from_application_group() {
+        let info = "[Instance]\nname=org.example.Spoof\n\n[Application]\n\
+                    name=org.example.Viewer\nruntime=runtime/org.gnome.Platform\n";
+        assert_eq!(flatpak_app_id(info).as_deref(), Some("org.example.Viewer"));
+        assert_eq!(flatpak_app_id("[Application]\nname=\n"), None);
+        assert_eq!(
+            flatpak_app_id("[Instance]\nname=org.example.Viewer\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn snap_app_id_comes_from_cgroup_unit() {
+        let cgroup = "0::/user.slice/user-1000.slice/user@1000.service/app.slice/\
+                      snap.remmina.remmina-1234.scope\n";
+        assert_eq!(snap_app_id(cgroup).as_deref(), Some("snap.remmina"));
+        assert_eq!(
+            snap_app_id("0::/user.slice/user-1000.slice/session-2.scope\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn own_process_is_not_sandboxed() {
+        assert_eq!(pid_app_id(std::process::id()), None);
+    }
+}
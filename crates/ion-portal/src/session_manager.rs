@@ -5,34 +5,180 @@
 //!
 //! Provides concurrent-safe session storage and lookup.
 
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tracing::{debug, info, warn};
 
-use ion_core::event::InputEvent;
-use ion_core::session::{SessionHandle, SessionId};
-use ion_core::{Error, Result};
+use ion_core::error::SessionError;
+use ion_core::event::StampedEvent;
+use ion_core::session::{SessionHandle, SessionId, SessionState};
+use ion_core::Result;
+
+use crate::clock::{Clock, SystemClock};
+use crate::observer::ObserverRegistry;
+
+/// Close reason reported for a session that lost exclusive control.
+pub const PREEMPTED_REASON: &str = "preempted";
+
+/// Close reason reported for a session the compositor revoked.
+pub const REVOKED_REASON: &str = "revoked by compositor";
+
+/// Close reason reported for a session an operator revoked.
+pub const ADMIN_REVOKED_REASON: &str = "revoked by administrator";
+
+/// Close reason reported for a session whose owning connection left the
+/// bus.
+pub const OWNER_LEFT_REASON: &str = "owner left the bus";
+
+/// Close reason reported for a session reaped after staying ownerless
+/// past [`SessionManagerConfig::orphan_grace`].
+pub const ORPHANED_REASON: &str = "orphaned";
+
+/// Close reason reported for a session reaped after sending no input for
+/// [`SessionManagerConfig::idle_timeout`].
+pub const IDLE_REASON: &str = "idle timeout";
+
+/// Close reason reported for a session that sent
+/// [`SessionManagerConfig::max_events_per_session`] events.
+pub const EVENT_BUDGET_REASON: &str = "event budget exhausted";
+
+/// What happens when an exclusive session starts while another
+/// exclusive session holds control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TakeoverPolicy {
+    /// The new session fails to start
+    #[default]
+    Refuse,
+    /// The holder is closed with [`PREEMPTED_REASON`] and the new
+    /// session takes over
+    Takeover,
+}
+
+/// What a session does with input events when the compositor falls
+/// behind and the event channel is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// `send_event` waits for room, failing with
+    /// [`InputError::WouldBlock`](ion_core::error::InputError::WouldBlock)
+    /// after [`SessionManagerConfig::block_timeout`]. Nothing is lost.
+    #[default]
+    Block,
+    /// The oldest queued event is discarded to make room, favoring
+    /// latency over completeness.
+    DropOldest,
+    /// The incoming event is discarded, keeping what is already queued.
+    DropNewest,
+}
+
+/// What [`SessionManager::close_session`] found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseOutcome {
+    /// The session was open and has been closed.
+    Closed,
+    /// No session with that ID exists, e.g. it was already closed.
+    NotFound,
+    /// The session had already been closed through its handle; it has
+    /// now been removed.
+    AlreadyClosing,
+}
+
+impl CloseOutcome {
+    /// Whether the session existed before the call.
+    #[must_use]
+    pub const fn existed(self) -> bool {
+        !matches!(self, Self::NotFound)
+    }
+}
 
 /// Configuration for the session manager.
 #[derive(Debug, Clone)]
 pub struct SessionManagerConfig {
     /// Maximum number of concurrent sessions
     pub max_sessions: usize,
-    /// Event channel buffer size
-    pub event_buffer_size: usize,
+    /// Events buffered per session, and in the compositor channel
+    pub event_channel_capacity: usize,
+    /// What happens to events when a session's buffer is full
+    pub overflow_policy: OverflowPolicy,
+    /// How long `send_event` waits for room under [`OverflowPolicy::Block`]
+    pub block_timeout: Duration,
+    /// How competing exclusive sessions are resolved
+    pub takeover_policy: TakeoverPolicy,
+    /// How long an ownerless session survives before it is reaped
+    pub orphan_grace: Duration,
+    /// How long a session may go without input before
+    /// [`SessionManager::reap_idle`] closes it; `None` never reaps
+    pub idle_timeout: Option<Duration>,
+    /// Events a session may send over its lifetime; spending them is
+    /// reported by [`SessionManager::budget_exhaustions`]. `None` is
+    /// unlimited
+    pub max_events_per_session: Option<u64>,
 }
 
 impl Default for SessionManagerConfig {
     fn default() -> Self {
         Self {
             max_sessions: 10,
-            event_buffer_size: 256,
+            event_channel_capacity: 256,
+            overflow_policy: OverflowPolicy::default(),
+            block_timeout: Duration::from_secs(1),
+            takeover_policy: TakeoverPolicy::default(),
+            orphan_grace: Duration::from_secs(30),
+            idle_timeout: None,
+            max_events_per_session: None,
         }
     }
 }
 
+/// Sessions that asked for exclusive control, and which one has it.
+#[derive(Debug, Default)]
+struct ExclusiveState {
+    requested: HashSet<SessionId>,
+    holder: Option<SessionId>,
+}
+
+/// Bus connections owning sessions, and sessions left without one.
+#[derive(Debug, Default)]
+struct OwnerState {
+    /// Unique bus name of each owned session's connection
+    owners: HashMap<SessionId, String>,
+    /// When each ownerless session lost its owner
+    orphaned: HashMap<SessionId, Instant>,
+}
+
+impl OwnerState {
+    fn forget(&mut self, id: &SessionId) {
+        self.owners.remove(id);
+        self.orphaned.remove(id);
+    }
+}
+
+/// When a session last sent input, shared with its event forwarder.
+#[derive(Debug, Clone)]
+struct Activity {
+    clock: Arc<dyn Clock>,
+    last: Arc<Mutex<Instant>>,
+}
+
+impl Activity {
+    fn new(clock: Arc<dyn Clock>) -> Self {
+        let last = Arc::new(Mutex::new(clock.now()));
+        Self { clock, last }
+    }
+
+    fn touch(&self) {
+        *self.last.lock().unwrap_or_else(PoisonError::into_inner) = self.clock.now();
+    }
+
+    fn idle_for(&self) -> Duration {
+        let last = *self.last.lock().unwrap_or_else(PoisonError::into_inner);
+        self.clock.now().saturating_duration_since(last)
+    }
+}
+
 /// Thread-safe session manager.
 ///
 /// Manages the lifecycle of remote desktop sessions including
@@ -42,7 +188,23 @@ pub struct SessionManager {
     config: SessionManagerConfig,
     sessions: Arc<RwLock<HashMap<SessionId, SessionHandle>>>,
     /// Channel for forwarding input events to the compositor
-    compositor_tx: mpsc::Sender<(SessionId, InputEvent)>,
+    compositor_tx: mpsc::Sender<(SessionId, StampedEvent)>,
+    /// Whether new sessions may be created (cleared on shutdown)
+    accepting: Arc<AtomicBool>,
+    /// Exclusive control bookkeeping
+    exclusive: Arc<RwLock<ExclusiveState>>,
+    /// Owning connection bookkeeping
+    owners: Arc<RwLock<OwnerState>>,
+    /// Events discarded by a drop overflow policy
+    dropped_events: Arc<AtomicU64>,
+    /// Time source for orphan and idle timeouts
+    clock: Arc<dyn Clock>,
+    /// Last input from each session
+    activity: Arc<RwLock<HashMap<SessionId, Activity>>>,
+    /// Told about session lifecycle transitions
+    observers: ObserverRegistry,
+    /// Sessions that spent their event budget
+    budget_tx: broadcast::Sender<SessionId>,
 }
 
 impl SessionManager {
@@ -50,28 +212,68 @@ impl SessionManager {
     ///
     /// Returns the manager and a receiver for compositor events.
     #[must_use]
-    pub fn new(config: SessionManagerConfig) -> (Self, mpsc::Receiver<(SessionId, InputEvent)>) {
-        let (compositor_tx, compositor_rx) = mpsc::channel(config.event_buffer_size);
+    pub fn new(config: SessionManagerConfig) -> (Self, mpsc::Receiver<(SessionId, StampedEvent)>) {
+        let (compositor_tx, compositor_rx) = mpsc::channel(config.event_channel_capacity);
 
         let manager = Self {
             config,
             sessions: Arc::new(RwLock::new(HashMap::new())),
             compositor_tx,
+            accepting: Arc::new(AtomicBool::new(true)),
+            exclusive: Arc::new(RwLock::new(ExclusiveState::default())),
+            owners: Arc::new(RwLock::new(OwnerState::default())),
+            dropped_events: Arc::new(AtomicU64::new(0)),
+            clock: Arc::new(SystemClock),
+            activity: Arc::default(),
+            observers: ObserverRegistry::default(),
+            budget_tx: broadcast::channel(16).0,
         };
 
         (manager, compositor_rx)
     }
 
+    /// Reads the time from `clock` instead of the system clock.
+    ///
+    /// Set before creating sessions; tests pass a
+    /// [`MockClock`](crate::clock::MockClock) to trigger timeouts without
+    /// sleeping.
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Observers told about session lifecycle transitions.
+    #[must_use]
+    pub fn observers(&self) -> &ObserverRegistry {
+        &self.observers
+    }
+
+    /// Sessions that spent their event budget.
+    ///
+    /// Such a session refuses further input but stays open until its
+    /// owner closes it, so the client can be told why.
+    #[must_use]
+    pub fn budget_exhaustions(&self) -> broadcast::Receiver<SessionId> {
+        self.budget_tx.subscribe()
+    }
+
     /// Creates a new session.
     ///
     /// # Errors
     ///
     /// Returns an error if:
+    /// - The manager is shutting down
     /// - Maximum sessions reached
     /// - Session ID already exists
     pub async fn create_session(&self, id: SessionId, app_id: String) -> Result<SessionHandle> {
         let mut sessions = self.sessions.write().await;
 
+        if !self.is_accepting() {
+            warn!(session = %id, "Rejecting session, manager is shutting down");
+            return Err(SessionError::ShuttingDown.into());
+        }
+
         // Check limits
         if sessions.len() >= self.config.max_sessions {
             warn!(
@@ -79,7 +281,7 @@ impl SessionManager {
                 current = sessions.len(),
                 "Maximum sessions reached"
             );
-            return Err(Error::Internal("maximum sessions reached".into()));
+            return Err(SessionError::LimitReached(self.config.max_sessions).into());
         }
 
         // Check for duplicate
@@ -88,32 +290,120 @@ impl SessionManager {
         }
 
         // Create event channel for this session
-        let (event_tx, mut event_rx) = mpsc::channel(self.config.event_buffer_size);
-        let session = SessionHandle::new(id.clone(), app_id.clone(), event_tx);
+        let (event_tx, event_rx) = mpsc::channel(self.config.event_channel_capacity);
+        let mut session = SessionHandle::new(id.clone(), app_id.clone(), event_tx);
+        if self.config.overflow_policy == OverflowPolicy::Block {
+            session = session.with_send_timeout(self.config.block_timeout);
+        }
+        if let Some(max_events) = self.config.max_events_per_session {
+            session = session.with_event_budget(max_events, self.budget_tx.clone());
+        }
 
         // Spawn task to forward events to compositor
-        let compositor_tx = self.compositor_tx.clone();
-        let session_id = id.clone();
-        tokio::spawn(async move {
-            while let Some(event) = event_rx.recv().await {
-                if compositor_tx
-                    .send((session_id.clone(), event))
-                    .await
-                    .is_err()
-                {
-                    debug!(session = %session_id, "Compositor channel closed");
-                    break;
-                }
-            }
-            debug!(session = %session_id, "Session event forwarder stopped");
-        });
+        let activity = Activity::new(Arc::clone(&self.clock));
+        self.activity
+            .write()
+            .await
+            .insert(id.clone(), activity.clone());
+        tokio::spawn(forward_events(
+            id.clone(),
+            event_rx,
+            self.compositor_tx.clone(),
+            self.config.overflow_policy,
+            self.config.event_channel_capacity,
+            Arc::clone(&self.dropped_events),
+            activity,
+        ));
 
         info!(session = %id, app = %app_id, "Session created");
-        sessions.insert(id, session.clone());
+        sessions.insert(id.clone(), session.clone());
+        drop(sessions);
+        self.observers.created(&id, &app_id);
 
         Ok(session)
     }
 
+    /// Creates a session that requests exclusive input control when started.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::create_session`].
+    pub async fn create_exclusive_session(
+        &self,
+        id: SessionId,
+        app_id: String,
+    ) -> Result<SessionHandle> {
+        let session = self.create_session(id.clone(), app_id).await?;
+        self.exclusive.write().await.requested.insert(id);
+        Ok(session)
+    }
+
+    /// Returns true if the session requested exclusive control.
+    pub async fn is_exclusive(&self, id: &SessionId) -> bool {
+        self.exclusive.read().await.requested.contains(id)
+    }
+
+    /// Returns the session currently holding exclusive control.
+    pub async fn exclusive_holder(&self) -> Option<SessionId> {
+        self.exclusive.read().await.holder.clone()
+    }
+
+    /// Starts a session, resolving exclusive control.
+    ///
+    /// Non-exclusive sessions simply start. An exclusive session takes
+    /// control if nobody holds it; otherwise the configured
+    /// [`TakeoverPolicy`] decides. On takeover the previous holder is
+    /// closed, releasing any keys it still held, and its ID is returned
+    /// so the caller can report it as [`PREEMPTED_REASON`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SessionError::ExclusiveHeld`] if another session holds
+    /// control and the policy is [`TakeoverPolicy::Refuse`], or any error
+    /// from starting the session.
+    pub async fn start_session(&self, session: &SessionHandle) -> Result<Option<SessionId>> {
+        let id = session.id();
+        let mut exclusive = self.exclusive.write().await;
+        if !exclusive.requested.contains(id) {
+            drop(exclusive);
+            session.start().await?;
+            self.observers.started(id);
+            return Ok(None);
+        }
+
+        let preempted = match exclusive.holder.clone() {
+            Some(holder) if holder != *id => match self.config.takeover_policy {
+                TakeoverPolicy::Refuse => {
+                    warn!(session = %id, holder = %holder, "Exclusive control already held");
+                    return Err(SessionError::ExclusiveHeld(holder.to_string()).into());
+                },
+                TakeoverPolicy::Takeover => Some(holder),
+            },
+            _ => None,
+        };
+
+        session.start().await?;
+
+        if let Some(old) = &preempted {
+            exclusive.requested.remove(old);
+            if let Some(old_session) = self.sessions.write().await.remove(old) {
+                old_session.close().await;
+            }
+            self.owners.write().await.forget(old);
+            self.activity.write().await.remove(old);
+            info!(session = %old, by = %id, "Session preempted");
+        }
+        exclusive.holder = Some(id.clone());
+        drop(exclusive);
+        info!(session = %id, "Exclusive control acquired");
+
+        if let Some(old) = &preempted {
+            self.observers.closed(old);
+        }
+        self.observers.started(id);
+        Ok(preempted)
+    }
+
     /// Looks up a session by ID.
     pub async fn get_session(&self, id: &SessionId) -> Option<SessionHandle> {
         self.sessions.read().await.get(id).cloned()
@@ -126,19 +416,39 @@ impl SessionManager {
     }
 
     /// Closes and removes a session.
-    pub async fn close_session(&self, id: &SessionId) -> bool {
+    ///
+    /// Closing twice is harmless; the second call reports
+    /// [`CloseOutcome::NotFound`].
+    pub async fn close_session(&self, id: &SessionId) -> CloseOutcome {
+        self.owners.write().await.forget(id);
+        self.activity.write().await.remove(id);
         let mut sessions = self.sessions.write().await;
 
         if let Some(session) = sessions.remove(id) {
-            session.close().await;
-            info!(session = %id, "Session closed");
-            true
+            drop(sessions);
+            let outcome = if session.state().await == SessionState::Closed {
+                CloseOutcome::AlreadyClosing
+            } else {
+                session.close().await;
+                CloseOutcome::Closed
+            };
+            self.release_exclusive(id).await;
+            info!(session = %id, ?outcome, "Session closed");
+            self.observers.closed(id);
+            outcome
         } else {
             warn!(session = %id, "Attempted to close non-existent session");
-            false
+            CloseOutcome::NotFound
         }
     }
 
+    /// Returns how many events a drop [`OverflowPolicy`] has discarded
+    /// across all sessions.
+    #[must_use]
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events.load(Ordering::Relaxed)
+    }
+
     /// Returns the number of active sessions.
     pub async fn session_count(&self) -> usize {
         self.sessions.read().await.len()
@@ -149,13 +459,213 @@ impl SessionManager {
         self.sessions.read().await.keys().cloned().collect()
     }
 
+    /// Stops accepting new sessions.
+    ///
+    /// Existing sessions are unaffected; pair with [`Self::close_all`]
+    /// during shutdown.
+    pub fn stop_accepting(&self) {
+        self.accepting.store(false, Ordering::SeqCst);
+        info!("Session manager no longer accepting new sessions");
+    }
+
+    /// Returns true if new sessions may be created.
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.load(Ordering::SeqCst)
+    }
+
     /// Closes all sessions.
     pub async fn close_all(&self) {
         let mut sessions = self.sessions.write().await;
 
+        let mut closed = Vec::with_capacity(sessions.len());
         for (id, session) in sessions.drain() {
             session.close().await;
             info!(session = %id, "Session closed (shutdown)");
+            closed.push(id);
+        }
+        drop(sessions);
+
+        let mut exclusive = self.exclusive.write().await;
+        exclusive.requested.clear();
+        exclusive.holder = None;
+        drop(exclusive);
+
+        let mut owners = self.owners.write().await;
+        owners.owners.clear();
+        owners.orphaned.clear();
+        drop(owners);
+
+        self.activity.write().await.clear();
+
+        for id in &closed {
+            self.observers.closed(id);
+        }
+    }
+
+    /// Records the bus connection (by unique name) that owns a session.
+    ///
+    /// Replaces any previous owner and clears orphan status.
+    pub async fn set_owner(&self, id: &SessionId, unique_name: impl Into<String>) {
+        let mut owners = self.owners.write().await;
+        owners.orphaned.remove(id);
+        owners.owners.insert(id.clone(), unique_name.into());
+    }
+
+    /// Returns the unique name of the connection owning a session.
+    pub async fn owner_of(&self, id: &SessionId) -> Option<String> {
+        self.owners.read().await.owners.get(id).cloned()
+    }
+
+    /// Returns the sessions owned by a connection.
+    pub async fn sessions_owned_by(&self, unique_name: &str) -> Vec<SessionId> {
+        self.owners
+            .read()
+            .await
+            .owners
+            .iter()
+            .filter(|(_, owner)| owner.as_str() == unique_name)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Moves every session owned by `old_unique` to `new_unique`.
+    ///
+    /// Used when the service restarts or is socket-activated behind the
+    /// same well-known name: the sessions survive but the connection they
+    /// were recorded against is gone, so a later
+    /// [`Self::close_sessions_owned_by`] for the old name must not close
+    /// them.
+    ///
+    /// With no new owner the sessions become orphans. They stay open so
+    /// a replacement owner can still claim them with [`Self::set_owner`],
+    /// and [`Self::reap_orphans`] closes them once they have been ownerless
+    /// for [`SessionManagerConfig::orphan_grace`].
+    ///
+    /// Returns the sessions that were moved.
+    pub async fn transfer_ownership(
+        &self,
+        old_unique: &str,
+        new_unique: Option<&str>,
+    ) -> Vec<SessionId> {
+        let mut owners = self.owners.write().await;
+        let moved: Vec<SessionId> = owners
+            .owners
+            .iter()
+            .filter(|(_, owner)| owner.as_str() == old_unique)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let now = self.clock.now();
+        for id in &moved {
+            if let Some(new) = new_unique {
+                owners.owners.insert(id.clone(), new.to_owned());
+            } else {
+                owners.owners.remove(id);
+                owners.orphaned.insert(id.clone(), now);
+            }
+        }
+        drop(owners);
+
+        if !moved.is_empty() {
+            info!(
+                from = old_unique,
+                to = new_unique.unwrap_or("<none>"),
+                count = moved.len(),
+                "Session ownership transferred"
+            );
+        }
+        moved
+    }
+
+    /// Closes every session owned by a connection that left the bus.
+    ///
+    /// This is the `NameOwnerChanged` cleanup path. Returns the closed
+    /// sessions.
+    pub async fn close_sessions_owned_by(&self, unique_name: &str) -> Vec<SessionId> {
+        let mut owned = Vec::new();
+        for id in self.sessions_owned_by(unique_name).await {
+            // A session closed concurrently by its client is not reported
+            if self.close_session(&id).await.existed() {
+                owned.push(id);
+            }
+        }
+        if !owned.is_empty() {
+            info!(
+                owner = unique_name,
+                count = owned.len(),
+                "Owner left the bus, sessions closed"
+            );
+        }
+        owned
+    }
+
+    /// Returns sessions that currently have no owner.
+    pub async fn orphaned_sessions(&self) -> Vec<SessionId> {
+        self.owners.read().await.orphaned.keys().cloned().collect()
+    }
+
+    /// Closes sessions that have been orphaned for longer than
+    /// [`SessionManagerConfig::orphan_grace`].
+    ///
+    /// Returns the reaped sessions.
+    pub async fn reap_orphans(&self) -> Vec<SessionId> {
+        let grace = self.config.orphan_grace;
+        let now = self.clock.now();
+        let expired: Vec<SessionId> = self
+            .owners
+            .read()
+            .await
+            .orphaned
+            .iter()
+            .filter(|(_, since)| now.saturating_duration_since(**since) >= grace)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let mut reaped = Vec::with_capacity(expired.len());
+        for id in expired {
+            if self.close_session(&id).await.existed() {
+                info!(session = %id, "Orphaned session reaped");
+                reaped.push(id);
+            }
+        }
+        reaped
+    }
+
+    /// Closes sessions that have sent no input for longer than
+    /// [`SessionManagerConfig::idle_timeout`].
+    ///
+    /// A session's timer starts when it is created and restarts with
+    /// every event it sends. Returns the reaped sessions.
+    pub async fn reap_idle(&self) -> Vec<SessionId> {
+        let Some(timeout) = self.config.idle_timeout else {
+            return Vec::new();
+        };
+        let expired: Vec<SessionId> = self
+            .activity
+            .read()
+            .await
+            .iter()
+            .filter(|(_, activity)| activity.idle_for() >= timeout)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let mut reaped = Vec::with_capacity(expired.len());
+        for id in expired {
+            if self.close_session(&id).await.existed() {
+                info!(session = %id, ?timeout, "Idle session reaped");
+                reaped.push(id);
+            }
+        }
+        reaped
+    }
+
+    /// Forgets a closed session's exclusive request and control.
+    async fn release_exclusive(&self, id: &SessionId) {
+        let mut exclusive = self.exclusive.write().await;
+        exclusive.requested.remove(id);
+        if exclusive.holder.as_ref() == Some(id) {
+            exclusive.holder = None;
+            info!(session = %id, "Exclusive control released");
         }
     }
 }
@@ -166,13 +676,102 @@ impl Clone for SessionManager {
             config: self.config.clone(),
             sessions: Arc::clone(&self.sessions),
             compositor_tx: self.compositor_tx.clone(),
+            accepting: Arc::clone(&self.accepting),
+            exclusive: Arc::clone(&self.exclusive),
+            owners: Arc::clone(&self.owners),
+            dropped_events: Arc::clone(&self.dropped_events),
+            clock: Arc::clone(&self.clock),
+            activity: Arc::clone(&self.activity),
+            observers: self.observers.clone(),
+            budget_tx: self.budget_tx.clone(),
         }
     }
 }
 
+/// Forwards a session's events to the compositor channel.
+///
+/// Under [`OverflowPolicy::Block`] a full compositor channel stalls the
+/// forwarder, which fills the session channel and makes `send_event`
+/// wait. The drop policies instead keep draining the session channel
+/// into a buffer of `capacity` events and discard from it when full.
+///
+/// Every event received restarts the session's idle timer.
+async fn forward_events(
+    session_id: SessionId,
+    mut event_rx: mpsc::Receiver<StampedEvent>,
+    compositor_tx: mpsc::Sender<(SessionId, StampedEvent)>,
+    policy: OverflowPolicy,
+    capacity: usize,
+    dropped: Arc<AtomicU64>,
+    activity: Activity,
+) {
+    if policy == OverflowPolicy::Block {
+        while let Some(event) = event_rx.recv().await {
+            activity.touch();
+            if compositor_tx
+                .send((session_id.clone(), event))
+                .await
+                .is_err()
+            {
+                debug!(session = %session_id, "Compositor channel closed");
+                return;
+            }
+        }
+        debug!(session = %session_id, "Session event forwarder stopped");
+        return;
+    }
+
+    let mut pending: VecDeque<StampedEvent> = VecDeque::with_capacity(capacity);
+    loop {
+        tokio::select! {
+            // Deliver before accepting more, so drops only happen while
+            // the compositor channel is actually full
+            biased;
+            permit = compositor_tx.reserve(), if !pending.is_empty() => {
+                let Ok(permit) = permit else {
+                    debug!(session = %session_id, "Compositor channel closed");
+                    return;
+                };
+                if let Some(event) = pending.pop_front() {
+                    permit.send((session_id.clone(), event));
+                }
+            },
+            event = event_rx.recv() => {
+                let Some(event) = event else { break };
+                activity.touch();
+                if pending.len() < capacity {
+                    pending.push_back(event);
+                    continue;
+                }
+                dropped.fetch_add(1, Ordering::Relaxed);
+                debug!(session = %session_id, ?policy, "Event channel full, dropping event");
+                if policy == OverflowPolicy::DropOldest {
+                    pending.pop_front();
+                    pending.push_back(event);
+                }
+            },
+        }
+    }
+
+    // The session is gone; deliver what is still buffered
+    for event in pending {
+        if compositor_tx
+            .send((session_id.clone(), event))
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+    debug!(session = %session_id, "Session event forwarder stopped");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::MockClock;
+    use ion_core::event::InputEvent;
+    use ion_core::Error;
 
     #[tokio::test]
     async fn session_lifecycle() {
@@ -202,10 +801,13 @@ mod tests {
         // Receive at compositor
         let (id, event) = rx.recv().await.unwrap();
         assert_eq!(id.as_str(), "/test/1");
-        assert!(event.is_pointer());
+        assert!(event.event.is_pointer());
 
         // Close session
-        manager.close_session(&SessionId::new("/test/1")).await;
+        assert_eq!(
+            manager.close_session(&SessionId::new("/test/1")).await,
+            CloseOutcome::Closed
+        );
         assert_eq!(manager.session_count().await, 0);
     }
 
@@ -237,17 +839,28 @@ mod tests {
     fn config_default() {
         let config = SessionManagerConfig::default();
         assert_eq!(config.max_sessions, 10);
-        assert_eq!(config.event_buffer_size, 256);
+        assert_eq!(config.event_channel_capacity, 256);
+        assert_eq!(config.overflow_policy, OverflowPolicy::Block);
+        assert_eq!(config.orphan_grace, Duration::from_secs(30));
+        assert_eq!(config.idle_timeout, None);
     }
 
     #[test]
     fn config_custom() {
         let config = SessionManagerConfig {
             max_sessions: 5,
-            event_buffer_size: 128,
+            event_channel_capacity: 128,
+            overflow_policy: OverflowPolicy::DropOldest,
+            block_timeout: Duration::from_millis(100),
+            takeover_policy: TakeoverPolicy::Takeover,
+            orphan_grace: Duration::from_secs(5),
+            idle_timeout: Some(Duration::from_secs(45)),
+            max_events_per_session: Some(1_000_000),
         };
         assert_eq!(config.max_sessions, 5);
-        assert_eq!(config.event_buffer_size, 128);
+        assert_eq!(config.event_channel_capacity, 128);
+        assert_eq!(config.overflow_policy, OverflowPolicy::DropOldest);
+        assert_eq!(config.takeover_policy, TakeoverPolicy::Takeover);
     }
 
     #[tokio::test]
@@ -308,7 +921,33 @@ mod tests {
         let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
 
         let result = manager.close_session(&SessionId::new("/nonexistent")).await;
-        assert!(!result);
+        assert_eq!(result, CloseOutcome::NotFound);
+        assert!(!result.existed());
+    }
+
+    #[tokio::test]
+    async fn close_after_close_is_not_found() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let id = SessionId::new("/test/twice");
+        manager
+            .create_session(id.clone(), "app".into())
+            .await
+            .unwrap();
+
+        assert_eq!(manager.close_session(&id).await, CloseOutcome::Closed);
+        assert_eq!(manager.close_session(&id).await, CloseOutcome::NotFound);
+
+        // Closed through its handle, but still registered
+        let session = manager
+            .create_session(id.clone(), "app".into())
+            .await
+            .unwrap();
+        session.close().await;
+        assert_eq!(
+            manager.close_session(&id).await,
+            CloseOutcome::AlreadyClosing
+        );
+        assert_eq!(manager.session_count().await, 0);
     }
 
     #[tokio::test]
@@ -361,9 +1000,10 @@ mod tests {
         assert_eq!(manager2.session_count().await, 1);
 
         // Close from cloned manager
-        manager2
+        let outcome = manager2
             .close_session(&SessionId::new("/test/shared"))
             .await;
+        assert_eq!(outcome, CloseOutcome::Closed);
 
         // Both should see it closed
         assert_eq!(manager1.session_count().await, 0);
@@ -403,8 +1043,123 @@ mod tests {
         for _ in 0..3 {
             let (id, event) = rx.recv().await.unwrap();
             assert_eq!(id.as_str(), "/test/events");
-            assert!(event.is_pointer());
+            assert!(event.event.is_pointer());
+        }
+    }
+
+    /// Active pointer session on a manager with a two-event channel.
+    async fn saturated_manager(
+        policy: OverflowPolicy,
+    ) -> (
+        SessionManager,
+        SessionHandle,
+        mpsc::Receiver<(SessionId, StampedEvent)>,
+    ) {
+        let (manager, rx) = SessionManager::new(SessionManagerConfig {
+            event_channel_capacity: 2,
+            overflow_policy: policy,
+            block_timeout: Duration::from_millis(50),
+            ..Default::default()
+        });
+        let session = manager
+            .create_session(SessionId::new("/test/overflow"), "app".into())
+            .await
+            .unwrap();
+        session
+            .select_devices(ion_core::DeviceType::POINTER)
+            .await
+            .unwrap();
+        session.start().await.unwrap();
+        (manager, session, rx)
+    }
+
+    /// Sends motions `1..=count`, tagged by their `dx`.
+    async fn send_motions(session: &SessionHandle, count: u32) -> Vec<Result<()>> {
+        let mut results = Vec::new();
+        for i in 1..=count {
+            let motion = ion_core::InputEvent::pointer_motion(f64::from(i), 0.0);
+            results.push(session.send_event(motion).await);
+        }
+        results
+    }
+
+    async fn wait_for_drops(manager: &SessionManager, count: u64) {
+        for _ in 0..100 {
+            if manager.dropped_events() == count {
+                return;
+            }
+            tokio::task::yield_now().await;
+        }
+        panic!("expected {count} drops, saw {}", manager.dropped_events());
+    }
+
+    /// Drains the compositor channel, returning each motion's `dx`.
+    fn received(rx: &mut mpsc::Receiver<(SessionId, StampedEvent)>) -> Vec<f64> {
+        std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|(_, event)| match event.event {
+                InputEvent::PointerMotion { dx, .. } => dx,
+                other => panic!("unexpected event {other:?}"),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn overflow_drop_newest_keeps_queued_events() {
+        let (manager, session, mut rx) = saturated_manager(OverflowPolicy::DropNewest).await;
+
+        let results = send_motions(&session, 10).await;
+        assert!(results.iter().all(Result::is_ok));
+        wait_for_drops(&manager, 6).await;
+
+        // Two in the compositor channel, two buffered, the rest dropped
+        let mut seen = received(&mut rx);
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        seen.extend(received(&mut rx));
+        assert_eq!(seen, [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(manager.dropped_events(), 6);
+    }
+
+    #[tokio::test]
+    async fn overflow_drop_oldest_keeps_latest_events() {
+        let (manager, session, mut rx) = saturated_manager(OverflowPolicy::DropOldest).await;
+
+        let results = send_motions(&session, 10).await;
+        assert!(results.iter().all(Result::is_ok));
+        wait_for_drops(&manager, 6).await;
+
+        let mut seen = received(&mut rx);
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
         }
+        seen.extend(received(&mut rx));
+        assert_eq!(seen, [1.0, 2.0, 9.0, 10.0]);
+    }
+
+    #[tokio::test]
+    async fn overflow_block_reports_would_block() {
+        let (manager, session, mut rx) = saturated_manager(OverflowPolicy::Block).await;
+
+        // Two in the compositor channel, one held by the forwarder, two in
+        // the session channel; the sixth cannot be queued
+        let results = send_motions(&session, 6).await;
+        assert!(results[..5].iter().all(Result::is_ok));
+        assert!(matches!(
+            results[5],
+            Err(Error::Input(ion_core::error::InputError::WouldBlock(_)))
+        ));
+        assert_eq!(manager.dropped_events(), 0);
+
+        // Nothing was lost, and draining makes room again
+        assert_eq!(
+            rx.recv().await.unwrap().1,
+            InputEvent::pointer_motion(1.0, 0.0)
+        );
+        session
+            .send_event(InputEvent::pointer_motion(7.0, 0.0))
+            .await
+            .unwrap();
     }
 
     #[test]
@@ -413,4 +1168,435 @@ mod tests {
         assert_send_sync::<SessionManager>();
         assert_send_sync::<SessionManagerConfig>();
     }
+
+    #[tokio::test]
+    async fn stop_accepting_rejects_new_sessions() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let clone = manager.clone();
+
+        manager
+            .create_session(SessionId::new("/test/before"), "app".into())
+            .await
+            .unwrap();
+
+        clone.stop_accepting();
+        assert!(!manager.is_accepting());
+
+        let result = manager
+            .create_session(SessionId::new("/test/after"), "app".into())
+            .await;
+        assert!(result.is_err());
+        assert_eq!(manager.session_count().await, 1);
+    }
+
+    async fn started_exclusive(manager: &SessionManager, path: &str) -> SessionHandle {
+        let session = manager
+            .create_exclusive_session(SessionId::new(path), "app".into())
+            .await
+            .unwrap();
+        session
+            .select_devices(ion_core::DeviceType::KEYBOARD)
+            .await
+            .unwrap();
+        session
+    }
+
+    #[tokio::test]
+    async fn exclusive_refuse_policy_rejects_second_holder() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+
+        let first = started_exclusive(&manager, "/test/first").await;
+        assert_eq!(manager.start_session(&first).await.unwrap(), None);
+        assert_eq!(manager.exclusive_holder().await, Some(first.id().clone()));
+
+        let second = started_exclusive(&manager, "/test/second").await;
+        let err = manager.start_session(&second).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Session(SessionError::ExclusiveHeld(ref holder)) if holder == "/test/first"
+        ));
+        assert_ne!(second.state().await, SessionState::Active);
+        assert_eq!(first.state().await, SessionState::Active);
+
+        // Control frees up once the holder closes
+        manager.close_session(first.id()).await;
+        assert_eq!(manager.exclusive_holder().await, None);
+        assert_eq!(manager.start_session(&second).await.unwrap(), None);
+        assert_eq!(manager.exclusive_holder().await, Some(second.id().clone()));
+    }
+
+    #[tokio::test]
+    async fn exclusive_takeover_policy_preempts_holder() {
+        let (manager, mut rx) = SessionManager::new(SessionManagerConfig {
+            takeover_policy: TakeoverPolicy::Takeover,
+            ..Default::default()
+        });
+
+        let first = started_exclusive(&manager, "/test/first").await;
+        manager.start_session(&first).await.unwrap();
+        first
+            .send_event(ion_core::InputEvent::key(
+                42,
+                ion_core::event::KeyState::Pressed,
+            ))
+            .await
+            .unwrap();
+        rx.recv().await.unwrap();
+
+        let second = started_exclusive(&manager, "/test/second").await;
+        let preempted = manager.start_session(&second).await.unwrap();
+        assert_eq!(preempted, Some(first.id().clone()));
+
+        // The held key was released on the preempted session's behalf
+        let (id, event) = rx.recv().await.unwrap();
+        assert_eq!(id, *first.id());
+        assert_eq!(
+            event,
+            ion_core::InputEvent::key(42, ion_core::event::KeyState::Released)
+        );
+
+        assert!(manager.get_session(first.id()).await.is_none());
+        assert!(!manager.is_exclusive(first.id()).await);
+        assert_eq!(second.state().await, SessionState::Active);
+        assert_eq!(manager.exclusive_holder().await, Some(second.id().clone()));
+    }
+
+    #[tokio::test]
+    async fn non_exclusive_sessions_coexist_with_holder() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+
+        let holder = started_exclusive(&manager, "/test/holder").await;
+        manager.start_session(&holder).await.unwrap();
+
+        let shared = manager
+            .create_session(SessionId::new("/test/shared"), "app".into())
+            .await
+            .unwrap();
+        shared
+            .select_devices(ion_core::DeviceType::POINTER)
+            .await
+            .unwrap();
+        assert!(!manager.is_exclusive(shared.id()).await);
+        assert_eq!(manager.start_session(&shared).await.unwrap(), None);
+
+        assert_eq!(holder.state().await, SessionState::Active);
+        assert_eq!(shared.state().await, SessionState::Active);
+        assert_eq!(manager.exclusive_holder().await, Some(holder.id().clone()));
+    }
+
+    #[tokio::test]
+    async fn ownership_transfer_prevents_close() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let a = SessionId::new("/own/a");
+        let b = SessionId::new("/own/b");
+        manager
+            .create_session(a.clone(), "app".into())
+            .await
+            .unwrap();
+        manager
+            .create_session(b.clone(), "app".into())
+            .await
+            .unwrap();
+        manager.set_owner(&a, ":1.10").await;
+        manager.set_owner(&b, ":1.11").await;
+
+        let moved = manager.transfer_ownership(":1.10", Some(":1.20")).await;
+        assert_eq!(moved, vec![a.clone()]);
+        assert_eq!(manager.sessions_owned_by(":1.20").await, vec![a.clone()]);
+        assert!(manager.sessions_owned_by(":1.10").await.is_empty());
+
+        // The old connection leaving the bus no longer closes the session
+        assert!(manager.close_sessions_owned_by(":1.10").await.is_empty());
+        assert!(manager.get_session(&a).await.is_some());
+
+        // The untouched owner's cleanup still works
+        assert_eq!(
+            manager.close_sessions_owned_by(":1.11").await,
+            vec![b.clone()]
+        );
+        assert!(manager.get_session(&b).await.is_none());
+        assert_eq!(manager.owner_of(&b).await, None);
+    }
+
+    #[tokio::test]
+    async fn orphaned_sessions_are_reaped() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig {
+            orphan_grace: Duration::ZERO,
+            ..Default::default()
+        });
+        let orphan = SessionId::new("/own/orphan");
+        let claimed = SessionId::new("/own/claimed");
+        manager
+            .create_session(orphan.clone(), "app".into())
+            .await
+            .unwrap();
+        manager
+            .create_session(claimed.clone(), "app".into())
+            .await
+            .unwrap();
+        manager.set_owner(&orphan, ":1.30").await;
+        manager.set_owner(&claimed, ":1.30").await;
+
+        let moved = manager.transfer_ownership(":1.30", None).await;
+        assert_eq!(moved.len(), 2);
+        assert_eq!(manager.orphaned_sessions().await.len(), 2);
+
+        // A new owner claims one of them before the reap
+        manager.set_owner(&claimed, ":1.31").await;
+
+        assert_eq!(manager.reap_orphans().await, vec![orphan.clone()]);
+        assert!(manager.get_session(&orphan).await.is_none());
+        assert!(manager.get_session(&claimed).await.is_some());
+        assert!(manager.orphaned_sessions().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn orphan_grace_follows_clock() {
+        let clock = MockClock::new();
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let manager = manager.with_clock(Arc::new(clock.clone()));
+        let id = SessionId::new("/own/clock");
+        manager
+            .create_session(id.clone(), "app".into())
+            .await
+            .unwrap();
+        manager.set_owner(&id, ":1.50").await;
+        manager.transfer_ownership(":1.50", None).await;
+
+        clock.advance(Duration::from_secs(29));
+        assert!(manager.reap_orphans().await.is_empty());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(manager.reap_orphans().await, vec![id]);
+    }
+
+    #[tokio::test]
+    async fn idle_sessions_reaped_by_clock() {
+        let clock = MockClock::new();
+        let (manager, mut rx) = SessionManager::new(SessionManagerConfig {
+            idle_timeout: Some(Duration::from_secs(30)),
+            ..Default::default()
+        });
+        let manager = manager.with_clock(Arc::new(clock.clone()));
+        let idle = SessionId::new("/idle/quiet");
+        let busy = SessionId::new("/idle/busy");
+        manager
+            .create_session(idle.clone(), "app".into())
+            .await
+            .unwrap();
+        let session = manager
+            .create_session(busy.clone(), "app".into())
+            .await
+            .unwrap();
+        session
+            .select_devices(ion_core::DeviceType::POINTER)
+            .await
+            .unwrap();
+        session.start().await.unwrap();
+
+        clock.advance(Duration::from_secs(20));
+        assert!(manager.reap_idle().await.is_empty());
+
+        // Input restarts the busy session's timer
+        session
+            .send_event(InputEvent::pointer_motion(1.0, 0.0))
+            .await
+            .unwrap();
+        rx.recv().await.unwrap();
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(manager.reap_idle().await, vec![idle.clone()]);
+        assert!(manager.get_session(&idle).await.is_none());
+
+        clock.advance(Duration::from_secs(19));
+        assert!(manager.reap_idle().await.is_empty());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(manager.reap_idle().await, vec![busy]);
+        assert_eq!(manager.session_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn idle_reaping_disabled_by_default() {
+        let clock = MockClock::new();
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let manager = manager.with_clock(Arc::new(clock.clone()));
+        manager
+            .create_session(SessionId::new("/idle/forever"), "app".into())
+            .await
+            .unwrap();
+
+        clock.advance(Duration::from_secs(1_000_000));
+        assert!(manager.reap_idle().await.is_empty());
+        assert_eq!(manager.session_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn event_budget_exhaustion_is_reported() {
+        let (manager, mut rx) = SessionManager::new(SessionManagerConfig {
+            max_events_per_session: Some(3),
+            ..Default::default()
+        });
+        let mut exhausted = manager.budget_exhaustions();
+        let mut started = Vec::new();
+        for id in ["/budget/spent", "/budget/under"] {
+            let session = manager
+                .create_session(SessionId::new(id), "app".into())
+                .await
+                .unwrap();
+            session
+                .select_devices(ion_core::DeviceType::POINTER)
+                .await
+                .unwrap();
+            session.start().await.unwrap();
+            started.push(session);
+        }
+        let (spent, under) = (&started[0], &started[1]);
+
+        for _ in 0..3 {
+            spent
+                .send_event(InputEvent::pointer_motion(1.0, 0.0))
+                .await
+                .unwrap();
+        }
+        assert_eq!(exhausted.try_recv().unwrap(), *spent.id());
+        assert!(matches!(
+            spent.send_event(InputEvent::pointer_motion(1.0, 0.0)).await,
+            Err(Error::Session(SessionError::EventBudgetExhausted(3)))
+        ));
+        assert_eq!(spent.event_count().await, 3);
+
+        for _ in 0..2 {
+            under
+                .send_event(InputEvent::pointer_motion(0.0, 1.0))
+                .await
+                .unwrap();
+        }
+        assert!(exhausted.try_recv().is_err());
+        assert_eq!(under.event_count().await, 2);
+
+        for _ in 0..5 {
+            rx.recv().await.unwrap();
+        }
+        assert!(rx.try_recv().is_err());
+
+        // Whoever follows the exhaustions closes the session
+        assert!(manager.close_session(spent.id()).await.existed());
+        assert!(spent.is_closed().await);
+        assert_eq!(manager.session_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn orphans_survive_grace_period() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let id = SessionId::new("/own/grace");
+        manager
+            .create_session(id.clone(), "app".into())
+            .await
+            .unwrap();
+        manager.set_owner(&id, ":1.40").await;
+        manager.transfer_ownership(":1.40", None).await;
+
+        assert!(manager.reap_orphans().await.is_empty());
+        assert!(manager.get_session(&id).await.is_some());
+    }
+
+    /// Records every transition as `"<event> <session>"`.
+    #[derive(Default)]
+    struct RecordingObserver(Mutex<Vec<String>>);
+
+    impl crate::observer::SessionObserver for RecordingObserver {
+        fn on_created(&self, session: &SessionId, app_id: &str) {
+            self.push(format!("created {session} by {app_id}"));
+        }
+
+        fn on_started(&self, session: &SessionId) {
+            self.push(format!("started {session}"));
+        }
+
+        fn on_closed(&self, session: &SessionId) {
+            self.push(format!("closed {session}"));
+        }
+    }
+
+    impl RecordingObserver {
+        fn push(&self, entry: String) {
+            self.0.lock().unwrap().push(entry);
+        }
+
+        fn take(&self) -> Vec<String> {
+            std::mem::take(&mut *self.0.lock().unwrap())
+        }
+    }
+
+    #[tokio::test]
+    async fn observers_see_create_start_close_in_order() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let observer = Arc::new(RecordingObserver::default());
+        manager.clone().observers().register(observer.clone());
+        assert_eq!(manager.observers().len(), 1);
+
+        let id = SessionId::new("/test/observed");
+        let session = manager
+            .create_session(id.clone(), "app".into())
+            .await
+            .unwrap();
+        session
+            .select_devices(ion_core::DeviceType::POINTER)
+            .await
+            .unwrap();
+        manager.start_session(&session).await.unwrap();
+        manager.close_session(&id).await;
+        // Already gone, so not reported twice
+        manager.close_session(&id).await;
+
+        assert_eq!(
+            observer.take(),
+            [
+                "created /test/observed by app",
+                "started /test/observed",
+                "closed /test/observed",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn unregistered_observers_are_not_notified() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let observer = Arc::new(RecordingObserver::default());
+        let registered: Arc<dyn crate::observer::SessionObserver> = observer.clone();
+        manager.observers().register(Arc::clone(&registered));
+
+        assert!(manager.observers().unregister(&registered));
+        assert!(!manager.observers().unregister(&registered));
+        assert!(manager.observers().is_empty());
+
+        manager
+            .create_session(SessionId::new("/test/unobserved"), "app".into())
+            .await
+            .unwrap();
+        assert!(observer.take().is_empty());
+    }
+
+    #[tokio::test]
+    async fn observers_see_preempted_session_closed() {
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig {
+            takeover_policy: TakeoverPolicy::Takeover,
+            ..Default::default()
+        });
+        let observer = Arc::new(RecordingObserver::default());
+        manager.observers().register(observer.clone());
+
+        let first = started_exclusive(&manager, "/test/first").await;
+        manager.start_session(&first).await.unwrap();
+        let second = started_exclusive(&manager, "/test/second").await;
+        observer.take();
+
+        manager.start_session(&second).await.unwrap();
+        assert_eq!(
+            observer.take(),
+            ["closed /test/first", "started /test/second"]
+        );
+
+        manager.close_all().await;
+        assert_eq!(observer.take(), ["closed /test/second"]);
+    }
 }
@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Typing text into a session.
+//!
+//! Most text is typed as keysym presses, which the compositor maps onto
+//! the active keymap. Characters without a keysym that every keymap can be
+//! remapped to (most non-Latin scripts, emoji) are pasted instead: the
+//! selection is set to the text, Ctrl+V is pressed, and the user's previous
+//! selection is put back. [`TextInjectionStrategy`] picks between the two.
+
+use std::time::Duration;
+
+/// MIME type of pasted text.
+pub const TEXT_MIME_TYPE: &str = "text/plain;charset=utf-8";
+
+/// How long pasted text stays on the clipboard before the previous
+/// selection is restored, so the focused client can read it.
+pub const PASTE_SETTLE: Duration = Duration::from_millis(150);
+
+/// Keysym of the Return key.
+pub const KEYSYM_RETURN: i32 = 0xff0d;
+
+/// Keysym of the Tab key.
+pub const KEYSYM_TAB: i32 = 0xff09;
+
+/// Keysym of the left Control key.
+pub const KEYSYM_CONTROL_L: i32 = 0xffe3;
+
+/// Keysym of the `v` key.
+pub const KEYSYM_V: i32 = 0x76;
+
+/// How [`PortalCore::inject_text`](crate::PortalCore::inject_text) types
+/// text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextInjectionStrategy {
+    /// Key events only; fails on characters without a keysym
+    KeyEvents,
+    /// Paste the whole text through the clipboard
+    ClipboardPaste,
+    /// Key events where possible, pasting the characters in between
+    #[default]
+    Auto,
+}
+
+/// A run of text typed the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TextChunk {
+    /// Keysyms to press and release in order
+    Keys(Vec<i32>),
+    /// Text to paste
+    Paste(String),
+}
+
+/// Returns the keysym that types `c`, if there is one.
+///
+/// Latin-1 keysyms equal their code point, so printable ASCII and Latin-1
+/// map directly; newlines and tabs map to Return and Tab.
+#[must_use]
+pub fn keysym_for_char(c: char) -> Option<i32> {
+    match c {
+        '\n' | '\r' => Some(KEYSYM_RETURN),
+        '\t' => Some(KEYSYM_TAB),
+        ' '..='~' | '\u{a0}'..='\u{ff}' => i32::try_from(u32::from(c)).ok(),
+        _ => None,
+    }
+}
+
+/// Splits `text` into runs of key events and pastes.
+///
+/// Returns the first character without a keysym if `strategy` is
+/// [`TextInjectionStrategy::KeyEvents`].
+pub(crate) fn chunk(text: &str, strategy: TextInjectionStrategy) -> Result<Vec<TextChunk>, char> {
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+    if strategy == TextInjectionStrategy::ClipboardPaste {
+        return Ok(vec![TextChunk::Paste(text.to_string())]);
+    }
+
+    let mut chunks: Vec<TextChunk> = Vec::new();
+    for c in text.chars() {
+        match (keysym_for_char(c), chunks.last_mut()) {
+            (Some(keysym), Some(TextChunk::Keys(keys))) => keys.push(keysym),
+            (Some(keysym), _) => chunks.push(TextChunk::Keys(vec![keysym])),
+            (None, _) if strategy == TextInjectionStrategy::KeyEvents => return Err(c),
+            (None, Some(TextChunk::Paste(pasted))) => pasted.push(c),
+            (None, _) => chunks.push(TextChunk::Paste(c.to_string())),
+        }
+    }
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_pastes_only_runs_without_keysyms() {
+        assert_eq!(
+            chunk("hi 👋🏽!", TextInjectionStrategy::Auto),
+            Ok(vec![
+                TextChunk::Keys(vec![0x68, 0x69, 0x20]),
+                TextChunk::Paste("👋🏽".to_string()),
+                TextChunk::Keys(vec![0x21]),
+            ])
+        );
+        assert_eq!(chunk("日本", TextInjectionStrategy::KeyEvents), Err('日'));
+        assert_eq!(
+            chunk("é\n", TextInjectionStrategy::ClipboardPaste),
+            Ok(vec![TextChunk::Paste("é\n".to_string())])
+        );
+        assert_eq!(keysym_for_char('é'), Some(0xe9));
+    }
+}
@@ -0,0 +1,835 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Delivering session input to the compositor.
+//!
+//! The [`SessionManager`](crate::session_manager::SessionManager) hands
+//! validated events to the compositor as `(SessionId, StampedEvent)` pairs.
+//! A [`CompositorTransport`] carries them the rest of the way, along with
+//! the pointer locks sessions take and the pointer transforms they ask
+//! for: [`InProcessTransport`] when the compositor side lives in the same
+//! process, [`UnixSocketTransport`] when it is a separate service. The
+//! compositor side receives all of them as [`CompositorMessage`]s.
+//!
+//! ## Framing
+//!
+//! On the socket every event is one JSON object per line, using the serde
+//! form of [`InputEvent`]:
+//!
+//! ```text
+//! {"session":"/org/freedesktop/portal/desktop/session/1","event":{"type":"keyboard_keycode","keycode":30,"state":"pressed"}}
+//! ```
+//!
+//! Events the client stamped also carry `"client_timestamp"`, in
+//! microseconds since the Unix epoch, and events of sessions bound to a
+//! seat carry its name as `"seat"`; both are omitted otherwise.
+//!
+//! A session locking or releasing the pointer is a frame of its own:
+//!
+//! ```text
+//! {"session":"/org/freedesktop/portal/desktop/session/1","pointer_lock":true}
+//! ```
+//!
+//! So is setting a session's pointer transform, in the serde form of
+//! [`PointerTransform`]:
+//!
+//! ```text
+//! {"session":"/org/freedesktop/portal/desktop/session/1","pointer_transform":{"sensitivity":2.0,"accel_profile":"adaptive"}}
+//! ```
+//!
+//! The compositor side accepts connections with [`serve_events`], which
+//! reads them back with [`forward_events`].
+//!
+//! ## Reconnection
+//!
+//! The socket transport connects on first use. When a write fails because
+//! the compositor went away, it reconnects once and resends the event;
+//! if the compositor cannot be reached the event is reported as failed
+//! and the next one tries again.
+//!
+//! Keys and buttons pressed over a lost connection may be stuck on the
+//! compositor side, so a new connection first releases everything each
+//! session held. Pointer locks are taken again and pointer transforms
+//! set again, in case the compositor restarted without them.
+//!
+//! A compositor that stops reading would block senders once the socket
+//! buffer fills. Writes give up after [`DEFAULT_SEND_TIMEOUT`] instead,
+//! failing the event and dropping the connection.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::de::Error as _;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+
+use ion_core::error::InvalidSessionId;
+use ion_core::event::{InputEvent, PointerTransform, StampedEvent};
+use ion_core::session::{HeldInputs, SessionId};
+
+/// How long [`UnixSocketTransport`] waits for the compositor to take an
+/// event.
+pub const DEFAULT_SEND_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Failure to deliver an event to the compositor.
+#[derive(Debug, Error)]
+pub enum TransportError {
+    /// The compositor side has gone away
+    #[error("compositor transport closed")]
+    Closed,
+    /// The compositor did not take the event in time
+    #[error("compositor did not take the event within {0:?}")]
+    Timeout(Duration),
+    /// Reading or writing the socket failed
+    #[error("compositor transport I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    /// A frame could not be encoded or decoded
+    #[error("malformed transport frame: {0}")]
+    Frame(#[from] serde_json::Error),
+    /// A decoded frame named an invalid session
+    #[error("transport frame has an invalid session: {0}")]
+    Session(#[from] InvalidSessionId),
+}
+
+/// Result type for transport operations.
+pub type TransportResult<T> = std::result::Result<T, TransportError>;
+
+/// What the compositor side of a transport receives.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompositorMessage {
+    /// Input a session sent
+    Event(SessionId, StampedEvent),
+    /// A session locked (`true`) or released (`false`) the pointer
+    PointerLock(SessionId, bool),
+    /// A session's relative motion is to be transformed; the identity
+    /// removes its transform
+    PointerTransform(SessionId, PointerTransform),
+}
+
+/// Carries session input to the compositor.
+#[async_trait]
+pub trait CompositorTransport: Send + Sync {
+    /// Delivers one event from `session_id`.
+    async fn send(&self, session_id: &SessionId, event: &StampedEvent) -> TransportResult<()>;
+
+    /// Tells the compositor that `session_id` locked or released the
+    /// pointer, ordered with the session's events.
+    async fn set_pointer_lock(&self, session_id: &SessionId, locked: bool) -> TransportResult<()>;
+
+    /// Tells the compositor how to transform `session_id`'s relative
+    /// motion, ordered with the session's events.
+    async fn set_pointer_transform(
+        &self,
+        session_id: &SessionId,
+        transform: PointerTransform,
+    ) -> TransportResult<()>;
+
+    /// Name used in logs.
+    fn name(&self) -> &'static str;
+}
+
+/// Transport to a compositor in the same process, over a channel.
+#[derive(Debug, Clone)]
+pub struct InProcessTransport {
+    tx: mpsc::Sender<CompositorMessage>,
+}
+
+impl InProcessTransport {
+    /// Delivers events, pointer locks and pointer transforms to `tx`.
+    pub fn new(tx: mpsc::Sender<CompositorMessage>) -> Self {
+        Self { tx }
+    }
+}
+
+#[async_trait]
+impl CompositorTransport for InProcessTransport {
+    async fn send(&self, session_id: &SessionId, event: &StampedEvent) -> TransportResult<()> {
+        self.tx
+            .send(CompositorMessage::Event(session_id.clone(), event.clone()))
+            .await
+            .map_err(|_| TransportError::Closed)
+    }
+
+    async fn set_pointer_lock(&self, session_id: &SessionId, locked: bool) -> TransportResult<()> {
+        self.tx
+            .send(CompositorMessage::PointerLock(session_id.clone(), locked))
+            .await
+            .map_err(|_| TransportError::Closed)
+    }
+
+    async fn set_pointer_transform(
+        &self,
+        session_id: &SessionId,
+        transform: PointerTransform,
+    ) -> TransportResult<()> {
+        self.tx
+            .send(CompositorMessage::PointerTransform(
+                session_id.clone(),
+                transform,
+            ))
+            .await
+            .map_err(|_| TransportError::Closed)
+    }
+
+    fn name(&self) -> &'static str {
+        "in-process"
+    }
+}
+
+/// Transport to a compositor service listening on a Unix socket.
+///
+/// See the [module docs](self) for the framing and reconnection.
+#[derive(Debug)]
+pub struct UnixSocketTransport {
+    path: PathBuf,
+    send_timeout: Duration,
+    connection: Mutex<Connection>,
+}
+
+/// The socket, and what was pressed, locked and transformed through it.
+#[derive(Debug, Default)]
+struct Connection {
+    stream: Option<UnixStream>,
+    /// Inputs each session holds on the compositor side, and their seat
+    held: HashMap<SessionId, (HeldInputs, Option<String>)>,
+    /// Sessions holding the pointer lock
+    locked: HashSet<SessionId>,
+    /// Sessions with a pointer transform other than the identity
+    transforms: HashMap<SessionId, PointerTransform>,
+}
+
+/// What is being sent while reconnecting, so restoring the lost
+/// connection's state does not duplicate it.
+#[derive(Clone, Copy)]
+enum Sending<'a> {
+    Event(&'a SessionId, &'a InputEvent),
+    PointerLock(&'a SessionId),
+    PointerTransform(&'a SessionId),
+}
+
+impl Connection {
+    fn track(&mut self, session_id: &SessionId, event: &StampedEvent) {
+        let (held, seat) = self.held.entry(session_id.clone()).or_default();
+        held.track(&event.event);
+        seat.clone_from(&event.seat);
+        if held.is_empty() {
+            self.held.remove(session_id);
+        }
+    }
+
+    fn track_lock(&mut self, session_id: &SessionId, locked: bool) {
+        if locked {
+            self.locked.insert(session_id.clone());
+        } else {
+            self.locked.remove(session_id);
+        }
+    }
+
+    fn track_transform(&mut self, session_id: &SessionId, transform: PointerTransform) {
+        if transform.is_identity() {
+            self.transforms.remove(session_id);
+        } else {
+            self.transforms.insert(session_id.clone(), transform);
+        }
+    }
+
+    /// Frames restoring what the lost connection left behind: releases
+    /// of everything held, then the pointer locks and transforms.
+    fn restore(&mut self, sending: Sending<'_>) -> TransportResult<Vec<String>> {
+        let mut frames = Vec::new();
+        for (held_by, (mut inputs, seat)) in std::mem::take(&mut self.held) {
+            for release in inputs.drain_releases() {
+                if matches!(sending, Sending::Event(id, event) if *id == held_by && *event == release)
+                {
+                    continue;
+                }
+                let release = StampedEvent::from(release).with_seat(seat.clone());
+                frames.push(encode_frame(&held_by, &release)?);
+            }
+        }
+        for locked_by in &self.locked {
+            if !matches!(sending, Sending::PointerLock(id) if id == locked_by) {
+                frames.push(encode_pointer_lock_frame(locked_by, true)?);
+            }
+        }
+        for (transformed, transform) in &self.transforms {
+            if !matches!(sending, Sending::PointerTransform(id) if id == transformed) {
+                frames.push(encode_pointer_transform_frame(transformed, *transform)?);
+            }
+        }
+        Ok(frames)
+    }
+}
+
+impl UnixSocketTransport {
+    /// Delivers events to the socket at `path`, connecting on first use.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            send_timeout: DEFAULT_SEND_TIMEOUT,
+            connection: Mutex::default(),
+        }
+    }
+
+    /// Sets how long to wait for the compositor to take an event.
+    #[must_use]
+    pub fn with_send_timeout(mut self, timeout: Duration) -> Self {
+        self.send_timeout = timeout;
+        self
+    }
+
+    /// Path of the compositor's socket.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn connect(&self) -> TransportResult<UnixStream> {
+        let stream = tokio::time::timeout(self.send_timeout, UnixStream::connect(&self.path))
+            .await
+            .map_err(|_| TransportError::Timeout(self.send_timeout))??;
+        debug!(path = %self.path.display(), "Connected to compositor");
+        Ok(stream)
+    }
+
+    async fn write(&self, stream: &mut UnixStream, frame: &str) -> TransportResult<()> {
+        tokio::time::timeout(self.send_timeout, stream.write_all(frame.as_bytes()))
+            .await
+            .map_err(|_| TransportError::Timeout(self.send_timeout))?
+            .map_err(TransportError::from)
+    }
+
+    /// Writes `frame`, reconnecting once if the connection was lost.
+    async fn deliver(
+        &self,
+        connection: &mut Connection,
+        frame: &str,
+        sending: Sending<'_>,
+    ) -> TransportResult<()> {
+        if let Some(stream) = connection.stream.as_mut() {
+            match self.write(stream, frame).await {
+                Ok(()) => return Ok(()),
+                Err(e @ TransportError::Timeout(_)) => {
+                    // A partial frame may be on the wire; start afresh
+                    warn!(error = %e, "Compositor stalled, dropping connection");
+                    connection.stream = None;
+                    return Err(e);
+                },
+                Err(e) => {
+                    warn!(error = %e, "Lost compositor connection, reconnecting");
+                    connection.stream = None;
+                },
+            }
+        }
+
+        let mut stream = self.connect().await?;
+        for restore in connection.restore(sending)? {
+            self.write(&mut stream, &restore).await?;
+        }
+        self.write(&mut stream, frame).await?;
+        connection.stream = Some(stream);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CompositorTransport for UnixSocketTransport {
+    async fn send(&self, session_id: &SessionId, event: &StampedEvent) -> TransportResult<()> {
+        let frame = encode_frame(session_id, event)?;
+        let mut connection = self.connection.lock().await;
+        self.deliver(
+            &mut connection,
+            &frame,
+            Sending::Event(session_id, &event.event),
+        )
+        .await?;
+        connection.track(session_id, event);
+        Ok(())
+    }
+
+    async fn set_pointer_lock(&self, session_id: &SessionId, locked: bool) -> TransportResult<()> {
+        let frame = encode_pointer_lock_frame(session_id, locked)?;
+        let mut connection = self.connection.lock().await;
+        self.deliver(&mut connection, &frame, Sending::PointerLock(session_id))
+            .await?;
+        connection.track_lock(session_id, locked);
+        Ok(())
+    }
+
+    async fn set_pointer_transform(
+        &self,
+        session_id: &SessionId,
+        transform: PointerTransform,
+    ) -> TransportResult<()> {
+        let frame = encode_pointer_transform_frame(session_id, transform)?;
+        let mut connection = self.connection.lock().await;
+        self.deliver(
+            &mut connection,
+            &frame,
+            Sending::PointerTransform(session_id),
+        )
+        .await?;
+        connection.track_transform(session_id, transform);
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "unix-socket"
+    }
+}
+
+/// Wire form of one event.
+#[derive(Serialize)]
+struct Frame<'a> {
+    session: &'a str,
+    event: &'a InputEvent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_timestamp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seat: Option<&'a str>,
+}
+
+/// Wire form of a pointer lock change.
+#[derive(Serialize)]
+struct LockFrame<'a> {
+    session: &'a str,
+    pointer_lock: bool,
+}
+
+/// Wire form of a pointer transform change.
+#[derive(Serialize)]
+struct TransformFrame<'a> {
+    session: &'a str,
+    pointer_transform: PointerTransform,
+}
+
+/// Wire form of one event, pointer lock or pointer transform change, as
+/// decoded.
+#[derive(Deserialize)]
+struct OwnedFrame {
+    session: String,
+    #[serde(default)]
+    event: Option<InputEvent>,
+    #[serde(default)]
+    pointer_lock: Option<bool>,
+    #[serde(default)]
+    pointer_transform: Option<PointerTransform>,
+    #[serde(default)]
+    client_timestamp: Option<u64>,
+    #[serde(default)]
+    seat: Option<String>,
+}
+
+/// Encodes an event as one newline-terminated frame.
+pub fn encode_frame(session_id: &SessionId, event: &StampedEvent) -> TransportResult<String> {
+    let mut line = serde_json::to_string(&Frame {
+        session: session_id.as_str(),
+        event: &event.event,
+        client_timestamp: event.client_timestamp,
+        seat: event.seat.as_deref(),
+    })?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Encodes a pointer lock change as one newline-terminated frame.
+pub fn encode_pointer_lock_frame(session_id: &SessionId, locked: bool) -> TransportResult<String> {
+    let mut line = serde_json::to_string(&LockFrame {
+        session: session_id.as_str(),
+        pointer_lock: locked,
+    })?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Encodes a pointer transform change as one newline-terminated frame.
+pub fn encode_pointer_transform_frame(
+    session_id: &SessionId,
+    transform: PointerTransform,
+) -> TransportResult<String> {
+    let mut line = serde_json::to_string(&TransformFrame {
+        session: session_id.as_str(),
+        pointer_transform: transform,
+    })?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Decodes one frame, with or without its trailing newline.
+pub fn decode_frame(line: &str) -> TransportResult<CompositorMessage> {
+    let frame: OwnedFrame = serde_json::from_str(line.trim_end())?;
+    let session_id = SessionId::new_validated(&frame.session)?;
+    match (frame.event, frame.pointer_lock, frame.pointer_transform) {
+        (Some(event), None, None) => Ok(CompositorMessage::Event(
+            session_id,
+            StampedEvent::new(event, frame.client_timestamp).with_seat(frame.seat),
+        )),
+        (None, Some(locked), None) => Ok(CompositorMessage::PointerLock(session_id, locked)),
+        (None, None, Some(transform)) => {
+            Ok(CompositorMessage::PointerTransform(session_id, transform))
+        },
+        _ => Err(serde_json::Error::custom(
+            "frame needs exactly one of an event, a pointer_lock or a pointer_transform",
+        )
+        .into()),
+    }
+}
+
+/// Accepts [`UnixSocketTransport`] connections on `listener` and forwards
+/// what they carry to `tx`.
+///
+/// This is the compositor service's end of the socket; each connection is
+/// read with [`forward_events`] on its own task. Dropping the returned
+/// future closes every connection it accepted.
+///
+/// # Errors
+///
+/// Returns [`TransportError::Closed`] once `tx` is closed, and
+/// [`TransportError::Io`] if accepting fails.
+pub async fn serve_events(
+    listener: UnixListener,
+    tx: mpsc::Sender<CompositorMessage>,
+) -> TransportResult<()> {
+    let mut connections = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _) = accepted?;
+                debug!("Portal connected to compositor socket");
+                let tx = tx.clone();
+                connections.spawn(async move {
+                    if let Err(e) = forward_events(BufReader::new(stream), &tx).await {
+                        debug!(error = %e, "Portal connection ended");
+                    }
+                });
+            },
+            Some(_) = connections.join_next() => {},
+            () = tx.closed() => return Err(TransportError::Closed),
+        }
+    }
+}
+
+/// Reads frames from a connected transport and forwards what they carry
+/// to `tx`.
+///
+/// This is the compositor side of [`UnixSocketTransport`]. Malformed
+/// frames are skipped. Returns once the peer disconnects, or with
+/// [`TransportError::Closed`] once `tx` is closed.
+pub async fn forward_events<R>(
+    reader: R,
+    tx: &mpsc::Sender<CompositorMessage>,
+) -> TransportResult<()>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await? {
+        match decode_frame(&line) {
+            Ok(message) => tx.send(message).await.map_err(|_| TransportError::Closed)?,
+            Err(e) => warn!(error = %e, "Skipping malformed transport frame"),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ion_core::event::{AccelProfile, ButtonState, KeyState};
+    use tokio::task::JoinHandle;
+
+    /// Mock compositor service: serves the socket at `path`, forwarding
+    /// what it receives to `tx`.
+    fn mock_compositor(
+        path: &Path,
+        tx: mpsc::Sender<CompositorMessage>,
+    ) -> JoinHandle<TransportResult<()>> {
+        tokio::spawn(serve_events(UnixListener::bind(path).unwrap(), tx))
+    }
+
+    /// Restarts the mock compositor serving `path`.
+    async fn restart(
+        compositor: JoinHandle<TransportResult<()>>,
+        path: &Path,
+        tx: mpsc::Sender<CompositorMessage>,
+    ) -> JoinHandle<TransportResult<()>> {
+        compositor.abort();
+        let _ = compositor.await;
+        std::fs::remove_file(path).unwrap();
+        mock_compositor(path, tx)
+    }
+
+    #[tokio::test]
+    async fn unix_socket_round_trips_events_and_reconnects() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("compositor.sock");
+        let (tx, mut rx) = mpsc::channel(16);
+        let compositor = mock_compositor(&path, tx.clone());
+
+        let transport = UnixSocketTransport::new(&path);
+        let session = SessionId::new("/org/freedesktop/portal/desktop/session/1");
+        let events = [
+            StampedEvent::from(InputEvent::pointer_motion(1.5, -2.0)),
+            StampedEvent::new(
+                InputEvent::key(30, KeyState::Pressed),
+                Some(1_700_000_000_000_000),
+            ),
+            StampedEvent::from(InputEvent::PointerButton {
+                button: 0x110,
+                state: ButtonState::Released,
+            })
+            .with_seat(Some("seat1".into())),
+        ];
+        for event in &events {
+            transport.send(&session, event).await.unwrap();
+        }
+        for event in &events {
+            assert_eq!(
+                rx.recv().await.unwrap(),
+                CompositorMessage::Event(session.clone(), event.clone())
+            );
+        }
+
+        // The compositor restarts; the next event reconnects
+        let _compositor = restart(compositor, &path, tx).await;
+
+        let release = StampedEvent::from(InputEvent::key(30, KeyState::Released));
+        transport.send(&session, &release).await.unwrap();
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            CompositorMessage::Event(session, release)
+        );
+        assert!(rx.try_recv().is_err(), "release is not sent twice");
+    }
+
+    #[tokio::test]
+    async fn reconnect_releases_inputs_held_on_the_lost_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("compositor.sock");
+        let (tx, mut rx) = mpsc::channel(16);
+        let compositor = mock_compositor(&path, tx.clone());
+
+        let transport = UnixSocketTransport::new(&path);
+        let typing = SessionId::new("/test/typing");
+        let clicking = SessionId::new("/test/clicking");
+        transport
+            .send(&typing, &InputEvent::key(42, KeyState::Pressed).into())
+            .await
+            .unwrap();
+        let click = StampedEvent::from(InputEvent::pointer_button(0x110, ButtonState::Pressed))
+            .with_seat(Some("seat1".into()));
+        transport.send(&clicking, &click).await.unwrap();
+        rx.recv().await.unwrap();
+        rx.recv().await.unwrap();
+
+        let _compositor = restart(compositor, &path, tx).await;
+        let motion = StampedEvent::from(InputEvent::pointer_motion(1.0, 0.0));
+        transport.send(&typing, &motion).await.unwrap();
+
+        let mut released = vec![rx.recv().await.unwrap(), rx.recv().await.unwrap()];
+        released.sort_by_key(|message| match message {
+            CompositorMessage::Event(id, _)
+            | CompositorMessage::PointerLock(id, _)
+            | CompositorMessage::PointerTransform(id, _) => id.as_str().to_owned(),
+        });
+        assert_eq!(
+            released,
+            [
+                CompositorMessage::Event(
+                    clicking,
+                    StampedEvent::from(InputEvent::pointer_button(0x110, ButtonState::Released))
+                        .with_seat(Some("seat1".into()))
+                ),
+                CompositorMessage::Event(
+                    typing.clone(),
+                    InputEvent::key(42, KeyState::Released).into()
+                ),
+            ]
+        );
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            CompositorMessage::Event(typing, motion)
+        );
+    }
+
+    #[tokio::test]
+    async fn pointer_locks_are_sent_and_retaken_after_reconnect() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("compositor.sock");
+        let (tx, mut rx) = mpsc::channel(16);
+        let compositor = mock_compositor(&path, tx.clone());
+
+        let transport = UnixSocketTransport::new(&path);
+        let gaming = SessionId::new("/test/gaming");
+        let released = SessionId::new("/test/released");
+        transport.set_pointer_lock(&gaming, true).await.unwrap();
+        transport.set_pointer_lock(&released, true).await.unwrap();
+        transport.set_pointer_lock(&released, false).await.unwrap();
+        for expected in [
+            CompositorMessage::PointerLock(gaming.clone(), true),
+            CompositorMessage::PointerLock(released.clone(), true),
+            CompositorMessage::PointerLock(released, false),
+        ] {
+            assert_eq!(rx.recv().await.unwrap(), expected);
+        }
+
+        let _compositor = restart(compositor, &path, tx).await;
+        let motion = StampedEvent::from(InputEvent::pointer_motion(1.0, 0.0));
+        transport.send(&gaming, &motion).await.unwrap();
+
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            CompositorMessage::PointerLock(gaming.clone(), true)
+        );
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            CompositorMessage::Event(gaming, motion)
+        );
+        assert!(rx.try_recv().is_err(), "released lock is not retaken");
+    }
+
+    #[tokio::test]
+    async fn pointer_transforms_are_sent_and_set_again_after_reconnect() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("compositor.sock");
+        let (tx, mut rx) = mpsc::channel(16);
+        let compositor = mock_compositor(&path, tx.clone());
+
+        let transport = UnixSocketTransport::new(&path);
+        let trackpad = SessionId::new("/test/trackpad");
+        let reset = SessionId::new("/test/reset");
+        let transform =
+            PointerTransform::with_sensitivity(2.0).with_accel_profile(AccelProfile::Adaptive);
+        transport
+            .set_pointer_transform(&trackpad, transform)
+            .await
+            .unwrap();
+        transport
+            .set_pointer_transform(&reset, transform)
+            .await
+            .unwrap();
+        transport
+            .set_pointer_transform(&reset, PointerTransform::IDENTITY)
+            .await
+            .unwrap();
+        for expected in [
+            CompositorMessage::PointerTransform(trackpad.clone(), transform),
+            CompositorMessage::PointerTransform(reset.clone(), transform),
+            CompositorMessage::PointerTransform(reset, PointerTransform::IDENTITY),
+        ] {
+            assert_eq!(rx.recv().await.unwrap(), expected);
+        }
+
+        let _compositor = restart(compositor, &path, tx).await;
+        let motion = StampedEvent::from(InputEvent::pointer_motion(1.0, 0.0));
+        transport.send(&trackpad, &motion).await.unwrap();
+
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            CompositorMessage::PointerTransform(trackpad.clone(), transform)
+        );
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            CompositorMessage::Event(trackpad, motion)
+        );
+        assert!(rx.try_recv().is_err(), "removed transform is not set again");
+    }
+
+    #[tokio::test]
+    async fn stalled_compositor_times_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("compositor.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        // Accepts the connection but never reads from it
+        let _stalled = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+            drop(stream);
+        });
+
+        let timeout = Duration::from_millis(50);
+        let transport = UnixSocketTransport::new(&path).with_send_timeout(timeout);
+        let session = SessionId::new("/test/stalled");
+        let event = StampedEvent::from(InputEvent::pointer_motion(1.0, 1.0));
+        let result = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Err(e) = transport.send(&session, &event).await {
+                    return e;
+                }
+            }
+        })
+        .await
+        .expect("send gave up instead of blocking");
+        assert!(matches!(result, TransportError::Timeout(t) if t == timeout));
+    }
+
+    #[tokio::test]
+    async fn serving_ends_when_the_compositor_stops_taking_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("compositor.sock");
+        let (tx, rx) = mpsc::channel(1);
+        let compositor = mock_compositor(&path, tx);
+
+        drop(rx);
+        let result = compositor.await.unwrap();
+        assert!(matches!(result, Err(TransportError::Closed)));
+    }
+
+    #[tokio::test]
+    async fn unreachable_compositor_fails_the_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let transport = UnixSocketTransport::new(dir.path().join("missing.sock"));
+
+        let result = transport
+            .send(
+                &SessionId::new("/test/1"),
+                &InputEvent::key(30, KeyState::Pressed).into(),
+            )
+            .await;
+        assert!(matches!(result, Err(TransportError::Io(_))));
+    }
+
+    #[tokio::test]
+    async fn malformed_frames_are_skipped() {
+        let session = SessionId::new("/test/frames");
+        let event = StampedEvent::from(InputEvent::key(30, KeyState::Pressed));
+        let input = format!(
+            "not json\n{{\"session\":\"relative\",\"event\":\"Nope\"}}\n{{\"session\":\"/test/frames\"}}\n{}{}",
+            encode_frame(&session, &event).unwrap(),
+            encode_pointer_lock_frame(&session, true).unwrap()
+        );
+        let (tx, mut rx) = mpsc::channel(4);
+
+        forward_events(input.as_bytes(), &tx).await.unwrap();
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            CompositorMessage::Event(session.clone(), event)
+        );
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            CompositorMessage::PointerLock(session, true)
+        );
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn in_process_transport_reports_closed_channel() {
+        let (tx, rx) = mpsc::channel(1);
+        let transport = InProcessTransport::new(tx);
+        drop(rx);
+
+        let result = transport
+            .send(
+                &SessionId::new("/test/1"),
+                &InputEvent::key(30, KeyState::Pressed).into(),
+            )
+            .await;
+        assert!(matches!(result, Err(TransportError::Closed)));
+    }
+}
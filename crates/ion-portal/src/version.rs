@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Interface version reported to clients.
+//!
+//! Clients key behavior off the `version` property, so it must describe
+//! the methods this build actually implements. Each method set has the
+//! version that introduced it; [`PORTAL_VERSION`] is the highest one
+//! compiled in. Optional sets come after those every build has, so a
+//! build without them still reports all the methods it does have.
+//!
+//! A portal may be pinned to a lower version for clients that mishandle
+//! newer ones, through `with_version` on
+//! [`RemoteDesktopPortal`](crate::portal::RemoteDesktopPortal) or
+//! [`PortalCore`](crate::core::PortalCore). Methods above the reported
+//! version then fail with [`PortalError::UnsupportedVersion`].
+
+use ion_core::error::PortalError;
+use ion_core::Result;
+
+/// Version of the base input and session methods.
+pub const BASE_VERSION: u32 = 1;
+
+/// Version that introduced touchpad gestures.
+pub const GESTURE_VERSION: u32 = 2;
+
+/// Version that introduced clipboard transfer, behind the `clipboard`
+/// feature.
+pub const CLIPBOARD_VERSION: u32 = 3;
+
+/// Version of the method set compiled into this build.
+pub const PORTAL_VERSION: u32 = if cfg!(feature = "clipboard") {
+    CLIPBOARD_VERSION
+} else {
+    GESTURE_VERSION
+};
+
+/// Fails unless a portal reporting `reported` offers `method`, which was
+/// introduced in `required`.
+///
+/// # Errors
+///
+/// Returns [`PortalError::UnsupportedVersion`] if `reported` is lower
+/// than `required`.
+pub fn require(reported: u32, required: u32, method: &'static str) -> Result<()> {
+    if reported < required {
+        return Err(PortalError::UnsupportedVersion {
+            method,
+            required,
+            reported,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_matches_enabled_features() {
+        if cfg!(feature = "clipboard") {
+            assert_eq!(PORTAL_VERSION, CLIPBOARD_VERSION);
+        } else {
+            assert_eq!(PORTAL_VERSION, GESTURE_VERSION);
+        }
+    }
+
+    #[test]
+    fn every_build_reports_gestures() {
+        assert!(require(PORTAL_VERSION, GESTURE_VERSION, "NotifyGestureHold").is_ok());
+    }
+
+    #[test]
+    fn require_names_method_and_versions() {
+        assert!(require(3, CLIPBOARD_VERSION, "SetSelection").is_ok());
+
+        let err = require(2, CLIPBOARD_VERSION, "SetSelection").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("SetSelection"), "{message}");
+        assert!(message.contains("version 3"), "{message}");
+    }
+}
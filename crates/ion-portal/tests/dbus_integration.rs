@@ -17,7 +17,7 @@ use zbus::zvariant::{ObjectPath, OwnedValue};
 use zbus::Connection;
 
 use ion_core::device::DeviceType;
-use ion_core::event::InputEvent;
+use ion_core::event::{InputEvent, StampedEvent};
 use ion_core::mode::RemoteDesktopMode;
 use ion_core::session::SessionId;
 use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
@@ -30,7 +30,7 @@ struct DbusTestEnv {
     /// Connection to the test bus
     connection: Connection,
     /// Event receiver
-    event_rx: mpsc::Receiver<(SessionId, InputEvent)>,
+    event_rx: mpsc::Receiver<(SessionId, StampedEvent)>,
 }
 
 impl DbusTestEnv {
@@ -213,7 +213,7 @@ async fn test_dbus_properties() {
 
     // Test properties
     let version = proxy.version().await.unwrap();
-    assert_eq!(version, 2);
+    assert_eq!(version, ion_portal::PORTAL_VERSION);
 
     let device_types = proxy.available_device_types().await.unwrap();
     assert_eq!(device_types, DeviceType::desktop_standard().bits());
@@ -366,19 +366,19 @@ async fn test_dbus_full_session_lifecycle() {
         .await
         .expect("Timeout waiting for event")
         .expect("Channel closed");
-    assert!(matches!(event1.1, InputEvent::PointerMotion { .. }));
+    assert!(matches!(event1.1.event, InputEvent::PointerMotion { .. }));
 
     let event2 = timeout(Duration::from_secs(1), env.event_rx.recv())
         .await
         .expect("Timeout waiting for event")
         .expect("Channel closed");
-    assert!(matches!(event2.1, InputEvent::PointerButton { .. }));
+    assert!(matches!(event2.1.event, InputEvent::PointerButton { .. }));
 
     let event3 = timeout(Duration::from_secs(1), env.event_rx.recv())
         .await
         .expect("Timeout waiting for event")
         .expect("Channel closed");
-    assert!(matches!(event3.1, InputEvent::KeyboardKeycode { .. }));
+    assert!(matches!(event3.1.event, InputEvent::KeyboardKeycode { .. }));
 }
 
 #[tokio::test]
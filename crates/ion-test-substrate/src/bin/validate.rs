@@ -6,7 +6,10 @@
 //! Runs headlessly, suitable for CI/CD pipelines and agent automation.
 
 use clap::{Parser, ValueEnum};
-use ion_test_substrate::{TestHarness, TestHarnessConfig, ValidationResult};
+use ion_compositor::rate_limiter::{RateLimitMiddleware, RateLimiterConfig};
+use ion_test_substrate::{
+    SuiteReport, TestHarness, TestHarnessConfig, ValidationResult, ValidationSuite, Validator,
+};
 use tracing::{error, info, Level};
 use tracing_subscriber::FmtSubscriber;
 
@@ -40,6 +43,44 @@ struct Args {
     /// Timeout in milliseconds
     #[arg(long, default_value = "5000")]
     timeout: u64,
+
+    /// Run the full suite, up to N independent validators at once
+    #[arg(long, value_name = "N", conflicts_with = "test")]
+    parallel: Option<usize>,
+}
+
+/// Run the session lifecycle on `harness` and validate it.
+async fn session_test(harness: &TestHarness) -> anyhow::Result<ValidationResult> {
+    let session = harness.create_session("test-session").await?;
+    harness
+        .select_devices(&session, ion_test_substrate::DeviceType::all())
+        .await?;
+    harness.start_session(&session).await?;
+    harness.close_session(&session).await?;
+    Ok(harness.validate().await)
+}
+
+/// Every validator, each with its own harness (and so its own mock bus).
+fn full_suite(config: &TestHarnessConfig) -> ValidationSuite {
+    let smoke_config = config.clone();
+    let session_config = config.clone();
+    ValidationSuite::new()
+        .add("smoke", &[], move || async move {
+            TestHarness::spawn_with_config(smoke_config)
+                .await?
+                .smoke_test()
+                .await
+        })
+        .add("session", &["smoke"], move || async move {
+            session_test(&TestHarness::spawn_with_config(session_config).await?).await
+        })
+        .add("rate_limiting", &[], || async {
+            let mut validator = Validator::new();
+            validator
+                .validate_rate_limiting(&RateLimitMiddleware::new(RateLimiterConfig::default()))
+                .await;
+            Ok(validator.build())
+        })
 }
 
 fn print_result_text(result: &ValidationResult) {
@@ -81,6 +122,33 @@ fn print_result_json(result: &ValidationResult) {
     }
 }
 
+fn print_suite_text(report: &SuiteReport) {
+    print_result_text(&report.combined());
+    for test in &report.tests {
+        let status = if test.passed() { "✓" } else { "✗" };
+        println!(
+            "{status} {:<24} started {:>8.1} ms  took {:>8.1} ms",
+            test.name, test.started_ms, test.duration_ms
+        );
+        if let Some(ref error) = test.error {
+            println!("    {error}");
+        }
+    }
+    println!(
+        "\n{} validators in {:.1} ms (parallelism {})",
+        report.tests.len(),
+        report.elapsed_ms,
+        report.parallelism
+    );
+}
+
+fn print_suite_json(report: &SuiteReport) {
+    match serde_json::to_string_pretty(report) {
+        Ok(json) => println!("{json}"),
+        Err(e) => error!("Failed to serialize report: {e}"),
+    }
+}
+
 fn print_result_summary(result: &ValidationResult) {
     let status = if result.all_passed { "PASS" } else { "FAIL" };
     println!(
@@ -122,6 +190,22 @@ async fn main() -> anyhow::Result<()> {
         timeout_ms: args.timeout,
     };
 
+    if let Some(parallel) = args.parallel {
+        info!("Running full suite with parallelism {parallel}");
+        let report = full_suite(&config).run(parallel).await?;
+
+        match args.format {
+            OutputFormat::Text => print_suite_text(&report),
+            OutputFormat::Json => print_suite_json(&report),
+            OutputFormat::Summary => print_result_summary(&report.combined()),
+        }
+
+        if report.all_passed {
+            return Ok(());
+        }
+        std::process::exit(1)
+    }
+
     let harness = TestHarness::spawn_with_config(config).await?;
 
     // Run tests
@@ -129,16 +213,7 @@ async fn main() -> anyhow::Result<()> {
         info!("Running specific test: {test_name}");
         match test_name.as_str() {
             "smoke" => harness.smoke_test().await?,
-            "session" => {
-                // Just session lifecycle
-                let session = harness.create_session("test-session").await?;
-                harness
-                    .select_devices(&session, ion_test_substrate::DeviceType::all())
-                    .await?;
-                harness.start_session(&session).await?;
-                harness.close_session(&session).await?;
-                harness.validate().await
-            },
+            "session" => session_test(&harness).await?,
             _ => {
                 error!("Unknown test: {test_name}");
                 std::process::exit(1);
@@ -0,0 +1,297 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Clipboard round-trip probe backed by an in-memory clipboard.
+//!
+//! Implements the `clipboard-roundtrip` validation capability for the
+//! substrate: portals whose desktop clipboard is a [`MemoryClipboard`] are
+//! served on a private bus, each payload is set with `SetSelection` over
+//! D-Bus, exactly as a client would, and read back from the clipboard the
+//! portal handed it to. Payloads past
+//! [`PortalClipboardProbe::INLINE_LIMIT`] go through `SetSelectionFd`, as
+//! clients pass large selections.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use ion_core::backend::MockBackend;
+use ion_core::event::StampedEvent;
+use ion_core::mode::RemoteDesktopMode;
+use ion_core::session::SessionId;
+use ion_portal::clipboard::{Clipboard, ClipboardRecord, MemoryClipboard};
+use ion_portal::portal::RemoteDesktopPortal;
+use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
+use ion_validation::providers::{ClipboardPayload, ClipboardRoundTripProbe};
+use ion_validation::ValidationError;
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+use zbus::zvariant::{Fd, ObjectPath, OwnedValue, Value};
+
+use crate::mock_bus::MockBus;
+
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const PORTAL_INTERFACE: &str = "org.freedesktop.impl.portal.RemoteDesktop";
+const SESSION: &str = "/org/freedesktop/portal/desktop/session/clipboard/probe";
+
+/// Clipboard probe that round-trips payloads through portals on a
+/// private bus.
+pub struct PortalClipboardProbe {
+    _bus: MockBus,
+    /// Connection serving the portal whose session may write the clipboard
+    full: zbus::Connection,
+    /// Connection serving the portal whose session must be refused
+    view_only: zbus::Connection,
+    /// Connection acting as the portal client
+    client: zbus::Connection,
+    clipboard: Arc<MemoryClipboard>,
+    /// Input channels of both portals, kept open for their lifetime
+    _input: [mpsc::Receiver<(SessionId, StampedEvent)>; 2],
+}
+
+impl PortalClipboardProbe {
+    /// Largest payload passed inline; anything bigger goes over an fd.
+    pub const INLINE_LIMIT: usize = 64 * 1024;
+
+    /// Serve a `Full` and a `ViewOnly` portal sharing one clipboard, and
+    /// start a session on each.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bus, the portals or either session cannot
+    /// be started.
+    pub async fn start() -> anyhow::Result<Self> {
+        let bus = MockBus::spawn().await?;
+        let client = bus.connect().await?;
+        let clipboard = Arc::new(MemoryClipboard::default());
+        let (full, full_rx) = Self::serve(&bus, RemoteDesktopMode::Full, &clipboard).await?;
+        let (view_only, view_rx) =
+            Self::serve(&bus, RemoteDesktopMode::ViewOnly, &clipboard).await?;
+
+        let probe = Self {
+            _bus: bus,
+            full,
+            view_only,
+            client,
+            clipboard,
+            _input: [full_rx, view_rx],
+        };
+        probe.start_session(&probe.full).await?;
+        probe.start_session(&probe.view_only).await?;
+
+        info!("Clipboard probe portals ready");
+        Ok(probe)
+    }
+
+    async fn serve(
+        bus: &MockBus,
+        mode: RemoteDesktopMode,
+        clipboard: &Arc<MemoryClipboard>,
+    ) -> anyhow::Result<(zbus::Connection, mpsc::Receiver<(SessionId, StampedEvent)>)> {
+        let (manager, rx) = SessionManager::new(SessionManagerConfig::default());
+        let portal = RemoteDesktopPortal::with_mode(manager, mode, Arc::new(MockBackend::new()))
+            .with_clipboard(Arc::clone(clipboard) as Arc<dyn Clipboard>);
+        Ok((bus.serve_on_runtime(PORTAL_PATH, portal).await?, rx))
+    }
+
+    /// Create and start the probe session on `portal`.
+    async fn start_session(&self, portal: &zbus::Connection) -> anyhow::Result<()> {
+        let handle = ObjectPath::try_from(format!("{PORTAL_PATH}/request/clipboard"))?;
+        let session = ObjectPath::try_from(SESSION)?;
+        let app_id = "org.ionchannel.ClipboardProbe";
+        let options: HashMap<&str, Value<'_>> = HashMap::new();
+
+        self.request(
+            portal,
+            "CreateSession",
+            &(&handle, &session, app_id, &options),
+        )
+        .await?;
+        self.request(
+            portal,
+            "SelectDevices",
+            &(&handle, &session, app_id, &options),
+        )
+        .await?;
+        let response = self
+            .request(portal, "Start", &(&handle, &session, app_id, "", &options))
+            .await?;
+        anyhow::ensure!(response == 0, "clipboard probe session refused: {response}");
+        Ok(())
+    }
+
+    /// Call a portal method that returns a `(response, results)` pair.
+    async fn request(
+        &self,
+        portal: &zbus::Connection,
+        method: &str,
+        body: &(impl serde::Serialize + zbus::zvariant::DynamicType),
+    ) -> zbus::Result<u32> {
+        let reply = self.call(portal, method, body).await?;
+        let (response, _results): (u32, HashMap<String, OwnedValue>) =
+            reply.body().deserialize()?;
+        Ok(response)
+    }
+
+    async fn call(
+        &self,
+        portal: &zbus::Connection,
+        method: &str,
+        body: &(impl serde::Serialize + zbus::zvariant::DynamicType),
+    ) -> zbus::Result<zbus::Message> {
+        self.client
+            .call_method(
+                portal.unique_name(),
+                PORTAL_PATH,
+                Some(PORTAL_INTERFACE),
+                method,
+                body,
+            )
+            .await
+    }
+
+    /// Set `payload` on `portal`, inline or over a socket depending on
+    /// size.
+    async fn transfer(
+        &self,
+        portal: &zbus::Connection,
+        payload: &ClipboardPayload,
+    ) -> zbus::Result<()> {
+        let session = ObjectPath::try_from(SESSION)?;
+        if payload.content.len() <= Self::INLINE_LIMIT {
+            self.call(
+                portal,
+                "SetSelection",
+                &(&session, &payload.mime_type, &payload.content),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let (reader, mut writer) = UnixStream::pair().map_err(zbus::Error::from)?;
+        let content = payload.content.clone();
+        // Write from another thread: the portal reads while we write
+        let write = std::thread::spawn(move || writer.write_all(&content));
+        let transferred = self
+            .call(
+                portal,
+                "SetSelectionFd",
+                &(&session, &payload.mime_type, Fd::from(&reader)),
+            )
+            .await;
+        drop(reader);
+        let written = write
+            .join()
+            .map_err(|_| zbus::Error::Failure("selection writer panicked".into()))?;
+        transferred?;
+        written.map_err(zbus::Error::from)
+    }
+
+    /// Clipboard decisions of the `Full` portal, oldest first.
+    pub async fn clipboard_history(&self) -> Vec<ClipboardRecord> {
+        Self::history(&self.full).await
+    }
+
+    /// Clipboard decisions of the `ViewOnly` portal, oldest first.
+    pub async fn view_only_clipboard_history(&self) -> Vec<ClipboardRecord> {
+        Self::history(&self.view_only).await
+    }
+
+    async fn history(portal: &zbus::Connection) -> Vec<ClipboardRecord> {
+        match portal
+            .object_server()
+            .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+            .await
+        {
+            Ok(iface) => iface.get().await.clipboard_history(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl ClipboardRoundTripProbe for PortalClipboardProbe {
+    async fn round_trip(&self, payload: &ClipboardPayload) -> ion_validation::Result<Vec<u8>> {
+        // Whatever is read back must have come from this transfer
+        self.clipboard.set_selection(None);
+        self.transfer(&self.full, payload).await.map_err(|e| {
+            ValidationError::portal(format!("set {} selection", payload.mime_type), e)
+        })?;
+
+        let Some(selection) = self.clipboard.selection() else {
+            return Ok(Vec::new());
+        };
+        debug!(
+            mime_type = %selection.mime_type,
+            len = selection.content.len(),
+            "Clipboard selection read back"
+        );
+        if selection.mime_type != payload.mime_type {
+            return Ok(Vec::new());
+        }
+        Ok(selection.content)
+    }
+
+    async fn view_only_write_refused(&self) -> ion_validation::Result<Option<bool>> {
+        let payload = ClipboardPayload::new("text/plain", b"view-only".to_vec());
+        self.clipboard.set_selection(None);
+        let refused = self.transfer(&self.view_only, &payload).await.is_err();
+        Ok(Some(refused && self.clipboard.selection().is_none()))
+    }
+
+    async fn close_sessions(&self) -> ion_validation::Result<()> {
+        for portal in [&self.full, &self.view_only] {
+            let iface = portal
+                .object_server()
+                .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+                .await
+                .map_err(|e| ValidationError::portal("clipboard probe portal", e))?;
+            let closed = iface
+                .get()
+                .await
+                .session_manager()
+                .close_session(&SessionId::new(SESSION))
+                .await;
+            debug!(?closed, "Clipboard probe session closed");
+        }
+        Ok(())
+    }
+
+    async fn is_available(&self) -> bool {
+        self.client.unique_name().is_some()
+    }
+
+    fn name(&self) -> &'static str {
+        "memory-clipboard"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ion_validation::providers::clipboard::{verify, LARGE_PAYLOAD_BYTES};
+
+    #[tokio::test]
+    async fn round_trips_text_image_and_large_payload() {
+        let probe = PortalClipboardProbe::start().await.unwrap();
+        let payloads = ClipboardPayload::standard_set();
+        assert!(payloads
+            .iter()
+            .any(|p| p.content.len() > PortalClipboardProbe::INLINE_LIMIT));
+
+        let report = verify(&probe, &payloads).await.unwrap();
+
+        assert!(report.passed(), "{report:?}");
+        assert!(report.max_size_ok());
+        assert_eq!(report.mimes_tested().len(), payloads.len());
+        assert!(report.mimes_tested().iter().any(|m| m == "image/png"));
+        assert_eq!(report.view_only_refused, Some(true));
+        assert_eq!(
+            probe.clipboard_history().await.last().map(|r| r.len),
+            Some(LARGE_PAYLOAD_BYTES)
+        );
+        assert!(probe.view_only_clipboard_history().await.is_empty());
+    }
+}
@@ -0,0 +1,358 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Consent enforcement probe backed by a scripted consent provider.
+//!
+//! Implements the `consent-enforcement` validation capability for the
+//! substrate: a portal with a [`ScriptedConsentProvider`] is served on a
+//! private bus, and each check drives a session through `CreateSession`,
+//! `SelectDevices`, `Start` and `NotifyPointerMotion` over D-Bus, exactly
+//! as a client would.
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use ion_core::backend::MockBackend;
+use ion_core::mode::RemoteDesktopMode;
+use ion_core::session::SessionId;
+use ion_portal::consent::{ConsentProvider, ConsentRequest, ConsentResult};
+use ion_portal::portal::RemoteDesktopPortal;
+use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
+use ion_validation::providers::{ConsentDecision, ConsentEnforcementProbe, ConsentPathOutcome};
+use ion_validation::ValidationError;
+use tracing::{debug, info};
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+use crate::mock_bus::MockBus;
+use crate::mock_compositor::MockCompositor;
+
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const PORTAL_INTERFACE: &str = "org.freedesktop.impl.portal.RemoteDesktop";
+
+/// Consent provider that answers from a script instead of a dialog.
+///
+/// Each request takes the next queued answer. Once the script runs out
+/// every request is denied, so a test that under-scripts fails closed.
+#[derive(Debug, Default)]
+pub struct ScriptedConsentProvider {
+    script: Mutex<VecDeque<ConsentResult>>,
+    requests: AtomicUsize,
+}
+
+impl ScriptedConsentProvider {
+    /// Create a provider that answers with `script`, in order.
+    pub fn new(script: impl IntoIterator<Item = ConsentResult>) -> Self {
+        Self {
+            script: Mutex::new(script.into_iter().collect()),
+            requests: AtomicUsize::new(0),
+        }
+    }
+
+    /// Queue another answer.
+    pub fn push(&self, result: ConsentResult) {
+        self.lock_script().push_back(result);
+    }
+
+    /// Number of consent requests received so far.
+    #[must_use]
+    pub fn request_count(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    fn lock_script(&self) -> std::sync::MutexGuard<'_, VecDeque<ConsentResult>> {
+        self.script
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl ConsentProvider for ScriptedConsentProvider {
+    fn request_consent(
+        &self,
+        request: ConsentRequest,
+        _timeout: Duration,
+    ) -> Pin<Box<dyn Future<Output = ConsentResult> + Send + '_>> {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        let result = self
+            .lock_script()
+            .pop_front()
+            .unwrap_or(ConsentResult::Denied);
+        debug!(session = %request.session_id, %result, "Scripted consent answer");
+        Box::pin(async move { result })
+    }
+}
+
+/// Consent probe that exercises a portal served on a private bus.
+pub struct PortalConsentProbe {
+    _bus: MockBus,
+    /// Connection the portal is served on
+    server: zbus::Connection,
+    /// Connection acting as the portal client
+    client: zbus::Connection,
+    consent: Arc<ScriptedConsentProvider>,
+    compositor: MockCompositor,
+    next_session: AtomicU32,
+    delivery_timeout: Duration,
+}
+
+impl PortalConsentProbe {
+    /// Default time to wait for injected input to reach the compositor.
+    pub const DEFAULT_DELIVERY_TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Spawn a private bus and serve a portal with scripted consent on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bus or the portal cannot be started.
+    pub async fn spawn() -> anyhow::Result<Self> {
+        let bus = MockBus::spawn().await?;
+        let client = bus.connect().await?;
+
+        let (compositor, compositor_rx) = MockCompositor::new();
+        tokio::spawn(compositor.clone().run(compositor_rx));
+
+        let (session_manager, portal_rx) = SessionManager::new(SessionManagerConfig::default());
+        let compositor_tx = compositor.event_sender();
+        tokio::spawn(async move {
+            let mut rx = portal_rx;
+            while let Some(event) = rx.recv().await {
+                let _ = compositor_tx.send(event).await;
+            }
+        });
+
+        let consent = Arc::new(ScriptedConsentProvider::default());
+        let portal = RemoteDesktopPortal::with_consent_provider(
+            session_manager,
+            RemoteDesktopMode::InputOnly,
+            Arc::clone(&consent) as Arc<dyn ConsentProvider>,
+            Arc::new(MockBackend::new()),
+        );
+        let server = bus.serve_on_runtime(PORTAL_PATH, portal).await?;
+
+        info!("Consent probe portal ready");
+
+        Ok(Self {
+            _bus: bus,
+            server,
+            client,
+            consent,
+            compositor,
+            next_session: AtomicU32::new(0),
+            delivery_timeout: Self::DEFAULT_DELIVERY_TIMEOUT,
+        })
+    }
+
+    /// Set how long to wait for injected input to be delivered.
+    #[must_use]
+    pub const fn with_delivery_timeout(mut self, timeout: Duration) -> Self {
+        self.delivery_timeout = timeout;
+        self
+    }
+
+    /// Call a portal method that returns a `(response, results)` pair.
+    async fn request(
+        &self,
+        method: &str,
+        body: &(impl serde::Serialize + zbus::zvariant::DynamicType),
+    ) -> zbus::Result<u32> {
+        let reply = self.call(method, body).await?;
+        let (response, _results): (u32, HashMap<String, OwnedValue>) =
+            reply.body().deserialize()?;
+        Ok(response)
+    }
+
+    async fn call(
+        &self,
+        method: &str,
+        body: &(impl serde::Serialize + zbus::zvariant::DynamicType),
+    ) -> zbus::Result<zbus::Message> {
+        self.client
+            .call_method(
+                self.server.unique_name(),
+                PORTAL_PATH,
+                Some(PORTAL_INTERFACE),
+                method,
+                body,
+            )
+            .await
+    }
+
+    /// Run one session through the portal, returning what it could do.
+    async fn run_session(&self) -> zbus::Result<ConsentPathOutcome> {
+        let n = self.next_session.fetch_add(1, Ordering::SeqCst);
+        let handle = ObjectPath::try_from(format!("{PORTAL_PATH}/request/consent/{n}"))?;
+        let session = ObjectPath::try_from(format!("{PORTAL_PATH}/session/consent/{n}"))?;
+        let app_id = "org.ionchannel.ConsentProbe";
+        let options: HashMap<&str, Value<'_>> = HashMap::new();
+        let prompts_before = self.consent.request_count();
+
+        self.request("CreateSession", &(&handle, &session, app_id, &options))
+            .await?;
+        self.request("SelectDevices", &(&handle, &session, app_id, &options))
+            .await?;
+        let started = self
+            .request("Start", &(&handle, &session, app_id, "", &options))
+            .await?
+            == 0;
+
+        let injected = self
+            .call(
+                "NotifyPointerMotion",
+                &(&session, &options, 1.0_f64, 1.0_f64),
+            )
+            .await
+            .is_ok();
+        let input_delivered = injected && self.wait_for_delivery(&session).await;
+
+        Ok(ConsentPathOutcome {
+            prompted: self.consent.request_count() > prompts_before,
+            started,
+            input_delivered,
+        })
+    }
+
+    async fn wait_for_delivery(&self, session: &ObjectPath<'_>) -> bool {
+        let session_id = SessionId::new(session.as_str());
+        tokio::time::timeout(self.delivery_timeout, async {
+            while self
+                .compositor
+                .events_for_session(&session_id)
+                .await
+                .is_empty()
+            {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+}
+
+#[async_trait]
+impl ConsentEnforcementProbe for PortalConsentProbe {
+    async fn exercise(
+        &self,
+        decision: ConsentDecision,
+    ) -> ion_validation::Result<Option<ConsentPathOutcome>> {
+        self.consent.push(match decision {
+            ConsentDecision::Grant => ConsentResult::Granted,
+            ConsentDecision::Deny => ConsentResult::Denied,
+            ConsentDecision::Unattended => ConsentResult::Timeout,
+        });
+
+        let outcome = self
+            .run_session()
+            .await
+            .map_err(|e| ValidationError::portal(format!("{decision:?} consent path"), e))?;
+        debug!(?decision, ?outcome, "Consent path exercised");
+        Ok(Some(outcome))
+    }
+
+    async fn close_sessions(&self) -> ion_validation::Result<()> {
+        let portal = self
+            .server
+            .object_server()
+            .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+            .await
+            .map_err(|e| ValidationError::portal("consent probe portal", e))?;
+        let client = self.client.unique_name().map_or("", |name| name.as_str());
+        let closed = portal
+            .get()
+            .await
+            .session_manager()
+            .close_sessions_owned_by(client)
+            .await;
+        debug!(closed = closed.len(), "Consent probe sessions closed");
+        Ok(())
+    }
+
+    async fn is_available(&self) -> bool {
+        self.client.unique_name().is_some()
+    }
+
+    fn name(&self) -> &'static str {
+        "scripted-consent"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ion_core::device::DeviceType;
+    use ion_validation::providers::consent::verify;
+
+    fn request() -> ConsentRequest {
+        ConsentRequest {
+            session_id: SessionId::new("/test/consent"),
+            app_id: "app".into(),
+            verified_app_id: None,
+            device_types: DeviceType::POINTER,
+            include_screen_capture: false,
+            parent_window: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scripted_provider_answers_in_order_then_denies() {
+        let provider = ScriptedConsentProvider::new([ConsentResult::Granted]);
+        provider.push(ConsentResult::Timeout);
+
+        let timeout = Duration::from_secs(1);
+        assert_eq!(
+            provider.request_consent(request(), timeout).await,
+            ConsentResult::Granted
+        );
+        assert_eq!(
+            provider.request_consent(request(), timeout).await,
+            ConsentResult::Timeout
+        );
+        assert_eq!(
+            provider.request_consent(request(), timeout).await,
+            ConsentResult::Denied
+        );
+        assert_eq!(provider.request_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_portal_enforces_consent() {
+        let probe = PortalConsentProbe::spawn().await.unwrap();
+        assert!(probe.is_available().await);
+
+        let report = verify(&probe).await.unwrap();
+
+        let granted = report.granted.unwrap();
+        assert!(granted.started && granted.input_delivered);
+
+        let denied = report.denied.unwrap();
+        assert!(denied.prompted);
+        assert!(!denied.started);
+        assert!(!denied.input_delivered);
+
+        assert_eq!(report.unattended_path(), Some(true));
+        assert!(report.passed());
+    }
+
+    #[tokio::test]
+    async fn test_close_sessions_closes_the_granted_session() {
+        let probe = PortalConsentProbe::spawn().await.unwrap();
+        let granted = probe.exercise(ConsentDecision::Grant).await.unwrap();
+        assert!(granted.unwrap().started);
+
+        let portal = probe
+            .server
+            .object_server()
+            .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+            .await
+            .unwrap();
+        let manager = portal.get().await.session_manager().clone();
+        assert_eq!(manager.session_ids().await.len(), 1);
+
+        probe.close_sessions().await.unwrap();
+        assert!(manager.session_ids().await.is_empty());
+    }
+}
@@ -0,0 +1,309 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Keymap ground truth for keyboard validation.
+//!
+//! The portal accepts keyboard input both as hardware keycodes and as
+//! keysyms. [`KeymapHarness`] compiles a real XKB keymap so tests can check
+//! that the two resolve to the same logical key, and that dead-key
+//! sequences compose as a user would expect.
+//!
+//! Keycodes are evdev codes, as used by `NotifyKeyboardKeycode`; the
+//! harness applies the XKB offset internally. [`SeatKeyboard`] tracks
+//! modifier state across a stream of events the way a compositor seat
+//! does, so explicit modifier masks can be checked against key presses.
+
+use anyhow::{anyhow, Result};
+use ion_core::event::{InputEvent, KeyState};
+use xkbcommon::xkb;
+
+/// Compiled XKB keymap for a single layout.
+pub struct KeymapHarness {
+    context: xkb::Context,
+    keymap: xkb::Keymap,
+}
+
+impl KeymapHarness {
+    /// Offset between evdev keycodes and XKB keycodes.
+    pub const EVDEV_OFFSET: u32 = 8;
+
+    /// Locale whose compose table is used for dead keys.
+    const COMPOSE_LOCALE: &'static str = "en_US.UTF-8";
+
+    /// Compile the keymap for `layout` (e.g. `us`, `de`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layout cannot be compiled.
+    pub fn new(layout: &str) -> Result<Self> {
+        Self::with_variant(layout, "")
+    }
+
+    /// Compile the keymap for `layout` with a variant (e.g. `us`, `intl`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layout cannot be compiled.
+    pub fn with_variant(layout: &str, variant: &str) -> Result<Self> {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let keymap = xkb::Keymap::new_from_names(
+            &context,
+            "",
+            "",
+            layout,
+            variant,
+            None,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )
+        .ok_or_else(|| anyhow!("failed to compile XKB keymap {layout}({variant})"))?;
+
+        Ok(Self { context, keymap })
+    }
+
+    /// Compile the `us` layout.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layout cannot be compiled.
+    pub fn us() -> Result<Self> {
+        Self::new("us")
+    }
+
+    /// Unshifted keysym produced by an evdev keycode.
+    #[must_use]
+    pub fn keysym_for_keycode(&self, keycode: i32) -> Option<i32> {
+        let key = Self::xkb_keycode(keycode)?;
+        self.keymap
+            .key_get_syms_by_level(key, 0, 0)
+            .first()
+            .and_then(|sym| i32::try_from(sym.raw()).ok())
+    }
+
+    /// Evdev keycodes that produce `keysym` at any shift level.
+    #[must_use]
+    pub fn keycodes_for_keysym(&self, keysym: i32) -> Vec<i32> {
+        let Ok(wanted) = u32::try_from(keysym) else {
+            return Vec::new();
+        };
+
+        let mut keycodes = Vec::new();
+        self.keymap.key_for_each(|keymap, key| {
+            let levels = keymap.num_levels_for_key(key, 0);
+            let produces = (0..levels).any(|level| {
+                keymap
+                    .key_get_syms_by_level(key, 0, level)
+                    .iter()
+                    .any(|sym| sym.raw() == wanted)
+            });
+            if produces {
+                if let Some(evdev) = key.raw().checked_sub(Self::EVDEV_OFFSET) {
+                    keycodes.extend(i32::try_from(evdev).ok());
+                }
+            }
+        });
+        keycodes
+    }
+
+    /// Keysym that a keyboard event resolves to.
+    ///
+    /// Keycode events are resolved through the keymap; keysym events are
+    /// taken as-is. Other events resolve to `None`.
+    #[must_use]
+    pub fn resolve(&self, event: &InputEvent) -> Option<i32> {
+        match event {
+            InputEvent::KeyboardKeycode { keycode, .. } => self.keysym_for_keycode(*keycode),
+            InputEvent::KeyboardKeysym { keysym, .. } => Some(*keysym),
+            _ => None,
+        }
+    }
+
+    /// Mask bit of the named modifier (e.g. [`xkb::MOD_NAME_CTRL`]), as
+    /// used in `KeyboardModifiers` events for this keymap.
+    #[must_use]
+    pub fn modifier_mask(&self, name: &str) -> Option<u32> {
+        let index = self.keymap.mod_get_index(name);
+        (index != xkb::MOD_INVALID).then(|| 1 << index)
+    }
+
+    /// Keyboard state for a seat using this keymap.
+    #[must_use]
+    pub fn seat(&self) -> SeatKeyboard {
+        SeatKeyboard {
+            state: xkb::State::new(&self.keymap),
+        }
+    }
+
+    /// Text produced by pressing the given evdev keycodes in order.
+    ///
+    /// Dead keys are composed with the following key using the
+    /// `en_US.UTF-8` compose table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compose table is unavailable.
+    pub fn type_keycodes(&self, keycodes: &[i32]) -> Result<String> {
+        let table = xkb::compose::Table::new_from_locale(
+            &self.context,
+            Self::COMPOSE_LOCALE.as_ref(),
+            xkb::compose::COMPILE_NO_FLAGS,
+        )
+        .map_err(|()| anyhow!("no compose table for {}", Self::COMPOSE_LOCALE))?;
+        let mut compose = xkb::compose::State::new(&table, xkb::compose::STATE_NO_FLAGS);
+
+        let mut text = String::new();
+        for &keycode in keycodes {
+            let key =
+                Self::xkb_keycode(keycode).ok_or_else(|| anyhow!("invalid keycode {keycode}"))?;
+            let Some(&sym) = self.keymap.key_get_syms_by_level(key, 0, 0).first() else {
+                continue;
+            };
+
+            compose.feed(sym);
+            match compose.status() {
+                xkb::compose::Status::Composing => {},
+                xkb::compose::Status::Composed => {
+                    text.extend(compose.utf8());
+                    compose.reset();
+                },
+                xkb::compose::Status::Cancelled => compose.reset(),
+                xkb::compose::Status::Nothing => text.push_str(&xkb::keysym_to_utf8(sym)),
+            }
+        }
+
+        // keysym_to_utf8 includes the C string terminator
+        Ok(text.replace('\0', ""))
+    }
+
+    fn xkb_keycode(keycode: i32) -> Option<xkb::Keycode> {
+        let evdev = u32::try_from(keycode).ok()?;
+        Some(xkb::Keycode::new(evdev.checked_add(Self::EVDEV_OFFSET)?))
+    }
+}
+
+/// A key press as the focused client would see it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPress {
+    /// Keysym of the key under the active modifiers
+    pub keysym: i32,
+    /// Effective modifier mask when the key went down
+    pub modifiers: u32,
+}
+
+/// Keyboard state of a seat, driven by input events.
+pub struct SeatKeyboard {
+    state: xkb::State,
+}
+
+impl SeatKeyboard {
+    /// Apply a keyboard event, returning the press it produced, if any.
+    ///
+    /// `KeyboardModifiers` replaces the modifier state outright; keycode
+    /// events update it as the keys themselves would.
+    pub fn apply(&mut self, event: &InputEvent) -> Option<KeyPress> {
+        match *event {
+            InputEvent::KeyboardModifiers {
+                depressed,
+                latched,
+                locked,
+                group,
+            } => {
+                self.state
+                    .update_mask(depressed, latched, locked, 0, 0, group);
+                None
+            },
+            InputEvent::KeyboardKeycode { keycode, state } => {
+                let key = KeymapHarness::xkb_keycode(keycode)?;
+                if state == KeyState::Released {
+                    self.state.update_key(key, xkb::KeyDirection::Up);
+                    return None;
+                }
+                let press = KeyPress {
+                    keysym: i32::try_from(self.state.key_get_one_sym(key).raw()).ok()?,
+                    modifiers: self.state.serialize_mods(xkb::STATE_MODS_EFFECTIVE),
+                };
+                self.state.update_key(key, xkb::KeyDirection::Down);
+                Some(press)
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether the named modifier is currently in effect.
+    #[must_use]
+    pub fn is_active(&self, name: &str) -> bool {
+        self.state
+            .mod_name_is_active(name, xkb::STATE_MODS_EFFECTIVE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evdev keycode for the A key.
+    const KEY_A: i32 = 30;
+
+    #[test]
+    fn test_keycode_and_keysym_resolve_consistently() {
+        let keymap = KeymapHarness::us().unwrap();
+
+        let by_keycode = InputEvent::KeyboardKeycode {
+            keycode: KEY_A,
+            state: KeyState::Pressed,
+        };
+        let by_keysym = InputEvent::KeyboardKeysym {
+            keysym: i32::from(b'a'),
+            state: KeyState::Pressed,
+        };
+
+        assert_eq!(keymap.resolve(&by_keycode), Some(i32::from(b'a')));
+        assert_eq!(keymap.resolve(&by_keycode), keymap.resolve(&by_keysym));
+        assert_eq!(keymap.keycodes_for_keysym(i32::from(b'a')), vec![KEY_A]);
+        // Shifted level is found too
+        assert_eq!(keymap.keycodes_for_keysym(i32::from(b'A')), vec![KEY_A]);
+    }
+
+    #[test]
+    fn test_layout_changes_mapping() {
+        // KEY_Y (21) is 'z' on a German layout
+        let de = KeymapHarness::new("de").unwrap();
+        assert_eq!(de.keysym_for_keycode(21), Some(i32::from(b'z')));
+    }
+
+    #[test]
+    fn test_dead_key_composes() {
+        // On us(intl) the apostrophe key (40) is dead_acute
+        let keymap = KeymapHarness::with_variant("us", "intl").unwrap();
+        let text = keymap.type_keycodes(&[40, 18, KEY_A]).unwrap();
+        assert_eq!(text, "éa");
+    }
+
+    #[test]
+    fn test_modifier_mask_applies_to_next_key() {
+        let keymap = KeymapHarness::us().unwrap();
+        let ctrl = keymap.modifier_mask(xkb::MOD_NAME_CTRL).unwrap();
+        let mut seat = keymap.seat();
+
+        seat.apply(&InputEvent::keyboard_modifiers(ctrl, 0, 0, 0));
+        assert!(seat.is_active(xkb::MOD_NAME_CTRL));
+        let press = seat
+            .apply(&InputEvent::key(KEY_A, KeyState::Pressed))
+            .unwrap();
+        assert_eq!(press.keysym, i32::from(b'a'));
+        assert_ne!(press.modifiers & ctrl, 0, "Ctrl not applied to the key");
+        seat.apply(&InputEvent::key(KEY_A, KeyState::Released));
+
+        // Clearing the mask releases Ctrl
+        seat.apply(&InputEvent::keyboard_modifiers(0, 0, 0, 0));
+        assert!(!seat.is_active(xkb::MOD_NAME_CTRL));
+        let press = seat
+            .apply(&InputEvent::key(KEY_A, KeyState::Pressed))
+            .unwrap();
+        assert_eq!(press.modifiers & ctrl, 0);
+    }
+
+    #[test]
+    fn test_unknown_layout_fails() {
+        assert!(KeymapHarness::new("no-such-layout").is_err());
+    }
+}
@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Input latency probe backed by the mock compositor.
+//!
+//! Implements the `input-latency` validation capability for the substrate:
+//! each sample injects a tagged pointer motion into the compositor's event
+//! forwarding and measures how long it takes to be captured.
+
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use ion_core::event::{InputEvent, StampedEvent};
+use ion_core::session::SessionId;
+use ion_validation::providers::InputLatencyProbe;
+use tokio::sync::mpsc;
+
+use crate::mock_compositor::MockCompositor;
+
+/// Latency probe that measures delivery to a [`MockCompositor`].
+///
+/// Samples that cannot be queued (channel full) or are not captured within
+/// the timeout are reported as dropped.
+pub struct CompositorLatencyProbe {
+    compositor: MockCompositor,
+    sender: mpsc::Sender<(SessionId, StampedEvent)>,
+    session_id: SessionId,
+    timeout: Duration,
+}
+
+impl CompositorLatencyProbe {
+    /// Default time to wait for a sample before counting it as dropped.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Create a probe that injects directly into the compositor.
+    #[must_use]
+    pub fn new(compositor: MockCompositor, session_id: SessionId) -> Self {
+        Self {
+            sender: compositor.event_sender(),
+            compositor,
+            session_id,
+            timeout: Self::DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Inject through a different sender that eventually feeds the compositor.
+    #[must_use]
+    pub fn with_sender(mut self, sender: mpsc::Sender<(SessionId, StampedEvent)>) -> Self {
+        self.sender = sender;
+        self
+    }
+
+    /// Set how long to wait for each sample.
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Wait until `marker` shows up for our session, returning its capture time.
+    async fn wait_for_marker(&self, marker: &InputEvent, mut seen: usize) -> Instant {
+        loop {
+            self.compositor.wait_for_events(seen + 1).await;
+            let events = self.compositor.captured_events().await;
+            if let Some(captured) = events
+                .iter()
+                .skip(seen)
+                .find(|e| e.session_id == self.session_id && &e.event == marker)
+            {
+                return captured.timestamp;
+            }
+            seen = events.len();
+        }
+    }
+}
+
+#[async_trait]
+impl InputLatencyProbe for CompositorLatencyProbe {
+    async fn sample(&self, sequence: u32) -> ion_validation::Result<Option<Duration>> {
+        let marker = InputEvent::PointerMotion {
+            dx: f64::from(sequence),
+            dy: 0.0,
+        };
+        let seen = self.compositor.event_count().await;
+
+        let sent_at = Instant::now();
+        if self
+            .sender
+            .try_send((self.session_id.clone(), marker.clone().into()))
+            .is_err()
+        {
+            return Ok(None);
+        }
+
+        let captured =
+            tokio::time::timeout(self.timeout, self.wait_for_marker(&marker, seen)).await;
+        Ok(captured
+            .ok()
+            .map(|at| at.saturating_duration_since(sent_at)))
+    }
+
+    async fn is_available(&self) -> bool {
+        !self.sender.is_closed()
+    }
+
+    fn name(&self) -> &'static str {
+        "mock-compositor"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ion_validation::providers::latency::measure;
+
+    #[tokio::test]
+    async fn test_measure_through_compositor() {
+        let (compositor, rx) = MockCompositor::new();
+        tokio::spawn(compositor.clone().run(rx));
+
+        let probe = CompositorLatencyProbe::new(compositor, SessionId::new("/test/latency"));
+        let report = measure(&probe, 20).await.unwrap();
+
+        assert_eq!(report.samples, 20);
+        assert_eq!(report.dropped, 0);
+        assert!(report.p50 <= report.p99);
+    }
+
+    #[tokio::test]
+    async fn test_unacknowledged_samples_are_dropped() {
+        // Receiver is held but never drained, so nothing gets captured
+        let (compositor, _rx) = MockCompositor::new();
+
+        let probe = CompositorLatencyProbe::new(compositor, SessionId::new("/test/latency"))
+            .with_timeout(Duration::from_millis(10));
+        let report = measure(&probe, 3).await.unwrap();
+
+        assert_eq!(report.samples, 0);
+        assert_eq!(report.dropped, 3);
+    }
+}
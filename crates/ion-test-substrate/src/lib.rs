@@ -8,7 +8,14 @@
 //! This crate provides:
 //! - **Mock D-Bus session** - isolated bus for testing without system interference
 //! - **Mock compositor** - receives and validates input events
+//! - **Mock cosmic-comp** - owns cosmic-comp's bus name and emits its signals
+//! - **Keymap harness** - XKB ground truth for keycode/keysym consistency (`keymap` feature)
+//! - **Latency probe** - measures input round trips for the `input-latency` capability
+//! - **Consent probe** - checks the portal honors consent for the `consent-enforcement` capability
+//! - **Clipboard probe** - round-trips selections for the `clipboard-roundtrip` capability
+//! - **Bus registration** - checks a portal claimed its bus name and exports its interface
 //! - **Spec validator** - validates portal implementation against xdg-desktop-portal spec
+//! - **Validation suite** - runs independent validators in parallel, honoring dependencies
 //! - **CLI runner** - `ion-validate` binary for CI/headless testing
 //!
 //! ## Architecture
@@ -74,19 +81,38 @@
 //!
 //! # Output JSON for CI
 //! ion-validate --format json
+//!
+//! # Run independent validators four at a time
+//! ion-validate --parallel 4
 //! ```
 
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
+pub mod clipboard;
+pub mod consent;
 pub mod harness;
+#[cfg(feature = "keymap")]
+pub mod keymap;
+pub mod latency;
 pub mod mock_bus;
 pub mod mock_compositor;
+pub mod mock_cosmic;
+pub mod registration;
+pub mod suite;
 pub mod validator;
 
+pub use clipboard::PortalClipboardProbe;
+pub use consent::{PortalConsentProbe, ScriptedConsentProvider};
 pub use harness::{TestHarness, TestHarnessConfig};
+#[cfg(feature = "keymap")]
+pub use keymap::{KeyPress, KeymapHarness, SeatKeyboard};
+pub use latency::CompositorLatencyProbe;
 pub use mock_compositor::{CapturedEvent, MockCompositor};
-pub use validator::{ValidationResult, Validator};
+pub use mock_cosmic::MockCosmicComp;
+pub use registration::ZbusSessionBus;
+pub use suite::{SuiteReport, TestReport, ValidationSuite};
+pub use validator::{FloodMeasurement, ValidationResult, Validator};
 
 /// Re-export core types for convenience
 pub use ion_core::{
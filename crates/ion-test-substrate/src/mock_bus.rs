@@ -100,6 +100,65 @@ impl MockBus {
         debug!("Connected to mock bus");
         Ok(connection)
     }
+
+    /// Serve `iface` at `path` on a connection driven by the current Tokio
+    /// runtime.
+    ///
+    /// zbus normally runs method handlers on its own executor thread, where
+    /// `tokio::spawn` panics; interfaces served here can spawn tasks. The
+    /// interface is registered before the connection is returned, so no
+    /// early method call can slip past it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if connection fails.
+    pub async fn serve_on_runtime<I>(
+        &self,
+        path: &str,
+        iface: I,
+    ) -> anyhow::Result<zbus::Connection>
+    where
+        I: zbus::object_server::Interface,
+    {
+        let connection = zbus::connection::Builder::address(self.address.as_str())?
+            .internal_executor(false)
+            .serve_at(path, iface)?
+            .build()
+            .await?;
+        drive_on_runtime(&connection);
+
+        debug!(path, "Serving on mock bus (runtime executor)");
+        Ok(connection)
+    }
+
+    /// Create a connection driven by the current Tokio runtime, for code
+    /// that registers its own interfaces, such as the portal service.
+    ///
+    /// See [`Self::serve_on_runtime`] for why the runtime matters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if connection fails.
+    pub async fn connect_on_runtime(&self) -> anyhow::Result<zbus::Connection> {
+        let connection = zbus::connection::Builder::address(self.address.as_str())?
+            .internal_executor(false)
+            .build()
+            .await?;
+        drive_on_runtime(&connection);
+
+        debug!("Connected to mock bus (runtime executor)");
+        Ok(connection)
+    }
+}
+
+/// Runs `connection`'s executor on the current Tokio runtime.
+fn drive_on_runtime(connection: &zbus::Connection) {
+    let ticker = connection.clone();
+    tokio::spawn(async move {
+        loop {
+            ticker.executor().tick().await;
+        }
+    });
 }
 
 impl Drop for MockBus {
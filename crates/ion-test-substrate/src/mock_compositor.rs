@@ -9,7 +9,7 @@
 use std::sync::Arc;
 use std::time::Instant;
 
-use ion_core::event::InputEvent;
+use ion_core::event::{InputEvent, StampedEvent};
 use ion_core::session::SessionId;
 use tokio::sync::{mpsc, watch, RwLock};
 use tracing::{debug, info};
@@ -21,6 +21,8 @@ pub struct CapturedEvent {
     pub session_id: SessionId,
     /// The input event
     pub event: InputEvent,
+    /// When the client sent it, in microseconds since the Unix epoch
+    pub client_timestamp: Option<u64>,
     /// When the event was captured
     pub timestamp: Instant,
     /// Sequence number for ordering
@@ -34,7 +36,7 @@ pub struct CapturedEvent {
 #[derive(Clone)]
 pub struct MockCompositor {
     events: Arc<RwLock<Vec<CapturedEvent>>>,
-    event_tx: mpsc::Sender<(SessionId, InputEvent)>,
+    event_tx: mpsc::Sender<(SessionId, StampedEvent)>,
     sequence: Arc<RwLock<u64>>,
     /// Watch channel for event count - tests can wait for specific counts
     count_tx: Arc<watch::Sender<usize>>,
@@ -47,7 +49,7 @@ impl MockCompositor {
     /// Returns the compositor and a receiver channel that the
     /// portal should send events to.
     #[must_use]
-    pub fn new() -> (Self, mpsc::Receiver<(SessionId, InputEvent)>) {
+    pub fn new() -> (Self, mpsc::Receiver<(SessionId, StampedEvent)>) {
         let (event_tx, event_rx) = mpsc::channel(1024);
         let (count_tx, count_rx) = watch::channel(0);
 
@@ -64,12 +66,17 @@ impl MockCompositor {
 
     /// Get the sender for the portal to use.
     #[must_use]
-    pub fn event_sender(&self) -> mpsc::Sender<(SessionId, InputEvent)> {
+    pub fn event_sender(&self) -> mpsc::Sender<(SessionId, StampedEvent)> {
         self.event_tx.clone()
     }
 
     /// Capture an event (called by the event processing loop).
-    pub async fn capture(&self, session_id: SessionId, event: InputEvent) {
+    pub async fn capture(&self, session_id: SessionId, event: impl Into<StampedEvent>) {
+        let StampedEvent {
+            event,
+            client_timestamp,
+            ..
+        } = event.into();
         let mut seq = self.sequence.write().await;
         *seq += 1;
         let sequence = *seq;
@@ -78,6 +85,7 @@ impl MockCompositor {
         let captured = CapturedEvent {
             session_id: session_id.clone(),
             event: event.clone(),
+            client_timestamp,
             timestamp: Instant::now(),
             sequence,
         };
@@ -139,7 +147,7 @@ impl MockCompositor {
     /// Run the event capture loop.
     ///
     /// Call this in a spawned task to process events from the portal.
-    pub async fn run(self, mut event_rx: mpsc::Receiver<(SessionId, InputEvent)>) {
+    pub async fn run(self, mut event_rx: mpsc::Receiver<(SessionId, StampedEvent)>) {
         info!("Mock compositor started");
         while let Some((session_id, event)) = event_rx.recv().await {
             self.capture(session_id, event).await;
@@ -174,7 +182,7 @@ mod tests {
         let session = SessionId::new("/test/session/1");
         tx.send((
             session.clone(),
-            InputEvent::PointerMotion { dx: 10.0, dy: 20.0 },
+            InputEvent::PointerMotion { dx: 10.0, dy: 20.0 }.into(),
         ))
         .await
         .unwrap();
@@ -183,7 +191,8 @@ mod tests {
             InputEvent::PointerButton {
                 button: 1,
                 state: ButtonState::Pressed,
-            },
+            }
+            .into(),
         ))
         .await
         .unwrap();
@@ -0,0 +1,536 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Mock cosmic-comp on a [`MockBus`].
+//!
+//! Claims cosmic-comp's bus name, serves the capture methods of its
+//! proposed `RemoteDesktop` interface and emits its signals, so the COSMIC
+//! backend's D-Bus side can be exercised without the compositor.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use ion_backend_cosmic::{
+    COSMIC_COMP_PATH, COSMIC_COMP_SERVICE, COSMIC_REMOTE_DESKTOP_INTERFACE, FOCUS_CHANGED_SIGNAL,
+    SESSION_REVOKED_SIGNAL,
+};
+use ion_core::backend::{CursorMode, SurfaceGeometry};
+use ion_core::session::SessionId;
+use tracing::debug;
+use zbus::names::BusName;
+use zbus::Connection;
+
+use crate::mock_bus::MockBus;
+
+/// Outputs the mock reports: stream ID, name, width and height.
+pub const MOCK_OUTPUTS: [(u32, &str, u32, u32); 2] =
+    [(0, "eDP-1", 1920, 1080), (1, "DP-1", 2560, 1440)];
+
+/// Running captures by session and output, with their `PipeWire` node and
+/// cursor mode.
+type Captures = Arc<Mutex<HashMap<(String, u32), (u32, CursorMode)>>>;
+
+/// The capture methods of cosmic-comp's `RemoteDesktop` interface.
+#[derive(Debug)]
+struct MockRemoteDesktop {
+    captures: Captures,
+    next_node: u32,
+}
+
+#[zbus::interface(name = "com.system76.cosmic.RemoteDesktop")]
+impl MockRemoteDesktop {
+    fn list_outputs(&self) -> Vec<(u32, String, u32, u32)> {
+        MOCK_OUTPUTS
+            .iter()
+            .map(|&(stream, name, width, height)| (stream, name.to_string(), width, height))
+            .collect()
+    }
+
+    fn start_capture(
+        &mut self,
+        session: String,
+        output: u32,
+        cursor_mode: u32,
+    ) -> zbus::fdo::Result<u32> {
+        if !MOCK_OUTPUTS.iter().any(|o| o.0 == output) {
+            return Err(zbus::fdo::Error::InvalidArgs(format!("no output {output}")));
+        }
+        self.next_node += 1;
+        let node = self.next_node;
+        self.captures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert((session, output), (node, CursorMode::from(cursor_mode)));
+        Ok(node)
+    }
+
+    fn stop_capture(&self, session: String, output: u32) {
+        self.captures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&(session, output));
+    }
+}
+
+/// Stand-in for cosmic-comp owning [`COSMIC_COMP_SERVICE`].
+#[derive(Debug, Clone)]
+pub struct MockCosmicComp {
+    connection: Connection,
+    captures: Captures,
+}
+
+impl MockCosmicComp {
+    /// Connect to `bus` and claim cosmic-comp's name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if connecting or claiming the name fails.
+    pub async fn spawn(bus: &MockBus) -> anyhow::Result<Self> {
+        let connection = bus.connect().await?;
+        let captures = Captures::default();
+        let iface = MockRemoteDesktop {
+            captures: Arc::clone(&captures),
+            next_node: 40,
+        };
+        connection
+            .object_server()
+            .at(COSMIC_COMP_PATH, iface)
+            .await?;
+        connection.request_name(COSMIC_COMP_SERVICE).await?;
+        debug!("Mock cosmic-comp owns {COSMIC_COMP_SERVICE}");
+        Ok(Self {
+            connection,
+            captures,
+        })
+    }
+
+    /// `PipeWire` node of the running capture of `output`, if any.
+    pub fn capture_node(&self, session_id: &SessionId, output: u32) -> Option<u32> {
+        self.captures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(session_id.to_string(), output))
+            .map(|&(node, _)| node)
+    }
+
+    /// Cursor mode the running capture of `output` was started with, if
+    /// any.
+    pub fn capture_cursor_mode(&self, session_id: &SessionId, output: u32) -> Option<CursorMode> {
+        self.captures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(session_id.to_string(), output))
+            .map(|&(_, cursor_mode)| cursor_mode)
+    }
+
+    /// Emit `SessionRevoked` for a portal session, as cosmic-comp does when
+    /// the user revokes access from the compositor.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signal cannot be sent.
+    pub async fn revoke_session(&self, session_id: &SessionId, reason: &str) -> zbus::Result<()> {
+        self.connection
+            .emit_signal(
+                None::<BusName<'_>>,
+                COSMIC_COMP_PATH,
+                COSMIC_REMOTE_DESKTOP_INTERFACE,
+                SESSION_REVOKED_SIGNAL,
+                &(session_id.as_str(), reason),
+            )
+            .await
+    }
+
+    /// Emit `FocusChanged`, as cosmic-comp does when keyboard focus moves
+    /// to another toplevel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signal cannot be sent.
+    pub async fn focus_surface(
+        &self,
+        title: &str,
+        app_id: &str,
+        geometry: Option<SurfaceGeometry>,
+    ) -> zbus::Result<()> {
+        let g = geometry.unwrap_or_default();
+        self.connection
+            .emit_signal(
+                None::<BusName<'_>>,
+                COSMIC_COMP_PATH,
+                COSMIC_REMOTE_DESKTOP_INTERFACE,
+                FOCUS_CHANGED_SIGNAL,
+                &(title, app_id, g.x, g.y, g.width, g.height),
+            )
+            .await
+    }
+
+    /// The mock's bus connection.
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use ion_backend_cosmic::CosmicBackend;
+    use ion_core::backend::{CompositorBackend, OutputCaptureRequest};
+    use ion_core::device::DeviceType;
+    use ion_core::event::{InputEvent, KeyState};
+    use ion_core::mode::RemoteDesktopMode;
+    use ion_portal::core::{PortalCore, SelectDevicesRequest, StartSessionRequest};
+    use ion_portal::portal::RemoteDesktopPortal;
+    use ion_portal::session_manager::{SessionManager, SessionManagerConfig, REVOKED_REASON};
+    use std::collections::HashMap;
+    use zbus::zvariant::{ObjectPath, OwnedValue};
+
+    const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+    #[tokio::test]
+    async fn revoked_session_is_closed_and_reported() {
+        let bus = MockBus::spawn().await.unwrap();
+        let comp = MockCosmicComp::spawn(&bus).await.unwrap();
+        let mut backend = CosmicBackend::new();
+        backend
+            .connect_with(bus.connect().await.unwrap())
+            .await
+            .unwrap();
+        let revocations = backend.session_revocations();
+
+        let (manager, mut rx) = SessionManager::new(SessionManagerConfig::default());
+        let portal = RemoteDesktopPortal::with_backend(manager.clone(), Arc::new(backend));
+        let server = bus.serve_on_runtime(PORTAL_PATH, portal).await.unwrap();
+        let iface = server
+            .object_server()
+            .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+            .await
+            .unwrap();
+        tokio::spawn(RemoteDesktopPortal::follow_revocations(iface, revocations));
+
+        let client = bus.connect().await.unwrap();
+        let proxy = zbus::Proxy::new(
+            &client,
+            server.unique_name().unwrap().to_owned(),
+            PORTAL_PATH,
+            "org.freedesktop.impl.portal.RemoteDesktop",
+        )
+        .await
+        .unwrap();
+        let mut closed = proxy.receive_signal("SessionClosed").await.unwrap();
+
+        let id = SessionId::new(format!("{PORTAL_PATH}/session/revoked"));
+        let session = manager
+            .create_session(id.clone(), "app".into())
+            .await
+            .unwrap();
+        session.select_devices(DeviceType::KEYBOARD).await.unwrap();
+        session.start().await.unwrap();
+        session
+            .send_event(InputEvent::key(29, KeyState::Pressed))
+            .await
+            .unwrap();
+        rx.recv().await.unwrap();
+
+        comp.revoke_session(&id, "access revoked in Settings")
+            .await
+            .unwrap();
+
+        let signal = tokio::time::timeout(Duration::from_secs(5), closed.next())
+            .await
+            .expect("SessionClosed should be emitted")
+            .unwrap();
+        let body = signal.body();
+        let (handle, reason): (ObjectPath<'_>, String) = body.deserialize().unwrap();
+        assert_eq!(handle.as_str(), id.as_str());
+        assert_eq!(reason, REVOKED_REASON);
+        assert!(session.is_closed().await);
+        assert_eq!(manager.session_count().await, 0);
+
+        let (_, release) = rx.recv().await.unwrap();
+        assert_eq!(release, InputEvent::key(29, KeyState::Released));
+    }
+
+    #[tokio::test]
+    async fn focus_changes_reach_started_sessions() {
+        let bus = MockBus::spawn().await.unwrap();
+        let comp = MockCosmicComp::spawn(&bus).await.unwrap();
+        let mut backend = CosmicBackend::new();
+        backend
+            .connect_with(bus.connect().await.unwrap())
+            .await
+            .unwrap();
+        let focus_changes = backend.focus_changes();
+
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let portal = RemoteDesktopPortal::with_backend(manager.clone(), Arc::new(backend));
+        let server = bus.serve_on_runtime(PORTAL_PATH, portal).await.unwrap();
+        let iface = server
+            .object_server()
+            .interface::<_, RemoteDesktopPortal>(PORTAL_PATH)
+            .await
+            .unwrap();
+        tokio::spawn(RemoteDesktopPortal::follow_focus(iface, focus_changes));
+
+        let client = bus.connect().await.unwrap();
+        let proxy = zbus::Proxy::new(
+            &client,
+            server.unique_name().unwrap().to_owned(),
+            PORTAL_PATH,
+            "org.freedesktop.impl.portal.RemoteDesktop",
+        )
+        .await
+        .unwrap();
+        let mut focus = proxy.receive_signal("FocusChanged").await.unwrap();
+        let bystander = bus.connect().await.unwrap();
+        let bystander_proxy = zbus::Proxy::new(
+            &bystander,
+            server.unique_name().unwrap().to_owned(),
+            PORTAL_PATH,
+            "org.freedesktop.impl.portal.RemoteDesktop",
+        )
+        .await
+        .unwrap();
+        let mut overheard = bystander_proxy
+            .receive_signal("FocusChanged")
+            .await
+            .unwrap();
+
+        let id = SessionId::new(format!("{PORTAL_PATH}/session/focus"));
+        let session = manager
+            .create_session(id.clone(), "app".into())
+            .await
+            .unwrap();
+        manager
+            .set_owner(&id, client.unique_name().unwrap().as_str())
+            .await;
+        session.select_devices(DeviceType::KEYBOARD).await.unwrap();
+        session.start().await.unwrap();
+        let path = ObjectPath::try_from(id.as_str()).unwrap();
+
+        // Nothing focused yet
+        let current: HashMap<String, OwnedValue> =
+            proxy.call("CurrentFocus", &(&path,)).await.unwrap();
+        assert!(current.is_empty());
+
+        let geometry = SurfaceGeometry {
+            x: 100,
+            y: 50,
+            width: 800,
+            height: 600,
+        };
+        comp.focus_surface("notes.txt - Editor", "com.example.Editor", Some(geometry))
+            .await
+            .unwrap();
+
+        let signal = tokio::time::timeout(Duration::from_secs(5), focus.next())
+            .await
+            .expect("FocusChanged should be emitted")
+            .unwrap();
+        let body = signal.body();
+        let (handle, dict): (ObjectPath<'_>, HashMap<String, OwnedValue>) =
+            body.deserialize().unwrap();
+        assert_eq!(handle.as_str(), id.as_str());
+        assert_eq!(
+            String::try_from(dict["surface_title"].try_clone().unwrap()).unwrap(),
+            "notes.txt - Editor"
+        );
+        assert_eq!(
+            String::try_from(dict["app_id"].try_clone().unwrap()).unwrap(),
+            "com.example.Editor"
+        );
+        let bounds: (i32, i32, u32, u32) =
+            dict["geometry"].try_clone().unwrap().try_into().unwrap();
+        assert_eq!(bounds, (100, 50, 800, 600));
+
+        let current: HashMap<String, OwnedValue> =
+            proxy.call("CurrentFocus", &(&path,)).await.unwrap();
+        assert_eq!(current, dict);
+
+        // Without geometry the key is left out
+        comp.focus_surface("Terminal", "com.example.Terminal", None)
+            .await
+            .unwrap();
+        let signal = tokio::time::timeout(Duration::from_secs(5), focus.next())
+            .await
+            .expect("FocusChanged should be emitted")
+            .unwrap();
+        let body = signal.body();
+        let (_, dict): (ObjectPath<'_>, HashMap<String, OwnedValue>) = body.deserialize().unwrap();
+        assert!(!dict.contains_key("geometry"));
+
+        // Sent to the session's owner only
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), overheard.next())
+                .await
+                .is_err(),
+            "FocusChanged must not be broadcast"
+        );
+    }
+
+    #[tokio::test]
+    async fn current_focus_needs_input() {
+        let bus = MockBus::spawn().await.unwrap();
+        let comp = MockCosmicComp::spawn(&bus).await.unwrap();
+        let mut backend = CosmicBackend::new();
+        backend
+            .connect_with(bus.connect().await.unwrap())
+            .await
+            .unwrap();
+        comp.focus_surface("Terminal", "com.example.Terminal", None)
+            .await
+            .unwrap();
+
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let portal = RemoteDesktopPortal::with_backend(manager.clone(), Arc::new(backend));
+        let server = bus.serve_on_runtime(PORTAL_PATH, portal).await.unwrap();
+        let client = bus.connect().await.unwrap();
+        let proxy = zbus::Proxy::new(
+            &client,
+            server.unique_name().unwrap().to_owned(),
+            PORTAL_PATH,
+            "org.freedesktop.impl.portal.RemoteDesktop",
+        )
+        .await
+        .unwrap();
+
+        let id = SessionId::new(format!("{PORTAL_PATH}/session/view"));
+        let session = manager
+            .create_session(id.clone(), "app".into())
+            .await
+            .unwrap();
+        session.select_devices(DeviceType::KEYBOARD).await.unwrap();
+        session.set_mode(RemoteDesktopMode::ViewOnly).await.unwrap();
+        session.start().await.unwrap();
+        let path = ObjectPath::try_from(id.as_str()).unwrap();
+
+        let denied = proxy
+            .call::<_, _, HashMap<String, OwnedValue>>("CurrentFocus", &(&path,))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(denied, zbus::Error::MethodError(ref name, _, _)
+                if name.as_str() == "org.freedesktop.DBus.Error.AccessDenied"),
+            "{denied:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn capture_streams_are_cosmic_comp_nodes() {
+        let bus = MockBus::spawn().await.unwrap();
+        let comp = MockCosmicComp::spawn(&bus).await.unwrap();
+        let mut backend = CosmicBackend::new();
+        backend
+            .connect_with(bus.connect().await.unwrap())
+            .await
+            .unwrap();
+        assert!(backend.capabilities().can_capture_screen);
+
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let core = PortalCore::new(manager).with_backend(Arc::new(backend));
+        let id = format!("{PORTAL_PATH}/session/capture");
+        core.create_session(id.clone(), "app".into()).await.unwrap();
+        core.select_devices(SelectDevicesRequest {
+            session_id: id.clone(),
+            device_types: None,
+        })
+        .await
+        .unwrap();
+        let response = core
+            .start_session(StartSessionRequest {
+                session_id: id.clone(),
+                parent_window: None,
+            })
+            .await
+            .unwrap();
+
+        // Every output is exported, as the node cosmic-comp handed out
+        let session_id = SessionId::new(&id);
+        assert_eq!(response.streams.len(), MOCK_OUTPUTS.len());
+        for (stream, name, width, height) in MOCK_OUTPUTS {
+            let info = response
+                .streams
+                .iter()
+                .find(|s| s.output_index == stream)
+                .unwrap_or_else(|| panic!("no stream for {name}"));
+            assert_eq!((info.width, info.height), (width, height));
+            assert_eq!(comp.capture_node(&session_id, stream), Some(info.node_id));
+            assert_eq!(
+                comp.capture_cursor_mode(&session_id, stream),
+                Some(CursorMode::Embedded)
+            );
+        }
+
+        // Closing the session tears the nodes down in cosmic-comp
+        core.close_session(&id).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while MOCK_OUTPUTS
+                .iter()
+                .any(|o| comp.capture_node(&session_id, o.0).is_some())
+            {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("cosmic-comp captures should stop");
+    }
+
+    #[tokio::test]
+    async fn disconnect_stops_following_cosmic_comp() {
+        let bus = MockBus::spawn().await.unwrap();
+        let comp = MockCosmicComp::spawn(&bus).await.unwrap();
+        let mut backend = CosmicBackend::new();
+        backend
+            .connect_with(bus.connect().await.unwrap())
+            .await
+            .unwrap();
+        let mut revocations = backend.session_revocations();
+        let id = SessionId::new(format!("{PORTAL_PATH}/session/disconnect"));
+
+        comp.revoke_session(&id, "before").await.unwrap();
+        let revocation = tokio::time::timeout(Duration::from_secs(5), revocations.recv())
+            .await
+            .expect("revocation forwarded while connected")
+            .unwrap();
+        assert_eq!(revocation.reason, "before");
+
+        backend.disconnect().await;
+        assert!(!backend.capabilities().can_capture_screen);
+        comp.revoke_session(&id, "after").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(matches!(
+            revocations.try_recv(),
+            Err(tokio::sync::broadcast::error::TryRecvError::Empty)
+        ));
+    }
+
+    #[tokio::test]
+    async fn capture_cursor_mode_reaches_cosmic_comp() {
+        let bus = MockBus::spawn().await.unwrap();
+        let comp = MockCosmicComp::spawn(&bus).await.unwrap();
+        let mut backend = CosmicBackend::new();
+        backend
+            .connect_with(bus.connect().await.unwrap())
+            .await
+            .unwrap();
+
+        let session_id = SessionId::new(format!("{PORTAL_PATH}/session/cursor"));
+        for (output, cursor_mode) in [(0, CursorMode::Metadata), (1, CursorMode::Hidden)] {
+            let request = OutputCaptureRequest::new(output).with_cursor_mode(cursor_mode);
+            let stream = backend
+                .start_capture_output(&session_id, request)
+                .await
+                .unwrap();
+            // cosmic-comp delivers every mode, so none falls back
+            assert_eq!(stream.cursor_mode, cursor_mode);
+            assert_eq!(
+                comp.capture_cursor_mode(&session_id, output),
+                Some(cursor_mode)
+            );
+        }
+    }
+}
@@ -0,0 +1,166 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Session bus view backed by a zbus connection.
+//!
+//! Lets [`check_registration`](ion_validation::providers::portal::check_registration)
+//! run against a [`MockBus`](crate::mock_bus::MockBus), or any bus the
+//! substrate can connect to directly.
+
+use async_trait::async_trait;
+use ion_validation::providers::SessionBus;
+use ion_validation::ValidationError;
+use zbus::fdo::{DBusProxy, IntrospectableProxy};
+use zbus::names::BusName;
+use zbus::Connection;
+
+/// [`SessionBus`] that asks the bus daemon and introspects over a
+/// connection.
+#[derive(Debug, Clone)]
+pub struct ZbusSessionBus {
+    connection: Connection,
+}
+
+impl ZbusSessionBus {
+    /// Query the bus `connection` is attached to.
+    #[must_use]
+    pub fn new(connection: Connection) -> Self {
+        Self { connection }
+    }
+}
+
+#[async_trait]
+impl SessionBus for ZbusSessionBus {
+    async fn name_has_owner(&self, name: &str) -> ion_validation::Result<bool> {
+        let name = BusName::try_from(name).map_err(|e| bus_error("invalid bus name", e))?;
+        DBusProxy::new(&self.connection)
+            .await
+            .map_err(|e| bus_error("failed to reach the bus daemon", e))?
+            .name_has_owner(name)
+            .await
+            .map_err(|e| bus_error("NameHasOwner failed", e))
+    }
+
+    async fn interfaces(&self, name: &str, path: &str) -> ion_validation::Result<Vec<String>> {
+        let proxy = IntrospectableProxy::builder(&self.connection)
+            .destination(name)
+            .and_then(|builder| builder.path(path))
+            .map_err(|e| bus_error("invalid introspection target", e))?
+            .build()
+            .await
+            .map_err(|e| bus_error("failed to create introspection proxy", e))?;
+        match proxy.introspect().await {
+            Ok(xml) => Ok(interface_names(&xml)),
+            // Nothing is served at the path
+            Err(
+                zbus::fdo::Error::UnknownObject(_)
+                | zbus::fdo::Error::UnknownMethod(_)
+                | zbus::fdo::Error::UnknownInterface(_),
+            ) => Ok(Vec::new()),
+            Err(e) => Err(bus_error("Introspect failed", e)),
+        }
+    }
+}
+
+fn bus_error(message: &str, source: impl Into<zbus::Error>) -> ValidationError {
+    ValidationError::portal(message, source.into())
+}
+
+/// Names of the interfaces declared in introspection XML.
+fn interface_names(xml: &str) -> Vec<String> {
+    const TAG: &str = "<interface name=\"";
+
+    xml.match_indices(TAG)
+        .filter_map(|(start, _)| {
+            let name = &xml[start + TAG.len()..];
+            name.find('"').map(|end| name[..end].to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_bus::MockBus;
+    use ion_portal::portal::RemoteDesktopPortal;
+    use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
+    use ion_validation::providers::portal::{
+        await_registration, check_registration, PORTAL_BUS_NAME, PORTAL_OBJECT_PATH,
+    };
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn registration_needs_the_name_claimed() {
+        let bus = MockBus::spawn().await.unwrap();
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let server = bus
+            .serve_on_runtime(PORTAL_OBJECT_PATH, RemoteDesktopPortal::new(manager))
+            .await
+            .unwrap();
+        let probe = ZbusSessionBus::new(bus.connect().await.unwrap());
+
+        let unclaimed = check_registration(&probe).await.unwrap();
+        assert!(!unclaimed.name_owned);
+        assert!(unclaimed.missing().unwrap().contains(PORTAL_BUS_NAME));
+
+        server.request_name(PORTAL_BUS_NAME).await.unwrap();
+        let claimed = check_registration(&probe).await.unwrap();
+        assert!(claimed.is_registered(), "{claimed:?}");
+    }
+
+    #[tokio::test]
+    async fn registration_needs_the_interface_at_the_expected_path() {
+        let bus = MockBus::spawn().await.unwrap();
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let misplaced = bus
+            .serve_on_runtime(
+                "/org/freedesktop/portal/elsewhere",
+                RemoteDesktopPortal::new(manager),
+            )
+            .await
+            .unwrap();
+        misplaced.request_name(PORTAL_BUS_NAME).await.unwrap();
+        let probe = ZbusSessionBus::new(bus.connect().await.unwrap());
+
+        let status = check_registration(&probe).await.unwrap();
+        assert!(status.name_owned);
+        assert!(!status.interface_exported);
+        assert!(status.missing().unwrap().contains(PORTAL_OBJECT_PATH));
+    }
+
+    #[tokio::test]
+    async fn awaiting_registration_waits_for_a_slow_start() {
+        let bus = MockBus::spawn().await.unwrap();
+        let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+        let server = bus
+            .serve_on_runtime(PORTAL_OBJECT_PATH, RemoteDesktopPortal::new(manager))
+            .await
+            .unwrap();
+        let probe = ZbusSessionBus::new(bus.connect().await.unwrap());
+
+        let starting = async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            server.request_name(PORTAL_BUS_NAME).await.unwrap();
+        };
+        let (status, ()) = tokio::join!(
+            await_registration(&probe, Duration::from_secs(5), Duration::from_millis(20)),
+            starting
+        );
+        assert!(status.unwrap().is_registered());
+    }
+
+    #[tokio::test]
+    async fn awaiting_registration_gives_up_at_the_deadline() {
+        let bus = MockBus::spawn().await.unwrap();
+        let probe = ZbusSessionBus::new(bus.connect().await.unwrap());
+
+        let status = await_registration(
+            &probe,
+            Duration::from_millis(100),
+            Duration::from_millis(20),
+        )
+        .await
+        .unwrap();
+        assert!(status.missing().unwrap().contains(PORTAL_BUS_NAME));
+    }
+}
@@ -0,0 +1,367 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Parallel validation runner.
+//!
+//! A [`ValidationSuite`] holds named validators, each of which may declare
+//! prerequisites. Validators run concurrently up to a parallelism limit; a
+//! validator starts only once all its prerequisites have finished, and is
+//! skipped if any of them did not pass. Validators own whatever mock
+//! infrastructure they need (typically a [`TestHarness`](crate::TestHarness)
+//! with its own [`MockBus`](crate::mock_bus::MockBus)), so concurrent runs
+//! never share a bus.
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::validator::{ValidationResult, ValidationStats, Validator};
+
+type RunFn = Box<dyn FnOnce() -> BoxFuture<'static, anyhow::Result<ValidationResult>> + Send>;
+
+/// A named validator and its prerequisites.
+struct SuiteEntry {
+    name: String,
+    depends_on: Vec<String>,
+    run: RunFn,
+}
+
+/// Validators to run together, in dependency order.
+#[derive(Default)]
+pub struct ValidationSuite {
+    entries: Vec<SuiteEntry>,
+}
+
+/// Outcome of one validator in a suite run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestReport {
+    /// Name of the validator
+    pub name: String,
+    /// Validators that had to finish first
+    pub depends_on: Vec<String>,
+    /// Checks reported, `None` if the validator errored or was skipped
+    pub result: Option<ValidationResult>,
+    /// Why the validator produced no result
+    pub error: Option<String>,
+    /// Milliseconds from suite start until the validator started
+    pub started_ms: f64,
+    /// Milliseconds the validator ran for
+    pub duration_ms: f64,
+}
+
+impl TestReport {
+    /// Whether the validator ran and all its checks passed.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.result.as_ref().is_some_and(ValidationResult::is_valid)
+    }
+
+    fn failed(name: String, depends_on: Vec<String>, error: String, started: Duration) -> Self {
+        Self {
+            name,
+            depends_on,
+            result: None,
+            error: Some(error),
+            started_ms: millis(started),
+            duration_ms: 0.0,
+        }
+    }
+}
+
+/// Aggregated outcome of a suite run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuiteReport {
+    /// Most validators allowed to run at once
+    pub parallelism: usize,
+    /// Per-validator outcomes, sorted by name
+    pub tests: Vec<TestReport>,
+    /// Whether every validator ran and passed
+    pub all_passed: bool,
+    /// Check counts across all validators
+    pub stats: ValidationStats,
+    /// Wall-clock milliseconds for the whole run
+    pub elapsed_ms: f64,
+}
+
+impl SuiteReport {
+    /// Merges every validator's checks into one result.
+    ///
+    /// Check names are prefixed with the validator name (`smoke/session_start`).
+    /// A validator that errored or was skipped contributes one failed check.
+    #[must_use]
+    pub fn combined(&self) -> ValidationResult {
+        let mut validator = Validator::new();
+        for test in &self.tests {
+            match (&test.result, &test.error) {
+                (Some(result), _) => validator.merge(&test.name, result),
+                (None, error) => validator.check(
+                    format!("{}/run", test.name),
+                    false,
+                    error.clone().unwrap_or_default(),
+                ),
+            }
+        }
+        validator.build()
+    }
+}
+
+impl ValidationSuite {
+    /// Create an empty suite.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a validator that starts once everything in `depends_on` has
+    /// passed.
+    #[must_use]
+    pub fn add<F, Fut>(mut self, name: impl Into<String>, depends_on: &[&str], run: F) -> Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<ValidationResult>> + Send + 'static,
+    {
+        self.entries.push(SuiteEntry {
+            name: name.into(),
+            depends_on: depends_on.iter().map(ToString::to_string).collect(),
+            run: Box::new(move || Box::pin(run())),
+        });
+        self
+    }
+
+    /// Run the suite with at most `parallelism` validators at once.
+    ///
+    /// Each validator is spawned on the Tokio runtime, so they run in
+    /// parallel on a multi-threaded runtime. A `parallelism` of 0 is
+    /// treated as 1.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if two validators share a name, a prerequisite is
+    /// not in the suite, or prerequisites form a cycle. Validator failures
+    /// are reported in the [`SuiteReport`], not as errors.
+    pub async fn run(self, parallelism: usize) -> anyhow::Result<SuiteReport> {
+        let parallelism = parallelism.max(1);
+        self.check_dependencies()?;
+
+        let suite_start = Instant::now();
+        let mut pending = self.entries;
+        let mut done: HashMap<String, TestReport> = HashMap::new();
+        let mut running = FuturesUnordered::new();
+
+        loop {
+            while running.len() < parallelism {
+                let Some(index) = pending
+                    .iter()
+                    .position(|e| e.depends_on.iter().all(|d| done.contains_key(d)))
+                else {
+                    break;
+                };
+                let entry = pending.remove(index);
+
+                if let Some(failed) = entry.depends_on.iter().find(|d| !done[*d].passed()) {
+                    warn!(test = %entry.name, prerequisite = %failed, "Skipping validator");
+                    let error = format!("skipped: prerequisite `{failed}` did not pass");
+                    let report = TestReport::failed(
+                        entry.name.clone(),
+                        entry.depends_on,
+                        error,
+                        suite_start.elapsed(),
+                    );
+                    done.insert(entry.name, report);
+                    continue;
+                }
+
+                running.push(spawn(entry, suite_start));
+            }
+
+            match running.next().await {
+                Some(report) => {
+                    info!(test = %report.name, passed = report.passed(), "Validator finished");
+                    done.insert(report.name.clone(), report);
+                },
+                None if pending.is_empty() => break,
+                None => anyhow::bail!(
+                    "dependency cycle among validators: {}",
+                    pending
+                        .iter()
+                        .map(|e| e.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }
+        }
+
+        let mut tests: Vec<TestReport> = done.into_values().collect();
+        tests.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut report = SuiteReport {
+            parallelism,
+            all_passed: tests.iter().all(TestReport::passed),
+            tests,
+            stats: ValidationStats {
+                total: 0,
+                passed: 0,
+                failed: 0,
+            },
+            elapsed_ms: millis(suite_start.elapsed()),
+        };
+        report.stats = report.combined().stats;
+        Ok(report)
+    }
+
+    fn check_dependencies(&self) -> anyhow::Result<()> {
+        let mut names = HashSet::new();
+        for entry in &self.entries {
+            if !names.insert(entry.name.as_str()) {
+                anyhow::bail!("duplicate validator: {}", entry.name);
+            }
+        }
+        for entry in &self.entries {
+            if let Some(missing) = entry
+                .depends_on
+                .iter()
+                .find(|d| !names.contains(d.as_str()))
+            {
+                anyhow::bail!("{} depends on unknown validator {missing}", entry.name);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Spawn `entry`, resolving to its report once it finishes.
+fn spawn(entry: SuiteEntry, suite_start: Instant) -> impl Future<Output = TestReport> {
+    let SuiteEntry {
+        name,
+        depends_on,
+        run,
+    } = entry;
+    let started = suite_start.elapsed();
+    info!(test = %name, "Validator started");
+
+    let handle = tokio::spawn(async move {
+        let start = Instant::now();
+        let outcome = run().await;
+        (outcome, start.elapsed())
+    });
+
+    async move {
+        match handle.await {
+            Ok((outcome, elapsed)) => {
+                let (result, error) = match outcome {
+                    Ok(result) => (Some(result), None),
+                    Err(e) => (None, Some(format!("{e:#}"))),
+                };
+                TestReport {
+                    name,
+                    depends_on,
+                    result,
+                    error,
+                    started_ms: millis(started),
+                    duration_ms: millis(elapsed),
+                }
+            },
+            Err(e) => TestReport::failed(name, depends_on, e.to_string(), started),
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use tokio::sync::Barrier;
+
+    fn passing(name: &str) -> ValidationResult {
+        let mut validator = Validator::new();
+        validator.check(name, true, "ok");
+        validator.build()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn independent_run_concurrently_and_dependent_waits() {
+        // Neither independent validator can finish unless both are running
+        let barrier = Arc::new(Barrier::new(2));
+        let prerequisite_done = Arc::new(AtomicBool::new(false));
+
+        let (b1, b2) = (Arc::clone(&barrier), Arc::clone(&barrier));
+        let (done_flag, seen_flag) = (
+            Arc::clone(&prerequisite_done),
+            Arc::clone(&prerequisite_done),
+        );
+        let suite = ValidationSuite::new()
+            .add("dependent", &["first"], move || async move {
+                let mut validator = Validator::new();
+                validator.check(
+                    "prerequisite_finished",
+                    seen_flag.load(Ordering::SeqCst),
+                    "first finished before dependent started",
+                );
+                Ok(validator.build())
+            })
+            .add("first", &[], move || async move {
+                b1.wait().await;
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                done_flag.store(true, Ordering::SeqCst);
+                Ok(passing("first"))
+            })
+            .add("second", &[], move || async move {
+                b2.wait().await;
+                Ok(passing("second"))
+            });
+
+        let report = tokio::time::timeout(Duration::from_secs(5), suite.run(2))
+            .await
+            .expect("independent validators did not run concurrently")
+            .unwrap();
+
+        assert!(report.all_passed, "{report:?}");
+        let names: Vec<_> = report.tests.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["dependent", "first", "second"]);
+        let first = &report.tests[1];
+        assert!(report.tests[0].started_ms >= first.started_ms + first.duration_ms);
+        assert_eq!(report.stats.total, 3);
+        assert!(report
+            .combined()
+            .checks
+            .iter()
+            .any(|c| c.name == "dependent/prerequisite_finished"));
+    }
+
+    #[tokio::test]
+    async fn failed_prerequisite_skips_dependent() {
+        let report = ValidationSuite::new()
+            .add("broken", &[], || async { anyhow::bail!("bus unavailable") })
+            .add("after", &["broken"], || async { Ok(passing("after")) })
+            .run(4)
+            .await
+            .unwrap();
+
+        assert!(!report.all_passed);
+        assert_eq!(report.tests[0].name, "after");
+        assert!(report.tests[0].result.is_none());
+        assert!(report.tests[0].error.as_deref().unwrap().contains("broken"));
+        assert_eq!(report.stats.failed, 2);
+    }
+
+    #[tokio::test]
+    async fn invalid_dependencies_rejected() {
+        let unknown = ValidationSuite::new().add("a", &["missing"], || async { Ok(passing("a")) });
+        assert!(unknown.run(1).await.is_err());
+
+        let cycle = ValidationSuite::new()
+            .add("a", &["b"], || async { Ok(passing("a")) })
+            .add("b", &["a"], || async { Ok(passing("b")) });
+        assert!(cycle.run(1).await.is_err());
+    }
+}
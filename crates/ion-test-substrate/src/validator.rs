@@ -4,10 +4,26 @@
 //! Spec validator for xdg-desktop-portal RemoteDesktop interface.
 //!
 //! Validates that the portal implementation conforms to the
-//! freedesktop specification.
-
+//! freedesktop specification, that its input pipeline resists
+//! flooding, and that capture streams neither drop nor reorder frames
+//! unannounced.
+
+use ion_compositor::{
+    CaptureFrame, EventPriority, InputMiddleware, MiddlewareOutcome, RateLimitMiddleware,
+    VirtualInput, VirtualInputEvent, VirtualInputSender,
+};
+use ion_core::event::{InputEvent, KeyState};
+use ion_core::session::SessionId;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Multiple of the session's burst limit sent in each flood.
+const FLOOD_FACTOR: u32 = 4;
 
 /// Result of a validation check.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +47,101 @@ pub struct ValidationResult {
     pub all_passed: bool,
     /// Summary statistics
     pub stats: ValidationStats,
+    /// Measurements from [`Validator::validate_rate_limiting`]
+    #[serde(default)]
+    pub flood: Vec<FloodMeasurement>,
+    /// Measurements from [`Validator::validate_frame_continuity`]
+    #[serde(default)]
+    pub frames: Vec<FrameContinuity>,
+    /// Files recorded while validating, e.g. capture recordings from
+    /// [`ion_core::recording::CaptureRecorder`]
+    #[serde(default)]
+    pub evidence: Vec<PathBuf>,
+}
+
+/// Accepted-vs-sent numbers for one flooded device category.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FloodMeasurement {
+    /// Device category that was flooded (`pointer`, `keyboard`, `touch`)
+    pub category: String,
+    /// Events sent in the flood
+    pub sent: u32,
+    /// Events the rate limiter forwarded
+    pub accepted: u32,
+    /// Whether the flood also blocked another event type in the same session
+    pub blocks_other_types: bool,
+}
+
+impl FloodMeasurement {
+    /// Fraction of flood events that were accepted (0.0 - 1.0).
+    #[must_use]
+    pub fn accept_ratio(&self) -> f64 {
+        if self.sent == 0 {
+            return 0.0;
+        }
+        f64::from(self.accepted) / f64::from(self.sent)
+    }
+}
+
+/// Sequence numbers seen on a capture stream during one window.
+///
+/// Gaps are explained when the receiver lagged: a `Lagged(n)` from the
+/// broadcast channel accounts for up to `n` sequence numbers missing
+/// before the next frame, where the receiver picks the stream up again.
+/// Drops left over after that frame explain nothing later on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FrameContinuity {
+    /// Frames received
+    pub received: u64,
+    /// Frames the receiver reported as dropped (lagged)
+    pub dropped: u64,
+    /// Unexplained gaps as `(expected, received)` sequence numbers
+    pub gaps: Vec<(u64, u64)>,
+    /// Repeated or out-of-order frames as `(previous, received)`
+    pub out_of_order: Vec<(u64, u64)>,
+    /// Sequence number expected next
+    #[serde(skip)]
+    next: Option<u64>,
+    /// Drops reported since the last frame
+    #[serde(skip)]
+    pending_drops: u64,
+}
+
+impl FrameContinuity {
+    /// Record a received frame.
+    fn observe(&mut self, sequence: u64) {
+        self.received += 1;
+        // The receiver resubscribed at this frame; older drops are spent
+        let drops = std::mem::take(&mut self.pending_drops);
+        let Some(expected) = self.next else {
+            self.next = Some(sequence + 1);
+            return;
+        };
+
+        if sequence < expected {
+            self.out_of_order.push((expected - 1, sequence));
+            return;
+        }
+
+        // Drops reported before this frame explain (part of) a skip
+        let skipped = sequence - expected;
+        if skipped > drops {
+            self.gaps.push((expected, sequence));
+        }
+        self.next = Some(sequence + 1);
+    }
+
+    /// Record a `Lagged(n)` from the stream.
+    fn lagged(&mut self, n: u64) {
+        self.dropped += n;
+        self.pending_drops += n;
+    }
+
+    /// Whether every frame arrived in order, skips all explained by drops.
+    #[must_use]
+    pub fn is_continuous(&self) -> bool {
+        self.gaps.is_empty() && self.out_of_order.is_empty()
+    }
 }
 
 /// Validation statistics.
@@ -56,18 +167,32 @@ impl ValidationResult {
     pub fn failures(&self) -> Vec<&ValidationCheck> {
         self.checks.iter().filter(|c| !c.passed).collect()
     }
+
+    /// Attach a file that helps explain the result, typically a capture
+    /// recording kept because a check failed.
+    pub fn attach_evidence(&mut self, path: impl Into<PathBuf>) {
+        self.evidence.push(path.into());
+    }
 }
 
 /// Validator for RemoteDesktop portal implementation.
 pub struct Validator {
     checks: Vec<ValidationCheck>,
+    flood: Vec<FloodMeasurement>,
+    frames: Vec<FrameContinuity>,
+    evidence: Vec<PathBuf>,
 }
 
 impl Validator {
     /// Create a new validator.
     #[must_use]
     pub fn new() -> Self {
-        Self { checks: Vec::new() }
+        Self {
+            checks: Vec::new(),
+            flood: Vec::new(),
+            frames: Vec::new(),
+            evidence: Vec::new(),
+        }
     }
 
     /// Add a check result.
@@ -205,6 +330,198 @@ impl Validator {
         );
     }
 
+    /// Validate that the rate limit middleware resists input flooding.
+    ///
+    /// Floods a fresh session per device category with several times its
+    /// burst limit and checks that excess motion is dropped while key
+    /// state changes all pass, that the flood does not also lock out
+    /// motion of another category for that session, and that a control
+    /// event sent concurrently from another session is delivered ahead of
+    /// the queued flood.
+    pub async fn validate_rate_limiting(&mut self, limits: &RateLimitMiddleware) {
+        let touch_motion = InputEvent::TouchMotion {
+            stream: 0,
+            slot: 0,
+            x: 1.0,
+            y: 1.0,
+        };
+        let floods = [
+            (
+                "pointer",
+                InputEvent::pointer_motion(1.0, 0.0),
+                touch_motion.clone(),
+            ),
+            (
+                "keyboard",
+                InputEvent::key(30, KeyState::Pressed),
+                InputEvent::pointer_motion(1.0, 0.0),
+            ),
+            ("touch", touch_motion, InputEvent::pointer_motion(1.0, 0.0)),
+        ];
+
+        for (category, flood_event, other_event) in floods {
+            let session = SessionId::new(format!("/validator/flood/{category}"));
+            let sent = flood_size(limits, &session);
+            let (_input, tx) = VirtualInput::new(sent as usize + 1);
+            let droppable = EventPriority::of(&flood_event) == EventPriority::Low;
+
+            let mut accepted = 0;
+            for _ in 0..sent {
+                if submit(limits, &tx, &session, flood_event.clone()).await {
+                    accepted += 1;
+                }
+            }
+            let blocks_other_types = !submit(limits, &tx, &session, other_event).await;
+            limits.remove_session(&session);
+
+            let measurement = FloodMeasurement {
+                category: category.to_string(),
+                sent,
+                accepted,
+                blocks_other_types,
+            };
+
+            self.check(
+                format!("rate_limit_{category}_flood"),
+                if droppable {
+                    accepted > 0 && accepted < sent
+                } else {
+                    accepted == sent
+                },
+                format!(
+                    "Accepted {accepted}/{sent} {category} events ({:.1}%)",
+                    measurement.accept_ratio() * 100.0
+                ),
+            );
+            self.check(
+                format!("rate_limit_{category}_isolation"),
+                !blocks_other_types,
+                if blocks_other_types {
+                    format!("A {category} flood blocks other motion in the same session")
+                } else {
+                    format!("Other motion still accepted during a {category} flood")
+                },
+            );
+
+            self.flood.push(measurement);
+        }
+
+        self.validate_control_not_starved(limits).await;
+    }
+
+    /// Flood one session with motion while another sends a button release,
+    /// then check the release is dispatched before the queued motion.
+    async fn validate_control_not_starved(&mut self, limits: &RateLimitMiddleware) {
+        let flooder = SessionId::new("/validator/flood/starvation");
+        let controller = SessionId::new("/validator/flood/control");
+        let sent = flood_size(limits, &flooder);
+        let (mut input, tx) = VirtualInput::new(sent as usize + 1);
+        let queued = AtomicUsize::new(0);
+
+        let flood = async {
+            for i in 0..sent {
+                let motion = InputEvent::pointer_motion(f64::from(i), 0.0);
+                if submit(limits, &tx, &flooder, motion).await {
+                    queued.fetch_add(1, Ordering::Relaxed);
+                }
+                tokio::task::yield_now().await;
+            }
+        };
+        let control = async {
+            tokio::task::yield_now().await;
+            let ahead = queued.load(Ordering::Relaxed);
+            let accepted = submit(limits, &tx, &controller, InputEvent::left_click(false)).await;
+            (accepted, ahead)
+        };
+        let ((), (control_accepted, ahead)) = tokio::join!(flood, control);
+
+        limits.remove_session(&flooder);
+        limits.remove_session(&controller);
+
+        let position =
+            std::iter::from_fn(|| input.try_recv()).position(|e| e.session_id == controller);
+        let delivered_first = position == Some(0);
+
+        self.check(
+            "rate_limit_control_not_starved",
+            control_accepted && delivered_first,
+            if !control_accepted {
+                "Control event rejected while another session was flooding".to_string()
+            } else if delivered_first {
+                format!("Control event delivered ahead of {ahead} queued motion events")
+            } else {
+                format!("Control event delivered at {position:?}, behind queued motion")
+            },
+        );
+    }
+
+    /// Validate that a capture stream delivers frames in sequence.
+    ///
+    /// Consumes `stream` for `window` (or until it closes) and checks that
+    /// [`FrameMetadata::sequence`](ion_compositor::capture::FrameMetadata) increases
+    /// by one per frame. Skips are only accepted when the receiver reported
+    /// lagging by at least as many frames; anything else, and any repeated
+    /// or out-of-order frame, fails with the sequence numbers involved.
+    pub async fn validate_frame_continuity(
+        &mut self,
+        stream: &mut broadcast::Receiver<Arc<CaptureFrame>>,
+        window: Duration,
+    ) {
+        let mut continuity = FrameContinuity::default();
+        let deadline = tokio::time::Instant::now() + window;
+
+        loop {
+            match tokio::time::timeout_at(deadline, stream.recv()).await {
+                Ok(Ok(frame)) => continuity.observe(frame.metadata.sequence),
+                Ok(Err(broadcast::error::RecvError::Lagged(n))) => continuity.lagged(n),
+                Ok(Err(broadcast::error::RecvError::Closed)) | Err(_) => break,
+            }
+        }
+
+        self.check(
+            "frame_sequence_gaps",
+            continuity.gaps.is_empty(),
+            if continuity.gaps.is_empty() {
+                format!(
+                    "{} frames received, {} dropped frames accounted for",
+                    continuity.received, continuity.dropped
+                )
+            } else {
+                format!(
+                    "Unexplained gaps (expected, received): {:?}",
+                    continuity.gaps
+                )
+            },
+        );
+        self.check(
+            "frame_sequence_order",
+            continuity.out_of_order.is_empty(),
+            if continuity.out_of_order.is_empty() {
+                "Frames arrived in sequence order".to_string()
+            } else {
+                format!(
+                    "Repeated or out-of-order frames (previous, received): {:?}",
+                    continuity.out_of_order
+                )
+            },
+        );
+
+        self.frames.push(continuity);
+    }
+
+    /// Add every check and measurement from another validator's result,
+    /// prefixing check names with `prefix/`.
+    pub(crate) fn merge(&mut self, prefix: &str, result: &ValidationResult) {
+        self.checks
+            .extend(result.checks.iter().map(|check| ValidationCheck {
+                name: format!("{prefix}/{}", check.name),
+                ..check.clone()
+            }));
+        self.flood.extend(result.flood.iter().cloned());
+        self.frames.extend(result.frames.iter().cloned());
+        self.evidence.extend(result.evidence.iter().cloned());
+    }
+
     /// Build the final validation result.
     #[must_use]
     pub fn build(self) -> ValidationResult {
@@ -221,10 +538,41 @@ impl Validator {
                 passed,
                 failed,
             },
+            flood: self.flood,
+            frames: self.frames,
+            evidence: self.evidence,
         }
     }
 }
 
+/// Number of events to send when flooding `session`.
+fn flood_size(limits: &RateLimitMiddleware, session: &SessionId) -> u32 {
+    limits
+        .session_config(session)
+        .burst_limit
+        .saturating_mul(FLOOD_FACTOR)
+}
+
+/// Pass an event through the rate limit stage and, if forwarded, queue it.
+///
+/// Motion is dropped when its queue is full; state changes wait for room.
+/// Returns whether the event was accepted.
+async fn submit(
+    limits: &RateLimitMiddleware,
+    tx: &VirtualInputSender,
+    session: &SessionId,
+    event: InputEvent,
+) -> bool {
+    let MiddlewareOutcome::Forward(event) = limits.process(session, event) else {
+        return false;
+    };
+    let event = VirtualInputEvent::new(session.clone(), event);
+    match event.priority() {
+        EventPriority::High => tx.send(event).await.is_ok(),
+        EventPriority::Low => tx.try_send(event).is_ok(),
+    }
+}
+
 impl Default for Validator {
     fn default() -> Self {
         Self::new()
@@ -330,6 +678,19 @@ mod tests {
         assert_eq!(failures[1].name, "fail2");
     }
 
+    #[test]
+    fn test_evidence_survives_merge() {
+        let mut v = Validator::new();
+        v.check("frames", false, "dropped");
+        let mut failed = v.build();
+        failed.attach_evidence("/tmp/capture.ionrec");
+
+        let mut combined = Validator::new();
+        combined.merge("capture", &failed);
+        let result = combined.build();
+        assert_eq!(result.evidence, [PathBuf::from("/tmp/capture.ionrec")]);
+    }
+
     #[test]
     fn test_validate_session_lifecycle_all_success() {
         let mut v = Validator::new();
@@ -462,4 +823,127 @@ mod tests {
         assert_eq!(cloned.all_passed, result.all_passed);
         assert_eq!(cloned.checks.len(), result.checks.len());
     }
+
+    #[tokio::test]
+    async fn test_validate_rate_limiting_reports_measurements() {
+        let mut v = Validator::new();
+        v.validate_rate_limiting(&RateLimitMiddleware::with_defaults())
+            .await;
+        let result = v.build();
+
+        assert_eq!(result.flood.len(), 3);
+        for measurement in &result.flood {
+            assert_eq!(measurement.sent, 400);
+            // Key presses are state changes and never dropped
+            let expected = if measurement.category == "keyboard" {
+                400
+            } else {
+                100
+            };
+            assert_eq!(measurement.accepted, expected, "{}", measurement.category);
+        }
+        assert!((result.flood[0].accept_ratio() - 0.25).abs() < f64::EPSILON);
+
+        let passed = |name: &str| {
+            result
+                .checks
+                .iter()
+                .find(|c| c.name == name)
+                .unwrap()
+                .passed
+        };
+        assert!(passed("rate_limit_pointer_flood"));
+        assert!(passed("rate_limit_control_not_starved"));
+        // Each device category has its own budget, so a flood of one
+        // leaves the others' motion open
+        for category in ["pointer", "keyboard", "touch"] {
+            assert!(passed(&format!("rate_limit_{category}_isolation")));
+        }
+        assert!(result.flood.iter().all(|m| !m.blocks_other_types));
+        assert!(result.all_passed);
+    }
+
+    fn frame(sequence: u64) -> Arc<CaptureFrame> {
+        let metadata = ion_compositor::capture::FrameMetadataBuilder::new()
+            .sequence(sequence)
+            .dimensions(1, 1)
+            .build();
+        Arc::new(CaptureFrame::new(metadata, vec![0; 4]))
+    }
+
+    async fn continuity_of(sequences: &[u64], capacity: usize) -> ValidationResult {
+        let (tx, mut rx) = broadcast::channel(capacity);
+        for &sequence in sequences {
+            tx.send(frame(sequence)).unwrap();
+        }
+        drop(tx);
+
+        let mut v = Validator::new();
+        v.validate_frame_continuity(&mut rx, Duration::from_secs(1))
+            .await;
+        v.build()
+    }
+
+    #[tokio::test]
+    async fn test_frame_continuity_in_order() {
+        let result = continuity_of(&[5, 6, 7, 8], 8).await;
+        assert!(result.is_valid());
+        assert_eq!(result.frames[0].received, 4);
+        assert!(result.frames[0].is_continuous());
+    }
+
+    #[tokio::test]
+    async fn test_frame_continuity_flags_out_of_order() {
+        let result = continuity_of(&[1, 2, 4, 3, 5], 8).await;
+        let failures = result.failures();
+        let names: Vec<_> = failures.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["frame_sequence_gaps", "frame_sequence_order"]);
+        assert_eq!(result.frames[0].gaps, [(3, 4)]);
+        assert_eq!(result.frames[0].out_of_order, [(4, 3)]);
+        assert!(failures[1].message.contains("(4, 3)"));
+    }
+
+    #[tokio::test]
+    async fn test_frame_continuity_accepts_lagged_drops() {
+        // Capacity 2 drops the oldest three frames, reported as Lagged(3)
+        let result = continuity_of(&[0, 1, 2, 3, 4], 2).await;
+        assert!(result.is_valid(), "{:?}", result.failures());
+        assert_eq!(result.frames[0].received, 2);
+        assert_eq!(result.frames[0].dropped, 3);
+    }
+
+    #[test]
+    fn test_frame_continuity_unexplained_gap() {
+        let mut continuity = FrameContinuity::default();
+        continuity.observe(10);
+        continuity.lagged(1);
+        continuity.observe(13);
+        assert_eq!(continuity.gaps, [(11, 13)]);
+        assert!(!continuity.is_continuous());
+    }
+
+    #[test]
+    fn test_frame_continuity_spends_drops_on_resubscribe() {
+        let mut continuity = FrameContinuity::default();
+        continuity.observe(10);
+        continuity.lagged(5);
+        // The receiver picked up again two frames on
+        continuity.observe(13);
+        assert!(continuity.is_continuous());
+        // The three drops left over don't cover a later gap
+        continuity.observe(17);
+        assert_eq!(continuity.gaps, [(14, 17)]);
+        assert_eq!(continuity.dropped, 5);
+    }
+
+    #[test]
+    fn test_flood_measurement_ratio_empty() {
+        let m = FloodMeasurement {
+            category: "pointer".to_string(),
+            sent: 0,
+            accepted: 0,
+            blocks_other_types: false,
+        };
+        assert!(m.accept_ratio().abs() < f64::EPSILON);
+    }
 }
@@ -18,7 +18,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use ion_core::device::DeviceType;
-use ion_core::event::{ButtonState, KeyState};
+use ion_core::event::{ButtonState, InputEvent, KeyState};
 use ion_core::mode::RemoteDesktopMode;
 use ion_portal::core::{PortalCore, SelectDevicesRequest, StartSessionRequest};
 use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
@@ -325,6 +325,77 @@ async fn chaos_concurrent_input_same_session() {
     );
 }
 
+/// Tests that concurrent senders on one session never see their own
+/// events reordered.
+#[tokio::test]
+async fn chaos_concurrent_input_keeps_per_task_order() {
+    init_tracing();
+
+    let (session_manager, mut rx) = SessionManager::new(SessionManagerConfig::default());
+    let portal = Arc::new(PortalCore::new(session_manager));
+
+    let session_id = "/chaos/ordered_input";
+    portal
+        .create_session(session_id.to_string(), "test.app".to_string())
+        .await
+        .unwrap();
+    let select_req = SelectDevicesRequest {
+        session_id: session_id.to_string(),
+        device_types: Some(DeviceType::POINTER.bits()),
+    };
+    portal.select_devices(select_req).await.unwrap();
+    let start_req = StartSessionRequest {
+        session_id: session_id.to_string(),
+        parent_window: None,
+    };
+    portal.start_session(start_req).await.unwrap();
+
+    // Each task tags its motion with dx = task, dy = sequence number
+    let num_tasks: u32 = 2;
+    let events_per_task: u32 = 200;
+    let barrier = Arc::new(Barrier::new(num_tasks as usize));
+    let handles: Vec<_> = (0..num_tasks)
+        .map(|task| {
+            let portal = portal.clone();
+            let barrier = barrier.clone();
+            tokio::spawn(async move {
+                barrier.wait().await;
+                for seq in 0..events_per_task {
+                    portal
+                        .notify_pointer_motion(session_id, f64::from(task), f64::from(seq))
+                        .await
+                        .unwrap();
+                    tokio::task::yield_now().await;
+                }
+            })
+        })
+        .collect();
+
+    // Drain while sending so the channels never fill
+    let mut received = Vec::new();
+    while received.len() < (num_tasks * events_per_task) as usize {
+        let (_, event) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("all events should arrive")
+            .unwrap();
+        let InputEvent::PointerMotion { dx, dy } = event.event else {
+            panic!("unexpected event {event:?}");
+        };
+        received.push((dx, dy));
+    }
+    futures::future::join_all(handles).await;
+
+    for task in 0..num_tasks {
+        let sequence: Vec<f64> = received
+            .iter()
+            .filter(|(dx, _)| *dx == f64::from(task))
+            .map(|&(_, dy)| dy)
+            .collect();
+        let expected: Vec<f64> = (0..events_per_task).map(f64::from).collect();
+        assert_eq!(sequence, expected, "task {task} events were reordered");
+    }
+}
+
 /// Tests rapid session create/close cycles.
 #[tokio::test]
 async fn chaos_rapid_create_close_cycles() {
@@ -17,7 +17,7 @@
 use std::time::Duration;
 
 use ion_core::device::DeviceType;
-use ion_core::event::{ButtonState, InputEvent, KeyState};
+use ion_core::event::{ButtonState, InputEvent, KeyState, StampedEvent};
 use ion_core::mode::RemoteDesktopMode;
 use ion_portal::core::{PortalCore, SelectDevicesRequest, StartSessionRequest};
 use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
@@ -29,8 +29,8 @@ const RECV_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Helper to receive an event with a generous timeout guard
 async fn recv_event(
-    rx: &mut mpsc::Receiver<(ion_core::session::SessionId, InputEvent)>,
-) -> (ion_core::session::SessionId, InputEvent) {
+    rx: &mut mpsc::Receiver<(ion_core::session::SessionId, StampedEvent)>,
+) -> (ion_core::session::SessionId, StampedEvent) {
     tokio::time::timeout(RECV_TIMEOUT, rx.recv())
         .await
         .expect("Event receive should not timeout")
@@ -39,9 +39,9 @@ async fn recv_event(
 
 /// Helper to receive N events
 async fn recv_n_events(
-    rx: &mut mpsc::Receiver<(ion_core::session::SessionId, InputEvent)>,
+    rx: &mut mpsc::Receiver<(ion_core::session::SessionId, StampedEvent)>,
     n: usize,
-) -> Vec<(ion_core::session::SessionId, InputEvent)> {
+) -> Vec<(ion_core::session::SessionId, StampedEvent)> {
     let mut events = Vec::with_capacity(n);
     for _ in 0..n {
         events.push(recv_event(rx).await);
@@ -110,7 +110,7 @@ async fn e2e_basic_session_lifecycle() {
 
     // Verify event was forwarded (no short timeout - use generous guard)
     let received = recv_event(&mut event_rx).await;
-    assert!(matches!(received.1, InputEvent::PointerMotion { .. }));
+    assert!(matches!(received.1.event, InputEvent::PointerMotion { .. }));
 
     // Step 5: Close session
     portal.close_session(session_id).await.unwrap();
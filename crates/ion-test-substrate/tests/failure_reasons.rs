@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Failure reasons reported over D-Bus.
+//!
+//! Every failed portal response keeps the spec's "other" response code but
+//! carries an `error_detail` map with a stable `reason`. These tests drive a
+//! portal on a private bus into each failure and check the reason a client
+//! would see.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ion_core::backend::MockBackend;
+use ion_core::mode::RemoteDesktopMode;
+use ion_core::session::SessionId;
+use ion_portal::consent::{ConsentProvider, ConsentResult};
+use ion_portal::failure::{FailureReason, ERROR_DETAIL_KEY};
+use ion_portal::portal::{RemoteDesktopPortal, ResponseCode};
+use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
+use ion_test_substrate::mock_bus::MockBus;
+use ion_test_substrate::ScriptedConsentProvider;
+use zbus::zvariant::{Dict, ObjectPath, OwnedValue, Value};
+
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const PORTAL_INTERFACE: &str = "org.freedesktop.impl.portal.RemoteDesktop";
+const APP_ID: &str = "org.ionchannel.FailureReasons";
+
+/// A portal served on a private bus, and a client connection to it.
+struct Fixture {
+    _bus: MockBus,
+    server: zbus::Connection,
+    client: zbus::Connection,
+}
+
+impl Fixture {
+    async fn spawn(portal: RemoteDesktopPortal) -> Self {
+        let bus = MockBus::spawn().await.unwrap();
+        let server = bus.serve_on_runtime(PORTAL_PATH, portal).await.unwrap();
+        let client = bus.connect().await.unwrap();
+        Self {
+            _bus: bus,
+            server,
+            client,
+        }
+    }
+
+    /// Calls `method` and returns its response code and results.
+    async fn request(
+        &self,
+        method: &str,
+        body: &(impl serde::Serialize + zbus::zvariant::DynamicType),
+    ) -> (u32, HashMap<String, OwnedValue>) {
+        let reply = self
+            .client
+            .call_method(
+                self.server.unique_name(),
+                PORTAL_PATH,
+                Some(PORTAL_INTERFACE),
+                method,
+                body,
+            )
+            .await
+            .unwrap();
+        reply.body().deserialize().unwrap()
+    }
+
+    async fn create(&self, session: &str) -> (u32, HashMap<String, OwnedValue>) {
+        let options: HashMap<&str, Value<'_>> = HashMap::new();
+        self.request(
+            "CreateSession",
+            &(request_path(), session_path(session), APP_ID, &options),
+        )
+        .await
+    }
+
+    async fn select(&self, session: &str) -> (u32, HashMap<String, OwnedValue>) {
+        let options: HashMap<&str, Value<'_>> = HashMap::new();
+        self.request(
+            "SelectDevices",
+            &(request_path(), session_path(session), APP_ID, &options),
+        )
+        .await
+    }
+
+    async fn start(&self, session: &str) -> (u32, HashMap<String, OwnedValue>) {
+        let options: HashMap<&str, Value<'_>> = HashMap::new();
+        self.request(
+            "Start",
+            &(request_path(), session_path(session), APP_ID, "", &options),
+        )
+        .await
+    }
+}
+
+fn request_path() -> ObjectPath<'static> {
+    ObjectPath::try_from(format!("{PORTAL_PATH}/request/failure")).unwrap()
+}
+
+fn session_path(name: &str) -> ObjectPath<'static> {
+    ObjectPath::try_from(format!("{PORTAL_PATH}/session/{name}")).unwrap()
+}
+
+/// The reason code of a failed response.
+fn reason(response: &(u32, HashMap<String, OwnedValue>)) -> String {
+    let (code, results) = response;
+    assert_eq!(*code, ResponseCode::Other as u32, "{results:?}");
+    let detail = results[ERROR_DETAIL_KEY].downcast_ref::<Dict>().unwrap();
+    let message: String = detail.get(&"message").unwrap().unwrap();
+    assert!(!message.is_empty());
+    detail.get(&"reason").unwrap().unwrap()
+}
+
+fn portal(mode: RemoteDesktopMode, backend: MockBackend) -> RemoteDesktopPortal {
+    let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+    RemoteDesktopPortal::with_mode(manager, mode, Arc::new(backend))
+}
+
+#[tokio::test]
+async fn invalid_session_handle() {
+    let fixture = Fixture::spawn(portal(RemoteDesktopMode::Full, MockBackend::new())).await;
+
+    let oversized = "a".repeat(SessionId::MAX_LEN);
+    assert_eq!(
+        reason(&fixture.create(&oversized).await),
+        FailureReason::InvalidSessionHandle.code()
+    );
+}
+
+#[tokio::test]
+async fn unknown_session() {
+    let fixture = Fixture::spawn(portal(RemoteDesktopMode::Full, MockBackend::new())).await;
+
+    assert_eq!(
+        reason(&fixture.select("missing").await),
+        FailureReason::SessionNotFound.code()
+    );
+    assert_eq!(
+        reason(&fixture.start("missing").await),
+        FailureReason::SessionNotFound.code()
+    );
+}
+
+#[tokio::test]
+async fn session_limit() {
+    let (manager, _rx) = SessionManager::new(SessionManagerConfig {
+        max_sessions: 1,
+        ..SessionManagerConfig::default()
+    });
+    let fixture = Fixture::spawn(RemoteDesktopPortal::with_backend(
+        manager,
+        Arc::new(MockBackend::new()),
+    ))
+    .await;
+
+    assert_eq!(
+        fixture.create("first").await.0,
+        ResponseCode::Success as u32
+    );
+    assert_eq!(
+        reason(&fixture.create("second").await),
+        FailureReason::SessionLimit.code()
+    );
+}
+
+#[tokio::test]
+async fn consent_denied() {
+    let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+    let consent = Arc::new(ScriptedConsentProvider::new([ConsentResult::Denied]));
+    let fixture = Fixture::spawn(RemoteDesktopPortal::with_consent_provider(
+        manager,
+        RemoteDesktopMode::InputOnly,
+        consent as Arc<dyn ConsentProvider>,
+        Arc::new(MockBackend::new()),
+    ))
+    .await;
+
+    assert_eq!(
+        fixture.create("denied").await.0,
+        ResponseCode::Success as u32
+    );
+    assert_eq!(
+        reason(&fixture.select("denied").await),
+        FailureReason::ConsentDenied.code()
+    );
+}
+
+#[tokio::test]
+async fn backend_without_capture() {
+    let backend = MockBackend::new();
+    backend.set_capture_available(false);
+    let fixture = Fixture::spawn(portal(RemoteDesktopMode::Full, backend)).await;
+
+    assert_eq!(
+        fixture.create("blind").await.0,
+        ResponseCode::Success as u32
+    );
+    assert_eq!(
+        reason(&fixture.select("blind").await),
+        FailureReason::NoCaptureBackend.code()
+    );
+    assert_eq!(
+        reason(&fixture.start("blind").await),
+        FailureReason::NoCaptureBackend.code()
+    );
+}
+
+#[tokio::test]
+async fn no_backend_capabilities() {
+    let fixture = Fixture::spawn(portal(RemoteDesktopMode::None, MockBackend::new())).await;
+
+    assert_eq!(fixture.create("idle").await.0, ResponseCode::Success as u32);
+    assert_eq!(
+        reason(&fixture.select("idle").await),
+        FailureReason::NoBackend.code()
+    );
+}
+
+#[tokio::test]
+async fn successful_responses_have_no_detail() {
+    let fixture = Fixture::spawn(portal(RemoteDesktopMode::Full, MockBackend::new())).await;
+
+    for (code, results) in [
+        fixture.create("fine").await,
+        fixture.select("fine").await,
+        fixture.start("fine").await,
+    ] {
+        assert_eq!(code, ResponseCode::Success as u32);
+        assert!(!results.contains_key(ERROR_DETAIL_KEY));
+    }
+}
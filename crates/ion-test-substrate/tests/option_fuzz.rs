@@ -0,0 +1,301 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Property tests for portal option parsing.
+//!
+//! Option maps arrive straight from clients, so every value under every
+//! key is attacker-controlled. These tests throw arbitrary values at the
+//! keys the portal reads (`types`, `persist_mode`, `restore_token` and
+//! `cursor_mode`): wrong types, variants wrapping other values, nested
+//! containers and very large arrays. The accessors must never panic and
+//! must return either the value the client meant or the documented
+//! default.
+//!
+//! Each generated value carries a model of itself ([`Arb`]) from which the
+//! expected result is computed, so the tests check the exact outcome
+//! rather than only the absence of panics.
+
+use std::collections::HashMap;
+
+use ion_core::backend::CursorMode;
+use ion_core::device::DeviceType;
+use ion_portal::options::{PersistMode, PortalOptions};
+use proptest::prelude::*;
+use zvariant::{OwnedValue, StructureBuilder, Value};
+
+/// Keys the portal reads from client option maps.
+const KEYS: [&str; 4] = [
+    PortalOptions::TYPES_KEY,
+    PersistMode::OPTION_KEY,
+    PortalOptions::RESTORE_TOKEN_KEY,
+    CursorMode::OPTION_KEY,
+];
+
+/// Model of a D-Bus value a client might send.
+#[derive(Debug, Clone)]
+enum Arb {
+    U8(u8),
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Str(String),
+    /// `au`
+    U32Array(Vec<u32>),
+    /// `ai`
+    I32Array(Vec<i32>),
+    /// `as`
+    StrArray(Vec<String>),
+    /// `v`
+    Variant(Box<Arb>),
+    /// `av`
+    VariantArray(Vec<Arb>),
+    /// `a{sv}`
+    Dict(Vec<(String, Arb)>),
+    /// `(us)`
+    Struct(u32, String),
+}
+
+impl Arb {
+    fn to_value(&self) -> Value<'static> {
+        match self {
+            Self::U8(v) => Value::from(*v),
+            Self::Bool(v) => Value::from(*v),
+            Self::I32(v) => Value::from(*v),
+            Self::U32(v) => Value::from(*v),
+            Self::I64(v) => Value::from(*v),
+            Self::U64(v) => Value::from(*v),
+            Self::F64(v) => Value::from(*v),
+            Self::Str(v) => Value::from(v.clone()),
+            Self::U32Array(v) => Value::from(v.clone()),
+            Self::I32Array(v) => Value::from(v.clone()),
+            Self::StrArray(v) => Value::from(v.clone()),
+            Self::Variant(inner) => Value::Value(Box::new(inner.to_value())),
+            // Elements of `v` type are wrapped in a variant each
+            Self::VariantArray(items) => {
+                Value::from(items.iter().map(Self::to_value).collect::<Vec<_>>())
+            },
+            Self::Dict(entries) => Value::from(
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.to_value()))
+                    .collect::<HashMap<_, _>>(),
+            ),
+            Self::Struct(n, s) => Value::from(
+                StructureBuilder::new()
+                    .add_field(*n)
+                    .add_field(s.clone())
+                    .build(),
+            ),
+        }
+    }
+
+    /// Unwraps the single variant level that zvariant looks through.
+    fn unwrapped(&self) -> &Self {
+        match self {
+            Self::Variant(inner) => inner,
+            other => other,
+        }
+    }
+
+    /// The `u32` a client meant, if it sent one.
+    fn as_u32(&self) -> Option<u32> {
+        match self.unwrapped() {
+            Self::U32(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// The string a client meant, if it sent one.
+    fn as_string(&self) -> Option<String> {
+        match self.unwrapped() {
+            Self::Str(v) => Some(v.clone()),
+            _ => None,
+        }
+    }
+
+    /// The `u32` array a client meant, if it sent one.
+    ///
+    /// An `av` counts when every element is a `u32`, so an empty one
+    /// does. Its elements are already a variant deep, so a variant element
+    /// is one level too many.
+    fn as_u32_array(&self) -> Option<Vec<u32>> {
+        match self.unwrapped() {
+            Self::U32Array(v) => Some(v.clone()),
+            Self::VariantArray(items) => items
+                .iter()
+                .map(|item| match item {
+                    Self::U32(v) => Some(*v),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+fn leaf() -> impl Strategy<Value = Arb> {
+    prop_oneof![
+        any::<u8>().prop_map(Arb::U8),
+        any::<bool>().prop_map(Arb::Bool),
+        any::<i32>().prop_map(Arb::I32),
+        // Bias towards the values the portal actually interprets
+        prop_oneof![0u32..8, any::<u32>()].prop_map(Arb::U32),
+        any::<i64>().prop_map(Arb::I64),
+        any::<u64>().prop_map(Arb::U64),
+        any::<f64>().prop_map(Arb::F64),
+        ".{0,64}".prop_map(Arb::Str),
+        prop::collection::vec(any::<u32>(), 0..4096).prop_map(Arb::U32Array),
+        prop::collection::vec(any::<i32>(), 0..64).prop_map(Arb::I32Array),
+        prop::collection::vec(".{0,8}", 0..16).prop_map(Arb::StrArray),
+        (any::<u32>(), ".{0,16}").prop_map(|(n, s)| Arb::Struct(n, s)),
+    ]
+}
+
+fn value() -> impl Strategy<Value = Arb> {
+    leaf().prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            inner.clone().prop_map(|v| Arb::Variant(Box::new(v))),
+            prop::collection::vec(inner.clone(), 0..8).prop_map(Arb::VariantArray),
+            prop::collection::vec((".{0,8}", inner), 0..8).prop_map(Arb::Dict),
+        ]
+    })
+}
+
+fn options(key: &str, value: &Arb) -> HashMap<String, OwnedValue> {
+    let value = OwnedValue::try_from(value.to_value()).expect("generated value has no fds");
+    HashMap::from([(key.to_string(), value)])
+}
+
+/// Reads every option the portal interprets, as the portal does.
+fn parse_all(map: &HashMap<String, OwnedValue>) {
+    let opts = PortalOptions::new(map);
+    let _ = opts
+        .get_u32(PortalOptions::TYPES_KEY)
+        .map_or_else(DeviceType::desktop_standard, DeviceType::from);
+    let _ = opts.get_u32_array(PortalOptions::TYPES_KEY);
+    let _ = opts.persist_mode();
+    let _ = opts.restore_token();
+    let _ = opts
+        .get_u32(CursorMode::OPTION_KEY)
+        .map_or_else(CursorMode::default, CursorMode::from);
+}
+
+proptest! {
+    #[test]
+    fn types_yield_sent_value_or_none(value in value()) {
+        let map = options(PortalOptions::TYPES_KEY, &value);
+        let opts = PortalOptions::new(&map);
+
+        prop_assert_eq!(opts.get_u32(PortalOptions::TYPES_KEY), value.as_u32());
+        prop_assert_eq!(
+            opts.get_u32_array(PortalOptions::TYPES_KEY),
+            value.as_u32_array()
+        );
+        prop_assert_eq!(
+            opts.try_u32(PortalOptions::TYPES_KEY).is_err(),
+            value.as_u32().is_none()
+        );
+    }
+
+    #[test]
+    fn persist_mode_yields_known_mode_or_default(value in value()) {
+        let map = options(PersistMode::OPTION_KEY, &value);
+        let expected = value
+            .as_u32()
+            .and_then(PersistMode::from_u32)
+            .unwrap_or_default();
+
+        prop_assert_eq!(PortalOptions::new(&map).persist_mode(), expected);
+    }
+
+    #[test]
+    fn restore_token_yields_string_or_none(value in value()) {
+        let map = options(PortalOptions::RESTORE_TOKEN_KEY, &value);
+
+        prop_assert_eq!(PortalOptions::new(&map).restore_token(), value.as_string());
+    }
+
+    #[test]
+    fn cursor_mode_yields_known_mode_or_default(value in value()) {
+        let map = options(CursorMode::OPTION_KEY, &value);
+        let mode = PortalOptions::new(&map)
+            .get_u32(CursorMode::OPTION_KEY)
+            .map_or_else(CursorMode::default, CursorMode::from);
+
+        let expected = value.as_u32().map_or_else(CursorMode::default, CursorMode::from);
+        prop_assert_eq!(mode, expected);
+    }
+
+    #[test]
+    fn any_option_map_parses_without_panicking(
+        entries in prop::collection::vec(
+            (prop_oneof![prop::sample::select(KEYS.to_vec()).prop_map(str::to_string), ".{0,16}"], value()),
+            0..8,
+        )
+    ) {
+        let map = entries
+            .iter()
+            .filter_map(|(key, value)| {
+                Some((key.clone(), OwnedValue::try_from(value.to_value()).ok()?))
+            })
+            .collect();
+        parse_all(&map);
+    }
+}
+
+// Regression cases for the array parsing, kept outside the generator so
+// they always run
+
+#[test]
+fn huge_types_array_is_read_in_full() {
+    let huge: Vec<u32> = (0..1 << 20).collect();
+    let map = options(PortalOptions::TYPES_KEY, &Arb::U32Array(huge.clone()));
+    let opts = PortalOptions::new(&map);
+
+    assert_eq!(opts.get_u32_array(PortalOptions::TYPES_KEY), Some(huge));
+    assert_eq!(opts.get_u32(PortalOptions::TYPES_KEY), None);
+}
+
+#[test]
+fn empty_and_mixed_arrays_are_not_types() {
+    let opts_for = |value: Arb| options(PortalOptions::TYPES_KEY, &value);
+
+    let map = opts_for(Arb::U32Array(Vec::new()));
+    assert_eq!(
+        PortalOptions::new(&map).get_u32_array(PortalOptions::TYPES_KEY),
+        Some(Vec::new())
+    );
+
+    // Found by the generator: an empty array of any type passed as `au`
+    let map = opts_for(Arb::Variant(Box::new(Arb::I32Array(Vec::new()))));
+    assert_eq!(
+        PortalOptions::new(&map).get_u32_array(PortalOptions::TYPES_KEY),
+        None
+    );
+
+    let map = opts_for(Arb::I32Array(vec![1, 2]));
+    assert_eq!(
+        PortalOptions::new(&map).get_u32_array(PortalOptions::TYPES_KEY),
+        None
+    );
+
+    let map = opts_for(Arb::VariantArray(vec![Arb::U32(1), Arb::Str("2".into())]));
+    assert_eq!(
+        PortalOptions::new(&map).get_u32_array(PortalOptions::TYPES_KEY),
+        None
+    );
+}
+
+#[test]
+fn doubly_wrapped_values_default() {
+    let nested = Arb::Variant(Box::new(Arb::Variant(Box::new(Arb::U32(2)))));
+    let map = options(PersistMode::OPTION_KEY, &nested);
+
+    assert_eq!(
+        PortalOptions::new(&map).persist_mode(),
+        PersistMode::DoNotPersist
+    );
+}
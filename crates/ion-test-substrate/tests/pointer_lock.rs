@@ -0,0 +1,348 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Pointer lock for relative-only control, end to end.
+//!
+//! A client locks the pointer through the assembled service, as a game
+//! streaming client would, and these tests check what reaches the
+//! compositor transport, or the backend when there is none: absolute
+//! motion is ignored while locked, relative deltas still apply, and
+//! unlocking or closing the session restores normal pointer behaviour.
+//! The last test takes the COSMIC backend's path, to the compositor-side
+//! service cosmic-comp embeds.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use ion_backend_cosmic::{CosmicBackend, COSMIC_COMP_PATH, COSMIC_COMP_SERVICE};
+use ion_compositor::{RemoteDesktopService, VirtualInput};
+use ion_core::backend::{BackendCapabilities, CompositorBackend, MockBackend};
+use ion_core::device::DeviceType;
+use ion_core::event::InputEvent;
+use ion_core::session::SessionId;
+use ion_portal::portal::{RemoteDesktopPortal, ResponseCode};
+use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
+use ion_portal::transport::{CompositorMessage, CompositorTransport, InProcessTransport};
+use ion_portal_service::{run_service, ServiceConfig, ServiceHandle, PORTAL_PATH};
+use ion_test_substrate::mock_bus::MockBus;
+use tokio::sync::mpsc;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+const PORTAL_INTERFACE: &str = "org.freedesktop.impl.portal.RemoteDesktop";
+const BUS_NAME: &str = "org.ionchannel.test.PointerLock";
+const APP_ID: &str = "org.ionchannel.PointerLock";
+
+/// How long to wait for an event, signal or release.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The service on a private bus with a started session.
+struct Fixture {
+    _bus: MockBus,
+    service: ServiceHandle,
+    backend: Arc<MockBackend>,
+    compositor: mpsc::Receiver<CompositorMessage>,
+    client: zbus::Connection,
+    session: ObjectPath<'static>,
+}
+
+impl Fixture {
+    /// Starts the service forwarding to an in-process compositor.
+    async fn start(name: &str) -> Self {
+        Self::serve(name, true).await
+    }
+
+    /// Starts the service injecting through its backend.
+    async fn without_transport(name: &str) -> Self {
+        Self::serve(name, false).await
+    }
+
+    async fn serve(name: &str, transport: bool) -> Self {
+        let bus = MockBus::spawn().await.unwrap();
+        let backend = Arc::new(MockBackend::new());
+        let (compositor_tx, compositor) = mpsc::channel(16);
+        let service = run_service(ServiceConfig {
+            backend: Some(backend.clone()),
+            transport: transport.then(|| {
+                Arc::new(InProcessTransport::new(compositor_tx)) as Arc<dyn CompositorTransport>
+            }),
+            connection: Some(bus.connect_on_runtime().await.unwrap()),
+            bus_name: BUS_NAME.to_string(),
+            ..ServiceConfig::default()
+        })
+        .await
+        .unwrap();
+        let client = bus.connect().await.unwrap();
+        let fixture = Self {
+            _bus: bus,
+            service,
+            backend,
+            compositor,
+            client,
+            session: ObjectPath::try_from(format!("{PORTAL_PATH}/session/{name}")).unwrap(),
+        };
+
+        let request = ObjectPath::try_from(format!("{PORTAL_PATH}/request/{name}")).unwrap();
+        let options: HashMap<&str, Value<'_>> = HashMap::new();
+        let session = &fixture.session;
+        for method in ["CreateSession", "SelectDevices"] {
+            let code = fixture
+                .request(method, &(&request, session, APP_ID, &options))
+                .await;
+            assert_eq!(code, ResponseCode::Success as u32, "{method}");
+        }
+        let code = fixture
+            .request("Start", &(&request, session, APP_ID, "", &options))
+            .await;
+        assert_eq!(code, ResponseCode::Success as u32, "Start");
+        fixture
+    }
+
+    /// Calls a request method and returns its response code.
+    async fn request<B>(&self, method: &str, body: &B) -> u32
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        let (code, _): (u32, HashMap<String, OwnedValue>) =
+            self.call(method, body).await.body().deserialize().unwrap();
+        code
+    }
+
+    async fn call<B>(&self, method: &str, body: &B) -> zbus::Message
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        self.client
+            .call_method(
+                Some(BUS_NAME),
+                PORTAL_PATH,
+                Some(PORTAL_INTERFACE),
+                method,
+                body,
+            )
+            .await
+            .unwrap()
+    }
+
+    fn session_id(&self) -> SessionId {
+        SessionId::new(self.session.as_str())
+    }
+
+    /// Sends absolute then relative motion, returning the first event that
+    /// reaches the compositor.
+    async fn move_pointer(&mut self) -> InputEvent {
+        let options: HashMap<&str, Value<'_>> = HashMap::new();
+        self.call(
+            "NotifyPointerMotionAbsolute",
+            &(&self.session, &options, 0u32, 100.0f64, 50.0f64),
+        )
+        .await;
+        self.call(
+            "NotifyPointerMotion",
+            &(&self.session, &options, 4.0f64, -2.0f64),
+        )
+        .await;
+        self.next_event().await
+    }
+
+    async fn next_event(&mut self) -> InputEvent {
+        match self.next_message().await {
+            CompositorMessage::Event(_, event) => event.event,
+            other => panic!("expected an event, got {other:?}"),
+        }
+    }
+
+    async fn next_message(&mut self) -> CompositorMessage {
+        tokio::time::timeout(TIMEOUT, self.compositor.recv())
+            .await
+            .expect("message reached the compositor")
+            .expect("event forwarding still running")
+    }
+}
+
+#[tokio::test]
+async fn lock_pointer_switches_to_relative_only() {
+    let mut fixture = Fixture::start("lock").await;
+    let proxy = zbus::Proxy::new(&fixture.client, BUS_NAME, PORTAL_PATH, PORTAL_INTERFACE)
+        .await
+        .unwrap();
+    let mut changes = proxy.receive_signal("PointerLockChanged").await.unwrap();
+
+    // Lock: the compositor constrains the pointer and the client is told
+    fixture.call("LockPointer", &(&fixture.session,)).await;
+    assert_eq!(
+        fixture.next_message().await,
+        CompositorMessage::PointerLock(fixture.session_id(), true)
+    );
+    assert!(!fixture.backend.is_pointer_locked(&fixture.session_id()));
+    let signal = tokio::time::timeout(TIMEOUT, changes.next())
+        .await
+        .expect("PointerLockChanged should be emitted")
+        .unwrap();
+    let body = signal.body();
+    let (path, locked): (ObjectPath<'_>, bool) = body.deserialize().unwrap();
+    assert_eq!(path, fixture.session);
+    assert!(locked);
+
+    // Absolute motion is ignored, relative deltas still apply
+    assert_eq!(
+        fixture.move_pointer().await,
+        InputEvent::PointerMotion { dx: 4.0, dy: -2.0 }
+    );
+
+    // Unlock: absolute motion reaches the compositor again
+    fixture.call("UnlockPointer", &(&fixture.session,)).await;
+    assert_eq!(
+        fixture.next_message().await,
+        CompositorMessage::PointerLock(fixture.session_id(), false)
+    );
+    let signal = tokio::time::timeout(TIMEOUT, changes.next())
+        .await
+        .expect("PointerLockChanged should be emitted")
+        .unwrap();
+    let (_, locked): (ObjectPath<'_>, bool) = signal.body().deserialize().unwrap();
+    assert!(!locked);
+
+    assert_eq!(
+        fixture.move_pointer().await,
+        InputEvent::PointerMotionAbsolute {
+            stream: 0,
+            x: 100.0,
+            y: 50.0
+        }
+    );
+    assert_eq!(
+        fixture.next_event().await,
+        InputEvent::PointerMotion { dx: 4.0, dy: -2.0 }
+    );
+
+    fixture.service.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn closing_session_releases_pointer_lock() {
+    let mut fixture = Fixture::start("close").await;
+    let session_id = fixture.session_id();
+
+    fixture.call("LockPointer", &(&fixture.session,)).await;
+    assert_eq!(
+        fixture.next_message().await,
+        CompositorMessage::PointerLock(session_id.clone(), true)
+    );
+
+    fixture
+        .service
+        .session_manager()
+        .close_session(&session_id)
+        .await;
+    assert_eq!(
+        fixture.next_message().await,
+        CompositorMessage::PointerLock(session_id, false)
+    );
+
+    fixture.service.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn without_transport_the_backend_holds_the_lock() {
+    let fixture = Fixture::without_transport("backend").await;
+    let session_id = fixture.session_id();
+
+    fixture.call("LockPointer", &(&fixture.session,)).await;
+    assert!(fixture.backend.is_pointer_locked(&session_id));
+
+    fixture
+        .service
+        .session_manager()
+        .close_session(&session_id)
+        .await;
+
+    // Released off the close path, so give it a moment
+    tokio::time::timeout(TIMEOUT, async {
+        while fixture.backend.is_pointer_locked(&session_id) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("pointer lock released on close");
+
+    fixture.service.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn losing_input_releases_the_pointer_lock() {
+    let fixture = Fixture::without_transport("degraded").await;
+    let session_id = fixture.session_id();
+
+    fixture.call("LockPointer", &(&fixture.session,)).await;
+    assert!(fixture.backend.is_pointer_locked(&session_id));
+
+    // The backend can no longer inject, so the session drops to view-only
+    let portal = fixture.service.portal().await.unwrap();
+    let mode = portal
+        .get_mut()
+        .await
+        .apply_capabilities(&BackendCapabilities {
+            can_inject_keyboard: false,
+            can_inject_pointer: false,
+            ..fixture.backend.capabilities()
+        })
+        .await;
+    assert!(!mode.has_input());
+    assert!(!fixture.backend.is_pointer_locked(&session_id));
+
+    // Unlocking what is already released still succeeds
+    fixture.call("UnlockPointer", &(&fixture.session,)).await;
+
+    fixture.service.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn cosmic_backend_locks_the_compositor_pointer() {
+    let bus = MockBus::spawn().await.unwrap();
+    let (handler, input) = VirtualInput::with_defaults();
+    let service = RemoteDesktopService::new(input);
+    let comp = bus.connect().await.unwrap();
+    comp.object_server()
+        .at(COSMIC_COMP_PATH, service.clone())
+        .await
+        .unwrap();
+    comp.request_name(COSMIC_COMP_SERVICE).await.unwrap();
+
+    let mut backend = CosmicBackend::new();
+    backend
+        .connect_with(bus.connect().await.unwrap())
+        .await
+        .unwrap();
+    let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+    let portal = RemoteDesktopPortal::with_backend(manager.clone(), Arc::new(backend));
+    let server = bus.serve_on_runtime(PORTAL_PATH, portal).await.unwrap();
+
+    let session = ObjectPath::try_from(format!("{PORTAL_PATH}/session/cosmic")).unwrap();
+    let session_id = SessionId::new(session.as_str());
+    let started = manager
+        .create_session(session_id.clone(), APP_ID.into())
+        .await
+        .unwrap();
+    started.select_devices(DeviceType::POINTER).await.unwrap();
+    started.start().await.unwrap();
+    service
+        .register_session(session.as_str(), DeviceType::POINTER)
+        .await;
+
+    let client = bus.connect().await.unwrap();
+    for (method, locked) in [("LockPointer", true), ("UnlockPointer", false)] {
+        client
+            .call_method(
+                server.unique_name(),
+                PORTAL_PATH,
+                Some(PORTAL_INTERFACE),
+                method,
+                &(&session,),
+            )
+            .await
+            .unwrap();
+        assert_eq!(handler.is_pointer_locked(&session_id), locked, "{method}");
+    }
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Pointer transforms requested at session start, end to end.
+//!
+//! A remote trackpad starts its session with a sensitivity and
+//! acceleration profile. The compositor's input handler is the one place
+//! the transform applies, so these tests check that the request reaches
+//! it, over the transport or through the COSMIC backend, and that the
+//! portal forwards motion as the client sent it.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ion_backend_cosmic::{CosmicBackend, COSMIC_COMP_PATH, COSMIC_COMP_SERVICE};
+use ion_compositor::{RemoteDesktopService, VirtualInput};
+use ion_core::backend::MockBackend;
+use ion_core::device::DeviceType;
+use ion_core::event::{AccelProfile, InputEvent, PointerTransform};
+use ion_core::session::SessionId;
+use ion_portal::portal::{RemoteDesktopPortal, ResponseCode};
+use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
+use ion_portal::transport::{CompositorMessage, CompositorTransport, InProcessTransport};
+use ion_portal_service::{run_service, ServiceConfig, PORTAL_PATH};
+use ion_test_substrate::mock_bus::MockBus;
+use tokio::sync::mpsc;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+const PORTAL_INTERFACE: &str = "org.freedesktop.impl.portal.RemoteDesktop";
+const BUS_NAME: &str = "org.ionchannel.test.PointerTransform";
+const APP_ID: &str = "org.ionchannel.PointerTransform";
+
+/// How long to wait for a message to reach the compositor.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `Start` options asking for double sensitivity with acceleration.
+fn trackpad_options() -> HashMap<&'static str, Value<'static>> {
+    HashMap::from([
+        (PointerTransform::SENSITIVITY_KEY, Value::from(2.0f64)),
+        (AccelProfile::OPTION_KEY, Value::from(1u32)),
+    ])
+}
+
+fn trackpad_transform() -> PointerTransform {
+    PointerTransform::with_sensitivity(2.0).with_accel_profile(AccelProfile::Adaptive)
+}
+
+/// Calls a request method on `destination` and returns its response code.
+async fn request<B>(client: &zbus::Connection, destination: &str, method: &str, body: &B) -> u32
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    let reply = client
+        .call_method(
+            Some(destination),
+            PORTAL_PATH,
+            Some(PORTAL_INTERFACE),
+            method,
+            body,
+        )
+        .await
+        .unwrap();
+    let (code, _): (u32, HashMap<String, OwnedValue>) = reply.body().deserialize().unwrap();
+    code
+}
+
+/// Waits for the next message to reach the compositor.
+async fn next_message(compositor: &mut mpsc::Receiver<CompositorMessage>) -> CompositorMessage {
+    tokio::time::timeout(TIMEOUT, compositor.recv())
+        .await
+        .expect("message reached the compositor")
+        .expect("event forwarding still running")
+}
+
+#[tokio::test]
+async fn start_option_reaches_the_compositor_ahead_of_motion() {
+    let bus = MockBus::spawn().await.unwrap();
+    let (compositor_tx, mut compositor) = mpsc::channel(16);
+    let service = run_service(ServiceConfig {
+        backend: Some(Arc::new(MockBackend::new())),
+        transport: Some(
+            Arc::new(InProcessTransport::new(compositor_tx)) as Arc<dyn CompositorTransport>
+        ),
+        connection: Some(bus.connect_on_runtime().await.unwrap()),
+        bus_name: BUS_NAME.to_string(),
+        ..ServiceConfig::default()
+    })
+    .await
+    .unwrap();
+    let client = bus.connect().await.unwrap();
+
+    let request_path = ObjectPath::try_from(format!("{PORTAL_PATH}/request/trackpad")).unwrap();
+    let session = ObjectPath::try_from(format!("{PORTAL_PATH}/session/trackpad")).unwrap();
+    let none: HashMap<&str, Value<'_>> = HashMap::new();
+    for method in ["CreateSession", "SelectDevices"] {
+        let code = request(
+            &client,
+            BUS_NAME,
+            method,
+            &(&request_path, &session, APP_ID, &none),
+        )
+        .await;
+        assert_eq!(code, ResponseCode::Success as u32, "{method}");
+    }
+    let code = request(
+        &client,
+        BUS_NAME,
+        "Start",
+        &(&request_path, &session, APP_ID, "", &trackpad_options()),
+    )
+    .await;
+    assert_eq!(code, ResponseCode::Success as u32, "Start");
+
+    client
+        .call_method(
+            Some(BUS_NAME),
+            PORTAL_PATH,
+            Some(PORTAL_INTERFACE),
+            "NotifyPointerMotion",
+            &(&session, &none, 4.0f64, -2.0f64),
+        )
+        .await
+        .unwrap();
+
+    let session_id = SessionId::new(session.as_str());
+    assert_eq!(
+        next_message(&mut compositor).await,
+        CompositorMessage::PointerTransform(session_id.clone(), trackpad_transform())
+    );
+    // Left for the compositor to transform, once
+    match next_message(&mut compositor).await {
+        CompositorMessage::Event(id, event) => {
+            assert_eq!(id, session_id);
+            assert_eq!(event.event, InputEvent::pointer_motion(4.0, -2.0));
+        },
+        other => panic!("expected motion, got {other:?}"),
+    }
+
+    service.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn cosmic_backend_sets_the_compositor_transform() {
+    let bus = MockBus::spawn().await.unwrap();
+    let (handler, input) = VirtualInput::with_defaults();
+    let service = RemoteDesktopService::new(input);
+    let comp = bus.connect().await.unwrap();
+    comp.object_server()
+        .at(COSMIC_COMP_PATH, service.clone())
+        .await
+        .unwrap();
+    comp.request_name(COSMIC_COMP_SERVICE).await.unwrap();
+
+    let mut backend = CosmicBackend::new();
+    backend
+        .connect_with(bus.connect().await.unwrap())
+        .await
+        .unwrap();
+    let (manager, _rx) = SessionManager::new(SessionManagerConfig::default());
+    let portal = RemoteDesktopPortal::with_backend(manager.clone(), Arc::new(backend));
+    let server = bus.serve_on_runtime(PORTAL_PATH, portal).await.unwrap();
+    let portal_name = server.unique_name().unwrap().to_string();
+
+    let session = ObjectPath::try_from(format!("{PORTAL_PATH}/session/cosmic")).unwrap();
+    let session_id = SessionId::new(session.as_str());
+    let created = manager
+        .create_session(session_id.clone(), APP_ID.into())
+        .await
+        .unwrap();
+    created.select_devices(DeviceType::POINTER).await.unwrap();
+    service
+        .register_session(session.as_str(), DeviceType::POINTER)
+        .await;
+
+    let client = bus.connect().await.unwrap();
+    let request_path = ObjectPath::try_from(format!("{PORTAL_PATH}/request/cosmic")).unwrap();
+    let code = request(
+        &client,
+        &portal_name,
+        "Start",
+        &(&request_path, &session, APP_ID, "", &trackpad_options()),
+    )
+    .await;
+    assert_eq!(code, ResponseCode::Success as u32);
+    assert_eq!(handler.pointer_transform(&session_id), trackpad_transform());
+}
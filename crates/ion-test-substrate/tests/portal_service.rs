@@ -0,0 +1,468 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! The assembled portal service, end to end.
+//!
+//! Unit tests cover the portal, the session manager and the transports on
+//! their own. These tests boot the service the way the binary does, with
+//! a mock backend, a compositor transport and a private bus,
+//! and drive it as a client would, so a wiring mistake between the pieces
+//! shows up here.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use ion_core::backend::{ColorSpace, MockBackend, OutputInfo, OutputTransform};
+use ion_core::event::{InputEvent, KeyState, StampedEvent};
+use ion_core::session::SessionId;
+use ion_portal::portal::ResponseCode;
+use ion_portal::session_manager::{SessionManagerConfig, IDLE_REASON};
+use ion_portal::transport::{
+    serve_events, CompositorMessage, InProcessTransport, UnixSocketTransport,
+};
+use ion_portal_service::{run_service, ServiceConfig, PORTAL_PATH};
+use ion_test_substrate::mock_bus::MockBus;
+use tokio::sync::mpsc;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+const PORTAL_INTERFACE: &str = "org.freedesktop.impl.portal.RemoteDesktop";
+const BUS_NAME: &str = "org.ionchannel.test.PortalService";
+const APP_ID: &str = "org.ionchannel.PortalService";
+const KEY_A: i32 = 30;
+
+/// How long to wait for an event to reach the compositor.
+const EVENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Calls `method` on the service by its bus name.
+async fn call<B>(client: &zbus::Connection, method: &str, body: &B) -> zbus::Message
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    client
+        .call_method(
+            Some(BUS_NAME),
+            PORTAL_PATH,
+            Some(PORTAL_INTERFACE),
+            method,
+            body,
+        )
+        .await
+        .unwrap()
+}
+
+/// Calls a request method and returns its response code and results.
+async fn request<B>(
+    client: &zbus::Connection,
+    method: &str,
+    body: &B,
+) -> (u32, HashMap<String, OwnedValue>)
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    call(client, method, body)
+        .await
+        .body()
+        .deserialize()
+        .unwrap()
+}
+
+async fn next_event(
+    compositor: &mut mpsc::Receiver<CompositorMessage>,
+) -> (SessionId, StampedEvent) {
+    let message = tokio::time::timeout(EVENT_TIMEOUT, compositor.recv())
+        .await
+        .expect("event reached the compositor")
+        .expect("event forwarding still running");
+    match message {
+        CompositorMessage::Event(session, event) => (session, event),
+        other => panic!("expected an event, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn session_cycle_through_assembled_service() {
+    let bus = MockBus::spawn().await.unwrap();
+    let backend = Arc::new(MockBackend::new());
+    let (compositor_tx, mut compositor) = mpsc::channel(16);
+
+    let service = run_service(ServiceConfig {
+        backend: Some(backend.clone()),
+        transport: Some(Arc::new(InProcessTransport::new(compositor_tx))),
+        connection: Some(bus.connect_on_runtime().await.unwrap()),
+        bus_name: BUS_NAME.to_string(),
+        ..ServiceConfig::default()
+    })
+    .await
+    .unwrap();
+    let client = bus.connect().await.unwrap();
+
+    let request_path = ObjectPath::try_from(format!("{PORTAL_PATH}/request/cycle")).unwrap();
+    let session_path = ObjectPath::try_from(format!("{PORTAL_PATH}/session/cycle")).unwrap();
+    let options: HashMap<&str, Value<'_>> = HashMap::new();
+
+    // Create → select → start
+    let (code, _) = request(
+        &client,
+        "CreateSession",
+        &(&request_path, &session_path, APP_ID, &options),
+    )
+    .await;
+    assert_eq!(code, ResponseCode::Success as u32);
+    let (code, _) = request(
+        &client,
+        "SelectDevices",
+        &(&request_path, &session_path, APP_ID, &options),
+    )
+    .await;
+    assert_eq!(code, ResponseCode::Success as u32);
+    let (code, results) = request(
+        &client,
+        "Start",
+        &(&request_path, &session_path, APP_ID, "", &options),
+    )
+    .await;
+    assert_eq!(code, ResponseCode::Success as u32, "{results:?}");
+    assert!(results.contains_key("streams"), "{results:?}");
+    assert_eq!(service.session_manager().session_count().await, 1);
+
+    // Inject: the key press reaches the compositor transport, still
+    // carrying when the client sent it
+    let sent_at = 1_700_000_000_000_000u64;
+    let stamped = HashMap::from([(InputEvent::CLIENT_TIMESTAMP_KEY, Value::from(sent_at))]);
+    call(
+        &client,
+        "NotifyKeyboardKeycode",
+        &(&session_path, &stamped, KEY_A, 1u32),
+    )
+    .await;
+    let (session, event) = next_event(&mut compositor).await;
+    assert_eq!(session.as_str(), session_path.as_str());
+    assert_eq!(event.client_timestamp, Some(sent_at));
+    assert!(matches!(
+        event.event,
+        InputEvent::KeyboardKeycode {
+            keycode: KEY_A,
+            state: KeyState::Pressed
+        }
+    ));
+
+    // Close: shutdown ends the session and releases what it held
+    let manager = service.session_manager().clone();
+    service.shutdown().await.unwrap();
+
+    assert_eq!(manager.session_count().await, 0);
+    let (_, release) = next_event(&mut compositor).await;
+    assert_eq!(release.client_timestamp, None);
+    assert!(matches!(
+        release.event,
+        InputEvent::KeyboardKeycode {
+            keycode: KEY_A,
+            state: KeyState::Released
+        }
+    ));
+    assert_eq!(backend.active_captures(), 0);
+
+    let dbus = zbus::fdo::DBusProxy::new(&client).await.unwrap();
+    let owned = dbus
+        .name_has_owner(BUS_NAME.try_into().unwrap())
+        .await
+        .unwrap();
+    assert!(!owned);
+}
+
+#[tokio::test]
+async fn sessions_close_when_owner_leaves_bus() {
+    let bus = MockBus::spawn().await.unwrap();
+    let service = run_service(ServiceConfig {
+        backend: Some(Arc::new(MockBackend::new())),
+        connection: Some(bus.connect_on_runtime().await.unwrap()),
+        bus_name: BUS_NAME.to_string(),
+        ..ServiceConfig::default()
+    })
+    .await
+    .unwrap();
+    let client = bus.connect().await.unwrap();
+
+    let request_path = ObjectPath::try_from(format!("{PORTAL_PATH}/request/owner")).unwrap();
+    let session_path = ObjectPath::try_from(format!("{PORTAL_PATH}/session/owner")).unwrap();
+    let options: HashMap<&str, Value<'_>> = HashMap::new();
+    let (code, _) = request(
+        &client,
+        "CreateSession",
+        &(&request_path, &session_path, APP_ID, &options),
+    )
+    .await;
+    assert_eq!(code, ResponseCode::Success as u32);
+
+    // The creating connection owns the session
+    let manager = service.session_manager().clone();
+    let session_id = SessionId::new(session_path.as_str());
+    let owner = client.unique_name().unwrap().to_string();
+    assert_eq!(manager.owner_of(&session_id).await, Some(owner));
+
+    // Leaving the bus closes it
+    client.close().await.unwrap();
+    tokio::time::timeout(EVENT_TIMEOUT, async {
+        while manager.session_count().await > 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("session closed once its owner left");
+
+    service.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn sessions_follow_a_replaced_well_known_name() {
+    const FRONTEND: &str = "org.ionchannel.test.Frontend";
+    let bus = MockBus::spawn().await.unwrap();
+    let service = run_service(ServiceConfig {
+        backend: Some(Arc::new(MockBackend::new())),
+        connection: Some(bus.connect_on_runtime().await.unwrap()),
+        bus_name: BUS_NAME.to_string(),
+        ..ServiceConfig::default()
+    })
+    .await
+    .unwrap();
+    let manager = service.session_manager().clone();
+
+    let old = bus.connect().await.unwrap();
+    old.request_name_with_flags(
+        FRONTEND,
+        zbus::fdo::RequestNameFlags::AllowReplacement.into(),
+    )
+    .await
+    .unwrap();
+    let request_path = ObjectPath::try_from(format!("{PORTAL_PATH}/request/handoff")).unwrap();
+    let session_path = ObjectPath::try_from(format!("{PORTAL_PATH}/session/handoff")).unwrap();
+    let options: HashMap<&str, Value<'_>> = HashMap::new();
+    let (code, _) = request(
+        &old,
+        "CreateSession",
+        &(&request_path, &session_path, APP_ID, &options),
+    )
+    .await;
+    assert_eq!(code, ResponseCode::Success as u32);
+
+    // A replacement takes over the frontend's name, then the old one leaves
+    let new = bus.connect().await.unwrap();
+    new.request_name_with_flags(
+        FRONTEND,
+        zbus::fdo::RequestNameFlags::ReplaceExisting.into(),
+    )
+    .await
+    .unwrap();
+    let session_id = SessionId::new(session_path.as_str());
+    let new_owner = new.unique_name().unwrap().to_string();
+    tokio::time::timeout(EVENT_TIMEOUT, async {
+        while manager.owner_of(&session_id).await.as_ref() != Some(&new_owner) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("session moved to the new frontend");
+
+    old.close().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(manager.session_count().await, 1);
+
+    service.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn idle_sessions_are_reaped_and_reported() {
+    let bus = MockBus::spawn().await.unwrap();
+    let service = run_service(ServiceConfig {
+        backend: Some(Arc::new(MockBackend::new())),
+        connection: Some(bus.connect_on_runtime().await.unwrap()),
+        bus_name: BUS_NAME.to_string(),
+        sessions: SessionManagerConfig {
+            idle_timeout: Some(Duration::from_millis(100)),
+            ..SessionManagerConfig::default()
+        },
+        ..ServiceConfig::default()
+    })
+    .await
+    .unwrap();
+    let client = bus.connect().await.unwrap();
+    let portal = zbus::Proxy::new(&client, BUS_NAME, PORTAL_PATH, PORTAL_INTERFACE)
+        .await
+        .unwrap();
+    let mut closed = portal.receive_signal("SessionClosed").await.unwrap();
+
+    let request_path = ObjectPath::try_from(format!("{PORTAL_PATH}/request/idle")).unwrap();
+    let session_path = ObjectPath::try_from(format!("{PORTAL_PATH}/session/idle")).unwrap();
+    let options: HashMap<&str, Value<'_>> = HashMap::new();
+    let (code, _) = request(
+        &client,
+        "CreateSession",
+        &(&request_path, &session_path, APP_ID, &options),
+    )
+    .await;
+    assert_eq!(code, ResponseCode::Success as u32);
+
+    // Without input the service closes the session and tells the client
+    let signal = tokio::time::timeout(EVENT_TIMEOUT, closed.next())
+        .await
+        .expect("idle session reaped")
+        .unwrap();
+    let (path, reason): (OwnedObjectPath, String) = signal.body().deserialize().unwrap();
+    assert_eq!(path.as_str(), session_path.as_str());
+    assert_eq!(reason, IDLE_REASON);
+    assert_eq!(service.session_manager().session_count().await, 0);
+
+    service.shutdown().await.unwrap();
+}
+
+/// Starts a session on a service whose backend has one 64x48 output, and
+/// sends absolute motion at `(x, y)` on it.
+///
+/// Returns the call's error name, or the event that reached the
+/// compositor.
+async fn absolute_motion(clamp_absolute: bool, x: f64, y: f64) -> Result<InputEvent, String> {
+    let bus = MockBus::spawn().await.unwrap();
+    let backend = Arc::new(MockBackend::new().with_outputs(vec![OutputInfo {
+        stream: 0,
+        name: "DP-1".to_string(),
+        width: 64,
+        height: 48,
+        color_space: ColorSpace::Srgb,
+        hdr: false,
+        transform: OutputTransform::Normal,
+    }]));
+    let (compositor_tx, mut compositor) = mpsc::channel(16);
+    let service = run_service(ServiceConfig {
+        backend: Some(backend),
+        transport: Some(Arc::new(InProcessTransport::new(compositor_tx))),
+        connection: Some(bus.connect_on_runtime().await.unwrap()),
+        bus_name: BUS_NAME.to_string(),
+        clamp_absolute,
+        ..ServiceConfig::default()
+    })
+    .await
+    .unwrap();
+    let client = bus.connect().await.unwrap();
+
+    let request_path = ObjectPath::try_from(format!("{PORTAL_PATH}/request/bounds")).unwrap();
+    let session_path = ObjectPath::try_from(format!("{PORTAL_PATH}/session/bounds")).unwrap();
+    let options: HashMap<&str, Value<'_>> = HashMap::new();
+    for method in ["CreateSession", "SelectDevices"] {
+        let (code, _) = request(
+            &client,
+            method,
+            &(&request_path, &session_path, APP_ID, &options),
+        )
+        .await;
+        assert_eq!(code, ResponseCode::Success as u32, "{method}");
+    }
+    let (code, _) = request(
+        &client,
+        "Start",
+        &(&request_path, &session_path, APP_ID, "", &options),
+    )
+    .await;
+    assert_eq!(code, ResponseCode::Success as u32);
+
+    let reply = client
+        .call_method(
+            Some(BUS_NAME),
+            PORTAL_PATH,
+            Some(PORTAL_INTERFACE),
+            "NotifyPointerMotionAbsolute",
+            &(&session_path, &options, 0u32, x, y),
+        )
+        .await;
+    let outcome = match reply {
+        Ok(_) => Ok(next_event(&mut compositor).await.1.event),
+        Err(zbus::Error::MethodError(name, _, _)) => Err(name.to_string()),
+        Err(e) => panic!("unexpected error: {e}"),
+    };
+    service.shutdown().await.unwrap();
+    outcome
+}
+
+#[tokio::test]
+async fn absolute_motion_is_bounded_by_output() {
+    // Inside the output: forwarded unchanged
+    assert_eq!(
+        absolute_motion(false, 10.0, 20.0).await,
+        Ok(InputEvent::PointerMotionAbsolute {
+            stream: 0,
+            x: 10.0,
+            y: 20.0
+        })
+    );
+
+    // Outside: rejected by default, clamped when configured
+    assert_eq!(
+        absolute_motion(false, 100.0, 20.0).await,
+        Err("org.freedesktop.DBus.Error.Failed".to_string())
+    );
+    assert_eq!(
+        absolute_motion(true, 100.0, -5.0).await,
+        Ok(InputEvent::PointerMotionAbsolute {
+            stream: 0,
+            x: 64.0,
+            y: 0.0
+        })
+    );
+}
+
+#[tokio::test]
+async fn input_reaches_a_compositor_service_over_its_socket() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("compositor.sock");
+    let (compositor_tx, mut compositor) = mpsc::channel(16);
+    let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+    let _compositor = tokio::spawn(serve_events(listener, compositor_tx));
+
+    let bus = MockBus::spawn().await.unwrap();
+    let service = run_service(ServiceConfig {
+        backend: Some(Arc::new(MockBackend::new())),
+        transport: Some(Arc::new(UnixSocketTransport::new(&socket))),
+        connection: Some(bus.connect_on_runtime().await.unwrap()),
+        bus_name: BUS_NAME.to_string(),
+        ..ServiceConfig::default()
+    })
+    .await
+    .unwrap();
+    let client = bus.connect().await.unwrap();
+
+    let request_path = ObjectPath::try_from(format!("{PORTAL_PATH}/request/socket")).unwrap();
+    let session_path = ObjectPath::try_from(format!("{PORTAL_PATH}/session/socket")).unwrap();
+    let options: HashMap<&str, Value<'_>> = HashMap::new();
+    for method in ["CreateSession", "SelectDevices"] {
+        let (code, _) = request(
+            &client,
+            method,
+            &(&request_path, &session_path, APP_ID, &options),
+        )
+        .await;
+        assert_eq!(code, ResponseCode::Success as u32, "{method}");
+    }
+    let (code, _) = request(
+        &client,
+        "Start",
+        &(&request_path, &session_path, APP_ID, "", &options),
+    )
+    .await;
+    assert_eq!(code, ResponseCode::Success as u32);
+
+    call(
+        &client,
+        "NotifyKeyboardKeycode",
+        &(&session_path, &options, KEY_A, 1u32),
+    )
+    .await;
+    let (session, event) = next_event(&mut compositor).await;
+    assert_eq!(session.as_str(), session_path.as_str());
+    assert_eq!(event.event, InputEvent::key(KEY_A, KeyState::Pressed));
+
+    // Closing the session releases the key on the compositor side too
+    service.shutdown().await.unwrap();
+    let (_, release) = next_event(&mut compositor).await;
+    assert_eq!(release.event, InputEvent::key(KEY_A, KeyState::Released));
+}
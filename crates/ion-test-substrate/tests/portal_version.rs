@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright © 2024-2025 DataScienceBioLab
+
+//! Portals pinned to an older interface version, over D-Bus.
+//!
+//! A portal pinned for clients that mishandle newer versions reports the
+//! pinned version and refuses the methods introduced after it, even when
+//! the build implements them.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ion_core::device::DeviceType;
+use ion_core::event::StampedEvent;
+use ion_core::session::SessionId;
+use ion_portal::clipboard::{Clipboard, MemoryClipboard};
+use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
+use ion_portal::version::{CLIPBOARD_VERSION, GESTURE_VERSION};
+use ion_portal::RemoteDesktopPortal;
+use ion_portal_service::PORTAL_PATH;
+use ion_test_substrate::mock_bus::MockBus;
+use tokio::sync::mpsc;
+use zbus::zvariant::{ObjectPath, Value};
+
+const PORTAL_INTERFACE: &str = "org.freedesktop.impl.portal.RemoteDesktop";
+const APP_ID: &str = "org.ionchannel.PortalVersion";
+
+/// A pinned portal on a private bus with a started session.
+struct Pinned {
+    _bus: MockBus,
+    _events: mpsc::Receiver<(SessionId, StampedEvent)>,
+    client: zbus::Connection,
+    name: String,
+    session: ObjectPath<'static>,
+}
+
+impl Pinned {
+    async fn call<B>(&self, method: &str, body: &B) -> zbus::Result<zbus::Message>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        self.client
+            .call_method(
+                Some(self.name.as_str()),
+                PORTAL_PATH,
+                Some(PORTAL_INTERFACE),
+                method,
+                body,
+            )
+            .await
+    }
+
+    async fn reported_version(&self) -> u32 {
+        zbus::Proxy::new(
+            &self.client,
+            self.name.clone(),
+            PORTAL_PATH,
+            PORTAL_INTERFACE,
+        )
+        .await
+        .unwrap()
+        .get_property("version")
+        .await
+        .unwrap()
+    }
+
+    async fn hold_gesture(&self) -> zbus::Result<zbus::Message> {
+        let none: HashMap<&str, Value<'_>> = HashMap::new();
+        self.call("NotifyGestureHold", &(&self.session, &none, 0u32, 3u32))
+            .await
+    }
+}
+
+/// Serves a portal pinned to `version` with a started session.
+async fn pinned_portal(version: u32, clipboard: Arc<MemoryClipboard>) -> Pinned {
+    let bus = MockBus::spawn().await.unwrap();
+    let (manager, events) = SessionManager::new(SessionManagerConfig::default());
+    let portal = RemoteDesktopPortal::new(manager.clone())
+        .with_clipboard(clipboard as Arc<dyn Clipboard>)
+        .with_version(version);
+    let server = bus.serve_on_runtime(PORTAL_PATH, portal).await.unwrap();
+    let name = server.unique_name().unwrap().to_string();
+
+    let session = ObjectPath::try_from(format!("{PORTAL_PATH}/session/pinned")).unwrap();
+    let started = manager
+        .create_session(SessionId::new(session.as_str()), APP_ID.into())
+        .await
+        .unwrap();
+    started
+        .select_devices(DeviceType::desktop_standard() | DeviceType::GESTURE)
+        .await
+        .unwrap();
+    started.start().await.unwrap();
+
+    let client = bus.connect().await.unwrap();
+    Pinned {
+        _bus: bus,
+        _events: events,
+        client,
+        name,
+        session,
+    }
+}
+
+fn is_not_supported(result: &zbus::Result<zbus::Message>) -> bool {
+    matches!(
+        result,
+        Err(zbus::Error::MethodError(name, _, _))
+            if name.as_str() == "org.freedesktop.DBus.Error.NotSupported"
+    )
+}
+
+#[tokio::test]
+async fn portal_pinned_below_clipboard_rejects_set_selection() {
+    let clipboard = Arc::new(MemoryClipboard::default());
+    let portal = pinned_portal(CLIPBOARD_VERSION - 1, Arc::clone(&clipboard)).await;
+    assert_eq!(portal.reported_version().await, 2);
+
+    let result = portal
+        .call(
+            "SetSelection",
+            &(&portal.session, "text/plain", b"hello".to_vec()),
+        )
+        .await;
+    assert!(is_not_supported(&result), "{result:?}");
+    assert!(clipboard.selection().is_none());
+
+    // Gestures came before the pinned version and still work
+    portal.hold_gesture().await.unwrap();
+}
+
+#[tokio::test]
+async fn portal_pinned_below_gestures_rejects_them() {
+    let portal = pinned_portal(GESTURE_VERSION - 1, Arc::default()).await;
+    assert_eq!(portal.reported_version().await, 1);
+
+    let result = portal.hold_gesture().await;
+    assert!(is_not_supported(&result), "{result:?}");
+}
@@ -21,7 +21,7 @@
 use std::time::Duration;
 
 use ion_core::device::DeviceType;
-use ion_core::event::{ButtonState, InputEvent, KeyState};
+use ion_core::event::{ButtonState, KeyState, StampedEvent};
 use ion_core::mode::RemoteDesktopMode;
 use ion_portal::core::{PortalCore, SelectDevicesRequest, StartSessionRequest};
 use ion_portal::session_manager::{SessionManager, SessionManagerConfig};
@@ -33,9 +33,9 @@ const RECV_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Helper to receive events with generous timeout
 async fn recv_n_events(
-    rx: &mut mpsc::Receiver<(ion_core::session::SessionId, InputEvent)>,
+    rx: &mut mpsc::Receiver<(ion_core::session::SessionId, StampedEvent)>,
     n: usize,
-) -> Vec<(ion_core::session::SessionId, InputEvent)> {
+) -> Vec<(ion_core::session::SessionId, StampedEvent)> {
     let mut events = Vec::with_capacity(n);
     for _ in 0..n {
         let event = tokio::time::timeout(RECV_TIMEOUT, rx.recv())
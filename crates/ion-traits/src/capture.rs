@@ -103,6 +103,8 @@ pub enum FrameFormat {
     Rgb888,
     /// Blue, Green, Red (8 bits each, no alpha)
     Bgr888,
+    /// X (unused), Red, Green, Blue (10 bits each, HDR)
+    Xrgb2101010,
     /// NV12 (YUV 4:2:0 semi-planar)
     Nv12,
     /// YUY2 (YUV 4:2:2 packed)
@@ -114,7 +116,11 @@ impl FrameFormat {
     #[must_use]
     pub const fn bytes_per_pixel(&self) -> usize {
         match self {
-            Self::Bgra8888 | Self::Rgba8888 | Self::Xrgb8888 | Self::Xbgr8888 => 4,
+            Self::Bgra8888
+            | Self::Rgba8888
+            | Self::Xrgb8888
+            | Self::Xbgr8888
+            | Self::Xrgb2101010 => 4,
             Self::Rgb888 | Self::Bgr888 => 3,
             Self::Nv12 => 1, // Average, actual is 1.5
             Self::Yuy2 => 2,
@@ -143,6 +149,7 @@ impl std::fmt::Display for FrameFormat {
             Self::Xbgr8888 => "XBGR8888",
             Self::Rgb888 => "RGB888",
             Self::Bgr888 => "BGR888",
+            Self::Xrgb2101010 => "XRGB2101010",
             Self::Nv12 => "NV12",
             Self::Yuy2 => "YUY2",
         };
@@ -150,6 +157,117 @@ impl std::fmt::Display for FrameFormat {
     }
 }
 
+/// How an output's panel is rotated or flipped (`wl_output.transform`).
+///
+/// Rotations are counter-clockwise. A capture of the panel's native
+/// orientation comes out sideways or mirrored unless the transform is
+/// applied; see [`TransformHandling`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OutputTransform {
+    /// No transform
+    #[default]
+    Normal,
+    /// Rotated 90°
+    Rotate90,
+    /// Rotated 180°
+    Rotate180,
+    /// Rotated 270°
+    Rotate270,
+    /// Flipped around the vertical axis
+    Flipped,
+    /// Flipped, then rotated 90°
+    Flipped90,
+    /// Flipped, then rotated 180°
+    Flipped180,
+    /// Flipped, then rotated 270°
+    Flipped270,
+}
+
+impl OutputTransform {
+    /// Maps a `wl_output.transform` value; `None` for unknown values.
+    #[must_use]
+    pub const fn from_wl(value: u32) -> Option<Self> {
+        Some(match value {
+            0 => Self::Normal,
+            1 => Self::Rotate90,
+            2 => Self::Rotate180,
+            3 => Self::Rotate270,
+            4 => Self::Flipped,
+            5 => Self::Flipped90,
+            6 => Self::Flipped180,
+            7 => Self::Flipped270,
+            _ => return None,
+        })
+    }
+
+    /// Whether the transform turns the output by a quarter, so width and
+    /// height trade places.
+    #[must_use]
+    pub const fn swaps_dimensions(self) -> bool {
+        matches!(
+            self,
+            Self::Rotate90 | Self::Rotate270 | Self::Flipped90 | Self::Flipped270
+        )
+    }
+
+    /// Whether the transform mirrors the output.
+    #[must_use]
+    pub const fn is_flipped(self) -> bool {
+        matches!(
+            self,
+            Self::Flipped | Self::Flipped90 | Self::Flipped180 | Self::Flipped270
+        )
+    }
+
+    /// Size after applying the transform to a `width` by `height` buffer.
+    #[must_use]
+    pub const fn apply_to_size(self, width: u32, height: u32) -> (u32, u32) {
+        if self.swaps_dimensions() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Short name, as reported to clients.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Rotate90 => "90",
+            Self::Rotate180 => "180",
+            Self::Rotate270 => "270",
+            Self::Flipped => "flipped",
+            Self::Flipped90 => "flipped-90",
+            Self::Flipped180 => "flipped-180",
+            Self::Flipped270 => "flipped-270",
+        }
+    }
+}
+
+impl std::fmt::Display for OutputTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Who applied an output's transform to a frame.
+///
+/// Frames handled by the compositor or the CPU are delivered upright, and
+/// clients must not apply the transform again. Frames left unhandled are
+/// in the output's native orientation, and clients rotate them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TransformHandling {
+    /// Nobody applied the transform: the frame is in the output's native
+    /// orientation, which is upright only for a normal transform
+    #[default]
+    None,
+    /// The compositor rendered the frame upright
+    Compositor,
+    /// The capture backend rotated the buffer on the CPU
+    Cpu,
+}
+
 /// A captured screen frame.
 #[derive(Clone)]
 pub struct CaptureFrame {
@@ -259,6 +377,15 @@ pub struct FrameMetadata {
     pub sequence: u64,
     /// When this frame was captured
     pub captured_at: Instant,
+    /// Name of the captured output (e.g. `DP-1`), if the backend knows it.
+    ///
+    /// Unlike output indices, names stay stable across reconnects.
+    pub output_name: Option<String>,
+    /// Transform of the captured output, applied to the pixels unless
+    /// [`Self::transform_handling`] is [`TransformHandling::None`]
+    pub transform: OutputTransform,
+    /// Who applied [`Self::transform`]
+    pub transform_handling: TransformHandling,
     /// Platform-specific metadata (optional)
     pub platform_data: Option<PlatformFrameData>,
 }
@@ -294,6 +421,7 @@ mod tests {
     fn frame_format_bytes() {
         assert_eq!(FrameFormat::Bgra8888.bytes_per_pixel(), 4);
         assert_eq!(FrameFormat::Rgb888.bytes_per_pixel(), 3);
+        assert_eq!(FrameFormat::Xrgb2101010.bytes_per_pixel(), 4);
     }
 
     #[test]
@@ -311,6 +439,9 @@ mod tests {
             format: FrameFormat::Bgra8888,
             sequence: 1,
             captured_at: Instant::now(),
+            output_name: None,
+            transform: OutputTransform::Normal,
+            transform_handling: TransformHandling::None,
             platform_data: None,
         };
         let data = vec![0u8; 40000];
@@ -331,6 +462,9 @@ mod tests {
             format: FrameFormat::Bgra8888,
             sequence: 1,
             captured_at: Instant::now(),
+            output_name: None,
+            transform: OutputTransform::Normal,
+            transform_handling: TransformHandling::None,
             platform_data: None,
         };
         let frame = CaptureFrame::new(metadata, vec![0u8; 400]);
@@ -338,6 +472,22 @@ mod tests {
         assert!(frame.is_fresh(Duration::from_secs(1)));
     }
 
+    #[test]
+    fn quarter_turns_swap_dimensions() {
+        assert_eq!(OutputTransform::from_wl(1), Some(OutputTransform::Rotate90));
+        assert_eq!(OutputTransform::from_wl(8), None);
+        assert_eq!(
+            OutputTransform::Rotate90.apply_to_size(1920, 1080),
+            (1080, 1920)
+        );
+        assert_eq!(
+            OutputTransform::Flipped180.apply_to_size(1920, 1080),
+            (1920, 1080)
+        );
+        assert!(OutputTransform::Flipped270.is_flipped());
+        assert!(!OutputTransform::Rotate270.is_flipped());
+    }
+
     #[test]
     fn capture_capabilities_default() {
         let caps = CaptureCapabilities::default();
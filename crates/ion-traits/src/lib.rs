@@ -41,7 +41,10 @@ pub mod input;
 pub mod platform;
 pub mod service;
 
-pub use capture::{CaptureCapabilities, CaptureFrame, FrameFormat, ScreenCapture};
+pub use capture::{
+    CaptureCapabilities, CaptureFrame, FrameFormat, OutputTransform, ScreenCapture,
+    TransformHandling,
+};
 pub use error::{CaptureError, InputError, ServiceError};
 pub use input::{InputCapabilities, InputInjector, KeyEvent, PointerEvent, TouchEvent};
 pub use platform::Platform;
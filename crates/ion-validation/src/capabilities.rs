@@ -1,15 +1,37 @@
 //! Capability discovery system
 
 use crate::errors::{Result, ValidationError};
-use crate::providers::{desktop::RemoteDesktop, portal::PortalDeployer, vm::VmProvisioner};
+use crate::providers::{
+    clipboard::{ClipboardRoundTripProbe, CLIPBOARD_ROUNDTRIP_CAPABILITY},
+    consent::{ConsentEnforcementProbe, CONSENT_ENFORCEMENT_CAPABILITY},
+    desktop::RemoteDesktop,
+    latency::{InputLatencyProbe, INPUT_LATENCY_CAPABILITY},
+    portal::PortalDeployer,
+    vm::VmProvisioner,
+};
 use std::sync::Arc;
 use tracing::info;
 
+/// Capability provisioning a VM to validate against
+pub const VM_PROVISIONING_CAPABILITY: &str = "vm-provisioning";
+
+/// Capability installing a remote desktop client on the VM
+pub const REMOTE_DESKTOP_CAPABILITY: &str = "remote-desktop";
+
+/// Capability deploying the portal onto the VM
+pub const PORTAL_DEPLOYMENT_CAPABILITY: &str = "portal-deployment";
+
+/// Capability verifying the deployed portal end to end
+pub const E2E_VERIFICATION_CAPABILITY: &str = "e2e-verification";
+
 /// Capability registry for discovering providers
 pub struct CapabilityRegistry {
     vm_provisioners: Vec<Arc<dyn VmProvisioner>>,
     remote_desktops: Vec<Arc<dyn RemoteDesktop>>,
     portal_deployers: Vec<Arc<dyn PortalDeployer>>,
+    latency_probes: Vec<Arc<dyn InputLatencyProbe>>,
+    consent_probes: Vec<Arc<dyn ConsentEnforcementProbe>>,
+    clipboard_probes: Vec<Arc<dyn ClipboardRoundTripProbe>>,
 }
 
 impl CapabilityRegistry {
@@ -19,6 +41,9 @@ impl CapabilityRegistry {
             vm_provisioners: Vec::new(),
             remote_desktops: Vec::new(),
             portal_deployers: Vec::new(),
+            latency_probes: Vec::new(),
+            consent_probes: Vec::new(),
+            clipboard_probes: Vec::new(),
         }
     }
 
@@ -37,6 +62,43 @@ impl CapabilityRegistry {
         self.portal_deployers.push(deployer);
     }
 
+    /// Register an input latency probe
+    pub fn register_latency_probe(&mut self, probe: Arc<dyn InputLatencyProbe>) {
+        self.latency_probes.push(probe);
+    }
+
+    /// Register a consent enforcement probe
+    pub fn register_consent_probe(&mut self, probe: Arc<dyn ConsentEnforcementProbe>) {
+        self.consent_probes.push(probe);
+    }
+
+    /// Register a clipboard round-trip probe
+    pub fn register_clipboard_probe(&mut self, probe: Arc<dyn ClipboardRoundTripProbe>) {
+        self.clipboard_probes.push(probe);
+    }
+
+    /// Names of the providers registered for a capability, in discovery order
+    ///
+    /// Unlike the `discover_*` methods this does not check availability, so
+    /// it never touches real infrastructure.
+    pub fn provider_names(&self, capability: &str) -> Vec<&'static str> {
+        match capability {
+            VM_PROVISIONING_CAPABILITY => self.vm_provisioners.iter().map(|p| p.name()).collect(),
+            REMOTE_DESKTOP_CAPABILITY => self.remote_desktops.iter().map(|p| p.name()).collect(),
+            PORTAL_DEPLOYMENT_CAPABILITY | E2E_VERIFICATION_CAPABILITY => {
+                self.portal_deployers.iter().map(|p| p.name()).collect()
+            },
+            INPUT_LATENCY_CAPABILITY => self.latency_probes.iter().map(|p| p.name()).collect(),
+            CONSENT_ENFORCEMENT_CAPABILITY => {
+                self.consent_probes.iter().map(|p| p.name()).collect()
+            },
+            CLIPBOARD_ROUNDTRIP_CAPABILITY => {
+                self.clipboard_probes.iter().map(|p| p.name()).collect()
+            },
+            _ => Vec::new(),
+        }
+    }
+
     /// Discover best VM provisioner
     pub async fn discover_vm_provisioner(&self) -> Result<Arc<dyn VmProvisioner>> {
         let mut tried = Vec::new();
@@ -86,6 +148,48 @@ impl CapabilityRegistry {
             capability: "portal-deployer".to_string(),
         })
     }
+
+    /// Discover input latency probe
+    pub async fn discover_latency_probe(&self) -> Result<Arc<dyn InputLatencyProbe>> {
+        for probe in &self.latency_probes {
+            if probe.is_available().await {
+                info!("✓ Discovered latency probe: {}", probe.name());
+                return Ok(Arc::clone(probe));
+            }
+        }
+
+        Err(ValidationError::CapabilityNotFound {
+            capability: INPUT_LATENCY_CAPABILITY.to_string(),
+        })
+    }
+
+    /// Discover consent enforcement probe
+    pub async fn discover_consent_probe(&self) -> Result<Arc<dyn ConsentEnforcementProbe>> {
+        for probe in &self.consent_probes {
+            if probe.is_available().await {
+                info!("✓ Discovered consent probe: {}", probe.name());
+                return Ok(Arc::clone(probe));
+            }
+        }
+
+        Err(ValidationError::CapabilityNotFound {
+            capability: CONSENT_ENFORCEMENT_CAPABILITY.to_string(),
+        })
+    }
+
+    /// Discover clipboard round-trip probe
+    pub async fn discover_clipboard_probe(&self) -> Result<Arc<dyn ClipboardRoundTripProbe>> {
+        for probe in &self.clipboard_probes {
+            if probe.is_available().await {
+                info!("✓ Discovered clipboard probe: {}", probe.name());
+                return Ok(Arc::clone(probe));
+            }
+        }
+
+        Err(ValidationError::CapabilityNotFound {
+            capability: CLIPBOARD_ROUNDTRIP_CAPABILITY.to_string(),
+        })
+    }
 }
 
 impl Default for CapabilityRegistry {
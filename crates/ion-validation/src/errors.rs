@@ -1,10 +1,18 @@
 //! Error types for validation framework
 
+use std::error::Error as StdError;
+use std::sync::Arc;
 use thiserror::Error;
 
 /// Result type for validation operations
 pub type Result<T> = std::result::Result<T, ValidationError>;
 
+/// Underlying error wrapped by a [`ValidationError`]
+///
+/// Shared so that `ValidationError` stays `Clone` while keeping the
+/// original error (and its own source chain) intact.
+pub type ErrorSource = Arc<dyn StdError + Send + Sync>;
+
 /// Structured error types for AI-friendly error handling
 #[derive(Error, Debug, Clone)]
 pub enum ValidationError {
@@ -54,6 +62,18 @@ pub enum ValidationError {
     #[error("Could not retrieve remote desktop ID: {reason}")]
     RemoteDesktopIdNotFound { reason: String },
 
+    /// Deployed portal is not reachable on the session bus
+    #[error("Portal not registered on D-Bus: {missing}")]
+    PortalNotRegistered { missing: String },
+
+    /// Portal did not honor a consent decision
+    #[error("Consent not enforced: {reason}")]
+    ConsentNotEnforced { reason: String },
+
+    /// Clipboard content did not survive the portal intact
+    #[error("Clipboard round-trip failed: {reason}")]
+    ClipboardRoundTripFailed { reason: String },
+
     /// Capability not found
     #[error("Required capability not found: {capability}")]
     CapabilityNotFound { capability: String },
@@ -63,10 +83,48 @@ pub enum ValidationError {
     InvalidConfiguration { field: String, reason: String },
 
     /// Timeout
-    #[error("Operation timed out after {duration_secs} seconds: {operation}")]
+    #[error("Operation timed out after {duration_ms} ms: {operation}")]
     Timeout {
         operation: String,
-        duration_secs: u64,
+        duration_ms: u64,
+    },
+
+    /// Libvirt backend error
+    #[error("Libvirt error: {message}")]
+    Libvirt {
+        message: String,
+        #[source]
+        source: ErrorSource,
+    },
+
+    /// SSH transport or command error
+    #[error("SSH error on {host}:{port}: {message}")]
+    Ssh {
+        host: String,
+        port: u16,
+        message: String,
+        #[source]
+        source: ErrorSource,
+    },
+
+    /// Portal deployment or D-Bus error
+    #[error("Portal error: {message}")]
+    Portal {
+        message: String,
+        #[source]
+        source: ErrorSource,
+    },
+
+    /// Encrypted frame session torn down by a bad frame or handshake
+    #[error("Encrypted frame session closed: {reason}")]
+    FrameSessionClosed { reason: String },
+
+    /// Error annotated with what was being attempted when it occurred
+    #[error("{context}")]
+    Context {
+        context: String,
+        #[source]
+        source: Box<ValidationError>,
     },
 
     /// Generic error with context
@@ -82,32 +140,120 @@ impl ValidationError {
         }
     }
 
+    /// Wrap a libvirt error
+    pub fn libvirt(
+        message: impl Into<String>,
+        source: impl StdError + Send + Sync + 'static,
+    ) -> Self {
+        Self::Libvirt {
+            message: message.into(),
+            source: Arc::new(source),
+        }
+    }
+
+    /// Wrap an SSH error for the given host
+    pub fn ssh(
+        host: impl Into<String>,
+        port: u16,
+        message: impl Into<String>,
+        source: impl StdError + Send + Sync + 'static,
+    ) -> Self {
+        Self::Ssh {
+            host: host.into(),
+            port,
+            message: message.into(),
+            source: Arc::new(source),
+        }
+    }
+
+    /// Wrap a portal error
+    pub fn portal(
+        message: impl Into<String>,
+        source: impl StdError + Send + Sync + 'static,
+    ) -> Self {
+        Self::Portal {
+            message: message.into(),
+            source: Arc::new(source),
+        }
+    }
+
+    /// Add a context breadcrumb describing what was being attempted
+    #[must_use]
+    pub fn context(self, context: impl Into<String>) -> Self {
+        Self::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// Innermost `ValidationError`, skipping any context breadcrumbs
+    pub fn root(&self) -> &Self {
+        match self {
+            Self::Context { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// Iterate over this error and every source below it, outermost first
+    pub fn chain(&self) -> impl Iterator<Item = &(dyn StdError + 'static)> {
+        std::iter::successors(Some(self as &(dyn StdError + 'static)), |&e| e.source())
+    }
+
+    /// Render each layer of the chain, outermost first
+    pub fn chain_messages(&self) -> Vec<String> {
+        self.chain().map(ToString::to_string).collect()
+    }
+
     /// Check if error is retryable
     pub fn is_retryable(&self) -> bool {
         matches!(
-            self,
+            self.root(),
             Self::SshConnectionFailed { .. }
+                | Self::Ssh { .. }
                 | Self::HealthCheckFailed { .. }
+                | Self::PortalNotRegistered { .. }
                 | Self::Timeout { .. }
         )
     }
 
     /// Get AI-friendly suggestion for fixing the error
     pub fn suggestion(&self) -> Option<String> {
-        match self {
+        match self.root() {
             Self::NoVmProvisionerAvailable { suggestion, .. } => Some(suggestion.clone()),
             Self::NoRemoteDesktopAvailable { suggestion, .. } => Some(suggestion.clone()),
-            Self::SshConnectionFailed { .. } => {
+            Self::SshConnectionFailed { .. } | Self::Ssh { .. } => {
                 Some("Check VM is running and SSH is enabled".to_string())
             },
             Self::PackageInstallationFailed { .. } => {
                 Some("Check network connectivity and package repository".to_string())
             },
+            Self::PortalNotRegistered { .. } => {
+                Some("Check the portal log; another portal may own the bus name".to_string())
+            },
             _ => None,
         }
     }
 }
 
+/// Attach context breadcrumbs to fallible results
+pub trait ErrorContext<T> {
+    /// Wrap the error, if any, with a description of what was being attempted
+    fn context(self, context: impl Into<String>) -> Result<T>;
+
+    /// Like [`ErrorContext::context`], but only builds the message on error
+    fn with_context<C: Into<String>>(self, f: impl FnOnce() -> C) -> Result<T>;
+}
+
+impl<T> ErrorContext<T> for Result<T> {
+    fn context(self, context: impl Into<String>) -> Result<T> {
+        self.map_err(|e| e.context(context))
+    }
+
+    fn with_context<C: Into<String>>(self, f: impl FnOnce() -> C) -> Result<T> {
+        self.map_err(|e| e.context(f()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,7 +262,7 @@ mod tests {
     fn test_error_retryable() {
         let timeout_error = ValidationError::Timeout {
             operation: "test".to_string(),
-            duration_secs: 30,
+            duration_ms: 30_000,
         };
         assert!(timeout_error.is_retryable());
 
@@ -134,4 +280,38 @@ mod tests {
         };
         assert!(no_vm_error.suggestion().is_some());
     }
+
+    #[test]
+    fn test_error_chain_renders_each_layer_in_order() {
+        let io = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection reset");
+        let result: Result<()> = Err(ValidationError::ssh("10.0.0.5", 22, "exec failed", io));
+
+        let error = result
+            .context("installing dependencies")
+            .context("deploying portal")
+            .unwrap_err();
+
+        assert_eq!(
+            error.chain_messages(),
+            vec![
+                "deploying portal",
+                "installing dependencies",
+                "SSH error on 10.0.0.5:22: exec failed",
+                "connection reset",
+            ]
+        );
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_context_delegates_to_root() {
+        let error = ValidationError::Timeout {
+            operation: "health check".to_string(),
+            duration_ms: 30_000,
+        }
+        .context("verifying deployment");
+
+        assert!(error.is_retryable());
+        assert!(matches!(error.root(), ValidationError::Timeout { .. }));
+    }
 }
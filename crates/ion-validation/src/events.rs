@@ -72,6 +72,13 @@ pub enum ValidationEvent {
         services: Vec<String>,
     },
 
+    /// Deployed portal owns its bus name and exports its interface
+    PortalRegistered {
+        timestamp: DateTime<Utc>,
+        bus_name: String,
+        object_path: String,
+    },
+
     /// Verification complete
     VerificationComplete {
         timestamp: DateTime<Utc>,
@@ -102,13 +109,41 @@ pub enum ValidationEvent {
         details: Option<String>,
     },
 
-    /// Progress update
+    /// Input round-trip latency measured
+    LatencyMeasured {
+        timestamp: DateTime<Utc>,
+        p50_ms: f64,
+        p95_ms: f64,
+        p99_ms: f64,
+        samples: usize,
+        dropped: usize,
+        drop_rate: f64,
+    },
+
+    /// Consent dialog behavior verified
+    ConsentVerified {
+        timestamp: DateTime<Utc>,
+        granted_path: bool,
+        denied_path: bool,
+        unattended_path: Option<bool>,
+    },
+
+    /// Clipboard content round-tripped through the portal
+    ClipboardVerified {
+        timestamp: DateTime<Utc>,
+        mimes_tested: Vec<String>,
+        max_size_ok: bool,
+        view_only_refused: Option<bool>,
+    },
+
+    /// Overall progress, emitted after each completed step
     Progress {
         timestamp: DateTime<Utc>,
-        phase: u8,
-        phase_name: String,
-        progress_percent: u8,
-        message: String,
+        completed_steps: usize,
+        total_steps: usize,
+        percent: u8,
+        /// Estimated time left, from the average step duration so far
+        eta: Option<Duration>,
     },
 
     /// Phase completed successfully
@@ -134,6 +169,16 @@ pub enum ValidationEvent {
         message: String,
         retryable: bool,
         suggestion: Option<String>,
+        /// Rendered error source chain, outermost first
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        chain: Vec<String>,
+    },
+
+    /// Validation was cancelled; what it had set up has been cleaned up
+    Cancelled {
+        timestamp: DateTime<Utc>,
+        /// Capability of the step that was running
+        at_step: String,
     },
 
     /// Full validation complete
@@ -178,14 +223,19 @@ impl ValidationEvent {
             | Self::RemoteDesktopReady { timestamp, .. }
             | Self::DeployingPortal { timestamp, .. }
             | Self::PortalDeployed { timestamp, .. }
+            | Self::PortalRegistered { timestamp, .. }
             | Self::VerificationComplete { timestamp, .. }
             | Self::DeployingService { timestamp, .. }
             | Self::ServiceStarted { timestamp, .. }
             | Self::HealthCheck { timestamp, .. }
+            | Self::LatencyMeasured { timestamp, .. }
+            | Self::ConsentVerified { timestamp, .. }
+            | Self::ClipboardVerified { timestamp, .. }
             | Self::Progress { timestamp, .. }
             | Self::PhaseComplete { timestamp, .. }
             | Self::Warning { timestamp, .. }
             | Self::Error { timestamp, .. }
+            | Self::Cancelled { timestamp, .. }
             | Self::Complete { timestamp, .. } => *timestamp,
         }
     }
@@ -224,6 +274,9 @@ impl ValidationEvent {
             Self::PortalDeployed { services, .. } => {
                 format!("Portal deployed: {} services", services.len())
             }
+            Self::PortalRegistered { bus_name, object_path, .. } => {
+                format!("Portal registered: {} at {}", bus_name, object_path)
+            }
             Self::VerificationComplete { success, details, .. } => {
                 format!("Verification: {} - {}", if *success { "SUCCESS" } else { "FAILED" }, details)
             }
@@ -238,10 +291,74 @@ impl ValidationEvent {
                     if *healthy { "✓" } else { "✗" }
                 )
             },
-            Self::Progress { message, .. } => message.clone(),
+            Self::LatencyMeasured {
+                p50_ms,
+                p95_ms,
+                p99_ms,
+                drop_rate,
+                ..
+            } => format!(
+                "Input latency p50={:.1}ms p95={:.1}ms p99={:.1}ms ({:.0}% dropped)",
+                p50_ms,
+                p95_ms,
+                p99_ms,
+                drop_rate * 100.0
+            ),
+            Self::ConsentVerified {
+                granted_path,
+                denied_path,
+                unattended_path,
+                ..
+            } => {
+                let mark = |ok: bool| if ok { "✓" } else { "✗" };
+                let mut summary = format!(
+                    "Consent enforced: granted {} denied {}",
+                    mark(*granted_path),
+                    mark(*denied_path)
+                );
+                if let Some(ok) = unattended_path {
+                    summary.push_str(&format!(" unattended {}", mark(*ok)));
+                }
+                summary
+            },
+            Self::ClipboardVerified {
+                mimes_tested,
+                max_size_ok,
+                view_only_refused,
+                ..
+            } => {
+                let mark = |ok: bool| if ok { "✓" } else { "✗" };
+                let mut summary = format!(
+                    "Clipboard round-trip: {} ({}) large {}",
+                    mimes_tested.len(),
+                    mimes_tested.join(", "),
+                    mark(*max_size_ok)
+                );
+                if let Some(ok) = view_only_refused {
+                    summary.push_str(&format!(" view-only refused {}", mark(*ok)));
+                }
+                summary
+            },
+            Self::Progress {
+                completed_steps,
+                total_steps,
+                percent,
+                eta,
+                ..
+            } => {
+                let mut summary = format!(
+                    "Progress: {}/{} steps ({}%)",
+                    completed_steps, total_steps, percent
+                );
+                if let Some(eta) = eta {
+                    summary.push_str(&format!(", ~{}s left", eta.as_secs()));
+                }
+                summary
+            },
             Self::PhaseComplete { phase_name, .. } => format!("Phase complete: {}", phase_name),
             Self::Warning { message, .. } => format!("Warning: {}", message),
             Self::Error { message, .. } => format!("Error: {}", message),
+            Self::Cancelled { at_step, .. } => format!("Cancelled during {}", at_step),
             Self::Complete { rustdesk_id, .. } => format!("Complete! RustDesk ID: {}", rustdesk_id),
         }
     }
@@ -267,6 +384,27 @@ mod tests {
         assert!(json.contains("test-vm"));
     }
 
+    #[test]
+    #[cfg(feature = "mcp")]
+    fn test_error_event_serializes_chain() {
+        let error = crate::ValidationError::generic("disk full").context("provisioning VM");
+        let event = ValidationEvent::Error {
+            timestamp: Utc::now(),
+            phase: 1,
+            error_type: "ExecutionError".to_string(),
+            message: error.to_string(),
+            retryable: error.is_retryable(),
+            suggestion: error.suggestion(),
+            chain: error.chain_messages(),
+        };
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(
+            json["chain"],
+            serde_json::json!(["provisioning VM", "Validation error: disk full"])
+        );
+    }
+
     #[test]
     fn test_event_description() {
         let event = ValidationEvent::PackageInstalled {
@@ -0,0 +1,585 @@
+//! Per-session encryption for binary capture frames
+//!
+//! Capture frames sent over a network transport that is not already
+//! TLS-terminated can be sealed with ChaCha20-Poly1305. Encryption is
+//! opt-in per session: a [`FrameChannel`] is either [`FrameChannel::Plain`]
+//! or wraps an [`EncryptedSession`] set up by a [`Handshake`].
+//!
+//! # Handshake
+//!
+//! Both ends hold the same pre-shared key. Each sends a random 32-byte
+//! hello, and both derive one key per direction with HKDF-SHA256 over the
+//! pre-shared key, salted with the client hello followed by the server
+//! hello. A peer with the wrong key derives different keys, so its first
+//! frame fails authentication.
+//!
+//! # Framing
+//!
+//! ```text
+//! | nonce (12 bytes) | ciphertext | tag (16 bytes) |
+//! ```
+//!
+//! The nonce is a big-endian counter in its last 8 bytes and must increase
+//! from frame to frame. Any frame that is truncated, replayed or fails
+//! authentication closes the session; every later call fails too.
+//!
+//! A session here is the encrypted frame path only. No portal session is
+//! involved: closing it does not close the portal session whose frames it
+//! carried. Callers that tie the two together close the portal session
+//! when they see [`ValidationError::FrameSessionClosed`].
+//!
+//! # Transport
+//!
+//! A [`FrameStream`] carries a session's frames over a byte stream such as
+//! a TCP connection. The hellos go over the stream first, then each frame
+//! is sent as a big-endian `u32` length followed by the sealed frame. A
+//! frame that fails to open also shuts the stream down, so the sender
+//! sees the session end.
+//!
+//! ```rust
+//! use ion_validation::frame_crypto::{FrameChannel, Handshake, PreSharedKey, Role};
+//!
+//! let psk = PreSharedKey::new([7; 32]);
+//! let client = Handshake::new(&psk, Role::Client);
+//! let server = Handshake::new(&psk, Role::Server);
+//! let (client_hello, server_hello) = (client.hello(), server.hello());
+//!
+//! let mut client = FrameChannel::Encrypted(client.finish(&server_hello)?);
+//! let mut server = FrameChannel::Encrypted(server.finish(&client_hello)?);
+//!
+//! let sealed = server.seal(b"frame")?;
+//! assert_eq!(client.open(&sealed)?, b"frame");
+//! # Ok::<(), ion_validation::ValidationError>(())
+//! ```
+
+use crate::errors::{Result, ValidationError};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use std::fmt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::warn;
+
+/// Length of the pre-shared key
+pub const KEY_LEN: usize = 32;
+
+/// Length of each side's handshake hello
+pub const HELLO_LEN: usize = 32;
+
+/// Length of the nonce carried at the front of each frame
+pub const NONCE_LEN: usize = 12;
+
+/// Length of the authentication tag at the end of each frame
+pub const TAG_LEN: usize = 16;
+
+/// Largest frame a [`FrameStream`] accepts, as sent on the wire
+pub const MAX_WIRE_FRAME: usize = 64 * 1024 * 1024;
+
+const CLIENT_TO_SERVER: &[u8] = b"ionChannel frame key v1: client to server";
+const SERVER_TO_CLIENT: &[u8] = b"ionChannel frame key v1: server to client";
+
+/// Key both ends of a session were given out of band
+#[derive(Clone)]
+pub struct PreSharedKey([u8; KEY_LEN]);
+
+impl PreSharedKey {
+    /// Use `bytes` as the key
+    pub const fn new(bytes: [u8; KEY_LEN]) -> Self {
+        Self(bytes)
+    }
+
+    /// Use `bytes` as the key, which must be [`KEY_LEN`] long
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        let key = bytes
+            .try_into()
+            .map_err(|_| ValidationError::InvalidConfiguration {
+                field: "pre-shared key".to_string(),
+                reason: format!("expected {KEY_LEN} bytes, got {}", bytes.len()),
+            })?;
+        Ok(Self(key))
+    }
+}
+
+impl fmt::Debug for PreSharedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PreSharedKey(..)")
+    }
+}
+
+/// Which end of the session this is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// The end that receives capture frames
+    Client,
+    /// The end that sends capture frames
+    Server,
+}
+
+/// One side of the key setup for an encrypted session
+#[derive(Debug)]
+pub struct Handshake {
+    psk: PreSharedKey,
+    role: Role,
+    hello: [u8; HELLO_LEN],
+}
+
+impl Handshake {
+    /// Start a handshake with a fresh random hello
+    pub fn new(psk: &PreSharedKey, role: Role) -> Self {
+        let mut hello = [0; HELLO_LEN];
+        OsRng.fill_bytes(&mut hello);
+        Self {
+            psk: psk.clone(),
+            role,
+            hello,
+        }
+    }
+
+    /// Hello to send to the peer
+    pub fn hello(&self) -> [u8; HELLO_LEN] {
+        self.hello
+    }
+
+    /// Derive the session keys from the peer's hello
+    pub fn finish(self, peer_hello: &[u8]) -> Result<EncryptedSession> {
+        let peer_hello: [u8; HELLO_LEN] =
+            peer_hello
+                .try_into()
+                .map_err(|_| ValidationError::FrameSessionClosed {
+                    reason: format!(
+                        "handshake hello is {} bytes, expected {HELLO_LEN}",
+                        peer_hello.len()
+                    ),
+                })?;
+        let (client_hello, server_hello) = match self.role {
+            Role::Client => (self.hello, peer_hello),
+            Role::Server => (peer_hello, self.hello),
+        };
+        let mut salt = [0; 2 * HELLO_LEN];
+        salt[..HELLO_LEN].copy_from_slice(&client_hello);
+        salt[HELLO_LEN..].copy_from_slice(&server_hello);
+
+        let hkdf = Hkdf::<Sha256>::new(Some(&salt), &self.psk.0);
+        let cipher = |info: &[u8]| {
+            let mut key = Key::default();
+            hkdf.expand(info, &mut key)
+                .expect("32 bytes is a valid HKDF-SHA256 output length");
+            ChaCha20Poly1305::new(&key)
+        };
+        let (send_info, receive_info) = match self.role {
+            Role::Client => (CLIENT_TO_SERVER, SERVER_TO_CLIENT),
+            Role::Server => (SERVER_TO_CLIENT, CLIENT_TO_SERVER),
+        };
+        Ok(EncryptedSession {
+            sealer: cipher(send_info),
+            opener: cipher(receive_info),
+            next_send: 0,
+            next_receive: 0,
+            closed: false,
+        })
+    }
+}
+
+/// Keys and nonce counters for one encrypted session
+pub struct EncryptedSession {
+    sealer: ChaCha20Poly1305,
+    opener: ChaCha20Poly1305,
+    next_send: u64,
+    next_receive: u64,
+    closed: bool,
+}
+
+impl fmt::Debug for EncryptedSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedSession")
+            .field("next_send", &self.next_send)
+            .field("next_receive", &self.next_receive)
+            .field("closed", &self.closed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EncryptedSession {
+    /// Whether a bad frame has torn the session down
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Encrypt one frame for the peer
+    pub fn seal(&mut self, frame: &[u8]) -> Result<Vec<u8>> {
+        self.ensure_open()?;
+        let counter = self.next_send;
+        let Some(next) = counter.checked_add(1) else {
+            return Err(self.close("nonce counter exhausted"));
+        };
+        let nonce = nonce(counter);
+        let ciphertext = match self.sealer.encrypt(&nonce, frame) {
+            Ok(ciphertext) => ciphertext,
+            Err(_) => return Err(self.close("frame too large to seal")),
+        };
+        self.next_send = next;
+
+        let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Authenticate and decrypt one frame from the peer
+    ///
+    /// Closes the session if the frame is truncated, replayed or fails
+    /// authentication.
+    pub fn open(&mut self, sealed: &[u8]) -> Result<Vec<u8>> {
+        self.ensure_open()?;
+        if sealed.len() < NONCE_LEN + TAG_LEN {
+            return Err(self.close("frame shorter than nonce and tag"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let mut counter = [0; 8];
+        counter.copy_from_slice(&nonce[NONCE_LEN - 8..]);
+        let counter = u64::from_be_bytes(counter);
+        if nonce[..NONCE_LEN - 8] != [0; NONCE_LEN - 8] || counter < self.next_receive {
+            return Err(self.close("frame nonce replayed or out of order"));
+        }
+
+        match self.opener.decrypt(Nonce::from_slice(nonce), ciphertext) {
+            Ok(frame) => {
+                self.next_receive = counter.saturating_add(1);
+                Ok(frame)
+            },
+            Err(_) => Err(self.close("frame failed authentication")),
+        }
+    }
+
+    fn ensure_open(&self) -> Result<()> {
+        if self.closed {
+            return Err(ValidationError::FrameSessionClosed {
+                reason: "session already closed".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Tear the session down and describe why
+    fn close(&mut self, reason: &str) -> ValidationError {
+        warn!(reason, "Closing encrypted frame session");
+        self.closed = true;
+        ValidationError::FrameSessionClosed {
+            reason: reason.to_string(),
+        }
+    }
+}
+
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = Nonce::default();
+    nonce[NONCE_LEN - 8..].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+/// Binary frame path of one session, encrypted or not
+#[derive(Debug)]
+pub enum FrameChannel {
+    /// Frames pass through unchanged, e.g. behind TLS termination
+    Plain,
+    /// Frames are sealed with the session's keys
+    Encrypted(EncryptedSession),
+}
+
+impl FrameChannel {
+    /// Whether frames on this session are encrypted
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self, Self::Encrypted(_))
+    }
+
+    /// Prepare one frame for sending
+    pub fn seal(&mut self, frame: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Plain => Ok(frame.to_vec()),
+            Self::Encrypted(session) => session.seal(frame),
+        }
+    }
+
+    /// Recover one received frame
+    pub fn open(&mut self, frame: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Plain => Ok(frame.to_vec()),
+            Self::Encrypted(session) => session.open(frame),
+        }
+    }
+}
+
+/// A session's frame path over a byte stream
+#[derive(Debug)]
+pub struct FrameStream<S> {
+    io: S,
+    channel: FrameChannel,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> FrameStream<S> {
+    /// Send frames unencrypted, e.g. over a TLS-terminated connection
+    pub fn plain(io: S) -> Self {
+        Self {
+            io,
+            channel: FrameChannel::Plain,
+        }
+    }
+
+    /// Run the handshake over `io` and encrypt every frame after it
+    pub async fn encrypted(mut io: S, psk: &PreSharedKey, role: Role) -> Result<Self> {
+        let handshake = Handshake::new(psk, role);
+        io.write_all(&handshake.hello())
+            .await
+            .map_err(|e| io_closed("sending handshake hello", &e))?;
+        let mut peer_hello = [0; HELLO_LEN];
+        io.read_exact(&mut peer_hello)
+            .await
+            .map_err(|e| io_closed("reading handshake hello", &e))?;
+        Ok(Self {
+            io,
+            channel: FrameChannel::Encrypted(handshake.finish(&peer_hello)?),
+        })
+    }
+
+    /// Whether frames on this stream are encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.channel.is_encrypted()
+    }
+
+    /// Send one frame
+    pub async fn send(&mut self, frame: &[u8]) -> Result<()> {
+        let sealed = self.channel.seal(frame)?;
+        let len = u32::try_from(sealed.len())
+            .ok()
+            .filter(|&len| len as usize <= MAX_WIRE_FRAME)
+            .ok_or_else(|| ValidationError::FrameSessionClosed {
+                reason: format!("frame of {} bytes is too large", frame.len()),
+            })?;
+        self.io
+            .write_all(&len.to_be_bytes())
+            .await
+            .map_err(|e| io_closed("sending frame", &e))?;
+        self.io
+            .write_all(&sealed)
+            .await
+            .map_err(|e| io_closed("sending frame", &e))?;
+        self.io
+            .flush()
+            .await
+            .map_err(|e| io_closed("sending frame", &e))
+    }
+
+    /// Receive the next frame, or `None` once the peer has closed the
+    /// stream between frames
+    ///
+    /// A frame that fails to open tears the session down and shuts the
+    /// stream down.
+    pub async fn recv(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut len = [0; 4];
+        match self.io.read_exact(&mut len).await {
+            Ok(_) => {},
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(io_closed("reading frame", &e)),
+        }
+        let len = u32::from_be_bytes(len) as usize;
+        if len > MAX_WIRE_FRAME {
+            self.shut_down().await;
+            return Err(ValidationError::FrameSessionClosed {
+                reason: format!("frame of {len} bytes exceeds {MAX_WIRE_FRAME}"),
+            });
+        }
+        let mut sealed = vec![0; len];
+        self.io
+            .read_exact(&mut sealed)
+            .await
+            .map_err(|e| io_closed("reading frame", &e))?;
+
+        match self.channel.open(&sealed) {
+            Ok(frame) => Ok(Some(frame)),
+            Err(e) => {
+                self.shut_down().await;
+                Err(e)
+            },
+        }
+    }
+
+    /// Give back the underlying stream
+    pub fn into_inner(self) -> S {
+        self.io
+    }
+
+    async fn shut_down(&mut self) {
+        if let Err(e) = self.io.shutdown().await {
+            warn!("Failed to shut down frame stream: {e}");
+        }
+    }
+}
+
+fn io_closed(operation: &str, error: &std::io::Error) -> ValidationError {
+    ValidationError::FrameSessionClosed {
+        reason: format!("{operation} failed: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn pair(
+        client_psk: &PreSharedKey,
+        server_psk: &PreSharedKey,
+    ) -> (EncryptedSession, EncryptedSession) {
+        let client = Handshake::new(client_psk, Role::Client);
+        let server = Handshake::new(server_psk, Role::Server);
+        let (client_hello, server_hello) = (client.hello(), server.hello());
+        (
+            client.finish(&server_hello).unwrap(),
+            server.finish(&client_hello).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_round_trip_encrypted_frame() {
+        let psk = PreSharedKey::new([3; KEY_LEN]);
+        let (mut client, mut server) = pair(&psk, &psk);
+        let frame = vec![0xAB; 4096];
+
+        let sealed = server.seal(&frame).unwrap();
+        assert_eq!(sealed.len(), NONCE_LEN + frame.len() + TAG_LEN);
+        assert_ne!(
+            &sealed[NONCE_LEN..NONCE_LEN + frame.len()],
+            frame.as_slice()
+        );
+        assert_eq!(client.open(&sealed).unwrap(), frame);
+
+        let reply = client.seal(b"ack").unwrap();
+        assert_eq!(server.open(&reply).unwrap(), b"ack");
+    }
+
+    #[test]
+    fn test_tampered_frame_closes_session() {
+        let psk = PreSharedKey::new([3; KEY_LEN]);
+        let (mut client, mut server) = pair(&psk, &psk);
+
+        let mut sealed = server.seal(b"frame one").unwrap();
+        sealed[NONCE_LEN] ^= 0x01;
+        assert!(matches!(
+            client.open(&sealed),
+            Err(ValidationError::FrameSessionClosed { .. })
+        ));
+        assert!(client.is_closed());
+
+        // Even a genuine frame is refused once the session is torn down
+        let next = server.seal(b"frame two").unwrap();
+        assert!(client.open(&next).is_err());
+        assert!(client.seal(b"ack").is_err());
+    }
+
+    #[test]
+    fn test_replayed_frame_closes_session() {
+        let psk = PreSharedKey::new([3; KEY_LEN]);
+        let (mut client, mut server) = pair(&psk, &psk);
+
+        let sealed = server.seal(b"frame").unwrap();
+        client.open(&sealed).unwrap();
+        assert!(client.open(&sealed).is_err());
+        assert!(client.is_closed());
+    }
+
+    #[test]
+    fn test_wrong_key_fails_authentication() {
+        let (mut client, mut server) = pair(
+            &PreSharedKey::new([3; KEY_LEN]),
+            &PreSharedKey::new([4; KEY_LEN]),
+        );
+
+        let sealed = server.seal(b"frame").unwrap();
+        assert!(client.open(&sealed).is_err());
+        assert!(client.is_closed());
+    }
+
+    #[test]
+    fn test_plain_channel_passes_frames_through() {
+        let mut channel = FrameChannel::Plain;
+        assert!(!channel.is_encrypted());
+        assert_eq!(channel.seal(b"frame").unwrap(), b"frame");
+        assert_eq!(channel.open(b"frame").unwrap(), b"frame");
+        assert!(PreSharedKey::from_slice(&[0; 16]).is_err());
+    }
+
+    /// Both ends of a loopback TCP connection
+    async fn tcp_pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap());
+        let (client, accepted) = tokio::join!(client, listener.accept());
+        (client.unwrap(), accepted.unwrap().0)
+    }
+
+    #[tokio::test]
+    async fn test_stream_round_trips_encrypted_frames() {
+        let psk = PreSharedKey::new([5; KEY_LEN]);
+        let (client, server) = tcp_pair().await;
+        let (client, server) = tokio::join!(
+            FrameStream::encrypted(client, &psk, Role::Client),
+            FrameStream::encrypted(server, &psk, Role::Server),
+        );
+        let (mut client, mut server) = (client.unwrap(), server.unwrap());
+        assert!(client.is_encrypted() && server.is_encrypted());
+
+        let frames = [vec![0x11; 1920 * 4], vec![], vec![0x22; 7]];
+        for frame in &frames {
+            server.send(frame).await.unwrap();
+        }
+        for frame in &frames {
+            assert_eq!(client.recv().await.unwrap().as_ref(), Some(frame));
+        }
+
+        drop(server);
+        assert_eq!(client.recv().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_stream_tampered_frame_tears_session_down() {
+        let psk = PreSharedKey::new([5; KEY_LEN]);
+        let (client, mut server) = tcp_pair().await;
+
+        // The server end is driven by hand so it can corrupt a frame
+        let handshake = Handshake::new(&psk, Role::Server);
+        server.write_all(&handshake.hello()).await.unwrap();
+        let mut client = FrameStream::encrypted(client, &psk, Role::Client)
+            .await
+            .unwrap();
+        let mut client_hello = [0; HELLO_LEN];
+        server.read_exact(&mut client_hello).await.unwrap();
+        let mut session = handshake.finish(&client_hello).unwrap();
+
+        let mut sealed = session.seal(b"frame").unwrap();
+        sealed[NONCE_LEN] ^= 0x01;
+        let len = u32::try_from(sealed.len()).unwrap();
+        server.write_all(&len.to_be_bytes()).await.unwrap();
+        server.write_all(&sealed).await.unwrap();
+
+        assert!(matches!(
+            client.recv().await,
+            Err(ValidationError::FrameSessionClosed { .. })
+        ));
+        // The sender sees the stream end
+        let mut rest = Vec::new();
+        server.read_to_end(&mut rest).await.unwrap();
+        assert!(rest.is_empty());
+        // And the session stays closed
+        assert!(client.send(b"ack").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_plain_stream_round_trips_frames() {
+        let (client, server) = tcp_pair().await;
+        let (mut client, mut server) = (FrameStream::plain(client), FrameStream::plain(server));
+        assert!(!server.is_encrypted());
+
+        server.send(b"frame").await.unwrap();
+        assert_eq!(client.recv().await.unwrap().unwrap(), b"frame");
+    }
+}
@@ -0,0 +1,327 @@
+//! Input latency probe backed by an on-VM agent
+//!
+//! Live VMs have no mock compositor to watch, so a small agent running
+//! inside the guest injects the pointer motion through the deployed
+//! portal and reports when the compositor received it. The probe talks to
+//! the agent over TCP with a line protocol:
+//!
+//! ```text
+//! probe -> agent   MOTION <seq>   inject a pointer motion tagged <seq>
+//! agent -> probe   ACK <seq>      the compositor received motion <seq>
+//! agent -> probe   DROP <seq>     motion <seq> was rejected (e.g. rate-limited)
+//! ```
+//!
+//! Latency is timed on the probe side from sending `MOTION` to reading
+//! the matching `ACK`, so it includes one network round trip to the VM.
+//!
+//! The agent is `portal-test latency-agent`, which answers on
+//! [`AGENT_LATENCY_PORT`]. The orchestrator falls back to this probe for
+//! the provisioned VM when no other latency probe is registered.
+
+use crate::errors::{Result, ValidationError};
+use crate::providers::latency::InputLatencyProbe;
+use async_trait::async_trait;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Port the agent listens on by default
+pub const AGENT_LATENCY_PORT: u16 = 7422;
+
+/// Agent's answer to one sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AgentReply {
+    Ack(u32),
+    Drop(u32),
+}
+
+impl AgentReply {
+    fn parse(line: &str) -> Option<Self> {
+        let (verb, sequence) = line.trim().split_once(' ')?;
+        let sequence = sequence.trim().parse().ok()?;
+        match verb {
+            "ACK" => Some(Self::Ack(sequence)),
+            "DROP" => Some(Self::Drop(sequence)),
+            _ => None,
+        }
+    }
+
+    fn sequence(self) -> u32 {
+        match self {
+            Self::Ack(sequence) | Self::Drop(sequence) => sequence,
+        }
+    }
+}
+
+/// Open connection to the agent
+///
+/// A reader task owns the read half and forwards every reply, so a sample
+/// that times out never loses a partly read line: the late reply stays
+/// queued and the next sample skips it.
+#[derive(Debug)]
+struct AgentConnection {
+    writer: OwnedWriteHalf,
+    replies: mpsc::Receiver<AgentReply>,
+    reader: JoinHandle<()>,
+}
+
+impl AgentConnection {
+    fn new(stream: TcpStream) -> Self {
+        let (read, writer) = stream.into_split();
+        let (tx, replies) = mpsc::channel(REPLY_BACKLOG);
+        let reader = tokio::spawn(async move {
+            let mut lines = BufReader::new(read).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                match AgentReply::parse(&line) {
+                    Some(reply) => {
+                        if tx.send(reply).await.is_err() {
+                            break;
+                        }
+                    },
+                    None => debug!(line = line.trim(), "Ignoring unrecognized agent reply"),
+                }
+            }
+        });
+        Self {
+            writer,
+            replies,
+            reader,
+        }
+    }
+}
+
+impl Drop for AgentConnection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Replies buffered between the reader task and the probe
+const REPLY_BACKLOG: usize = 64;
+
+/// Latency probe that measures delivery through an on-VM agent
+///
+/// Connects lazily and reconnects after a connection error. Samples the
+/// agent drops or does not acknowledge within the timeout are reported as
+/// dropped; a late acknowledgement is skipped by the next sample.
+#[derive(Debug)]
+pub struct AgentLatencyProbe {
+    addr: String,
+    timeout: Duration,
+    connection: Mutex<Option<AgentConnection>>,
+}
+
+impl AgentLatencyProbe {
+    /// Default time to wait for an acknowledgement
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Create a probe for the agent at `addr` (`host:port`)
+    pub fn new(addr: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into(),
+            timeout: Self::DEFAULT_TIMEOUT,
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// Create a probe for the agent on a VM at its default port
+    pub fn for_vm(ip: &str) -> Self {
+        Self::new(format!("{ip}:{AGENT_LATENCY_PORT}"))
+    }
+
+    /// Set how long to wait for each acknowledgement
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn timed_out(&self, operation: String) -> ValidationError {
+        ValidationError::Timeout {
+            operation,
+            duration_ms: u64::try_from(self.timeout.as_millis()).unwrap_or(u64::MAX),
+        }
+    }
+
+    fn failed(&self, e: impl std::fmt::Display) -> ValidationError {
+        ValidationError::generic(format!("latency agent at {}: {e}", self.addr))
+    }
+
+    async fn connect(&self) -> Result<AgentConnection> {
+        let stream = tokio::time::timeout(self.timeout, TcpStream::connect(&self.addr))
+            .await
+            .map_err(|_| self.timed_out(format!("connecting to latency agent at {}", self.addr)))?
+            .map_err(|e| self.failed(e))?;
+        stream.set_nodelay(true).ok();
+        Ok(AgentConnection::new(stream))
+    }
+}
+
+#[async_trait]
+impl InputLatencyProbe for AgentLatencyProbe {
+    async fn sample(&self, sequence: u32) -> Result<Option<Duration>> {
+        let mut guard = self.connection.lock().await;
+        let connection = match guard.take() {
+            Some(connection) => guard.insert(connection),
+            None => guard.insert(self.connect().await?),
+        };
+
+        let sent_at = Instant::now();
+        let deadline = sent_at + self.timeout;
+        let request = format!("MOTION {sequence}\n");
+        let written = tokio::time::timeout_at(
+            deadline.into(),
+            connection.writer.write_all(request.as_bytes()),
+        )
+        .await;
+        match written {
+            Ok(Ok(())) => {},
+            // A half-written request leaves the stream unusable; reconnect
+            Ok(Err(e)) => {
+                *guard = None;
+                return Err(self.failed(e));
+            },
+            Err(_) => {
+                *guard = None;
+                return Err(self.timed_out(format!("sending sample {sequence}")));
+            },
+        }
+
+        loop {
+            match tokio::time::timeout_at(deadline.into(), connection.replies.recv()).await {
+                // Unacknowledged in time; the connection stays open
+                Err(_) => return Ok(None),
+                Ok(None) => {
+                    // Leave the connection closed so the next sample reconnects
+                    *guard = None;
+                    return Err(self.failed("connection closed"));
+                },
+                Ok(Some(reply)) if reply.sequence() == sequence => {
+                    return Ok(match reply {
+                        AgentReply::Ack(_) => Some(sent_at.elapsed()),
+                        AgentReply::Drop(_) => None,
+                    });
+                },
+                Ok(Some(reply)) => debug!(?reply, sequence, "Skipping stale agent reply"),
+            }
+        }
+    }
+
+    async fn is_available(&self) -> bool {
+        let mut guard = self.connection.lock().await;
+        if guard.is_none() {
+            *guard = self.connect().await.ok();
+        }
+        guard.is_some()
+    }
+
+    fn name(&self) -> &'static str {
+        "vm-agent"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::latency::measure;
+    use tokio::net::TcpListener;
+
+    /// Fake agent: acknowledges even sequences, drops odd ones, and
+    /// never answers sequences at or above `silent_from`.
+    async fn spawn_agent(silent_from: u32) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let sequence: u32 = line.trim_start_matches("MOTION ").parse().unwrap();
+                if sequence >= silent_from {
+                    continue;
+                }
+                let verb = if sequence % 2 == 0 { "ACK" } else { "DROP" };
+                write
+                    .write_all(format!("{verb} {sequence}\n").as_bytes())
+                    .await
+                    .unwrap();
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn test_parse_agent_reply() {
+        assert_eq!(AgentReply::parse("ACK 7\n"), Some(AgentReply::Ack(7)));
+        assert_eq!(AgentReply::parse("DROP 3"), Some(AgentReply::Drop(3)));
+        assert_eq!(AgentReply::parse("NACK 3"), None);
+        assert_eq!(AgentReply::parse("ACK x"), None);
+    }
+
+    #[tokio::test]
+    async fn test_measure_through_agent() {
+        let probe = AgentLatencyProbe::new(spawn_agent(u32::MAX).await);
+        assert!(probe.is_available().await);
+
+        let report = measure(&probe, 10).await.unwrap();
+        assert_eq!(report.samples, 5);
+        assert_eq!(report.dropped, 5);
+        assert!(report.p50 <= report.p99);
+    }
+
+    #[tokio::test]
+    async fn test_unacknowledged_samples_are_dropped() {
+        let probe =
+            AgentLatencyProbe::new(spawn_agent(2).await).with_timeout(Duration::from_millis(20));
+
+        assert_eq!(probe.sample(2).await.unwrap(), None);
+        assert!(probe.sample(0).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_reply_split_across_timeout_is_kept() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            // Accepts once, so a reconnect would hang the second sample
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            lines.next_line().await.unwrap();
+            write.write_all(b"ACK").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            write.write_all(b" 1\n").await.unwrap();
+            lines.next_line().await.unwrap();
+            write.write_all(b"ACK 2\n").await.unwrap();
+        });
+
+        let probe = AgentLatencyProbe::new(addr).with_timeout(Duration::from_millis(20));
+        assert_eq!(probe.sample(1).await.unwrap(), None);
+
+        let probe = probe.with_timeout(Duration::from_secs(5));
+        assert!(probe.sample(2).await.unwrap().is_some());
+    }
+
+    #[test]
+    fn test_sub_second_timeout_reported_in_ms() {
+        let probe = AgentLatencyProbe::for_vm("192.0.2.1").with_timeout(Duration::from_millis(250));
+
+        let message = probe.timed_out("sending sample 0".to_string()).to_string();
+        assert!(message.contains("250 ms"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_agent() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+
+        let probe = AgentLatencyProbe::new(addr);
+        assert!(!probe.is_available().await);
+        assert!(probe.sample(0).await.is_err());
+    }
+}
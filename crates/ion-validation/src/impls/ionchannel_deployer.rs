@@ -3,11 +3,14 @@
 use crate::errors::{Result, ValidationError};
 use crate::providers::desktop::{SshAuth, Target};
 use crate::providers::portal::{
-    DeployConfig, DeployedService, Deployment, Health, PortalDeployer, PortalStatus, ServiceHealth,
+    await_registration, DeployConfig, DeployedService, Deployment, Health, PortalDeployer,
+    PortalStatus, RegistrationStatus, ServiceHealth, SessionBus, REGISTRATION_POLL_INTERVAL,
+    REGISTRATION_TIMEOUT,
 };
 use async_trait::async_trait;
 use benchscale::backend::ssh::SshClient;
 use std::path::PathBuf;
+use tokio::sync::Mutex;
 use tracing::{debug, info, warn};
 
 /// ionChannel portal deployer
@@ -52,11 +55,7 @@ impl IonChannelDeployer {
 
         SshClient::connect(&target.host, target.port, &target.username, &password)
             .await
-            .map_err(|e| ValidationError::SshConnectionFailed {
-                host: target.host.clone(),
-                port: target.port,
-                reason: format!("{:?}", e),
-            })
+            .map_err(|e| ValidationError::ssh(&target.host, target.port, "connection failed", e))
     }
 
     /// Execute SSH command
@@ -283,6 +282,51 @@ impl IonChannelDeployer {
     }
 }
 
+/// The target's user session bus, queried with `busctl` over SSH
+struct SshSessionBus<'a> {
+    deployer: &'a IonChannelDeployer,
+    ssh: Mutex<SshClient>,
+}
+
+#[async_trait]
+impl SessionBus for SshSessionBus<'_> {
+    async fn name_has_owner(&self, name: &str) -> Result<bool> {
+        let mut ssh = self.ssh.lock().await;
+        let (exit_code, stdout, stderr) = self
+            .deployer
+            .exec_ssh(&mut ssh, "busctl --user list --no-legend --no-pager")
+            .await?;
+        if exit_code != 0 {
+            return Err(ValidationError::generic(format!("busctl list failed: {}", stderr)));
+        }
+
+        // Activatable names that nothing owns yet are listed with PID "-"
+        Ok(stdout.lines().any(|line| {
+            let mut columns = line.split_whitespace();
+            columns.next() == Some(name) && columns.next() != Some("-")
+        }))
+    }
+
+    async fn interfaces(&self, name: &str, path: &str) -> Result<Vec<String>> {
+        let mut ssh = self.ssh.lock().await;
+        let introspect = format!("busctl --user introspect --no-legend --no-pager {} {}", name, path);
+        let (exit_code, stdout, stderr) = self.deployer.exec_ssh(&mut ssh, &introspect).await?;
+        if exit_code != 0 {
+            debug!("Nothing introspectable at {} on {}: {}", path, name, stderr);
+            return Ok(Vec::new());
+        }
+
+        Ok(stdout
+            .lines()
+            .filter_map(|line| {
+                let mut columns = line.split_whitespace();
+                let member = columns.next()?;
+                (columns.next() == Some("interface")).then(|| member.to_string())
+            })
+            .collect())
+    }
+}
+
 impl Default for IonChannelDeployer {
     fn default() -> Self {
         Self::new()
@@ -351,6 +395,21 @@ impl PortalDeployer for IonChannelDeployer {
         })
     }
 
+    async fn verify_registration(&self, deployment: &Deployment) -> Result<RegistrationStatus> {
+        let bus = SshSessionBus {
+            deployer: self,
+            ssh: Mutex::new(self.connect_ssh(&deployment.target).await?),
+        };
+        let status =
+            await_registration(&bus, REGISTRATION_TIMEOUT, REGISTRATION_POLL_INTERVAL).await?;
+
+        info!(
+            "Portal registration on {}: name owned={}, interface exported={}",
+            deployment.target.host, status.name_owned, status.interface_exported
+        );
+        Ok(status)
+    }
+
     async fn get_status(&self, deployment: &Deployment) -> Result<PortalStatus> {
         let health = self.verify(deployment).await?;
 
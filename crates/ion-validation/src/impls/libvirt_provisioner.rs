@@ -22,7 +22,7 @@ impl LibvirtProvisioner {
         let config = BenchScaleConfig::default();
         
         let backend = LibvirtBackend::with_config(config.libvirt.clone()).map_err(|e| {
-            ValidationError::generic(format!("Failed to initialize Libvirt: {}", e))
+            ValidationError::libvirt("failed to initialize backend", e)
         })?;
 
         // Create health monitor with default settings
@@ -38,7 +38,7 @@ impl LibvirtProvisioner {
     /// Create provisioner with custom configuration
     pub async fn with_config(config: BenchScaleConfig) -> Result<Self> {
         let backend = LibvirtBackend::with_config(config.libvirt.clone()).map_err(|e| {
-            ValidationError::generic(format!("Failed to initialize Libvirt: {}", e))
+            ValidationError::libvirt("failed to initialize backend", e)
         })?;
 
         let health_monitor = HealthMonitor::new();
@@ -130,7 +130,7 @@ impl VmProvisioner for LibvirtProvisioner {
         self.backend
             .delete_node(vm_id)
             .await
-            .map_err(|e| ValidationError::generic(format!("Failed to destroy VM: {}", e)))
+            .map_err(|e| ValidationError::libvirt(format!("failed to destroy VM {}", vm_id), e))
     }
 
     async fn list(&self) -> Result<Vec<VmInfo>> {
@@ -138,7 +138,7 @@ impl VmProvisioner for LibvirtProvisioner {
             .backend
             .list_nodes("default")
             .await
-            .map_err(|e| ValidationError::generic(format!("Failed to list VMs: {}", e)))?;
+            .map_err(|e| ValidationError::libvirt("failed to list VMs", e))?;
 
         Ok(nodes
             .into_iter()
@@ -0,0 +1,268 @@
+//! Scriptable in-memory VM provisioner
+//!
+//! Stands in for a hypervisor so the orchestrator can be exercised without
+//! KVM. Provisioning delay, the IP handed out and injected failures are
+//! configured up front, which keeps runs deterministic.
+//!
+//! Like a hypervisor, the mock defines a VM as soon as provisioning starts
+//! and boots it once the delay has passed, so provisioning that is
+//! abandoned midway leaves a stopped VM behind.
+
+use crate::errors::{Result, ValidationError};
+use crate::providers::vm::{ProvisionedVm, VmInfo, VmProvisioner, VmSpec, VmStatus};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// Address handed out by default (TEST-NET-1, never routable)
+pub const MOCK_VM_IP: &str = "192.0.2.10";
+
+/// In-memory VM provisioner with scripted behavior
+#[derive(Debug)]
+pub struct MockVmProvisioner {
+    ip: Option<String>,
+    delay: Duration,
+    state: Mutex<MockState>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    failures_remaining: u32,
+    attempts: u32,
+    next_id: u32,
+    vms: BTreeMap<String, MockVm>,
+}
+
+#[derive(Debug)]
+struct MockVm {
+    name: String,
+    status: VmStatus,
+    /// Snapshot name to the status captured with it
+    snapshots: BTreeMap<String, VmStatus>,
+}
+
+impl MockVmProvisioner {
+    /// Create a provisioner that succeeds immediately with [`MOCK_VM_IP`]
+    pub fn new() -> Self {
+        Self {
+            ip: Some(MOCK_VM_IP.to_string()),
+            delay: Duration::ZERO,
+            state: Mutex::new(MockState::default()),
+        }
+    }
+
+    /// Hand out `ip` to provisioned VMs
+    pub fn with_ip(mut self, ip: impl Into<String>) -> Self {
+        self.ip = Some(ip.into());
+        self
+    }
+
+    /// Never assign an IP address, as if DHCP never answered
+    pub fn without_ip(mut self) -> Self {
+        self.ip = None;
+        self
+    }
+
+    /// Take `delay` to provision each VM
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Fail the next `times` provisioning attempts
+    pub fn failing(self, times: u32) -> Self {
+        self.state().failures_remaining = times;
+        self
+    }
+
+    /// Number of times [`VmProvisioner::provision`] has been called
+    pub fn provision_attempts(&self) -> u32 {
+        self.state().attempts
+    }
+
+    /// Record the VM's current status under `name`
+    pub fn snapshot(&self, vm_id: &str, name: &str) -> Result<()> {
+        let mut state = self.state();
+        let vm = Self::vm_mut(&mut state, vm_id)?;
+        vm.snapshots.insert(name.to_string(), vm.status);
+        Ok(())
+    }
+
+    /// Return the VM to the status recorded in snapshot `name`
+    pub fn restore_snapshot(&self, vm_id: &str, name: &str) -> Result<()> {
+        let mut state = self.state();
+        let vm = Self::vm_mut(&mut state, vm_id)?;
+        vm.status = *vm.snapshots.get(name).ok_or_else(|| {
+            ValidationError::generic(format!("VM {vm_id} has no snapshot '{name}'"))
+        })?;
+        Ok(())
+    }
+
+    /// Snapshot names of a VM, sorted
+    pub fn snapshots(&self, vm_id: &str) -> Vec<String> {
+        self.state()
+            .vms
+            .get(vm_id)
+            .map(|vm| vm.snapshots.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Change a VM's status, e.g. to simulate a crash
+    pub fn set_status(&self, vm_id: &str, status: VmStatus) -> Result<()> {
+        Self::vm_mut(&mut self.state(), vm_id)?.status = status;
+        Ok(())
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn vm_mut<'a>(state: &'a mut MockState, vm_id: &str) -> Result<&'a mut MockVm> {
+        state
+            .vms
+            .get_mut(vm_id)
+            .ok_or_else(|| ValidationError::VmNotFound {
+                vm_id: vm_id.to_string(),
+            })
+    }
+}
+
+impl Default for MockVmProvisioner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl VmProvisioner for MockVmProvisioner {
+    async fn provision(&self, spec: VmSpec) -> Result<ProvisionedVm> {
+        self.provision_reporting(spec, &|_| {}).await
+    }
+
+    async fn provision_reporting(
+        &self,
+        spec: VmSpec,
+        created: &(dyn Fn(String) + Send + Sync),
+    ) -> Result<ProvisionedVm> {
+        let defined = {
+            let mut state = self.state();
+            state.attempts += 1;
+            (state.failures_remaining == 0).then(|| {
+                state.next_id += 1;
+                let id = format!("mock-vm-{}", state.next_id);
+                state.vms.insert(
+                    id.clone(),
+                    MockVm {
+                        name: spec.name.clone(),
+                        status: VmStatus::Stopped,
+                        snapshots: BTreeMap::new(),
+                    },
+                );
+                id
+            })
+        };
+        if let Some(id) = &defined {
+            created(id.clone());
+        }
+        tokio::time::sleep(self.delay).await;
+
+        let mut state = self.state();
+        let Some(id) = defined else {
+            state.failures_remaining -= 1;
+            return Err(ValidationError::VmProvisioningFailed {
+                reason: format!("scripted failure ({} remaining)", state.failures_remaining),
+            });
+        };
+        Self::vm_mut(&mut state, &id)?.status = VmStatus::Running;
+
+        Ok(ProvisionedVm {
+            id,
+            name: spec.name,
+            ip: self.ip.clone(),
+            ssh_port: 22,
+            status: VmStatus::Running,
+        })
+    }
+
+    async fn get_status(&self, vm_id: &str) -> Result<VmStatus> {
+        Ok(Self::vm_mut(&mut self.state(), vm_id)?.status)
+    }
+
+    async fn get_ip(&self, vm_id: &str) -> Result<String> {
+        Self::vm_mut(&mut self.state(), vm_id)?;
+        self.ip
+            .clone()
+            .ok_or_else(|| ValidationError::generic(format!("VM {vm_id} has no IP address yet")))
+    }
+
+    async fn destroy(&self, vm_id: &str) -> Result<()> {
+        self.state()
+            .vms
+            .remove(vm_id)
+            .map(|_| ())
+            .ok_or_else(|| ValidationError::VmNotFound {
+                vm_id: vm_id.to_string(),
+            })
+    }
+
+    async fn list(&self) -> Result<Vec<VmInfo>> {
+        Ok(self
+            .state()
+            .vms
+            .iter()
+            .map(|(id, vm)| VmInfo {
+                id: id.clone(),
+                name: vm.name.clone(),
+                status: vm.status,
+            })
+            .collect())
+    }
+
+    async fn is_available(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scripted_failures_then_success() {
+        let mock = MockVmProvisioner::new().failing(1);
+
+        let err = mock.provision(VmSpec::default()).await.unwrap_err();
+        assert!(matches!(err, ValidationError::VmProvisioningFailed { .. }));
+
+        let vm = mock.provision(VmSpec::default()).await.unwrap();
+        assert_eq!(vm.ip.as_deref(), Some(MOCK_VM_IP));
+        assert_eq!(mock.provision_attempts(), 2);
+        assert_eq!(mock.list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_restore_and_destroy() {
+        let mock = MockVmProvisioner::new().without_ip();
+        let vm = mock.provision(VmSpec::default()).await.unwrap();
+        assert!(vm.ip.is_none());
+        assert!(mock.get_ip(&vm.id).await.is_err());
+
+        mock.snapshot(&vm.id, "clean").unwrap();
+        mock.set_status(&vm.id, VmStatus::Stopped).unwrap();
+        mock.restore_snapshot(&vm.id, "clean").unwrap();
+        assert_eq!(mock.get_status(&vm.id).await.unwrap(), VmStatus::Running);
+        assert_eq!(mock.snapshots(&vm.id), ["clean"]);
+        assert!(mock.restore_snapshot(&vm.id, "missing").is_err());
+
+        mock.destroy(&vm.id).await.unwrap();
+        assert!(matches!(
+            mock.get_status(&vm.id).await,
+            Err(ValidationError::VmNotFound { .. })
+        ));
+    }
+}
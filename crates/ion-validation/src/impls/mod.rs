@@ -1,5 +1,8 @@
 //! Concrete implementations of capability traits
 
+pub mod agent_latency;
+pub mod mock_provisioner;
+
 #[cfg(feature = "libvirt")]
 pub mod libvirt_provisioner;
 
@@ -12,6 +15,9 @@ pub mod rustdesk_provider;
 #[cfg(feature = "libvirt")]
 pub mod ionchannel_deployer;
 
+pub use agent_latency::AgentLatencyProbe;
+pub use mock_provisioner::MockVmProvisioner;
+
 #[cfg(feature = "libvirt")]
 pub use libvirt_provisioner::*;
 
@@ -53,11 +53,7 @@ impl RustDeskProvider {
 
         SshClient::connect(&target.host, target.port, &target.username, &password)
             .await
-            .map_err(|e| ValidationError::SshConnectionFailed {
-                host: target.host.clone(),
-                port: target.port,
-                reason: format!("{:?}", e),
-            })
+            .map_err(|e| ValidationError::ssh(&target.host, target.port, "connection failed", e))
     }
 
     /// Execute SSH command and get result
@@ -51,24 +51,29 @@ pub mod capabilities;
 pub mod errors;
 pub mod events;
 pub mod orchestrator;
+pub mod impls;
 pub mod providers;
 
-#[cfg(feature = "libvirt")]
-pub mod impls;
+#[cfg(feature = "frame-encryption")]
+pub mod frame_crypto;
 
 #[cfg(feature = "mcp")]
 pub mod mcp;
 
 // Re-exports for convenience
-pub use errors::{Result, ValidationError};
+pub use errors::{ErrorContext, Result, ValidationError};
 pub use events::ValidationEvent;
-pub use orchestrator::{ValidationOrchestrator, ValidationPlan};
+pub use orchestrator::{PlanPreview, PlannedStep, ValidationOrchestrator, ValidationPlan};
 
 /// Prelude module for convenient imports
 pub mod prelude {
     pub use crate::capabilities::*;
-    pub use crate::errors::{Result, ValidationError};
+    pub use crate::errors::{ErrorContext, Result, ValidationError};
     pub use crate::events::*;
     pub use crate::orchestrator::{ValidationOrchestrator, ValidationPlan};
-    pub use crate::providers::{desktop::RemoteDesktop, portal::PortalDeployer, vm::VmProvisioner};
+    pub use crate::providers::{
+        clipboard::ClipboardRoundTripProbe, consent::ConsentEnforcementProbe,
+        desktop::RemoteDesktop, latency::InputLatencyProbe, portal::PortalDeployer,
+        vm::VmProvisioner,
+    };
 }
@@ -21,6 +21,12 @@
 //! // - "stream_events": Subscribe to validation events
 //! // - "discover_capabilities": List available validation types
 //! ```
+//!
+//! # Frame Transport Security
+//!
+//! Where capture frames cross a transport that is not TLS-terminated, each
+//! session can opt into ChaCha20-Poly1305 framing with the
+//! `frame-encryption` feature; see `frame_crypto`.
 
 use crate::ValidationOrchestrator;
 use serde::{Deserialize, Serialize};
@@ -1,22 +1,40 @@
 //! Validation orchestrator - coordinates the validation process
 
-use crate::capabilities::CapabilityRegistry;
+use crate::capabilities::{
+    CapabilityRegistry, E2E_VERIFICATION_CAPABILITY, PORTAL_DEPLOYMENT_CAPABILITY,
+    REMOTE_DESKTOP_CAPABILITY, VM_PROVISIONING_CAPABILITY,
+};
 use crate::errors::{Result, ValidationError};
 use crate::events::{ValidationEvent, ValidationMetrics};
+use crate::impls::AgentLatencyProbe;
 use crate::providers::{
+    clipboard::{self, ClipboardRoundTripProbe},
+    consent::{self, ConsentEnforcementProbe},
     desktop::{SshAuth, Target},
-    portal::DeployConfig,
-    vm::VmSpec,
+    latency::{self, InputLatencyProbe},
+    portal::{DeployConfig, Deployment, PortalDeployer},
+    vm::{ProvisionedVm, VmProvisioner, VmSpec},
 };
 use chrono::Utc;
 use futures::stream::Stream;
+use serde::Serialize;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+/// Default time to wait for a provisioned VM to get an IP address
+pub const DEFAULT_IP_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Latency samples taken when the step is requested by capability name
+const DEFAULT_LATENCY_SAMPLES: u32 = 20;
+
+/// How often to ask the provisioner for a pending IP address
+const IP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Validation orchestrator
 ///
 /// Coordinates the end-to-end validation process with observable execution
@@ -43,21 +61,60 @@ impl ValidationOrchestrator {
     pub async fn execute(
         &self,
         plan: ValidationPlan,
+    ) -> Result<Pin<Box<dyn Stream<Item = ValidationEvent> + Send>>> {
+        self.execute_with_cancellation(plan, CancellationToken::new())
+            .await
+    }
+
+    /// Execute validation plan until it completes or `cancel` fires
+    ///
+    /// Cancelling aborts the step in flight, closes the portal sessions of
+    /// a probe in progress, stops a portal the run deployed and destroys
+    /// the VMs it created, including one whose provisioning was cut short.
+    /// VMs the provisioner did not report as created by the run are left
+    /// alone. The stream then ends with [`ValidationEvent::Cancelled`].
+    pub async fn execute_with_cancellation(
+        &self,
+        plan: ValidationPlan,
+        cancel: CancellationToken,
     ) -> Result<Pin<Box<dyn Stream<Item = ValidationEvent> + Send>>> {
         let (tx, rx) = mpsc::unbounded_channel();
         let registry = Arc::clone(&self.registry);
 
         tokio::spawn(async move {
-            if let Err(e) = execute_validation(registry, plan, tx.clone()).await {
-                error!("Validation failed: {:?}", e);
-                let _ = tx.send(ValidationEvent::Error {
-                    timestamp: Utc::now(),
-                    phase: 0,
-                    error_type: "ExecutionError".to_string(),
-                    message: format!("{:?}", e),
-                    retryable: e.is_retryable(),
-                    suggestion: e.suggestion(),
-                });
+            let mut run = RunState::default();
+            let outcome = {
+                let validation = execute_validation(registry, plan, tx.clone(), &mut run);
+                tokio::select! {
+                    biased;
+                    () = cancel.cancelled() => None,
+                    result = validation => Some(result),
+                }
+            };
+
+            match outcome {
+                Some(Ok(())) => {}
+                Some(Err(e)) => {
+                    error!("Validation failed: {:?}", e);
+                    let _ = tx.send(ValidationEvent::Error {
+                        timestamp: Utc::now(),
+                        phase: 0,
+                        error_type: "ExecutionError".to_string(),
+                        message: format!("{:?}", e),
+                        retryable: e.is_retryable(),
+                        suggestion: e.suggestion(),
+                        chain: e.chain_messages(),
+                    });
+                }
+                None => {
+                    let at_step = run.step.to_string();
+                    warn!("Validation cancelled during {}", at_step);
+                    run.clean_up(&tx).await;
+                    let _ = tx.send(ValidationEvent::Cancelled {
+                        timestamp: Utc::now(),
+                        at_step,
+                    });
+                }
             }
         });
 
@@ -65,6 +122,153 @@ impl ValidationOrchestrator {
             tokio_stream::wrappers::UnboundedReceiverStream::new(rx),
         ))
     }
+
+    /// Preview the steps a plan would run, without running any of them
+    ///
+    /// These are the steps [`execute`](Self::execute) runs, in the same
+    /// order.
+    /// Steps are ordered so each comes after its prerequisites. Providers
+    /// are the ones registered for each capability; their availability is
+    /// not checked, so nothing touches real infrastructure. A cyclic plan
+    /// yields the steps that could be ordered plus the cycle.
+    pub fn dry_plan(&self, plan: &ValidationPlan) -> PlanPreview {
+        let (order, cycle) = resolve_steps(&plan.steps());
+
+        let steps: Vec<PlannedStep> = order
+            .into_iter()
+            .map(|(capability, prerequisites)| PlannedStep {
+                providers: self
+                    .registry
+                    .provider_names(&capability)
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect(),
+                capability,
+                prerequisites,
+            })
+            .collect();
+
+        let provisions_vm = steps
+            .iter()
+            .any(|s| s.capability == VM_PROVISIONING_CAPABILITY);
+        let unsupported = steps
+            .iter()
+            .filter(|s| phase(&s.capability).is_none())
+            .map(|s| s.capability.clone())
+            .collect();
+
+        PlanPreview {
+            steps,
+            cycle,
+            unsupported,
+            vm: provisions_vm.then(|| plan.vm_spec.clone()),
+        }
+    }
+}
+
+/// What a [`ValidationPlan`] would do, from [`ValidationOrchestrator::dry_plan`]
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanPreview {
+    /// Steps in execution order
+    pub steps: Vec<PlannedStep>,
+    /// Capabilities forming a dependency cycle, first repeated at the end
+    pub cycle: Option<Vec<String>>,
+    /// Steps no phase of the orchestrator runs
+    pub unsupported: Vec<String>,
+    /// VM that would be provisioned, if any
+    pub vm: Option<VmSpec>,
+}
+
+impl PlanPreview {
+    /// Whether executing the plan would run these steps
+    ///
+    /// A plan whose steps can't be ordered, or that has steps the
+    /// orchestrator can't run, is rejected before anything is provisioned.
+    pub fn is_executable(&self) -> bool {
+        self.cycle.is_none() && self.unsupported.is_empty()
+    }
+
+    /// Capability names in execution order
+    pub fn order(&self) -> Vec<&str> {
+        self.steps.iter().map(|s| s.capability.as_str()).collect()
+    }
+}
+
+/// One step of a [`PlanPreview`]
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedStep {
+    /// Capability the step exercises
+    pub capability: String,
+    /// Registered providers, in the order discovery would try them
+    pub providers: Vec<String>,
+    /// Capabilities that must complete first
+    pub prerequisites: Vec<String>,
+}
+
+/// A capability with the capabilities it waits on
+type Step = (String, Vec<String>);
+
+/// Order steps after their prerequisites, keeping declaration order
+/// otherwise
+///
+/// Prerequisites not declared as steps are added. Returns the ordered
+/// steps with their prerequisites, and the first cycle found, if any.
+fn resolve_steps(steps: &[Step]) -> (Vec<Step>, Option<Vec<String>>) {
+    let mut pending: Vec<Step> = Vec::new();
+    for (capability, prerequisites) in steps {
+        for name in std::iter::once(capability).chain(prerequisites) {
+            if !pending.iter().any(|(c, _)| c == name) {
+                pending.push((name.clone(), Vec::new()));
+            }
+        }
+        let entry = pending
+            .iter_mut()
+            .find(|(c, _)| c == capability)
+            .expect("just inserted");
+        for prerequisite in prerequisites {
+            if !entry.1.contains(prerequisite) {
+                entry.1.push(prerequisite.clone());
+            }
+        }
+    }
+
+    let mut order: Vec<Step> = Vec::new();
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|(_, prerequisites)| {
+            prerequisites
+                .iter()
+                .all(|p| order.iter().any(|(done, _)| done == p))
+        });
+        match ready {
+            Some(index) => order.push(pending.remove(index)),
+            None => return (order, Some(find_cycle(&pending))),
+        }
+    }
+    (order, None)
+}
+
+/// Follow unresolved prerequisites from the first pending step until one
+/// repeats
+fn find_cycle(pending: &[Step]) -> Vec<String> {
+    let mut path: Vec<String> = Vec::new();
+    let mut current = pending[0].0.clone();
+    loop {
+        if let Some(start) = path.iter().position(|c| *c == current) {
+            let mut cycle = path.split_off(start);
+            cycle.push(current);
+            return cycle;
+        }
+        path.push(current.clone());
+        let (_, prerequisites) = pending
+            .iter()
+            .find(|(c, _)| *c == current)
+            .expect("pending steps only wait on pending steps");
+        current = prerequisites
+            .iter()
+            .find(|p| pending.iter().any(|(c, _)| c == *p))
+            .expect("a blocked step has a pending prerequisite")
+            .clone();
+    }
 }
 
 impl Default for ValidationOrchestrator {
@@ -73,14 +277,170 @@ impl Default for ValidationOrchestrator {
     }
 }
 
+/// What a run has set up so far, undone if it is cancelled
+struct RunState {
+    /// Capability of the step in progress
+    step: &'static str,
+    provisioner: Option<Arc<dyn VmProvisioner>>,
+    /// IDs of the VMs the run created, reported by the provisioner as soon
+    /// as each exists
+    created_vms: Arc<std::sync::Mutex<Vec<String>>>,
+    /// Portal the run deployed
+    deployment: Option<(Arc<dyn PortalDeployer>, Deployment)>,
+    /// Probe of the step in progress, whose sessions may still be open
+    session_probe: Option<SessionProbe>,
+}
+
+/// A probe that opens portal sessions
+enum SessionProbe {
+    Consent(Arc<dyn ConsentEnforcementProbe>),
+    Clipboard(Arc<dyn ClipboardRoundTripProbe>),
+}
+
+impl SessionProbe {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Consent(probe) => probe.name(),
+            Self::Clipboard(probe) => probe.name(),
+        }
+    }
+
+    async fn close_sessions(&self) -> Result<()> {
+        match self {
+            Self::Consent(probe) => probe.close_sessions().await,
+            Self::Clipboard(probe) => probe.close_sessions().await,
+        }
+    }
+}
+
+impl Default for RunState {
+    fn default() -> Self {
+        Self {
+            step: VM_PROVISIONING_CAPABILITY,
+            provisioner: None,
+            created_vms: Arc::default(),
+            deployment: None,
+            session_probe: None,
+        }
+    }
+}
+
+impl RunState {
+    /// Provision a VM with `provisioner`, recording it as the run's as
+    /// soon as it exists
+    async fn provision(
+        &mut self,
+        provisioner: &Arc<dyn VmProvisioner>,
+        spec: VmSpec,
+    ) -> Result<ProvisionedVm> {
+        self.provisioner = Some(Arc::clone(provisioner));
+        let created_vms = Arc::clone(&self.created_vms);
+        provisioner
+            .provision_reporting(spec, &move |id| {
+                created_vms
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .push(id);
+            })
+            .await
+    }
+
+    /// Close the sessions of the probe in progress, stop the deployed
+    /// portal and destroy the VMs the run created
+    ///
+    /// Failures are reported as warnings so the rest still gets cleaned up.
+    async fn clean_up(self, tx: &mpsc::UnboundedSender<ValidationEvent>) {
+        let warn_about = |message: String, e: ValidationError| {
+            warn!("{}: {}", message, e);
+            tx.send(ValidationEvent::Warning {
+                timestamp: Utc::now(),
+                message,
+                context: Some(e.to_string()),
+            })
+            .ok();
+        };
+
+        if let Some(probe) = &self.session_probe {
+            if let Err(e) = probe.close_sessions().await {
+                warn_about(format!("Failed to close sessions of {}", probe.name()), e);
+            }
+        }
+
+        if let Some((deployer, deployment)) = &self.deployment {
+            if let Err(e) = deployer.stop(deployment).await {
+                warn_about(format!("Failed to stop portal deployment {}", deployment.id), e);
+            }
+        }
+
+        let Some(provisioner) = self.provisioner else {
+            return;
+        };
+        let vm_ids = std::mem::take(
+            &mut *self
+                .created_vms
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner),
+        );
+        for vm_id in vm_ids {
+            match provisioner.destroy(&vm_id).await {
+                Ok(()) => info!("Destroyed VM {} of cancelled validation", vm_id),
+                Err(e) => warn_about(format!("Failed to destroy VM {}", vm_id), e),
+            }
+        }
+    }
+}
+
+/// Counts completed steps against the plan's resolved step count
+struct ProgressTracker {
+    total: usize,
+    completed: usize,
+    started: Instant,
+}
+
+impl ProgressTracker {
+    fn new(total: usize) -> Self {
+        Self {
+            total,
+            completed: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Record a completed step and report overall progress
+    ///
+    /// Call once per step, after any retries, so the count never exceeds
+    /// the total.
+    fn complete_step(&mut self, tx: &mpsc::UnboundedSender<ValidationEvent>) {
+        self.completed = (self.completed + 1).min(self.total);
+        let remaining = self.total - self.completed;
+        let per_step = self.started.elapsed().as_secs_f64() / self.completed as f64;
+
+        tx.send(ValidationEvent::Progress {
+            timestamp: Utc::now(),
+            completed_steps: self.completed,
+            total_steps: self.total,
+            percent: (self.completed * 100 / self.total) as u8,
+            eta: Some(Duration::from_secs_f64(per_step * remaining as f64)),
+        })
+        .ok();
+    }
+}
+
 /// Execute validation with event streaming
+///
+/// Runs the plan's resolved steps in order, the same steps
+/// [`ValidationOrchestrator::dry_plan`] previews. Records what it sets up
+/// in `run`, so a cancelled run can be cleaned up.
 async fn execute_validation(
     registry: Arc<CapabilityRegistry>,
     plan: ValidationPlan,
     tx: mpsc::UnboundedSender<ValidationEvent>,
+    run: &mut RunState,
 ) -> Result<()> {
     let start_time = Instant::now();
     let plan_id = Uuid::new_v4().to_string();
+    let steps = plan.executable_steps()?;
+    let mut progress = ProgressTracker::new(steps.len());
 
     // Start event
     tx.send(ValidationEvent::Started {
@@ -89,74 +449,92 @@ async fn execute_validation(
     })
     .ok();
 
-    // Phase 1: VM Provisioning
-    info!("Phase 1: VM Provisioning");
-    let provisioning_start = Instant::now();
+    let mut provisioned_vm: Option<ProvisionedVm> = None;
+    let mut retries = 0;
+    let mut rustdesk_id = "UNAVAILABLE".to_string();
+    let mut provisioning_duration = Duration::from_secs(0);
+    let mut installation_duration = Duration::from_secs(0);
+    let mut deployment_duration = Duration::from_secs(0);
+    let mut verification_duration = Duration::from_secs(0);
+
+    for step in steps {
+        run.step = step;
+        match step {
+            VM_PROVISIONING_CAPABILITY => {
+                info!("Phase 1: VM Provisioning");
+                let provisioning_start = Instant::now();
 
-    tx.send(ValidationEvent::ProvisioningStarted {
-        timestamp: Utc::now(),
-        vm_name: plan.vm_spec.name.clone(),
-    })
-    .ok();
+                tx.send(ValidationEvent::ProvisioningStarted {
+                    timestamp: Utc::now(),
+                    vm_name: plan.vm_spec.name.clone(),
+                })
+                .ok();
 
-    let vm_provisioner = registry.discover_vm_provisioner().await?;
-    let provisioned_vm = vm_provisioner.provision(plan.vm_spec).await?;
+                let vm_provisioner = registry.discover_vm_provisioner().await?;
+                let mut vm = loop {
+                    match run.provision(&vm_provisioner, plan.vm_spec.clone()).await {
+                        Ok(vm) => break vm,
+                        Err(e) if retries + 1 < plan.provision_attempts => {
+                            retries += 1;
+                            warn!("Provisioning attempt {} failed, retrying: {}", retries, e);
+                            tx.send(ValidationEvent::Warning {
+                                timestamp: Utc::now(),
+                                message: format!(
+                                    "Provisioning attempt {} failed, retrying",
+                                    retries
+                                ),
+                                context: Some(e.to_string()),
+                            })
+                            .ok();
+                        },
+                        Err(e) => return Err(e),
+                    }
+                };
 
-    let provisioning_duration = provisioning_start.elapsed();
+                if vm.ip.is_none() {
+                    let ip = wait_for_ip(vm_provisioner.as_ref(), &vm.id, plan.ip_timeout).await?;
+                    vm.ip = Some(ip);
+                }
 
-    tx.send(ValidationEvent::VmProvisioned {
-        timestamp: Utc::now(),
-        vm_id: provisioned_vm.id.clone(),
-        vm_name: provisioned_vm.name.clone(),
-        ip: provisioned_vm.ip.clone().unwrap_or_default(),
-        duration: provisioning_duration,
-    })
-    .ok();
+                provisioning_duration = provisioning_start.elapsed();
 
-    tx.send(ValidationEvent::PhaseComplete {
-        timestamp: Utc::now(),
-        phase: 1,
-        phase_name: "VM Provisioning".to_string(),
-        duration: provisioning_duration,
-    })
-    .ok();
+                tx.send(ValidationEvent::VmProvisioned {
+                    timestamp: Utc::now(),
+                    vm_id: vm.id.clone(),
+                    vm_name: vm.name.clone(),
+                    ip: vm.ip.clone().unwrap_or_default(),
+                    duration: provisioning_duration,
+                })
+                .ok();
 
-    // Phase 2: Remote Desktop Installation
-    let mut rustdesk_id = "UNAVAILABLE".to_string();
-    let mut installation_duration = Duration::from_secs(0);
-    
-    if plan.install_remote_desktop {
-        info!("Phase 2: Remote Desktop Installation");
-        let install_start = Instant::now();
-
-        // Create Target from provisioned VM
-        let vm_ip = provisioned_vm.ip.clone().ok_or_else(|| {
-            ValidationError::generic("VM has no IP address")
-        })?;
-
-        let target = Target {
-            host: vm_ip.clone(),
-            port: provisioned_vm.ssh_port,
-            username: plan.ssh_username.clone()
-                .unwrap_or_else(|| std::env::var("VM_SSH_USER").unwrap_or_else(|_| "ubuntu".to_string())),
-            auth: SshAuth::Password {
-                password: plan.ssh_password.clone()
-                    .unwrap_or_else(|| std::env::var("VM_SSH_PASSWORD").unwrap_or_else(|_| "changeme".to_string())),
+                tx.send(ValidationEvent::PhaseComplete {
+                    timestamp: Utc::now(),
+                    phase: 1,
+                    phase_name: "VM Provisioning".to_string(),
+                    duration: provisioning_duration,
+                })
+                .ok();
+                provisioned_vm = Some(vm);
             },
-        };
+            REMOTE_DESKTOP_CAPABILITY => {
+                info!("Phase 2: Remote Desktop Installation");
+                let install_start = Instant::now();
+                let target = ssh_target(&plan, provisioned_vm.as_ref())?;
 
-        tx.send(ValidationEvent::InstallingPackage {
-            timestamp: Utc::now(),
-            package: "rustdesk".to_string(),
-        })
-        .ok();
+                tx.send(ValidationEvent::InstallingPackage {
+                    timestamp: Utc::now(),
+                    package: "rustdesk".to_string(),
+                })
+                .ok();
+
+                let remote_desktop = registry.discover_remote_desktop().await?;
 
-        let remote_desktop = registry.discover_remote_desktop().await?;
-        
-        match remote_desktop.install(&target).await {
-            Ok(installation) => {
+                let installation = remote_desktop.install(&target).await.map_err(|e| {
+                    error!("Failed to install RustDesk: {:?}", e);
+                    e
+                })?;
                 info!("RustDesk installed: version {}", installation.version);
-                
+
                 tx.send(ValidationEvent::PackageInstalled {
                     timestamp: Utc::now(),
                     package: "rustdesk".to_string(),
@@ -169,72 +547,56 @@ async fn execute_validation(
                     Ok(id) => {
                         rustdesk_id = id.clone();
                         info!("RustDesk ID: {}", id);
-                        
+
                         tx.send(ValidationEvent::RemoteDesktopReady {
                             timestamp: Utc::now(),
                             desktop_id: id,
                         })
                         .ok();
-                    }
+                    },
                     Err(e) => {
                         warn!("Failed to get RustDesk ID: {:?}", e);
                         rustdesk_id = "ERROR".to_string();
-                    }
+                    },
                 }
-            }
-            Err(e) => {
-                error!("Failed to install RustDesk: {:?}", e);
-                return Err(e);
-            }
-        }
 
-        installation_duration = install_start.elapsed();
+                installation_duration = install_start.elapsed();
 
-        tx.send(ValidationEvent::PhaseComplete {
-            timestamp: Utc::now(),
-            phase: 2,
-            phase_name: "Remote Desktop Installation".to_string(),
-            duration: installation_duration,
-        })
-        .ok();
-    }
-
-    // Phase 3: Portal Deployment
-    let mut deployment_duration = Duration::from_secs(0);
-    let mut phases_completed = if plan.install_remote_desktop { 2 } else { 1 };
-    
-    if plan.deploy_portal {
-        info!("Phase 3: Portal Deployment");
-        let deploy_start = Instant::now();
-
-        let vm_ip = provisioned_vm.ip.clone().ok_or_else(|| {
-            ValidationError::generic("VM has no IP address")
-        })?;
-
-        let target = Target {
-            host: vm_ip,
-            port: provisioned_vm.ssh_port,
-            username: plan.ssh_username.clone()
-                .unwrap_or_else(|| std::env::var("VM_SSH_USER").unwrap_or_else(|_| "ubuntu".to_string())),
-            auth: SshAuth::Password {
-                password: plan.ssh_password.clone()
-                    .unwrap_or_else(|| std::env::var("VM_SSH_PASSWORD").unwrap_or_else(|_| "changeme".to_string())),
+                tx.send(ValidationEvent::PhaseComplete {
+                    timestamp: Utc::now(),
+                    phase: 2,
+                    phase_name: "Remote Desktop Installation".to_string(),
+                    duration: installation_duration,
+                })
+                .ok();
             },
-        };
+            PORTAL_DEPLOYMENT_CAPABILITY => {
+                info!("Phase 3: Portal Deployment");
+                let deploy_start = Instant::now();
+                let target = ssh_target(&plan, provisioned_vm.as_ref())?;
 
-        tx.send(ValidationEvent::DeployingPortal {
-            timestamp: Utc::now(),
-            target: target.host.clone(),
-        })
-        .ok();
+                tx.send(ValidationEvent::DeployingPortal {
+                    timestamp: Utc::now(),
+                    target: target.host.clone(),
+                })
+                .ok();
+
+                let portal_deployer = registry.discover_portal_deployer().await?;
+                let deploy_config = plan.deploy_config.clone().unwrap_or_default();
 
-        let portal_deployer = registry.discover_portal_deployer().await?;
-        let deploy_config = plan.deploy_config.unwrap_or_default();
+                let deployment = portal_deployer
+                    .deploy(&target, deploy_config)
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to deploy portal: {:?}", e);
+                        e
+                    })?;
+                info!(
+                    "Portal deployed successfully: {} services",
+                    deployment.services.len()
+                );
+                run.deployment = Some((Arc::clone(&portal_deployer), deployment.clone()));
 
-        match portal_deployer.deploy(&target, deploy_config).await {
-            Ok(deployment) => {
-                info!("Portal deployed successfully: {} services", deployment.services.len());
-                
                 tx.send(ValidationEvent::PortalDeployed {
                     timestamp: Utc::now(),
                     deployment_id: deployment.id.clone(),
@@ -242,57 +604,181 @@ async fn execute_validation(
                 })
                 .ok();
 
-                // Phase 4: Verification
-                if plan.verify_e2e {
-                    info!("Phase 4: E2E Verification");
-                    let verify_start = Instant::now();
+                let registration = portal_deployer
+                    .verify_registration(&deployment)
+                    .await
+                    .map_err(|e| e.context("verifying portal registration"))?;
+                if let Some(missing) = registration.missing() {
+                    return Err(ValidationError::PortalNotRegistered { missing });
+                }
+                tx.send(ValidationEvent::PortalRegistered {
+                    timestamp: Utc::now(),
+                    bus_name: registration.bus_name,
+                    object_path: registration.object_path,
+                })
+                .ok();
+
+                deployment_duration = deploy_start.elapsed();
 
-                    match portal_deployer.verify(&deployment).await {
-                        Ok(health) => {
-                            info!("Portal verification: healthy={}", health.healthy);
-                            
-                            tx.send(ValidationEvent::VerificationComplete {
-                                timestamp: Utc::now(),
-                                success: health.healthy,
-                                details: health.details.unwrap_or_default(),
-                            })
-                            .ok();
-                        }
-                        Err(e) => {
-                            warn!("Portal verification failed: {:?}", e);
+                tx.send(ValidationEvent::PhaseComplete {
+                    timestamp: Utc::now(),
+                    phase: 3,
+                    phase_name: "Portal Deployment".to_string(),
+                    duration: deployment_duration,
+                })
+                .ok();
+            },
+            E2E_VERIFICATION_CAPABILITY => {
+                info!("Phase 4: E2E Verification");
+                let verify_start = Instant::now();
+                let (portal_deployer, deployment) = run
+                    .deployment
+                    .as_ref()
+                    .ok_or_else(|| ValidationError::generic("No portal deployed to verify"))?;
+
+                match portal_deployer.verify(deployment).await {
+                    Ok(health) => {
+                        info!("Portal verification: healthy={}", health.healthy);
+
+                        tx.send(ValidationEvent::VerificationComplete {
+                            timestamp: Utc::now(),
+                            success: health.healthy,
+                            details: health.details.unwrap_or_default(),
+                        })
+                        .ok();
+                    },
+                    Err(e) => {
+                        warn!("Portal verification failed: {:?}", e);
+                    },
+                }
+
+                verification_duration = verify_start.elapsed();
+
+                tx.send(ValidationEvent::PhaseComplete {
+                    timestamp: Utc::now(),
+                    phase: 4,
+                    phase_name: "E2E Verification".to_string(),
+                    duration: verification_duration,
+                })
+                .ok();
+            },
+            latency::INPUT_LATENCY_CAPABILITY => {
+                let samples = plan.latency_samples.unwrap_or(DEFAULT_LATENCY_SAMPLES);
+                info!("Phase 5: Input Latency ({} samples)", samples);
+                let latency_start = Instant::now();
+
+                let probe = match registry.discover_latency_probe().await {
+                    Ok(probe) => probe,
+                    // Nothing registered: measure through the agent on the VM itself
+                    Err(e) => {
+                        let agent = provisioned_vm
+                            .as_ref()
+                            .and_then(|vm| vm.ip.as_deref())
+                            .map(AgentLatencyProbe::for_vm);
+                        match agent {
+                            Some(agent) if agent.is_available().await => {
+                                info!("✓ Discovered latency probe: {}", agent.name());
+                                Arc::new(agent)
+                            },
+                            _ => return Err(e),
                         }
-                    }
+                    },
+                };
+                let report = latency::measure(probe.as_ref(), samples).await?;
 
-                    let verification_duration = verify_start.elapsed();
-                    
-                    tx.send(ValidationEvent::PhaseComplete {
-                        timestamp: Utc::now(),
-                        phase: 4,
-                        phase_name: "E2E Verification".to_string(),
-                        duration: verification_duration,
-                    })
-                    .ok();
+                if report.dropped > 0 {
+                    warn!(
+                        "{} of {} latency samples dropped",
+                        report.dropped,
+                        report.samples + report.dropped
+                    );
+                }
+
+                tx.send(ValidationEvent::LatencyMeasured {
+                    timestamp: Utc::now(),
+                    p50_ms: report.p50.as_secs_f64() * 1000.0,
+                    p95_ms: report.p95.as_secs_f64() * 1000.0,
+                    p99_ms: report.p99.as_secs_f64() * 1000.0,
+                    samples: report.samples,
+                    dropped: report.dropped,
+                    drop_rate: report.drop_rate(),
+                })
+                .ok();
+
+                tx.send(ValidationEvent::PhaseComplete {
+                    timestamp: Utc::now(),
+                    phase: 5,
+                    phase_name: "Input Latency".to_string(),
+                    duration: latency_start.elapsed(),
+                })
+                .ok();
+            },
+            consent::CONSENT_ENFORCEMENT_CAPABILITY => {
+                info!("Phase 6: Consent Enforcement");
+                let consent_start = Instant::now();
+
+                let probe = registry.discover_consent_probe().await?;
+                run.session_probe = Some(SessionProbe::Consent(Arc::clone(&probe)));
+                let report = consent::verify(probe.as_ref()).await?;
+                run.session_probe = None;
 
-                    phases_completed += 1;
+                tx.send(ValidationEvent::ConsentVerified {
+                    timestamp: Utc::now(),
+                    granted_path: report.granted_path(),
+                    denied_path: report.denied_path(),
+                    unattended_path: report.unattended_path(),
+                })
+                .ok();
+
+                if !report.passed() {
+                    return Err(ValidationError::ConsentNotEnforced {
+                        reason: format!("{} reported {:?}", probe.name(), report),
+                    });
                 }
-            }
-            Err(e) => {
-                error!("Failed to deploy portal: {:?}", e);
-                return Err(e);
-            }
-        }
 
-        deployment_duration = deploy_start.elapsed();
+                tx.send(ValidationEvent::PhaseComplete {
+                    timestamp: Utc::now(),
+                    phase: 6,
+                    phase_name: "Consent Enforcement".to_string(),
+                    duration: consent_start.elapsed(),
+                })
+                .ok();
+            },
+            clipboard::CLIPBOARD_ROUNDTRIP_CAPABILITY => {
+                info!("Phase 7: Clipboard Round-Trip");
+                let clipboard_start = Instant::now();
 
-        tx.send(ValidationEvent::PhaseComplete {
-            timestamp: Utc::now(),
-            phase: 3,
-            phase_name: "Portal Deployment".to_string(),
-            duration: deployment_duration,
-        })
-        .ok();
+                let probe = registry.discover_clipboard_probe().await?;
+                let payloads = clipboard::ClipboardPayload::standard_set();
+                run.session_probe = Some(SessionProbe::Clipboard(Arc::clone(&probe)));
+                let report = clipboard::verify(probe.as_ref(), &payloads).await?;
+                run.session_probe = None;
+
+                tx.send(ValidationEvent::ClipboardVerified {
+                    timestamp: Utc::now(),
+                    mimes_tested: report.mimes_tested(),
+                    max_size_ok: report.max_size_ok(),
+                    view_only_refused: report.view_only_refused,
+                })
+                .ok();
 
-        phases_completed += 1;
+                if !report.passed() {
+                    return Err(ValidationError::ClipboardRoundTripFailed {
+                        reason: format!("{} reported {:?}", probe.name(), report),
+                    });
+                }
+
+                tx.send(ValidationEvent::PhaseComplete {
+                    timestamp: Utc::now(),
+                    phase: 7,
+                    phase_name: "Clipboard Round-Trip".to_string(),
+                    duration: clipboard_start.elapsed(),
+                })
+                .ok();
+            },
+            _ => unreachable!("executable_steps only yields steps the orchestrator runs"),
+        }
+        progress.complete_step(&tx);
     }
 
     // Completion
@@ -301,14 +787,14 @@ async fn execute_validation(
         timestamp: Utc::now(),
         rustdesk_id,
         total_duration,
-        phases_completed,
+        phases_completed: u8::try_from(progress.completed).unwrap_or(u8::MAX),
         metrics: ValidationMetrics {
             total_duration,
             provisioning_duration,
             installation_duration,
             deployment_duration,
-            verification_duration: Duration::from_secs(0), // Included in deployment
-            retries: 0,
+            verification_duration,
+            retries,
             peak_memory_mb: None,
         },
     })
@@ -317,6 +803,53 @@ async fn execute_validation(
     Ok(())
 }
 
+/// SSH target for the provisioned VM, with the plan's credentials
+fn ssh_target(plan: &ValidationPlan, vm: Option<&ProvisionedVm>) -> Result<Target> {
+    let vm = vm.ok_or_else(|| ValidationError::generic("No VM provisioned"))?;
+    let vm_ip = vm
+        .ip
+        .clone()
+        .ok_or_else(|| ValidationError::generic("VM has no IP address"))?;
+
+    Ok(Target {
+        host: vm_ip,
+        port: vm.ssh_port,
+        username: plan.ssh_username.clone().unwrap_or_else(|| {
+            std::env::var("VM_SSH_USER").unwrap_or_else(|_| "ubuntu".to_string())
+        }),
+        auth: SshAuth::Password {
+            password: plan.ssh_password.clone().unwrap_or_else(|| {
+                std::env::var("VM_SSH_PASSWORD").unwrap_or_else(|_| "changeme".to_string())
+            }),
+        },
+    })
+}
+
+/// Poll the provisioner until the VM reports an IP address
+async fn wait_for_ip(
+    provisioner: &dyn VmProvisioner,
+    vm_id: &str,
+    timeout: Duration,
+) -> Result<String> {
+    let poll = async {
+        loop {
+            match provisioner.get_ip(vm_id).await {
+                Ok(ip) if !ip.is_empty() => return ip,
+                Ok(_) => {}
+                Err(e) => info!("Waiting for IP of VM {}: {}", vm_id, e),
+            }
+            tokio::time::sleep(IP_POLL_INTERVAL).await;
+        }
+    };
+
+    tokio::time::timeout(timeout, poll)
+        .await
+        .map_err(|_| ValidationError::Timeout {
+            operation: format!("waiting for an IP address for VM {}", vm_id),
+            duration_ms: u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX),
+        })
+}
+
 /// Validation plan builder
 #[derive(Debug, Clone)]
 pub struct ValidationPlan {
@@ -327,6 +860,17 @@ pub struct ValidationPlan {
     pub ssh_username: Option<String>,
     pub ssh_password: Option<String>,
     pub deploy_config: Option<DeployConfig>,
+    pub latency_samples: Option<u32>,
+    pub verify_consent: bool,
+    pub verify_clipboard: bool,
+    /// Extra capabilities requested by name
+    pub capabilities: Vec<String>,
+    /// Declared `(capability, prerequisite)` dependencies
+    pub prerequisites: Vec<(String, String)>,
+    /// Provisioning attempts before giving up, at least 1
+    pub provision_attempts: u32,
+    /// How long to wait for a provisioned VM to get an IP address
+    pub ip_timeout: Duration,
 }
 
 impl ValidationPlan {
@@ -334,6 +878,83 @@ impl ValidationPlan {
     pub fn builder() -> ValidationPlanBuilder {
         ValidationPlanBuilder::default()
     }
+
+    /// Steps with their prerequisites, in phase order then declaration order
+    fn steps(&self) -> Vec<Step> {
+        let mut steps = self.phase_steps();
+        for capability in &self.capabilities {
+            let prerequisites = phase(capability)
+                .map(|(_, prerequisites)| prerequisites.iter().map(|p| p.to_string()).collect())
+                .unwrap_or_default();
+            steps.push((capability.clone(), prerequisites));
+        }
+        for (capability, prerequisite) in &self.prerequisites {
+            steps.push((capability.clone(), vec![prerequisite.clone()]));
+        }
+        steps
+    }
+
+    /// Steps of the phases the plan enables
+    fn phase_steps(&self) -> Vec<Step> {
+        let enabled = [
+            true,
+            self.install_remote_desktop,
+            self.deploy_portal,
+            self.deploy_portal && self.verify_e2e,
+            self.latency_samples.is_some(),
+            self.verify_consent,
+            self.verify_clipboard,
+        ];
+        PHASES
+            .iter()
+            .zip(enabled)
+            .filter(|(_, enabled)| *enabled)
+            .map(|((capability, prerequisites), _)| {
+                let prerequisites = prerequisites.iter().map(|p| p.to_string()).collect();
+                (capability.to_string(), prerequisites)
+            })
+            .collect()
+    }
+
+    /// Capabilities of the resolved steps, in execution order
+    ///
+    /// Fails if the steps form a cycle or one has no phase to run it.
+    fn executable_steps(&self) -> Result<Vec<&'static str>> {
+        let (order, cycle) = resolve_steps(&self.steps());
+        if let Some(cycle) = cycle {
+            return Err(ValidationError::InvalidConfiguration {
+                field: "prerequisites".to_string(),
+                reason: format!("steps form a cycle: {}", cycle.join(" -> ")),
+            });
+        }
+        order
+            .into_iter()
+            .map(|(capability, _)| match phase(&capability) {
+                Some((name, _)) => Ok(name),
+                None => Err(ValidationError::InvalidConfiguration {
+                    field: "capabilities".to_string(),
+                    reason: format!("no phase runs {}", capability),
+                }),
+            })
+            .collect()
+    }
+}
+
+/// Capabilities the orchestrator runs itself, in phase order, with the
+/// capabilities each waits on
+const PHASES: [(&str, &[&str]); 7] = [
+    (VM_PROVISIONING_CAPABILITY, &[]),
+    (REMOTE_DESKTOP_CAPABILITY, &[VM_PROVISIONING_CAPABILITY]),
+    (PORTAL_DEPLOYMENT_CAPABILITY, &[VM_PROVISIONING_CAPABILITY]),
+    (E2E_VERIFICATION_CAPABILITY, &[PORTAL_DEPLOYMENT_CAPABILITY]),
+    (latency::INPUT_LATENCY_CAPABILITY, &[]),
+    (consent::CONSENT_ENFORCEMENT_CAPABILITY, &[]),
+    (clipboard::CLIPBOARD_ROUNDTRIP_CAPABILITY, &[]),
+];
+
+/// The phase running `capability`, if the orchestrator has one
+fn phase(capability: &str) -> Option<(&'static str, &'static [&'static str])> {
+    PHASES.iter().copied().find(|(name, _)| *name == capability)
 }
 
 /// Builder for validation plans
@@ -346,6 +967,13 @@ pub struct ValidationPlanBuilder {
     ssh_username: Option<String>,
     ssh_password: Option<String>,
     deploy_config: Option<DeployConfig>,
+    latency_samples: Option<u32>,
+    verify_consent: bool,
+    verify_clipboard: bool,
+    capabilities: Vec<String>,
+    prerequisites: Vec<(String, String)>,
+    provision_attempts: Option<u32>,
+    ip_timeout: Option<Duration>,
 }
 
 impl ValidationPlanBuilder {
@@ -386,9 +1014,49 @@ impl ValidationPlanBuilder {
         self
     }
 
-    /// Add a capability requirement (for compatibility)
-    pub fn with_capability(self, _capability: &str) -> Self {
-        // Capabilities are automatically discovered
+    /// Enable input latency measurement over `samples` round trips
+    pub fn with_latency_measurement(mut self, samples: u32) -> Self {
+        self.latency_samples = Some(samples);
+        self
+    }
+
+    /// Enable verification that the portal prompts for and honors consent
+    pub fn with_consent_verification(mut self) -> Self {
+        self.verify_consent = true;
+        self
+    }
+
+    /// Enable verification that clipboard content survives the portal
+    pub fn with_clipboard_verification(mut self) -> Self {
+        self.verify_clipboard = true;
+        self
+    }
+
+    /// Add a capability requirement
+    ///
+    /// Providers are still discovered automatically. The capability runs as
+    /// a step, so it must be one the orchestrator has a phase for.
+    pub fn with_capability(mut self, capability: &str) -> Self {
+        self.capabilities.push(capability.to_string());
+        self
+    }
+
+    /// Declare that `capability` must run after `prerequisite`
+    pub fn with_prerequisite(mut self, capability: &str, prerequisite: &str) -> Self {
+        self.prerequisites
+            .push((capability.to_string(), prerequisite.to_string()));
+        self
+    }
+
+    /// Retry failed provisioning, making up to `attempts` attempts in total
+    pub fn with_provision_attempts(mut self, attempts: u32) -> Self {
+        self.provision_attempts = Some(attempts);
+        self
+    }
+
+    /// Wait up to `timeout` for a provisioned VM to get an IP address
+    pub fn with_ip_timeout(mut self, timeout: Duration) -> Self {
+        self.ip_timeout = Some(timeout);
         self
     }
 
@@ -402,6 +1070,13 @@ impl ValidationPlanBuilder {
             ssh_username: self.ssh_username,
             ssh_password: self.ssh_password,
             deploy_config: self.deploy_config,
+            latency_samples: self.latency_samples,
+            verify_consent: self.verify_consent,
+            verify_clipboard: self.verify_clipboard,
+            capabilities: self.capabilities,
+            prerequisites: self.prerequisites,
+            provision_attempts: self.provision_attempts.unwrap_or(1).max(1),
+            ip_timeout: self.ip_timeout.unwrap_or(DEFAULT_IP_TIMEOUT),
         })
     }
 }
@@ -409,6 +1084,49 @@ impl ValidationPlanBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::impls::mock_provisioner::{MockVmProvisioner, MOCK_VM_IP};
+    use async_trait::async_trait;
+    use futures::StreamExt;
+
+    /// Latency probe acknowledging every sample after a fixed delay
+    struct FixedLatency;
+
+    #[async_trait]
+    impl InputLatencyProbe for FixedLatency {
+        async fn sample(&self, _sequence: u32) -> Result<Option<Duration>> {
+            Ok(Some(Duration::from_millis(8)))
+        }
+
+        async fn is_available(&self) -> bool {
+            true
+        }
+
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+    }
+
+    async fn run(
+        provisioner: Arc<MockVmProvisioner>,
+        plan: ValidationPlan,
+    ) -> Vec<ValidationEvent> {
+        let mut registry = CapabilityRegistry::new();
+        registry.register_vm_provisioner(provisioner);
+        registry.register_latency_probe(Arc::new(FixedLatency));
+        ValidationOrchestrator::with_registry(registry)
+            .execute(plan)
+            .await
+            .unwrap()
+            .collect()
+            .await
+    }
+
+    fn retries(events: &[ValidationEvent]) -> Option<u32> {
+        events.iter().find_map(|e| match e {
+            ValidationEvent::Complete { metrics, .. } => Some(metrics.retries),
+            _ => None,
+        })
+    }
 
     #[test]
     fn test_plan_builder() {
@@ -419,4 +1137,375 @@ mod tests {
 
         assert!(plan.install_remote_desktop);
     }
+
+    #[tokio::test]
+    async fn test_full_plan_through_mock_provisioner() {
+        let mock = Arc::new(MockVmProvisioner::new().with_delay(Duration::from_millis(20)));
+        let plan = ValidationPlan::builder()
+            .with_latency_measurement(5)
+            .build()
+            .unwrap();
+
+        let events = run(Arc::clone(&mock), plan).await;
+
+        assert!(matches!(events[0], ValidationEvent::Started { .. }));
+        assert!(matches!(
+            events[1],
+            ValidationEvent::ProvisioningStarted { .. }
+        ));
+        match &events[2] {
+            ValidationEvent::VmProvisioned { ip, duration, .. } => {
+                assert_eq!(ip, MOCK_VM_IP);
+                assert!(*duration >= Duration::from_millis(20));
+            }
+            other => panic!("expected VmProvisioned, got {:?}", other),
+        }
+        assert!(events.iter().any(|e| matches!(
+            e,
+            ValidationEvent::LatencyMeasured { samples: 5, .. }
+        )));
+        match events.last().unwrap() {
+            ValidationEvent::Complete {
+                phases_completed, ..
+            } => assert_eq!(*phases_completed, 2),
+            other => panic!("expected Complete, got {:?}", other),
+        }
+        assert!(!events.iter().any(ValidationEvent::is_error));
+        assert_eq!(mock.provision_attempts(), 1);
+        assert_eq!(mock.list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_provisioning_failure_recovers_on_retry() {
+        let mock = Arc::new(MockVmProvisioner::new().failing(2));
+        let plan = ValidationPlan::builder()
+            .with_provision_attempts(3)
+            .build()
+            .unwrap();
+
+        let events = run(Arc::clone(&mock), plan).await;
+
+        let warnings = events
+            .iter()
+            .filter(|e| matches!(e, ValidationEvent::Warning { .. }))
+            .count();
+        assert_eq!(warnings, 2);
+        assert_eq!(retries(&events), Some(2));
+        assert_eq!(mock.provision_attempts(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_progress_counts_resolved_steps_once() {
+        let mock = Arc::new(MockVmProvisioner::new().failing(1));
+        let plan = ValidationPlan::builder()
+            .with_provision_attempts(2)
+            .with_latency_measurement(3)
+            .build()
+            .unwrap();
+
+        let events = run(mock, plan).await;
+
+        let progress: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                ValidationEvent::Progress {
+                    completed_steps,
+                    total_steps,
+                    percent,
+                    eta,
+                    ..
+                } => Some((*completed_steps, *total_steps, *percent, *eta)),
+                _ => None,
+            })
+            .collect();
+        // The retried provisioning step counts once
+        assert_eq!(progress.len(), 2);
+        assert!(progress.iter().all(|&(_, total, _, _)| total == 2));
+        assert!(progress.windows(2).all(|w| w[0].2 < w[1].2));
+        assert!(progress.iter().all(|&(_, _, _, eta)| eta.is_some()));
+        assert_eq!(progress.last().unwrap().2, 100);
+        assert_eq!(progress.last().unwrap().3, Some(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_provisioning_failure_without_retries_errors() {
+        let mock = Arc::new(MockVmProvisioner::new().failing(1));
+        let plan = ValidationPlan::builder().build().unwrap();
+
+        let events = run(Arc::clone(&mock), plan).await;
+
+        match events.last().unwrap() {
+            ValidationEvent::Error { message, .. } => {
+                assert!(message.contains("VmProvisioningFailed"));
+            }
+            other => panic!("expected Error, got {:?}", other),
+        }
+        assert_eq!(retries(&events), None);
+        assert_eq!(mock.provision_attempts(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unassigned_ip_times_out() {
+        let mock = Arc::new(MockVmProvisioner::new().without_ip());
+        let plan = ValidationPlan::builder()
+            .with_ip_timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+
+        let events = run(mock, plan).await;
+
+        match events.last().unwrap() {
+            ValidationEvent::Error {
+                message, retryable, ..
+            } => {
+                assert!(message.contains("Timeout"));
+                assert!(*retryable);
+            }
+            other => panic!("expected Error, got {:?}", other),
+        }
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, ValidationEvent::VmProvisioned { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_mid_provision_destroys_half_provisioned_vm() {
+        let mock = Arc::new(MockVmProvisioner::new().with_delay(Duration::from_secs(30)));
+        // Left behind by an earlier run; not this run's to destroy
+        let abandoned = mock.provision(VmSpec::default());
+        assert!(tokio::time::timeout(Duration::from_millis(1), abandoned)
+            .await
+            .is_err());
+        let earlier = mock.list().await.unwrap();
+        assert_eq!(earlier.len(), 1);
+
+        let mut registry = CapabilityRegistry::new();
+        registry.register_vm_provisioner(mock.clone());
+        let cancel = CancellationToken::new();
+        let mut events = ValidationOrchestrator::with_registry(registry)
+            .execute_with_cancellation(ValidationPlan::builder().build().unwrap(), cancel.clone())
+            .await
+            .unwrap();
+
+        // Cancel once the run's VM is defined but not yet booted
+        while mock.provision_attempts() < 2 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(mock.list().await.unwrap().len(), 2);
+        // A concurrent run defines a VM of the same name; not ours either
+        let concurrent = mock.provision(VmSpec::default());
+        assert!(tokio::time::timeout(Duration::from_millis(1), concurrent)
+            .await
+            .is_err());
+        let before_cancel = mock.list().await.unwrap();
+        assert_eq!(before_cancel.len(), 3);
+        cancel.cancel();
+
+        let mut seen = Vec::new();
+        while let Some(event) = events.next().await {
+            seen.push(event);
+        }
+        assert!(matches!(seen[0], ValidationEvent::Started { .. }));
+        match seen.last().unwrap() {
+            ValidationEvent::Cancelled { at_step, .. } => {
+                assert_eq!(at_step, VM_PROVISIONING_CAPABILITY);
+            }
+            other => panic!("expected Cancelled, got {:?}", other),
+        }
+        assert!(!seen.iter().any(|e| matches!(
+            e,
+            ValidationEvent::VmProvisioned { .. }
+                | ValidationEvent::Warning { .. }
+                | ValidationEvent::Error { .. }
+        )));
+
+        let remaining: Vec<String> = mock
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|vm| vm.id)
+            .collect();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining.contains(&earlier[0].id));
+        assert!(remaining.contains(&before_cancel[2].id));
+    }
+
+    /// Consent probe whose sessions stay open until closed
+    #[derive(Default)]
+    struct HangingConsent {
+        started: std::sync::atomic::AtomicBool,
+        closed: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait]
+    impl ConsentEnforcementProbe for HangingConsent {
+        async fn exercise(
+            &self,
+            _decision: consent::ConsentDecision,
+        ) -> Result<Option<consent::ConsentPathOutcome>> {
+            self.started.store(true, std::sync::atomic::Ordering::SeqCst);
+            std::future::pending().await
+        }
+
+        async fn close_sessions(&self) -> Result<()> {
+            self.closed.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn is_available(&self) -> bool {
+            true
+        }
+
+        fn name(&self) -> &'static str {
+            "hanging"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_mid_consent_closes_probe_sessions_and_destroys_vm() {
+        let mock = Arc::new(MockVmProvisioner::new());
+        let probe = Arc::new(HangingConsent::default());
+        let mut registry = CapabilityRegistry::new();
+        registry.register_vm_provisioner(mock.clone());
+        registry.register_consent_probe(probe.clone());
+        let cancel = CancellationToken::new();
+        let plan = ValidationPlan::builder()
+            .with_consent_verification()
+            .build()
+            .unwrap();
+        let events = ValidationOrchestrator::with_registry(registry)
+            .execute_with_cancellation(plan, cancel.clone())
+            .await
+            .unwrap();
+
+        while !probe.started.load(std::sync::atomic::Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        cancel.cancel();
+        let seen: Vec<ValidationEvent> = events.collect().await;
+
+        match seen.last().unwrap() {
+            ValidationEvent::Cancelled { at_step, .. } => {
+                assert_eq!(at_step, consent::CONSENT_ENFORCEMENT_CAPABILITY);
+            }
+            other => panic!("expected Cancelled, got {:?}", other),
+        }
+        assert!(probe.closed.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(mock.list().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dry_plan_orders_prerequisites_first() {
+        let plan = ValidationPlan::builder()
+            .with_capability("remote-desktop")
+            .with_prerequisite("remote-desktop", "vm-provisioning")
+            .with_portal()
+            .with_verification()
+            .build()
+            .unwrap();
+
+        let preview = ValidationOrchestrator::new().dry_plan(&plan);
+
+        assert!(preview.is_executable());
+        assert_eq!(
+            preview.order(),
+            [
+                "vm-provisioning",
+                "portal-deployment",
+                "e2e-verification",
+                "remote-desktop"
+            ]
+        );
+        let remote = &preview.steps[3];
+        assert_eq!(remote.prerequisites, ["vm-provisioning"]);
+        assert!(remote.providers.is_empty());
+        assert_eq!(preview.vm.unwrap().name, "iontest");
+    }
+
+    #[test]
+    fn test_dry_plan_adds_undeclared_prerequisites() {
+        let plan = ValidationPlan::builder()
+            .with_prerequisite("wayland-portal", "remote-desktop")
+            .with_prerequisite("remote-desktop", "vm-provisioning")
+            .build()
+            .unwrap();
+
+        let preview = ValidationOrchestrator::new().dry_plan(&plan);
+
+        assert_eq!(
+            preview.order(),
+            ["vm-provisioning", "remote-desktop", "wayland-portal"]
+        );
+        assert_eq!(preview.unsupported, ["wayland-portal"]);
+        assert!(!preview.is_executable());
+    }
+
+    #[tokio::test]
+    async fn test_execution_follows_dry_plan_order() {
+        let mock = Arc::new(MockVmProvisioner::new());
+        let plan = ValidationPlan::builder()
+            .with_latency_measurement(3)
+            .with_prerequisite(
+                VM_PROVISIONING_CAPABILITY,
+                latency::INPUT_LATENCY_CAPABILITY,
+            )
+            .build()
+            .unwrap();
+
+        let preview = ValidationOrchestrator::new().dry_plan(&plan);
+        assert!(preview.is_executable());
+        assert_eq!(
+            preview.order(),
+            [
+                latency::INPUT_LATENCY_CAPABILITY,
+                VM_PROVISIONING_CAPABILITY
+            ]
+        );
+
+        let events = run(mock, plan).await;
+        let executed: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                ValidationEvent::PhaseComplete { phase, .. } => Some(PHASES[*phase as usize - 1].0),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(executed, preview.order());
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_step_fails_before_provisioning() {
+        let mock = Arc::new(MockVmProvisioner::new());
+        let plan = ValidationPlan::builder()
+            .with_capability("wayland-portal")
+            .build()
+            .unwrap();
+
+        let events = run(Arc::clone(&mock), plan).await;
+
+        assert!(matches!(
+            events.last(),
+            Some(ValidationEvent::Error { message, .. }) if message.contains("wayland-portal")
+        ));
+        assert_eq!(mock.provision_attempts(), 0);
+    }
+
+    #[test]
+    fn test_dry_plan_surfaces_cycle() {
+        let plan = ValidationPlan::builder()
+            .with_prerequisite("remote-desktop", "wayland-portal")
+            .with_prerequisite("wayland-portal", "remote-desktop")
+            .build()
+            .unwrap();
+
+        let preview = ValidationOrchestrator::new().dry_plan(&plan);
+
+        assert!(!preview.is_executable());
+        assert_eq!(preview.order(), ["vm-provisioning"]);
+        assert_eq!(
+            preview.cycle.unwrap(),
+            ["remote-desktop", "wayland-portal", "remote-desktop"]
+        );
+    }
 }
@@ -6,10 +6,13 @@
 
 use async_trait::async_trait;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::debug;
 
-use crate::errors::Result;
+use crate::errors::{Result, ValidationError};
 
 /// A capability that a VM backend can provide
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -47,6 +50,12 @@ pub enum VmType {
     Unknown,
 }
 
+/// Identifier of a registered provider, as returned by [`VmBackendProvider::id`]
+pub type ProviderId = String;
+
+/// Default interval for [`VmBackendRegistry::start_status_polling`]
+pub const DEFAULT_STATUS_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
 /// A VM backend provider that can be discovered at runtime
 #[async_trait]
 pub trait VmBackendProvider: Send + Sync {
@@ -99,14 +108,115 @@ pub struct ResourceStatus {
     pub networks: Vec<String>,
 }
 
+impl ResourceStatus {
+    /// Share of VM capacity not currently running (0.0 - 1.0)
+    ///
+    /// A provider reporting no VMs at all has no known free capacity.
+    pub fn free_capacity(&self) -> f64 {
+        let total = self.vms_available + self.vms_running;
+        if total == 0 {
+            0.0
+        } else {
+            self.vms_available as f64 / total as f64
+        }
+    }
+}
+
+/// Weights used to score providers in [`VmBackendRegistry::best_for`]
+///
+/// Each term is normalized to 0.0 - 1.0 before weighting. With the
+/// defaults, health outweighs capacity and coverage combined, so a
+/// degraded provider never outranks a healthy one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthWeighting {
+    /// Weight for being healthy (scaled down by reported warnings)
+    pub health: f64,
+    /// Weight for free VM capacity
+    pub capacity: f64,
+    /// Weight for offering capabilities beyond those required
+    pub coverage: f64,
+    /// Health lost per reported warning (capped at half)
+    pub warning_penalty: f64,
+    /// Age at which a polled status counts for half its health weight
+    pub status_half_life: Duration,
+}
+
+impl Default for HealthWeighting {
+    fn default() -> Self {
+        Self {
+            health: 4.0,
+            capacity: 1.0,
+            coverage: 1.0,
+            warning_penalty: 0.1,
+            status_half_life: Duration::from_secs(30),
+        }
+    }
+}
+
+impl HealthWeighting {
+    /// Health term: 0.0 when unhealthy, reduced by warnings otherwise
+    fn health_score(&self, health: &ProviderHealth) -> f64 {
+        if !health.healthy {
+            return 0.0;
+        }
+        let penalty = (health.warnings.len() as f64 * self.warning_penalty).min(0.5);
+        1.0 - penalty
+    }
+
+    /// Share of the health term still trusted for a status `age` old
+    fn freshness(&self, age: Duration) -> f64 {
+        if self.status_half_life.is_zero() {
+            return 1.0;
+        }
+        0.5_f64.powf(age.as_secs_f64() / self.status_half_life.as_secs_f64())
+    }
+
+    /// Weighted score for a provider that covers every required capability
+    ///
+    /// `age` is how long ago `health` was confirmed; only the health term
+    /// decays with it, so a stale healthy report still beats an unhealthy
+    /// one but loses to a fresh healthy one.
+    fn score(&self, health: &ProviderHealth, extra_coverage: f64, age: Duration) -> f64 {
+        self.health * self.health_score(health) * self.freshness(age)
+            + self.capacity * health.resources.free_capacity()
+            + self.coverage * extra_coverage
+    }
+}
+
+/// Last polled status of a provider
+#[derive(Debug, Clone)]
+struct CachedStatus {
+    /// Health report, or why the check failed
+    health: std::result::Result<ProviderHealth, String>,
+    /// When the check completed
+    at: Instant,
+}
+
+type StatusCache = Arc<RwLock<HashMap<ProviderId, CachedStatus>>>;
+
+/// Background task refreshing the status cache; stopped when dropped
+struct StatusPoller(JoinHandle<()>);
+
+impl Drop for StatusPoller {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
 /// Registry for VM backend providers
 ///
 /// Backends register themselves with their capabilities, and consumers
 /// query by capability rather than by concrete type. This is primal
 /// discovery - backends have only self-knowledge and are discovered at runtime.
+///
+/// Provider health can be polled in the background with
+/// [`start_status_polling`](Self::start_status_polling), so selection works
+/// from recent reports rather than querying every provider on demand.
 #[derive(Clone)]
 pub struct VmBackendRegistry {
     providers: Arc<RwLock<Vec<Arc<dyn VmBackendProvider>>>>,
+    status: StatusCache,
+    poller: Arc<Mutex<Option<StatusPoller>>>,
 }
 
 impl VmBackendRegistry {
@@ -114,9 +224,73 @@ impl VmBackendRegistry {
     pub fn new() -> Self {
         Self {
             providers: Arc::new(RwLock::new(Vec::new())),
+            status: Arc::new(RwLock::new(HashMap::new())),
+            poller: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Poll every provider's health every `interval` in the background
+    ///
+    /// The first poll runs immediately. Replaces any poller already
+    /// running; the poller stops with [`stop_status_polling`](Self::stop_status_polling)
+    /// or when the last clone of the registry is dropped. Must be called
+    /// from within a Tokio runtime.
+    pub fn start_status_polling(&self, interval: Duration) {
+        let providers = Arc::clone(&self.providers);
+        let status = Arc::clone(&self.status);
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                poll_status(&providers, &status).await;
+            }
+        });
+        let mut poller = self.poller.lock().unwrap_or_else(PoisonError::into_inner);
+        *poller = Some(StatusPoller(handle));
+    }
+
+    /// Stop background status polling, keeping the last polled statuses
+    pub fn stop_status_polling(&self) {
+        self.poller
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+
+    /// Poll every provider's health once, now
+    pub async fn refresh_status(&self) {
+        poll_status(&self.providers, &self.status).await;
+    }
+
+    /// Last polled status of each provider, in registration order
+    ///
+    /// Providers not polled yet are omitted. A failed health check is
+    /// reported as unhealthy, with the failure as a warning.
+    pub async fn provider_status(
+        &self,
+    ) -> Vec<(ProviderId, ProviderHealth, ResourceStatus, Instant)> {
+        let providers = self.providers.read().await;
+        let status = self.status.read().await;
+        providers
+            .iter()
+            .filter_map(|provider| {
+                let cached = status.get(provider.id())?;
+                let health = match &cached.health {
+                    Ok(health) => health.clone(),
+                    Err(e) => ProviderHealth {
+                        healthy: false,
+                        version: None,
+                        warnings: vec![format!("health check failed: {e}")],
+                        resources: ResourceStatus::default(),
+                    },
+                };
+                let resources = health.resources.clone();
+                Some((provider.id().to_string(), health, resources, cached.at))
+            })
+            .collect()
+    }
+
     /// Register a VM backend provider
     pub async fn register(&self, provider: Arc<dyn VmBackendProvider>) {
         let mut providers = self.providers.write().await;
@@ -213,6 +387,112 @@ impl VmBackendRegistry {
             .map(|(_, provider, _)| provider)
     }
 
+    /// Pick the provider best suited to `required` capabilities
+    ///
+    /// Only available providers covering every required capability are
+    /// considered. They are scored by [`ProviderHealth`], free capacity
+    /// from [`ResourceStatus`], and how many capabilities they offer
+    /// beyond `required`, as weighted by `prefer`. Ties go to the provider
+    /// registered first.
+    ///
+    /// Providers with a polled status are scored from it, the health term
+    /// decaying with its age; the others are health-checked on the spot.
+    ///
+    /// Returns [`ValidationError::NoVmProvisionerAvailable`] listing why
+    /// each provider was rejected when none qualifies.
+    pub async fn best_for(
+        &self,
+        required: &[VmCapability],
+        prefer: &HealthWeighting,
+    ) -> Result<Arc<dyn VmBackendProvider>> {
+        use futures::future::join_all;
+
+        let providers = self.providers.read().await;
+        let cached = self.status.read().await.clone();
+
+        let checks: Vec<_> = providers
+            .iter()
+            .map(|provider| {
+                let p = Arc::clone(provider);
+                let cached = cached.get(p.id()).cloned();
+                async move {
+                    let capabilities = p.capabilities();
+                    let missing: Vec<VmCapability> = required
+                        .iter()
+                        .filter(|c| !capabilities.contains(c))
+                        .cloned()
+                        .collect();
+                    if !missing.is_empty() {
+                        return (p, Err(format!("missing {missing:?}")));
+                    }
+                    if !p.is_available().await {
+                        return (p, Err("not available".to_string()));
+                    }
+                    let (health, age) = match cached {
+                        Some(status) => (status.health, status.at.elapsed()),
+                        None => (
+                            p.check_health().await.map_err(|e| e.to_string()),
+                            Duration::ZERO,
+                        ),
+                    };
+                    match health {
+                        Ok(health) => {
+                            let extra = capabilities.len().saturating_sub(required.len());
+                            (p, Ok((health, extra, age)))
+                        },
+                        Err(e) => (p, Err(format!("health check failed: {e}"))),
+                    }
+                }
+            })
+            .collect();
+
+        let results = join_all(checks).await;
+
+        let max_extra = results
+            .iter()
+            .filter_map(|(_, r)| r.as_ref().ok().map(|(_, extra, _)| *extra))
+            .max()
+            .unwrap_or(0);
+
+        let mut best: Option<(f64, Arc<dyn VmBackendProvider>)> = None;
+        let mut rejected = Vec::new();
+
+        for (provider, result) in results {
+            match result {
+                Ok((health, extra, age)) => {
+                    let extra_coverage = if max_extra == 0 {
+                        0.0
+                    } else {
+                        extra as f64 / max_extra as f64
+                    };
+                    let score = prefer.score(&health, extra_coverage, age);
+                    debug!(
+                        "VM backend {} scored {:.2} (healthy: {}, status age: {:?})",
+                        provider.id(),
+                        score,
+                        health.healthy,
+                        age
+                    );
+                    // Strictly greater keeps the earliest registration on ties
+                    let better = match &best {
+                        Some((best_score, _)) => score > *best_score,
+                        None => true,
+                    };
+                    if better {
+                        best = Some((score, provider));
+                    }
+                },
+                Err(reason) => rejected.push(format!("{}: {}", provider.id(), reason)),
+            }
+        }
+
+        best.map(|(_, provider)| provider)
+            .ok_or_else(|| ValidationError::NoVmProvisionerAvailable {
+                tried: rejected,
+                suggestion: format!("Register or start a VM backend providing {required:?}"),
+            })
+    }
+
     /// Create a provisioner from the best available provider
     pub async fn create_best_provisioner(&self) -> Result<Arc<dyn crate::providers::vm::VmProvisioner>> {
         let provider = self.find_best().await.ok_or_else(|| {
@@ -261,6 +541,34 @@ impl Default for VmBackendRegistry {
     }
 }
 
+/// Health-check every provider in parallel and cache the results
+async fn poll_status(providers: &RwLock<Vec<Arc<dyn VmBackendProvider>>>, status: &StatusCache) {
+    use futures::future::join_all;
+
+    let providers: Vec<_> = providers.read().await.iter().cloned().collect();
+    let checks = providers.iter().map(|p| async move {
+        let health = p.check_health().await.map_err(|e| e.to_string());
+        (
+            p.id().to_string(),
+            CachedStatus {
+                health,
+                at: Instant::now(),
+            },
+        )
+    });
+    let results = join_all(checks).await;
+
+    let mut status = status.write().await;
+    for (id, cached) in results {
+        debug!(
+            "Polled VM backend {} (healthy: {:?})",
+            id,
+            cached.health.as_ref().map(|h| h.healthy)
+        );
+        status.insert(id, cached);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -379,6 +687,296 @@ mod tests {
         assert_eq!(available[0].id(), "available");
     }
 
+    struct HealthMock {
+        id: String,
+        capabilities: Vec<VmCapability>,
+        healthy: bool,
+        resources: ResourceStatus,
+    }
+
+    #[async_trait]
+    impl VmBackendProvider for HealthMock {
+        fn id(&self) -> &str {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            &self.id
+        }
+
+        async fn is_available(&self) -> bool {
+            true
+        }
+
+        fn capabilities(&self) -> Vec<VmCapability> {
+            self.capabilities.clone()
+        }
+
+        fn vm_type(&self) -> VmType {
+            VmType::FullVirt
+        }
+
+        async fn check_health(&self) -> Result<ProviderHealth> {
+            Ok(ProviderHealth {
+                healthy: self.healthy,
+                version: None,
+                warnings: vec![],
+                resources: self.resources.clone(),
+            })
+        }
+
+        async fn create_provisioner(&self) -> Result<Arc<dyn VmProvisioner>> {
+            Err(crate::errors::ValidationError::generic("Mock provider"))
+        }
+    }
+
+    /// Provider whose health can be flipped while registered
+    struct TogglingMock {
+        id: String,
+        healthy: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    #[async_trait]
+    impl VmBackendProvider for TogglingMock {
+        fn id(&self) -> &str {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            &self.id
+        }
+
+        async fn is_available(&self) -> bool {
+            true
+        }
+
+        fn capabilities(&self) -> Vec<VmCapability> {
+            vec![VmCapability::ProvisionVm]
+        }
+
+        fn vm_type(&self) -> VmType {
+            VmType::FullVirt
+        }
+
+        async fn check_health(&self) -> Result<ProviderHealth> {
+            Ok(ProviderHealth {
+                healthy: self.healthy.load(std::sync::atomic::Ordering::SeqCst),
+                version: None,
+                warnings: vec![],
+                resources: resources(1, 1),
+            })
+        }
+
+        async fn create_provisioner(&self) -> Result<Arc<dyn VmProvisioner>> {
+            Err(crate::errors::ValidationError::generic("Mock provider"))
+        }
+    }
+
+    fn resources(vms_available: usize, vms_running: usize) -> ResourceStatus {
+        ResourceStatus {
+            vms_available,
+            vms_running,
+            networks: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_best_for_prefers_healthy_provider() {
+        let registry = VmBackendRegistry::new();
+
+        // Degraded but broader and with more free capacity
+        registry
+            .register(Arc::new(HealthMock {
+                id: "libvirt".to_string(),
+                capabilities: vec![
+                    VmCapability::ProvisionVm,
+                    VmCapability::SshAccess,
+                    VmCapability::SerialConsole,
+                    VmCapability::DiskOverlay,
+                ],
+                healthy: false,
+                resources: resources(10, 0),
+            }))
+            .await;
+        registry
+            .register(Arc::new(HealthMock {
+                id: "cloud".to_string(),
+                capabilities: vec![VmCapability::ProvisionVm, VmCapability::SshAccess],
+                healthy: true,
+                resources: resources(1, 3),
+            }))
+            .await;
+
+        let required = [VmCapability::ProvisionVm, VmCapability::SshAccess];
+        let best = registry
+            .best_for(&required, &HealthWeighting::default())
+            .await
+            .unwrap();
+        assert_eq!(best.id(), "cloud");
+
+        // Only libvirt has a serial console, so it wins despite its health
+        let best = registry
+            .best_for(&[VmCapability::SerialConsole], &HealthWeighting::default())
+            .await
+            .unwrap();
+        assert_eq!(best.id(), "libvirt");
+    }
+
+    #[tokio::test]
+    async fn test_best_for_uses_capacity_between_healthy_providers() {
+        let registry = VmBackendRegistry::new();
+
+        for (id, resources) in [("busy", resources(1, 9)), ("idle", resources(8, 2))] {
+            registry
+                .register(Arc::new(HealthMock {
+                    id: id.to_string(),
+                    capabilities: vec![VmCapability::ProvisionVm],
+                    healthy: true,
+                    resources,
+                }))
+                .await;
+        }
+
+        let best = registry
+            .best_for(&[VmCapability::ProvisionVm], &HealthWeighting::default())
+            .await
+            .unwrap();
+        assert_eq!(best.id(), "idle");
+    }
+
+    #[tokio::test]
+    async fn test_poller_reflects_provider_turning_unhealthy() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let registry = VmBackendRegistry::new();
+        let primary_healthy = Arc::new(AtomicBool::new(true));
+        registry
+            .register(Arc::new(TogglingMock {
+                id: "primary".to_string(),
+                healthy: Arc::clone(&primary_healthy),
+            }))
+            .await;
+        registry
+            .register(Arc::new(TogglingMock {
+                id: "backup".to_string(),
+                healthy: Arc::new(AtomicBool::new(true)),
+            }))
+            .await;
+
+        registry.start_status_polling(Duration::from_millis(20));
+        let best = |registry: VmBackendRegistry| async move {
+            registry
+                .best_for(&[VmCapability::ProvisionVm], &HealthWeighting::default())
+                .await
+                .unwrap()
+                .id()
+                .to_string()
+        };
+
+        // Wait for the first poll
+        while registry.provider_status().await.len() < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(best(registry.clone()).await, "primary");
+
+        primary_healthy.store(false, Ordering::SeqCst);
+        let flipped = Instant::now();
+        tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                let status = registry.provider_status().await;
+                if status[0].3 > flipped {
+                    assert_eq!(status[0].0, "primary");
+                    assert!(!status[0].1.healthy);
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("poller did not run again");
+        assert_eq!(best(registry.clone()).await, "backup");
+
+        // Polling stops with the registry
+        let poller = Arc::downgrade(&registry.poller);
+        drop(registry);
+        assert!(poller.upgrade().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_best_for_deprioritizes_stale_status() {
+        let registry = VmBackendRegistry::new();
+        for id in ["stale", "fresh"] {
+            registry
+                .register(Arc::new(TogglingMock {
+                    id: id.to_string(),
+                    healthy: Arc::new(std::sync::atomic::AtomicBool::new(true)),
+                }))
+                .await;
+        }
+        registry.refresh_status().await;
+
+        // Backdate the first provider's report by ten minutes
+        let Some(long_ago) = Instant::now().checked_sub(Duration::from_secs(600)) else {
+            return;
+        };
+        registry.status.write().await.get_mut("stale").unwrap().at = long_ago;
+
+        let best = registry
+            .best_for(&[VmCapability::ProvisionVm], &HealthWeighting::default())
+            .await
+            .unwrap();
+        assert_eq!(best.id(), "fresh");
+
+        // Without decay the earlier registration wins the tie
+        let no_decay = HealthWeighting {
+            status_half_life: Duration::ZERO,
+            ..HealthWeighting::default()
+        };
+        let best = registry
+            .best_for(&[VmCapability::ProvisionVm], &no_decay)
+            .await
+            .unwrap();
+        assert_eq!(best.id(), "stale");
+    }
+
+    #[tokio::test]
+    async fn test_best_for_reports_why_nothing_matched() {
+        let registry = VmBackendRegistry::new();
+
+        registry
+            .register(Arc::new(MockProvider {
+                id: "docker".to_string(),
+                name: "Docker".to_string(),
+                available: true,
+                capabilities: vec![VmCapability::ProvisionVm],
+                vm_type: VmType::Container,
+            }))
+            .await;
+        registry
+            .register(Arc::new(MockProvider {
+                id: "libvirt".to_string(),
+                name: "Libvirt".to_string(),
+                available: false,
+                capabilities: vec![VmCapability::ProvisionVm, VmCapability::SerialConsole],
+                vm_type: VmType::FullVirt,
+            }))
+            .await;
+
+        let err = registry
+            .best_for(&[VmCapability::SerialConsole], &HealthWeighting::default())
+            .await
+            .err()
+            .unwrap();
+        match err {
+            ValidationError::NoVmProvisionerAvailable { tried, .. } => {
+                assert_eq!(tried.len(), 2);
+                assert!(tried[0].starts_with("docker: missing"));
+                assert_eq!(tried[1], "libvirt: not available");
+            },
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
     #[tokio::test]
     async fn test_find_best() {
         let registry = VmBackendRegistry::new();
@@ -0,0 +1,202 @@
+//! Clipboard round-trip capability trait
+//!
+//! Checks that clipboard content crosses the portal unchanged: each
+//! payload is set through the portal's clipboard API and read back (or
+//! echoed by an on-VM agent), and must match byte for byte. A session
+//! without input must have its clipboard writes refused.
+
+use crate::errors::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Capability name used for discovery and error reporting
+pub const CLIPBOARD_ROUNDTRIP_CAPABILITY: &str = "clipboard-roundtrip";
+
+/// Size of the large payload, well past what fits inline in a message
+pub const LARGE_PAYLOAD_BYTES: usize = 4 * 1024 * 1024;
+
+/// Content sent through the clipboard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardPayload {
+    /// MIME type the content is offered as
+    pub mime_type: String,
+    /// The content itself
+    pub content: Vec<u8>,
+}
+
+impl ClipboardPayload {
+    /// Create a payload
+    pub fn new(mime_type: &str, content: Vec<u8>) -> Self {
+        Self {
+            mime_type: mime_type.to_string(),
+            content,
+        }
+    }
+
+    /// Text, a small image and a large binary payload
+    ///
+    /// The image is a PNG signature followed by every byte value, so
+    /// anything that treats it as text mangles it. The large payload has
+    /// [`LARGE_PAYLOAD_BYTES`] and must be passed by file descriptor.
+    pub fn standard_set() -> Vec<Self> {
+        let mut image = b"\x89PNG\r\n\x1a\n".to_vec();
+        image.extend((0..=255u8).cycle().take(4096));
+        let large = (0..LARGE_PAYLOAD_BYTES).map(|i| (i % 251) as u8).collect();
+
+        vec![
+            Self::new(
+                "text/plain;charset=utf-8",
+                "ionChannel clipboard ✓ round trip".into(),
+            ),
+            Self::new("image/png", image),
+            Self::new("application/octet-stream", large),
+        ]
+    }
+}
+
+/// Outcome of one payload's round trip
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundTrip {
+    /// MIME type of the payload
+    pub mime_type: String,
+    /// Bytes sent
+    pub size: usize,
+    /// The content read back matched byte for byte
+    pub matched: bool,
+}
+
+/// Universal trait for clipboard round-trip probes
+///
+/// The substrate hands the portal's output to an in-memory clipboard and
+/// reads it back; live targets have an on-VM agent echo the selection.
+#[async_trait]
+pub trait ClipboardRoundTripProbe: Send + Sync {
+    /// Set `payload` through the portal and return what is read back
+    async fn round_trip(&self, payload: &ClipboardPayload) -> Result<Vec<u8>>;
+
+    /// Try a clipboard write from a `ViewOnly` session
+    ///
+    /// Returns whether it was refused, or `Ok(None)` when the probe cannot
+    /// open such a session.
+    async fn view_only_write_refused(&self) -> Result<Option<bool>>;
+
+    /// Close the portal sessions the probe has open
+    ///
+    /// Called when a run is cancelled mid-step. The default does nothing,
+    /// for probes that keep no session open between calls.
+    async fn close_sessions(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Check if probe is available
+    async fn is_available(&self) -> bool;
+
+    /// Get probe name
+    fn name(&self) -> &'static str;
+}
+
+/// Result of round-tripping a set of payloads
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClipboardReport {
+    /// One entry per payload, in the order sent
+    pub round_trips: Vec<RoundTrip>,
+    /// Whether a `ViewOnly` write was refused, if it was tried
+    pub view_only_refused: Option<bool>,
+}
+
+impl ClipboardReport {
+    /// MIME types whose round trip matched
+    pub fn mimes_tested(&self) -> Vec<String> {
+        self.round_trips
+            .iter()
+            .filter(|r| r.matched)
+            .map(|r| r.mime_type.clone())
+            .collect()
+    }
+
+    /// The largest payload came back intact
+    pub fn max_size_ok(&self) -> bool {
+        self.round_trips
+            .iter()
+            .max_by_key(|r| r.size)
+            .is_some_and(|r| r.matched)
+    }
+
+    /// Every payload matched and no `ViewOnly` write got through
+    pub fn passed(&self) -> bool {
+        !self.round_trips.is_empty()
+            && self.round_trips.iter().all(|r| r.matched)
+            && self.view_only_refused != Some(false)
+    }
+}
+
+/// Round-trip every payload through a probe, then check `ViewOnly`
+pub async fn verify(
+    probe: &dyn ClipboardRoundTripProbe,
+    payloads: &[ClipboardPayload],
+) -> Result<ClipboardReport> {
+    let mut round_trips = Vec::with_capacity(payloads.len());
+    for payload in payloads {
+        let read_back = probe.round_trip(payload).await?;
+        round_trips.push(RoundTrip {
+            mime_type: payload.mime_type.clone(),
+            size: payload.content.len(),
+            matched: read_back == payload.content,
+        });
+    }
+    Ok(ClipboardReport {
+        round_trips,
+        view_only_refused: probe.view_only_write_refused().await?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trip(mime_type: &str, size: usize, matched: bool) -> RoundTrip {
+        RoundTrip {
+            mime_type: mime_type.to_string(),
+            size,
+            matched,
+        }
+    }
+
+    #[test]
+    fn test_report_tracks_largest_payload() {
+        let mut report = ClipboardReport {
+            round_trips: vec![trip("text/plain", 10, true), trip("image/png", 4096, true)],
+            view_only_refused: Some(true),
+        };
+        assert_eq!(report.mimes_tested(), ["text/plain", "image/png"]);
+        assert!(report.max_size_ok());
+        assert!(report.passed());
+
+        report
+            .round_trips
+            .push(trip("application/octet-stream", 1 << 22, false));
+        assert!(!report.max_size_ok());
+        assert!(!report.passed());
+        assert_eq!(report.mimes_tested().len(), 2);
+    }
+
+    #[test]
+    fn test_report_fails_when_view_only_write_succeeds() {
+        let report = ClipboardReport {
+            round_trips: vec![trip("text/plain", 10, true)],
+            view_only_refused: Some(false),
+        };
+        assert!(!report.passed());
+
+        assert!(!ClipboardReport::default().passed());
+    }
+
+    #[test]
+    fn test_standard_set_includes_large_payload() {
+        let payloads = ClipboardPayload::standard_set();
+        assert!(payloads
+            .iter()
+            .any(|p| p.content.len() == LARGE_PAYLOAD_BYTES));
+        assert!(payloads.iter().any(|p| p.mime_type == "image/png"));
+    }
+}
@@ -0,0 +1,177 @@
+//! Consent enforcement capability trait
+//!
+//! Checks that a portal actually asks for consent and honors the answer:
+//! a granted session starts and can inject input, a denied one can do
+//! neither.
+
+use crate::errors::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Capability name used for discovery and error reporting
+pub const CONSENT_ENFORCEMENT_CAPABILITY: &str = "consent-enforcement";
+
+/// How the consent dialog is answered for one session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConsentDecision {
+    /// The user grants access
+    Grant,
+    /// The user denies access
+    Deny,
+    /// Nobody answers the dialog
+    Unattended,
+}
+
+/// What a session could do after its consent dialog was answered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsentPathOutcome {
+    /// The portal was asked for consent
+    pub prompted: bool,
+    /// The session reached the active state
+    pub started: bool,
+    /// An injected event reached the compositor
+    pub input_delivered: bool,
+}
+
+impl ConsentPathOutcome {
+    /// Whether the portal behaved correctly for `decision`
+    pub fn honors(&self, decision: ConsentDecision) -> bool {
+        match decision {
+            ConsentDecision::Grant => self.prompted && self.started && self.input_delivered,
+            ConsentDecision::Deny | ConsentDecision::Unattended => {
+                self.prompted && !self.started && !self.input_delivered
+            },
+        }
+    }
+}
+
+/// Universal trait for consent enforcement probes
+///
+/// Implementations run one session through the portal with the consent
+/// dialog answered as requested. The substrate scripts the answer; live
+/// targets observe the real dialog being answered.
+#[async_trait]
+pub trait ConsentEnforcementProbe: Send + Sync {
+    /// Drive one session through the given consent decision
+    ///
+    /// Returns `Ok(None)` when the probe cannot produce that decision (for
+    /// example a live target where nobody is there to grant access).
+    async fn exercise(&self, decision: ConsentDecision) -> Result<Option<ConsentPathOutcome>>;
+
+    /// Close the portal sessions the probe has open
+    ///
+    /// Called when a run is cancelled mid-step. The default does nothing,
+    /// for probes that keep no session open between calls.
+    async fn close_sessions(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Check if probe is available
+    async fn is_available(&self) -> bool;
+
+    /// Get probe name
+    fn name(&self) -> &'static str;
+}
+
+/// Result of exercising every consent path a probe supports
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsentReport {
+    /// Outcome with consent granted
+    pub granted: Option<ConsentPathOutcome>,
+    /// Outcome with consent denied
+    pub denied: Option<ConsentPathOutcome>,
+    /// Outcome with the dialog left unanswered
+    pub unattended: Option<ConsentPathOutcome>,
+}
+
+impl ConsentReport {
+    /// Granted path was exercised and behaved correctly
+    pub fn granted_path(&self) -> bool {
+        Self::path_ok(self.granted, ConsentDecision::Grant)
+    }
+
+    /// Denied path was exercised and behaved correctly
+    pub fn denied_path(&self) -> bool {
+        Self::path_ok(self.denied, ConsentDecision::Deny)
+    }
+
+    /// Unattended path behaved correctly, or `None` if it wasn't exercised
+    pub fn unattended_path(&self) -> Option<bool> {
+        self.unattended
+            .map(|outcome| outcome.honors(ConsentDecision::Unattended))
+    }
+
+    /// Every exercised path behaved correctly and grant/deny were both covered
+    pub fn passed(&self) -> bool {
+        self.granted_path() && self.denied_path() && self.unattended_path() != Some(false)
+    }
+
+    fn path_ok(outcome: Option<ConsentPathOutcome>, decision: ConsentDecision) -> bool {
+        outcome.is_some_and(|outcome| outcome.honors(decision))
+    }
+}
+
+/// Exercise the grant, deny and unattended paths of a probe
+pub async fn verify(probe: &dyn ConsentEnforcementProbe) -> Result<ConsentReport> {
+    Ok(ConsentReport {
+        granted: probe.exercise(ConsentDecision::Grant).await?,
+        denied: probe.exercise(ConsentDecision::Deny).await?,
+        unattended: probe.exercise(ConsentDecision::Unattended).await?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRANTED: ConsentPathOutcome = ConsentPathOutcome {
+        prompted: true,
+        started: true,
+        input_delivered: true,
+    };
+
+    const REFUSED: ConsentPathOutcome = ConsentPathOutcome {
+        prompted: true,
+        started: false,
+        input_delivered: false,
+    };
+
+    #[test]
+    fn test_outcome_honors_decision() {
+        assert!(GRANTED.honors(ConsentDecision::Grant));
+        assert!(!GRANTED.honors(ConsentDecision::Deny));
+        assert!(REFUSED.honors(ConsentDecision::Deny));
+        assert!(REFUSED.honors(ConsentDecision::Unattended));
+        assert!(!REFUSED.honors(ConsentDecision::Grant));
+
+        // Never prompting is a failure even if nothing got through
+        let silent = ConsentPathOutcome::default();
+        assert!(!silent.honors(ConsentDecision::Deny));
+
+        // Denied but input still delivered
+        let leaky = ConsentPathOutcome {
+            input_delivered: true,
+            ..REFUSED
+        };
+        assert!(!leaky.honors(ConsentDecision::Deny));
+    }
+
+    #[test]
+    fn test_report_requires_grant_and_deny() {
+        let mut report = ConsentReport {
+            granted: Some(GRANTED),
+            denied: Some(REFUSED),
+            unattended: None,
+        };
+        assert!(report.passed());
+        assert_eq!(report.unattended_path(), None);
+
+        report.unattended = Some(GRANTED);
+        assert!(!report.passed());
+
+        report.unattended = None;
+        report.denied = None;
+        assert!(!report.denied_path());
+        assert!(!report.passed());
+    }
+}
@@ -0,0 +1,119 @@
+//! Input latency capability trait
+//!
+//! Measures the round trip from injecting a pointer motion to the
+//! compositor (or an on-VM agent) reporting that it received it.
+
+use crate::errors::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Capability name used for discovery and error reporting
+pub const INPUT_LATENCY_CAPABILITY: &str = "input-latency";
+
+/// Universal trait for input latency probes
+///
+/// Implementations inject a timestamped pointer motion and wait for the
+/// receiving side to acknowledge it. The substrate uses the mock
+/// compositor's event forwarding; live VMs use an on-VM agent through
+/// [`AgentLatencyProbe`](crate::impls::AgentLatencyProbe).
+#[async_trait]
+pub trait InputLatencyProbe: Send + Sync {
+    /// Inject one tagged pointer motion and measure its round trip
+    ///
+    /// Returns `Ok(None)` when the sample was dropped (for example because
+    /// it was rate-limited or never acknowledged).
+    async fn sample(&self, sequence: u32) -> Result<Option<Duration>>;
+
+    /// Check if probe is available
+    async fn is_available(&self) -> bool;
+
+    /// Get probe name
+    fn name(&self) -> &'static str;
+}
+
+/// Latency percentiles over a batch of samples
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyReport {
+    /// Median latency
+    pub p50: Duration,
+    /// 95th percentile latency
+    pub p95: Duration,
+    /// 99th percentile latency
+    pub p99: Duration,
+    /// Number of samples that were acknowledged
+    pub samples: usize,
+    /// Number of samples that were dropped
+    pub dropped: usize,
+}
+
+impl LatencyReport {
+    /// Build a report from raw samples, excluding dropped ones
+    pub fn from_samples(samples: &[Option<Duration>]) -> Self {
+        let mut measured: Vec<Duration> = samples.iter().flatten().copied().collect();
+        measured.sort_unstable();
+
+        Self {
+            p50: percentile(&measured, 50),
+            p95: percentile(&measured, 95),
+            p99: percentile(&measured, 99),
+            samples: measured.len(),
+            dropped: samples.len() - measured.len(),
+        }
+    }
+
+    /// Fraction of samples that were dropped (0.0 - 1.0)
+    pub fn drop_rate(&self) -> f64 {
+        let total = self.samples + self.dropped;
+        if total == 0 {
+            return 0.0;
+        }
+        self.dropped as f64 / total as f64
+    }
+}
+
+/// Nearest-rank percentile of an already sorted slice
+fn percentile(sorted: &[Duration], pct: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Collect `count` samples from a probe and summarize them
+pub async fn measure(probe: &dyn InputLatencyProbe, count: u32) -> Result<LatencyReport> {
+    let mut samples = Vec::with_capacity(count as usize);
+    for sequence in 0..count {
+        samples.push(probe.sample(sequence).await?);
+    }
+    Ok(LatencyReport::from_samples(&samples))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_exclude_dropped_samples() {
+        let mut samples: Vec<Option<Duration>> = (1..=100)
+            .map(|ms| Some(Duration::from_millis(ms)))
+            .collect();
+        samples.extend([None; 25]);
+
+        let report = LatencyReport::from_samples(&samples);
+        assert_eq!(report.p50, Duration::from_millis(50));
+        assert_eq!(report.p95, Duration::from_millis(95));
+        assert_eq!(report.p99, Duration::from_millis(99));
+        assert_eq!(report.samples, 100);
+        assert_eq!(report.dropped, 25);
+        assert!((report.drop_rate() - 0.2).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_empty_report() {
+        let report = LatencyReport::from_samples(&[None, None]);
+        assert_eq!(report.p99, Duration::ZERO);
+        assert!((report.drop_rate() - 1.0).abs() < f64::EPSILON);
+    }
+}
@@ -1,13 +1,20 @@
 //! Universal capability providers
 
 pub mod backend_discovery;
+pub mod clipboard;
+pub mod consent;
 pub mod desktop;
+pub mod latency;
 pub mod portal;
 pub mod vm;
 
 pub use backend_discovery::{
-    ProviderHealth, ResourceStatus, VmBackendProvider, VmBackendRegistry, VmCapability, VmType,
+    HealthWeighting, ProviderHealth, ProviderId, ResourceStatus, VmBackendProvider,
+    VmBackendRegistry, VmCapability, VmType, DEFAULT_STATUS_POLL_INTERVAL,
 };
+pub use clipboard::{ClipboardPayload, ClipboardReport, ClipboardRoundTripProbe, RoundTrip};
+pub use consent::{ConsentDecision, ConsentEnforcementProbe, ConsentPathOutcome, ConsentReport};
 pub use desktop::RemoteDesktop;
-pub use portal::PortalDeployer;
+pub use latency::{InputLatencyProbe, LatencyReport};
+pub use portal::{PortalDeployer, RegistrationStatus, SessionBus};
 pub use vm::VmProvisioner;
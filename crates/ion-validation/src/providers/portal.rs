@@ -4,6 +4,23 @@ use crate::errors::Result;
 use crate::providers::desktop::Target;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Well-known bus name the deployed portal claims
+pub const PORTAL_BUS_NAME: &str = "org.freedesktop.impl.portal.desktop.cosmic";
+
+/// Object path the portal serves its interfaces at
+pub const PORTAL_OBJECT_PATH: &str = "/org/freedesktop/portal/desktop";
+
+/// Interface every working portal exports
+pub const REMOTE_DESKTOP_INTERFACE: &str = "org.freedesktop.impl.portal.RemoteDesktop";
+
+/// How long a freshly started portal gets to register on the bus
+pub const REGISTRATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Delay between registration checks while waiting for the portal
+pub const REGISTRATION_POLL_INTERVAL: Duration = Duration::from_millis(250);
 
 /// Universal trait for portal deployment
 ///
@@ -16,6 +33,9 @@ pub trait PortalDeployer: Send + Sync {
     /// Verify portal is running
     async fn verify(&self, deployment: &Deployment) -> Result<Health>;
 
+    /// Verify the deployed portal registered on the target's session bus
+    async fn verify_registration(&self, deployment: &Deployment) -> Result<RegistrationStatus>;
+
     /// Get portal status
     async fn get_status(&self, deployment: &Deployment) -> Result<PortalStatus>;
 
@@ -112,6 +132,95 @@ pub enum PortalStatus {
     Unknown,
 }
 
+/// Read-only view of a session bus
+///
+/// Implemented over a direct connection in the substrate, and over
+/// `busctl` on the target for deployed portals.
+#[async_trait]
+pub trait SessionBus: Send + Sync {
+    /// Whether some connection owns `name`
+    async fn name_has_owner(&self, name: &str) -> Result<bool>;
+
+    /// Interfaces `name` exports at `path`
+    async fn interfaces(&self, name: &str, path: &str) -> Result<Vec<String>>;
+}
+
+/// Whether the portal claimed its bus name and exports its interface
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistrationStatus {
+    /// Bus name that was checked
+    pub bus_name: String,
+    /// Object path that was checked
+    pub object_path: String,
+    /// The bus name has an owner
+    pub name_owned: bool,
+    /// The RemoteDesktop interface is introspectable at the object path
+    pub interface_exported: bool,
+}
+
+impl RegistrationStatus {
+    /// Name owned and interface exported
+    pub fn is_registered(&self) -> bool {
+        self.name_owned && self.interface_exported
+    }
+
+    /// The first missing piece, or `None` when registered
+    pub fn missing(&self) -> Option<String> {
+        if !self.name_owned {
+            Some(format!("{} has no owner", self.bus_name))
+        } else if !self.interface_exported {
+            Some(format!(
+                "{} is not exported at {}",
+                REMOTE_DESKTOP_INTERFACE, self.object_path
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Check that the portal owns [`PORTAL_BUS_NAME`] and exports
+/// [`REMOTE_DESKTOP_INTERFACE`] at [`PORTAL_OBJECT_PATH`]
+///
+/// The interface is only introspected once the name has an owner.
+pub async fn check_registration(bus: &dyn SessionBus) -> Result<RegistrationStatus> {
+    let name_owned = bus.name_has_owner(PORTAL_BUS_NAME).await?;
+    let interface_exported = name_owned
+        && bus
+            .interfaces(PORTAL_BUS_NAME, PORTAL_OBJECT_PATH)
+            .await?
+            .iter()
+            .any(|iface| iface == REMOTE_DESKTOP_INTERFACE);
+
+    Ok(RegistrationStatus {
+        bus_name: PORTAL_BUS_NAME.to_string(),
+        object_path: PORTAL_OBJECT_PATH.to_string(),
+        name_owned,
+        interface_exported,
+    })
+}
+
+/// Poll [`check_registration`] until the portal is registered or
+/// `timeout` has passed
+///
+/// Services are started in the background, so the portal may still be
+/// starting when deployment returns. The last status is returned either
+/// way; callers check [`RegistrationStatus::missing`].
+pub async fn await_registration(
+    bus: &dyn SessionBus,
+    timeout: Duration,
+    interval: Duration,
+) -> Result<RegistrationStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let status = check_registration(bus).await?;
+        if status.is_registered() || Instant::now() + interval > deadline {
+            return Ok(status);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,6 +232,24 @@ mod tests {
         assert!(config.release);
     }
 
+    #[test]
+    fn test_registration_status_reports_missing_piece() {
+        let mut status = RegistrationStatus {
+            bus_name: PORTAL_BUS_NAME.to_string(),
+            object_path: PORTAL_OBJECT_PATH.to_string(),
+            name_owned: false,
+            interface_exported: false,
+        };
+        assert!(status.missing().unwrap().contains("has no owner"));
+
+        status.name_owned = true;
+        assert!(status.missing().unwrap().contains(REMOTE_DESKTOP_INTERFACE));
+
+        status.interface_exported = true;
+        assert!(status.is_registered());
+        assert_eq!(status.missing(), None);
+    }
+
     #[test]
     #[cfg(feature = "mcp")]
     fn test_health_serialization() {
@@ -14,6 +14,22 @@ pub trait VmProvisioner: Send + Sync {
     /// Provision a new VM
     async fn provision(&self, spec: VmSpec) -> Result<ProvisionedVm>;
 
+    /// Provision a new VM, passing its ID to `created` once the VM exists
+    ///
+    /// Only VMs this call creates are reported, so a caller that abandons
+    /// provisioning can destroy what it left behind and nothing else.
+    /// Provisioners that create VMs should report them before booting
+    /// them. The default reports nothing, as a provisioner that hands out
+    /// existing VMs must.
+    async fn provision_reporting(
+        &self,
+        spec: VmSpec,
+        created: &(dyn Fn(String) + Send + Sync),
+    ) -> Result<ProvisionedVm> {
+        let _ = created;
+        self.provision(spec).await
+    }
+
     /// Get VM status
     async fn get_status(&self, vm_id: &str) -> Result<VmStatus>;
 
@@ -13,15 +13,30 @@
 //!   portal-test check           # Check which portals are available
 //!   portal-test screencast      # Test screen capture
 //!   portal-test remote-desktop  # Test screen + input control
+//!   portal-test remote-desktop --type-string "hello" --click 100,200
+//!   portal-test latency-agent   # Answer ion-validation's latency probe
 
 use anyhow::{Context, Result};
-use ashpd::desktop::remote_desktop::{DeviceType, RemoteDesktop};
+use ashpd::desktop::remote_desktop::{DeviceType, KeyState, RemoteDesktop};
 use ashpd::desktop::screencast::{CursorMode, Screencast, SourceType};
-use ashpd::desktop::PersistMode;
+use ashpd::desktop::{PersistMode, Session};
 use ashpd::enumflags2::BitFlag;
 use ashpd::WindowIdentifier;
 use clap::{Parser, Subcommand};
-use tracing::{error, info, warn};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, error, info, warn};
+
+/// `XK_Return`
+const KEYSYM_RETURN: i32 = 0xff0d;
+/// `XK_Tab`
+const KEYSYM_TAB: i32 = 0xff09;
+/// `BTN_LEFT` from linux/input-event-codes.h
+const BTN_LEFT: i32 = 0x110;
+/// Pause between injected keystrokes so they can be watched
+const KEYSTROKE_DELAY: Duration = Duration::from_millis(50);
 
 #[derive(Parser)]
 #[command(name = "portal-test")]
@@ -50,10 +65,29 @@ enum Commands {
         /// Request pointer/mouse access
         #[arg(long, default_value = "true")]
         pointer: bool,
+
+        /// Type this text via keysym events once the session starts
+        #[arg(long, value_name = "TEXT")]
+        type_string: Option<String>,
+
+        /// Left-click at this position on the first shared stream
+        #[arg(long, value_name = "X,Y")]
+        click: Option<ClickPoint>,
     },
 
     /// Check which portals are available
     Check,
+
+    /// Serve the agent ion-validation measures input latency through
+    ///
+    /// Starts a pointer-only RemoteDesktop session, then answers each
+    /// `MOTION <seq>` line from the probe with `ACK <seq>` once the portal
+    /// accepted the motion, or `DROP <seq>` if it rejected it.
+    LatencyAgent {
+        /// Address to listen on for the probe
+        #[arg(long, default_value = "0.0.0.0:7422")]
+        listen: String,
+    },
 }
 
 #[tokio::main]
@@ -69,10 +103,55 @@ async fn main() -> Result<()> {
 
     match cli.command {
         Commands::Screencast { cursor } => test_screencast(cursor).await,
-        Commands::RemoteDesktop { keyboard, pointer } => {
-            test_remote_desktop(keyboard, pointer).await
-        },
+        Commands::RemoteDesktop {
+            keyboard,
+            pointer,
+            type_string,
+            click,
+        } => test_remote_desktop(keyboard, pointer, type_string.as_deref(), click).await,
         Commands::Check => check_portals().await,
+        Commands::LatencyAgent { listen } => serve_latency_agent(&listen).await,
+    }
+}
+
+/// Stream-relative coordinate given as `x,y`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ClickPoint {
+    x: f64,
+    y: f64,
+}
+
+impl FromStr for ClickPoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (x, y) = s
+            .split_once(',')
+            .ok_or_else(|| format!("expected X,Y but got '{s}'"))?;
+        let coord = |v: &str| {
+            v.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite() && *v >= 0.0)
+                .ok_or_else(|| format!("invalid coordinate '{v}'"))
+        };
+        Ok(Self {
+            x: coord(x)?,
+            y: coord(y)?,
+        })
+    }
+}
+
+/// X11 keysym for a character, if it can be typed.
+///
+/// Printable ASCII keysyms equal their code points; the portal works
+/// out any modifiers (e.g. Shift for uppercase) itself.
+fn char_to_keysym(c: char) -> Option<i32> {
+    match c {
+        ' '..='~' => Some(c as i32),
+        '\n' => Some(KEYSYM_RETURN),
+        '\t' => Some(KEYSYM_TAB),
+        _ => None,
     }
 }
 
@@ -229,7 +308,12 @@ async fn test_screencast(show_cursor: bool) -> Result<()> {
     Ok(())
 }
 
-async fn test_remote_desktop(keyboard: bool, pointer: bool) -> Result<()> {
+async fn test_remote_desktop(
+    keyboard: bool,
+    pointer: bool,
+    type_string: Option<&str>,
+    click: Option<ClickPoint>,
+) -> Result<()> {
     info!("Testing RemoteDesktop portal...");
     info!("(This will fail on COSMIC until RemoteDesktop portal is implemented)\n");
 
@@ -257,6 +341,24 @@ async fn test_remote_desktop(keyboard: bool, pointer: bool) -> Result<()> {
         .await
         .context("Failed to select devices")?;
 
+    // Absolute coordinates are relative to a screencast stream
+    if click.is_some() {
+        info!("Selecting a monitor to click on...");
+        Screencast::new()
+            .await
+            .context("Failed to connect to ScreenCast portal")?
+            .select_sources(
+                &session,
+                CursorMode::Embedded,
+                SourceType::Monitor.into(),
+                false,
+                None,
+                PersistMode::DoNot,
+            )
+            .await
+            .context("Failed to select sources")?;
+    }
+
     info!("Starting remote desktop session...");
     let response = remote_desktop
         .start(&session, &WindowIdentifier::default())
@@ -277,13 +379,30 @@ async fn test_remote_desktop(keyboard: bool, pointer: bool) -> Result<()> {
                 .await
                 .context("Failed to inject pointer motion")?;
             info!("  Moved pointer right (iteration {})", i + 1);
-            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            tokio::time::sleep(Duration::from_millis(200)).await;
         }
         info!("✅ Pointer injection works!");
     }
 
-    if keyboard {
-        info!("\nKeyboard injection available (not tested - would type characters)");
+    if let Some(point) = click {
+        if pointer {
+            let stream = response
+                .streams()
+                .and_then(<[_]>::first)
+                .context("No stream to click on")?;
+            click_at(&remote_desktop, &session, stream.pipe_wire_node_id(), point).await?;
+        } else {
+            warn!("\n--click needs pointer access, skipping");
+        }
+    }
+
+    match (keyboard, type_string) {
+        (true, Some(text)) => type_text(&remote_desktop, &session, text).await?,
+        (false, Some(_)) => warn!("\n--type-string needs keyboard access, skipping"),
+        (true, None) => {
+            info!("\nKeyboard injection available (use --type-string to test it)");
+        },
+        (false, None) => {},
     }
 
     info!("\nPress Ctrl+C to stop...");
@@ -294,3 +413,165 @@ async fn test_remote_desktop(keyboard: bool, pointer: bool) -> Result<()> {
 
     Ok(())
 }
+
+async fn click_at(
+    remote_desktop: &RemoteDesktop<'_>,
+    session: &Session<'_, RemoteDesktop<'_>>,
+    stream: u32,
+    point: ClickPoint,
+) -> Result<()> {
+    info!(
+        "\nClicking at ({}, {}) on stream {stream}...",
+        point.x, point.y
+    );
+    remote_desktop
+        .notify_pointer_motion_absolute(session, stream, point.x, point.y)
+        .await
+        .context("Failed to inject absolute pointer motion")?;
+    for state in [KeyState::Pressed, KeyState::Released] {
+        remote_desktop
+            .notify_pointer_button(session, BTN_LEFT, state)
+            .await
+            .context("Failed to inject pointer button")?;
+    }
+    info!("✅ Pointer button injection works!");
+    Ok(())
+}
+
+async fn type_text(
+    remote_desktop: &RemoteDesktop<'_>,
+    session: &Session<'_, RemoteDesktop<'_>>,
+    text: &str,
+) -> Result<()> {
+    info!("\nTyping {text:?}...");
+    let mut typed = 0;
+    for c in text.chars() {
+        let Some(keysym) = char_to_keysym(c) else {
+            warn!("  Skipping {c:?}: no keysym mapping");
+            continue;
+        };
+        for state in [KeyState::Pressed, KeyState::Released] {
+            remote_desktop
+                .notify_keyboard_keysym(session, keysym, state)
+                .await
+                .context("Failed to inject keysym")?;
+        }
+        info!("  Typed {c:?} (keysym 0x{keysym:04x})");
+        typed += 1;
+        tokio::time::sleep(KEYSTROKE_DELAY).await;
+    }
+    info!(
+        "✅ Keyboard injection works! ({typed}/{} characters typed)",
+        text.chars().count()
+    );
+    Ok(())
+}
+
+/// Sequence number of a `MOTION <seq>` latency probe request.
+fn parse_motion_request(line: &str) -> Option<u32> {
+    line.trim().strip_prefix("MOTION ")?.trim().parse().ok()
+}
+
+async fn serve_latency_agent(listen: &str) -> Result<()> {
+    info!("Starting latency agent...");
+
+    let remote_desktop = RemoteDesktop::new()
+        .await
+        .context("Failed to connect to RemoteDesktop portal")?;
+    let session = remote_desktop
+        .create_session()
+        .await
+        .context("Failed to create session")?;
+    remote_desktop
+        .select_devices(
+            &session,
+            DeviceType::Pointer.into(),
+            None,
+            PersistMode::DoNot,
+        )
+        .await
+        .context("Failed to select devices")?;
+    remote_desktop
+        .start(&session, &WindowIdentifier::default())
+        .await
+        .context("Failed to start remote desktop")?
+        .response()
+        .context("User cancelled or error in response")?;
+
+    let listener = TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to listen on {listen}"))?;
+    info!("✅ Latency agent listening on {listen}");
+
+    // One probe at a time; each gets the session to itself
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        info!("Latency probe connected from {peer}");
+        match answer_latency_probe(&remote_desktop, &session, stream).await {
+            Ok(()) => info!("Latency probe {peer} disconnected"),
+            Err(e) => warn!("Latency probe {peer} failed: {e:#}"),
+        }
+    }
+}
+
+async fn answer_latency_probe(
+    remote_desktop: &RemoteDesktop<'_>,
+    session: &Session<'_, RemoteDesktop<'_>>,
+    stream: TcpStream,
+) -> Result<()> {
+    stream.set_nodelay(true).ok();
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let Some(sequence) = parse_motion_request(&line) else {
+            warn!("Ignoring unrecognized probe request {:?}", line.trim());
+            continue;
+        };
+        // Alternate direction so the pointer stays where it was
+        let dx = if sequence % 2 == 0 { 1.0 } else { -1.0 };
+        let verb = match remote_desktop.notify_pointer_motion(session, dx, 0.0).await {
+            Ok(()) => "ACK",
+            Err(e) => {
+                debug!("Motion {sequence} rejected: {e}");
+                "DROP"
+            },
+        };
+        write
+            .write_all(format!("{verb} {sequence}\n").as_bytes())
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn click_point_parses_x_y() {
+        assert_eq!(
+            "100, 200.5".parse::<ClickPoint>(),
+            Ok(ClickPoint { x: 100.0, y: 200.5 })
+        );
+        assert!("100".parse::<ClickPoint>().is_err());
+        assert!("-1,5".parse::<ClickPoint>().is_err());
+        assert!("a,b".parse::<ClickPoint>().is_err());
+    }
+
+    #[test]
+    fn motion_requests_parse() {
+        assert_eq!(parse_motion_request("MOTION 7\n"), Some(7));
+        assert_eq!(parse_motion_request("MOTION x"), None);
+        assert_eq!(parse_motion_request("ACK 7"), None);
+    }
+
+    #[test]
+    fn ascii_maps_to_keysyms() {
+        assert_eq!(char_to_keysym('a'), Some(0x61));
+        assert_eq!(char_to_keysym('A'), Some(0x41));
+        assert_eq!(char_to_keysym(' '), Some(0x20));
+        assert_eq!(char_to_keysym('\n'), Some(KEYSYM_RETURN));
+        assert_eq!(char_to_keysym('é'), None);
+    }
+}